@@ -0,0 +1,88 @@
+//! Throughput benchmarks for the built-in processors and the full dispatch loop.
+//!
+//! Run with `cargo bench`. Inputs are generated with [SyntheticRibBuilder], so no RIB dumps need
+//! to be downloaded.
+
+use criterion::{criterion_group, criterion_main, BatchSize, Criterion, Throughput};
+use ribeye::testing::SyntheticRibBuilder;
+use ribeye::{RibEye, RibMeta};
+
+const NUM_PEERS: usize = 10;
+const NUM_PREFIXES: usize = 2_000;
+
+fn bench_meta(output_dir: &str) -> RibMeta {
+    RibMeta {
+        project: "riperis".to_string(),
+        collector: "rrc99".to_string(),
+        rib_dump_url: output_dir.to_string(),
+        timestamp: chrono::DateTime::from_timestamp(1_704_067_200, 0)
+            .unwrap()
+            .naive_utc(),
+        ..Default::default()
+    }
+}
+
+fn bench_process_entry(c: &mut Criterion) {
+    let elems = SyntheticRibBuilder::new()
+        .generated(NUM_PEERS, NUM_PREFIXES)
+        .build();
+    let output_dir = tempfile::tempdir().unwrap();
+    let dir = output_dir.path().to_str().unwrap();
+
+    let mut group = c.benchmark_group("process_entry");
+    group.throughput(Throughput::Elements(elems.len() as u64));
+    for name in RibEye::available_processors() {
+        group.bench_function(name, |b| {
+            b.iter_batched(
+                || {
+                    let mut processor = RibEye::get_processor(name, dir).unwrap();
+                    processor.reset_processor(&bench_meta(dir));
+                    processor
+                },
+                |mut processor| {
+                    for elem in &elems {
+                        processor.process_entry(elem).unwrap();
+                    }
+                    processor
+                },
+                BatchSize::LargeInput,
+            )
+        });
+    }
+    group.finish();
+}
+
+fn bench_pipeline(c: &mut Criterion) {
+    let builder = SyntheticRibBuilder::new().generated(NUM_PEERS, NUM_PREFIXES);
+    let num_elems = builder.elems().len();
+    let output_dir = tempfile::tempdir().unwrap();
+    let dir = output_dir.path().to_str().unwrap();
+    let mrt_path = output_dir.path().join("rib.bench.bz2");
+    let mrt_path = mrt_path.to_str().unwrap();
+    builder.write_mrt_file(mrt_path).unwrap();
+
+    let processor_names = RibEye::available_processors()
+        .into_iter()
+        .map(|n| n.to_string())
+        .collect::<Vec<String>>();
+
+    let mut group = c.benchmark_group("pipeline");
+    group.throughput(Throughput::Elements(num_elems as u64));
+    group.sample_size(10);
+    group.bench_function("all_processors", |b| {
+        b.iter_batched(
+            || {
+                RibEye::new()
+                    .with_processor_names(&processor_names, dir)
+                    .unwrap()
+                    .with_rib_meta(&bench_meta(dir))
+            },
+            |mut ribeye| ribeye.process_mrt_file(mrt_path).unwrap(),
+            BatchSize::LargeInput,
+        )
+    });
+    group.finish();
+}
+
+criterion_group!(benches, bench_process_entry, bench_pipeline);
+criterion_main!(benches);
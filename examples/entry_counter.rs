@@ -1,7 +1,8 @@
 use anyhow::Result;
 use bgpkit_parser::models::ElemType;
 use bgpkit_parser::BgpElem;
-use ribeye::{MessageProcessor, RibEye, RibMeta};
+use ribeye::{MessageProcessor, OutputSink, RibEye, RibMeta};
+use serde_json::Value;
 
 #[derive(Default)]
 struct EntryCounter {
@@ -23,12 +24,17 @@ impl MessageProcessor for EntryCounter {
         "basic_counter".to_string()
     }
 
-    fn output_paths(&self) -> Option<String> {
-        todo!()
+    fn new_instance(&self) -> Box<dyn MessageProcessor> {
+        Box::new(EntryCounter::new())
     }
 
-    fn reset_processor(&mut self, rib_meta: &RibMeta) {
-        todo!()
+    fn output_paths(&self) -> Option<Vec<String>> {
+        None
+    }
+
+    fn reset_processor(&mut self, _rib_meta: &RibMeta) {
+        self.a_count = 0;
+        self.w_count = 0;
     }
 
     fn process_entry(&mut self, elem: &BgpElem) -> Result<()> {
@@ -43,7 +49,7 @@ impl MessageProcessor for EntryCounter {
         Ok(())
     }
 
-    fn output(&mut self) -> Result<()> {
+    fn output(&mut self, _sink: &dyn OutputSink) -> Result<()> {
         println!(
             "{}: {} announcements, {} withdrawals",
             self.name(),
@@ -52,13 +58,19 @@ impl MessageProcessor for EntryCounter {
         );
         Ok(())
     }
+
+    fn summarize_latest(
+        &self,
+        _rib_metas: &[RibMeta],
+        _ignore_error: bool,
+    ) -> Result<Option<Value>> {
+        Ok(None)
+    }
 }
 
 fn main() {
     let mut ribeye = RibEye::new();
-    ribeye
-        .add_processor(EntryCounter::new().to_boxed())
-        .unwrap();
+    ribeye.add_processor(EntryCounter::new().to_boxed());
     ribeye
         .process_mrt_file("https://data.ris.ripe.net/rrc21/2023.08/updates.20230806.1640.gz")
         .unwrap();
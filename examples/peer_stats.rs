@@ -1,6 +1,6 @@
 use chrono::NaiveDateTime;
 use ribeye::processors::PeerStatsProcessor;
-use ribeye::{MessageProcessor, RibEye};
+use ribeye::{MessageProcessor, RibEye, RibMeta};
 
 fn main() {
     tracing_subscriber::fmt().init();
@@ -8,8 +8,16 @@ fn main() {
     const RIB_URL: &str = "https://data.ris.ripe.net/rrc18/2023.08/bview.20230806.1600.gz";
     let timestamp =
         NaiveDateTime::parse_from_str("2023-08-06 16:00:00", "%Y-%m-%d %H:%M:%S").unwrap();
-    let processor = PeerStatsProcessor::new("riperis", "rrc18", RIB_URL, &timestamp, "test_output");
-    let mut ribeye = RibEye::new();
-    ribeye.add_processor(processor.to_boxed()).unwrap();
+    let rib_meta = RibMeta {
+        project: "riperis".to_string(),
+        collector: "rrc18".to_string(),
+        rib_dump_url: RIB_URL.to_string(),
+        timestamp,
+        ..Default::default()
+    };
+    let processor = PeerStatsProcessor::new("test_output");
+    let mut ribeye = RibEye::new()
+        .with_processors(vec![processor.to_boxed()])
+        .with_rib_meta(&rib_meta);
     ribeye.process_mrt_file(RIB_URL).unwrap();
 }
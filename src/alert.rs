@@ -0,0 +1,424 @@
+//! Alerting rules evaluated over summarized outputs.
+//!
+//! An [AlertConfig], loaded from a JSON configuration file, lists the [AlertRule]s with their
+//! thresholds and the notification hooks alerts are sent to:
+//!
+//! ```json
+//! {
+//!   "rules": [
+//!     {"rule": "origin_change", "min_slash8": 1.0},
+//!     {"rule": "peer_drop", "ratio": 0.2},
+//!     {"rule": "moas_conflict"},
+//!     {"rule": "stale_collector", "hours": 48}
+//!   ],
+//!   "hooks": [{"type": "log"}, {"type": "file", "path": "/var/log/ribeye-alerts.jsonl"}]
+//! }
+//! ```
+//!
+//! Each rule applies to the summary of one processor and compares it with the previous summary of
+//! that processor, see [RibEye::with_alert_rules](crate::RibEye::with_alert_rules). The `feed`
+//! hook keeps a subscribable feed of the alerts, see [feed](crate::feed).
+
+use crate::diff::{origin_changes, ChangeKind};
+use crate::feed::{FeedHook, DEFAULT_FEED_MAX_ITEMS, DEFAULT_FEED_TITLE};
+use crate::health::{collector_health, DEFAULT_PEER_DROP_RATIO};
+use crate::processors::{
+    CollectorDump, PeerInfoSummaryJson, Prefix2AsSummaryJson, COLLECTOR_DUMPS_FIELD,
+    DEFAULT_STALE_AFTER,
+};
+use crate::util::PrefixSpace;
+use anyhow::{anyhow, Result};
+use chrono::Utc;
+use ipnet::IpNet;
+use itertools::Itertools;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::process::{Command, Stdio};
+use std::str::FromStr;
+use std::sync::Arc;
+use tracing::warn;
+
+/// Number of IPv4 addresses in a /8 prefix.
+const SLASH8_ADDRESSES: f64 = (1u64 << 24) as f64;
+/// Number of prefixes listed in the message of a `moas_conflict` alert.
+const MOAS_LISTED_PREFIXES: usize = 5;
+
+fn default_min_slash8() -> f64 {
+    1.0
+}
+
+fn default_peer_drop_ratio() -> f64 {
+    DEFAULT_PEER_DROP_RATIO
+}
+
+fn default_min_prefixes() -> usize {
+    1
+}
+
+fn default_stale_hours() -> f64 {
+    DEFAULT_STALE_AFTER.as_secs_f64() / 3600.0
+}
+
+/// A condition over a processor's summary and its previous summary.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "rule", rename_all = "snake_case")]
+pub enum AlertRule {
+    /// alert if at least `min_slash8` /8-equivalents of IPv4 address space changed their set of
+    /// origin ASNs in the pfx2as summary
+    OriginChange {
+        #[serde(default = "default_min_slash8")]
+        min_slash8: f64,
+    },
+    /// alert for each collector that lost more than `ratio` of its peers in the peer-stats summary
+    PeerDrop {
+        #[serde(default = "default_peer_drop_ratio")]
+        ratio: f64,
+    },
+    /// alert if at least `min_prefixes` prefixes of the pfx2as summary have several origins that
+    /// had a single origin, or were not announced, in the previous summary
+    MoasConflict {
+        #[serde(default = "default_min_prefixes")]
+        min_prefixes: usize,
+    },
+    /// alert for each collector whose summarized RIB dump is older than `hours` and that was not
+    /// stale in the previous summary of peer-stats
+    StaleCollector {
+        #[serde(default = "default_stale_hours")]
+        hours: f64,
+    },
+}
+
+impl AlertRule {
+    pub fn name(&self) -> &'static str {
+        match self {
+            AlertRule::OriginChange { .. } => "origin_change",
+            AlertRule::PeerDrop { .. } => "peer_drop",
+            AlertRule::MoasConflict { .. } => "moas_conflict",
+            AlertRule::StaleCollector { .. } => "stale_collector",
+        }
+    }
+
+    /// Name of the processor whose summary the rule applies to.
+    pub fn processor(&self) -> &'static str {
+        match self {
+            AlertRule::OriginChange { .. } => "pfx2as",
+            AlertRule::PeerDrop { .. } | AlertRule::StaleCollector { .. } => "peer-stats",
+            AlertRule::MoasConflict { .. } => "pfx2as",
+        }
+    }
+
+    /// Evaluate the rule over a summary of its processor and the previous summary, both with the
+    /// pipeline fields they are written with.
+    pub fn evaluate(&self, summary: &Value, previous: &Value) -> Result<Vec<Alert>> {
+        let alert =
+            |collector: Option<String>, value: f64, threshold: f64, message: String| Alert {
+                rule: self.name().to_string(),
+                processor: self.processor().to_string(),
+                collector,
+                value,
+                threshold,
+                message,
+            };
+        match self {
+            AlertRule::OriginChange { min_slash8 } => {
+                let changed = origin_changed_slash8(
+                    &Prefix2AsSummaryJson::deserialize(summary)?,
+                    &Prefix2AsSummaryJson::deserialize(previous)?,
+                );
+                Ok(match changed >= *min_slash8 {
+                    true => vec![alert(
+                        None,
+                        changed,
+                        *min_slash8,
+                        format!(
+                            "{:.2} /8-equivalents of IPv4 address space changed origin",
+                            changed
+                        ),
+                    )],
+                    false => vec![],
+                })
+            }
+            AlertRule::PeerDrop { ratio } => {
+                let report = collector_health(
+                    &PeerInfoSummaryJson::deserialize(summary)?,
+                    Some(&PeerInfoSummaryJson::deserialize(previous)?),
+                    *ratio,
+                );
+                Ok(report
+                    .collectors
+                    .into_iter()
+                    .filter(|entry| entry.dropped)
+                    .map(|entry| {
+                        let before = entry.previous_peers_count.unwrap_or_default();
+                        let lost = 1.0 - entry.peers_count as f64 / before as f64;
+                        alert(
+                            Some(entry.collector.clone()),
+                            lost,
+                            *ratio,
+                            format!(
+                                "collector {} lost {:.0}% of its peers ({} to {})",
+                                entry.collector,
+                                lost * 100.0,
+                                before,
+                                entry.peers_count
+                            ),
+                        )
+                    })
+                    .collect())
+            }
+            AlertRule::MoasConflict { min_prefixes } => {
+                let conflicts: Vec<(String, String)> = origin_changes(
+                    &Prefix2AsSummaryJson::deserialize(previous)?,
+                    &Prefix2AsSummaryJson::deserialize(summary)?,
+                )
+                .into_iter()
+                .filter(|c| c.new_origins.len() > 1 && c.old_origins.len() < 2)
+                .map(|c| {
+                    (
+                        c.prefix,
+                        c.new_origins.iter().map(|a| format!("AS{}", a)).join(", "),
+                    )
+                })
+                .collect();
+                if conflicts.is_empty() || conflicts.len() < *min_prefixes {
+                    return Ok(vec![]);
+                }
+                let mut listed = conflicts
+                    .iter()
+                    .take(MOAS_LISTED_PREFIXES)
+                    .map(|(prefix, origins)| format!("{} ({})", prefix, origins))
+                    .join(", ");
+                if conflicts.len() > MOAS_LISTED_PREFIXES {
+                    listed.push_str(
+                        format!(" and {} more", conflicts.len() - MOAS_LISTED_PREFIXES).as_str(),
+                    );
+                }
+                Ok(vec![alert(
+                    None,
+                    conflicts.len() as f64,
+                    *min_prefixes as f64,
+                    format!("{} new MOAS conflicts: {}", conflicts.len(), listed),
+                )])
+            }
+            AlertRule::StaleCollector { hours } => {
+                let dumps = |value: &Value| -> Result<Vec<CollectorDump>> {
+                    Ok(match value.get(COLLECTOR_DUMPS_FIELD) {
+                        Some(dumps) => Vec::<CollectorDump>::deserialize(dumps)?,
+                        None => vec![],
+                    })
+                };
+                let previous: HashMap<String, bool> = dumps(previous)?
+                    .into_iter()
+                    .map(|d| (d.collector, d.stale))
+                    .collect();
+                let now = Utc::now().timestamp();
+                Ok(dumps(summary)?
+                    .into_iter()
+                    .filter(|d| !previous.get(&d.collector).copied().unwrap_or_default())
+                    .filter_map(|d| {
+                        let age = (now - d.rib_timestamp) as f64 / 3600.0;
+                        (age > *hours).then(|| {
+                            alert(
+                                Some(d.collector.clone()),
+                                age,
+                                *hours,
+                                format!(
+                                    "collector {} is stale, its latest RIB dump is {:.0} hours old",
+                                    d.collector, age
+                                ),
+                            )
+                        })
+                    })
+                    .collect())
+            }
+        }
+    }
+}
+
+/// IPv4 address space, in /8-equivalents, of the prefixes present in both summaries with
+/// different sets of origins. Overlapping prefixes are counted once.
+fn origin_changed_slash8(current: &Prefix2AsSummaryJson, previous: &Prefix2AsSummaryJson) -> f64 {
+    let mut space = PrefixSpace::new();
+    for change in origin_changes(previous, current) {
+        if change.kind != ChangeKind::Changed {
+            continue;
+        }
+        if let Ok(prefix @ IpNet::V4(_)) = IpNet::from_str(change.prefix.as_str()) {
+            space.insert(&prefix);
+        }
+    }
+    space.v4_addresses() as f64 / SLASH8_ADDRESSES
+}
+
+/// An alert raised by a rule.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Alert {
+    pub rule: String,
+    pub processor: String,
+    /// collector the alert is about, if specific to one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub collector: Option<String>,
+    /// measured value that crossed the threshold
+    pub value: f64,
+    pub threshold: f64,
+    pub message: String,
+}
+
+/// Destination of raised alerts.
+pub trait NotificationHook: Send + Sync {
+    /// Send the alerts raised by one summarization run.
+    fn notify(&self, alerts: &[Alert]) -> Result<()>;
+}
+
+/// Log alerts as warnings.
+pub struct LogHook;
+
+impl NotificationHook for LogHook {
+    fn notify(&self, alerts: &[Alert]) -> Result<()> {
+        for alert in alerts {
+            warn!(
+                processor = alert.processor.as_str(),
+                rule = alert.rule.as_str(),
+                "alert: {}",
+                alert.message
+            );
+        }
+        Ok(())
+    }
+}
+
+/// Append alerts to a local file, one JSON object per line.
+pub struct FileHook {
+    pub path: String,
+}
+
+impl NotificationHook for FileHook {
+    fn notify(&self, alerts: &[Alert]) -> Result<()> {
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(self.path.as_str())?;
+        for alert in alerts {
+            writeln!(file, "{}", serde_json::to_string(alert)?)?;
+        }
+        Ok(())
+    }
+}
+
+/// Run a shell command with the alerts as a JSON array on its standard input.
+pub struct CommandHook {
+    pub command: String,
+}
+
+impl NotificationHook for CommandHook {
+    fn notify(&self, alerts: &[Alert]) -> Result<()> {
+        let mut child = Command::new("sh")
+            .arg("-c")
+            .arg(self.command.as_str())
+            .stdin(Stdio::piped())
+            .spawn()?;
+        if let Some(mut stdin) = child.stdin.take() {
+            stdin.write_all(serde_json::to_string(alerts)?.as_bytes())?;
+        }
+        let status = child.wait()?;
+        match status.success() {
+            true => Ok(()),
+            false => Err(anyhow!(
+                "alert command `{}` failed: {}",
+                self.command,
+                status
+            )),
+        }
+    }
+}
+
+/// Configuration of a notification hook.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum HookConfig {
+    Log,
+    File {
+        path: String,
+    },
+    Command {
+        command: String,
+    },
+    /// JSON Feed of the alerts at `path`, with an Atom rendering at `atom_path` if given
+    Feed {
+        path: String,
+        #[serde(default)]
+        atom_path: Option<String>,
+        #[serde(default = "default_feed_title")]
+        title: String,
+        #[serde(default = "default_feed_max_items")]
+        max_items: usize,
+    },
+}
+
+fn default_feed_title() -> String {
+    DEFAULT_FEED_TITLE.to_string()
+}
+
+fn default_feed_max_items() -> usize {
+    DEFAULT_FEED_MAX_ITEMS
+}
+
+impl HookConfig {
+    pub fn build(&self) -> Arc<dyn NotificationHook> {
+        match self {
+            HookConfig::Log => Arc::new(LogHook),
+            HookConfig::File { path } => Arc::new(FileHook { path: path.clone() }),
+            HookConfig::Command { command } => Arc::new(CommandHook {
+                command: command.clone(),
+            }),
+            HookConfig::Feed {
+                path,
+                atom_path,
+                title,
+                max_items,
+            } => {
+                let mut hook = FeedHook::new(path.as_str())
+                    .with_title(title.as_str())
+                    .with_max_items(*max_items);
+                if let Some(atom_path) = atom_path {
+                    hook = hook.with_atom_path(atom_path.as_str());
+                }
+                Arc::new(hook)
+            }
+        }
+    }
+}
+
+/// Alerting rules and notification hooks, from a JSON configuration file.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct AlertConfig {
+    #[serde(default)]
+    pub rules: Vec<AlertRule>,
+    /// hooks alerts are sent to, alerts are logged if none is configured
+    #[serde(default)]
+    pub hooks: Vec<HookConfig>,
+}
+
+impl AlertConfig {
+    /// Load a configuration file, local or remote.
+    pub fn load(path: &str) -> Result<Self> {
+        let content = oneio::read_to_string(path)
+            .map_err(|e| anyhow!("failed to read alert configuration {}: {}", path, e))?;
+        Self::from_json_str(content.as_str())
+    }
+
+    pub fn from_json_str(content: &str) -> Result<Self> {
+        serde_json::from_str(content).map_err(|e| anyhow!("invalid alert configuration: {}", e))
+    }
+
+    /// Notification hooks of the configuration.
+    pub fn hooks(&self) -> Vec<Arc<dyn NotificationHook>> {
+        match self.hooks.is_empty() {
+            true => vec![Arc::new(LogHook)],
+            false => self.hooks.iter().map(HookConfig::build).collect(),
+        }
+    }
+}
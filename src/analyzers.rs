@@ -0,0 +1,206 @@
+//! Analyzers: a second pipeline stage over the in-memory processor results.
+//!
+//! An [Analyzer] consumes the per-collector results of processors, and of other analyzers, once a
+//! RIB dump has been processed, without reading them back from the outputs. Each analyzer declares
+//! the results it depends on by name; [order_analyzers] checks that every dependency is a
+//! processor or analyzer of the pipeline and runs analyzers after the analyzers they depend on.
+//! Analyzers are added with [RibEye::with_analyzers](crate::RibEye::with_analyzers) and their
+//! results are written like processor outputs, e.g. under `<dir>/hijacks/<collector>/`.
+//!
+//! The built-in `hijacks` analyzer ([HijackAnalyzer]) ranks the hijack candidates of each
+//! collector from its pfx2as MOAS conflicts, the RPKI validity of the origins and, if available,
+//! its as2rel relationships.
+
+use crate::hijack::{find_hijack_candidates, Vrps};
+use crate::processors::{
+    get_default_output_path, As2relCollectorJson, As2relSummaryJson, Prefix2AsCollectorJson,
+    Prefix2AsSummaryJson, ProcessorMeta, RibMeta,
+};
+use anyhow::{anyhow, Result};
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+use std::collections::{BTreeMap, HashSet};
+use std::sync::Arc;
+
+/// Analysis of processor results after a RIB dump has been processed.
+///
+/// Analyzers are shared by the pipelines of concurrently processed RIB dumps and keep no state
+/// between dumps.
+pub trait Analyzer: Send + Sync {
+    /// Name of the analyzer, also the name of its dataset
+    fn name(&self) -> String;
+
+    /// Names of the processors and analyzers whose results the analyzer requires; the analyzer is
+    /// skipped if one of them has no result
+    fn dependencies(&self) -> Vec<String>;
+
+    /// Analyze the results of a RIB dump, `None` if there is nothing to write
+    fn analyze(&self, rib_meta: &RibMeta, inputs: &AnalyzerInputs) -> Result<Option<Value>>;
+
+    /// Output path of the analysis of a RIB dump, not written if `None`
+    fn output_path(&self, rib_meta: &RibMeta) -> Option<String>;
+}
+
+/// Results of the processors and preceding analyzers of a RIB dump, by name.
+#[derive(Debug, Clone, Default)]
+pub struct AnalyzerInputs {
+    results: BTreeMap<String, Value>,
+}
+
+impl AnalyzerInputs {
+    pub fn insert(&mut self, name: &str, result: Value) {
+        self.results.insert(name.to_string(), result);
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Value> {
+        self.results.get(name)
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.results.contains_key(name)
+    }
+
+    /// Decode the result of a processor or analyzer, e.g. into its typed output.
+    pub fn decode<T: DeserializeOwned>(&self, name: &str) -> Result<T> {
+        let value = self
+            .get(name)
+            .ok_or_else(|| anyhow!("no {} result to analyze", name))?;
+        serde_json::from_value(value.clone())
+            .map_err(|e| anyhow!("invalid {} result to analyze: {}", name, e))
+    }
+}
+
+/// Names of the built-in analyzers.
+pub fn available_analyzers() -> Vec<&'static str> {
+    vec!["hijacks"]
+}
+
+/// Create a built-in analyzer writing under `output_dir`. `vrps` are used by the `hijacks`
+/// analyzer to check the RPKI validity of origins.
+pub fn get_analyzer(
+    name: &str,
+    output_dir: &str,
+    vrps: Option<Arc<Vrps>>,
+) -> Result<Arc<dyn Analyzer>> {
+    match name {
+        "hijacks" => {
+            let analyzer = HijackAnalyzer::new(output_dir);
+            Ok(Arc::new(match vrps {
+                Some(vrps) => analyzer.with_vrps(vrps),
+                None => analyzer,
+            }))
+        }
+        _ => Err(anyhow!(
+            "unknown analyzer {}, available: {}",
+            name,
+            available_analyzers().join(", ")
+        )),
+    }
+}
+
+/// Order analyzers so that each runs after the analyzers it depends on, keeping their order
+/// otherwise. Fails if a dependency is neither one of `processors` nor an analyzer, or if
+/// analyzers depend on each other in a cycle.
+pub fn order_analyzers(
+    analyzers: &[Arc<dyn Analyzer>],
+    processors: &[String],
+) -> Result<Vec<Arc<dyn Analyzer>>> {
+    let names: HashSet<String> = analyzers.iter().map(|a| a.name()).collect();
+    for analyzer in analyzers {
+        if let Some(missing) = analyzer
+            .dependencies()
+            .into_iter()
+            .find(|d| !processors.contains(d) && !names.contains(d))
+        {
+            return Err(anyhow!(
+                "analyzer {} depends on {}, which is neither a processor nor an analyzer of the pipeline",
+                analyzer.name(),
+                missing
+            ));
+        }
+    }
+
+    let mut available: HashSet<String> = processors.iter().cloned().collect();
+    let mut remaining: Vec<Arc<dyn Analyzer>> = analyzers.to_vec();
+    let mut ordered = vec![];
+    while !remaining.is_empty() {
+        let ready = remaining
+            .iter()
+            .position(|a| a.dependencies().iter().all(|d| available.contains(d)));
+        match ready {
+            Some(i) => {
+                let analyzer = remaining.remove(i);
+                available.insert(analyzer.name());
+                ordered.push(analyzer);
+            }
+            None => {
+                let cycle: Vec<String> = remaining.iter().map(|a| a.name()).collect();
+                return Err(anyhow!(
+                    "dependency cycle between analyzers {}",
+                    cycle.join(", ")
+                ));
+            }
+        }
+    }
+    Ok(ordered)
+}
+
+/// Hijack candidates of a collector, see [find_hijack_candidates], from its pfx2as result and, if
+/// the pipeline has the as2rel processor, its relationships.
+pub struct HijackAnalyzer {
+    processor_meta: ProcessorMeta,
+    vrps: Option<Arc<Vrps>>,
+}
+
+impl HijackAnalyzer {
+    pub fn new(output_dir: &str) -> Self {
+        HijackAnalyzer {
+            processor_meta: ProcessorMeta {
+                name: "hijacks".to_string(),
+                output_dir: output_dir.to_string(),
+            },
+            vrps: None,
+        }
+    }
+
+    /// Check the RPKI validity of origins against the given VRPs.
+    pub fn with_vrps(mut self, vrps: Arc<Vrps>) -> Self {
+        self.vrps = Some(vrps);
+        self
+    }
+}
+
+impl Analyzer for HijackAnalyzer {
+    fn name(&self) -> String {
+        self.processor_meta.name.clone()
+    }
+
+    fn dependencies(&self) -> Vec<String> {
+        vec!["pfx2as".to_string()]
+    }
+
+    fn analyze(&self, _rib_meta: &RibMeta, inputs: &AnalyzerInputs) -> Result<Option<Value>> {
+        let pfx2as: Prefix2AsCollectorJson = inputs.decode("pfx2as")?;
+        let as2rel = match inputs.contains("as2rel") {
+            true => {
+                let as2rel: As2relCollectorJson = inputs.decode("as2rel")?;
+                Some(As2relSummaryJson {
+                    rib_dump_urls: vec![as2rel.rib_dump_url],
+                    as2rel: as2rel.as2rel,
+                })
+            }
+            false => None,
+        };
+        let pfx2as = Prefix2AsSummaryJson {
+            rib_dump_urls: vec![pfx2as.rib_dump_url],
+            pfx2as: pfx2as.pfx2as,
+            aggregated: false,
+        };
+        let report = find_hijack_candidates(&pfx2as, as2rel.as_ref(), self.vrps.as_deref());
+        Ok(Some(serde_json::to_value(report)?))
+    }
+
+    fn output_path(&self, rib_meta: &RibMeta) -> Option<String> {
+        Some(get_default_output_path(rib_meta, &self.processor_meta))
+    }
+}
@@ -0,0 +1,303 @@
+//! Delta-encoded archive of daily pfx2as summaries.
+//!
+//! Keeping years of daily pfx2as summaries mostly stores the same prefix-origin pairs over and
+//! over. A [DeltaArchive] writes the first snapshot fully and each later one as the entries added,
+//! removed and changed since the previous snapshot, so that a day typically costs a small fraction
+//! of a full summary. [DeltaArchive::reconstruct] rebuilds the summary of any archived date from
+//! the nearest full snapshot and the deltas after it.
+//!
+//! Layout under the archive directory (by default `<dir>/archive/pfx2as`):
+//!
+//! - `index.json`: archived snapshots in date order, see [ArchiveIndex]
+//! - `<YYYY-MM-DD>.full.json.bz2`: full snapshot, a pfx2as summary
+//! - `<YYYY-MM-DD>.delta.json.bz2`: changes since the previous snapshot, see [Pfx2AsDelta]
+//!
+//! A new full snapshot is written every [DeltaArchive::with_full_every] snapshots, if set, which
+//! bounds the number of deltas read to reconstruct a date.
+
+use crate::processors::{OutputSink, Prefix2AsCount, Prefix2AsSummaryJson};
+use crate::storage;
+use anyhow::{anyhow, Result};
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+
+/// Directory under the output root directory holding the archive of a processor.
+pub const ARCHIVE_DIR: &str = "archive";
+
+/// Default archive directory of the pfx2as summaries under an output root directory.
+pub fn get_archive_dir(output_dir: &str) -> String {
+    format!(
+        "{}/{}/pfx2as",
+        output_dir.trim_end_matches('/'),
+        ARCHIVE_DIR
+    )
+}
+
+/// Kind of an archived snapshot.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SnapshotKind {
+    Full,
+    Delta,
+}
+
+/// An archived snapshot.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ArchivedSnapshot {
+    pub date: NaiveDate,
+    pub kind: SnapshotKind,
+    /// file name relative to the archive directory
+    pub file: String,
+    /// number of prefix-origin entries of the snapshot
+    pub entries: usize,
+}
+
+/// Index of an archive, `index.json`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ArchiveIndex {
+    /// snapshots sorted by date, the first one full
+    pub snapshots: Vec<ArchivedSnapshot>,
+}
+
+/// Prefix-origin pair identifying a pfx2as entry.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct Pfx2AsKey {
+    pub prefix: String,
+    pub asn: u32,
+}
+
+/// Changes of a pfx2as summary since the previous snapshot.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Pfx2AsDelta {
+    /// RIB dump URLs of the new snapshot, stored whole
+    pub rib_dump_urls: Vec<String>,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub aggregated: bool,
+    /// entries only present in the new snapshot
+    pub added: Vec<Prefix2AsCount>,
+    /// entries present in both snapshots with different counts or peers, as in the new snapshot
+    pub changed: Vec<Prefix2AsCount>,
+    /// entries only present in the old snapshot
+    pub removed: Vec<Pfx2AsKey>,
+}
+
+impl Pfx2AsDelta {
+    /// Changes from the `old` to the `new` summary.
+    pub fn between(old: &Prefix2AsSummaryJson, new: &Prefix2AsSummaryJson) -> Result<Self> {
+        let old = entries_by_key(old)?;
+        let new_entries = entries_by_key(new)?;
+        let mut delta = Pfx2AsDelta {
+            rib_dump_urls: new.rib_dump_urls.clone(),
+            aggregated: new.aggregated,
+            ..Default::default()
+        };
+        for (key, (value, entry)) in &new_entries {
+            match old.get(key) {
+                None => delta.added.push((*entry).clone()),
+                Some((old_value, _)) if old_value != value => delta.changed.push((*entry).clone()),
+                Some(_) => {}
+            }
+        }
+        delta.removed = old
+            .keys()
+            .filter(|key| !new_entries.contains_key(*key))
+            .cloned()
+            .collect();
+        Ok(delta)
+    }
+
+    /// Apply the changes to the summary they were computed from.
+    pub fn apply(&self, old: &Prefix2AsSummaryJson) -> Prefix2AsSummaryJson {
+        let mut entries: BTreeMap<Pfx2AsKey, Prefix2AsCount> =
+            old.pfx2as.iter().map(|e| (key_of(e), e.clone())).collect();
+        for key in &self.removed {
+            entries.remove(key);
+        }
+        for entry in self.added.iter().chain(self.changed.iter()) {
+            entries.insert(key_of(entry), entry.clone());
+        }
+        Prefix2AsSummaryJson {
+            rib_dump_urls: self.rib_dump_urls.clone(),
+            pfx2as: entries.into_values().collect(),
+            aggregated: self.aggregated,
+        }
+    }
+
+    /// Number of changed entries.
+    pub fn len(&self) -> usize {
+        self.added.len() + self.changed.len() + self.removed.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+fn key_of(entry: &Prefix2AsCount) -> Pfx2AsKey {
+    Pfx2AsKey {
+        prefix: entry.prefix.clone(),
+        asn: entry.asn,
+    }
+}
+
+/// Entries of a summary by key, with their JSON value for comparison.
+fn entries_by_key(
+    summary: &Prefix2AsSummaryJson,
+) -> Result<BTreeMap<Pfx2AsKey, (Value, &Prefix2AsCount)>> {
+    summary
+        .pfx2as
+        .iter()
+        .map(|e| Ok((key_of(e), (serde_json::to_value(e)?, e))))
+        .collect()
+}
+
+/// Sort the entries of a summary by prefix and origin, the order of reconstructed summaries.
+fn sorted(summary: &Prefix2AsSummaryJson) -> Prefix2AsSummaryJson {
+    let mut summary = summary.clone();
+    summary.pfx2as.sort_by_key(key_of);
+    summary
+}
+
+/// Delta-encoded archive of pfx2as summaries in a directory, see the [module documentation](self).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DeltaArchive {
+    dir: String,
+    full_every: Option<usize>,
+}
+
+impl DeltaArchive {
+    pub fn new(dir: &str) -> Self {
+        DeltaArchive {
+            dir: dir.trim_end_matches('/').to_string(),
+            full_every: None,
+        }
+    }
+
+    /// Write a full snapshot every `n` snapshots instead of only the first one.
+    pub fn with_full_every(mut self, n: usize) -> Self {
+        self.full_every = Some(n).filter(|n| *n > 0);
+        self
+    }
+
+    fn index_path(&self) -> String {
+        format!("{}/index.json", self.dir)
+    }
+
+    /// Index of the archive, empty if nothing is archived yet.
+    pub fn index(&self, sink: &dyn OutputSink) -> Result<ArchiveIndex> {
+        let path = self.index_path();
+        // a missing index starts a new archive, while other read errors must not overwrite one
+        let exists = match storage::is_object_store(path.as_str()) {
+            true => !storage::list(path.as_str())?.is_empty(),
+            false => std::path::Path::new(path.as_str()).exists(),
+        };
+        match exists {
+            true => Ok(serde_json::from_value(sink.read_json(path.as_str())?)?),
+            false => Ok(ArchiveIndex::default()),
+        }
+    }
+
+    /// Archive the summary of a date. Archiving a date again replaces its snapshot; dates before
+    /// the last archived one are rejected.
+    pub fn append(
+        &self,
+        sink: &dyn OutputSink,
+        date: NaiveDate,
+        summary: &Prefix2AsSummaryJson,
+    ) -> Result<ArchivedSnapshot> {
+        let mut index = self.index(sink)?;
+        if let Some(last) = index.snapshots.last() {
+            if date < last.date {
+                return Err(anyhow!(
+                    "cannot archive {} before the last archived date {}",
+                    date,
+                    last.date
+                ));
+            }
+            if date == last.date {
+                index.snapshots.pop();
+            }
+        }
+        let since_full = index
+            .snapshots
+            .iter()
+            .rev()
+            .take_while(|s| s.kind == SnapshotKind::Delta)
+            .count();
+        let full = match (index.snapshots.last(), self.full_every) {
+            (None, _) => true,
+            (Some(_), Some(n)) => since_full + 1 >= n,
+            (Some(_), None) => false,
+        };
+        let snapshot = match full {
+            true => {
+                let file = format!("{}.full.json.bz2", date.format("%Y-%m-%d"));
+                let path = format!("{}/{}", self.dir, file);
+                sink.write_json(path.as_str(), &serde_json::to_value(sorted(summary))?)?;
+                ArchivedSnapshot {
+                    date,
+                    kind: SnapshotKind::Full,
+                    file,
+                    entries: summary.pfx2as.len(),
+                }
+            }
+            false => {
+                let previous = self.reconstruct_from(sink, &index.snapshots)?;
+                let delta = Pfx2AsDelta::between(&previous, summary)?;
+                let file = format!("{}.delta.json.bz2", date.format("%Y-%m-%d"));
+                let path = format!("{}/{}", self.dir, file);
+                sink.write_json(path.as_str(), &serde_json::to_value(&delta)?)?;
+                ArchivedSnapshot {
+                    date,
+                    kind: SnapshotKind::Delta,
+                    file,
+                    entries: summary.pfx2as.len(),
+                }
+            }
+        };
+        index.snapshots.push(snapshot.clone());
+        sink.write_json(self.index_path().as_str(), &serde_json::to_value(&index)?)?;
+        Ok(snapshot)
+    }
+
+    /// Reconstruct the summary of the last archived date on or before `date`.
+    pub fn reconstruct(
+        &self,
+        sink: &dyn OutputSink,
+        date: NaiveDate,
+    ) -> Result<(NaiveDate, Prefix2AsSummaryJson)> {
+        let index = self.index(sink)?;
+        let count = index.snapshots.iter().filter(|s| s.date <= date).count();
+        if count == 0 {
+            return Err(anyhow!("no archived snapshot on or before {}", date));
+        }
+        let snapshots = &index.snapshots[..count];
+        Ok((
+            snapshots[count - 1].date,
+            self.reconstruct_from(sink, snapshots)?,
+        ))
+    }
+
+    /// Summary of the last of `snapshots`, from the last full one and the deltas after it.
+    fn reconstruct_from(
+        &self,
+        sink: &dyn OutputSink,
+        snapshots: &[ArchivedSnapshot],
+    ) -> Result<Prefix2AsSummaryJson> {
+        let start = snapshots
+            .iter()
+            .rposition(|s| s.kind == SnapshotKind::Full)
+            .ok_or_else(|| anyhow!("no full snapshot in archive {}", self.dir))?;
+        let path = format!("{}/{}", self.dir, snapshots[start].file);
+        let mut summary: Prefix2AsSummaryJson =
+            serde_json::from_value(sink.read_json(path.as_str())?)?;
+        for snapshot in &snapshots[start + 1..] {
+            let path = format!("{}/{}", self.dir, snapshot.file);
+            let delta: Pfx2AsDelta = serde_json::from_value(sink.read_json(path.as_str())?)?;
+            summary = delta.apply(&summary);
+        }
+        Ok(sorted(&summary))
+    }
+}
@@ -0,0 +1,423 @@
+//! Self-contained `.ribeye` artifact of all summaries, indexed for lookups.
+//!
+//! An artifact bundles the summaries of all processors of a run into one file, together with
+//! indexes answering the queries of [lookup](crate::lookup) without loading the summaries:
+//! longest-prefix matches of IP addresses and the prefixes and relationships of an ASN. Indexes
+//! are arrays of fixed-size little-endian records sorted by their key, searched in place, so the
+//! file can as well be memory-mapped.
+//!
+//! Layout, all integers little-endian:
+//!
+//! - header: magic `RIBEYE\0\0`, `u32` format version, `u32` number of sections
+//! - section table: per section a name (32 bytes, zero-padded), `u64` offset and `u64` length
+//! - sections, 8-byte aligned:
+//!   - `pfx2as.v4`: 16-byte records `u32` network, `u8` length, 3 padding bytes, `u32` origin,
+//!     `u32` count, sorted by network, length and origin
+//!   - `pfx2as.v6`: 32-byte records `u128` network, `u8` length, 3 padding bytes, `u32` origin,
+//!     `u32` count, 4 padding bytes, sorted likewise
+//!   - `pfx2as.origins`: 12-byte records `u32` origin, `u32` address family (4 or 6), `u32`
+//!     record index in `pfx2as.v4` or `pfx2as.v6`, sorted by origin
+//!   - `as2rel`: 16-byte records `u32` ASN, `u32` related ASN, `u32` kind (0 neighbor, 1 customer,
+//!     2 provider), `u32` number of paths, sorted by ASN, kind and related ASN
+//!   - `peers`: CBOR array of the `peer-stats` summary's peers
+//!   - `summary:<processor>`: CBOR summary of each processor
+
+use crate::lookup::{AsnLookupResult, CoveringPrefix, IpLookupResult};
+use crate::processors::{
+    As2relSummaryJson, FileSink, OutputSink, PeerInfoEntry, PeerInfoSummaryJson,
+    Prefix2AsSummaryJson,
+};
+use anyhow::{anyhow, Result};
+use ipnet::{IpNet, Ipv4Net, Ipv6Net};
+use serde::Deserialize;
+use serde_json::Value;
+use std::collections::{BTreeMap, BTreeSet};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+/// Magic bytes at the start of every artifact.
+pub const ARTIFACT_MAGIC: &[u8; 8] = b"RIBEYE\0\0";
+/// Version of the artifact layout.
+pub const ARTIFACT_VERSION: u32 = 1;
+
+const NAME_LEN: usize = 32;
+const TABLE_ENTRY_LEN: usize = NAME_LEN + 16;
+const V4_RECORD_LEN: usize = 16;
+const V6_RECORD_LEN: usize = 32;
+const ORIGIN_RECORD_LEN: usize = 12;
+const AS2REL_RECORD_LEN: usize = 16;
+
+const NEIGHBOR: u32 = 0;
+const CUSTOMER: u32 = 1;
+const PROVIDER: u32 = 2;
+
+/// Path of the artifact under an output root directory.
+pub fn get_artifact_path(output_dir: &str) -> String {
+    format!("{}/latest.ribeye", output_dir)
+}
+
+/// Build an artifact from summaries by processor name. The indexes are built from the `pfx2as`,
+/// `as2rel` and `peer-stats` summaries, if present.
+pub fn build_artifact(summaries: &BTreeMap<String, Value>) -> Result<Vec<u8>> {
+    let mut sections: Vec<(String, Vec<u8>)> = vec![];
+
+    let (mut v4, mut v6) = (vec![], vec![]);
+    if let Some(summary) = summaries.get("pfx2as") {
+        for entry in Prefix2AsSummaryJson::deserialize(summary)?.pfx2as {
+            match entry.prefix.parse::<IpNet>().map(|p| p.trunc()) {
+                Ok(IpNet::V4(p)) => v4.push((
+                    u32::from(p.network()),
+                    p.prefix_len(),
+                    entry.asn,
+                    entry.count as u32,
+                )),
+                Ok(IpNet::V6(p)) => v6.push((
+                    u128::from(p.network()),
+                    p.prefix_len(),
+                    entry.asn,
+                    entry.count as u32,
+                )),
+                Err(_) => {}
+            }
+        }
+    }
+    v4.sort();
+    v6.sort();
+    let mut origins = vec![];
+    let mut data = vec![];
+    for (i, (network, len, asn, count)) in v4.iter().enumerate() {
+        data.extend(network.to_le_bytes());
+        data.extend([*len, 0, 0, 0]);
+        data.extend(asn.to_le_bytes());
+        data.extend(count.to_le_bytes());
+        origins.push((*asn, 4u32, i as u32));
+    }
+    sections.push(("pfx2as.v4".to_string(), data));
+    let mut data = vec![];
+    for (i, (network, len, asn, count)) in v6.iter().enumerate() {
+        data.extend(network.to_le_bytes());
+        data.extend([*len, 0, 0, 0]);
+        data.extend(asn.to_le_bytes());
+        data.extend(count.to_le_bytes());
+        data.extend([0; 4]);
+        origins.push((*asn, 6u32, i as u32));
+    }
+    sections.push(("pfx2as.v6".to_string(), data));
+    origins.sort();
+    sections.push(("pfx2as.origins".to_string(), u32_records(&origins)));
+
+    let mut relationships: BTreeMap<(u32, u32, u32), u32> = BTreeMap::new();
+    if let Some(summary) = summaries.get("as2rel") {
+        for entry in As2relSummaryJson::deserialize(summary)?.as2rel {
+            let paths = entry.paths_count as u32;
+            let (kind1, kind2) = match entry.rel {
+                // asn1 is the provider of asn2
+                1 => (CUSTOMER, PROVIDER),
+                _ => (NEIGHBOR, NEIGHBOR),
+            };
+            *relationships
+                .entry((entry.asn1, kind1, entry.asn2))
+                .or_default() += paths;
+            *relationships
+                .entry((entry.asn2, kind2, entry.asn1))
+                .or_default() += paths;
+        }
+    }
+    let records: Vec<(u32, u32, u32, u32)> = relationships
+        .into_iter()
+        .map(|((asn, kind, other), paths)| (asn, other, kind, paths))
+        .collect();
+    let mut data = vec![];
+    for (asn, other, kind, paths) in records {
+        for value in [asn, other, kind, paths] {
+            data.extend(value.to_le_bytes());
+        }
+    }
+    sections.push(("as2rel".to_string(), data));
+
+    let mut peers: Vec<PeerInfoEntry> = match summaries.get("peer-stats") {
+        Some(summary) => PeerInfoSummaryJson::deserialize(summary)?
+            .peers
+            .into_iter()
+            .collect(),
+        None => vec![],
+    };
+    peers.sort_by_key(|p| (p.asn, p.collector.clone(), p.ip));
+    sections.push(("peers".to_string(), cbor(&peers)?));
+
+    for (processor, summary) in summaries {
+        sections.push((format!("summary:{}", processor), cbor(summary)?));
+    }
+    Ok(assemble(&sections))
+}
+
+fn cbor<T: serde::Serialize>(value: &T) -> Result<Vec<u8>> {
+    let mut data = vec![];
+    ciborium::into_writer(value, &mut data)?;
+    Ok(data)
+}
+
+fn u32_records(records: &[(u32, u32, u32)]) -> Vec<u8> {
+    let mut data = vec![];
+    for (a, b, c) in records {
+        for value in [a, b, c] {
+            data.extend(value.to_le_bytes());
+        }
+    }
+    data
+}
+
+fn align(len: usize) -> usize {
+    len.div_ceil(8) * 8
+}
+
+fn assemble(sections: &[(String, Vec<u8>)]) -> Vec<u8> {
+    let mut data = ARTIFACT_MAGIC.to_vec();
+    data.extend(ARTIFACT_VERSION.to_le_bytes());
+    data.extend((sections.len() as u32).to_le_bytes());
+    let mut offset = align(data.len() + sections.len() * TABLE_ENTRY_LEN);
+    for (name, content) in sections {
+        let mut padded = [0u8; NAME_LEN];
+        let name = &name.as_bytes()[..name.len().min(NAME_LEN)];
+        padded[..name.len()].copy_from_slice(name);
+        data.extend(padded);
+        data.extend((offset as u64).to_le_bytes());
+        data.extend((content.len() as u64).to_le_bytes());
+        offset = align(offset + content.len());
+    }
+    for (_, content) in sections {
+        data.resize(align(data.len()), 0);
+        data.extend(content);
+    }
+    data
+}
+
+fn read_u32(data: &[u8], at: usize) -> u32 {
+    u32::from_le_bytes(data[at..at + 4].try_into().unwrap())
+}
+
+fn read_u64(data: &[u8], at: usize) -> u64 {
+    u64::from_le_bytes(data[at..at + 8].try_into().unwrap())
+}
+
+fn read_u128(data: &[u8], at: usize) -> u128 {
+    u128::from_le_bytes(data[at..at + 16].try_into().unwrap())
+}
+
+/// A loaded `.ribeye` artifact.
+#[derive(Debug, Clone)]
+pub struct Artifact {
+    data: Vec<u8>,
+    /// offset and length of each section
+    sections: BTreeMap<String, (usize, usize)>,
+}
+
+impl Artifact {
+    /// Load an artifact from a local path, URL or object store.
+    pub fn load(path: &str) -> Result<Self> {
+        Self::from_bytes(FileSink.read_bytes(path)?)
+    }
+
+    pub fn from_bytes(data: Vec<u8>) -> Result<Self> {
+        if data.len() < 16 || &data[..8] != ARTIFACT_MAGIC {
+            return Err(anyhow!("not a ribeye artifact"));
+        }
+        let version = read_u32(&data, 8);
+        if version != ARTIFACT_VERSION {
+            return Err(anyhow!("unsupported artifact version {}", version));
+        }
+        let count = read_u32(&data, 12) as usize;
+        let mut sections = BTreeMap::new();
+        for i in 0..count {
+            let at = 16 + i * TABLE_ENTRY_LEN;
+            if at + TABLE_ENTRY_LEN > data.len() {
+                return Err(anyhow!("truncated artifact section table"));
+            }
+            let name = &data[at..at + NAME_LEN];
+            let name = String::from_utf8_lossy(
+                &name[..name.iter().position(|b| *b == 0).unwrap_or(NAME_LEN)],
+            )
+            .to_string();
+            let offset = read_u64(&data, at + NAME_LEN) as usize;
+            let len = read_u64(&data, at + NAME_LEN + 8) as usize;
+            if offset.checked_add(len).is_none_or(|end| end > data.len()) {
+                return Err(anyhow!("truncated artifact section {}", name));
+            }
+            sections.insert(name, (offset, len));
+        }
+        Ok(Artifact { data, sections })
+    }
+
+    fn section(&self, name: &str) -> &[u8] {
+        match self.sections.get(name) {
+            Some((offset, len)) => &self.data[*offset..*offset + *len],
+            None => &[],
+        }
+    }
+
+    /// Names of the processors whose summaries the artifact bundles.
+    pub fn processors(&self) -> Vec<String> {
+        self.sections
+            .keys()
+            .filter_map(|name| name.strip_prefix("summary:"))
+            .map(|name| name.to_string())
+            .collect()
+    }
+
+    /// Summary of a processor, none if not bundled.
+    pub fn summary(&self, processor: &str) -> Result<Option<Value>> {
+        let name = format!("summary:{}", processor);
+        match self.sections.contains_key(&name) {
+            true => Ok(Some(ciborium::from_reader(self.section(name.as_str()))?)),
+            false => Ok(None),
+        }
+    }
+
+    /// Index of the first record of a sorted section whose key is not less than `key`.
+    fn lower_bound<K: Ord>(
+        records: &[u8],
+        record_len: usize,
+        key: &K,
+        key_of: impl Fn(&[u8]) -> K,
+    ) -> usize {
+        let (mut low, mut high) = (0, records.len() / record_len);
+        while low < high {
+            let mid = (low + high) / 2;
+            match key_of(&records[mid * record_len..(mid + 1) * record_len]) < *key {
+                true => low = mid + 1,
+                false => high = mid,
+            }
+        }
+        low
+    }
+
+    /// Prefix-origin records of a prefix, with their counts.
+    fn prefix_records(&self, prefix: &IpNet) -> Vec<(u32, u32)> {
+        let (records, record_len, key): (&[u8], usize, (u128, u8)) = match prefix {
+            IpNet::V4(p) => (
+                self.section("pfx2as.v4"),
+                V4_RECORD_LEN,
+                (u32::from(p.network()) as u128, p.prefix_len()),
+            ),
+            IpNet::V6(p) => (
+                self.section("pfx2as.v6"),
+                V6_RECORD_LEN,
+                (u128::from(p.network()), p.prefix_len()),
+            ),
+        };
+        let network_len = record_len - 16;
+        let key_of = |record: &[u8]| -> (u128, u8) {
+            match network_len {
+                0 => (read_u32(record, 0) as u128, record[4]),
+                _ => (read_u128(record, 0), record[16]),
+            }
+        };
+        let start = Self::lower_bound(records, record_len, &key, key_of);
+        records[start * record_len..]
+            .chunks_exact(record_len)
+            .take_while(|record| key_of(record) == key)
+            .map(|record| {
+                let at = record_len - 8 - if network_len == 0 { 0 } else { 4 };
+                (read_u32(record, at), read_u32(record, at + 4))
+            })
+            .collect()
+    }
+
+    /// Longest-prefix match of an IP address, with all covering prefixes, as
+    /// [LookupData::lookup_ip](crate::lookup::LookupData::lookup_ip).
+    pub fn lookup_ip(&self, ip: IpAddr) -> IpLookupResult {
+        let max_len = match ip {
+            IpAddr::V4(_) => 32,
+            IpAddr::V6(_) => 128,
+        };
+        let mut covering = vec![];
+        for len in (0..=max_len).rev() {
+            let Ok(prefix) = IpNet::new(ip, len).map(|p| p.trunc()) else {
+                continue;
+            };
+            for (origin, count) in self.prefix_records(&prefix) {
+                covering.push((
+                    prefix,
+                    CoveringPrefix {
+                        prefix: prefix.to_string(),
+                        origin,
+                        visibility: count as usize,
+                    },
+                ));
+            }
+        }
+        let origins = match covering.first() {
+            None => vec![],
+            Some((most_specific, _)) => covering
+                .iter()
+                .filter(|(p, _)| p == most_specific)
+                .map(|(_, c)| c.origin)
+                .collect(),
+        };
+        IpLookupResult {
+            ip,
+            origins,
+            covering_prefixes: covering.into_iter().map(|(_, c)| c).collect(),
+        }
+    }
+
+    /// Prefixes and relationships of an ASN and its collector peers, as
+    /// [LookupData::lookup_asn](crate::lookup::LookupData::lookup_asn).
+    pub fn lookup_asn(&self, asn: u32) -> Result<AsnLookupResult> {
+        let origins = self.section("pfx2as.origins");
+        let start = Self::lower_bound(origins, ORIGIN_RECORD_LEN, &asn, |r| read_u32(r, 0));
+        let mut prefixes = vec![];
+        for record in origins[start * ORIGIN_RECORD_LEN..]
+            .chunks_exact(ORIGIN_RECORD_LEN)
+            .take_while(|r| read_u32(r, 0) == asn)
+        {
+            let index = read_u32(record, 8) as usize;
+            let (prefix, count) = match read_u32(record, 4) {
+                4 => {
+                    let r = &self.section("pfx2as.v4")[index * V4_RECORD_LEN..];
+                    let network = Ipv4Addr::from(read_u32(r, 0));
+                    (IpNet::V4(Ipv4Net::new(network, r[4])?), read_u32(r, 12))
+                }
+                _ => {
+                    let r = &self.section("pfx2as.v6")[index * V6_RECORD_LEN..];
+                    let network = Ipv6Addr::from(read_u128(r, 0));
+                    (IpNet::V6(Ipv6Net::new(network, r[16])?), read_u32(r, 24))
+                }
+            };
+            prefixes.push(CoveringPrefix {
+                prefix: prefix.to_string(),
+                origin: asn,
+                visibility: count as usize,
+            });
+        }
+        prefixes.sort_by(|a, b| a.prefix.cmp(&b.prefix));
+
+        let (mut neighbors, mut providers, mut customers) =
+            (BTreeSet::new(), BTreeSet::new(), BTreeSet::new());
+        let as2rel = self.section("as2rel");
+        let start = Self::lower_bound(as2rel, AS2REL_RECORD_LEN, &asn, |r| read_u32(r, 0));
+        for record in as2rel[start * AS2REL_RECORD_LEN..]
+            .chunks_exact(AS2REL_RECORD_LEN)
+            .take_while(|r| read_u32(r, 0) == asn)
+        {
+            let other = read_u32(record, 4);
+            match read_u32(record, 8) {
+                CUSTOMER => customers.insert(other),
+                PROVIDER => providers.insert(other),
+                _ => neighbors.insert(other),
+            };
+        }
+
+        let peers: Vec<PeerInfoEntry> = match self.sections.contains_key("peers") {
+            true => ciborium::from_reader(self.section("peers"))?,
+            false => vec![],
+        };
+        Ok(AsnLookupResult {
+            asn,
+            prefixes,
+            neighbors,
+            providers,
+            customers,
+            collector_peers: peers.into_iter().filter(|p| p.asn == asn).collect(),
+        })
+    }
+}
@@ -0,0 +1,273 @@
+//! Read-only consistency audit of an output root directory.
+//!
+//! [OutputAudit::run] walks an output root directory, local or on an object store, without
+//! writing to it, and reports an [AuditIssue] for:
+//!
+//! - `parse`: per-collector outputs and summaries that cannot be read or decoded
+//! - `schema`: outputs and summaries of built-in processors not matching their JSON schemas
+//! - `latest`: latest outputs of a collector older than its newest dated output
+//! - `index`: `index.json` manifests (see [index](crate::index)) listing missing files or missing
+//!   files of the tree
+//! - `signature`: outputs and summaries without a valid detached signature, if a public key is
+//!   given
+//!
+//! Encrypted outputs are read with the sink they were written with, e.g. an `EncryptingSink`.
+
+use crate::index::{build_output_index, DatasetIndex, RootIndex, INDEX_FILE_NAME};
+use crate::listing::{list_files, parse_output_path, ListedOutput};
+use crate::processors::schema::{processor_schemas, validate};
+use crate::processors::{FileSink, OutputSink, RIB_TIMESTAMP_FIELD};
+#[cfg(feature = "signing")]
+use crate::signing::{verify_file, PublicKey};
+use crate::timeseries::TIMESERIES_DIR;
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{BTreeMap, BTreeSet};
+use std::sync::Arc;
+use tracing::info;
+
+/// Inconsistency found in an output root directory.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AuditIssue {
+    /// check that failed: `parse`, `schema`, `latest`, `index` or `signature`
+    pub check: String,
+    /// path relative to the output root directory
+    pub path: String,
+    pub detail: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AuditReport {
+    /// number of per-collector outputs and summaries read
+    pub files: usize,
+    pub issues: Vec<AuditIssue>,
+}
+
+impl AuditReport {
+    /// Whether no issue was found.
+    pub fn passed(&self) -> bool {
+        self.issues.is_empty()
+    }
+
+    fn issue(&mut self, check: &str, path: &str, detail: String) {
+        self.issues.push(AuditIssue {
+            check: check.to_string(),
+            path: path.to_string(),
+            detail,
+        });
+    }
+}
+
+/// Audit of an output root directory, see the [module documentation](self).
+pub struct OutputAudit {
+    output_dir: String,
+    sink: Arc<dyn OutputSink>,
+    #[cfg(feature = "signing")]
+    public_key: Option<PublicKey>,
+}
+
+impl OutputAudit {
+    pub fn new(output_dir: &str) -> Self {
+        OutputAudit {
+            output_dir: output_dir.trim_end_matches('/').to_string(),
+            sink: Arc::new(FileSink),
+            #[cfg(feature = "signing")]
+            public_key: None,
+        }
+    }
+
+    /// Read outputs with the given sink, e.g. to decrypt them.
+    pub fn with_sink(mut self, sink: Arc<dyn OutputSink>) -> Self {
+        self.sink = sink;
+        self
+    }
+
+    /// Also verify the detached signatures of outputs and summaries with the given key.
+    #[cfg(feature = "signing")]
+    pub fn with_public_key(mut self, key: PublicKey) -> Self {
+        self.public_key = Some(key);
+        self
+    }
+
+    /// Run the audit. Fails only if the output root directory cannot be listed.
+    pub fn run(&self) -> Result<AuditReport> {
+        let mut report = AuditReport::default();
+        let mut files = list_files(self.output_dir.as_str())?;
+        files.sort();
+
+        let mut summaries = vec![];
+        let mut outputs = vec![];
+        for path in &files {
+            if path.split('/').next() == Some(TIMESERIES_DIR) {
+                continue;
+            }
+            match path.split('/').collect::<Vec<&str>>().as_slice() {
+                [processor, "latest.json.bz2"] => summaries.push((processor.to_string(), path)),
+                _ => outputs.extend(parse_output_path(path)),
+            }
+        }
+
+        for (processor, path) in &summaries {
+            if let Some(value) = self.read(&mut report, path) {
+                self.check_schema(&mut report, processor, path, &value, true);
+            }
+            self.check_signature(&mut report, path);
+        }
+
+        let mut collectors: BTreeMap<(&str, &str), Vec<&ListedOutput>> = BTreeMap::new();
+        for output in &outputs {
+            collectors
+                .entry((output.processor.as_str(), output.collector.as_str()))
+                .or_default()
+                .push(output);
+        }
+        for ((processor, collector), outputs) in collectors {
+            let mut latest_ts = None;
+            for output in &outputs {
+                if let Some(value) = self.read(&mut report, output.path.as_str()) {
+                    self.check_schema(&mut report, processor, &output.path, &value, false);
+                    if output.ts.is_none() {
+                        latest_ts = value[RIB_TIMESTAMP_FIELD].as_i64();
+                    }
+                }
+                self.check_signature(&mut report, output.path.as_str());
+            }
+            let newest = outputs.iter().filter_map(|o| o.ts).max();
+            if let (Some(latest_ts), Some(newest)) = (latest_ts, newest) {
+                if latest_ts < newest {
+                    report.issue(
+                        "latest",
+                        format!("{}/{}/latest.json.bz2", processor, collector).as_str(),
+                        format!(
+                            "latest output of RIB dump {} is older than dated output of RIB dump {}",
+                            latest_ts, newest
+                        ),
+                    );
+                }
+            }
+        }
+
+        if files.iter().any(|f| f == INDEX_FILE_NAME) {
+            self.check_index(&mut report, &files)?;
+        }
+
+        info!(
+            phase = "audit",
+            "audited {} files under {}: {} issues",
+            report.files,
+            self.output_dir,
+            report.issues.len()
+        );
+        Ok(report)
+    }
+
+    fn full_path(&self, path: &str) -> String {
+        format!("{}/{}", self.output_dir, path)
+    }
+
+    fn read(&self, report: &mut AuditReport, path: &str) -> Option<Value> {
+        report.files += 1;
+        match self.sink.read_json(self.full_path(path).as_str()) {
+            Ok(value) => Some(value),
+            Err(e) => {
+                report.issue("parse", path, e.to_string());
+                None
+            }
+        }
+    }
+
+    fn check_schema(
+        &self,
+        report: &mut AuditReport,
+        processor: &str,
+        path: &str,
+        value: &Value,
+        summary: bool,
+    ) {
+        // datasets of other tools or derived outputs have no schema
+        let Some(schemas) = processor_schemas(processor) else {
+            return;
+        };
+        let schema = match summary {
+            true => &schemas.summary,
+            false => &schemas.output,
+        };
+        let errors = validate(value, schema);
+        if !errors.is_empty() {
+            report.issue("schema", path, errors.join("; "));
+        }
+    }
+
+    #[cfg(feature = "signing")]
+    fn check_signature(&self, report: &mut AuditReport, path: &str) {
+        let Some(key) = &self.public_key else {
+            return;
+        };
+        if let Err(e) = verify_file(self.full_path(path).as_str(), None, key) {
+            report.issue("signature", path, e.to_string());
+        }
+    }
+
+    #[cfg(not(feature = "signing"))]
+    fn check_signature(&self, _report: &mut AuditReport, _path: &str) {}
+
+    /// Compare the written indexes with the index of the current tree.
+    fn check_index(&self, report: &mut AuditReport, files: &[String]) -> Result<()> {
+        let mut indexed = BTreeSet::new();
+        match self
+            .sink
+            .read_json(self.full_path(INDEX_FILE_NAME).as_str())
+            .and_then(|v| Ok(serde_json::from_value::<RootIndex>(v)?))
+        {
+            Ok(root) => {
+                for dataset in root.datasets {
+                    indexed.extend(dataset.latest);
+                    indexed.extend(dataset.variants);
+                    match self
+                        .sink
+                        .read_json(self.full_path(dataset.index.as_str()).as_str())
+                        .and_then(|v| Ok(serde_json::from_value::<DatasetIndex>(v)?))
+                    {
+                        Ok(dataset_index) => indexed.extend(indexed_paths(&dataset_index)),
+                        Err(e) => report.issue("index", dataset.index.as_str(), e.to_string()),
+                    }
+                }
+            }
+            Err(e) => {
+                report.issue("index", INDEX_FILE_NAME, e.to_string());
+                return Ok(());
+            }
+        }
+
+        let files: BTreeSet<&String> = files.iter().collect();
+        for path in &indexed {
+            if !files.contains(path) {
+                report.issue("index", path, "listed in the index but missing".to_string());
+            }
+        }
+        for dataset in build_output_index(self.output_dir.as_str())? {
+            let paths = dataset.latest.iter().chain(&dataset.variants).cloned();
+            for path in paths.chain(indexed_paths(&dataset)) {
+                if !indexed.contains(&path) {
+                    report.issue("index", path.as_str(), "missing from the index".to_string());
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Per-collector paths listed in a dataset index.
+fn indexed_paths(dataset: &DatasetIndex) -> Vec<String> {
+    dataset
+        .collectors
+        .iter()
+        .flat_map(|c| {
+            c.latest
+                .iter()
+                .cloned()
+                .chain(c.files.iter().map(|f| f.path.clone()))
+        })
+        .collect()
+}
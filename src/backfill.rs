@@ -0,0 +1,241 @@
+//! Historical backfill planning and checkpointing.
+//!
+//! A backfill processes the midnight RIB dumps of every day (or of the first day of every month)
+//! in a date range, e.g. years of archives for a new processor. [plan_backfill] lists the RIB
+//! dumps through the broker, month by month; [BackfillState] records the processed and failed RIB
+//! dumps in a local state file, saved after each RIB, so that a multi-week backfill can be stopped
+//! and resumed where it left off. See `ribeye backfill`.
+
+use crate::broker::BrokerConfig;
+use crate::collectors::CollectorProfiles;
+use crate::processors::RibMeta;
+use anyhow::{anyhow, Result};
+use chrono::{Datelike, Months, NaiveDate, Timelike};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::{Display, Formatter};
+use std::path::Path;
+use std::str::FromStr;
+use tracing::info;
+
+/// Which days of the range are backfilled.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum BackfillInterval {
+    /// every day
+    #[default]
+    Daily,
+    /// the first day of every month
+    Monthly,
+}
+
+impl FromStr for BackfillInterval {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "daily" | "day" => Ok(BackfillInterval::Daily),
+            "monthly" | "month" => Ok(BackfillInterval::Monthly),
+            _ => Err(anyhow!("unknown backfill interval: {}", s)),
+        }
+    }
+}
+
+impl Display for BackfillInterval {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BackfillInterval::Daily => write!(f, "daily"),
+            BackfillInterval::Monthly => write!(f, "monthly"),
+        }
+    }
+}
+
+/// Days of the range `from..=to` to backfill.
+pub fn backfill_dates(
+    from: NaiveDate,
+    to: NaiveDate,
+    interval: BackfillInterval,
+) -> Vec<NaiveDate> {
+    let mut dates = vec![];
+    let mut next = match interval {
+        BackfillInterval::Daily => Some(from),
+        // the first day of the month on or after `from`
+        BackfillInterval::Monthly => match from.day() {
+            1 => Some(from),
+            _ => from
+                .with_day(1)
+                .and_then(|d| d.checked_add_months(Months::new(1))),
+        },
+    };
+    while let Some(date) = next.filter(|d| *d <= to) {
+        dates.push(date);
+        next = match interval {
+            BackfillInterval::Daily => date.succ_opt(),
+            BackfillInterval::Monthly => date.checked_add_months(Months::new(1)),
+        };
+    }
+    dates
+}
+
+/// Daily RIB dumps, by default the midnight ones, of the given days and collectors (all if
+/// empty), oldest first. The hour of each collector's daily dump is that of its profile.
+pub fn select_backfill_ribs(
+    rib_metas: Vec<RibMeta>,
+    dates: &[NaiveDate],
+    collectors: &[String],
+    profiles: &CollectorProfiles,
+) -> Vec<RibMeta> {
+    let dates: BTreeSet<&NaiveDate> = dates.iter().collect();
+    let mut seen = BTreeSet::new();
+    let mut selected: Vec<RibMeta> = rib_metas
+        .into_iter()
+        .filter(|r| {
+            profiles.is_daily_dump(r.collector.as_str(), r.rib_dump_url.as_str(), &r.timestamp)
+                && r.timestamp.minute() == 0
+                && dates.contains(&r.timestamp.date())
+                && (collectors.is_empty() || collectors.contains(&r.collector))
+        })
+        .filter(|r| seen.insert(r.rib_dump_url.clone()))
+        .collect();
+    selected.sort_by(|a, b| {
+        (a.timestamp, a.collector.as_str()).cmp(&(b.timestamp, b.collector.as_str()))
+    });
+    selected
+}
+
+/// List the RIB dumps to backfill through the broker, querying one month at a time.
+pub fn plan_backfill(
+    broker: &BrokerConfig,
+    from: NaiveDate,
+    to: NaiveDate,
+    interval: BackfillInterval,
+    collectors: &[String],
+    profiles: &CollectorProfiles,
+) -> Result<Vec<RibMeta>> {
+    let dates = backfill_dates(from, to, interval);
+    let months: BTreeSet<NaiveDate> = dates.iter().filter_map(|d| d.with_day(1)).collect();
+    let mut rib_metas = vec![];
+    for month in months {
+        let ts_start = month.and_hms_opt(0, 0, 0).unwrap_or_default();
+        let ts_end = month
+            .checked_add_months(Months::new(1))
+            .unwrap_or(month)
+            .min(to.succ_opt().unwrap_or(to))
+            .and_hms_opt(0, 0, 0)
+            .unwrap_or_default();
+        info!(
+            phase = "backfill",
+            "searching for RIB dumps of {}",
+            month.format("%Y-%m")
+        );
+        let items = broker.query_ribs(ts_start, ts_end)?;
+        rib_metas.extend(
+            items
+                .iter()
+                .map(|item| RibMeta::from_broker_item(item, profiles)),
+        );
+    }
+    Ok(select_backfill_ribs(
+        rib_metas, &dates, collectors, profiles,
+    ))
+}
+
+/// Progress of a backfill, persisted as JSON.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BackfillState {
+    /// first day of the range, `YYYY-MM-DD`
+    pub from: String,
+    /// last day of the range, `YYYY-MM-DD`
+    pub to: String,
+    pub interval: String,
+    pub collectors: Vec<String>,
+    pub processors: Vec<String>,
+    /// RIB dump URLs processed successfully
+    pub done: BTreeSet<String>,
+    /// RIB dump URLs that failed with their last error, retried on resume
+    pub failed: BTreeMap<String, String>,
+}
+
+impl BackfillState {
+    pub fn new(
+        from: NaiveDate,
+        to: NaiveDate,
+        interval: BackfillInterval,
+        collectors: &[String],
+        processors: &[String],
+    ) -> Self {
+        BackfillState {
+            from: from.to_string(),
+            to: to.to_string(),
+            interval: interval.to_string(),
+            collectors: collectors.to_vec(),
+            processors: processors.to_vec(),
+            done: BTreeSet::new(),
+            failed: BTreeMap::new(),
+        }
+    }
+
+    /// Resume the state saved at the given path, or start with `new` if there is none. A saved
+    /// state of a backfill with different parameters is rejected.
+    pub fn load_or(path: &str, new: BackfillState) -> Result<Self> {
+        if !Path::new(path).exists() {
+            return Ok(new);
+        }
+        let content = std::fs::read_to_string(path)?;
+        let state: BackfillState = serde_json::from_str(content.as_str())
+            .map_err(|e| anyhow!("invalid backfill state {}: {}", path, e))?;
+        let params = |s: &BackfillState| {
+            (
+                s.from.clone(),
+                s.to.clone(),
+                s.interval.clone(),
+                s.collectors.clone(),
+                s.processors.clone(),
+            )
+        };
+        if params(&state) != params(&new) {
+            return Err(anyhow!(
+                "backfill state {} belongs to a backfill with different parameters ({} to {}, {}); \
+                 remove it or choose another state file",
+                path,
+                state.from,
+                state.to,
+                state.interval
+            ));
+        }
+        Ok(state)
+    }
+
+    /// Write the state, replacing the file atomically so that an interrupted run keeps the
+    /// previous state.
+    pub fn save(&self, path: &str) -> Result<()> {
+        if let Some(parent) = Path::new(path).parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let temp_path = format!("{}.tmp", path);
+        std::fs::write(temp_path.as_str(), serde_json::to_string_pretty(self)?)?;
+        std::fs::rename(temp_path.as_str(), path)?;
+        Ok(())
+    }
+
+    pub fn is_done(&self, rib_dump_url: &str) -> bool {
+        self.done.contains(rib_dump_url)
+    }
+
+    pub fn mark_done(&mut self, rib_dump_url: &str) {
+        self.failed.remove(rib_dump_url);
+        self.done.insert(rib_dump_url.to_string());
+    }
+
+    pub fn mark_failed(&mut self, rib_dump_url: &str, error: &str) {
+        self.failed
+            .insert(rib_dump_url.to_string(), error.to_string());
+    }
+
+    /// RIB dumps not processed successfully yet.
+    pub fn remaining(&self, rib_metas: Vec<RibMeta>) -> Vec<RibMeta> {
+        rib_metas
+            .into_iter()
+            .filter(|r| !self.is_done(r.rib_dump_url.as_str()))
+            .collect()
+    }
+}
@@ -0,0 +1,182 @@
+//! BMP stream ingestion.
+//!
+//! [run_bmp_listener] acts as a minimal BMP station: it listens on a TCP socket for routers (or a
+//! local BMP collector relaying their feeds), parses the BMP messages, and applies the routes in
+//! route monitoring messages to a [RouteTable]; peer down messages remove the routes of their
+//! peer. Each router connection is read on its own thread, while the table updates and the
+//! periodic snapshot output, rebuilt from the table, happen on the calling thread.
+
+use crate::util::RouteTable;
+use crate::{RibEye, RibMeta};
+use anyhow::Result;
+use bgpkit_parser::models::BgpElem;
+use bgpkit_parser::parser::bmp::messages::{BmpMessage, BmpMessageBody};
+use bgpkit_parser::{parse_bmp_msg, Elementor};
+use bytes::Bytes;
+use std::io::Read;
+use std::net::{IpAddr, TcpListener, TcpStream};
+use std::sync::mpsc::{channel, RecvTimeoutError, Sender};
+use std::time::{Duration, Instant};
+use tracing::{info, warn};
+
+/// Length of the BMP common header: version (1), message length (4), message type (1).
+const BMP_COMMON_HEADER_LEN: usize = 6;
+
+/// Configuration of a BMP listener.
+#[derive(Debug, Clone)]
+pub struct BmpListenerConfig {
+    /// socket address to listen on (e.g. 0.0.0.0:11019)
+    pub listen_addr: String,
+    /// collector name used for the emitted outputs
+    pub collector: String,
+    /// interval between two emitted snapshots
+    pub output_interval: Duration,
+}
+
+impl BmpListenerConfig {
+    fn snapshot_meta(&self) -> RibMeta {
+        RibMeta {
+            project: "bmp".to_string(),
+            collector: self.collector.clone(),
+            rib_dump_url: format!("bmp://{}", self.listen_addr),
+            timestamp: chrono::Utc::now().naive_utc(),
+            ..Default::default()
+        }
+    }
+}
+
+/// Read one BMP message from the reader, returning `None` at the end of the stream.
+pub fn read_bmp_message<R: Read>(reader: &mut R) -> Result<Option<BmpMessage>> {
+    let mut header = [0u8; BMP_COMMON_HEADER_LEN];
+    match reader.read_exact(&mut header) {
+        Ok(_) => {}
+        Err(e) if e.kind() == std::io::ErrorKind::UnexpectedEof => return Ok(None),
+        Err(e) => return Err(e.into()),
+    }
+    let msg_len = u32::from_be_bytes([header[1], header[2], header[3], header[4]]) as usize;
+    if msg_len < BMP_COMMON_HEADER_LEN {
+        return Err(anyhow::anyhow!("invalid BMP message length: {}", msg_len));
+    }
+
+    let mut data = header.to_vec();
+    data.resize(msg_len, 0);
+    reader.read_exact(&mut data[BMP_COMMON_HEADER_LEN..])?;
+
+    let msg = parse_bmp_msg(&mut Bytes::from(data))
+        .map_err(|e| anyhow::anyhow!("failed to parse BMP message: {}", e))?;
+    Ok(Some(msg))
+}
+
+/// Convert a BMP message into BGP elements. Only route monitoring messages carry routes.
+pub fn bmp_msg_to_elems(msg: BmpMessage) -> Vec<BgpElem> {
+    match (msg.per_peer_header, msg.message_body) {
+        (Some(peer_header), BmpMessageBody::RouteMonitoring(rm)) => Elementor::bgp_to_elems(
+            rm.bgp_message,
+            peer_header.timestamp,
+            &peer_header.peer_ip,
+            &peer_header.peer_asn,
+        ),
+        _ => vec![],
+    }
+}
+
+/// Changes to the route table carried by one BMP message.
+enum BmpUpdate {
+    Routes(Vec<BgpElem>),
+    PeerDown(IpAddr),
+}
+
+impl BmpUpdate {
+    fn from_msg(msg: BmpMessage) -> Option<Self> {
+        match (&msg.per_peer_header, &msg.message_body) {
+            (Some(peer_header), BmpMessageBody::PeerDownNotification(_)) => {
+                Some(BmpUpdate::PeerDown(peer_header.peer_ip))
+            }
+            _ => {
+                let elems = bmp_msg_to_elems(msg);
+                (!elems.is_empty()).then_some(BmpUpdate::Routes(elems))
+            }
+        }
+    }
+}
+
+fn handle_connection(mut stream: TcpStream, sender: Sender<BmpUpdate>) {
+    let peer = stream
+        .peer_addr()
+        .map(|a| a.to_string())
+        .unwrap_or_default();
+    info!("BMP connection from {}", peer);
+    loop {
+        match read_bmp_message(&mut stream) {
+            Ok(Some(msg)) => {
+                if let Some(update) = BmpUpdate::from_msg(msg) {
+                    if sender.send(update).is_err() {
+                        break;
+                    }
+                }
+            }
+            Ok(None) => {
+                info!("BMP connection from {} closed", peer);
+                break;
+            }
+            Err(e) => {
+                warn!("dropping BMP connection from {}: {}", peer, e);
+                break;
+            }
+        }
+    }
+}
+
+/// Listen for BMP connections, apply their route monitoring and peer down messages to a route
+/// table, and emit outputs of the processors of `ribeye` rebuilt from it every
+/// `config.output_interval`.
+///
+/// The function runs until the listening socket fails.
+pub fn run_bmp_listener(ribeye: &mut RibEye, config: &BmpListenerConfig) -> Result<()> {
+    let listener = TcpListener::bind(config.listen_addr.as_str())?;
+    info!("listening for BMP connections on {}", config.listen_addr);
+
+    let (sender, receiver) = channel::<BmpUpdate>();
+    std::thread::spawn(move || {
+        for stream in listener.incoming() {
+            match stream {
+                Ok(s) => {
+                    let sender = sender.clone();
+                    std::thread::spawn(move || handle_connection(s, sender));
+                }
+                Err(e) => warn!("failed to accept BMP connection: {}", e),
+            }
+        }
+    });
+
+    let mut table = RouteTable::new();
+    let mut last_output = Instant::now();
+    loop {
+        let timeout = config.output_interval.saturating_sub(last_output.elapsed());
+        match receiver.recv_timeout(timeout) {
+            Ok(BmpUpdate::Routes(elems)) => {
+                for elem in elems {
+                    table.apply(elem);
+                }
+            }
+            Ok(BmpUpdate::PeerDown(peer_ip)) => {
+                let removed = table.remove_peer(peer_ip);
+                info!("BMP peer {} down, removed {} routes", peer_ip, removed);
+            }
+            Err(RecvTimeoutError::Timeout) => {}
+            Err(RecvTimeoutError::Disconnected) => break,
+        }
+
+        if last_output.elapsed() >= config.output_interval {
+            info!(
+                "emitting refreshed snapshot for {} with {} routes",
+                config.collector,
+                table.len()
+            );
+            ribeye.emit_table_snapshot(&config.snapshot_meta(), &table)?;
+            last_output = Instant::now();
+        }
+    }
+
+    ribeye.emit_table_snapshot(&config.snapshot_meta(), &table)
+}
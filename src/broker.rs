@@ -0,0 +1,194 @@
+//! Resilient BGPKIT Broker queries.
+//!
+//! Wraps broker queries with retries and exponential backoff, and caches the returned RIB dump
+//! items on disk per day, so that re-runs within the same day (e.g. after a partial failure) do
+//! not depend on the broker being reachable again.
+
+use anyhow::{anyhow, Result};
+use bgpkit_broker::{BgpkitBroker, BrokerError, BrokerItem};
+use chrono::NaiveDateTime;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::path::PathBuf;
+use std::time::Duration;
+use tracing::{info, warn};
+
+/// Default BGPKIT Broker API URL.
+pub const DEFAULT_BROKER_URL: &str = "https://api.broker.bgpkit.com/v3";
+
+#[derive(Debug, Clone)]
+pub struct BrokerConfig {
+    /// broker API URL
+    pub broker_url: String,
+    /// number of retries after a failed query
+    pub retries: u32,
+    /// delay before the first retry, doubled on each further retry
+    pub backoff: Duration,
+    /// directory of cached item lists, no caching if unset
+    pub cache_dir: Option<String>,
+}
+
+impl Default for BrokerConfig {
+    fn default() -> Self {
+        BrokerConfig {
+            broker_url: DEFAULT_BROKER_URL.to_string(),
+            retries: 3,
+            backoff: Duration::from_secs(1),
+            cache_dir: None,
+        }
+    }
+}
+
+impl BrokerConfig {
+    pub fn new(broker_url: &str) -> Self {
+        BrokerConfig {
+            broker_url: broker_url.to_string(),
+            ..Default::default()
+        }
+    }
+
+    pub fn with_retries(mut self, retries: u32, backoff: Duration) -> Self {
+        self.retries = retries;
+        self.backoff = backoff;
+        self
+    }
+
+    pub fn with_cache_dir(mut self, cache_dir: &str) -> Self {
+        self.cache_dir = Some(cache_dir.to_string());
+        self
+    }
+
+    fn broker(&self) -> BgpkitBroker {
+        BgpkitBroker::new().broker_url(self.broker_url.as_str())
+    }
+
+    /// Run a broker query, retrying with exponential backoff on failure.
+    fn query_with_retries<T>(
+        &self,
+        what: &str,
+        query: impl Fn() -> std::result::Result<T, BrokerError>,
+    ) -> Result<T> {
+        let mut delay = self.backoff;
+        let mut attempt = 0;
+        loop {
+            match query() {
+                Ok(res) => return Ok(res),
+                Err(e) if attempt < self.retries => {
+                    attempt += 1;
+                    warn!(
+                        "broker query for {} failed ({}), retry {}/{} in {:?}",
+                        what, e, attempt, self.retries, delay
+                    );
+                    std::thread::sleep(delay);
+                    delay *= 2;
+                }
+                Err(e) => {
+                    return Err(anyhow!(
+                        "broker query for {} failed after {} retries: {}",
+                        what,
+                        self.retries,
+                        e
+                    ))
+                }
+            }
+        }
+    }
+
+    /// Path of the cached item list of a query, if caching is enabled.
+    ///
+    /// Cached lists are keyed by broker URL, query and day, so they expire at UTC midnight.
+    fn cache_path(&self, query_key: &str) -> Option<PathBuf> {
+        let cache_dir = self.cache_dir.as_ref()?;
+        let today = chrono::Utc::now().date_naive();
+        let mut hasher = DefaultHasher::new();
+        (self.broker_url.as_str(), query_key).hash(&mut hasher);
+        Some(PathBuf::from(cache_dir).join(format!(
+            "broker-{}-{:016x}.json",
+            today,
+            hasher.finish()
+        )))
+    }
+
+    fn read_cache(&self, query_key: &str) -> Option<Vec<BrokerItem>> {
+        let path = self.cache_path(query_key)?;
+        let content = std::fs::read_to_string(&path).ok()?;
+        match serde_json::from_str(content.as_str()) {
+            Ok(items) => {
+                info!("using cached broker items from {}", path.to_string_lossy());
+                Some(items)
+            }
+            Err(e) => {
+                warn!(
+                    "ignoring invalid broker cache {}: {}",
+                    path.to_string_lossy(),
+                    e
+                );
+                None
+            }
+        }
+    }
+
+    fn write_cache(&self, query_key: &str, items: &[BrokerItem]) {
+        let path = match self.cache_path(query_key) {
+            None => return,
+            Some(p) => p,
+        };
+        let res = path
+            .parent()
+            .map(std::fs::create_dir_all)
+            .unwrap_or(Ok(()))
+            .and_then(|_| std::fs::write(&path, serde_json::to_string(items)?));
+        if let Err(e) = res {
+            warn!(
+                "failed to cache broker items to {}: {}",
+                path.to_string_lossy(),
+                e
+            );
+        }
+    }
+
+    /// Path of the cached RIB dump items between the given times, if caching is enabled.
+    pub fn rib_cache_path(
+        &self,
+        ts_start: NaiveDateTime,
+        ts_end: NaiveDateTime,
+    ) -> Option<PathBuf> {
+        self.cache_path(rib_query_key(ts_start, ts_end).as_str())
+    }
+
+    /// Query all RIB dump items between the given times.
+    pub fn query_ribs(
+        &self,
+        ts_start: NaiveDateTime,
+        ts_end: NaiveDateTime,
+    ) -> Result<Vec<BrokerItem>> {
+        let query_key = rib_query_key(ts_start, ts_end);
+        if let Some(items) = self.read_cache(query_key.as_str()) {
+            return Ok(items);
+        }
+
+        let broker = self
+            .broker()
+            .data_type("rib")
+            .ts_start(ts_start.and_utc().timestamp())
+            .ts_end(ts_end.and_utc().timestamp());
+        let items = self.query_with_retries("RIB dumps", || broker.query())?;
+        self.write_cache(query_key.as_str(), &items);
+        Ok(items)
+    }
+
+    /// Query the most recent RIB dump item of each collector.
+    ///
+    /// Latest items are never cached, as they are meant to reflect the broker's current state.
+    pub fn query_latest_ribs(&self) -> Result<Vec<BrokerItem>> {
+        let broker = self.broker();
+        let items = self.query_with_retries("latest RIB dumps", || broker.latest())?;
+        Ok(items.into_iter().filter(|item| item.is_rib()).collect())
+    }
+}
+
+/// Cache key of a RIB dump query; times are keyed by day, as they move with the current time on
+/// each run.
+fn rib_query_key(ts_start: NaiveDateTime, ts_end: NaiveDateTime) -> String {
+    format!("rib:{}:{}", ts_start.date(), ts_end.date())
+}
@@ -0,0 +1,120 @@
+//! Processing budgets and optional processors.
+//!
+//! Processors are critical by default and always see every element. Processors marked as optional
+//! with [RibEye::with_optional_processors](crate::RibEye::with_optional_processors) are degraded
+//! once the current RIB exceeds the pipeline's [ProcessingBudget]: from then on they are handed
+//! only every Nth element, or no elements at all. Each decision is recorded in the
+//! [RunReport](crate::report::RunReport), since degraded outputs are incomplete.
+
+use serde::{Deserialize, Serialize};
+use std::fmt::{Display, Formatter};
+use std::time::Duration;
+
+/// Time and memory limits for processing a RIB, checked before each batch of elements.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct ProcessingBudget {
+    /// time spent processing the current RIB
+    pub max_duration: Option<Duration>,
+    /// resident memory of the process in bytes, only checked on Linux
+    pub max_memory: Option<u64>,
+    /// once over budget, hand optional processors every Nth element; 0 skips them
+    pub sample_every: u64,
+}
+
+impl ProcessingBudget {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_max_duration(mut self, max_duration: Duration) -> Self {
+        self.max_duration = Some(max_duration);
+        self
+    }
+
+    pub fn with_max_memory(mut self, max_memory: u64) -> Self {
+        self.max_memory = Some(max_memory);
+        self
+    }
+
+    /// Sample optional processors over budget instead of skipping them.
+    pub fn with_sample_every(mut self, sample_every: u64) -> Self {
+        self.sample_every = sample_every;
+        self
+    }
+
+    /// The first limit exceeded after processing for `elapsed`, if any.
+    pub fn exceeded(&self, elapsed: Duration) -> Option<BudgetLimit> {
+        if self.max_duration.is_some_and(|max| elapsed > max) {
+            return Some(BudgetLimit::Duration);
+        }
+        match (self.max_memory, resident_memory()) {
+            (Some(max), Some(rss)) if rss > max => Some(BudgetLimit::Memory),
+            _ => None,
+        }
+    }
+
+    /// Action taken on optional processors once over budget.
+    pub fn action(&self) -> BudgetAction {
+        match self.sample_every {
+            0 => BudgetAction::Skipped,
+            _ => BudgetAction::Sampled,
+        }
+    }
+}
+
+/// Limit of a [ProcessingBudget].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BudgetLimit {
+    Duration,
+    Memory,
+}
+
+impl Display for BudgetLimit {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BudgetLimit::Duration => write!(f, "duration"),
+            BudgetLimit::Memory => write!(f, "memory"),
+        }
+    }
+}
+
+/// What happens to an optional processor once over budget.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BudgetAction {
+    /// the processor is handed every Nth element
+    Sampled,
+    /// the processor is handed no more elements
+    Skipped,
+}
+
+impl Display for BudgetAction {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BudgetAction::Sampled => write!(f, "sampled"),
+            BudgetAction::Skipped => write!(f, "skipped"),
+        }
+    }
+}
+
+/// Degradation of an optional processor for the current RIB.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BudgetDecision {
+    pub processor: String,
+    pub action: BudgetAction,
+    pub limit: BudgetLimit,
+    /// number of elements processed before the decision
+    pub after_elems: u64,
+    /// sampling interval, only for sampled processors
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sample_every: Option<u64>,
+}
+
+/// Resident memory of the current process in bytes, from `/proc/self/status`.
+pub fn resident_memory() -> Option<u64> {
+    let status = std::fs::read_to_string("/proc/self/status").ok()?;
+    let line = status.lines().find(|l| l.starts_with("VmRSS:"))?;
+    let kb: u64 = line.split_whitespace().nth(1)?.parse().ok()?;
+    Some(kb * 1024)
+}
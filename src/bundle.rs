@@ -0,0 +1,309 @@
+//! Bundles of the dated per-collector outputs of a run.
+//!
+//! Large backfills write one dated output per processor, collector and RIB dump, i.e. millions of
+//! small objects. With an [OutputBundle], pipelines write their dated outputs into gzip-compressed
+//! tar archives under `<dir>/bundles/<run>/` instead, see
+//! [RibEye::with_bundle](crate::RibEye::with_bundle):
+//!
+//! - `part-00000.tar.gz`, ...: tar (ustar) archives of the outputs, a new part being started once
+//!   the uncompressed part size is reached. Entries are stored uncompressed under their path
+//!   relative to the output root directory without the compression extension, e.g.
+//!   `pfx2as/rrc00/2024/01/pfx2as_rrc00_2024-01-01_0000_1704067200.json`.
+//! - `manifest.json`: the entries of each part, see [BundleManifest].
+//!
+//! Latest outputs, summaries, time series and archives are still written as individual files, as
+//! later runs read them back. Parts are written through the pipeline's sink, so they are encrypted
+//! or signed like other outputs, and can be read with [read_bundle_part] or extracted with `tar`.
+
+use crate::archive::ARCHIVE_DIR;
+use crate::listing::parse_output_path;
+use crate::processors::OutputSink;
+use crate::timeseries::TIMESERIES_DIR;
+use anyhow::{anyhow, Result};
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::sync::{Arc, Mutex};
+use tracing::info;
+
+/// Directory under the output root directory holding the bundles of all runs.
+pub const BUNDLES_DIR: &str = "bundles";
+/// File name of a bundle's manifest.
+pub const BUNDLE_MANIFEST_FILE_NAME: &str = "manifest.json";
+/// Default uncompressed size of a bundle part, 512 MiB.
+pub const DEFAULT_BUNDLE_PART_SIZE: u64 = 512 * 1024 * 1024;
+
+const BLOCK_SIZE: usize = 512;
+
+/// Bundle directory of a run under an output root directory, `<output_dir>/bundles/<run>`.
+pub fn get_bundle_dir(output_dir: &str, run: &str) -> String {
+    format!(
+        "{}/{}/{}",
+        output_dir.trim_end_matches('/'),
+        BUNDLES_DIR,
+        run
+    )
+}
+
+/// A part of a bundle and the paths of its entries.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BundlePart {
+    /// path relative to the bundle directory
+    pub path: String,
+    pub entries: Vec<String>,
+}
+
+/// Manifest of a bundle, `manifest.json`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BundleManifest {
+    pub parts: Vec<BundlePart>,
+}
+
+/// An entry read from a bundle part.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BundleEntry {
+    pub path: String,
+    pub data: Vec<u8>,
+}
+
+#[derive(Default)]
+struct BundleState {
+    /// temporary file of the current part
+    file: Option<File>,
+    size: u64,
+    entries: Vec<String>,
+    manifest: BundleManifest,
+    /// sink writing the parts, the inner sink of the first pipeline writing to the bundle
+    sink: Option<Arc<dyn OutputSink>>,
+}
+
+/// Bundle of the dated outputs of a run, shared by all pipelines of the run, see the [module
+/// documentation](self).
+pub struct OutputBundle {
+    output_dir: String,
+    bundle_dir: String,
+    part_size: u64,
+    state: Mutex<BundleState>,
+}
+
+impl OutputBundle {
+    /// Bundle the dated outputs under `output_dir` into `<output_dir>/bundles/<run>`.
+    pub fn new(output_dir: &str, run: &str) -> Self {
+        OutputBundle {
+            output_dir: output_dir.trim_end_matches('/').to_string(),
+            bundle_dir: get_bundle_dir(output_dir, run),
+            part_size: DEFAULT_BUNDLE_PART_SIZE,
+            state: Mutex::new(BundleState::default()),
+        }
+    }
+
+    /// Start a new part once the uncompressed part reaches the given size.
+    pub fn with_part_size(mut self, part_size: u64) -> Self {
+        self.part_size = part_size;
+        self
+    }
+
+    pub fn bundle_dir(&self) -> &str {
+        self.bundle_dir.as_str()
+    }
+
+    /// Path of an output relative to the output root directory without its compression
+    /// extension, if it is a dated per-collector output to bundle.
+    pub fn entry_path(&self, path: &str) -> Option<String> {
+        let relative = path
+            .strip_prefix(self.output_dir.as_str())?
+            .strip_prefix('/')?;
+        let first = relative.split('/').next()?;
+        if [TIMESERIES_DIR, ARCHIVE_DIR, BUNDLES_DIR].contains(&first) {
+            return None;
+        }
+        parse_output_path(relative)?.ts?;
+        let entry = [".bz2", ".gz", ".xz", ".lz4"]
+            .iter()
+            .find_map(|ext| relative.strip_suffix(ext))
+            .unwrap_or(relative);
+        Some(entry.to_string())
+    }
+
+    /// Append an entry with the given decoded content, writing the current part through `sink`
+    /// once it is full.
+    pub fn append(&self, sink: &Arc<dyn OutputSink>, entry: &str, data: &[u8]) -> Result<()> {
+        let mut state = self.state.lock().unwrap();
+        if state.sink.is_none() {
+            state.sink = Some(sink.clone());
+        }
+        if state.file.is_none() {
+            state.file = Some(tempfile::tempfile()?);
+            state.size = 0;
+        }
+        let header = tar_header(entry, data.len() as u64, Utc::now().timestamp())?;
+        let padding = (BLOCK_SIZE - data.len() % BLOCK_SIZE) % BLOCK_SIZE;
+        let file = state.file.as_mut().unwrap();
+        file.write_all(&header)?;
+        file.write_all(data)?;
+        file.write_all(&vec![0u8; padding])?;
+        state.size += (header.len() + data.len() + padding) as u64;
+        state.entries.push(entry.to_string());
+        if state.size >= self.part_size {
+            self.write_part(&mut state)?;
+        }
+        Ok(())
+    }
+
+    /// Write the last part and the manifest. Returns the number of bundled outputs.
+    pub fn finish(&self) -> Result<usize> {
+        let mut state = self.state.lock().unwrap();
+        self.write_part(&mut state)?;
+        let Some(sink) = state.sink.clone() else {
+            return Ok(0);
+        };
+        let manifest = serde_json::to_value(&state.manifest)?;
+        sink.write_json(
+            format!("{}/{}", self.bundle_dir, BUNDLE_MANIFEST_FILE_NAME).as_str(),
+            &manifest,
+        )?;
+        let entries = state.manifest.parts.iter().map(|p| p.entries.len()).sum();
+        info!(
+            phase = "bundle",
+            "bundled {} outputs into {} parts under {}",
+            entries,
+            state.manifest.parts.len(),
+            self.bundle_dir
+        );
+        Ok(entries)
+    }
+
+    fn write_part(&self, state: &mut BundleState) -> Result<()> {
+        let Some(mut file) = state.file.take() else {
+            return Ok(());
+        };
+        let sink = state
+            .sink
+            .clone()
+            .ok_or_else(|| anyhow!("no sink to write bundle parts"))?;
+        let mut data = Vec::with_capacity(state.size as usize + 2 * BLOCK_SIZE);
+        file.seek(SeekFrom::Start(0))?;
+        file.read_to_end(&mut data)?;
+        // end of archive
+        data.extend_from_slice(&[0u8; 2 * BLOCK_SIZE]);
+
+        let part = format!("part-{:05}.tar.gz", state.manifest.parts.len());
+        sink.write_bytes(format!("{}/{}", self.bundle_dir, part).as_str(), &data)?;
+        state.manifest.parts.push(BundlePart {
+            path: part,
+            entries: std::mem::take(&mut state.entries),
+        });
+        Ok(())
+    }
+}
+
+/// Sink writing the dated outputs under the bundle's output root directory to the bundle and all
+/// other files to the inner sink.
+pub struct BundlingSink {
+    inner: Arc<dyn OutputSink>,
+    bundle: Arc<OutputBundle>,
+}
+
+impl BundlingSink {
+    pub fn new(inner: Arc<dyn OutputSink>, bundle: Arc<OutputBundle>) -> Self {
+        BundlingSink { inner, bundle }
+    }
+}
+
+impl OutputSink for BundlingSink {
+    fn write_bytes(&self, path: &str, data: &[u8]) -> Result<()> {
+        match self.bundle.entry_path(path) {
+            Some(entry) => self.bundle.append(&self.inner, entry.as_str(), data),
+            None => self.inner.write_bytes(path, data),
+        }
+    }
+
+    fn write_json(&self, path: &str, value: &Value) -> Result<()> {
+        match self.bundle.entry_path(path) {
+            Some(entry) => {
+                let content = serde_json::to_string_pretty(value)?;
+                self.bundle
+                    .append(&self.inner, entry.as_str(), content.as_bytes())
+            }
+            None => self.inner.write_json(path, value),
+        }
+    }
+
+    fn read_bytes(&self, path: &str) -> Result<Vec<u8>> {
+        self.inner.read_bytes(path)
+    }
+
+    fn is_stream(&self) -> bool {
+        self.inner.is_stream()
+    }
+}
+
+/// Read the entries of a bundle part, decrypted and decompressed by the given sink.
+pub fn read_bundle_part(sink: &dyn OutputSink, path: &str) -> Result<Vec<BundleEntry>> {
+    let data = sink.read_bytes(path)?;
+    let mut entries = vec![];
+    let mut offset = 0;
+    while offset + BLOCK_SIZE <= data.len() {
+        let header = &data[offset..offset + BLOCK_SIZE];
+        if header.iter().all(|b| *b == 0) {
+            break;
+        }
+        let field = |start: usize, len: usize| {
+            let bytes = &header[start..start + len];
+            let end = bytes.iter().position(|b| *b == 0).unwrap_or(len);
+            String::from_utf8_lossy(&bytes[..end]).to_string()
+        };
+        let size = u64::from_str_radix(field(124, 12).trim(), 8)
+            .map_err(|_| anyhow!("invalid tar entry size in {}", path))?
+            as usize;
+        let name = match field(345, 155) {
+            prefix if prefix.is_empty() => field(0, 100),
+            prefix => format!("{}/{}", prefix, field(0, 100)),
+        };
+        let start = offset + BLOCK_SIZE;
+        let end = start + size;
+        if end > data.len() {
+            return Err(anyhow!("truncated tar entry {} in {}", name, path));
+        }
+        entries.push(BundleEntry {
+            path: name,
+            data: data[start..end].to_vec(),
+        });
+        offset = end + (BLOCK_SIZE - size % BLOCK_SIZE) % BLOCK_SIZE;
+    }
+    Ok(entries)
+}
+
+/// ustar header of a regular file, names longer than 100 bytes split into prefix and name at a
+/// `/`.
+fn tar_header(path: &str, size: u64, mtime: i64) -> Result<[u8; BLOCK_SIZE]> {
+    let (prefix, name) = match path.len() {
+        0..=100 => ("", path),
+        _ => path
+            .char_indices()
+            .filter(|(i, c)| *c == '/' && *i <= 155 && path.len() - i - 1 <= 100)
+            .map(|(i, _)| (&path[..i], &path[i + 1..]))
+            .next()
+            .ok_or_else(|| anyhow!("path too long for a bundle entry: {}", path))?,
+    };
+    let mut header = [0u8; BLOCK_SIZE];
+    let mut set = |start: usize, value: &[u8]| {
+        header[start..start + value.len()].copy_from_slice(value);
+    };
+    set(0, name.as_bytes());
+    set(100, b"0000644\0");
+    set(108, b"0000000\0");
+    set(116, b"0000000\0");
+    set(124, format!("{:011o}\0", size).as_bytes());
+    set(136, format!("{:011o}\0", mtime.max(0)).as_bytes());
+    set(148, b"        ");
+    set(156, b"0");
+    set(257, b"ustar\0");
+    set(263, b"00");
+    set(345, prefix.as_bytes());
+    let checksum: u32 = header.iter().map(|b| *b as u32).sum();
+    header[148..156].copy_from_slice(format!("{:06o}\0 ", checksum).as_bytes());
+    Ok(header)
+}
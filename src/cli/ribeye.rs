@@ -1,12 +1,62 @@
-use bgpkit_broker::BrokerItem;
-use chrono::Timelike;
-use clap::{Parser, Subcommand};
+use chrono::{NaiveDate, NaiveDateTime};
+use clap::{Parser, Subcommand, ValueEnum};
 use itertools::Itertools;
-use rayon::prelude::*;
-use ribeye::processors::RibMeta;
+use ribeye::alert::AlertConfig;
+use ribeye::analyzers::{get_analyzer, Analyzer};
+use ribeye::archive::{get_archive_dir, DeltaArchive};
+use ribeye::artifact::{get_artifact_path, Artifact};
+use ribeye::audit::OutputAudit;
+use ribeye::backfill::{plan_backfill, BackfillInterval, BackfillState};
+use ribeye::bmp::{run_bmp_listener, BmpListenerConfig};
+use ribeye::broker::{BrokerConfig, DEFAULT_BROKER_URL};
+use ribeye::budget::ProcessingBudget;
+use ribeye::bundle::OutputBundle;
+use ribeye::collectors::CollectorProfiles;
+use ribeye::compare::{compare_as2rel, ExternalAs2rel};
+use ribeye::country::get_country_matrix_path;
+use ribeye::dashboard::{get_dashboard_path, Dashboard};
+use ribeye::download::{Downloader, DEFAULT_DOWNLOAD_ATTEMPTS};
+use ribeye::encryption::{decrypt_stored, EncryptingSink, EncryptionKey};
+use ribeye::enrich::{enrich_summaries, AsnInfoData, PeeringDb, DEFAULT_ASINFO_URL};
+use ribeye::export::export_bgpkit;
+use ribeye::health::{get_health_report_path, DEFAULT_PEER_DROP_RATIO};
+use ribeye::hijack::{analyze_hijacks, Vrps};
+use ribeye::index::write_output_index;
+use ribeye::input::Compression;
+use ribeye::inspect::{OutputFile, RecordFilter};
+use ribeye::ip2asn::get_ip2asn_path;
+use ribeye::ledger::RunLedger;
+use ribeye::listing::{
+    collector_listings, discover_rib_metas, list_outputs, prune_outputs, ListedOutput,
+};
+use ribeye::live::{run_ris_live, RisLiveConfig};
+use ribeye::lock::LockOptions;
+use ribeye::lookup::LookupData;
+use ribeye::manifest::SourceManifest;
+use ribeye::pipeline::{JobPool, JobResult, JobStatus};
+use ribeye::processors::readers::read_as2rel_summary;
+use ribeye::processors::schema::processor_schemas;
+use ribeye::processors::{
+    dump_type_from_path, expand_input_paths, get_summary_output_path, FileSink, OutputEncoding,
+    OutputGranularity, OutputSelection, OutputSink, ProcessorConfig, ProcessorConfigFile, RibMeta,
+    SummaryWriter,
+};
+use ribeye::redis::{RedisExporter, DEFAULT_KEY_PREFIX};
+use ribeye::report::ErrorPolicy;
+use ribeye::selftest::Selftest;
+use ribeye::signing::{verify_file, PublicKey, SigningKey};
+use ribeye::storage;
+use ribeye::throttle::{ByteCap, IoLimiter};
+use ribeye::top::DEFAULT_TOP_N;
+use ribeye::util::{load_ixp_asns, AddressFamily, IxpPolicy, PrefixCountries, RouteTable};
 use ribeye::RibEye;
+use std::collections::BTreeMap;
+use std::io::Write;
+use std::net::IpAddr;
 use std::process::exit;
-use tracing::{error, info};
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::{error, info, warn};
 use tracing_subscriber::EnvFilter;
 
 #[derive(Parser)]
@@ -19,9 +69,23 @@ struct Cli {
     /// Path to environment variables file
     #[clap(short, long, global = true)]
     env: Option<String>,
+
+    /// Log output format
+    #[clap(long, global = true, value_enum, default_value = "text")]
+    log_format: LogFormat,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum LogFormat {
+    /// human-readable text logs
+    Text,
+    /// one JSON object per line, with event fields (collector, processor, phase, duration_ms) at the
+    /// top level
+    Json,
 }
 
 #[derive(Subcommand)]
+#[allow(clippy::large_enum_variant)]
 enum Commands {
     /// Process recent RIB dump files
     Cook {
@@ -39,133 +103,2864 @@ enum Commands {
 
         /// specify processors to use.
         ///
-        /// Available processors: pfx2as, pfx2dist, as2rel, peer_stats
+        /// Available processors: pfx2as, pfx2as-aggregated, pfx2as-peers, pfx2dist, pfxlen, as2rel,
+        /// peer_stats, peer-stats-approx, footprint, reachability, communities, pfxscope,
+        /// defaults, duplicates, pathdiv, vantage, firsthop
         ///
         /// If not specified, all processors will be used
         #[clap(short, long)]
         processors: Vec<String>,
 
-        /// Number of threads to use
-        #[clap(short, long)]
+        /// Number of RIB files processed concurrently; defaults to the number of threads
+        #[clap(long, env = "RIBEYE_PARALLEL")]
+        parallel: Option<usize>,
+
+        /// Size of the worker thread pool; defaults to the number of CPU cores
+        #[clap(short, long, env = "RIBEYE_THREADS")]
         threads: Option<usize>,
 
         /// Root data directory
         #[clap(short, long, default_value = "./results")]
         dir: String,
 
+        /// Analyzers run over the processor results of each RIB dump, after the processors they
+        /// depend on.
+        ///
+        /// Available analyzers: hijacks (requires pfx2as, uses as2rel if processed)
+        #[clap(long, value_delimiter = ',')]
+        analyzers: Vec<String>,
+
+        /// VRPs exported by an RPKI relying party (JSON or CSV), local path or URL, for the RPKI
+        /// validity checks of the hijacks analyzer
+        #[clap(long)]
+        vrps: Option<String>,
+
         /// Only summarize latest results
         #[clap(long)]
         summarize_only: bool,
+
+        /// Process only the most recent RIB dump of each collector, ignoring `--days`
+        #[clap(long)]
+        latest: bool,
+
+        /// Re-process only the RIB dumps that failed in the previous run recorded in the run
+        /// ledger, then refresh the summaries
+        #[clap(long, conflicts_with_all = ["summarize_only", "latest"])]
+        only_failed: bool,
+
+        /// Local file recording the RIB dumps of the last run and their outcome, by default
+        /// `ribeye-run-ledger.json`, or `ribeye-run-ledger.<label>.json` for labeled runs
+        #[clap(long)]
+        ledger: Option<String>,
+
+        /// Write the dated per-collector outputs of the run into `<dir>/bundles/<run>/part-*.tar.gz`
+        /// archives instead of individual files, e.g. to avoid millions of small objects
+        #[clap(long)]
+        bundle: bool,
+
+        /// Uncompressed size in MiB of each bundle part
+        #[clap(long, default_value = "512")]
+        bundle_part_size: u64,
+
+        /// Process the MRT files listed in a JSON source manifest, with their meta information or
+        /// URL patterns, instead of RIB dumps found via the broker
+        #[clap(long, conflicts_with = "latest")]
+        source_manifest: Option<String>,
+
+        /// Only reread latest outputs that changed since the last summary, keeping the others in a
+        /// per-processor summary cache; summaries are still merged from all outputs
+        #[clap(long)]
+        incremental: bool,
+
+        /// Compressions of the summary copies published next to each `latest.json.bz2`:
+        /// none (`latest.json`), gzip, bzip2; comma-separated, empty for no copies
+        #[clap(long, value_delimiter = ',', default_value = "none")]
+        summary_copies: Vec<String>,
+
+        /// Formats of the summaries, comma-separated: json only, or also csv tables
+        /// (`latest.<table>.csv`) or protobuf for pfx2as and as2rel (`latest.pb`) next to each
+        /// `latest.json.bz2`
+        #[clap(long, value_enum, value_delimiter = ',', default_value = "json")]
+        summary_format: Vec<SummaryFormat>,
+
+        /// Seconds to wait for a summary locked by an overlapping run before skipping it
+        #[clap(long, default_value = "600")]
+        lock_wait: u64,
+
+        /// Seconds after which a summary lock of a crashed run expires
+        #[clap(long, default_value = "3600")]
+        lock_ttl: u64,
+
+        /// Do not lock summaries while writing them
+        #[clap(long)]
+        no_lock: bool,
+
+        /// Hours after which a collector's latest RIB dump is flagged as stale in the summaries
+        #[clap(long, default_value = "48")]
+        stale_after_hours: u64,
+
+        /// BGPKIT Broker API URL
+        #[clap(long, env = "BGPKIT_BROKER_URL", default_value = DEFAULT_BROKER_URL)]
+        broker_url: String,
+
+        /// Number of retries of failed broker queries, with exponential backoff
+        #[clap(long, default_value = "3")]
+        broker_retries: u32,
+
+        /// JSON list of collector profiles (project, collector name patterns, archive hosts, daily
+        /// dump hour) extending the built-in RIPE RIS, RouteViews, PCH and Isolario ones
+        #[clap(long, env = "RIBEYE_COLLECTOR_PROFILES")]
+        collector_profiles: Option<String>,
+
+        /// Directory caching broker query results for the day; defaults to the system temp directory
+        #[clap(long)]
+        broker_cache: Option<String>,
+
+        /// Always query the broker, without reading or writing cached results
+        #[clap(long)]
+        no_broker_cache: bool,
+
+        /// Maximum number of RIB dumps downloaded concurrently; 0 for no limit
+        #[clap(long, env = "RIBEYE_MAX_DOWNLOADS", default_value = "0")]
+        max_downloads: usize,
+
+        /// Maximum number of RIB dump downloads started per second; 0 for no limit
+        #[clap(long, env = "RIBEYE_DOWNLOAD_RATE", default_value = "0")]
+        download_rate: f64,
+
+        /// Download remote RIB dumps into this directory before processing them, resuming
+        /// interrupted downloads, instead of streaming them
+        #[clap(long, env = "RIBEYE_DOWNLOAD_CACHE")]
+        download_cache: Option<String>,
+
+        /// Attempts to download a RIB dump into the download cache before giving up
+        #[clap(long, default_value_t = DEFAULT_DOWNLOAD_ATTEMPTS)]
+        download_attempts: u32,
+
+        /// Stop downloading RIB dumps once this many bytes are downloaded, e.g. on metered
+        /// connections; dumps over the cap fail
+        #[clap(long, env = "RIBEYE_MAX_DOWNLOAD_BYTES")]
+        max_download_bytes: Option<u64>,
+
+        /// Maximum number of concurrent S3 uploads; 0 for no limit
+        #[clap(long, env = "RIBEYE_MAX_UPLOADS", default_value = "0")]
+        max_uploads: usize,
+
+        /// Maximum number of S3 uploads started per second; 0 for no limit
+        #[clap(long, env = "RIBEYE_UPLOAD_RATE", default_value = "0")]
+        upload_rate: f64,
+
+        /// Write intermediate outputs every N million elements of a RIB dump
+        #[clap(long)]
+        flush_every: Option<u64>,
+
+        /// Handling of processor errors: abort, skip-and-count, skip-silently
+        #[clap(long, default_value = "abort")]
+        on_error: ErrorPolicy,
+
+        /// Only process prefixes of one address family: v4 or v6; outputs are tagged with it
+        #[clap(long)]
+        af: Option<AddressFamily>,
+
+        /// Keep peers reported as AS_TRANS (AS23456) as they are instead of resolving their real
+        /// ASN from their AS paths
+        #[clap(long)]
+        no_as_trans_correction: bool,
+
+        /// Processors sampled or skipped once over the time or memory budget, e.g. pfx2dist
+        #[clap(long)]
+        optional: Vec<String>,
+
+        /// Time budget per RIB dump in seconds, after which optional processors are degraded
+        #[clap(long)]
+        time_budget: Option<u64>,
+
+        /// Resident memory budget in MiB, after which optional processors are degraded
+        #[clap(long)]
+        memory_budget: Option<u64>,
+
+        /// Hand optional processors every Nth element once over budget instead of skipping them
+        #[clap(long, default_value = "0")]
+        sample_every: u64,
+
+        /// Dump time in per-dump output file names: exact (date, hour and minute) or daily (one
+        /// output per day, later dumps replacing earlier ones)
+        #[clap(long, default_value = "exact")]
+        granularity: OutputGranularity,
+
+        /// Encoding of the dated per-collector outputs: json, or cbor (`.cbor.bz2`, smaller and
+        /// faster to parse); latest outputs and summaries are always JSON
+        #[clap(long, default_value = "json")]
+        encoding: OutputEncoding,
+
+        /// Only write dated per-collector outputs, leaving the collectors' latest outputs untouched
+        #[clap(long, conflicts_with = "latest_only")]
+        no_latest: bool,
+
+        /// Only write the collectors' latest outputs, without dated outputs
+        #[clap(long)]
+        latest_only: bool,
+
+        /// Replace latest outputs even if they come from a newer RIB dump
+        #[clap(long, conflicts_with = "no_latest")]
+        force_latest: bool,
+
+        /// IXP route-server ASNs, local path or URL: a PeeringDB `net` dump, a JSON array or one ASN
+        /// per line
+        #[clap(long)]
+        ixp_asns: Option<String>,
+
+        /// Handling of IXP route-server ASNs on AS paths by as2rel: keep, strip, tag
+        #[clap(long, default_value = "strip")]
+        ixp_policy: IxpPolicy,
+
+        /// RIR delegated statistics files used by footprint and pfxscope to map prefixes and peer
+        /// IPs to countries, local paths or URLs
+        #[clap(long)]
+        delegated: Vec<String>,
+
+        /// JSON configuration file, local path or URL, e.g. with per-processor output
+        /// directories: `{"output_dirs": {"pfx2as": "s3://public-bucket/ribeye"}}`
+        #[clap(long)]
+        config: Option<String>,
+
+        /// Label of the run, writing outputs under `<dir>/labels/<label>` and tagging them with
+        /// the label, so that experimental runs keep production outputs intact
+        #[clap(long, env = "RIBEYE_LABEL")]
+        label: Option<String>,
+
+        /// Secret key file (see `ribeye keygen`) to write detached signatures of all outputs with
+        #[clap(long)]
+        signing_key: Option<String>,
+
+        /// Key file (see `ribeye keygen --encryption`) to encrypt all outputs with
+        #[clap(long)]
+        encryption_key: Option<String>,
+
+        /// Also append compact records of each RIB's outputs to the date-partitioned time-series
+        /// dataset under `<dir>/timeseries`
+        #[clap(long)]
+        timeseries: bool,
+
+        /// Attach AS names and countries to the as2rel, peer-stats and pfx2as summaries
+        #[clap(long)]
+        enrich_asn_info: bool,
+
+        /// Write a country-to-country matrix of AS relationships with the as2rel summary
+        #[clap(long)]
+        country_matrix: bool,
+
+        /// Write the pfx2as summary as an ip2asn TSV table (range start, range end, ASN, country,
+        /// AS name)
+        #[clap(long)]
+        ip2asn: bool,
+
+        /// AS information dataset used by `--enrich-asn-info`, `--country-matrix` and `--ip2asn`,
+        /// local path or URL
+        #[clap(long, default_value = DEFAULT_ASINFO_URL)]
+        asn_info: String,
+
+        /// Load the pfx2as and as2rel summaries into Redis after summarizing,
+        /// `redis://[:<password>@]<host>[:<port>][/<db>]`
+        #[clap(long, env = "RIBEYE_REDIS_URL")]
+        redis_url: Option<String>,
+
+        /// Prefix of the keys loaded into Redis
+        #[clap(long, default_value = DEFAULT_KEY_PREFIX)]
+        redis_key_prefix: String,
+
+        /// Bundle all summaries into a `latest.ribeye` artifact indexed for `lookup`
+        #[clap(long)]
+        artifact: bool,
+
+        /// Append the pfx2as summary to a delta-encoded archive under `<dir>/archive/pfx2as`,
+        /// storing each day as the changes since the previous one
+        #[clap(long)]
+        delta_archive: bool,
+
+        /// Write a full archived snapshot every N days instead of only the first one
+        #[clap(long)]
+        archive_full_every: Option<usize>,
+
+        /// Write a collector health report with the peer-stats summary
+        #[clap(long)]
+        health_report: bool,
+
+        /// Ratio of lost peers since the previous summary above which the health report flags a
+        /// collector
+        #[clap(long, default_value_t = DEFAULT_PEER_DROP_RATIO)]
+        health_drop_ratio: f64,
+
+        /// Attach top views (largest and most deaggregated origins, busiest AS links, most
+        /// prepended ASNs) to the pfx2as, as2rel and communities summaries
+        #[clap(long)]
+        top_views: bool,
+
+        /// Number of entries of each top view
+        #[clap(long, default_value_t = DEFAULT_TOP_N)]
+        top_n: usize,
+
+        /// Write browsable `index.json` files of the output directory after summarizing
+        #[clap(long)]
+        index: bool,
+
+        /// Also summarize collectors with latest outputs from earlier runs, discovered by listing
+        /// the output directories
+        #[clap(long)]
+        summarize_discovered: bool,
+
+        /// Alerting configuration file (JSON) with rules evaluated over the summaries and the
+        /// notification hooks alerts are sent to
+        #[clap(long)]
+        alert_config: Option<String>,
     },
-}
 
-fn main() {
-    if std::env::var_os("RUST_LOG").is_none() {
-        std::env::set_var("RUST_LOG", "none,ribeye=info");
-    }
-    tracing_subscriber::fmt()
-        .with_env_filter(EnvFilter::from_default_env())
-        .init();
+    /// Process historical midnight RIB dumps of a date range, checkpointing progress to a state
+    /// file so that the backfill can be stopped and resumed
+    Backfill {
+        /// First day of the range (YYYY-MM-DD)
+        #[clap(long)]
+        from: NaiveDate,
 
-    let opts = Cli::parse();
+        /// Last day of the range (YYYY-MM-DD); defaults to today
+        #[clap(long)]
+        to: Option<NaiveDate>,
 
-    if let Some(env_path) = opts.env {
-        match dotenvy::from_path_override(env_path.as_str()) {
-            Ok(_) => {
-                info!("loaded environment variables from {}", env_path);
-            }
-            Err(_) => {
-                error!("failed to load environment variables from {}", env_path);
-                exit(1);
-            }
-        };
-    }
-    dotenvy::dotenv().ok();
+        /// Days backfilled in the range: daily, or monthly for the first day of every month
+        #[clap(long, default_value = "daily")]
+        interval: BackfillInterval,
 
-    match opts.command {
-        Commands::Cook {
-            days,
-            processors,
-            collectors,
-            dir,
-            threads,
-            limit,
-            summarize_only,
-        } => {
-            // check s3 environment variables if dir starts with s3://
-            if dir.starts_with("s3://") && oneio::s3_env_check().is_err() {
-                error!("S3 environment variables not set");
-                exit(1);
-            }
-
-            // find corresponding RIB dump files
-            let now = chrono::Utc::now().naive_utc();
-            let ts_start = now - chrono::Duration::days(days as i64);
-            info!("Searching for RIB dump files since {}", ts_start);
-            let mut rib_files = bgpkit_broker::BgpkitBroker::new()
-                .broker_url("https://api.broker.bgpkit.com/v3")
-                .data_type("rib")
-                .ts_start(ts_start.and_utc().timestamp())
-                .ts_end(now.and_utc().timestamp())
-                .query()
-                .unwrap()
-                .into_iter()
-                .filter(|entry| {
-                    entry.ts_start.hour() == 0
-                        && match collectors.len() {
-                            0 => true,
-                            _ => collectors.contains(&entry.collector_id),
-                        }
-                })
-                .sorted_by_key(|entry| entry.rough_size)
-                .collect::<Vec<BrokerItem>>();
-            rib_files = match limit {
-                None => rib_files,
-                Some(l) => rib_files.into_iter().take(l).collect::<Vec<BrokerItem>>(),
-            };
+        /// Route collectors to backfill (e.g. route-views2, rrc00); all if not specified
+        #[clap(short, long)]
+        collectors: Vec<String>,
 
-            let rib_metas: Vec<RibMeta> = rib_files.iter().map(RibMeta::from).collect();
+        /// Processors to use; the default processors if not specified
+        #[clap(short, long)]
+        processors: Vec<String>,
 
-            if !summarize_only {
-                match threads {
-                    None => {
-                        info!("using maximum threads for processing");
-                        rayon::ThreadPoolBuilder::new().build_global().unwrap();
-                    }
-                    Some(t) => {
-                        info!("using {} threads for processing", t);
-                        rayon::ThreadPoolBuilder::new()
-                            .num_threads(t)
-                            .build_global()
-                            .unwrap();
-                    }
-                }
-                // process each RIB file in parallel with provided meta information
-                info!("processing {} matching RIB dump files", rib_files.len(),);
-                rib_metas.par_iter().for_each(|rib_meta| {
-                    let mut ribeye =
-                        match RibEye::new().with_processor_names(&processors, dir.as_str()) {
-                            Ok(p) => p.with_rib_meta(rib_meta),
-                            Err(e) => {
-                                error!("failed to initialize RibEye: {}", e);
-                                exit(2);
-                            }
-                        };
-                    ribeye
-                        .process_mrt_file(rib_meta.rib_dump_url.as_str())
-                        .unwrap();
-                });
-            }
+        /// Number of RIB files processed concurrently; defaults to the number of threads
+        #[clap(long, env = "RIBEYE_PARALLEL")]
+        parallel: Option<usize>,
 
-            info!("summarize all latest results");
-            let mut ribeye = match RibEye::new().with_processor_names(&processors, dir.as_str()) {
-                Ok(p) => p,
-                Err(e) => {
-                    error!("failed to initialize RibEye: {}", e);
-                    exit(3);
-                }
-            };
-            ribeye.summarize_latest_files(&rib_metas).unwrap();
+        /// Size of the worker thread pool; defaults to the number of CPU cores
+        #[clap(short, long, env = "RIBEYE_THREADS")]
+        threads: Option<usize>,
+
+        /// Root data directory
+        #[clap(short, long, default_value = "./results")]
+        dir: String,
+
+        /// Local file recording processed RIB dumps, resumed if it exists, by default
+        /// `ribeye-backfill-state.json`, or `ribeye-backfill-state.<label>.json` for labeled runs
+        #[clap(long)]
+        state: Option<String>,
+
+        /// Write the dated per-collector outputs of the run into `<dir>/bundles/<run>/part-*.tar.gz`
+        /// archives instead of individual files, e.g. to avoid millions of small objects
+        #[clap(long)]
+        bundle: bool,
+
+        /// Uncompressed size in MiB of each bundle part
+        #[clap(long, default_value = "512")]
+        bundle_part_size: u64,
+
+        /// BGPKIT Broker API URL
+        #[clap(long, env = "BGPKIT_BROKER_URL", default_value = DEFAULT_BROKER_URL)]
+        broker_url: String,
+
+        /// Number of retries of failed broker queries, with exponential backoff
+        #[clap(long, default_value = "3")]
+        broker_retries: u32,
+
+        /// JSON list of collector profiles (project, collector name patterns, archive hosts, daily
+        /// dump hour) extending the built-in RIPE RIS, RouteViews, PCH and Isolario ones
+        #[clap(long, env = "RIBEYE_COLLECTOR_PROFILES")]
+        collector_profiles: Option<String>,
+
+        /// Maximum number of RIB dumps downloaded concurrently; 0 for no limit
+        #[clap(long, env = "RIBEYE_MAX_DOWNLOADS", default_value = "0")]
+        max_downloads: usize,
+
+        /// Download remote RIB dumps into this directory before processing them, resuming
+        /// interrupted downloads, instead of streaming them
+        #[clap(long, env = "RIBEYE_DOWNLOAD_CACHE")]
+        download_cache: Option<String>,
+
+        /// Attempts to download a RIB dump into the download cache before giving up
+        #[clap(long, default_value_t = DEFAULT_DOWNLOAD_ATTEMPTS)]
+        download_attempts: u32,
+
+        /// Stop downloading RIB dumps once this many bytes are downloaded, e.g. on metered
+        /// connections; dumps over the cap fail
+        #[clap(long, env = "RIBEYE_MAX_DOWNLOAD_BYTES")]
+        max_download_bytes: Option<u64>,
+
+        /// Handling of processor errors: abort, skip-and-count, skip-silently
+        #[clap(long, default_value = "abort")]
+        on_error: ErrorPolicy,
+
+        /// Only process prefixes of one address family: v4 or v6; outputs are tagged with it
+        #[clap(long)]
+        af: Option<AddressFamily>,
+
+        /// Keep peers reported as AS_TRANS (AS23456) as they are instead of resolving their real
+        /// ASN from their AS paths
+        #[clap(long)]
+        no_as_trans_correction: bool,
+
+        /// Processors sampled or skipped once over the time or memory budget, e.g. pfx2dist
+        #[clap(long)]
+        optional: Vec<String>,
+
+        /// Time budget per RIB dump in seconds, after which optional processors are degraded
+        #[clap(long)]
+        time_budget: Option<u64>,
+
+        /// Resident memory budget in MiB, after which optional processors are degraded
+        #[clap(long)]
+        memory_budget: Option<u64>,
+
+        /// Hand optional processors every Nth element once over budget instead of skipping them
+        #[clap(long, default_value = "0")]
+        sample_every: u64,
+
+        /// Dump time in per-dump output file names: exact (date, hour and minute) or daily (one
+        /// output per day, later dumps replacing earlier ones)
+        #[clap(long, default_value = "exact")]
+        granularity: OutputGranularity,
+
+        /// Encoding of the dated per-collector outputs: json, or cbor (`.cbor.bz2`, smaller and
+        /// faster to parse); latest outputs and summaries are always JSON
+        #[clap(long, default_value = "json")]
+        encoding: OutputEncoding,
+
+        /// Also append compact records of each RIB's outputs to the date-partitioned time-series
+        /// dataset under `<dir>/timeseries`
+        #[clap(long)]
+        timeseries: bool,
+
+        /// JSON configuration file, local path or URL, e.g. with per-processor output directories
+        #[clap(long)]
+        config: Option<String>,
+
+        /// Label of the run, writing outputs under `<dir>/labels/<label>` and tagging them with
+        /// the label, so that experimental runs keep production outputs intact
+        #[clap(long, env = "RIBEYE_LABEL")]
+        label: Option<String>,
+    },
+
+    /// Process a single MRT file, or MRT data piped through stdin with path `-`
+    File {
+        /// MRT file path or URL, use `-` to read from stdin
+        path: String,
+
+        /// Compression of MRT data read from stdin: none, gzip, bzip2
+        #[clap(long, default_value = "none")]
+        compression: Compression,
+
+        /// specify processors to use.
+        ///
+        /// Available processors: pfx2as, pfx2as-aggregated, pfx2as-peers, pfx2dist, pfxlen, as2rel,
+        /// peer_stats, peer-stats-approx, footprint, reachability, communities, pfxscope,
+        /// defaults, duplicates, pathdiv, vantage, firsthop
+        ///
+        /// If not specified, all processors will be used
+        #[clap(short, long)]
+        processors: Vec<String>,
+
+        /// Root data directory, use `-` to write results to stdout as JSON lines
+        #[clap(short, long, default_value = "./results")]
+        dir: String,
+
+        /// Route collector project name (e.g. route-views, riperis)
+        #[clap(long, default_value = "unknown")]
+        project: String,
+
+        /// Route collector name (e.g. route-views2, rrc00)
+        #[clap(long, default_value = "unknown")]
+        collector: String,
+
+        /// RIB dump timestamp, in unix seconds, RFC 3339 (e.g. `2024-01-01T08:00:00+01:00`, converted
+        /// to UTC) or `YYYY-MM-DDTHH:MM:SS` (UTC) format; defaults to now
+        #[clap(long)]
+        timestamp: Option<String>,
+
+        /// Seed processors from their previous outputs before processing
+        #[clap(long)]
+        warm_start: bool,
+
+        /// Write intermediate outputs every N million elements of a RIB dump
+        #[clap(long)]
+        flush_every: Option<u64>,
+
+        /// Handling of processor errors: abort, skip-and-count, skip-silently
+        #[clap(long, default_value = "abort")]
+        on_error: ErrorPolicy,
+
+        /// Only process prefixes of one address family: v4 or v6; outputs are tagged with it
+        #[clap(long)]
+        af: Option<AddressFamily>,
+
+        /// Keep peers reported as AS_TRANS (AS23456) as they are instead of resolving their real
+        /// ASN from their AS paths
+        #[clap(long)]
+        no_as_trans_correction: bool,
+
+        /// Processors sampled or skipped once over the time or memory budget, e.g. pfx2dist
+        #[clap(long)]
+        optional: Vec<String>,
+
+        /// Time budget per RIB dump in seconds, after which optional processors are degraded
+        #[clap(long)]
+        time_budget: Option<u64>,
+
+        /// Resident memory budget in MiB, after which optional processors are degraded
+        #[clap(long)]
+        memory_budget: Option<u64>,
+
+        /// Hand optional processors every Nth element once over budget instead of skipping them
+        #[clap(long, default_value = "0")]
+        sample_every: u64,
+
+        /// Dump time in per-dump output file names: exact (date, hour and minute) or daily (one
+        /// output per day, later dumps replacing earlier ones)
+        #[clap(long, default_value = "exact")]
+        granularity: OutputGranularity,
+
+        /// Encoding of the dated per-collector outputs: json, or cbor (`.cbor.bz2`, smaller and
+        /// faster to parse); latest outputs and summaries are always JSON
+        #[clap(long, default_value = "json")]
+        encoding: OutputEncoding,
+
+        /// Only write dated per-collector outputs, leaving the collectors' latest outputs untouched
+        #[clap(long, conflicts_with = "latest_only")]
+        no_latest: bool,
+
+        /// Only write the collectors' latest outputs, without dated outputs
+        #[clap(long)]
+        latest_only: bool,
+
+        /// Replace latest outputs even if they come from a newer RIB dump
+        #[clap(long, conflicts_with = "no_latest")]
+        force_latest: bool,
+
+        /// IXP route-server ASNs, local path or URL: a PeeringDB `net` dump, a JSON array or one ASN
+        /// per line
+        #[clap(long)]
+        ixp_asns: Option<String>,
+
+        /// Handling of IXP route-server ASNs on AS paths by as2rel: keep, strip, tag
+        #[clap(long, default_value = "strip")]
+        ixp_policy: IxpPolicy,
+
+        /// RIR delegated statistics files used by footprint and pfxscope to map prefixes and peer
+        /// IPs to countries, local paths or URLs
+        #[clap(long)]
+        delegated: Vec<String>,
+
+        /// JSON configuration file, local path or URL, e.g. with per-processor output
+        /// directories: `{"output_dirs": {"pfx2as": "s3://public-bucket/ribeye"}}`
+        #[clap(long)]
+        config: Option<String>,
+
+        /// Label of the run, writing outputs under `<dir>/labels/<label>` and tagging them with
+        /// the label, so that experimental runs keep production outputs intact
+        #[clap(long, env = "RIBEYE_LABEL")]
+        label: Option<String>,
+
+        /// Secret key file (see `ribeye keygen`) to write detached signatures of all outputs with
+        #[clap(long)]
+        signing_key: Option<String>,
+
+        /// Key file (see `ribeye keygen --encryption`) to encrypt all outputs with
+        #[clap(long)]
+        encryption_key: Option<String>,
+
+        /// Also append compact records of each RIB's outputs to the date-partitioned time-series
+        /// dataset under `<dir>/timeseries`
+        #[clap(long)]
+        timeseries: bool,
+    },
+
+    /// Process local MRT files matching glob patterns or under directories
+    Files {
+        /// Glob patterns (e.g. `archive/2024/**/rib.*.bz2`) or directories to search recursively
+        #[clap(required = true)]
+        paths: Vec<String>,
+
+        /// Process all files through one pipeline and write a single merged output per processor
+        /// instead of per-file outputs
+        #[clap(long)]
+        merge: bool,
+
+        /// JSON list of collector profiles (project, collector name patterns, archive hosts, daily
+        /// dump hour) extending the built-in RIPE RIS, RouteViews, PCH and Isolario ones
+        #[clap(long, env = "RIBEYE_COLLECTOR_PROFILES")]
+        collector_profiles: Option<String>,
+
+        /// specify processors to use.
+        ///
+        /// Available processors: pfx2as, pfx2as-aggregated, pfx2as-peers, pfx2dist, pfxlen, as2rel,
+        /// peer_stats, peer-stats-approx, footprint, reachability, communities, pfxscope,
+        /// defaults, duplicates, pathdiv, vantage, firsthop
+        ///
+        /// If not specified, all processors will be used
+        #[clap(short, long)]
+        processors: Vec<String>,
+
+        /// Number of RIB files processed concurrently; defaults to the number of threads
+        #[clap(long, env = "RIBEYE_PARALLEL")]
+        parallel: Option<usize>,
+
+        /// Size of the worker thread pool; defaults to the number of CPU cores
+        #[clap(short, long, env = "RIBEYE_THREADS")]
+        threads: Option<usize>,
+
+        /// Root data directory
+        #[clap(short, long, default_value = "./results")]
+        dir: String,
+
+        /// Write intermediate outputs every N million elements of a RIB dump
+        #[clap(long)]
+        flush_every: Option<u64>,
+
+        /// Handling of processor errors: abort, skip-and-count, skip-silently
+        #[clap(long, default_value = "abort")]
+        on_error: ErrorPolicy,
+
+        /// Only process prefixes of one address family: v4 or v6; outputs are tagged with it
+        #[clap(long)]
+        af: Option<AddressFamily>,
+
+        /// Keep peers reported as AS_TRANS (AS23456) as they are instead of resolving their real
+        /// ASN from their AS paths
+        #[clap(long)]
+        no_as_trans_correction: bool,
+
+        /// Processors sampled or skipped once over the time or memory budget, e.g. pfx2dist
+        #[clap(long)]
+        optional: Vec<String>,
+
+        /// Time budget per RIB dump in seconds, after which optional processors are degraded
+        #[clap(long)]
+        time_budget: Option<u64>,
+
+        /// Resident memory budget in MiB, after which optional processors are degraded
+        #[clap(long)]
+        memory_budget: Option<u64>,
+
+        /// Hand optional processors every Nth element once over budget instead of skipping them
+        #[clap(long, default_value = "0")]
+        sample_every: u64,
+
+        /// Dump time in per-dump output file names: exact (date, hour and minute) or daily (one
+        /// output per day, later dumps replacing earlier ones)
+        #[clap(long, default_value = "exact")]
+        granularity: OutputGranularity,
+
+        /// Encoding of the dated per-collector outputs: json, or cbor (`.cbor.bz2`, smaller and
+        /// faster to parse); latest outputs and summaries are always JSON
+        #[clap(long, default_value = "json")]
+        encoding: OutputEncoding,
+
+        /// Only write dated per-collector outputs, leaving the collectors' latest outputs untouched
+        #[clap(long, conflicts_with = "latest_only")]
+        no_latest: bool,
+
+        /// Only write the collectors' latest outputs, without dated outputs
+        #[clap(long)]
+        latest_only: bool,
+
+        /// Replace latest outputs even if they come from a newer RIB dump
+        #[clap(long, conflicts_with = "no_latest")]
+        force_latest: bool,
+
+        /// IXP route-server ASNs, local path or URL: a PeeringDB `net` dump, a JSON array or one ASN
+        /// per line
+        #[clap(long)]
+        ixp_asns: Option<String>,
+
+        /// Handling of IXP route-server ASNs on AS paths by as2rel: keep, strip, tag
+        #[clap(long, default_value = "strip")]
+        ixp_policy: IxpPolicy,
+
+        /// RIR delegated statistics files used by footprint and pfxscope to map prefixes and peer
+        /// IPs to countries, local paths or URLs
+        #[clap(long)]
+        delegated: Vec<String>,
+
+        /// JSON configuration file, local path or URL, e.g. with per-processor output
+        /// directories: `{"output_dirs": {"pfx2as": "s3://public-bucket/ribeye"}}`
+        #[clap(long)]
+        config: Option<String>,
+
+        /// Label of the run, writing outputs under `<dir>/labels/<label>` and tagging them with
+        /// the label, so that experimental runs keep production outputs intact
+        #[clap(long, env = "RIBEYE_LABEL")]
+        label: Option<String>,
+
+        /// Secret key file (see `ribeye keygen`) to write detached signatures of all outputs with
+        #[clap(long)]
+        signing_key: Option<String>,
+
+        /// Key file (see `ribeye keygen --encryption`) to encrypt all outputs with
+        #[clap(long)]
+        encryption_key: Option<String>,
+
+        /// Also append compact records of each RIB's outputs to the date-partitioned time-series
+        /// dataset under `<dir>/timeseries`
+        #[clap(long)]
+        timeseries: bool,
+    },
+
+    /// Keep a route table current with RIS Live updates, periodically emitting outputs rebuilt from it
+    Live {
+        /// RIS route collector to subscribe to (e.g. rrc00)
+        #[clap(short, long, default_value = "rrc00")]
+        collector: String,
+
+        /// Interval in seconds between emitted snapshots
+        #[clap(short, long, default_value = "3600")]
+        interval: u64,
+
+        /// specify processors to use.
+        ///
+        /// Available processors: pfx2as, pfx2as-aggregated, pfx2as-peers, pfx2dist, pfxlen, as2rel,
+        /// peer_stats, peer-stats-approx, footprint, reachability, communities, pfxscope,
+        /// defaults, duplicates, pathdiv, vantage, firsthop
+        ///
+        /// If not specified, all processors will be used
+        #[clap(short, long)]
+        processors: Vec<String>,
+
+        /// Root data directory
+        #[clap(short, long, default_value = "./results")]
+        dir: String,
+
+        /// Skip bootstrapping the route table from the collector's latest RIB dump
+        #[clap(long)]
+        no_bootstrap: bool,
+    },
+
+    /// Look up IP addresses or ASNs in the latest summarized results
+    Lookup {
+        #[clap(subcommand)]
+        query: LookupQuery,
+
+        /// Root data directory, local or S3, or a `.ribeye` artifact
+        #[clap(short, long, default_value = "./results", global = true)]
+        dir: String,
+    },
+
+    /// Pretty-print, filter and convert a ribeye output file
+    Inspect {
+        /// Output file path, local or remote (e.g. results/pfx2as/latest.json.bz2)
+        path: String,
+
+        /// Keep records of the given prefix or its more-specifics
+        #[clap(long)]
+        prefix: Option<ipnet::IpNet>,
+
+        /// Keep records with the given ASN
+        #[clap(long)]
+        asn: Option<u32>,
+
+        /// Keep records observed at the given collector
+        #[clap(long)]
+        collector: Option<String>,
+
+        /// Output format
+        #[clap(short, long, value_enum, default_value = "stats")]
+        format: InspectFormat,
+    },
+
+    /// Print the JSON schemas of a processor's per-collector and summary outputs
+    Schema {
+        /// Processor name, e.g. pfx2as or peer-stats
+        processor: String,
+
+        /// Print only the per-collector output schema
+        #[clap(long, conflicts_with = "summary")]
+        output: bool,
+
+        /// Print only the summary schema
+        #[clap(long)]
+        summary: bool,
+    },
+
+    /// Compare summarized results against external datasets
+    Compare {
+        #[clap(subcommand)]
+        dataset: CompareDataset,
+
+        /// Root data directory, local or S3
+        #[clap(short, long, default_value = "./results", global = true)]
+        dir: String,
+    },
+
+    /// Write PeeringDB-enriched variants of the summarized as2rel, peer-stats and pfx2as outputs
+    Enrich {
+        /// Path or URL of a PeeringDB dump, or of a PeeringDB `net` API response
+        #[clap(long)]
+        peeringdb: String,
+
+        /// Processors whose summaries to enrich: as2rel, peer-stats, pfx2as
+        ///
+        /// If not specified, all of them are enriched
+        #[clap(short, long)]
+        processors: Vec<String>,
+
+        /// Root data directory, local or S3
+        #[clap(short, long, default_value = "./results")]
+        dir: String,
+    },
+
+    /// Export the summarized as2rel and peer-stats outputs in the schemas of BGPKIT's public as2rel
+    /// data, next to the originals
+    ExportBgpkit {
+        /// Processors whose summaries to export: as2rel, peer-stats
+        ///
+        /// If not specified, all of them are exported
+        #[clap(short, long)]
+        processors: Vec<String>,
+
+        /// Root data directory, local or S3
+        #[clap(short, long, default_value = "./results")]
+        dir: String,
+    },
+
+    /// Rank hijack candidates from the summarized pfx2as and as2rel outputs, combining MOAS
+    /// conflicts, RPKI validity and AS relationships
+    Hijacks {
+        /// VRPs exported by an RPKI relying party (JSON or CSV), local path or URL; RPKI validity
+        /// is not checked without them
+        #[clap(long)]
+        vrps: Option<String>,
+
+        /// Root data directory, local or S3
+        #[clap(short, long, default_value = "./results")]
+        dir: String,
+    },
+
+    /// Generate a key pair for signing outputs
+    Keygen {
+        /// Path of the secret key file to write, keep it private
+        #[clap(long, default_value = "ribeye.key")]
+        secret_key: String,
+
+        /// Path of the minisign public key file to write, to be published for verification
+        #[clap(long, default_value = "ribeye.pub")]
+        public_key: String,
+
+        /// Write a key for encrypting outputs to this path instead of a signing key pair
+        #[clap(long)]
+        encryption: Option<String>,
+    },
+
+    /// Decrypt an output file written with `--encryption-key`
+    Decrypt {
+        /// Encrypted output file, local path or URL
+        file: String,
+
+        /// Key file the output was encrypted with
+        #[clap(long)]
+        key: String,
+
+        /// Path the output was written to, if the file was copied or moved since, or is read over
+        /// HTTP(S); encrypted outputs only decrypt with the path they were written to
+        #[clap(long)]
+        stored_path: Option<String>,
+
+        /// Path to write the decrypted content to, compressed according to its extension;
+        /// standard output if not given
+        #[clap(short, long)]
+        output: Option<String>,
+    },
+
+    /// Verify output files against their detached signatures (`<file>.minisig`)
+    Verify {
+        /// Output files to verify, local paths or URLs
+        #[clap(required = true)]
+        files: Vec<String>,
+
+        /// minisign public key file, local path or URL
+        #[clap(long)]
+        public_key: String,
+    },
+
+    /// Audit an output directory without modifying it: outputs and summaries parse and match
+    /// their schemas, latest outputs are not older than dated ones, `index.json` manifests match
+    /// the files and, with a public key, signatures are valid
+    VerifyOutputs {
+        /// Root data directory, local or S3
+        dir: String,
+
+        /// minisign public key file, local path or URL, to verify the detached signatures of all
+        /// outputs with
+        #[clap(long)]
+        public_key: Option<String>,
+
+        /// Key file the outputs were encrypted with
+        #[clap(long)]
+        encryption_key: Option<String>,
+
+        /// Print the audit report as JSON
+        #[clap(long)]
+        json: bool,
+    },
+
+    /// Render the summaries of an output directory into a static HTML report with tables and
+    /// charts of collectors, peers, top origins, relationships and churn
+    Report {
+        /// Root data directory, local or S3
+        #[clap(short, long, default_value = "./results")]
+        dir: String,
+
+        /// Path of the HTML report, `<dir>/report.html` by default
+        #[clap(short, long)]
+        output: Option<String>,
+
+        /// Title of the report
+        #[clap(long)]
+        title: Option<String>,
+
+        /// Number of entries of the top origin and busiest link tables
+        #[clap(long, default_value_t = DEFAULT_TOP_N)]
+        top_n: usize,
+
+        /// Key file the summaries were encrypted with
+        #[clap(long)]
+        encryption_key: Option<String>,
+    },
+
+    /// Write browsable `index.json` files listing the datasets, collectors and files of an output
+    /// directory
+    Index {
+        /// Root data directory, local or S3
+        #[clap(short, long, default_value = "./results")]
+        dir: String,
+    },
+
+    /// List the processors and collectors with outputs under an output directory, with their
+    /// latest output and range of dated outputs
+    List {
+        /// Root data directory, local or S3
+        #[clap(short, long, default_value = "./results")]
+        dir: String,
+
+        /// Only list outputs of the given processor
+        #[clap(long)]
+        processor: Option<String>,
+
+        /// Only list outputs of the given collector
+        #[clap(long)]
+        collector: Option<String>,
+    },
+
+    /// Reconstruct the pfx2as summary of a date from the delta-encoded archive written with
+    /// `cook --delta-archive`
+    Restore {
+        /// Date to reconstruct (YYYY-MM-DD); the last archived date before it if not archived
+        #[clap(long)]
+        date: NaiveDate,
+
+        /// Root data directory, local or S3
+        #[clap(short, long, default_value = "./results")]
+        dir: String,
+
+        /// Path to write the summary to, compressed according to its extension; standard output
+        /// if not given
+        #[clap(short, long)]
+        output: Option<String>,
+    },
+
+    /// Delete dated outputs older than a retention period; latest outputs are kept
+    Prune {
+        /// Root data directory, local or S3
+        #[clap(short, long, default_value = "./results")]
+        dir: String,
+
+        /// Keep dated outputs of RIB dumps from the last N days
+        #[clap(long)]
+        keep_days: u32,
+
+        /// Print the outputs that would be deleted without deleting them
+        #[clap(long)]
+        dry_run: bool,
+    },
+
+    /// Process a tiny RIB dump with every processor and validate the outputs against their
+    /// schemas, as a quick sanity check of a deployment
+    Selftest {
+        /// MRT file to process instead of the generated RIB dump, local path or URL
+        #[clap(long)]
+        mrt: Option<String>,
+
+        /// Also write, read back and delete a small object under this output directory, e.g. to
+        /// check object store credentials
+        #[clap(long)]
+        storage: Option<String>,
+    },
+
+    /// Listen for BMP feeds and run the processors on their route monitoring messages
+    Bmp {
+        /// Socket address to listen on for BMP connections
+        #[clap(short, long, default_value = "0.0.0.0:11019")]
+        listen: String,
+
+        /// Collector name used in output paths
+        #[clap(short, long, default_value = "bmp")]
+        collector: String,
+
+        /// Interval in seconds between emitted snapshots
+        #[clap(short, long, default_value = "3600")]
+        interval: u64,
+
+        /// specify processors to use.
+        ///
+        /// Available processors: pfx2as, pfx2as-aggregated, pfx2as-peers, pfx2dist, pfxlen, as2rel,
+        /// peer_stats, peer-stats-approx, footprint, reachability, communities, pfxscope,
+        /// defaults, duplicates, pathdiv, vantage, firsthop
+        ///
+        /// If not specified, all processors will be used
+        #[clap(short, long)]
+        processors: Vec<String>,
+
+        /// Root data directory
+        #[clap(short, long, default_value = "./results")]
+        dir: String,
+    },
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum InspectFormat {
+    /// summary statistics of the (filtered) records
+    Stats,
+    /// pretty-printed JSON object, same layout as the input file
+    Json,
+    /// one JSON record per line
+    Jsonl,
+    /// CSV records with a header row
+    Csv,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum SummaryFormat {
+    /// JSON summaries only
+    Json,
+    /// JSON summaries and their tables as CSV files
+    Csv,
+    /// JSON summaries and, for pfx2as and as2rel, Protocol Buffers (see `proto/ribeye.proto`)
+    Protobuf,
+}
+
+#[derive(Subcommand)]
+enum CompareDataset {
+    /// Compare the summarized as2rel output against a CAIDA as-rel formatted dataset
+    As2rel {
+        /// Path or URL of the external dataset in CAIDA as-rel format
+        #[clap(long)]
+        against: String,
+
+        /// Write the full comparison, including links unique to each source, to this JSON file
+        #[clap(short, long)]
+        output: Option<String>,
+    },
+}
+
+#[derive(Subcommand)]
+enum LookupQuery {
+    /// Look up origins and covering prefixes of an IP address
+    Ip {
+        /// IP address to look up
+        ip: IpAddr,
+    },
+    /// Look up originated prefixes, relationships and collector peerings of an ASN
+    Asn {
+        /// ASN to look up
+        asn: u32,
+    },
+}
+
+/// Parse a timestamp string in unix seconds, RFC 3339 (normalized to UTC) or `YYYY-MM-DDTHH:MM:SS`
+/// (taken as UTC) format.
+fn parse_timestamp(ts_str: &str) -> Option<NaiveDateTime> {
+    if let Ok(ts) = ts_str.parse::<i64>() {
+        return chrono::DateTime::from_timestamp(ts, 0).map(|t| t.naive_utc());
+    }
+    if let Ok(ts) = chrono::DateTime::parse_from_rfc3339(ts_str) {
+        return Some(ts.naive_utc());
+    }
+    NaiveDateTime::parse_from_str(ts_str, "%Y-%m-%dT%H:%M:%S").ok()
+}
+
+/// Build the global rayon thread pool with the given number of threads, or the maximum if unset,
+/// and return the job pool that processes up to `parallel` RIB files concurrently.
+fn init_thread_pools(parallel: Option<usize>, threads: Option<usize>) -> JobPool {
+    match threads {
+        None => {
+            info!("using maximum threads for processing");
+            rayon::ThreadPoolBuilder::new().build_global().unwrap();
+        }
+        Some(t) => {
+            info!("using {} threads for processing", t);
+            rayon::ThreadPoolBuilder::new()
+                .num_threads(t)
+                .build_global()
+                .unwrap();
+        }
+    }
+    match JobPool::new(parallel) {
+        Ok(pool) => pool,
+        Err(e) => {
+            error!("failed to create the job pool: {}", e);
+            exit(1);
+        }
+    }
+}
+
+/// Log the failed jobs of a run, returning their number.
+fn log_failed_jobs(results: &[JobResult]) -> usize {
+    let mut failed = 0;
+    for job in results {
+        if let Err(e) = &job.result {
+            error!(
+                collector = job.rib_meta.collector.as_str(),
+                phase = "process",
+                "failed to process {}: {}",
+                job.rib_meta.rib_dump_url,
+                e
+            );
+            failed += 1;
+        }
+    }
+    failed
+}
+
+/// Log the bytes downloaded and written by the successful jobs, per collector and in total.
+fn log_transfers(results: &[JobResult]) {
+    let mut collectors: BTreeMap<&str, (u64, u64)> = BTreeMap::new();
+    for job in results {
+        if let Ok(stats) = &job.result {
+            let bytes = collectors
+                .entry(job.rib_meta.collector.as_str())
+                .or_default();
+            bytes.0 += stats.downloaded_bytes;
+            bytes.1 += stats.written_bytes;
+        }
+    }
+    for (collector, (downloaded, written)) in &collectors {
+        info!(
+            collector = *collector,
+            phase = "process",
+            downloaded_bytes = *downloaded,
+            written_bytes = *written,
+            "downloaded {} bytes, wrote {} bytes",
+            downloaded,
+            written
+        );
+    }
+    let (downloaded, written) = collectors
+        .values()
+        .fold((0, 0), |(d, w), (downloaded, written)| {
+            (d + downloaded, w + written)
+        });
+    info!(
+        phase = "process",
+        downloaded_bytes = downloaded,
+        written_bytes = written,
+        "downloaded {} bytes from {} collectors, wrote {} bytes",
+        downloaded,
+        collectors.len(),
+        written
+    );
+}
+
+/// Build the processor options from the `--config`, `--ixp-asns`, `--ixp-policy`,
+/// `--delegated` and `--label` arguments.
+fn processor_config(
+    config_file: Option<String>,
+    ixp_asns: Option<String>,
+    ixp_policy: IxpPolicy,
+    delegated: &[String],
+    label: Option<String>,
+) -> ProcessorConfig {
+    let mut config = ProcessorConfig::new();
+    if let Some(path) = config_file {
+        match ProcessorConfigFile::load(path.as_str()) {
+            Ok(config_file) => {
+                for (processor, output_dir) in &config_file.output_dirs {
+                    info!("writing {} outputs to {}", processor, output_dir);
+                }
+                config = config.with_config_file(&config_file);
+            }
+            Err(e) => {
+                error!("{}", e);
+                exit(1);
+            }
+        }
+    }
+    if let Some(path) = ixp_asns {
+        match load_ixp_asns(path.as_str()) {
+            Ok(asns) => {
+                info!("loaded {} IXP route-server ASNs from {}", asns.len(), path);
+                config = config.with_ixp_asns(asns, ixp_policy);
+            }
+            Err(e) => {
+                error!("{}", e);
+                exit(1);
+            }
+        }
+    }
+    if !delegated.is_empty() {
+        match PrefixCountries::load(delegated) {
+            Ok(countries) => {
+                info!(
+                    "loaded {} delegated address blocks from {} files",
+                    countries.len(),
+                    delegated.len()
+                );
+                config = config.with_prefix_countries(countries);
+            }
+            Err(e) => {
+                error!("{}", e);
+                exit(1);
+            }
+        }
+    }
+    if let Some(label) = label {
+        match config.with_label(label.as_str()) {
+            Ok(c) => {
+                info!("labeling run {}", label);
+                config = c;
+            }
+            Err(e) => {
+                error!("{}", e);
+                exit(1);
+            }
+        }
+    }
+    config
+}
+
+/// Local state file of a run, with the run's label inserted before its extension if labeled, e.g.
+/// `ribeye-run-ledger.<label>.json`.
+fn labeled_state_file(name: &str, config: &ProcessorConfig) -> String {
+    match &config.label {
+        Some(label) => format!("{}.{}.json", name, label),
+        None => format!("{}.json", name),
+    }
+}
+
+/// Keys of `--encryption-key` and `--signing-key` applied to the outputs of each pipeline.
+struct OutputKeys {
+    encryption: Option<Arc<EncryptionKey>>,
+    signing: Option<Arc<SigningKey>>,
+}
+
+impl OutputKeys {
+    fn load(signing_key: Option<String>, encryption_key: Option<String>) -> Self {
+        let encryption = encryption_key.map(|path| match EncryptionKey::load(path.as_str()) {
+            Ok(key) => Arc::new(key),
+            Err(e) => {
+                error!("{}", e);
+                exit(1);
+            }
+        });
+        let signing = signing_key.map(|path| match SigningKey::load(path.as_str()) {
+            Ok(key) => Arc::new(key),
+            Err(e) => {
+                error!("{}", e);
+                exit(1);
+            }
+        });
+        OutputKeys {
+            encryption,
+            signing,
+        }
+    }
+
+    /// Encrypt and then sign the outputs of a pipeline, if keys are given.
+    fn apply(&self, mut ribeye: RibEye) -> RibEye {
+        if let Some(key) = &self.encryption {
+            ribeye = match ribeye.with_encryption_key(key.clone()) {
+                Ok(r) => r,
+                Err(e) => {
+                    error!("{}", e);
+                    exit(1);
+                }
+            };
+        }
+        if let Some(key) = &self.signing {
+            ribeye = ribeye.with_signing_key(key.clone());
+        }
+        ribeye
+    }
+}
+
+/// Append time-series records of the outputs of a pipeline with `--timeseries`.
+fn timeseries_outputs(ribeye: RibEye, timeseries: bool, dir: &str) -> RibEye {
+    match timeseries {
+        true => ribeye.with_timeseries(dir),
+        false => ribeye,
+    }
+}
+
+/// Bundle of `--bundle` and `--bundle-part-size` for the dated outputs of a run under `dir`.
+fn run_bundle(bundle: bool, part_size_mib: u64, dir: &str) -> Option<Arc<OutputBundle>> {
+    bundle.then(|| {
+        let run = chrono::Utc::now().format("%Y%m%dT%H%M%SZ").to_string();
+        let bundle = OutputBundle::new(dir, run.as_str()).with_part_size(part_size_mib << 20);
+        info!("bundling dated outputs under {}", bundle.bundle_dir());
+        Arc::new(bundle)
+    })
+}
+
+/// Write the last part of a run's bundle, if any.
+fn finish_bundle(bundle: &Option<Arc<OutputBundle>>) {
+    if let Some(bundle) = bundle {
+        if let Err(e) = bundle.finish() {
+            error!("failed to write bundle {}: {}", bundle.bundle_dir(), e);
+            exit(1);
+        }
+    }
+}
+
+/// Send the dated outputs of a pipeline to the run's bundle, if any. Applied last so that the
+/// bundle parts are written through the complete sink.
+fn bundle_outputs(ribeye: RibEye, bundle: &Option<Arc<OutputBundle>>) -> RibEye {
+    match bundle {
+        Some(bundle) => ribeye.with_bundle(bundle.clone()),
+        None => ribeye,
+    }
+}
+
+/// Limits of `--max-downloads`, `--download-rate`, `--max-uploads`, `--upload-rate` and
+/// `--max-download-bytes`, shared by all pipelines of a cook.
+struct IoLimits {
+    download: Option<Arc<IoLimiter>>,
+    upload: Option<Arc<IoLimiter>>,
+    downloader: Option<Downloader>,
+    download_cap: Option<Arc<ByteCap>>,
+}
+
+impl IoLimits {
+    fn new(max_downloads: usize, download_rate: f64, max_uploads: usize, upload_rate: f64) -> Self {
+        let limiter = |max: usize, rate: f64| {
+            (max > 0 || rate > 0.0)
+                .then(|| Arc::new(IoLimiter::new().with_max_concurrent(max).with_rate(rate)))
+        };
+        IoLimits {
+            download: limiter(max_downloads, download_rate),
+            upload: limiter(max_uploads, upload_rate),
+            downloader: None,
+            download_cap: None,
+        }
+    }
+
+    /// Download remote RIB dumps into the `--download-cache` directory, if set.
+    fn with_download_cache(mut self, cache_dir: Option<String>, max_attempts: u32) -> Self {
+        self.downloader =
+            cache_dir.map(|dir| Downloader::new(dir.as_str()).with_max_attempts(max_attempts));
+        self
+    }
+
+    /// Cap the bytes downloaded by all pipelines at `--max-download-bytes`, if set.
+    fn with_download_cap(mut self, max_bytes: Option<u64>) -> Self {
+        self.download_cap = max_bytes.map(|max| {
+            info!("downloading at most {} bytes of RIB dumps", max);
+            Arc::new(ByteCap::new(max))
+        });
+        self
+    }
+
+    /// Limit the remote I/O of a pipeline; call after its sink is set up.
+    fn apply(&self, mut ribeye: RibEye) -> RibEye {
+        if let Some(limiter) = &self.download {
+            ribeye = ribeye.with_download_limiter(limiter.clone());
+        }
+        if let Some(limiter) = &self.upload {
+            ribeye = ribeye.with_upload_limiter(limiter.clone());
+        }
+        if let Some(downloader) = &self.downloader {
+            ribeye = ribeye.with_downloader(downloader.clone());
+        }
+        if let Some(cap) = &self.download_cap {
+            ribeye = ribeye.with_download_cap(cap.clone());
+        }
+        ribeye
+    }
+}
+
+/// Load the RIB dumps of the collectors (all if empty) listed in a `--source-manifest`.
+fn load_source_manifest(path: &str, collectors: &[String]) -> Vec<RibMeta> {
+    let rib_metas = match SourceManifest::load(path).and_then(|m| m.rib_metas()) {
+        Ok(rib_metas) => rib_metas,
+        Err(e) => {
+            error!("{}", e);
+            exit(1);
+        }
+    };
+    let rib_metas: Vec<RibMeta> = rib_metas
+        .into_iter()
+        .filter(|r| collectors.is_empty() || collectors.contains(&r.collector))
+        .collect();
+    info!(
+        "loaded {} MRT files from source manifest {}",
+        rib_metas.len(),
+        path
+    );
+    rib_metas
+}
+
+/// Load the VRPs for `--vrps`.
+fn load_vrps(path: &str) -> Vrps {
+    match Vrps::load(path) {
+        Ok(v) => {
+            info!("loaded {} VRPs from {}", v.len(), path);
+            v
+        }
+        Err(e) => {
+            error!("{}", e);
+            exit(1);
+        }
+    }
+}
+
+/// Create the analyzers for `--analyzers` writing under `dir`.
+fn load_analyzers(names: &[String], dir: &str, vrps: Option<String>) -> Vec<Arc<dyn Analyzer>> {
+    if names.is_empty() {
+        return vec![];
+    }
+    let vrps = vrps.map(|path| Arc::new(load_vrps(path.as_str())));
+    names
+        .iter()
+        .map(
+            |name| match get_analyzer(name.as_str(), dir, vrps.clone()) {
+                Ok(analyzer) => analyzer,
+                Err(e) => {
+                    error!("{}", e);
+                    exit(1);
+                }
+            },
+        )
+        .collect()
+}
+
+/// Load the collector profiles for `--collector-profiles`, the built-in ones if not set.
+fn load_collector_profiles(path: Option<String>) -> CollectorProfiles {
+    let Some(path) = path else {
+        return CollectorProfiles::new();
+    };
+    match CollectorProfiles::load(path.as_str()) {
+        Ok(profiles) => {
+            info!(
+                "loaded {} collector profiles from {}",
+                profiles.profiles().len() - CollectorProfiles::builtin().profiles().len(),
+                path
+            );
+            profiles
+        }
+        Err(e) => {
+            error!("{}", e);
+            exit(1);
+        }
+    }
+}
+
+/// Load the alerting configuration for `--alert-config`.
+fn load_alert_config(path: &str) -> AlertConfig {
+    match AlertConfig::load(path) {
+        Ok(config) => {
+            info!("loaded {} alerting rules from {}", config.rules.len(), path);
+            config
+        }
+        Err(e) => {
+            error!("{}", e);
+            exit(1);
+        }
+    }
+}
+
+/// Load the AS information dataset for `--enrich-asn-info`.
+fn load_asn_info(path: &str) -> Arc<AsnInfoData> {
+    match AsnInfoData::load(path) {
+        Ok(data) => {
+            info!(
+                "loaded AS information of {} ASNs from {}",
+                data.asns.len(),
+                path
+            );
+            Arc::new(data)
+        }
+        Err(e) => {
+            error!("{}", e);
+            exit(1);
+        }
+    }
+}
+
+/// Convert a `--flush-every` value in millions of elements to a flush interval, 0 disables flushing.
+fn flush_interval(flush_every: Option<u64>) -> u64 {
+    flush_every.unwrap_or(0) * 1_000_000
+}
+
+/// Per-collector outputs to write from the `--no-latest` and `--latest-only` flags.
+fn output_selection(no_latest: bool, latest_only: bool) -> OutputSelection {
+    match (no_latest, latest_only) {
+        (true, _) => OutputSelection::DatedOnly,
+        (_, true) => OutputSelection::LatestOnly,
+        _ => OutputSelection::All,
+    }
+}
+
+/// Processing budget from the time budget in seconds and memory budget in MiB.
+fn processing_budget(
+    time_budget: Option<u64>,
+    memory_budget: Option<u64>,
+    sample_every: u64,
+) -> ProcessingBudget {
+    let mut budget = ProcessingBudget::new().with_sample_every(sample_every);
+    if let Some(secs) = time_budget {
+        budget = budget.with_max_duration(Duration::from_secs(secs));
+    }
+    if let Some(mib) = memory_budget {
+        budget = budget.with_max_memory(mib * 1024 * 1024);
+    }
+    budget
+}
+
+/// Exit if the credentials of an object store used by the output directories are not set.
+fn check_storage_env<'a>(dirs: impl Iterator<Item = &'a String>) {
+    for dir in dirs {
+        if let Err(e) = storage::env_check(dir.as_str()) {
+            error!("{}", e);
+            exit(1);
+        }
+    }
+}
+
+fn main() {
+    if std::env::var_os("RUST_LOG").is_none() {
+        std::env::set_var("RUST_LOG", "none,ribeye=info");
+    }
+    let opts = Cli::parse();
+
+    match opts.log_format {
+        LogFormat::Text => tracing_subscriber::fmt()
+            .with_writer(std::io::stderr)
+            .with_env_filter(EnvFilter::from_default_env())
+            .init(),
+        LogFormat::Json => tracing_subscriber::fmt()
+            .with_writer(std::io::stderr)
+            .json()
+            .flatten_event(true)
+            .with_current_span(false)
+            .with_env_filter(EnvFilter::from_default_env())
+            .init(),
+    }
+
+    if let Some(env_path) = opts.env {
+        match dotenvy::from_path_override(env_path.as_str()) {
+            Ok(_) => {
+                info!("loaded environment variables from {}", env_path);
+            }
+            Err(_) => {
+                error!("failed to load environment variables from {}", env_path);
+                exit(1);
+            }
+        };
+    }
+    dotenvy::dotenv().ok();
+
+    match opts.command {
+        Commands::Cook {
+            days,
+            processors,
+            collectors,
+            dir,
+            parallel,
+            threads,
+            limit,
+            summarize_only,
+            latest,
+            only_failed,
+            ledger,
+            bundle,
+            bundle_part_size,
+            source_manifest,
+            incremental,
+            summary_copies,
+            summary_format,
+            lock_wait,
+            lock_ttl,
+            no_lock,
+            stale_after_hours,
+            broker_url,
+            broker_retries,
+            collector_profiles,
+            analyzers,
+            vrps,
+            broker_cache,
+            no_broker_cache,
+            max_downloads,
+            download_rate,
+            download_cache,
+            download_attempts,
+            max_download_bytes,
+            max_uploads,
+            upload_rate,
+            flush_every,
+            on_error,
+            af,
+            no_as_trans_correction,
+            optional,
+            time_budget,
+            memory_budget,
+            sample_every,
+            granularity,
+            encoding,
+            no_latest,
+            latest_only,
+            force_latest,
+            ixp_asns,
+            ixp_policy,
+            delegated,
+            config,
+            signing_key,
+            encryption_key,
+            timeseries,
+            enrich_asn_info,
+            country_matrix,
+            ip2asn,
+            asn_info,
+            redis_url,
+            redis_key_prefix,
+            artifact,
+            delta_archive,
+            archive_full_every,
+            health_report,
+            health_drop_ratio,
+            top_views,
+            top_n,
+            alert_config,
+            index,
+            summarize_discovered,
+            label,
+        } => {
+            let config = processor_config(config, ixp_asns, ixp_policy, &delegated, label);
+            let dir = config.labeled_output_dir(dir.as_str());
+            let ledger = ledger.unwrap_or_else(|| labeled_state_file("ribeye-run-ledger", &config));
+            let summary_copies: Vec<Compression> = match summary_copies
+                .iter()
+                .filter(|c| !c.is_empty())
+                .map(|c| c.parse())
+                .collect()
+            {
+                Ok(c) => c,
+                Err(e) => {
+                    error!("{}", e);
+                    exit(1);
+                }
+            };
+            let output_keys = OutputKeys::load(signing_key, encryption_key);
+            let io_limits = IoLimits::new(max_downloads, download_rate, max_uploads, upload_rate)
+                .with_download_cache(download_cache, download_attempts)
+                .with_download_cap(max_download_bytes);
+            // load before processing, so that an unavailable dataset fails early
+            let asn_info = (enrich_asn_info || country_matrix || ip2asn)
+                .then(|| load_asn_info(asn_info.as_str()));
+            let alert_config = alert_config.map(|path| load_alert_config(path.as_str()));
+            // check object store credentials of the output directories
+            check_storage_env(std::iter::once(&dir).chain(config.output_dirs.values()));
+
+            // RIB dumps of the run: the failed ones of the previous run, those of the source
+            // manifest or found via the broker
+            let previous = match only_failed {
+                true => match RunLedger::load(ledger.as_str()) {
+                    Ok(previous) if previous.processors != processors => {
+                        error!(
+                            "run ledger {} belongs to a run with different processors ({}); rerun \
+                             them with the same processors",
+                            ledger,
+                            previous.processors.join(", ")
+                        );
+                        exit(1);
+                    }
+                    Ok(previous) => Some(previous),
+                    Err(e) => {
+                        error!("{}", e);
+                        exit(1);
+                    }
+                },
+                false => None,
+            };
+            let (rib_metas, to_process) = match (&previous, &source_manifest) {
+                (Some(previous), _) => {
+                    let failed = previous.failed();
+                    info!(
+                        "re-processing {} of {} RIB dumps that failed in the run started at {}",
+                        failed.len(),
+                        previous.ribs.len(),
+                        previous.started_at
+                    );
+                    (previous.rib_metas(), failed)
+                }
+                (None, Some(path)) => {
+                    let rib_metas = load_source_manifest(path.as_str(), &collectors);
+                    (rib_metas.clone(), rib_metas)
+                }
+                (None, None) => {
+                    let profiles = load_collector_profiles(collector_profiles);
+                    let mut broker = BrokerConfig::new(broker_url.as_str())
+                        .with_retries(broker_retries, std::time::Duration::from_secs(1));
+                    if !no_broker_cache {
+                        let cache_dir = broker_cache.unwrap_or_else(|| {
+                            std::env::temp_dir()
+                                .join("ribeye-broker-cache")
+                                .to_string_lossy()
+                                .to_string()
+                        });
+                        broker = broker.with_cache_dir(cache_dir.as_str());
+                    }
+                    let rib_items = if latest {
+                        info!("Searching for the latest RIB dump file of each collector");
+                        broker.query_latest_ribs()
+                    } else {
+                        let now = chrono::Utc::now().naive_utc();
+                        let ts_start = now - chrono::Duration::days(days as i64);
+                        info!("Searching for RIB dump files since {}", ts_start);
+                        broker.query_ribs(ts_start, now)
+                    };
+                    let rib_items = match rib_items {
+                        Ok(items) => items,
+                        Err(e) => {
+                            error!("{}", e);
+                            exit(1);
+                        }
+                    };
+                    let rib_metas: Vec<RibMeta> = rib_items
+                        .iter()
+                        .filter(|entry| {
+                            // latest RIB dumps are taken at any hour, otherwise use daily midnight dumps
+                            (latest
+                                || profiles.is_daily_dump(
+                                    entry.collector_id.as_str(),
+                                    entry.url.as_str(),
+                                    &entry.ts_start,
+                                ))
+                                && match collectors.len() {
+                                    0 => true,
+                                    _ => collectors.contains(&entry.collector_id),
+                                }
+                        })
+                        .map(|entry| RibMeta::from_broker_item(entry, &profiles))
+                        // smallest files first, so that a limit picks the smallest N RIB dumps
+                        .sorted_by_key(|rib_meta| rib_meta.file_size.unwrap_or(u64::MAX))
+                        .take(limit.unwrap_or(usize::MAX))
+                        .collect();
+                    (rib_metas.clone(), rib_metas)
+                }
+            };
+
+            let analyzers = load_analyzers(&analyzers, dir.as_str(), vrps);
+            if let Err(e) = RibEye::new()
+                .with_processor_names_and_config(&processors, dir.as_str(), &config)
+                .and_then(|p| p.with_analyzers(analyzers.clone()))
+            {
+                error!("{}", e);
+                exit(1);
+            }
+
+            if !summarize_only {
+                let bundle = run_bundle(bundle, bundle_part_size, dir.as_str());
+                let mut jobs = init_thread_pools(parallel, threads);
+                for rib_meta in &to_process {
+                    jobs.submit(rib_meta.clone());
+                }
+                // process each RIB file in parallel with provided meta information
+                info!("processing {} matching RIB dump files", to_process.len());
+                let results = jobs.run(|_| {
+                    match RibEye::new().with_processor_names_and_config(
+                        &processors,
+                        dir.as_str(),
+                        &config,
+                    ) {
+                        Ok(p) => Ok(bundle_outputs(
+                            io_limits.apply(timeseries_outputs(
+                                output_keys.apply(p),
+                                timeseries,
+                                dir.as_str(),
+                            )),
+                            &bundle,
+                        )
+                        .with_flush_interval(flush_interval(flush_every))
+                        .with_error_policy(on_error)
+                        .with_output_granularity(granularity)
+                        .with_output_encoding(encoding)
+                        .with_output_selection(output_selection(no_latest, latest_only))
+                        .with_force_latest(force_latest)
+                        .with_optional_processors(&optional)
+                        .with_budget(processing_budget(time_budget, memory_budget, sample_every))
+                        .with_address_family(af)
+                        .with_as_trans_correction(!no_as_trans_correction)
+                        .with_brief_summaries(true)
+                        .with_analyzers(analyzers.clone())?),
+                        Err(e) => {
+                            error!("failed to initialize RibEye: {}", e);
+                            exit(2);
+                        }
+                    }
+                });
+                log_transfers(&results);
+                finish_bundle(&bundle);
+                let mut run_ledger = previous.unwrap_or_else(|| RunLedger::new(&processors));
+                for job in &results {
+                    run_ledger.record(&job.rib_meta, &job.result);
+                }
+                if let Err(e) = run_ledger.save(ledger.as_str()) {
+                    error!("failed to save run ledger {}: {}", ledger, e);
+                }
+                if log_failed_jobs(&results) > 0 {
+                    exit(4);
+                }
+            }
+
+            info!("summarize all latest results");
+            let mut ribeye = match RibEye::new().with_processor_names_and_config(
+                &processors,
+                dir.as_str(),
+                &config,
+            ) {
+                Ok(p) => io_limits
+                    .apply(output_keys.apply(p))
+                    .with_incremental_summaries(incremental)
+                    .with_address_family(af)
+                    .with_summary_writer(
+                        SummaryWriter::new()
+                            .with_copies(&summary_copies)
+                            .with_csv(summary_format.contains(&SummaryFormat::Csv))
+                            .with_protobuf(summary_format.contains(&SummaryFormat::Protobuf)),
+                    )
+                    .with_stale_after(Duration::from_secs(stale_after_hours * 3600))
+                    .with_summary_lock((!no_lock).then_some(LockOptions {
+                        ttl: Duration::from_secs(lock_ttl),
+                        wait: Duration::from_secs(lock_wait),
+                    })),
+                Err(e) => {
+                    error!("failed to initialize RibEye: {}", e);
+                    exit(3);
+                }
+            };
+            // derived datasets are written next to the outputs they are derived from
+            let output_dir = |processor: &str| config.output_dir(processor).unwrap_or(dir.as_str());
+            let mut rib_metas = rib_metas;
+            if summarize_discovered {
+                for name in ribeye.processor_names() {
+                    let discovered = match discover_rib_metas(output_dir(name.as_str()), &name) {
+                        Ok(d) => d,
+                        Err(e) => {
+                            warn!(
+                                processor = name.as_str(),
+                                phase = "summarize",
+                                "failed to discover collectors: {}",
+                                e
+                            );
+                            continue;
+                        }
+                    };
+                    for rib_meta in discovered {
+                        if !rib_metas.iter().any(|r| r.collector == rib_meta.collector) {
+                            rib_metas.push(rib_meta);
+                        }
+                    }
+                }
+                info!("summarizing {} collectors", rib_metas.len());
+            }
+            if ip2asn {
+                ribeye = ribeye.with_ip2asn(
+                    get_ip2asn_path(output_dir("pfx2as")).as_str(),
+                    asn_info.clone(),
+                );
+            }
+            if let Some(asn_info) = asn_info {
+                if enrich_asn_info {
+                    ribeye = ribeye.with_asn_info(asn_info.clone());
+                }
+                if country_matrix {
+                    ribeye = ribeye.with_country_matrix(
+                        asn_info,
+                        get_country_matrix_path(output_dir("as2rel")).as_str(),
+                    );
+                }
+            }
+            if let Some(redis_url) = &redis_url {
+                ribeye = ribeye.with_redis(
+                    RedisExporter::new(redis_url.as_str())
+                        .with_key_prefix(redis_key_prefix.as_str()),
+                );
+            }
+            if artifact {
+                ribeye = ribeye.with_artifact(get_artifact_path(dir.as_str()).as_str());
+            }
+            if delta_archive {
+                let mut archive = DeltaArchive::new(get_archive_dir(dir.as_str()).as_str());
+                if let Some(n) = archive_full_every {
+                    archive = archive.with_full_every(n);
+                }
+                ribeye = ribeye.with_delta_archive(archive);
+            }
+            if health_report {
+                ribeye = ribeye.with_health_report(
+                    get_health_report_path(output_dir("peer-stats")).as_str(),
+                    health_drop_ratio,
+                );
+            }
+            if top_views {
+                ribeye = ribeye.with_top_views(top_n);
+            }
+            if let Some(alert_config) = &alert_config {
+                ribeye = ribeye.with_alert_config(alert_config);
+            }
+            if let Err(e) = ribeye.summarize_latest_files(&rib_metas) {
+                error!("failed to summarize latest outputs in {}: {}", dir, e);
+                exit(1);
+            }
+            if index {
+                if let Err(e) = write_output_index(dir.as_str()) {
+                    error!("failed to write output index: {}", e);
+                    exit(1);
+                }
+            }
+        }
+        Commands::File {
+            path,
+            compression,
+            processors,
+            dir,
+            project,
+            collector,
+            timestamp,
+            flush_every,
+            on_error,
+            af,
+            no_as_trans_correction,
+            optional,
+            time_budget,
+            memory_budget,
+            sample_every,
+            granularity,
+            encoding,
+            no_latest,
+            latest_only,
+            force_latest,
+            ixp_asns,
+            ixp_policy,
+            delegated,
+            config,
+            signing_key,
+            encryption_key,
+            timeseries,
+            warm_start,
+            label,
+        } => {
+            let config = processor_config(config, ixp_asns, ixp_policy, &delegated, label);
+            let dir = config.labeled_output_dir(dir.as_str());
+            let output_keys = OutputKeys::load(signing_key, encryption_key);
+            let timestamp = match timestamp {
+                None => chrono::Utc::now().naive_utc(),
+                Some(ts_str) => match parse_timestamp(ts_str.as_str()) {
+                    Some(t) => t,
+                    None => {
+                        error!("invalid timestamp: {}", ts_str);
+                        exit(1);
+                    }
+                },
+            };
+            let rib_meta = RibMeta {
+                project,
+                collector,
+                rib_dump_url: path.clone(),
+                timestamp,
+                file_size: std::fs::metadata(path.as_str()).ok().map(|m| m.len()),
+                dump_type: dump_type_from_path(path.as_str()),
+                broker_item_id: None,
+                ..Default::default()
+            };
+
+            let mut ribeye = match RibEye::new().with_processor_names_and_config(
+                &processors,
+                dir.as_str(),
+                &config,
+            ) {
+                Ok(p) => timeseries_outputs(output_keys.apply(p), timeseries, dir.as_str())
+                    .with_rib_meta(&rib_meta)
+                    .with_stdin_compression(compression)
+                    .with_flush_interval(flush_interval(flush_every))
+                    .with_error_policy(on_error)
+                    .with_output_granularity(granularity)
+                    .with_output_encoding(encoding)
+                    .with_output_selection(output_selection(no_latest, latest_only))
+                    .with_force_latest(force_latest)
+                    .with_optional_processors(&optional)
+                    .with_budget(processing_budget(time_budget, memory_budget, sample_every))
+                    .with_address_family(af)
+                    .with_as_trans_correction(!no_as_trans_correction)
+                    .with_brief_summaries(true),
+                Err(e) => {
+                    error!("failed to initialize RibEye: {}", e);
+                    exit(2);
+                }
+            };
+            if warm_start {
+                if let Err(e) = ribeye.warm_start() {
+                    error!("failed to warm start processors: {}", e);
+                    exit(1);
+                }
+            }
+            match ribeye.process_mrt_file(path.as_str()) {
+                Ok(stats) => info!(
+                    "processed {}: {}",
+                    path,
+                    serde_json::to_string(&stats).unwrap_or_default()
+                ),
+                Err(e) => {
+                    error!("failed to process {}: {}", path, e);
+                    exit(4);
+                }
+            }
+        }
+        Commands::Files {
+            paths,
+            merge,
+            collector_profiles,
+            processors,
+            parallel,
+            threads,
+            dir,
+            flush_every,
+            on_error,
+            af,
+            no_as_trans_correction,
+            optional,
+            time_budget,
+            memory_budget,
+            sample_every,
+            granularity,
+            encoding,
+            no_latest,
+            latest_only,
+            force_latest,
+            ixp_asns,
+            ixp_policy,
+            delegated,
+            config,
+            signing_key,
+            encryption_key,
+            timeseries,
+            label,
+        } => {
+            let config = processor_config(config, ixp_asns, ixp_policy, &delegated, label);
+            let dir = config.labeled_output_dir(dir.as_str());
+            let output_keys = OutputKeys::load(signing_key, encryption_key);
+            let files = match expand_input_paths(&paths) {
+                Ok(f) => f,
+                Err(e) => {
+                    error!("failed to expand input paths: {}", e);
+                    exit(1);
+                }
+            };
+
+            let profiles = load_collector_profiles(collector_profiles);
+            let rib_metas: Vec<RibMeta> = files
+                .iter()
+                .filter_map(
+                    |file| match RibMeta::from_file_path_with(file.as_str(), &profiles) {
+                        Some(m) => Some(m),
+                        None => {
+                            warn!("cannot infer RIB meta information for {}, skipping", file);
+                            None
+                        }
+                    },
+                )
+                .collect();
+
+            if merge {
+                info!("processing {} matching MRT files merged", rib_metas.len());
+                let mut ribeye = match RibEye::new().with_processor_names_and_config(
+                    &processors,
+                    dir.as_str(),
+                    &config,
+                ) {
+                    Ok(p) => timeseries_outputs(output_keys.apply(p), timeseries, dir.as_str())
+                        .with_flush_interval(flush_interval(flush_every))
+                        .with_error_policy(on_error)
+                        .with_output_granularity(granularity)
+                        .with_output_encoding(encoding)
+                        .with_output_selection(output_selection(no_latest, latest_only))
+                        .with_force_latest(force_latest)
+                        .with_optional_processors(&optional)
+                        .with_budget(processing_budget(time_budget, memory_budget, sample_every))
+                        .with_address_family(af)
+                        .with_as_trans_correction(!no_as_trans_correction)
+                        .with_brief_summaries(true),
+                    Err(e) => {
+                        error!("failed to initialize RibEye: {}", e);
+                        exit(2);
+                    }
+                };
+                match ribeye.process_mrt_files(&rib_metas) {
+                    Ok(stats) => info!(
+                        "processed {} MRT files: {}",
+                        rib_metas.len(),
+                        serde_json::to_string(&stats).unwrap_or_default()
+                    ),
+                    Err(e) => {
+                        error!("failed to process MRT files: {}", e);
+                        exit(4);
+                    }
+                }
+                return;
+            }
+
+            let mut jobs = init_thread_pools(parallel, threads);
+            for rib_meta in &rib_metas {
+                jobs.submit(rib_meta.clone());
+            }
+            info!("processing {} matching MRT files", rib_metas.len());
+            let results =
+                jobs.run(|_| {
+                    match RibEye::new().with_processor_names_and_config(
+                        &processors,
+                        dir.as_str(),
+                        &config,
+                    ) {
+                        Ok(p) => Ok(timeseries_outputs(
+                            output_keys.apply(p),
+                            timeseries,
+                            dir.as_str(),
+                        )
+                        .with_flush_interval(flush_interval(flush_every))
+                        .with_error_policy(on_error)
+                        .with_output_granularity(granularity)
+                        .with_output_encoding(encoding)
+                        .with_output_selection(output_selection(no_latest, latest_only))
+                        .with_force_latest(force_latest)
+                        .with_optional_processors(&optional)
+                        .with_budget(processing_budget(time_budget, memory_budget, sample_every))
+                        .with_address_family(af)
+                        .with_as_trans_correction(!no_as_trans_correction)
+                        .with_brief_summaries(true)),
+                        Err(e) => {
+                            error!("failed to initialize RibEye: {}", e);
+                            exit(2);
+                        }
+                    }
+                });
+            log_transfers(&results);
+            log_failed_jobs(&results);
+        }
+        Commands::Live {
+            collector,
+            interval,
+            processors,
+            dir,
+            no_bootstrap,
+        } => {
+            let config =
+                RisLiveConfig::new(collector.as_str(), std::time::Duration::from_secs(interval));
+            let mut ribeye = match RibEye::new().with_processor_names(&processors, dir.as_str()) {
+                Ok(p) => p,
+                Err(e) => {
+                    error!("failed to initialize RibEye: {}", e);
+                    exit(2);
+                }
+            };
+
+            let rib_url = config.latest_rib_url();
+            let rib_meta = RibMeta {
+                project: "riperis".to_string(),
+                collector: collector.clone(),
+                rib_dump_url: rib_url.clone(),
+                timestamp: chrono::Utc::now().naive_utc(),
+                dump_type: dump_type_from_path(rib_url.as_str()),
+                ..Default::default()
+            };
+            ribeye = ribeye.with_rib_meta(&rib_meta);
+
+            let mut table = RouteTable::new();
+            if !no_bootstrap {
+                if let Err(e) = ribeye.load_route_table(rib_url.as_str(), &mut table) {
+                    error!(
+                        collector = collector.as_str(),
+                        phase = "bootstrap",
+                        "failed to bootstrap from {}: {}",
+                        rib_url,
+                        e
+                    );
+                    exit(4);
+                }
+            }
+
+            if let Err(e) = run_ris_live(&mut ribeye, &config, &mut table) {
+                error!(
+                    collector = collector.as_str(),
+                    phase = "live",
+                    "RIS Live streaming failed: {}",
+                    e
+                );
+                exit(5);
+            }
+        }
+        Commands::Bmp {
+            listen,
+            collector,
+            interval,
+            processors,
+            dir,
+        } => {
+            let config = BmpListenerConfig {
+                listen_addr: listen,
+                collector,
+                output_interval: std::time::Duration::from_secs(interval),
+            };
+            let mut ribeye = match RibEye::new().with_processor_names(&processors, dir.as_str()) {
+                Ok(p) => p,
+                Err(e) => {
+                    error!("failed to initialize RibEye: {}", e);
+                    exit(2);
+                }
+            };
+            if let Err(e) = run_bmp_listener(&mut ribeye, &config) {
+                error!("BMP ingestion failed: {}", e);
+                exit(5);
+            }
+        }
+        Commands::Lookup { query, dir } if dir.ends_with(".ribeye") => {
+            let artifact = match Artifact::load(dir.as_str()) {
+                Ok(a) => a,
+                Err(e) => {
+                    error!("failed to load artifact: {}", e);
+                    exit(1);
+                }
+            };
+            let output = match query {
+                LookupQuery::Ip { ip } => serde_json::to_string_pretty(&artifact.lookup_ip(ip)),
+                LookupQuery::Asn { asn } => match artifact.lookup_asn(asn) {
+                    Ok(result) => serde_json::to_string_pretty(&result),
+                    Err(e) => {
+                        error!("failed to look up AS{}: {}", asn, e);
+                        exit(1);
+                    }
+                },
+            };
+            match output {
+                Ok(output) => println!("{}", output),
+                Err(e) => {
+                    error!("failed to serialize lookup result: {}", e);
+                    exit(1);
+                }
+            }
+        }
+        Commands::Lookup { query, dir } => {
+            let data = match LookupData::load(dir.as_str()) {
+                Ok(d) => d,
+                Err(e) => {
+                    error!("failed to load summarized results: {}", e);
+                    exit(1);
+                }
+            };
+            let output = match query {
+                LookupQuery::Ip { ip } => serde_json::to_string_pretty(&data.lookup_ip(ip)),
+                LookupQuery::Asn { asn } => serde_json::to_string_pretty(&data.lookup_asn(asn)),
+            };
+            match output {
+                Ok(output) => println!("{}", output),
+                Err(e) => {
+                    error!("failed to serialize lookup result: {}", e);
+                    exit(1);
+                }
+            }
+        }
+        Commands::Inspect {
+            path,
+            prefix,
+            asn,
+            collector,
+            format,
+        } => {
+            let file = match OutputFile::load(path.as_str()) {
+                Ok(f) => f,
+                Err(e) => {
+                    error!("failed to load {}: {}", path, e);
+                    exit(1);
+                }
+            };
+            let file = file.filter(&RecordFilter {
+                prefix,
+                asn,
+                collector,
+            });
+            let output = match format {
+                InspectFormat::Stats => {
+                    serde_json::to_string_pretty(&file.stats()).map_err(anyhow::Error::from)
+                }
+                InspectFormat::Json => file.to_json_string(),
+                InspectFormat::Jsonl => file.to_jsonl_string(),
+                InspectFormat::Csv => Ok(file.to_csv_string()),
+            };
+            match output {
+                Ok(output) => println!("{}", output),
+                Err(e) => {
+                    error!("failed to format {}: {}", path, e);
+                    exit(1);
+                }
+            }
+        }
+        Commands::Compare { dataset, dir } => match dataset {
+            CompareDataset::As2rel { against, output } => {
+                let summary_path = get_summary_output_path(dir.as_str(), "as2rel");
+                let ribeye_data = match read_as2rel_summary(summary_path.as_str()) {
+                    Ok(d) => d,
+                    Err(e) => {
+                        error!("failed to load {}: {}", summary_path, e);
+                        exit(1);
+                    }
+                };
+                let external = match ExternalAs2rel::load_caida(against.as_str()) {
+                    Ok(d) => d,
+                    Err(e) => {
+                        error!("failed to load {}: {}", against, e);
+                        exit(1);
+                    }
+                };
+
+                let mut result = compare_as2rel(&ribeye_data, &external);
+                if let Some(output_path) = output {
+                    let written = oneio::get_writer(output_path.as_str())
+                        .map_err(anyhow::Error::from)
+                        .and_then(|mut writer| {
+                            serde_json::to_writer_pretty(&mut writer, &result)?;
+                            Ok(writer.flush()?)
+                        });
+                    if let Err(e) = written {
+                        error!("failed to write full comparison to {}: {}", output_path, e);
+                        exit(1);
+                    }
+                    info!("full comparison written to {}", output_path);
+                }
+                info!(
+                    "{} links only observed by ribeye, {} links only in {}",
+                    result.ribeye_only.len(),
+                    result.external_only.len(),
+                    against
+                );
+                result.ribeye_only.clear();
+                result.external_only.clear();
+                match serde_json::to_string_pretty(&result) {
+                    Ok(output) => println!("{}", output),
+                    Err(e) => {
+                        error!("failed to serialize comparison: {}", e);
+                        exit(1);
+                    }
+                }
+            }
+        },
+        Commands::Enrich {
+            peeringdb,
+            processors,
+            dir,
+        } => {
+            let peeringdb = match PeeringDb::load(peeringdb.as_str()) {
+                Ok(d) => d,
+                Err(e) => {
+                    error!("{}", e);
+                    exit(1);
+                }
+            };
+            info!("loaded PeeringDB data of {} networks", peeringdb.nets.len());
+            if let Err(e) = enrich_summaries(dir.as_str(), &processors, &peeringdb) {
+                error!("{}", e);
+                exit(1);
+            }
+        }
+        Commands::ExportBgpkit { processors, dir } => {
+            if let Err(e) = export_bgpkit(dir.as_str(), &processors) {
+                error!("{}", e);
+                exit(1);
+            }
+        }
+        Commands::Keygen {
+            secret_key,
+            public_key,
+            encryption,
+        } => {
+            if let Some(path) = encryption {
+                if std::path::Path::new(path.as_str()).exists() {
+                    error!("encryption key file {} already exists", path);
+                    exit(1);
+                }
+                let written = EncryptionKey::generate()
+                    .and_then(|key| Ok(std::fs::write(path.as_str(), key.to_key_string())?));
+                if let Err(e) = written {
+                    error!("failed to write encryption key: {}", e);
+                    exit(1);
+                }
+                info!("wrote encryption key {}", path);
+                return;
+            }
+            if std::path::Path::new(secret_key.as_str()).exists() {
+                error!("secret key file {} already exists", secret_key);
+                exit(1);
+            }
+            let key = match SigningKey::generate() {
+                Ok(k) => k,
+                Err(e) => {
+                    error!("{}", e);
+                    exit(1);
+                }
+            };
+            let written = std::fs::write(secret_key.as_str(), key.to_secret_key_string())
+                .and_then(|_| std::fs::write(public_key.as_str(), key.public_key().to_string()));
+            if let Err(e) = written {
+                error!("failed to write key files: {}", e);
+                exit(1);
+            }
+            info!(
+                "wrote secret key {} and public key {}",
+                secret_key, public_key
+            );
+        }
+        Commands::Decrypt {
+            file,
+            key,
+            stored_path,
+            output,
+        } => {
+            let key = match EncryptionKey::load(key.as_str()) {
+                Ok(k) => k,
+                Err(e) => {
+                    error!("{}", e);
+                    exit(1);
+                }
+            };
+            let stored_path = stored_path.as_deref().unwrap_or(file.as_str());
+            let content = match decrypt_stored(file.as_str(), stored_path, &key) {
+                Ok(c) => c,
+                Err(e) => {
+                    error!("{}", e);
+                    exit(1);
+                }
+            };
+            let written = match output {
+                Some(path) => oneio::get_writer(path.as_str())
+                    .map_err(anyhow::Error::from)
+                    .and_then(|mut writer| Ok(writer.write_all(&content)?)),
+                None => std::io::stdout()
+                    .write_all(&content)
+                    .map_err(anyhow::Error::from),
+            };
+            if let Err(e) = written {
+                error!("failed to write decrypted output: {}", e);
+                exit(1);
+            }
+        }
+        Commands::Verify { files, public_key } => {
+            let key = match PublicKey::load(public_key.as_str()) {
+                Ok(k) => k,
+                Err(e) => {
+                    error!("{}", e);
+                    exit(1);
+                }
+            };
+            let mut failed = false;
+            for file in files {
+                match verify_file(file.as_str(), None, &key) {
+                    Ok(comment) => println!("{}: OK ({})", file, comment),
+                    Err(e) => {
+                        println!("{}: FAILED ({})", file, e);
+                        failed = true;
+                    }
+                }
+            }
+            if failed {
+                exit(1);
+            }
+        }
+        Commands::VerifyOutputs {
+            dir,
+            public_key,
+            encryption_key,
+            json,
+        } => {
+            let mut audit = OutputAudit::new(dir.as_str());
+            if let Some(path) = public_key {
+                match PublicKey::load(path.as_str()) {
+                    Ok(key) => audit = audit.with_public_key(key),
+                    Err(e) => {
+                        error!("{}", e);
+                        exit(1);
+                    }
+                }
+            }
+            if let Some(path) = encryption_key {
+                match EncryptionKey::load(path.as_str()) {
+                    Ok(key) => {
+                        audit = audit.with_sink(Arc::new(EncryptingSink::new(Arc::new(key))))
+                    }
+                    Err(e) => {
+                        error!("{}", e);
+                        exit(1);
+                    }
+                }
+            }
+            let report = match audit.run() {
+                Ok(r) => r,
+                Err(e) => {
+                    error!("failed to audit {}: {}", dir, e);
+                    exit(1);
+                }
+            };
+            match json {
+                true => match serde_json::to_string_pretty(&report) {
+                    Ok(output) => println!("{}", output),
+                    Err(e) => {
+                        error!("failed to serialize audit report: {}", e);
+                        exit(1);
+                    }
+                },
+                false => {
+                    for issue in &report.issues {
+                        println!("{:<9} {}: {}", issue.check, issue.path, issue.detail);
+                    }
+                    println!(
+                        "{} files audited, {} issues",
+                        report.files,
+                        report.issues.len()
+                    );
+                }
+            }
+            if !report.passed() {
+                exit(1);
+            }
+        }
+        Commands::Backfill {
+            from,
+            to,
+            interval,
+            collectors,
+            processors,
+            parallel,
+            threads,
+            dir,
+            state,
+            bundle,
+            bundle_part_size,
+            broker_url,
+            broker_retries,
+            collector_profiles,
+            max_downloads,
+            download_cache,
+            download_attempts,
+            max_download_bytes,
+            on_error,
+            af,
+            no_as_trans_correction,
+            optional,
+            time_budget,
+            memory_budget,
+            sample_every,
+            granularity,
+            encoding,
+            timeseries,
+            config,
+            label,
+        } => {
+            let config = processor_config(config, None, IxpPolicy::default(), &[], label);
+            let dir = config.labeled_output_dir(dir.as_str());
+            let to = to.unwrap_or_else(|| chrono::Utc::now().date_naive());
+            let state_path =
+                state.unwrap_or_else(|| labeled_state_file("ribeye-backfill-state", &config));
+            let new_state = BackfillState::new(from, to, interval, &collectors, &processors);
+            let state = match BackfillState::load_or(state_path.as_str(), new_state) {
+                Ok(s) => s,
+                Err(e) => {
+                    error!("{}", e);
+                    exit(1);
+                }
+            };
+            check_storage_env(std::iter::once(&dir).chain(config.output_dirs.values()));
+
+            let broker = BrokerConfig::new(broker_url.as_str())
+                .with_retries(broker_retries, std::time::Duration::from_secs(1));
+            let rib_metas = match plan_backfill(
+                &broker,
+                from,
+                to,
+                interval,
+                &collectors,
+                &load_collector_profiles(collector_profiles),
+            ) {
+                Ok(r) => r,
+                Err(e) => {
+                    error!("{}", e);
+                    exit(1);
+                }
+            };
+            let total = rib_metas.len();
+            let remaining = state.remaining(rib_metas);
+            info!(
+                phase = "backfill",
+                "backfilling {} of {} RIB dumps from {} to {} ({}), state in {}",
+                remaining.len(),
+                total,
+                from,
+                to,
+                interval,
+                state_path
+            );
+
+            let io_limits = IoLimits::new(max_downloads, 0.0, 0, 0.0)
+                .with_download_cache(download_cache, download_attempts)
+                .with_download_cap(max_download_bytes);
+            let bundle = run_bundle(bundle, bundle_part_size, dir.as_str());
+            let state = Arc::new(std::sync::Mutex::new(state));
+            let progress = state.clone();
+            let mut jobs =
+                init_thread_pools(parallel, threads).with_status_callback(move |job, status| {
+                    let url = job.rib_meta().rib_dump_url.as_str();
+                    let mut state = progress.lock().unwrap();
+                    match status {
+                        JobStatus::Done => state.mark_done(url),
+                        JobStatus::Failed(e) => {
+                            error!(
+                                collector = job.rib_meta().collector.as_str(),
+                                phase = "backfill",
+                                "failed to process {}: {}",
+                                url,
+                                e
+                            );
+                            state.mark_failed(url, e.as_str());
+                        }
+                        _ => return,
+                    }
+                    if let Err(e) = state.save(state_path.as_str()) {
+                        error!("failed to save backfill state {}: {}", state_path, e);
+                    }
+                    info!(
+                        phase = "backfill",
+                        "progress: {} of {} RIB dumps done, {} failed",
+                        state.done.len(),
+                        total,
+                        state.failed.len()
+                    );
+                });
+            for rib_meta in remaining {
+                jobs.submit(rib_meta);
+            }
+            let results = jobs.run(|_| {
+                RibEye::new()
+                    .with_processor_names_and_config(&processors, dir.as_str(), &config)
+                    .map(|p| {
+                        // backfilled RIBs must not replace the current latest outputs
+                        bundle_outputs(
+                            io_limits.apply(timeseries_outputs(p, timeseries, dir.as_str())),
+                            &bundle,
+                        )
+                        .with_latest_outputs(false)
+                        .with_error_policy(on_error)
+                        .with_output_granularity(granularity)
+                        .with_output_encoding(encoding)
+                        .with_optional_processors(&optional)
+                        .with_budget(processing_budget(time_budget, memory_budget, sample_every))
+                        .with_address_family(af)
+                        .with_as_trans_correction(!no_as_trans_correction)
+                        .with_brief_summaries(true)
+                    })
+            });
+            log_transfers(&results);
+            finish_bundle(&bundle);
+
+            let state = state.lock().unwrap();
+            if !state.failed.is_empty() {
+                error!(
+                    "{} RIB dumps failed, rerun the same command to retry them",
+                    state.failed.len()
+                );
+                exit(4);
+            }
+        }
+        Commands::Schema {
+            processor,
+            output,
+            summary,
+        } => {
+            let schemas = match processor_schemas(processor.as_str()) {
+                Some(s) => s,
+                None => {
+                    error!("unknown processor: {}", processor);
+                    exit(1);
+                }
+            };
+            let value = match (output, summary) {
+                (true, _) => serde_json::to_value(&schemas.output),
+                (_, true) => serde_json::to_value(&schemas.summary),
+                _ => serde_json::to_value(&schemas),
+            };
+            match value.and_then(|v| serde_json::to_string_pretty(&v)) {
+                Ok(output) => println!("{}", output),
+                Err(e) => {
+                    error!("failed to serialize schema of {}: {}", processor, e);
+                    exit(1);
+                }
+            }
+        }
+        Commands::Report {
+            dir,
+            output,
+            title,
+            top_n,
+            encryption_key,
+        } => {
+            let mut dashboard = Dashboard::new(dir.as_str()).with_top_n(top_n);
+            if let Some(title) = title {
+                dashboard = dashboard.with_title(title.as_str());
+            }
+            if let Some(path) = encryption_key {
+                match EncryptionKey::load(path.as_str()) {
+                    Ok(key) => {
+                        dashboard =
+                            dashboard.with_sink(Arc::new(EncryptingSink::new(Arc::new(key))))
+                    }
+                    Err(e) => {
+                        error!("{}", e);
+                        exit(1);
+                    }
+                }
+            }
+            let output = output.unwrap_or_else(|| get_dashboard_path(dir.as_str()));
+            if let Err(e) = dashboard
+                .render()
+                .and_then(|html| FileSink.write_bytes(output.as_str(), html.as_bytes()))
+            {
+                error!("failed to write report of {}: {}", dir, e);
+                exit(1);
+            }
+            info!("wrote report to {}", output);
+        }
+        Commands::Index { dir } => {
+            if let Err(e) = write_output_index(dir.as_str()) {
+                error!("{}", e);
+                exit(1);
+            }
+        }
+        Commands::Selftest { mrt, storage } => {
+            let mut selftest = Selftest::new();
+            if let Some(mrt) = mrt {
+                selftest = selftest.with_mrt_file(mrt.as_str());
+            }
+            if let Some(storage) = storage {
+                selftest = selftest.with_storage_dir(storage.as_str());
+            }
+            let report = match selftest.run() {
+                Ok(r) => r,
+                Err(e) => {
+                    error!("self-test failed: {}", e);
+                    exit(1);
+                }
+            };
+            for check in &report.checks {
+                println!(
+                    "{:<4} {:<24} {:>6}ms  {}",
+                    if check.passed { "ok" } else { "FAIL" },
+                    check.name,
+                    check.duration_ms,
+                    check.detail
+                );
+            }
+            if !report.passed() {
+                exit(1);
+            }
+        }
+        Commands::List {
+            dir,
+            processor,
+            collector,
+        } => {
+            let outputs = match list_outputs(dir.as_str()) {
+                Ok(outputs) => outputs,
+                Err(e) => {
+                    error!("failed to list {}: {}", dir, e);
+                    exit(1);
+                }
+            };
+            let outputs: Vec<ListedOutput> = outputs
+                .into_iter()
+                .filter(|o| processor.as_ref().is_none_or(|p| *p == o.processor))
+                .filter(|o| collector.as_ref().is_none_or(|c| *c == o.collector))
+                .collect();
+            let listings = collector_listings(&outputs);
+            match serde_json::to_string_pretty(&listings) {
+                Ok(output) => println!("{}", output),
+                Err(e) => {
+                    error!("failed to serialize output listing: {}", e);
+                    exit(1);
+                }
+            }
+        }
+        Commands::Restore { date, dir, output } => {
+            let archive = DeltaArchive::new(get_archive_dir(dir.as_str()).as_str());
+            let (archived, summary) = match archive.reconstruct(&FileSink, date) {
+                Ok(s) => s,
+                Err(e) => {
+                    error!("failed to reconstruct {}: {}", date, e);
+                    exit(1);
+                }
+            };
+            if archived != date {
+                info!("{} is not archived, using {}", date, archived);
+            }
+            let written = serde_json::to_value(&summary)
+                .map_err(anyhow::Error::from)
+                .and_then(|value| match &output {
+                    Some(path) => FileSink.write_json(path.as_str(), &value),
+                    None => {
+                        println!("{}", serde_json::to_string_pretty(&value)?);
+                        Ok(())
+                    }
+                });
+            if let Err(e) = written {
+                error!("failed to write reconstructed summary: {}", e);
+                exit(1);
+            }
+        }
+        Commands::Prune {
+            dir,
+            keep_days,
+            dry_run,
+        } => {
+            let before =
+                (chrono::Utc::now() - chrono::Duration::days(keep_days as i64)).timestamp();
+            match prune_outputs(dir.as_str(), before, dry_run) {
+                Ok(expired) => {
+                    if dry_run {
+                        for output in expired {
+                            println!("{}", output.path);
+                        }
+                    }
+                }
+                Err(e) => {
+                    error!("failed to prune {}: {}", dir, e);
+                    exit(1);
+                }
+            }
+        }
+        Commands::Hijacks { vrps, dir } => {
+            let vrps = vrps.map(|path| load_vrps(path.as_str()));
+            if let Err(e) = analyze_hijacks(dir.as_str(), vrps.as_ref()) {
+                error!("{}", e);
+                exit(1);
+            }
         }
     }
 }
@@ -0,0 +1,198 @@
+//! Collector profiles: project detection, archive layouts and dump cadences.
+//!
+//! A [CollectorProfile] describes the collectors of one project: the patterns of their names, the
+//! hosts of their archives and the hour of the RIB dump processed as the daily one. A set of
+//! [CollectorProfiles] labels RIB dumps with their project, from the broker's collector name or
+//! the dump URL, and infers the collector of MRT file paths in non-standard archive layouts, so
+//! that their outputs land under the collector's name rather than `unknown`.
+//!
+//! The built-in profiles cover RIPE RIS, RouteViews (including its kernel and extended collectors
+//! such as `route-views.kixp`), PCH and Isolario. Additional profiles are loaded from a JSON list,
+//! e.g. with `cook --collector-profiles`, and take precedence over the built-in ones:
+//!
+//! ```json
+//! [{"project": "pch", "collectors": ["route-collector.*.pch.net"], "daily_dump_hour": 2}]
+//! ```
+
+use anyhow::{anyhow, Result};
+use chrono::{NaiveDateTime, Timelike};
+use serde::{Deserialize, Serialize};
+use std::sync::OnceLock;
+
+/// Project of RIB dumps matching no profile.
+pub const UNKNOWN_PROJECT: &str = "unknown";
+
+/// Collectors of one project.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CollectorProfile {
+    /// project name, e.g. `riperis`
+    pub project: String,
+    /// collector name patterns, where `*` matches any characters and `?` a single digit, e.g.
+    /// `rrc??`
+    pub collectors: Vec<String>,
+    /// hosts of the project's archives, identifying dumps of collectors matching no pattern
+    #[serde(default)]
+    pub archive_hosts: Vec<String>,
+    /// hour (UTC) of the RIB dump processed as the daily dump
+    #[serde(default)]
+    pub daily_dump_hour: u32,
+}
+
+impl CollectorProfile {
+    pub fn new(project: &str, collectors: &[&str]) -> Self {
+        CollectorProfile {
+            project: project.to_string(),
+            collectors: collectors.iter().map(|c| c.to_string()).collect(),
+            archive_hosts: vec![],
+            daily_dump_hour: 0,
+        }
+    }
+
+    pub fn with_archive_hosts(mut self, hosts: &[&str]) -> Self {
+        self.archive_hosts = hosts.iter().map(|h| h.to_string()).collect();
+        self
+    }
+
+    pub fn with_daily_dump_hour(mut self, hour: u32) -> Self {
+        self.daily_dump_hour = hour;
+        self
+    }
+
+    /// Whether a collector name matches one of the profile's patterns.
+    pub fn matches_collector(&self, collector: &str) -> bool {
+        self.collectors
+            .iter()
+            .any(|pattern| matches_pattern(pattern.as_bytes(), collector.as_bytes()))
+    }
+
+    /// Whether a URL points to one of the profile's archive hosts.
+    pub fn matches_url(&self, url: &str) -> bool {
+        let host = url
+            .split_once("://")
+            .map(|(_, rest)| rest.split('/').next().unwrap_or_default())
+            .unwrap_or_default();
+        !host.is_empty()
+            && self
+                .archive_hosts
+                .iter()
+                .any(|h| host == h || host.ends_with(format!(".{}", h).as_str()))
+    }
+
+    /// Whether a dump taken at `timestamp` is the collector's daily dump.
+    pub fn is_daily_dump(&self, timestamp: &NaiveDateTime) -> bool {
+        timestamp.hour() == self.daily_dump_hour
+    }
+}
+
+/// Match a name against a pattern where `*` matches any characters and `?` a single digit.
+fn matches_pattern(pattern: &[u8], name: &[u8]) -> bool {
+    match (pattern.first(), name.first()) {
+        (None, None) => true,
+        (Some(b'*'), _) => {
+            matches_pattern(&pattern[1..], name)
+                || (!name.is_empty() && matches_pattern(pattern, &name[1..]))
+        }
+        (Some(b'?'), Some(c)) if c.is_ascii_digit() => matches_pattern(&pattern[1..], &name[1..]),
+        (Some(p), Some(c)) if p == c => matches_pattern(&pattern[1..], &name[1..]),
+        _ => false,
+    }
+}
+
+/// Ordered set of collector profiles, the first matching profile applies.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CollectorProfiles {
+    profiles: Vec<CollectorProfile>,
+}
+
+impl Default for CollectorProfiles {
+    fn default() -> Self {
+        CollectorProfiles {
+            profiles: vec![
+                CollectorProfile::new("riperis", &["rrc??"])
+                    .with_archive_hosts(&["data.ris.ripe.net"]),
+                CollectorProfile::new("route-views", &["route-views*"])
+                    .with_archive_hosts(&["routeviews.org"]),
+                CollectorProfile::new("pch", &["route-collector.*.pch.net", "pch-*"])
+                    .with_archive_hosts(&["pch.net"]),
+                CollectorProfile::new(
+                    "isolario",
+                    &["Alderaan", "Dagobah", "Korriban", "Naboo", "Taris"],
+                )
+                .with_archive_hosts(&["isolario.it"]),
+            ],
+        }
+    }
+}
+
+impl CollectorProfiles {
+    /// The built-in profiles.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Shared instance of the built-in profiles.
+    pub fn builtin() -> &'static CollectorProfiles {
+        static BUILTIN: OnceLock<CollectorProfiles> = OnceLock::new();
+        BUILTIN.get_or_init(CollectorProfiles::default)
+    }
+
+    /// Add a profile taking precedence over the existing ones.
+    pub fn with_profile(mut self, profile: CollectorProfile) -> Self {
+        self.profiles.insert(0, profile);
+        self
+    }
+
+    /// Add the profiles of a JSON list, taking precedence over the existing ones in their order.
+    pub fn with_json_str(mut self, content: &str) -> Result<Self> {
+        let profiles: Vec<CollectorProfile> = serde_json::from_str(content)
+            .map_err(|e| anyhow!("invalid collector profiles: {}", e))?;
+        self.profiles.splice(0..0, profiles);
+        Ok(self)
+    }
+
+    /// The built-in profiles extended with those of a JSON file.
+    pub fn load(path: &str) -> Result<Self> {
+        let content = oneio::read_to_string(path)
+            .map_err(|e| anyhow!("failed to read collector profiles {}: {}", path, e))?;
+        Self::new().with_json_str(content.as_str())
+    }
+
+    pub fn profiles(&self) -> &[CollectorProfile] {
+        &self.profiles
+    }
+
+    /// Profile of a collector, by name or else by the archive host of its dump URL.
+    pub fn profile(&self, collector: &str, url: &str) -> Option<&CollectorProfile> {
+        self.profiles
+            .iter()
+            .find(|p| p.matches_collector(collector))
+            .or_else(|| self.profiles.iter().find(|p| p.matches_url(url)))
+    }
+
+    /// Project of a collector, [UNKNOWN_PROJECT] if no profile matches.
+    pub fn project(&self, collector: &str, url: &str) -> String {
+        self.profile(collector, url)
+            .map(|p| p.project.clone())
+            .unwrap_or_else(|| UNKNOWN_PROJECT.to_string())
+    }
+
+    /// Project and collector of an MRT file path, from the last path segment naming a known
+    /// collector.
+    pub fn collector_from_path(&self, path: &str) -> Option<(String, String)> {
+        path.split('/').rev().skip(1).find_map(|segment| {
+            self.profiles
+                .iter()
+                .find(|p| p.matches_collector(segment))
+                .map(|p| (p.project.clone(), segment.to_string()))
+        })
+    }
+
+    /// Whether a dump of a collector taken at `timestamp` is its daily dump, by default the
+    /// midnight dump.
+    pub fn is_daily_dump(&self, collector: &str, url: &str, timestamp: &NaiveDateTime) -> bool {
+        match self.profile(collector, url) {
+            Some(profile) => profile.is_daily_dump(timestamp),
+            None => timestamp.hour() == 0,
+        }
+    }
+}
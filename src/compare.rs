@@ -0,0 +1,161 @@
+//! Comparison of ribeye outputs against external datasets.
+//!
+//! [compare_as2rel] cross-validates the summarized `as2rel` output against an AS relationship
+//! dataset in the CAIDA `as-rel` format (`<provider>|<customer>|-1` and `<peer>|<peer>|0` lines,
+//! `#` comments), which is also used by other inference projects such as ProbLink and TopoScope.
+//!
+//! ribeye records every observed AS adjacency and infers provider-to-customer relationships on
+//! paths through Tier-1 networks, so the comparison reports both link-level agreement and, for
+//! links with a ribeye provider-to-customer inference, relationship-level agreement.
+
+use crate::processors::As2relSummaryJson;
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::io::BufRead;
+
+/// Relationship of a link in an external dataset, relative to the link's (lower, higher) ASNs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ExternalRel {
+    /// the first ASN is the provider of the second
+    ProviderToCustomer,
+    /// the first ASN is the customer of the second
+    CustomerToProvider,
+    /// the ASNs are peers
+    PeerToPeer,
+}
+
+/// An external AS relationship dataset keyed by undirected link `(min_asn, max_asn)`.
+#[derive(Debug, Default, Clone)]
+pub struct ExternalAs2rel {
+    pub links: HashMap<(u32, u32), ExternalRel>,
+}
+
+impl ExternalAs2rel {
+    /// Load a CAIDA `as-rel` formatted file, local or remote, with any compression supported by
+    /// `oneio`.
+    pub fn load_caida(path: &str) -> Result<Self> {
+        let reader = oneio::get_reader(path)?;
+        let mut data = ExternalAs2rel::default();
+        for line in std::io::BufReader::new(reader).lines() {
+            let line = line?;
+            if line.starts_with('#') || line.trim().is_empty() {
+                continue;
+            }
+            let fields: Vec<&str> = line.split('|').collect();
+            if fields.len() < 3 {
+                return Err(anyhow!("invalid as-rel line: {}", line));
+            }
+            let asn1 = fields[0].parse::<u32>()?;
+            let asn2 = fields[1].parse::<u32>()?;
+            let rel = match fields[2] {
+                "-1" => ExternalRel::ProviderToCustomer,
+                "0" => ExternalRel::PeerToPeer,
+                r => return Err(anyhow!("unknown relationship type {} in: {}", r, line)),
+            };
+            data.insert(asn1, asn2, rel);
+        }
+        Ok(data)
+    }
+
+    /// Insert a link, normalizing it to `(min_asn, max_asn)` order.
+    pub fn insert(&mut self, asn1: u32, asn2: u32, rel: ExternalRel) {
+        let (key, rel) = match asn1 <= asn2 {
+            true => ((asn1, asn2), rel),
+            false => (
+                (asn2, asn1),
+                match rel {
+                    ExternalRel::ProviderToCustomer => ExternalRel::CustomerToProvider,
+                    ExternalRel::CustomerToProvider => ExternalRel::ProviderToCustomer,
+                    ExternalRel::PeerToPeer => ExternalRel::PeerToPeer,
+                },
+            ),
+        };
+        self.links.insert(key, rel);
+    }
+}
+
+/// Result of comparing ribeye's as2rel against an external dataset.
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct As2relComparison {
+    /// number of undirected links observed by ribeye
+    pub ribeye_links: usize,
+    /// number of undirected links in the external dataset
+    pub external_links: usize,
+    /// number of links present in both datasets
+    pub common_links: usize,
+    /// ribeye provider-to-customer inferences on common links
+    pub p2c_compared: usize,
+    /// ... where the external dataset agrees
+    pub p2c_agree: usize,
+    /// ... where the external dataset has the opposite direction
+    pub p2c_reversed: usize,
+    /// ... where the external dataset has a peering link
+    pub p2c_as_peering: usize,
+    /// links only observed by ribeye
+    #[serde(default, skip_serializing_if = "BTreeSet::is_empty")]
+    pub ribeye_only: BTreeSet<(u32, u32)>,
+    /// links only present in the external dataset
+    #[serde(default, skip_serializing_if = "BTreeSet::is_empty")]
+    pub external_only: BTreeSet<(u32, u32)>,
+}
+
+fn link_key(asn1: u32, asn2: u32) -> (u32, u32) {
+    (asn1.min(asn2), asn1.max(asn2))
+}
+
+/// Compare a summarized as2rel output against an external dataset.
+pub fn compare_as2rel(ribeye: &As2relSummaryJson, external: &ExternalAs2rel) -> As2relComparison {
+    let mut links = HashSet::new();
+    // provider-to-customer inferences, keyed by undirected link and valued with the provider
+    let mut providers: HashMap<(u32, u32), u32> = HashMap::new();
+    for entry in &ribeye.as2rel {
+        if entry.asn1 == entry.asn2 {
+            continue;
+        }
+        let key = link_key(entry.asn1, entry.asn2);
+        links.insert(key);
+        if entry.rel == 1 {
+            providers.insert(key, entry.asn1);
+        }
+    }
+
+    let mut result = As2relComparison {
+        ribeye_links: links.len(),
+        external_links: external.links.len(),
+        ..Default::default()
+    };
+
+    for key in &links {
+        match external.links.get(key) {
+            None => {
+                result.ribeye_only.insert(*key);
+            }
+            Some(ext_rel) => {
+                result.common_links += 1;
+                if let Some(provider) = providers.get(key) {
+                    result.p2c_compared += 1;
+                    let ext_provider = match ext_rel {
+                        ExternalRel::ProviderToCustomer => Some(key.0),
+                        ExternalRel::CustomerToProvider => Some(key.1),
+                        ExternalRel::PeerToPeer => None,
+                    };
+                    match ext_provider {
+                        Some(p) if p == *provider => result.p2c_agree += 1,
+                        Some(_) => result.p2c_reversed += 1,
+                        None => result.p2c_as_peering += 1,
+                    }
+                }
+            }
+        }
+    }
+
+    result.external_only = external
+        .links
+        .keys()
+        .filter(|k| !links.contains(*k))
+        .cloned()
+        .collect();
+
+    result
+}
@@ -0,0 +1,83 @@
+//! Country-level aggregation of AS relationships.
+//!
+//! [country_matrix] maps the ASes of a summarized `as2rel` output to their registration countries
+//! (from [AsnInfoData]) and counts the AS adjacencies and inferred provider-to-customer links
+//! between each pair of countries, including adjacencies within a country. ASes without a known
+//! country are left out.
+
+use crate::enrich::AsnInfoData;
+use crate::processors::As2relSummaryJson;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashSet};
+
+/// AS adjacencies between two countries, with `country1 <= country2`.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CountryLinkEntry {
+    pub country1: String,
+    pub country2: String,
+    /// number of distinct AS adjacencies between the countries
+    pub as_links: usize,
+    /// number of AS links where the AS in `country1` is the inferred provider; all inferred
+    /// provider-to-customer links within a country are counted here
+    pub p2c_links: usize,
+    /// number of AS links where the AS in `country1` is the inferred customer
+    pub c2p_links: usize,
+    /// number of paths the adjacencies were observed on
+    pub paths_count: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CountryMatrixJson {
+    pub rib_dump_urls: Vec<String>,
+    pub countries: Vec<CountryLinkEntry>,
+}
+
+/// Path of the country matrix under an output root directory, next to the as2rel summary.
+pub fn get_country_matrix_path(output_dir: &str) -> String {
+    format!("{}/as2rel/latest-country.json.bz2", output_dir)
+}
+
+/// Aggregate a summarized `as2rel` output into a country-to-country matrix.
+pub fn country_matrix(as2rel: &As2relSummaryJson, asn_info: &AsnInfoData) -> CountryMatrixJson {
+    let country = |asn: u32| -> Option<&str> {
+        asn_info
+            .asns
+            .get(&asn)
+            .map(|info| info.country.as_str())
+            .filter(|c| !c.is_empty())
+    };
+
+    let mut entries: BTreeMap<(&str, &str), CountryLinkEntry> = BTreeMap::new();
+    // adjacencies are recorded in both path directions, count each one once
+    let mut links: HashSet<(u32, u32)> = HashSet::new();
+    for entry in &as2rel.as2rel {
+        if entry.asn1 == entry.asn2 {
+            continue;
+        }
+        let (Some(c1), Some(c2)) = (country(entry.asn1), country(entry.asn2)) else {
+            continue;
+        };
+        let key = (c1.min(c2), c1.max(c2));
+        let matrix_entry = entries.entry(key).or_insert_with(|| CountryLinkEntry {
+            country1: key.0.to_string(),
+            country2: key.1.to_string(),
+            ..Default::default()
+        });
+        match entry.rel {
+            1 if c1 == key.0 => matrix_entry.p2c_links += 1,
+            1 => matrix_entry.c2p_links += 1,
+            _ => {
+                let link = (entry.asn1.min(entry.asn2), entry.asn1.max(entry.asn2));
+                if links.insert(link) {
+                    matrix_entry.as_links += 1;
+                }
+                matrix_entry.paths_count += entry.paths_count;
+            }
+        }
+    }
+
+    CountryMatrixJson {
+        rib_dump_urls: as2rel.rib_dump_urls.clone(),
+        countries: entries.into_values().collect(),
+    }
+}
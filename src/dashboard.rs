@@ -0,0 +1,480 @@
+//! Static HTML report of the summaries of an output root directory.
+//!
+//! [Dashboard::render] reads the latest summaries and renders a self-contained HTML page, with
+//! tables and inline SVG bar charts, that can be published next to the data outputs:
+//!
+//! - collectors: the summarized RIB dump of each collector and whether it is stale
+//! - peers: peers, full-feed peers and peer ASNs per collector, from `peer-stats`
+//! - top origins: the largest origins by address space, from `pfx2as` (see [top](crate::top))
+//! - relationships: AS adjacencies and provider-to-customer links, and the busiest links, from
+//!   `as2rel`
+//! - churn: prefix-origin entries added, removed and changed per day, from the pfx2as archive
+//!   written with [RibEye::with_delta_archive](crate::RibEye::with_delta_archive), and peers
+//!   lost and gained per collector, from the collector health report
+//!
+//! Sections without their summary are left out.
+
+use crate::archive::{get_archive_dir, DeltaArchive, Pfx2AsDelta, SnapshotKind};
+use crate::health::{get_health_report_path, CollectorHealthJson};
+use crate::processors::{
+    get_summary_output_path, As2relSummaryJson, CollectorDump, FileSink, OutputSink,
+    PeerInfoSummaryJson, COLLECTOR_DUMPS_FIELD,
+};
+use crate::top::{top_views, DEFAULT_TOP_N};
+use crate::util::format_count;
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+use serde_json::Value;
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+use std::fmt::Write;
+use std::sync::Arc;
+use tracing::info;
+
+/// File name of the report under an output root directory.
+pub const DASHBOARD_FILE_NAME: &str = "report.html";
+/// Number of most recent archived days shown in the churn section.
+pub const DEFAULT_CHURN_DAYS: usize = 30;
+
+const STYLE: &str = "body{font-family:sans-serif;margin:2em auto;max-width:60em;color:#222}\
+h1{font-size:1.6em}h2{font-size:1.25em;margin-top:2em;border-bottom:1px solid #ccc}\
+table{border-collapse:collapse;margin:1em 0}th,td{padding:.2em .8em;text-align:right}\
+th:first-child,td:first-child{text-align:left}tr:nth-child(even){background:#f4f4f4}\
+.note{color:#666}svg text{font-size:12px}";
+
+/// Default path of the report under an output root directory, `<output_dir>/report.html`.
+pub fn get_dashboard_path(output_dir: &str) -> String {
+    format!(
+        "{}/{}",
+        output_dir.trim_end_matches('/'),
+        DASHBOARD_FILE_NAME
+    )
+}
+
+/// HTML report of the summaries under an output root directory, see the [module
+/// documentation](self).
+pub struct Dashboard {
+    output_dir: String,
+    sink: Arc<dyn OutputSink>,
+    top_n: usize,
+    churn_days: usize,
+    title: String,
+}
+
+impl Dashboard {
+    pub fn new(output_dir: &str) -> Self {
+        Dashboard {
+            output_dir: output_dir.trim_end_matches('/').to_string(),
+            sink: Arc::new(FileSink),
+            top_n: DEFAULT_TOP_N,
+            churn_days: DEFAULT_CHURN_DAYS,
+            title: "ribeye report".to_string(),
+        }
+    }
+
+    /// Read summaries with the given sink, e.g. to decrypt them.
+    pub fn with_sink(mut self, sink: Arc<dyn OutputSink>) -> Self {
+        self.sink = sink;
+        self
+    }
+
+    /// Number of entries of the top origin and busiest link tables.
+    pub fn with_top_n(mut self, top_n: usize) -> Self {
+        self.top_n = top_n;
+        self
+    }
+
+    /// Number of most recent archived days shown in the churn section.
+    pub fn with_churn_days(mut self, churn_days: usize) -> Self {
+        self.churn_days = churn_days;
+        self
+    }
+
+    pub fn with_title(mut self, title: &str) -> Self {
+        self.title = title.to_string();
+        self
+    }
+
+    /// Render the report. Fails if there is no peer-stats, pfx2as or as2rel summary.
+    pub fn render(&self) -> Result<String> {
+        let peer_stats = self.read_summary("peer-stats");
+        let pfx2as = self.read_summary("pfx2as");
+        let as2rel = self.read_summary("as2rel");
+        if peer_stats.is_none() && pfx2as.is_none() && as2rel.is_none() {
+            return Err(anyhow!(
+                "no peer-stats, pfx2as or as2rel summary under {}",
+                self.output_dir
+            ));
+        }
+
+        let mut body = String::new();
+        let dumps = [&peer_stats, &pfx2as, &as2rel]
+            .into_iter()
+            .flatten()
+            .find_map(|s| Vec::<CollectorDump>::deserialize(s.get(COLLECTOR_DUMPS_FIELD)?).ok());
+        if let Some(dumps) = dumps {
+            body.push_str(collectors_section(&dumps).as_str());
+        }
+        if let Some(summary) = &peer_stats {
+            body.push_str(peers_section(&PeerInfoSummaryJson::deserialize(summary)?).as_str());
+        }
+        if let Some(summary) = &pfx2as {
+            body.push_str(origins_section(summary, self.top_n)?.as_str());
+        }
+        if let Some(summary) = &as2rel {
+            body.push_str(relationships_section(summary, self.top_n)?.as_str());
+        }
+        body.push_str(self.churn_section()?.as_str());
+
+        let mut html = String::new();
+        writeln!(html, "<!DOCTYPE html>")?;
+        writeln!(html, "<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">")?;
+        writeln!(html, "<title>{}</title>", escape(self.title.as_str()))?;
+        writeln!(html, "<style>{}</style>\n</head>\n<body>", STYLE)?;
+        writeln!(html, "<h1>{}</h1>", escape(self.title.as_str()))?;
+        writeln!(
+            html,
+            "<p class=\"note\">Generated {} from {}</p>",
+            Utc::now().format("%Y-%m-%d %H:%M UTC"),
+            escape(self.output_dir.as_str())
+        )?;
+        html.push_str(body.as_str());
+        writeln!(html, "</body>\n</html>")?;
+        info!(phase = "report", "rendered report of {}", self.output_dir);
+        Ok(html)
+    }
+
+    fn read_summary(&self, processor: &str) -> Option<Value> {
+        let path = get_summary_output_path(self.output_dir.as_str(), processor);
+        self.sink.read_json(path.as_str()).ok()
+    }
+
+    fn churn_section(&self) -> Result<String> {
+        let mut html = String::new();
+        let archive = DeltaArchive::new(get_archive_dir(self.output_dir.as_str()).as_str());
+        // an archive without an index has no snapshots
+        let index = archive.index(self.sink.as_ref()).unwrap_or_default();
+        let deltas: Vec<_> = index
+            .snapshots
+            .iter()
+            .filter(|s| s.kind == SnapshotKind::Delta)
+            .collect();
+        let mut rows = vec![];
+        for snapshot in &deltas[deltas.len().saturating_sub(self.churn_days)..] {
+            let path = format!(
+                "{}/{}",
+                get_archive_dir(self.output_dir.as_str()),
+                snapshot.file
+            );
+            let delta = Pfx2AsDelta::deserialize(self.sink.read_json(path.as_str())?)?;
+            rows.push((snapshot.date, snapshot.entries, delta));
+        }
+        if !rows.is_empty() {
+            html.push_str("<h3>Prefix-origin changes per day</h3>\n");
+            html.push_str(
+                bar_chart(
+                    &rows
+                        .iter()
+                        .map(|(date, _, d)| (date.to_string(), d.len() as f64))
+                        .collect::<Vec<_>>(),
+                )
+                .as_str(),
+            );
+            html.push_str(
+                table(
+                    &["Date", "Entries", "Added", "Removed", "Changed"],
+                    rows.iter()
+                        .rev()
+                        .map(|(date, entries, d)| {
+                            vec![
+                                date.to_string(),
+                                entries.to_string(),
+                                d.added.len().to_string(),
+                                d.removed.len().to_string(),
+                                d.changed.len().to_string(),
+                            ]
+                        })
+                        .collect(),
+                )
+                .as_str(),
+            );
+        }
+
+        let health_path = get_health_report_path(self.output_dir.as_str());
+        if let Ok(health) = self
+            .sink
+            .read_json(health_path.as_str())
+            .and_then(|v| Ok(CollectorHealthJson::deserialize(v)?))
+        {
+            html.push_str("<h3>Peers lost and gained since the previous summary</h3>\n");
+            html.push_str(
+                table(
+                    &["Collector", "Peers", "Previous", "Lost", "Gained"],
+                    health
+                        .collectors
+                        .iter()
+                        .map(|c| {
+                            let collector = match c.dropped {
+                                true => format!("{} (dropped)", c.collector),
+                                false => c.collector.clone(),
+                            };
+                            vec![
+                                collector,
+                                c.peers_count.to_string(),
+                                c.previous_peers_count
+                                    .map(|p| p.to_string())
+                                    .unwrap_or_default(),
+                                c.peers_lost.to_string(),
+                                c.peers_gained.to_string(),
+                            ]
+                        })
+                        .collect(),
+                )
+                .as_str(),
+            );
+        }
+
+        if html.is_empty() {
+            html.push_str(
+                "<p class=\"note\">No churn data, see <code>cook --delta-archive</code> and \
+                 <code>cook --health-report</code>.</p>\n",
+            );
+        }
+        Ok(format!("<h2>Churn</h2>\n{}", html))
+    }
+}
+
+fn collectors_section(dumps: &[CollectorDump]) -> String {
+    let rows = dumps
+        .iter()
+        .map(|d| {
+            let dump = DateTime::from_timestamp(d.rib_timestamp, 0)
+                .unwrap_or_default()
+                .format("%Y-%m-%d %H:%M")
+                .to_string();
+            vec![
+                d.collector.clone(),
+                d.project.clone(),
+                match d.stale {
+                    true => format!("{} (stale)", dump),
+                    false => dump,
+                },
+            ]
+        })
+        .collect();
+    format!(
+        "<h2>Collectors</h2>\n{}",
+        table(&["Collector", "Project", "RIB dump (UTC)"], rows)
+    )
+}
+
+fn peers_section(peer_stats: &PeerInfoSummaryJson) -> String {
+    // peers, full-feed peers and peer ASNs per collector
+    let mut collectors: BTreeMap<String, (usize, usize, BTreeSet<u32>)> = BTreeMap::new();
+    for peer in &peer_stats.peers {
+        let collector = peer.collector.clone().unwrap_or_default();
+        let entry = collectors.entry(collector).or_default();
+        entry.0 += 1;
+        entry.1 += peer.full_feed as usize;
+        entry.2.insert(peer.asn);
+    }
+    let asns: HashSet<u32> = peer_stats.peers.iter().map(|p| p.asn).collect();
+    let bars: Vec<(String, f64)> = collectors
+        .iter()
+        .map(|(c, (peers, _, _))| (c.clone(), *peers as f64))
+        .collect();
+    let rows = collectors
+        .iter()
+        .map(|(c, (peers, full_feed, asns))| {
+            vec![
+                c.clone(),
+                peers.to_string(),
+                full_feed.to_string(),
+                asns.len().to_string(),
+            ]
+        })
+        .collect();
+    format!(
+        "<h2>Peers</h2>\n<p>{} peers of {} ASes at {} collectors</p>\n{}{}",
+        peer_stats.peers.len(),
+        asns.len(),
+        collectors.len(),
+        bar_chart(&bars),
+        table(&["Collector", "Peers", "Full-feed", "Peer ASNs"], rows)
+    )
+}
+
+fn origins_section(summary: &Value, top_n: usize) -> Result<String> {
+    let views = top_views("pfx2as", summary, top_n)?.unwrap_or_default();
+    let pairs = summary["pfx2as"].as_array().map_or(0, |a| a.len());
+    let origins: HashSet<u64> = summary["pfx2as"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|e| e["asn"].as_u64())
+        .collect();
+    let mut html = format!(
+        "<h2>Top origins</h2>\n<p>{} prefix-origin pairs of {} origins</p>\n",
+        pairs,
+        origins.len()
+    );
+    for (family, unit, origins) in [
+        ("IPv4", "/24s", &views.largest_origins_v4),
+        ("IPv6", "/48s", &views.largest_origins_v6),
+    ] {
+        if origins.is_empty() {
+            continue;
+        }
+        writeln!(html, "<h3>Largest {} origins by address space</h3>", family)?;
+        html.push_str(
+            bar_chart(
+                &origins
+                    .iter()
+                    .map(|o| (format!("AS{}", o.asn), o.space))
+                    .collect::<Vec<_>>(),
+            )
+            .as_str(),
+        );
+        html.push_str(
+            table(
+                &["Origin", unit, "Prefixes"],
+                origins
+                    .iter()
+                    .map(|o| {
+                        vec![
+                            format!("AS{}", o.asn),
+                            format!("{:.1}", o.space),
+                            o.prefixes.to_string(),
+                        ]
+                    })
+                    .collect(),
+            )
+            .as_str(),
+        );
+    }
+    Ok(html)
+}
+
+fn relationships_section(summary: &Value, top_n: usize) -> Result<String> {
+    let as2rel = As2relSummaryJson::deserialize(summary)?;
+    let adjacencies: HashSet<(u32, u32)> = as2rel
+        .as2rel
+        .iter()
+        .filter(|e| e.rel == 0)
+        .map(|e| (e.asn1.min(e.asn2), e.asn1.max(e.asn2)))
+        .collect();
+    let p2c: HashSet<(u32, u32)> = as2rel
+        .as2rel
+        .iter()
+        .filter(|e| e.rel == 1)
+        .map(|e| (e.asn1, e.asn2))
+        .collect();
+    let counts = [
+        ("AS adjacencies".to_string(), adjacencies.len() as f64),
+        ("provider-customer".to_string(), p2c.len() as f64),
+    ];
+    let mut html = format!(
+        "<h2>Relationships</h2>\n{}{}",
+        bar_chart(&counts),
+        table(
+            &["Relationship", "Links"],
+            counts
+                .iter()
+                .map(|(r, c)| vec![r.clone(), (*c as usize).to_string()])
+                .collect(),
+        )
+    );
+    let views = top_views("as2rel", summary, top_n)?.unwrap_or_default();
+    if !views.busiest_links.is_empty() {
+        html.push_str("<h3>Busiest AS links</h3>\n");
+        html.push_str(
+            table(
+                &["Link", "Paths", "Peers"],
+                views
+                    .busiest_links
+                    .iter()
+                    .map(|l| {
+                        vec![
+                            format!("AS{} - AS{}", l.asn1, l.asn2),
+                            l.paths_count.to_string(),
+                            l.peers_count.to_string(),
+                        ]
+                    })
+                    .collect(),
+            )
+            .as_str(),
+        );
+    }
+    Ok(html)
+}
+
+/// HTML table with a header row; cells are escaped.
+fn table(header: &[&str], rows: Vec<Vec<String>>) -> String {
+    let mut html = String::from("<table>\n<tr>");
+    for cell in header {
+        html.push_str(format!("<th>{}</th>", escape(cell)).as_str());
+    }
+    html.push_str("</tr>\n");
+    for row in rows {
+        html.push_str("<tr>");
+        for cell in row {
+            html.push_str(format!("<td>{}</td>", escape(cell.as_str())).as_str());
+        }
+        html.push_str("</tr>\n");
+    }
+    html.push_str("</table>\n");
+    html
+}
+
+/// Horizontal SVG bar chart of labeled values, scaled to the largest value.
+fn bar_chart(bars: &[(String, f64)]) -> String {
+    const LABEL_WIDTH: f64 = 160.0;
+    const BAR_WIDTH: f64 = 380.0;
+    const ROW_HEIGHT: f64 = 20.0;
+    if bars.is_empty() {
+        return String::new();
+    }
+    let max = bars.iter().map(|(_, v)| *v).fold(0.0, f64::max);
+    let mut svg = format!(
+        "<svg width=\"{}\" height=\"{}\" role=\"img\">\n",
+        LABEL_WIDTH + BAR_WIDTH + 60.0,
+        ROW_HEIGHT * bars.len() as f64
+    );
+    for (i, (label, value)) in bars.iter().enumerate() {
+        let y = ROW_HEIGHT * i as f64;
+        let width = match max > 0.0 {
+            true => BAR_WIDTH * value / max,
+            false => 0.0,
+        };
+        let value = match value.fract() == 0.0 {
+            true => format_count(*value as usize),
+            false => format!("{:.1}", value),
+        };
+        svg.push_str(
+            format!(
+                "<text x=\"0\" y=\"{:.0}\">{}</text>\
+                 <rect x=\"{}\" y=\"{:.0}\" width=\"{:.1}\" height=\"{}\" fill=\"#4a7ebb\"/>\
+                 <text x=\"{:.1}\" y=\"{:.0}\">{}</text>\n",
+                y + 14.0,
+                escape(label.as_str()),
+                LABEL_WIDTH,
+                y + 3.0,
+                width,
+                ROW_HEIGHT - 6.0,
+                LABEL_WIDTH + width + 4.0,
+                y + 14.0,
+                value
+            )
+            .as_str(),
+        );
+    }
+    svg.push_str("</svg>\n");
+    svg
+}
+
+fn escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
@@ -0,0 +1,173 @@
+//! Differences between two snapshots of summarized outputs.
+//!
+//! [origin_changes], [as2rel_changes] and [peer_changes] compare an older and a newer summary of
+//! `pfx2as`, `as2rel` and `peer-stats` and return the typed changes between them, sorted for
+//! stable output, so that monitoring services can embed the comparison instead of diffing the
+//! JSON files themselves. The `origin_change` alert rule is built on [origin_changes].
+
+use crate::processors::{
+    As2relEntry, As2relSummaryJson, PeerInfoEntry, PeerInfoSummaryJson, Prefix2AsSummaryJson,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::net::IpAddr;
+
+/// Kind of change of an entry between two snapshots.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChangeKind {
+    /// only present in the new snapshot
+    Added,
+    /// only present in the old snapshot
+    Removed,
+    /// present in both snapshots with different values
+    Changed,
+}
+
+/// Change of the origins of a prefix.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OriginChange {
+    pub prefix: String,
+    pub kind: ChangeKind,
+    /// origins in the old snapshot, empty if the prefix was added
+    pub old_origins: BTreeSet<u32>,
+    /// origins in the new snapshot, empty if the prefix was removed
+    pub new_origins: BTreeSet<u32>,
+}
+
+/// An as2rel entry present in only one of the snapshots. A changed relationship inference shows up
+/// as a removed and an added entry of the same link.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct As2relChange {
+    pub asn1: u32,
+    pub asn2: u32,
+    pub rel: u8,
+    pub kind: ChangeKind,
+}
+
+/// Change of a route collector peer.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PeerChange {
+    pub ip: IpAddr,
+    pub collector: Option<String>,
+    pub kind: ChangeKind,
+    /// the peer in the old snapshot, none if it was added
+    pub old: Option<PeerInfoEntry>,
+    /// the peer in the new snapshot, none if it was removed
+    pub new: Option<PeerInfoEntry>,
+}
+
+/// Origins of each prefix of a summary.
+fn prefix_origins(summary: &Prefix2AsSummaryJson) -> BTreeMap<&str, BTreeSet<u32>> {
+    let mut origins: BTreeMap<&str, BTreeSet<u32>> = BTreeMap::new();
+    for entry in &summary.pfx2as {
+        origins
+            .entry(entry.prefix.as_str())
+            .or_default()
+            .insert(entry.asn);
+    }
+    origins
+}
+
+/// Prefixes whose set of origins differs between two pfx2as summaries, sorted by prefix.
+pub fn origin_changes(old: &Prefix2AsSummaryJson, new: &Prefix2AsSummaryJson) -> Vec<OriginChange> {
+    let (mut old, new) = (prefix_origins(old), prefix_origins(new));
+    let mut changes = vec![];
+    for (prefix, new_origins) in new {
+        let (kind, old_origins) = match old.remove(prefix) {
+            None => (ChangeKind::Added, BTreeSet::new()),
+            Some(old_origins) if old_origins != new_origins => (ChangeKind::Changed, old_origins),
+            Some(_) => continue,
+        };
+        changes.push(OriginChange {
+            prefix: prefix.to_string(),
+            kind,
+            old_origins,
+            new_origins,
+        });
+    }
+    changes.extend(old.into_iter().map(|(prefix, old_origins)| OriginChange {
+        prefix: prefix.to_string(),
+        kind: ChangeKind::Removed,
+        old_origins,
+        new_origins: BTreeSet::new(),
+    }));
+    changes.sort_by(|a, b| a.prefix.cmp(&b.prefix));
+    changes
+}
+
+/// Entries, identified by `(asn1, asn2, rel)`, present in only one of two as2rel summaries,
+/// sorted by link.
+pub fn as2rel_changes(old: &As2relSummaryJson, new: &As2relSummaryJson) -> Vec<As2relChange> {
+    let keys = |summary: &As2relSummaryJson| -> BTreeSet<(u32, u32, u8)> {
+        summary
+            .as2rel
+            .iter()
+            .map(|e: &As2relEntry| (e.asn1, e.asn2, e.rel))
+            .collect()
+    };
+    let (old, new) = (keys(old), keys(new));
+    let change = |kind: ChangeKind| {
+        move |&(asn1, asn2, rel): &(u32, u32, u8)| As2relChange {
+            asn1,
+            asn2,
+            rel,
+            kind,
+        }
+    };
+    let mut changes: Vec<As2relChange> = new
+        .difference(&old)
+        .map(change(ChangeKind::Added))
+        .chain(old.difference(&new).map(change(ChangeKind::Removed)))
+        .collect();
+    changes.sort_by_key(|c| (c.asn1, c.asn2, c.rel));
+    changes
+}
+
+/// Peers, identified by collector and IP address, added, removed or changed between two
+/// peer-stats summaries, sorted by IP address and collector. A peer changed if its ASN or prefix
+/// counts differ.
+pub fn peer_changes(old: &PeerInfoSummaryJson, new: &PeerInfoSummaryJson) -> Vec<PeerChange> {
+    let peers =
+        |summary: &PeerInfoSummaryJson| -> BTreeMap<(IpAddr, Option<String>), PeerInfoEntry> {
+            summary
+                .peers
+                .iter()
+                .map(|p| ((p.ip, p.collector.clone()), p.clone()))
+                .collect()
+        };
+    let (mut old, new) = (peers(old), peers(new));
+    let mut changes = vec![];
+    for ((ip, collector), new_peer) in new {
+        let (kind, old_peer) = match old.remove(&(ip, collector.clone())) {
+            None => (ChangeKind::Added, None),
+            Some(old_peer)
+                if old_peer.asn != new_peer.asn
+                    || old_peer.num_v4_pfxs != new_peer.num_v4_pfxs
+                    || old_peer.num_v6_pfxs != new_peer.num_v6_pfxs =>
+            {
+                (ChangeKind::Changed, Some(old_peer))
+            }
+            Some(_) => continue,
+        };
+        changes.push(PeerChange {
+            ip,
+            collector,
+            kind,
+            old: old_peer,
+            new: Some(new_peer),
+        });
+    }
+    changes.extend(
+        old.into_iter()
+            .map(|((ip, collector), old_peer)| PeerChange {
+                ip,
+                collector,
+                kind: ChangeKind::Removed,
+                old: Some(old_peer),
+                new: None,
+            }),
+    );
+    changes.sort_by(|a, b| (a.ip, &a.collector).cmp(&(b.ip, &b.collector)));
+    changes
+}
@@ -0,0 +1,345 @@
+//! Resumable downloads of RIB dump files.
+//!
+//! Large RIB dumps, e.g. 4 GB route-views2 ones, are usually streamed straight from the collector
+//! archives, so a dropped connection near the end means downloading the whole file again. A
+//! [Downloader] instead downloads HTTP(S) files into a cache directory, at
+//! `<cache_dir>/<host>/<path>`, and resumes interrupted downloads with range requests from where
+//! they stopped. Partial data is kept in a `.part` file next to the final path, so downloads also
+//! resume across runs.
+//!
+//! The remote file may change between attempts, e.g. RIPE RIS replaces `latest-bview.gz` every few
+//! hours, and appending to data of an older file would yield a corrupt RIB dump. The ETag (or
+//! Last-Modified date) of the response that started a partial file is therefore kept in a
+//! `.part.validator` file and sent as `If-Range` when resuming, so that the server sends the whole
+//! file again if it changed. Partial files without a validator, and partial responses that do not
+//! start where the partial file ends, are discarded and downloaded from scratch.
+//!
+//! Complete files are reused as they are, by URL, for as long as they stay in the cache directory,
+//! except for files named `latest*` whose content changes under the same URL: those are downloaded
+//! again each time.
+//!
+//! See [RibEye::with_downloader](crate::RibEye::with_downloader) to process remote RIB dumps
+//! through a downloader.
+
+use anyhow::{anyhow, Result};
+use reqwest::blocking::Client;
+use reqwest::blocking::Response;
+use reqwest::header::{CONTENT_RANGE, ETAG, IF_RANGE, LAST_MODIFIED, RANGE};
+use reqwest::StatusCode;
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::path::Path;
+use std::time::Duration;
+use tracing::{info, warn};
+
+/// Default number of attempts to download a file.
+pub const DEFAULT_DOWNLOAD_ATTEMPTS: u32 = 5;
+
+/// Suffix of the partial file of an incomplete download.
+pub const PART_SUFFIX: &str = ".part";
+
+/// Suffix, after [PART_SUFFIX], of the file keeping the validator of a partial download.
+pub const VALIDATOR_SUFFIX: &str = ".validator";
+
+/// Check if a path is an HTTP(S) URL, which downloads can resume.
+pub fn is_resumable(path: &str) -> bool {
+    path.starts_with("http://") || path.starts_with("https://")
+}
+
+/// A file downloaded into the cache directory.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Downloaded {
+    /// local path of the complete file
+    pub path: String,
+    /// bytes transferred, 0 if the file was already downloaded
+    pub bytes: u64,
+}
+
+/// Outcome of a download attempt that did not fail permanently, with the bytes received.
+enum Attempt {
+    Complete(u64),
+    Interrupted(u64, anyhow::Error),
+}
+
+/// Writer counting the bytes written through it, including those of an interrupted copy.
+struct CountingWriter<W> {
+    inner: W,
+    count: u64,
+}
+
+impl<W: Write> Write for CountingWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let n = self.inner.write(buf)?;
+        self.count += n as u64;
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+fn file_len(path: &str) -> u64 {
+    std::fs::metadata(path).map(|m| m.len()).unwrap_or(0)
+}
+
+/// Whether the content of a URL changes over time, e.g. RIPE RIS `latest-bview.gz`.
+fn is_mutable(url: &str) -> bool {
+    let path = url.split(['?', '#']).next().unwrap_or_default();
+    path.rsplit('/')
+        .next()
+        .unwrap_or_default()
+        .starts_with("latest")
+}
+
+fn validator_path(part: &str) -> String {
+    format!("{}{}", part, VALIDATOR_SUFFIX)
+}
+
+/// Validator of a response for `If-Range`: its strong ETag, or its Last-Modified date.
+fn response_validator(response: &Response) -> Option<String> {
+    let header = |name| response.headers().get(name).and_then(|v| v.to_str().ok());
+    header(ETAG)
+        .filter(|e| !e.starts_with("W/"))
+        .or_else(|| header(LAST_MODIFIED))
+        .map(|v| v.to_string())
+}
+
+/// First byte of a partial response, from `Content-Range: bytes <first>-<last>/<size>`.
+fn content_range_start(response: &Response) -> Option<u64> {
+    response
+        .headers()
+        .get(CONTENT_RANGE)?
+        .to_str()
+        .ok()?
+        .strip_prefix("bytes ")?
+        .split_once('-')?
+        .0
+        .parse()
+        .ok()
+}
+
+/// Remove a partial file and its validator, so that the next attempt starts from scratch.
+fn discard_partial(part: &str) -> Result<()> {
+    for path in [part.to_string(), validator_path(part)] {
+        match std::fs::remove_file(path) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => return Err(e.into()),
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+/// HTTP client for large files: downloads take long, only connecting is bounded.
+fn http_client() -> Client {
+    Client::builder()
+        .timeout(None)
+        .connect_timeout(Duration::from_secs(30))
+        .build()
+        .unwrap_or_default()
+}
+
+/// Open an HTTP(S) URL for streaming its content as stored, e.g. compressed, without caching it.
+pub fn stream(url: &str) -> Result<Response> {
+    Ok(http_client().get(url).send()?.error_for_status()?)
+}
+
+/// Downloader of remote files into a cache directory, see the [module documentation](self).
+#[derive(Debug, Clone)]
+pub struct Downloader {
+    cache_dir: String,
+    max_attempts: u32,
+    retry_delay: Duration,
+    client: Client,
+}
+
+impl Downloader {
+    pub fn new(cache_dir: &str) -> Self {
+        Downloader {
+            cache_dir: cache_dir.trim_end_matches('/').to_string(),
+            max_attempts: DEFAULT_DOWNLOAD_ATTEMPTS,
+            retry_delay: Duration::from_secs(1),
+            client: http_client(),
+        }
+    }
+
+    /// Give up on a file after `max_attempts` interrupted attempts (default:
+    /// [DEFAULT_DOWNLOAD_ATTEMPTS]).
+    pub fn with_max_attempts(mut self, max_attempts: u32) -> Self {
+        self.max_attempts = max_attempts.max(1);
+        self
+    }
+
+    /// Wait between attempts (default: 1 second).
+    pub fn with_retry_delay(mut self, retry_delay: Duration) -> Self {
+        self.retry_delay = retry_delay;
+        self
+    }
+
+    /// Local path of a URL in the cache directory.
+    pub fn cache_path(&self, url: &str) -> String {
+        let rest = url.split_once("://").map(|(_, rest)| rest).unwrap_or(url);
+        let rest = rest.split(['?', '#']).next().unwrap_or_default();
+        let segments: Vec<&str> = rest
+            .split('/')
+            .filter(|s| !s.is_empty() && *s != "." && *s != "..")
+            .collect();
+        format!("{}/{}", self.cache_dir, segments.join("/"))
+    }
+
+    /// Download a URL into the cache directory, resuming a partial download if any.
+    pub fn download(&self, url: &str) -> Result<Downloaded> {
+        let path = self.cache_path(url);
+        if Path::new(path.as_str()).exists() {
+            if !is_mutable(url) {
+                info!(phase = "download", "using downloaded {}", path);
+                return Ok(Downloaded { path, bytes: 0 });
+            }
+            std::fs::remove_file(path.as_str())?;
+        }
+        if let Some(parent) = Path::new(path.as_str()).parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let part = format!("{}{}", path, PART_SUFFIX);
+        info!(
+            phase = "download",
+            "downloading {} to {} (resuming at {} bytes)",
+            url,
+            path,
+            file_len(part.as_str())
+        );
+        let mut last_error = anyhow!("no attempt made");
+        let mut bytes = 0;
+        for attempt in 1..=self.max_attempts {
+            match self.attempt(url, part.as_str())? {
+                Attempt::Complete(received) => {
+                    std::fs::rename(part.as_str(), path.as_str())?;
+                    discard_partial(part.as_str())?;
+                    return Ok(Downloaded {
+                        path,
+                        bytes: bytes + received,
+                    });
+                }
+                Attempt::Interrupted(received, e) => {
+                    bytes += received;
+                    warn!(
+                        phase = "download",
+                        "download of {} interrupted at {} bytes (attempt {} of {}): {}",
+                        url,
+                        file_len(part.as_str()),
+                        attempt,
+                        self.max_attempts,
+                        e
+                    );
+                    last_error = e;
+                }
+            }
+            if attempt < self.max_attempts {
+                std::thread::sleep(self.retry_delay);
+            }
+        }
+        Err(anyhow!(
+            "failed to download {} after {} attempts: {}",
+            url,
+            self.max_attempts,
+            last_error
+        ))
+    }
+
+    /// Download the rest of a file into its partial file, starting at the partial file's size if
+    /// the remote file did not change since. Errors are permanent failures, e.g. a missing file,
+    /// that are not retried.
+    fn attempt(&self, url: &str, part: &str) -> Result<Attempt> {
+        let mut offset = file_len(part);
+        let validator = std::fs::read_to_string(validator_path(part)).ok();
+        let mut request = self.client.get(url);
+        match (offset, validator) {
+            (0, _) => {}
+            (_, Some(validator)) => {
+                request = request
+                    .header(RANGE, format!("bytes={}-", offset))
+                    .header(IF_RANGE, validator);
+            }
+            (_, None) => {
+                warn!(
+                    phase = "download",
+                    "discarding partial download of {} without validator", url
+                );
+                discard_partial(part)?;
+                offset = 0;
+            }
+        }
+        let mut response = match request.send() {
+            Ok(response) => response,
+            Err(e) => return Ok(Attempt::Interrupted(0, e.into())),
+        };
+        let mut file = match response.status() {
+            StatusCode::PARTIAL_CONTENT => {
+                let start = content_range_start(&response);
+                if start != Some(offset) {
+                    discard_partial(part)?;
+                    return Ok(Attempt::Interrupted(
+                        0,
+                        anyhow!(
+                            "partial content starts at {:?} instead of {} bytes",
+                            start,
+                            offset
+                        ),
+                    ));
+                }
+                OpenOptions::new().append(true).open(part)?
+            }
+            StatusCode::RANGE_NOT_SATISFIABLE if offset > 0 => {
+                // nothing left to download if the partial file has the full size, `bytes */<size>`
+                let size = response
+                    .headers()
+                    .get(CONTENT_RANGE)
+                    .and_then(|v| v.to_str().ok())
+                    .and_then(|v| v.strip_prefix("bytes */"))
+                    .and_then(|v| v.parse::<u64>().ok());
+                if size == Some(offset) {
+                    return Ok(Attempt::Complete(0));
+                }
+                discard_partial(part)?;
+                return Ok(Attempt::Interrupted(
+                    0,
+                    anyhow!(
+                        "partial download of {} bytes does not match the remote file",
+                        offset
+                    ),
+                ));
+            }
+            // servers ignoring the range, or whose file changed, send the whole file again
+            status if status.is_success() => {
+                match response_validator(&response) {
+                    Some(validator) => std::fs::write(validator_path(part), validator)?,
+                    None => discard_partial(part)?,
+                }
+                File::create(part)?
+            }
+            status if status.is_server_error() => {
+                return Ok(Attempt::Interrupted(0, anyhow!("HTTP {}", status)))
+            }
+            status => return Err(anyhow!("failed to download {}: HTTP {}", url, status)),
+        };
+        let expected = response.content_length();
+        let mut file = CountingWriter {
+            inner: &mut file,
+            count: 0,
+        };
+        if let Err(e) = response.copy_to(&mut file) {
+            return Ok(Attempt::Interrupted(file.count, e.into()));
+        }
+        match expected {
+            Some(expected) if file.count < expected => Ok(Attempt::Interrupted(
+                file.count,
+                anyhow!(
+                    "connection closed after {} of {} bytes",
+                    file.count,
+                    expected
+                ),
+            )),
+            _ => Ok(Attempt::Complete(file.count)),
+        }
+    }
+}
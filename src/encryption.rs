@@ -0,0 +1,210 @@
+//! Client-side encryption of output files.
+//!
+//! An [EncryptingSink] stores outputs at their usual local or object store paths, but encrypted with
+//! AES-256-GCM under a symmetric key: content is compressed according to the path's file extension
+//! first, then stored as `RIBEYE-AES256GCM1 || nonce || ciphertext`, so that the storage (e.g. a
+//! shared bucket) never sees the results. The sink reads its own outputs back transparently, which
+//! keeps summarization working; other readers need to [decrypt] them first, e.g. with
+//! `ribeye decrypt`.
+//!
+//! The ciphertext is bound to the path it is stored at (absolute for local files), so that an
+//! output swapped with another one, e.g. the latest output of another collector, fails to
+//! decrypt. Copies that moved elsewhere are decrypted with [decrypt_stored] and their original
+//! path.
+//!
+//! Key files hold the base64-encoded 256-bit key after an untrusted comment line.
+
+use crate::processors::{read_raw, OutputSink};
+use crate::storage;
+use anyhow::{anyhow, Result};
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use ring::aead::{Aad, LessSafeKey, Nonce, UnboundKey, AES_256_GCM, NONCE_LEN};
+use ring::rand::{SecureRandom, SystemRandom};
+use std::io::{Read, Write};
+use std::path::Path;
+use std::sync::Arc;
+
+/// Header of encrypted files, identifying the format and its version.
+pub const ENCRYPTED_MAGIC: &[u8] = b"RIBEYE-AES256GCM1";
+
+const KEY_LEN: usize = 32;
+const COMMENT_PREFIX: &str = "untrusted comment: ";
+
+/// Symmetric key encrypting output files.
+pub struct EncryptionKey {
+    key: [u8; KEY_LEN],
+    rng: SystemRandom,
+}
+
+impl EncryptionKey {
+    /// Generate a new random key.
+    pub fn generate() -> Result<Self> {
+        let rng = SystemRandom::new();
+        let mut key = [0u8; KEY_LEN];
+        rng.fill(&mut key)
+            .map_err(|_| anyhow!("failed to generate encryption key"))?;
+        Ok(EncryptionKey { key, rng })
+    }
+
+    /// Load a key file written by `ribeye keygen --encryption`.
+    pub fn load(path: &str) -> Result<Self> {
+        let content = std::fs::read_to_string(path)
+            .map_err(|e| anyhow!("failed to read encryption key {}: {}", path, e))?;
+        Self::parse(content.as_str())
+    }
+
+    /// Parse a key file, or its base64 line alone.
+    pub fn parse(content: &str) -> Result<Self> {
+        let line = content
+            .lines()
+            .map(|l| l.trim())
+            .find(|l| !l.is_empty() && !l.starts_with(COMMENT_PREFIX))
+            .ok_or_else(|| anyhow!("empty encryption key"))?;
+        let key: [u8; KEY_LEN] = BASE64
+            .decode(line)
+            .map_err(|e| anyhow!("invalid encryption key: {}", e))?
+            .try_into()
+            .map_err(|_| anyhow!("invalid encryption key length"))?;
+        Ok(EncryptionKey {
+            key,
+            rng: SystemRandom::new(),
+        })
+    }
+
+    /// Content of the key file.
+    pub fn to_key_string(&self) -> String {
+        format!(
+            "{}ribeye AES-256-GCM output encryption key\n{}\n",
+            COMMENT_PREFIX,
+            BASE64.encode(self.key)
+        )
+    }
+
+    fn aead_key(&self) -> Result<LessSafeKey> {
+        let key = UnboundKey::new(&AES_256_GCM, &self.key)
+            .map_err(|_| anyhow!("invalid encryption key"))?;
+        Ok(LessSafeKey::new(key))
+    }
+
+    /// Encrypt data stored at `path` with a random nonce.
+    pub fn encrypt(&self, path: &str, data: &[u8]) -> Result<Vec<u8>> {
+        let mut nonce = [0u8; NONCE_LEN];
+        self.rng
+            .fill(&mut nonce)
+            .map_err(|_| anyhow!("failed to generate nonce"))?;
+        let mut ciphertext = data.to_vec();
+        self.aead_key()?
+            .seal_in_place_append_tag(
+                Nonce::assume_unique_for_key(nonce),
+                Aad::from(associated_data(path)),
+                &mut ciphertext,
+            )
+            .map_err(|_| anyhow!("failed to encrypt"))?;
+        Ok([ENCRYPTED_MAGIC, nonce.as_slice(), ciphertext.as_slice()].concat())
+    }
+
+    /// Decrypt data written by [EncryptionKey::encrypt] to `path`, checking its integrity.
+    pub fn decrypt(&self, path: &str, data: &[u8]) -> Result<Vec<u8>> {
+        let data = data
+            .strip_prefix(ENCRYPTED_MAGIC)
+            .ok_or_else(|| anyhow!("not an encrypted ribeye output"))?;
+        if data.len() < NONCE_LEN {
+            return Err(anyhow!("truncated encrypted output"));
+        }
+        let (nonce, ciphertext) = data.split_at(NONCE_LEN);
+        let nonce = Nonce::try_assume_unique_for_key(nonce)
+            .map_err(|_| anyhow!("truncated encrypted output"))?;
+        let mut plaintext = ciphertext.to_vec();
+        let len = self
+            .aead_key()?
+            .open_in_place(nonce, Aad::from(associated_data(path)), &mut plaintext)
+            .map_err(|_| anyhow!("failed to decrypt: wrong key, path or modified file"))?
+            .len();
+        plaintext.truncate(len);
+        Ok(plaintext)
+    }
+}
+
+/// Data authenticated along with the content: the format header and the path the content is stored
+/// at, absolute for local files.
+fn associated_data(path: &str) -> Vec<u8> {
+    let path = match storage::is_object_store(path) || path.contains("://") {
+        true => path.to_string(),
+        false => std::path::absolute(path)
+            .map(|p| p.to_string_lossy().to_string())
+            .unwrap_or_else(|_| path.to_string()),
+    };
+    [ENCRYPTED_MAGIC, b"\n", path.as_bytes()].concat()
+}
+
+/// Compress content through a temporary file named like `path`, so that oneio picks the
+/// compression of its file extension.
+fn compress(path: &str, data: &[u8]) -> Result<Vec<u8>> {
+    let file_name = path.rsplit('/').next().unwrap_or("output");
+    let temp_dir = tempfile::tempdir()?;
+    let temp_path = temp_dir.path().join(file_name);
+    let mut writer = oneio::get_writer(temp_path.to_string_lossy().as_ref())?;
+    writer.write_all(data)?;
+    drop(writer);
+    Ok(std::fs::read(temp_path)?)
+}
+
+/// Decompress content according to the file extension of `path`.
+fn decompress(path: &str, data: &[u8]) -> Result<Vec<u8>> {
+    let file_name = path.rsplit('/').next().unwrap_or("output");
+    let temp_dir = tempfile::tempdir()?;
+    let temp_path = temp_dir.path().join(file_name);
+    std::fs::write(&temp_path, data)?;
+    let mut content = vec![];
+    oneio::get_reader(temp_path.to_string_lossy().as_ref())?.read_to_end(&mut content)?;
+    Ok(content)
+}
+
+/// Decrypted and decompressed content of an encrypted output file, local or object store.
+pub fn decrypt(path: &str, key: &EncryptionKey) -> Result<Vec<u8>> {
+    decrypt_stored(path, path, key)
+}
+
+/// Decrypted and decompressed content of a copy of an encrypted output file, local, object store
+/// or HTTP(S), originally stored at `stored_path`.
+pub fn decrypt_stored(path: &str, stored_path: &str, key: &EncryptionKey) -> Result<Vec<u8>> {
+    let data = key
+        .decrypt(stored_path, &read_raw(path)?)
+        .map_err(|e| anyhow!("{}: {}", path, e))?;
+    decompress(path, &data)
+}
+
+/// Sink writing encrypted outputs to local files or object store objects (`s3://`, `gs://` and
+/// `az://` paths), see the [module documentation](self).
+pub struct EncryptingSink {
+    key: Arc<EncryptionKey>,
+}
+
+impl EncryptingSink {
+    pub fn new(key: Arc<EncryptionKey>) -> Self {
+        EncryptingSink { key }
+    }
+}
+
+impl OutputSink for EncryptingSink {
+    fn write_bytes(&self, path: &str, data: &[u8]) -> Result<()> {
+        let data = self.key.encrypt(path, &compress(path, data)?)?;
+        if storage::is_object_store(path) {
+            let temp_dir = tempfile::tempdir()?;
+            let temp_path = temp_dir.path().join("encrypted");
+            std::fs::write(&temp_path, data)?;
+            storage::upload(path, temp_path.to_string_lossy().as_ref())?;
+        } else {
+            if let Some(parent) = Path::new(path).parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+            std::fs::write(path, data)?;
+        }
+        Ok(())
+    }
+
+    fn read_bytes(&self, path: &str) -> Result<Vec<u8>> {
+        decrypt(path, &self.key)
+    }
+}
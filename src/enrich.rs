@@ -0,0 +1,310 @@
+//! Enrichment of summarized outputs with PeeringDB data.
+//!
+//! [PeeringDb] loads network records from a PeeringDB dump, and [enrich_summaries] writes enriched
+//! variants of the summarized `as2rel`, `peer-stats` and `pfx2as` outputs next to the originals
+//! (`<dir>/<processor>/latest-peeringdb.json.bz2`). An enriched file is the summary with an added
+//! `peeringdb` object, mapping each ASN appearing in its records to the network's name, organization,
+//! network type, traffic level and number of IXPs it is present at.
+//!
+//! [AsnInfoData] holds AS names and registration countries (the BGPKIT Commons `asinfo` dataset),
+//! which [attach_asn_info] adds to summaries as an `asn_info` object while they are written; see
+//! [crate::RibEye::with_asn_info].
+
+use crate::inspect::{OutputFile, ASN_FIELDS, NON_DATASET_ARRAYS};
+use crate::processors::get_summary_output_path;
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::collections::{BTreeSet, HashMap, HashSet};
+use tracing::{info, warn};
+
+/// Processors whose summaries can be enriched.
+pub const ENRICHABLE_PROCESSORS: [&str; 3] = ["as2rel", "peer-stats", "pfx2as"];
+
+/// PeeringDB information of a network.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PeeringDbNet {
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub org_name: Option<String>,
+    /// network type, e.g. `NSP`, `Content` or `Route Server`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub info_type: Option<String>,
+    /// traffic level, e.g. `1-5Tbps`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub info_traffic: Option<String>,
+    /// number of IXPs the network is present at
+    pub ix_count: usize,
+}
+
+/// PeeringDB networks keyed by ASN.
+#[derive(Debug, Default, Clone)]
+pub struct PeeringDb {
+    pub nets: HashMap<u32, PeeringDbNet>,
+}
+
+/// Non-empty string field of a PeeringDB object.
+fn str_field(obj: &Value, field: &str) -> Option<String> {
+    obj.get(field)
+        .and_then(|v| v.as_str())
+        .filter(|s| !s.is_empty())
+        .map(|s| s.to_string())
+}
+
+/// The `data` array of a PeeringDB API response or dump section.
+fn data_array<'a>(value: &'a Value, section: Option<&str>) -> Option<&'a Vec<Value>> {
+    let value = match section {
+        None => value,
+        Some(s) => value.get(s)?,
+    };
+    value.get("data")?.as_array()
+}
+
+impl PeeringDb {
+    /// Load PeeringDB networks from a local file or URL.
+    ///
+    /// Accepted formats:
+    /// - a full PeeringDB dump (e.g. CAIDA's PeeringDB archive) with `net`, `org` and `netixlan`
+    ///   sections, each holding a `data` array;
+    /// - a PeeringDB `net` API response (`{"data": [...]}`), taking organization names from nested
+    ///   `org` objects and IXP counts from `ix_count`, if present.
+    pub fn load(path: &str) -> Result<Self> {
+        let content = oneio::read_to_string(path)
+            .map_err(|e| anyhow!("failed to read PeeringDB data from {}: {}", path, e))?;
+        Self::from_json_str(content.as_str())
+    }
+
+    pub fn from_json_str(content: &str) -> Result<Self> {
+        let value: Value = serde_json::from_str(content)?;
+        let (nets, orgs, netixlans) = match data_array(&value, Some("net")) {
+            Some(nets) => (
+                nets,
+                data_array(&value, Some("org")),
+                data_array(&value, Some("netixlan")),
+            ),
+            None => (
+                data_array(&value, None).ok_or_else(|| anyhow!("no PeeringDB net data found"))?,
+                None,
+                None,
+            ),
+        };
+
+        let org_names: HashMap<u64, String> = orgs
+            .into_iter()
+            .flatten()
+            .filter_map(|org| Some((org.get("id")?.as_u64()?, str_field(org, "name")?)))
+            .collect();
+        // distinct IXPs per network ID
+        let mut net_ixs: HashMap<u64, HashSet<u64>> = HashMap::new();
+        for netixlan in netixlans.into_iter().flatten() {
+            if let (Some(net_id), Some(ix_id)) = (
+                netixlan.get("net_id").and_then(|v| v.as_u64()),
+                netixlan.get("ix_id").and_then(|v| v.as_u64()),
+            ) {
+                net_ixs.entry(net_id).or_default().insert(ix_id);
+            }
+        }
+
+        let mut data = PeeringDb::default();
+        for net in nets {
+            let Some(asn) = net
+                .get("asn")
+                .and_then(|v| v.as_u64())
+                .and_then(|v| u32::try_from(v).ok())
+            else {
+                continue;
+            };
+            let net_id = net.get("id").and_then(|v| v.as_u64());
+            let org_name = net
+                .get("org")
+                .and_then(|org| str_field(org, "name"))
+                .or_else(|| {
+                    let org_id = net.get("org_id")?.as_u64()?;
+                    org_names.get(&org_id).cloned()
+                });
+            let ix_count = match net_id.and_then(|id| net_ixs.get(&id)) {
+                Some(ixs) => ixs.len(),
+                None => net.get("ix_count").and_then(|v| v.as_u64()).unwrap_or(0) as usize,
+            };
+            data.nets.insert(
+                asn,
+                PeeringDbNet {
+                    name: str_field(net, "name").unwrap_or_default(),
+                    org_name,
+                    info_type: str_field(net, "info_type"),
+                    info_traffic: str_field(net, "info_traffic"),
+                    ix_count,
+                },
+            );
+        }
+        Ok(data)
+    }
+}
+
+/// Default location of the AS information dataset published with BGPKIT Commons.
+pub const DEFAULT_ASINFO_URL: &str = "https://data.bgpkit.com/commons/asinfo.jsonl";
+
+/// Name and registration country of an AS.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AsnInfo {
+    pub name: String,
+    /// two-letter country code of the registration
+    pub country: String,
+}
+
+/// AS information keyed by ASN.
+#[derive(Debug, Default, Clone)]
+pub struct AsnInfoData {
+    pub asns: HashMap<u32, AsnInfo>,
+}
+
+/// Record of the AS information dataset; other fields of the dataset are ignored.
+#[derive(Deserialize)]
+struct AsnInfoRecord {
+    asn: u32,
+    #[serde(default)]
+    name: String,
+    #[serde(default)]
+    country: String,
+}
+
+impl AsnInfoData {
+    /// Load AS information from a local file or URL, e.g. [DEFAULT_ASINFO_URL].
+    ///
+    /// The dataset is read as JSON lines or a JSON array of objects with `asn`, `name` and
+    /// `country` fields, the format of the BGPKIT Commons `asinfo` export.
+    pub fn load(path: &str) -> Result<Self> {
+        let content = oneio::read_to_string(path)
+            .map_err(|e| anyhow!("failed to read AS information from {}: {}", path, e))?;
+        Self::from_json_str(content.as_str())
+    }
+
+    pub fn from_json_str(content: &str) -> Result<Self> {
+        let records: Vec<AsnInfoRecord> = match content.trim_start().starts_with('[') {
+            true => serde_json::from_str(content)?,
+            false => content
+                .lines()
+                .filter(|line| !line.trim().is_empty())
+                .map(serde_json::from_str)
+                .collect::<std::result::Result<_, _>>()?,
+        };
+        Ok(AsnInfoData {
+            asns: records
+                .into_iter()
+                .map(|r| {
+                    (
+                        r.asn,
+                        AsnInfo {
+                            name: r.name,
+                            country: r.country,
+                        },
+                    )
+                })
+                .collect(),
+        })
+    }
+}
+
+/// Path of the PeeringDB-enriched summary of a processor under an output root directory.
+pub fn get_enriched_output_path(output_dir: &str, processor_name: &str) -> String {
+    format!(
+        "{}/{}/latest-peeringdb.json.bz2",
+        output_dir, processor_name
+    )
+}
+
+/// Add a `peeringdb` object to an output file, with the PeeringDB information of every ASN in its
+/// records that is known to PeeringDB. Returns the number of ASNs added.
+pub fn enrich_output(file: &mut OutputFile, peeringdb: &PeeringDb) -> usize {
+    let object = asn_object(records_asns(&file.records), &peeringdb.nets);
+    let count = object.len();
+    file.meta
+        .insert("peeringdb".to_string(), Value::Object(object));
+    count
+}
+
+/// Add an `asn_info` object to a summary, with the name and country of every ASN in its records
+/// that is known to the AS information dataset. Returns the number of ASNs added.
+pub fn attach_asn_info(summary: &mut Value, asn_info: &AsnInfoData) -> usize {
+    let Value::Object(fields) = summary else {
+        return 0;
+    };
+    let asns: BTreeSet<u32> = fields
+        .iter()
+        .filter(|(k, _)| !NON_DATASET_ARRAYS.contains(&k.as_str()))
+        .filter_map(|(_, v)| v.as_array())
+        .flat_map(|records| records_asns(records))
+        .collect();
+    let object = asn_object(asns, &asn_info.asns);
+    let count = object.len();
+    fields.insert("asn_info".to_string(), Value::Object(object));
+    count
+}
+
+/// ASNs of output records, from the same fields [OutputFile] filters on.
+fn records_asns(records: &[Value]) -> BTreeSet<u32> {
+    records
+        .iter()
+        .flat_map(|record| ASN_FIELDS.iter().filter_map(|f| record.get(*f)?.as_u64()))
+        .filter_map(|asn| u32::try_from(asn).ok())
+        .collect()
+}
+
+/// JSON object of the given ASNs' data, keyed by ASN, skipping ASNs without data.
+fn asn_object<T: Serialize>(asns: BTreeSet<u32>, data: &HashMap<u32, T>) -> Map<String, Value> {
+    asns.into_iter()
+        .filter_map(|asn| Some((asn.to_string(), serde_json::to_value(data.get(&asn)?).ok()?)))
+        .collect()
+}
+
+/// Write PeeringDB-enriched variants of the summarized outputs of the given processors (all
+/// [ENRICHABLE_PROCESSORS] if empty) under an output root directory.
+///
+/// Summaries that are missing are skipped with a warning. Returns the paths written.
+pub fn enrich_summaries(
+    output_dir: &str,
+    processor_names: &[String],
+    peeringdb: &PeeringDb,
+) -> Result<Vec<String>> {
+    let names: Vec<String> = match processor_names.is_empty() {
+        true => ENRICHABLE_PROCESSORS
+            .iter()
+            .map(|n| n.to_string())
+            .collect(),
+        false => processor_names.to_vec(),
+    };
+
+    let mut written = vec![];
+    for name in names {
+        if !ENRICHABLE_PROCESSORS.contains(&name.as_str()) {
+            return Err(anyhow!("processor output cannot be enriched: {}", name));
+        }
+        let summary_path = get_summary_output_path(output_dir, name.as_str());
+        let mut file = match OutputFile::load(summary_path.as_str()) {
+            Ok(f) => f,
+            Err(e) => {
+                warn!(
+                    processor = name.as_str(),
+                    phase = "enrich",
+                    "skipping unavailable summary {}: {}",
+                    summary_path,
+                    e
+                );
+                continue;
+            }
+        };
+        let count = enrich_output(&mut file, peeringdb);
+        let output_path = get_enriched_output_path(output_dir, name.as_str());
+        let mut writer = oneio::get_writer(output_path.as_str())?;
+        serde_json::to_writer(&mut writer, &file.to_value())?;
+        info!(
+            processor = name.as_str(),
+            phase = "enrich",
+            "wrote {} with PeeringDB data of {} ASNs",
+            output_path,
+            count
+        );
+        written.push(output_path);
+    }
+    Ok(written)
+}
@@ -0,0 +1,156 @@
+//! Exports of summaries in the schemas of BGPKIT's public as2rel data.
+//!
+//! BGPKIT's as2rel files and the API endpoints backed by them serve AS relationships as a JSON
+//! array of `{asn1, asn2, paths_count, peers_count, rel}` records, and the peer counts next to
+//! them in the original peer-stats layout. The summaries written by current ribeye versions carry
+//! more fields (confidence scores, IXP tags, reverse path counts, full-feed flags, ...), so
+//! [export_bgpkit] writes variants of the as2rel and peer-stats summaries restricted to those
+//! schemas next to the originals (`<dir>/<processor>/latest-bgpkit.json.bz2`), which can back the
+//! APIs as drop-in data:
+//!
+//! - as2rel: [BgpkitAs2relEntry] records sorted by `asn1`, `asn2` and `rel`, without
+//!   relationships tagged as involving an IXP route server
+//! - peer-stats: a [BgpkitPeerStatsJson] object with the summarized RIB dumps and
+//!   [BgpkitPeerEntry] records sorted by collector and IP address
+
+use crate::processors::readers::{read_as2rel_summary, read_peer_stats_summary};
+use crate::processors::{get_summary_output_path, As2relSummaryJson, PeerInfoSummaryJson};
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use std::net::IpAddr;
+use tracing::{info, warn};
+
+/// Processors whose summaries can be exported.
+pub const BGPKIT_EXPORT_PROCESSORS: [&str; 2] = ["as2rel", "peer-stats"];
+
+/// AS relationship record of BGPKIT's as2rel data.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BgpkitAs2relEntry {
+    pub asn1: u32,
+    pub asn2: u32,
+    pub paths_count: usize,
+    pub peers_count: usize,
+    /// 0 for an adjacency, 1 if `asn1` is a provider of `asn2`
+    pub rel: u8,
+}
+
+/// Route collector peer record of BGPKIT's peer-stats data.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BgpkitPeerEntry {
+    pub ip: IpAddr,
+    pub collector: Option<String>,
+    pub asn: u32,
+    pub num_v4_pfxs: usize,
+    pub num_v6_pfxs: usize,
+    pub num_connected_asns: usize,
+    pub has_v4_default: bool,
+    pub has_v6_default: bool,
+}
+
+/// Peer-stats summary in BGPKIT's peer-stats layout.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BgpkitPeerStatsJson {
+    pub rib_dump_urls: Vec<String>,
+    pub peers: Vec<BgpkitPeerEntry>,
+}
+
+/// Path of the export of a processor's summary under an output root directory.
+pub fn get_bgpkit_export_path(output_dir: &str, processor_name: &str) -> String {
+    format!("{}/{}/latest-bgpkit.json.bz2", output_dir, processor_name)
+}
+
+/// as2rel records of a summary in BGPKIT's schema.
+pub fn bgpkit_as2rel(summary: &As2relSummaryJson) -> Vec<BgpkitAs2relEntry> {
+    let mut entries: Vec<BgpkitAs2relEntry> = summary
+        .as2rel
+        .iter()
+        .filter(|e| !e.ixp)
+        .map(|e| BgpkitAs2relEntry {
+            asn1: e.asn1,
+            asn2: e.asn2,
+            paths_count: e.paths_count,
+            peers_count: e.peers_count,
+            rel: e.rel,
+        })
+        .collect();
+    entries.sort_by_key(|e| (e.asn1, e.asn2, e.rel));
+    entries
+}
+
+/// Peer-stats summary in BGPKIT's layout.
+pub fn bgpkit_peer_stats(summary: &PeerInfoSummaryJson) -> BgpkitPeerStatsJson {
+    let mut peers: Vec<BgpkitPeerEntry> = summary
+        .peers
+        .iter()
+        .map(|p| BgpkitPeerEntry {
+            ip: p.ip,
+            collector: p.collector.clone(),
+            asn: p.asn,
+            num_v4_pfxs: p.num_v4_pfxs,
+            num_v6_pfxs: p.num_v6_pfxs,
+            num_connected_asns: p.num_connected_asns,
+            has_v4_default: p.has_v4_default,
+            has_v6_default: p.has_v6_default,
+        })
+        .collect();
+    peers.sort_by(|a, b| (&a.collector, a.ip).cmp(&(&b.collector, b.ip)));
+    BgpkitPeerStatsJson {
+        rib_dump_urls: summary.rib_dump_urls.clone(),
+        peers,
+    }
+}
+
+/// Write the exports of the summaries of the given processors (all [BGPKIT_EXPORT_PROCESSORS] if
+/// empty) under an output root directory.
+///
+/// Summaries that are missing are skipped with a warning. Returns the paths written.
+pub fn export_bgpkit(output_dir: &str, processor_names: &[String]) -> Result<Vec<String>> {
+    let names: Vec<String> = match processor_names.is_empty() {
+        true => BGPKIT_EXPORT_PROCESSORS
+            .iter()
+            .map(|n| n.to_string())
+            .collect(),
+        false => processor_names.to_vec(),
+    };
+
+    let mut written = vec![];
+    for name in names {
+        let summary_path = get_summary_output_path(output_dir, name.as_str());
+        let exported = match name.as_str() {
+            "as2rel" => read_as2rel_summary(summary_path.as_str()).map(|s| {
+                let entries = bgpkit_as2rel(&s);
+                (entries.len(), serde_json::to_value(entries))
+            }),
+            "peer-stats" => read_peer_stats_summary(summary_path.as_str()).map(|s| {
+                let peer_stats = bgpkit_peer_stats(&s);
+                (peer_stats.peers.len(), serde_json::to_value(peer_stats))
+            }),
+            _ => return Err(anyhow!("processor output cannot be exported: {}", name)),
+        };
+        let (count, value) = match exported {
+            Ok((count, value)) => (count, value?),
+            Err(e) => {
+                warn!(
+                    processor = name.as_str(),
+                    phase = "export",
+                    "skipping unavailable summary {}: {}",
+                    summary_path,
+                    e
+                );
+                continue;
+            }
+        };
+        let output_path = get_bgpkit_export_path(output_dir, name.as_str());
+        let mut writer = oneio::get_writer(output_path.as_str())?;
+        serde_json::to_writer(&mut writer, &value)?;
+        info!(
+            processor = name.as_str(),
+            phase = "export",
+            "wrote {} with {} records",
+            output_path,
+            count
+        );
+        written.push(output_path);
+    }
+    Ok(written)
+}
@@ -0,0 +1,177 @@
+//! Feeds of raised alerts.
+//!
+//! [FeedHook] is a [NotificationHook] keeping the most recent alerts in a [JSON
+//! Feed](https://www.jsonfeed.org/version/1.1/) file, newest first, and optionally rendering them
+//! as an Atom feed. Both are plain files, local or on an object store, so published next to the
+//! outputs they can be subscribed to with any feed reader. Each item carries the alert itself in a
+//! `_ribeye` extension object.
+//!
+//! The JSON Feed is read back and extended on every notification; the Atom feed is rendered from
+//! it.
+
+use crate::alert::{Alert, NotificationHook};
+use crate::processors::{FileSink, OutputSink};
+use anyhow::Result;
+use chrono::{DateTime, SecondsFormat, Utc};
+use serde::{Deserialize, Serialize};
+use std::fmt::Write;
+
+/// Default number of alerts kept in a feed.
+pub const DEFAULT_FEED_MAX_ITEMS: usize = 100;
+/// Default title of a feed.
+pub const DEFAULT_FEED_TITLE: &str = "ribeye alerts";
+
+const JSON_FEED_VERSION: &str = "https://jsonfeed.org/version/1.1";
+
+/// A JSON Feed document.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct JsonFeed {
+    pub version: String,
+    pub title: String,
+    /// items, newest first
+    pub items: Vec<FeedItem>,
+}
+
+/// An item of a feed, one raised alert.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FeedItem {
+    pub id: String,
+    pub title: String,
+    pub content_text: String,
+    /// RFC 3339 time the alert was raised
+    pub date_published: String,
+    #[serde(rename = "_ribeye", default, skip_serializing_if = "Option::is_none")]
+    pub alert: Option<Alert>,
+}
+
+impl FeedItem {
+    /// Item of an alert raised at `time`, `index` telling apart the alerts raised together.
+    pub fn new(alert: &Alert, time: DateTime<Utc>, index: usize) -> Self {
+        let title = match &alert.collector {
+            Some(collector) => format!("{} at {}", alert.rule, collector),
+            None => format!("{} in {}", alert.rule, alert.processor),
+        };
+        FeedItem {
+            id: format!("urn:ribeye:alert:{}:{}", time.timestamp(), index),
+            title,
+            content_text: alert.message.clone(),
+            date_published: time.to_rfc3339_opts(SecondsFormat::Secs, true),
+            alert: Some(alert.clone()),
+        }
+    }
+}
+
+impl JsonFeed {
+    pub fn new(title: &str) -> Self {
+        JsonFeed {
+            version: JSON_FEED_VERSION.to_string(),
+            title: title.to_string(),
+            items: vec![],
+        }
+    }
+
+    /// Render the feed as an Atom document.
+    pub fn to_atom(&self) -> String {
+        let updated = self
+            .items
+            .first()
+            .map(|i| i.date_published.clone())
+            .unwrap_or_else(|| Utc::now().to_rfc3339_opts(SecondsFormat::Secs, true));
+        let mut atom = String::new();
+        atom.push_str("<?xml version=\"1.0\" encoding=\"utf-8\"?>\n");
+        atom.push_str("<feed xmlns=\"http://www.w3.org/2005/Atom\">\n");
+        let _ = writeln!(atom, "  <title>{}</title>", escape(self.title.as_str()));
+        atom.push_str("  <id>urn:ribeye:alerts</id>\n");
+        let _ = writeln!(atom, "  <updated>{}</updated>", updated);
+        atom.push_str("  <author><name>ribeye</name></author>\n");
+        for item in &self.items {
+            atom.push_str("  <entry>\n");
+            let _ = writeln!(atom, "    <id>{}</id>", escape(item.id.as_str()));
+            let _ = writeln!(atom, "    <title>{}</title>", escape(item.title.as_str()));
+            let _ = writeln!(atom, "    <updated>{}</updated>", item.date_published);
+            let _ = writeln!(
+                atom,
+                "    <content type=\"text\">{}</content>",
+                escape(item.content_text.as_str())
+            );
+            atom.push_str("  </entry>\n");
+        }
+        atom.push_str("</feed>\n");
+        atom
+    }
+}
+
+/// Keep raised alerts in a JSON Feed, and optionally an Atom feed, see the [module
+/// documentation](self).
+pub struct FeedHook {
+    path: String,
+    atom_path: Option<String>,
+    title: String,
+    max_items: usize,
+}
+
+impl FeedHook {
+    /// Keep the alerts in a JSON Feed at `path`.
+    pub fn new(path: &str) -> Self {
+        FeedHook {
+            path: path.to_string(),
+            atom_path: None,
+            title: DEFAULT_FEED_TITLE.to_string(),
+            max_items: DEFAULT_FEED_MAX_ITEMS,
+        }
+    }
+
+    /// Also render the feed as Atom to `atom_path`.
+    pub fn with_atom_path(mut self, atom_path: &str) -> Self {
+        self.atom_path = Some(atom_path.to_string());
+        self
+    }
+
+    pub fn with_title(mut self, title: &str) -> Self {
+        self.title = title.to_string();
+        self
+    }
+
+    /// Keep at most `max_items` alerts, dropping the oldest ones.
+    pub fn with_max_items(mut self, max_items: usize) -> Self {
+        self.max_items = max_items;
+        self
+    }
+
+    /// Current feed, empty if it does not exist yet.
+    pub fn read(&self) -> JsonFeed {
+        FileSink
+            .read_json(self.path.as_str())
+            .ok()
+            .and_then(|v| serde_json::from_value(v).ok())
+            .unwrap_or_else(|| JsonFeed::new(self.title.as_str()))
+    }
+}
+
+impl NotificationHook for FeedHook {
+    fn notify(&self, alerts: &[Alert]) -> Result<()> {
+        let mut feed = self.read();
+        feed.title = self.title.clone();
+        let now = Utc::now();
+        let mut items: Vec<FeedItem> = alerts
+            .iter()
+            .enumerate()
+            .map(|(i, alert)| FeedItem::new(alert, now, i))
+            .collect();
+        items.append(&mut feed.items);
+        items.truncate(self.max_items);
+        feed.items = items;
+        FileSink.write_json(self.path.as_str(), &serde_json::to_value(&feed)?)?;
+        if let Some(atom_path) = &self.atom_path {
+            FileSink.write_bytes(atom_path.as_str(), feed.to_atom().as_bytes())?;
+        }
+        Ok(())
+    }
+}
+
+fn escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
@@ -0,0 +1,120 @@
+//! Collector health reports derived from peer-stats summaries.
+//!
+//! [collector_health] groups the peers of a `peer-stats` summary by collector and reports the
+//! number of peers and full-feed peers per address family. Compared with the previous summary, it
+//! also lists the number of peers lost and gained, and flags collectors whose peer count dropped
+//! by more than a given ratio, which usually indicates a collector or data collection problem.
+
+use crate::processors::peers::{DEFAULT_FULL_FEED_V4, DEFAULT_FULL_FEED_V6};
+use crate::processors::{PeerInfoEntry, PeerInfoSummaryJson};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashSet};
+use std::net::IpAddr;
+
+/// Default ratio of lost peers above which a collector is flagged.
+pub const DEFAULT_PEER_DROP_RATIO: f64 = 0.2;
+
+/// Collector name used for peers without a known collector.
+const UNKNOWN_COLLECTOR: &str = "unknown";
+
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CollectorHealthEntry {
+    pub collector: String,
+    pub peers_count: usize,
+    /// number of peers announcing at least the full-feed number of IPv4 prefixes
+    pub full_feed_v4: usize,
+    /// number of peers announcing at least the full-feed number of IPv6 prefixes
+    pub full_feed_v6: usize,
+    /// number of peers in the previous summary, if any
+    pub previous_peers_count: Option<usize>,
+    /// number of peers of the previous summary no longer present
+    pub peers_lost: usize,
+    /// number of peers not present in the previous summary
+    pub peers_gained: usize,
+    /// whether the peer count dropped by more than the drop ratio since the previous summary
+    pub dropped: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CollectorHealthJson {
+    pub rib_dump_urls: Vec<String>,
+    pub drop_ratio: f64,
+    pub collectors: Vec<CollectorHealthEntry>,
+}
+
+/// Path of the health report under an output root directory, next to the peer-stats summary.
+pub fn get_health_report_path(output_dir: &str) -> String {
+    format!("{}/peer-stats/latest-health.json.bz2", output_dir)
+}
+
+/// Peers of a summary grouped by collector, identified by IP address and ASN.
+fn collector_peers(summary: &PeerInfoSummaryJson) -> BTreeMap<&str, Vec<&PeerInfoEntry>> {
+    let mut collectors: BTreeMap<&str, Vec<&PeerInfoEntry>> = BTreeMap::new();
+    for peer in &summary.peers {
+        let collector = peer.collector.as_deref().unwrap_or(UNKNOWN_COLLECTOR);
+        collectors.entry(collector).or_default().push(peer);
+    }
+    collectors
+}
+
+/// Build the health report of the collectors of a peer-stats summary, compared with the previous
+/// summary if available. Collectors only present in the previous summary are reported with no
+/// peers.
+pub fn collector_health(
+    summary: &PeerInfoSummaryJson,
+    previous: Option<&PeerInfoSummaryJson>,
+    drop_ratio: f64,
+) -> CollectorHealthJson {
+    let current = collector_peers(summary);
+    let previous = previous.map(collector_peers);
+
+    let mut names: Vec<&str> = current.keys().copied().collect();
+    if let Some(previous) = &previous {
+        names.extend(previous.keys().filter(|c| !current.contains_key(*c)));
+        names.sort();
+    }
+
+    let collectors = names
+        .into_iter()
+        .map(|collector| {
+            let peers = current
+                .get(collector)
+                .map(Vec::as_slice)
+                .unwrap_or_default();
+            let mut entry = CollectorHealthEntry {
+                collector: collector.to_string(),
+                peers_count: peers.len(),
+                full_feed_v4: peers
+                    .iter()
+                    .filter(|p| p.num_v4_pfxs as u64 >= DEFAULT_FULL_FEED_V4)
+                    .count(),
+                full_feed_v6: peers
+                    .iter()
+                    .filter(|p| p.num_v6_pfxs as u64 >= DEFAULT_FULL_FEED_V6)
+                    .count(),
+                ..Default::default()
+            };
+            if let Some(previous) = &previous {
+                let ids = |peers: &[&PeerInfoEntry]| -> HashSet<(IpAddr, u32)> {
+                    peers.iter().map(|p| (p.ip, p.asn)).collect()
+                };
+                let before = ids(previous
+                    .get(collector)
+                    .map(Vec::as_slice)
+                    .unwrap_or_default());
+                let after = ids(peers);
+                entry.previous_peers_count = Some(before.len());
+                entry.peers_lost = before.difference(&after).count();
+                entry.peers_gained = after.difference(&before).count();
+                entry.dropped = (after.len() as f64) < before.len() as f64 * (1.0 - drop_ratio);
+            }
+            entry
+        })
+        .collect();
+
+    CollectorHealthJson {
+        rib_dump_urls: summary.rib_dump_urls.clone(),
+        drop_ratio,
+        collectors,
+    }
+}
@@ -0,0 +1,314 @@
+//! Hijack-candidate detection over summarized outputs.
+//!
+//! [find_hijack_candidates] cross-references the prefix-origin pairs of a `pfx2as` summary with
+//! three signals and ranks the suspicious pairs by a combined score:
+//!
+//! - MOAS conflicts: the prefix is also originated by other ASes none of which has a known
+//!   relationship with the origin in the `as2rel` summary; conflicts between related ASes (e.g. a
+//!   customer and its provider) are common and not flagged
+//! - RPKI-invalid announcements: the origin is not authorized by any VRP covering the prefix
+//! - unusual upstreams: the origin has no inferred provider in the `as2rel` summary
+//!
+//! Only pairs with a MOAS conflict or an RPKI-invalid origin are candidates; the other signals add
+//! to their score. The report is written as the `hijacks` dataset.
+
+use crate::processors::readers::{read_as2rel_summary, read_pfx2as_summary};
+use crate::processors::{
+    get_summary_output_path, As2relEntry, As2relSummaryJson, FileSink, OutputSink, Prefix2AsCount,
+    Prefix2AsSummaryJson,
+};
+use anyhow::{anyhow, Result};
+use ipnet::IpNet;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::str::FromStr;
+use tracing::{info, warn};
+
+/// Score of an RPKI-invalid announcement.
+const RPKI_INVALID_SCORE: f64 = 0.4;
+/// Score of a MOAS conflict with unrelated origins.
+const MOAS_SCORE: f64 = 0.3;
+/// Maximum score of a MOAS origin observed by few peers compared with the other origins.
+const LOW_VISIBILITY_SCORE: f64 = 0.2;
+/// Score of an origin without any inferred provider.
+const NO_UPSTREAM_SCORE: f64 = 0.1;
+
+/// RPKI route origin validation state of a prefix-origin pair.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RpkiStatus {
+    Valid,
+    Invalid,
+    NotFound,
+}
+
+/// Validated ROA payloads, indexed by prefix.
+#[derive(Debug, Default, Clone)]
+pub struct Vrps {
+    /// maximum length and authorized ASN of the VRPs of each prefix
+    vrps: HashMap<IpNet, Vec<(u8, u32)>>,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum VrpAsn {
+    Number(u32),
+    /// `AS13335`
+    Text(String),
+}
+
+#[derive(Deserialize)]
+struct VrpRecord {
+    prefix: String,
+    #[serde(alias = "maxLength", alias = "max_len")]
+    max_length: Option<u8>,
+    asn: VrpAsn,
+}
+
+fn parse_asn(asn: &str) -> Option<u32> {
+    let asn = asn.trim();
+    let asn = asn
+        .strip_prefix("AS")
+        .or_else(|| asn.strip_prefix("as"))
+        .unwrap_or(asn);
+    u32::from_str(asn).ok()
+}
+
+impl Vrps {
+    /// Load VRPs, local or remote, see [Vrps::parse].
+    pub fn load(path: &str) -> Result<Self> {
+        let content = oneio::read_to_string(path)
+            .map_err(|e| anyhow!("failed to read VRPs {}: {}", path, e))?;
+        Self::parse(content.as_str())
+    }
+
+    /// Parse VRPs exported by a relying party, either as JSON (`{"roas": [{"asn": "AS13335",
+    /// "prefix": "1.1.1.0/24", "maxLength": 24}]}`, as produced by rpki-client or Routinator) or as
+    /// CSV (`ASN,IP Prefix,Max Length[,Trust Anchor]` lines).
+    pub fn parse(content: &str) -> Result<Self> {
+        let mut vrps = Vrps::default();
+        if content.trim_start().starts_with('{') {
+            let value: Value = serde_json::from_str(content)?;
+            let roas = value
+                .get("roas")
+                .cloned()
+                .ok_or_else(|| anyhow!("no roas array in VRP file"))?;
+            for record in Vec::<VrpRecord>::deserialize(roas)? {
+                let asn = match record.asn {
+                    VrpAsn::Number(asn) => Some(asn),
+                    VrpAsn::Text(asn) => parse_asn(asn.as_str()),
+                }
+                .ok_or_else(|| anyhow!("invalid VRP ASN for prefix {}", record.prefix))?;
+                let prefix = IpNet::from_str(record.prefix.as_str())?;
+                vrps.insert(prefix, record.max_length, asn);
+            }
+        } else {
+            for line in content.lines() {
+                let fields: Vec<&str> = line.split(',').map(|f| f.trim()).collect();
+                if fields.len() < 3 {
+                    continue;
+                }
+                // skip the header line
+                let (Some(asn), Ok(prefix)) = (parse_asn(fields[0]), IpNet::from_str(fields[1]))
+                else {
+                    continue;
+                };
+                let max_length =
+                    u8::from_str(fields[2]).map_err(|_| anyhow!("invalid VRP line: {}", line))?;
+                vrps.insert(prefix, Some(max_length), asn);
+            }
+        }
+        Ok(vrps)
+    }
+
+    fn insert(&mut self, prefix: IpNet, max_length: Option<u8>, asn: u32) {
+        let max_length = max_length.unwrap_or(prefix.prefix_len());
+        self.vrps
+            .entry(prefix.trunc())
+            .or_default()
+            .push((max_length, asn));
+    }
+
+    /// Route origin validation of an announced prefix and origin (RFC 6811).
+    pub fn validate(&self, prefix: &IpNet, asn: u32) -> RpkiStatus {
+        let mut covered = false;
+        let mut covering = Some(prefix.trunc());
+        while let Some(net) = covering {
+            if let Some(vrps) = self.vrps.get(&net) {
+                covered = true;
+                let authorized = vrps.iter().any(|(max_length, vrp_asn)| {
+                    *vrp_asn == asn && asn != 0 && prefix.prefix_len() <= *max_length
+                });
+                if authorized {
+                    return RpkiStatus::Valid;
+                }
+            }
+            covering = net.supernet();
+        }
+        match covered {
+            true => RpkiStatus::Invalid,
+            false => RpkiStatus::NotFound,
+        }
+    }
+
+    /// Number of VRPs.
+    pub fn len(&self) -> usize {
+        self.vrps.values().map(Vec::len).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.vrps.is_empty()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HijackCandidate {
+    pub prefix: String,
+    pub asn: u32,
+    /// combined score between 0 and 1, higher is more suspicious
+    pub score: f64,
+    /// signals contributing to the score: `rpki_invalid`, `moas`, `low_visibility`,
+    /// `no_known_upstream`
+    pub reasons: Vec<String>,
+    /// other origins of the prefix
+    pub moas_origins: Vec<u32>,
+    /// RPKI validation state, not set without VRPs
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rpki: Option<RpkiStatus>,
+    /// number of RIB entries the prefix-origin pair was observed in
+    pub count: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HijackReportJson {
+    pub rib_dump_urls: Vec<String>,
+    /// candidates, most suspicious first
+    pub hijacks: Vec<HijackCandidate>,
+}
+
+/// Path of the hijack-candidate report under an output root directory.
+pub fn get_hijack_report_path(output_dir: &str) -> String {
+    format!("{}/hijacks/latest.json.bz2", output_dir)
+}
+
+/// Rank the suspicious prefix-origin pairs of a pfx2as summary, see the [module
+/// documentation](self). Without as2rel data, all MOAS origins are considered unrelated and
+/// upstreams are not checked; without VRPs, RPKI validity is not checked.
+pub fn find_hijack_candidates(
+    pfx2as: &Prefix2AsSummaryJson,
+    as2rel: Option<&As2relSummaryJson>,
+    vrps: Option<&Vrps>,
+) -> HijackReportJson {
+    let mut related: HashSet<(u32, u32)> = HashSet::new();
+    let mut has_provider: HashSet<u32> = HashSet::new();
+    for entry in as2rel.map(|s| s.as2rel.as_slice()).unwrap_or_default() {
+        let As2relEntry { asn1, asn2, .. } = *entry;
+        related.insert((asn1.min(asn2), asn1.max(asn2)));
+        if entry.rel == 1 {
+            // asn1 is the inferred provider of asn2
+            has_provider.insert(asn2);
+        }
+    }
+
+    let mut prefix_origins: HashMap<&str, Vec<&Prefix2AsCount>> = HashMap::new();
+    for entry in &pfx2as.pfx2as {
+        prefix_origins
+            .entry(entry.prefix.as_str())
+            .or_default()
+            .push(entry);
+    }
+
+    let mut candidates = vec![];
+    for (prefix, origins) in prefix_origins {
+        let Ok(net) = IpNet::from_str(prefix) else {
+            continue;
+        };
+        let total_count: usize = origins.iter().map(|o| o.count).sum();
+        for origin in &origins {
+            let others: BTreeSet<u32> = origins
+                .iter()
+                .map(|o| o.asn)
+                .filter(|asn| *asn != origin.asn)
+                .collect();
+            let rpki = vrps.map(|v| v.validate(&net, origin.asn));
+
+            let mut score = 0.0;
+            let mut reasons = vec![];
+            if rpki == Some(RpkiStatus::Invalid) {
+                score += RPKI_INVALID_SCORE;
+                reasons.push("rpki_invalid");
+            }
+            let moas = !others.is_empty()
+                && others.iter().all(|other| {
+                    !related.contains(&(origin.asn.min(*other), origin.asn.max(*other)))
+                });
+            if moas {
+                score += MOAS_SCORE;
+                reasons.push("moas");
+                // origins seen by a minority of the peers are more suspicious
+                let share = origin.count as f64 / total_count.max(1) as f64;
+                if share < 0.5 {
+                    score += LOW_VISIBILITY_SCORE * (1.0 - 2.0 * share);
+                    reasons.push("low_visibility");
+                }
+            }
+            if reasons.is_empty() {
+                continue;
+            }
+            if as2rel.is_some() && !has_provider.contains(&origin.asn) {
+                score += NO_UPSTREAM_SCORE;
+                reasons.push("no_known_upstream");
+            }
+            candidates.push(HijackCandidate {
+                prefix: prefix.to_string(),
+                asn: origin.asn,
+                score: (score * 1000.0).round() / 1000.0,
+                reasons: reasons.into_iter().map(|r| r.to_string()).collect(),
+                moas_origins: others.into_iter().collect(),
+                rpki,
+                count: origin.count,
+            });
+        }
+    }
+    candidates.sort_by(|a, b| {
+        b.score
+            .total_cmp(&a.score)
+            .then_with(|| a.prefix.cmp(&b.prefix))
+            .then(a.asn.cmp(&b.asn))
+    });
+
+    HijackReportJson {
+        rib_dump_urls: pfx2as.rib_dump_urls.clone(),
+        hijacks: candidates,
+    }
+}
+
+/// Build the hijack-candidate report from the pfx2as and, if available, as2rel summaries under an
+/// output root directory, and write it to [get_hijack_report_path]. Returns the written report.
+pub fn analyze_hijacks(output_dir: &str, vrps: Option<&Vrps>) -> Result<HijackReportJson> {
+    let pfx2as_path = get_summary_output_path(output_dir, "pfx2as");
+    let pfx2as = read_pfx2as_summary(pfx2as_path.as_str())?;
+    let as2rel_path = get_summary_output_path(output_dir, "as2rel");
+    let as2rel = match read_as2rel_summary(as2rel_path.as_str()) {
+        Ok(s) => Some(s),
+        Err(e) => {
+            warn!(
+                phase = "hijacks",
+                "as2rel summary {} unavailable, skipping relationship checks: {}", as2rel_path, e
+            );
+            None
+        }
+    };
+
+    let report = find_hijack_candidates(&pfx2as, as2rel.as_ref(), vrps);
+    let output_path = get_hijack_report_path(output_dir);
+    FileSink.write_json(output_path.as_str(), &serde_json::to_value(&report)?)?;
+    info!(
+        phase = "hijacks",
+        "wrote {} with {} hijack candidates",
+        output_path,
+        report.hijacks.len()
+    );
+    Ok(report)
+}
@@ -0,0 +1,186 @@
+//! Browsable index of an output root directory.
+//!
+//! [build_output_index] lists the files under an output root directory (local or S3) and groups
+//! them like BGPKIT's public data endpoints: per dataset (processor), with the summarized latest
+//! file and its variants (e.g. `latest-peeringdb.json.bz2`), and per collector, with the latest
+//! file and the dated outputs. [write_output_index] writes a root `index.json` listing the datasets
+//! and one `<dataset>/index.json` per dataset with its collectors and files, so that a static file
+//! server or S3 bucket of outputs can be browsed without listing support.
+//!
+//! Paths in the index are relative to the output root directory.
+
+use crate::listing::{list_files, parse_output_path};
+use crate::processors::{FileSink, OutputSink};
+use crate::timeseries::TIMESERIES_DIR;
+use anyhow::Result;
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use tracing::info;
+
+/// File name of the root and per-dataset indexes.
+pub const INDEX_FILE_NAME: &str = "index.json";
+
+/// A dated output of a collector.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IndexedFile {
+    pub path: String,
+    /// RIB dump Unix timestamp, from the file name
+    pub ts: i64,
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CollectorIndex {
+    pub collector: String,
+    /// latest output of the collector, if any
+    pub latest: Option<String>,
+    /// dated outputs, oldest first
+    pub files: Vec<IndexedFile>,
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DatasetIndex {
+    pub name: String,
+    /// summarized latest output, if any
+    pub latest: Option<String>,
+    /// other summary-level files, e.g. enriched or derived outputs
+    pub variants: Vec<String>,
+    pub collectors: Vec<CollectorIndex>,
+}
+
+/// Entry of the root index.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DatasetSummary {
+    pub name: String,
+    pub latest: Option<String>,
+    pub variants: Vec<String>,
+    /// path of the dataset's index
+    pub index: String,
+    pub collectors: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RootIndex {
+    /// RFC 3339 time the index was written
+    pub updated_at: String,
+    pub datasets: Vec<DatasetSummary>,
+}
+
+/// Index the outputs under an output root directory. Index files themselves, detached signatures,
+/// leases, time-series datasets, partial outputs and files not following the output layout are
+/// left out.
+pub fn build_output_index(output_dir: &str) -> Result<Vec<DatasetIndex>> {
+    let mut datasets: BTreeMap<String, DatasetIndex> = BTreeMap::new();
+    let mut paths = list_files(output_dir)?;
+    paths.sort();
+    for path in paths {
+        if path.ends_with(".minisig")
+            || path.ends_with(".lock")
+            || path.split('/').next() == Some(TIMESERIES_DIR)
+        {
+            continue;
+        }
+        let segments: Vec<&str> = path.split('/').collect();
+        let dataset = |name: &str| DatasetIndex {
+            name: name.to_string(),
+            ..Default::default()
+        };
+        match segments.as_slice() {
+            [_, INDEX_FILE_NAME] | [INDEX_FILE_NAME] => {}
+            [name, "latest.json.bz2"] => {
+                datasets
+                    .entry(name.to_string())
+                    .or_insert_with(|| dataset(name))
+                    .latest = Some(path.clone());
+            }
+            [name, file] if file.starts_with("latest") => {
+                let entry = datasets
+                    .entry(name.to_string())
+                    .or_insert_with(|| dataset(name));
+                entry.variants.push(path.clone());
+            }
+            _ => {
+                let Some(output) = parse_output_path(path.as_str()) else {
+                    continue;
+                };
+                let entry = datasets
+                    .entry(output.processor.clone())
+                    .or_insert_with(|| dataset(output.processor.as_str()));
+                let collector_index = match entry
+                    .collectors
+                    .iter_mut()
+                    .position(|c| c.collector == output.collector)
+                {
+                    Some(i) => &mut entry.collectors[i],
+                    None => {
+                        entry.collectors.push(CollectorIndex {
+                            collector: output.collector.clone(),
+                            ..Default::default()
+                        });
+                        entry.collectors.last_mut().unwrap()
+                    }
+                };
+                match output.ts {
+                    Some(ts) => collector_index.files.push(IndexedFile {
+                        path: path.clone(),
+                        ts,
+                    }),
+                    None => collector_index.latest = Some(path.clone()),
+                }
+            }
+        }
+    }
+
+    let mut datasets: Vec<DatasetIndex> = datasets.into_values().collect();
+    for dataset in &mut datasets {
+        dataset
+            .collectors
+            .sort_by(|a, b| a.collector.cmp(&b.collector));
+        for collector in &mut dataset.collectors {
+            collector.files.sort_by_key(|f| f.ts);
+        }
+    }
+    Ok(datasets)
+}
+
+/// Write the root and per-dataset indexes of an output root directory, see the [module
+/// documentation](self). Returns the number of indexed datasets.
+pub fn write_output_index(output_dir: &str) -> Result<usize> {
+    let output_dir = output_dir.trim_end_matches('/');
+    let datasets = build_output_index(output_dir)?;
+
+    let mut summaries = vec![];
+    for dataset in &datasets {
+        let index = format!("{}/{}", dataset.name, INDEX_FILE_NAME);
+        FileSink.write_json(
+            format!("{}/{}", output_dir, index).as_str(),
+            &serde_json::to_value(dataset)?,
+        )?;
+        summaries.push(DatasetSummary {
+            name: dataset.name.clone(),
+            latest: dataset.latest.clone(),
+            variants: dataset.variants.clone(),
+            index,
+            collectors: dataset
+                .collectors
+                .iter()
+                .map(|c| c.collector.clone())
+                .collect(),
+        });
+    }
+    let root = RootIndex {
+        updated_at: Utc::now().to_rfc3339(),
+        datasets: summaries,
+    };
+    FileSink.write_json(
+        format!("{}/{}", output_dir, INDEX_FILE_NAME).as_str(),
+        &serde_json::to_value(&root)?,
+    )?;
+    info!(
+        phase = "index",
+        "wrote index of {} datasets under {}",
+        datasets.len(),
+        output_dir
+    );
+    Ok(datasets.len())
+}
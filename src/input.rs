@@ -0,0 +1,85 @@
+//! MRT input sources.
+//!
+//! Besides local and remote file paths (handled by `oneio`), RibEye can also read MRT data from
+//! standard input, which allows chaining it behind custom downloaders or `mrt-cat`-style tools.
+//! Since a pipe carries no file extension, the compression of a stdin stream must be given
+//! explicitly.
+
+use anyhow::{anyhow, Result};
+use std::fmt::{Display, Formatter};
+use std::io::{BufReader, Read};
+use std::str::FromStr;
+
+/// Path value that denotes reading MRT data from standard input.
+pub const STDIN_PATH: &str = "-";
+
+/// Compression of an MRT stream read from standard input.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Compression {
+    /// uncompressed MRT data
+    #[default]
+    None,
+    /// gzip-compressed MRT data (RIPE RIS archives)
+    Gzip,
+    /// bzip2-compressed MRT data (RouteViews archives)
+    Bzip2,
+}
+
+impl Compression {
+    /// Compression of an MRT file by its extension, uncompressed if not gzip or bzip2.
+    pub fn from_path(path: &str) -> Self {
+        let path = path.split(['?', '#']).next().unwrap_or_default();
+        let ext = path.rsplit_once('.').map(|(_, ext)| ext.to_lowercase());
+        match ext.as_deref() {
+            Some("gz" | "gzip" | "tgz") => Compression::Gzip,
+            Some("bz" | "bz2") => Compression::Bzip2,
+            _ => Compression::None,
+        }
+    }
+}
+
+impl FromStr for Compression {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "none" | "raw" | "" => Ok(Compression::None),
+            "gz" | "gzip" => Ok(Compression::Gzip),
+            "bz" | "bz2" | "bzip2" => Ok(Compression::Bzip2),
+            _ => Err(anyhow!("unknown compression: {}", s)),
+        }
+    }
+}
+
+impl Display for Compression {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Compression::None => write!(f, "none"),
+            Compression::Gzip => write!(f, "gzip"),
+            Compression::Bzip2 => write!(f, "bzip2"),
+        }
+    }
+}
+
+/// Check if the given path refers to standard input.
+pub fn is_stdin(path: &str) -> bool {
+    path == STDIN_PATH
+}
+
+/// Wrap a raw reader with the decoder of the given compression.
+pub fn get_decompress_reader(
+    raw_reader: Box<dyn Read + Send>,
+    compression: Compression,
+) -> Box<dyn Read + Send> {
+    match compression {
+        Compression::None => raw_reader,
+        Compression::Gzip => Box::new(flate2::read::MultiGzDecoder::new(raw_reader)),
+        Compression::Bzip2 => Box::new(bzip2::read::MultiBzDecoder::new(raw_reader)),
+    }
+}
+
+/// Get a reader of MRT data from standard input.
+pub fn get_stdin_reader(compression: Compression) -> Box<dyn Read + Send> {
+    let raw_reader = Box::new(BufReader::new(std::io::stdin()));
+    get_decompress_reader(raw_reader, compression)
+}
@@ -0,0 +1,233 @@
+//! Inspection of ribeye output files.
+//!
+//! Every per-collector and summary output is a JSON object holding meta information fields and one
+//! dataset array of records (e.g. `pfx2as`, `as2rel`, `peers`). [OutputFile] loads any such file
+//! (local or remote, with any compression supported by `oneio`), filters its records and computes
+//! summary statistics, so results can be inspected without `bzcat | jq`.
+
+use crate::processors::csv::CsvTable;
+use crate::processors::schema::{processor_schemas, ProcessorSchemas};
+use crate::processors::{decode_content, FileSink, OutputSink};
+use crate::RibEye;
+use anyhow::{anyhow, Result};
+use ipnet::IpNet;
+use schemars::Schema;
+use serde::Serialize;
+use serde_json::{Map, Value};
+use std::collections::{BTreeMap, HashSet};
+
+/// A loaded ribeye output file.
+#[derive(Debug, Clone)]
+pub struct OutputFile {
+    /// non-dataset fields of the file, e.g. `collector` or `rib_dump_urls`
+    pub meta: Map<String, Value>,
+    /// name of the dataset field, e.g. `pfx2as`
+    pub dataset: String,
+    /// dataset records
+    pub records: Vec<Value>,
+}
+
+/// Record filter. Unset conditions match every record.
+#[derive(Debug, Default, Clone)]
+pub struct RecordFilter {
+    /// keep records whose `prefix` is the given prefix or a more-specific of it
+    pub prefix: Option<IpNet>,
+    /// keep records with any ASN field (`asn`, `asn1`, `asn2`, `collector_asn`) equal to it
+    pub asn: Option<u32>,
+    /// keep records observed at the given collector
+    pub collector: Option<String>,
+}
+
+/// Summary statistics of an output file.
+#[derive(Debug, Clone, Serialize)]
+pub struct OutputStats {
+    pub dataset: String,
+    pub records: usize,
+    /// number of distinct values per prefix/ASN/collector field
+    pub distinct_values: BTreeMap<String, usize>,
+    pub meta: Map<String, Value>,
+}
+
+/// Array fields that are not the dataset: meta information (`rib_dump_urls`, the summaries'
+/// `collector_dumps`) and views derived from the dataset (the `peer-stats` summary's `asns`).
+pub const NON_DATASET_ARRAYS: [&str; 3] = ["rib_dump_urls", "collector_dumps", "asns"];
+
+/// Record fields holding ASNs.
+pub const ASN_FIELDS: [&str; 4] = ["asn", "asn1", "asn2", "collector_asn"];
+const KEY_FIELDS: [&str; 7] = [
+    "prefix",
+    "asn",
+    "asn1",
+    "asn2",
+    "collector_asn",
+    "collector",
+    "ip",
+];
+
+/// Dataset field of a built-in processor's output or summary, see
+/// [TypedProcessor::DATASET_FIELD](crate::processors::TypedProcessor::DATASET_FIELD).
+///
+/// Some outputs carry other processors' dataset fields as secondary arrays (e.g. the `defaults`
+/// summary's `origins` or the `vantage` summary's `collectors`), so the processor whose output or
+/// summary schema has every array field of the file is preferred over the first processor whose
+/// dataset field is present.
+fn dataset_field(meta: &Map<String, Value>) -> Option<String> {
+    let candidates: Vec<ProcessorSchemas> = RibEye::available_processors()
+        .into_iter()
+        .filter_map(processor_schemas)
+        .filter(|s| meta.get(s.dataset.as_str()).is_some_and(|v| v.is_array()))
+        .collect();
+    let arrays: Vec<&String> = meta
+        .iter()
+        .filter(|(k, v)| !NON_DATASET_ARRAYS.contains(&k.as_str()) && v.is_array())
+        .map(|(k, _)| k)
+        .collect();
+    let has_all_fields = |schema: &Schema| {
+        let properties = schema
+            .as_value()
+            .get("properties")
+            .and_then(Value::as_object);
+        properties.is_some_and(|p| arrays.iter().all(|k| p.contains_key(k.as_str())))
+    };
+    candidates
+        .iter()
+        .find(|s| has_all_fields(&s.output) || has_all_fields(&s.summary))
+        .or(candidates.first())
+        .map(|s| s.dataset.clone())
+}
+
+impl OutputFile {
+    pub fn load(path: &str) -> Result<Self> {
+        let data = FileSink.read_bytes(path)?;
+        Self::from_value(decode_content(path, &data)?)
+    }
+
+    pub fn from_json_str(content: &str) -> Result<Self> {
+        Self::from_value(serde_json::from_str(content)?)
+    }
+
+    /// Output file of a decoded JSON or CBOR output.
+    pub fn from_value(value: Value) -> Result<Self> {
+        let Value::Object(mut meta) = value else {
+            return Err(anyhow!("output file content is not a JSON object"));
+        };
+
+        let dataset = dataset_field(&meta)
+            .or_else(|| {
+                meta.iter()
+                    .find(|(k, v)| !NON_DATASET_ARRAYS.contains(&k.as_str()) && v.is_array())
+                    .map(|(k, _)| k.clone())
+            })
+            .ok_or_else(|| anyhow!("no dataset array found in output file"))?;
+        let records = match meta.remove(dataset.as_str()) {
+            Some(Value::Array(records)) => records,
+            _ => vec![],
+        };
+
+        Ok(OutputFile {
+            meta,
+            dataset,
+            records,
+        })
+    }
+
+    fn record_matches(&self, record: &Value, filter: &RecordFilter) -> bool {
+        if let Some(filter_prefix) = &filter.prefix {
+            let prefix = record
+                .get("prefix")
+                .and_then(|p| p.as_str())
+                .and_then(|p| p.parse::<IpNet>().ok());
+            match prefix {
+                Some(p) if filter_prefix.contains(&p) => {}
+                _ => return false,
+            }
+        }
+
+        if let Some(asn) = filter.asn {
+            let matched = ASN_FIELDS
+                .iter()
+                .any(|f| record.get(*f).and_then(|v| v.as_u64()) == Some(asn as u64));
+            if !matched {
+                return false;
+            }
+        }
+
+        if let Some(collector) = &filter.collector {
+            // per-record collector takes precedence over the file-level one
+            let record_collector = record
+                .get("collector")
+                .or_else(|| self.meta.get("collector"))
+                .and_then(|c| c.as_str());
+            if record_collector != Some(collector.as_str()) {
+                return false;
+            }
+        }
+
+        true
+    }
+
+    /// Keep only records matching the filter.
+    pub fn filter(mut self, filter: &RecordFilter) -> Self {
+        let records = std::mem::take(&mut self.records);
+        self.records = records
+            .into_iter()
+            .filter(|r| self.record_matches(r, filter))
+            .collect();
+        self
+    }
+
+    pub fn stats(&self) -> OutputStats {
+        let mut distinct_values = BTreeMap::new();
+        for field in KEY_FIELDS {
+            let values: HashSet<String> = self
+                .records
+                .iter()
+                .filter_map(|r| r.get(field).map(|v| v.to_string()))
+                .collect();
+            if !values.is_empty() {
+                distinct_values.insert(field.to_string(), values.len());
+            }
+        }
+
+        OutputStats {
+            dataset: self.dataset.clone(),
+            records: self.records.len(),
+            distinct_values,
+            meta: self
+                .meta
+                .iter()
+                .map(|(k, v)| match v {
+                    // list of dump URLs can be long, only report its length
+                    Value::Array(a) => (k.clone(), Value::from(a.len())),
+                    v => (k.clone(), v.clone()),
+                })
+                .collect(),
+        }
+    }
+
+    /// The file content as a JSON object, same layout as the input file.
+    pub fn to_value(&self) -> Value {
+        let mut object = self.meta.clone();
+        object.insert(self.dataset.clone(), Value::from(self.records.clone()));
+        Value::Object(object)
+    }
+
+    /// The file content as a pretty-printed JSON object.
+    pub fn to_json_string(&self) -> Result<String> {
+        Ok(serde_json::to_string_pretty(&self.to_value())?)
+    }
+
+    /// The records as JSON lines, one record per line.
+    pub fn to_jsonl_string(&self) -> Result<String> {
+        let mut lines = vec![];
+        for record in &self.records {
+            lines.push(serde_json::to_string(record)?);
+        }
+        Ok(lines.join("\n"))
+    }
+
+    /// The records as CSV with a header row, key fields (e.g. `prefix`, `asn`) first.
+    pub fn to_csv_string(&self) -> String {
+        CsvTable::from_records(self.dataset.as_str(), &self.records, None).to_csv_string()
+    }
+}
@@ -0,0 +1,158 @@
+//! Export of summarized pfx2as data as an ip2asn-style TSV table.
+//!
+//! [ip2asn_ranges] flattens the announced prefixes of a `pfx2as` summary into disjoint address
+//! ranges, each mapped to the origin of its most specific covering prefix, in the format of the
+//! ip2asn and GeoLite ASN tables: `range_start`, `range_end`, `AS_number`, `country_code` and
+//! `AS_description`, tab-separated. Prefixes with several origins are mapped to the origin seen by
+//! the most peers. Unrouted address space is not listed.
+
+use crate::enrich::AsnInfoData;
+use crate::processors::Prefix2AsSummaryJson;
+use ipnet::IpNet;
+use std::collections::HashMap;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use std::str::FromStr;
+
+/// Country code of ASes without a known country, as in ip2asn tables.
+const UNKNOWN_COUNTRY: &str = "None";
+/// Description of ASes without a known name.
+const UNKNOWN_NAME: &str = "Unknown";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Ip2AsnRange {
+    pub start: IpAddr,
+    /// inclusive end
+    pub end: IpAddr,
+    pub asn: u32,
+    pub country: String,
+    pub name: String,
+}
+
+/// Path of the ip2asn table under an output root directory, next to the pfx2as summary.
+pub fn get_ip2asn_path(output_dir: &str) -> String {
+    format!("{}/pfx2as/latest-ip2asn.tsv.bz2", output_dir)
+}
+
+/// Disjoint address ranges of the prefixes of a pfx2as summary, IPv4 first, in address order.
+/// Adjacent ranges with the same origin are merged.
+pub fn ip2asn_ranges(
+    pfx2as: &Prefix2AsSummaryJson,
+    asn_info: Option<&AsnInfoData>,
+) -> Vec<Ip2AsnRange> {
+    // origin seen by the most peers for each prefix, the lowest ASN on ties
+    let mut origins: HashMap<IpNet, (usize, u32)> = HashMap::new();
+    for entry in &pfx2as.pfx2as {
+        let Ok(prefix) = IpNet::from_str(entry.prefix.as_str()) else {
+            continue;
+        };
+        let origin = origins
+            .entry(prefix.trunc())
+            .or_insert((entry.count, entry.asn));
+        if (entry.count, u32::MAX - entry.asn) > (origin.0, u32::MAX - origin.1) {
+            *origin = (entry.count, entry.asn);
+        }
+    }
+
+    let (mut v4, mut v6) = (vec![], vec![]);
+    for (prefix, (_, asn)) in origins {
+        match prefix {
+            IpNet::V4(p) => v4.push((
+                u32::from(p.network()) as u128,
+                u32::from(p.broadcast()) as u128,
+                asn,
+            )),
+            IpNet::V6(p) => v6.push((u128::from(p.network()), u128::from(p.broadcast()), asn)),
+        }
+    }
+
+    let info = |asn: u32| {
+        let info = asn_info.and_then(|data| data.asns.get(&asn));
+        let country = info
+            .map(|i| i.country.as_str())
+            .filter(|c| !c.is_empty())
+            .unwrap_or(UNKNOWN_COUNTRY);
+        let name = info
+            .map(|i| i.name.as_str())
+            .filter(|n| !n.is_empty())
+            .unwrap_or(UNKNOWN_NAME);
+        (country.to_string(), name.to_string())
+    };
+    let to_addr = |v6: bool, addr: u128| match v6 {
+        true => IpAddr::V6(Ipv6Addr::from(addr)),
+        false => IpAddr::V4(Ipv4Addr::from(addr as u32)),
+    };
+
+    let mut ranges = vec![];
+    for (is_v6, prefixes) in [(false, v4), (true, v6)] {
+        for (start, end, asn) in flatten(prefixes) {
+            let (country, name) = info(asn);
+            ranges.push(Ip2AsnRange {
+                start: to_addr(is_v6, start),
+                end: to_addr(is_v6, end),
+                asn,
+                country,
+                name,
+            });
+        }
+    }
+    ranges
+}
+
+/// Flatten nested or disjoint prefix ranges into disjoint ranges of their most specific prefixes.
+fn flatten(mut prefixes: Vec<(u128, u128, u32)>) -> Vec<(u128, u128, u32)> {
+    // less specifics first among prefixes with the same start
+    prefixes.sort_by(|a, b| a.0.cmp(&b.0).then(b.1.cmp(&a.1)));
+
+    let mut ranges: Vec<(u128, u128, u32)> = vec![];
+    let mut emit = |start: u128, end: u128, asn: u32| match ranges.last_mut() {
+        Some(last) if last.2 == asn && last.1.checked_add(1) == Some(start) => last.1 = end,
+        _ => ranges.push((start, end, asn)),
+    };
+
+    // enclosing prefixes, innermost last, and the first address not emitted yet
+    let mut stack: Vec<(u128, u32)> = vec![];
+    let mut cursor: u128 = 0;
+    for (start, end, asn) in prefixes {
+        while let Some(&(top_end, top_asn)) = stack.last() {
+            if top_end >= start {
+                break;
+            }
+            if cursor <= top_end {
+                emit(cursor, top_end, top_asn);
+            }
+            cursor = top_end.saturating_add(1);
+            stack.pop();
+        }
+        if let Some(&(_, top_asn)) = stack.last() {
+            if cursor < start {
+                emit(cursor, start - 1, top_asn);
+            }
+        }
+        cursor = start;
+        stack.push((end, asn));
+    }
+    while let Some((top_end, top_asn)) = stack.pop() {
+        if cursor <= top_end {
+            emit(cursor, top_end, top_asn);
+        }
+        cursor = top_end.saturating_add(1);
+    }
+    ranges
+}
+
+/// Format ranges as TSV lines, without a header line as in ip2asn tables.
+pub fn to_tsv(ranges: &[Ip2AsnRange]) -> String {
+    let mut tsv = String::new();
+    for range in ranges {
+        // keep one record per line
+        let name = range.name.replace(['\t', '\n', '\r'], " ");
+        tsv.push_str(
+            format!(
+                "{}\t{}\t{}\t{}\t{}\n",
+                range.start, range.end, range.asn, range.country, name
+            )
+            .as_str(),
+        );
+    }
+    tsv
+}
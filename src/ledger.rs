@@ -0,0 +1,132 @@
+//! Ledger of cook runs.
+//!
+//! `ribeye cook` records the RIB dumps of each run and whether they were processed in a local
+//! [RunLedger] file. `cook --only-failed` then re-processes only the RIB dumps that failed in the
+//! previous run, i.e. the same collectors and timestamp window without querying the broker again,
+//! updates their outcome in the ledger and refreshes the summaries.
+
+use crate::processors::RibMeta;
+use crate::report::ProcessingStats;
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// Outcome of processing a RIB dump in a run.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LedgerEntry {
+    pub project: String,
+    pub collector: String,
+    pub rib_dump_url: String,
+    /// RIB dump time, Unix timestamp
+    pub timestamp: i64,
+    pub file_size: Option<u64>,
+    pub dump_type: Option<String>,
+    pub broker_item_id: Option<String>,
+    /// error of the last attempt, none if the RIB dump was processed
+    pub error: Option<String>,
+    /// statistics of the successful attempt
+    pub stats: Option<ProcessingStats>,
+}
+
+impl LedgerEntry {
+    pub fn new(rib_meta: &RibMeta, result: &Result<ProcessingStats>) -> Self {
+        LedgerEntry {
+            project: rib_meta.project.clone(),
+            collector: rib_meta.collector.clone(),
+            rib_dump_url: rib_meta.rib_dump_url.clone(),
+            timestamp: rib_meta.timestamp.and_utc().timestamp(),
+            file_size: rib_meta.file_size,
+            dump_type: rib_meta.dump_type.clone(),
+            broker_item_id: rib_meta.broker_item_id.clone(),
+            error: result.as_ref().err().map(|e| e.to_string()),
+            stats: result.as_ref().ok().cloned(),
+        }
+    }
+
+    pub fn is_failed(&self) -> bool {
+        self.error.is_some()
+    }
+
+    pub fn rib_meta(&self) -> RibMeta {
+        RibMeta {
+            project: self.project.clone(),
+            collector: self.collector.clone(),
+            rib_dump_url: self.rib_dump_url.clone(),
+            timestamp: DateTime::from_timestamp(self.timestamp, 0)
+                .unwrap_or_default()
+                .naive_utc(),
+            file_size: self.file_size,
+            dump_type: self.dump_type.clone(),
+            broker_item_id: self.broker_item_id.clone(),
+            ..Default::default()
+        }
+    }
+}
+
+/// RIB dumps of a cook run and their outcome, persisted as JSON.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RunLedger {
+    /// start of the run, RFC 3339
+    pub started_at: String,
+    /// processors of the run, empty for the defaults
+    pub processors: Vec<String>,
+    /// RIB dumps of the run, in order of processing
+    pub ribs: Vec<LedgerEntry>,
+}
+
+impl RunLedger {
+    pub fn new(processors: &[String]) -> Self {
+        RunLedger {
+            started_at: Utc::now().to_rfc3339(),
+            processors: processors.to_vec(),
+            ribs: vec![],
+        }
+    }
+
+    pub fn load(path: &str) -> Result<Self> {
+        let content = std::fs::read_to_string(path)
+            .map_err(|e| anyhow!("failed to read run ledger {}: {}", path, e))?;
+        serde_json::from_str(content.as_str())
+            .map_err(|e| anyhow!("invalid run ledger {}: {}", path, e))
+    }
+
+    /// Write the ledger, replacing the file atomically so that an interrupted write keeps the
+    /// previous ledger.
+    pub fn save(&self, path: &str) -> Result<()> {
+        if let Some(parent) = Path::new(path).parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let temp_path = format!("{}.tmp", path);
+        std::fs::write(temp_path.as_str(), serde_json::to_string_pretty(self)?)?;
+        std::fs::rename(temp_path.as_str(), path)?;
+        Ok(())
+    }
+
+    /// Record the outcome of processing a RIB dump, replacing an earlier outcome of the same dump.
+    pub fn record(&mut self, rib_meta: &RibMeta, result: &Result<ProcessingStats>) {
+        let entry = LedgerEntry::new(rib_meta, result);
+        match self
+            .ribs
+            .iter_mut()
+            .find(|e| e.rib_dump_url == rib_meta.rib_dump_url)
+        {
+            Some(existing) => *existing = entry,
+            None => self.ribs.push(entry),
+        }
+    }
+
+    /// RIB dumps of the run.
+    pub fn rib_metas(&self) -> Vec<RibMeta> {
+        self.ribs.iter().map(|e| e.rib_meta()).collect()
+    }
+
+    /// RIB dumps that failed in the run.
+    pub fn failed(&self) -> Vec<RibMeta> {
+        self.ribs
+            .iter()
+            .filter(|e| e.is_failed())
+            .map(|e| e.rib_meta())
+            .collect()
+    }
+}
@@ -0,0 +1,88 @@
+//! Legacy MRT formats.
+//!
+//! RIB archives from before 2008 (e.g. early RouteViews dumps) use the TABLE_DUMP (v1) format
+//! instead of TABLE_DUMP_V2. Each v1 entry carries its own peer address and a 2-byte peer ASN, and
+//! there is no peer index table to look up 4-byte ASNs from. 4-byte ASNs only show up as
+//! `AS_TRANS` (23456), with the real path in the optional AS4_PATH attribute.
+//!
+//! [MrtElems] reads elements of any supported MRT format and fills in what v1 entries lack:
+//! - the AS path is merged with AS4_PATH, as for BGP4MP messages from 2-byte speakers;
+//! - an `AS_TRANS` peer ASN is replaced by the first hop of the (merged) AS path.
+
+use bgpkit_parser::models::{AsPath, Asn, AttrType, AttributeValue, MrtMessage, TableDumpMessage};
+use bgpkit_parser::{BgpElem, BgpkitParser, Elementor, RecordIterator};
+use std::io::Read;
+
+/// Iterator over the BGP elements of an MRT stream, with TABLE_DUMP (v1) entries normalized.
+pub struct MrtElems<R> {
+    records: RecordIterator<R>,
+    elementor: Elementor,
+    pending: std::vec::IntoIter<BgpElem>,
+    table_dump_v1_records: u64,
+}
+
+impl<R: Read> MrtElems<R> {
+    pub fn new(reader: R) -> Self {
+        MrtElems {
+            records: BgpkitParser::from_reader(reader).into_record_iter(),
+            elementor: Elementor::new(),
+            pending: vec![].into_iter(),
+            table_dump_v1_records: 0,
+        }
+    }
+
+    /// Number of TABLE_DUMP (v1) records read so far.
+    pub fn table_dump_v1_records(&self) -> u64 {
+        self.table_dump_v1_records
+    }
+}
+
+impl<R: Read> Iterator for MrtElems<R> {
+    type Item = BgpElem;
+
+    fn next(&mut self) -> Option<BgpElem> {
+        loop {
+            if let Some(elem) = self.pending.next() {
+                return Some(elem);
+            }
+            let record = self.records.next()?;
+            let as4_path = match &record.message {
+                MrtMessage::TableDumpMessage(msg) => {
+                    self.table_dump_v1_records += 1;
+                    Some(table_dump_as4_path(msg))
+                }
+                _ => None,
+            };
+            let mut elems = self.elementor.record_to_elems(record);
+            if let Some(as4_path) = as4_path {
+                for elem in elems.iter_mut() {
+                    normalize_table_dump_v1_elem(elem, as4_path.as_ref());
+                }
+            }
+            self.pending = elems.into_iter();
+        }
+    }
+}
+
+/// AS4_PATH attribute of a TABLE_DUMP (v1) entry, if any.
+fn table_dump_as4_path(msg: &TableDumpMessage) -> Option<AsPath> {
+    match msg.attributes.get_attr(AttrType::AS4_PATH)?.value {
+        AttributeValue::AsPath { path, .. } => Some(path),
+        _ => None,
+    }
+}
+
+/// Fill in the 4-byte AS path and peer ASN of an element parsed from a TABLE_DUMP (v1) entry.
+pub fn normalize_table_dump_v1_elem(elem: &mut BgpElem, as4_path: Option<&AsPath>) {
+    if let (Some(path), Some(as4_path)) = (elem.as_path.as_ref(), as4_path) {
+        let merged = AsPath::merge_aspath_as4path(path, as4_path);
+        elem.origin_asns = Some(merged.iter_origins().collect());
+        elem.as_path = Some(merged);
+    }
+    if elem.peer_asn == Asn::TRANSITION {
+        let first_hop = elem.as_path.as_ref().and_then(|p| p.get_collector_opt());
+        if let Some(asn) = first_hop.filter(|asn| *asn != Asn::TRANSITION) {
+            elem.peer_asn = asn;
+        }
+    }
+}
@@ -10,19 +10,320 @@
     html_favicon_url = "https://raw.githubusercontent.com/bgpkit/assets/main/logos/favicon.ico"
 )]
 
-pub use crate::processors::{MessageProcessor, RibMeta};
-use anyhow::Result;
-use tracing::info;
+use crate::alert::{Alert, AlertConfig, AlertRule, NotificationHook};
+use crate::analyzers::{order_analyzers, Analyzer, AnalyzerInputs};
+use crate::archive::DeltaArchive;
+use crate::budget::{BudgetAction, BudgetDecision, ProcessingBudget};
+use crate::download::Downloader;
+use crate::enrich::AsnInfoData;
+use crate::input::Compression;
+use crate::lock::{LockOptions, SummaryLock};
+use crate::processors::peers::{PeerTracker, DEFAULT_FULL_FEED_V4, DEFAULT_FULL_FEED_V6};
+use crate::processors::shared::{prepare_view, SharedData, SharedInput};
+use crate::processors::{
+    collector_dumps, As2relSummaryJson, EncodingSink, FieldSink, OutputEncoding, OutputGranularity,
+    OutputSelection, PeerInfoSummaryJson, Prefix2AsSummaryJson, ProcessorConfig, SkipPathSink,
+    SummaryWriter, ADDRESS_FAMILY_FIELD, COLLECTOR_DUMPS_FIELD, DEFAULT_STALE_AFTER, LABEL_FIELD,
+    RIB_TIMESTAMP_FIELD, ROUTE_POLICY_SKIPPED_FIELD,
+};
+pub use crate::processors::{FileSink, MessageProcessor, OutputSink, RibMeta, StdoutSink};
+use crate::redis::RedisExporter;
+pub use crate::report::ProcessingStats;
+use crate::report::{
+    ByteCountSink, CountingReader, ErrorCountSink, ErrorPolicy, ProcessorBytes, ProcessorErrors,
+    RunReport,
+};
+use crate::throttle::{ByteCap, IoLimiter, StreamedBytes};
+use crate::util::{
+    AddressFamily, AsTransResolver, ElemView, RoutePolicy, RouteSkipCounts, RouteTable,
+};
+use anyhow::{anyhow, Result};
+use bgpkit_parser::models::ElemType;
+use bgpkit_parser::BgpElem;
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+use serde_json::Value;
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+use std::io::Read;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tracing::{debug, info, warn};
 
+#[cfg(feature = "processors")]
+pub mod alert;
+#[cfg(feature = "processors")]
+pub mod analyzers;
+#[cfg(feature = "processors")]
+pub mod archive;
+#[cfg(feature = "processors")]
+pub mod artifact;
+#[cfg(feature = "processors")]
+pub mod audit;
+#[cfg(feature = "processors")]
+pub mod backfill;
+#[cfg(feature = "bmp")]
+pub mod bmp;
+#[cfg(feature = "processors")]
+pub mod broker;
+pub mod budget;
+#[cfg(feature = "processors")]
+pub mod bundle;
+#[cfg(feature = "processors")]
+pub mod collectors;
+#[cfg(feature = "processors")]
+pub mod compare;
+#[cfg(feature = "processors")]
+pub mod country;
+#[cfg(feature = "processors")]
+pub mod dashboard;
+#[cfg(feature = "processors")]
+pub mod diff;
+#[cfg(feature = "processors")]
+pub mod download;
+#[cfg(feature = "encryption")]
+pub mod encryption;
+#[cfg(feature = "processors")]
+pub mod enrich;
+#[cfg(feature = "processors")]
+pub mod export;
+#[cfg(feature = "processors")]
+pub mod feed;
+#[cfg(feature = "processors")]
+pub mod health;
+#[cfg(feature = "processors")]
+pub mod hijack;
+#[cfg(feature = "processors")]
+pub mod index;
+pub mod input;
+#[cfg(feature = "processors")]
+pub mod inspect;
+#[cfg(feature = "processors")]
+pub mod ip2asn;
+#[cfg(feature = "processors")]
+pub mod ledger;
+pub mod legacy;
+#[cfg(feature = "processors")]
+pub mod listing;
+#[cfg(feature = "live")]
+pub mod live;
+#[cfg(feature = "processors")]
+pub mod lock;
+#[cfg(feature = "processors")]
+pub mod lookup;
+#[cfg(feature = "processors")]
+pub mod manifest;
+#[cfg(feature = "pipeline")]
+pub mod pipeline;
 #[cfg(feature = "processors")]
 pub mod processors;
+#[cfg(feature = "processors")]
+pub mod redis;
+pub mod report;
+#[cfg(feature = "processors")]
+pub mod selftest;
+#[cfg(feature = "signing")]
+pub mod signing;
+#[cfg(feature = "processors")]
+pub mod storage;
+#[cfg(feature = "processors")]
+pub mod testing;
+#[cfg(feature = "processors")]
+pub mod throttle;
+#[cfg(feature = "processors")]
+pub mod timeseries;
+#[cfg(feature = "processors")]
+pub mod top;
+#[cfg(feature = "processors")]
+pub mod util;
+
+/// Names of the processors used if none are given.
+///
+/// `pfx2dist` is disabled by default due to high RAM usage.
+const DEFAULT_PROCESSORS: [&str; 3] = ["peer-stats", "pfx2as", "as2rel"];
+
+/// Number of elements of MRT data handed to processors at once.
+pub const BATCH_SIZE: usize = 4096;
 
-#[derive(Default)]
 pub struct RibEye {
     processors: Vec<Box<dyn MessageProcessor>>,
+    /// sink that stores processor results and summaries
+    sink: Arc<dyn OutputSink>,
+    /// whether the sink was replaced or wrapped since the default [FileSink]
+    custom_sink: bool,
+    /// compression of MRT data read from stdin (path `-`)
+    stdin_compression: Compression,
+    /// meta information of the RIB currently being processed
+    rib_meta: Option<RibMeta>,
+    /// number of elements between intermediate outputs, if any
+    flush_interval: Option<u64>,
+    /// number of elements processed for the current RIB
+    elem_count: u64,
+    /// handling of processor errors
+    error_policy: ErrorPolicy,
+    /// number of errors of each processor for the current RIB, with [ErrorPolicy::SkipAndCount]
+    error_counts: Vec<u64>,
+    /// bytes of outputs written by each processor for the current RIB
+    written_bytes: Vec<u64>,
+    /// AS names and countries attached to summaries, if any
+    asn_info: Option<Arc<AsnInfoData>>,
+    /// AS countries and output path of the country matrix written with the as2rel summary, if any
+    country_matrix: Option<(Arc<AsnInfoData>, String)>,
+    /// output path and peer drop ratio of the collector health report written with the
+    /// peer-stats summary, if any
+    health_report: Option<(String, f64)>,
+    /// output path and AS names and countries of the ip2asn table written with the pfx2as
+    /// summary, if any
+    ip2asn: Option<(String, Option<Arc<AsnInfoData>>)>,
+    /// exporter of the pfx2as and as2rel lookup tables to Redis, if any
+    redis: Option<RedisExporter>,
+    /// output path of the `.ribeye` artifact bundling all summaries, if any
+    artifact_path: Option<String>,
+    /// number of entries of the top-N views attached to summaries, if any
+    top_n: Option<usize>,
+    /// delta-encoded archive the pfx2as summary is appended to, if any
+    delta_archive: Option<DeltaArchive>,
+    /// rules evaluated over summaries
+    alert_rules: Vec<AlertRule>,
+    /// destinations of raised alerts
+    notification_hooks: Vec<Arc<dyn NotificationHook>>,
+    /// alerts raised by the last summarization
+    alerts: Vec<Alert>,
+    /// limits on reading remote MRT files, if any
+    download_limiter: Option<Arc<IoLimiter>>,
+    /// downloader of remote MRT files into a cache directory, if any, instead of streaming them
+    downloader: Option<Downloader>,
+    /// cap on the bytes downloaded from remote MRT files, if any
+    download_cap: Option<Arc<ByteCap>>,
+    /// bytes read from the remote MRT file being streamed, if any
+    streamed: Option<Arc<StreamedBytes>>,
+    /// whether summaries only read latest outputs that changed since the last summary
+    incremental_summaries: bool,
+    /// output root directory of the time-series records appended for each RIB, if any
+    timeseries_dir: Option<String>,
+    /// per-collector outputs written for each RIB
+    output_selection: OutputSelection,
+    /// whether latest outputs are replaced even if they come from a newer RIB dump
+    force_latest: bool,
+    /// leases taken on summary paths while summarizing, if enabled
+    summary_lock: Option<LockOptions>,
+    /// writer of summaries and their copies
+    summary_writer: SummaryWriter,
+    /// age after which a summarized collector's latest RIB dump is flagged as stale
+    stale_after: Duration,
+    /// statistics of the current RIB, with the element count in `elem_count` and the peer count in
+    /// `peers`
+    stats: ProcessingStats,
+    /// peers of the current RIB, whose IDs are handed to processors with each element view
+    peers: PeerTracker,
+    /// address family elements are limited to before dispatch, if any
+    address_family: Option<AddressFamily>,
+    /// resolver of peers reported as AS_TRANS, none if the correction is disabled
+    as_trans: Option<AsTransResolver>,
+    /// route policies overriding the processors' own, by processor output name
+    route_policies: BTreeMap<String, RoutePolicy>,
+    /// effective route policy of each processor, resolved on first dispatch
+    processor_policies: Vec<RoutePolicy>,
+    /// elements skipped by each processor's route policy for the current RIB
+    route_skips: Vec<RouteSkipCounts>,
+    /// limits after which optional processors are degraded, if any
+    budget: Option<ProcessingBudget>,
+    /// names of the processors degraded when over budget
+    optional_processors: BTreeSet<String>,
+    /// start of processing the current RIB, for the time budget
+    budget_start: Option<Instant>,
+    /// degradations of optional processors for the current RIB
+    budget_decisions: Vec<BudgetDecision>,
+    /// granularity of the dump time in output file names, overriding the RIB meta information's
+    output_granularity: Option<OutputGranularity>,
+    /// encoding of the dated per-collector outputs
+    output_encoding: OutputEncoding,
+    /// flag set to abort processing the current RIB without writing outputs, if any
+    cancel_flag: Option<Arc<AtomicBool>>,
+    /// analyzers of the processor results, in order of their dependencies
+    analyzers: Vec<Arc<dyn Analyzer>>,
+    /// minimum numbers of IPv4 and IPv6 prefixes of full-feed peers
+    full_feed_thresholds: (u64, u64),
+    /// label of the run tagging the outputs, if any
+    label: Option<String>,
+    /// log the brief summary of each processor once its output is written
+    log_brief_summaries: bool,
 }
 
-impl RibEye {}
+impl Default for RibEye {
+    fn default() -> Self {
+        RibEye {
+            processors: vec![],
+            sink: Arc::new(FileSink),
+            custom_sink: false,
+            stdin_compression: Compression::default(),
+            rib_meta: None,
+            flush_interval: None,
+            elem_count: 0,
+            error_policy: ErrorPolicy::default(),
+            error_counts: vec![],
+            written_bytes: vec![],
+            asn_info: None,
+            country_matrix: None,
+            health_report: None,
+            ip2asn: None,
+            redis: None,
+            artifact_path: None,
+            top_n: None,
+            delta_archive: None,
+            alert_rules: vec![],
+            notification_hooks: vec![],
+            alerts: vec![],
+            download_limiter: None,
+            downloader: None,
+            download_cap: None,
+            streamed: None,
+            incremental_summaries: false,
+            timeseries_dir: None,
+            output_selection: OutputSelection::All,
+            force_latest: false,
+            summary_lock: Some(LockOptions::default()),
+            summary_writer: SummaryWriter::default(),
+            stale_after: DEFAULT_STALE_AFTER,
+            stats: ProcessingStats::default(),
+            peers: PeerTracker::new(),
+            address_family: None,
+            as_trans: Some(AsTransResolver::new()),
+            route_policies: BTreeMap::new(),
+            processor_policies: vec![],
+            route_skips: vec![],
+            budget: None,
+            optional_processors: BTreeSet::new(),
+            budget_start: None,
+            budget_decisions: vec![],
+            output_granularity: None,
+            output_encoding: OutputEncoding::default(),
+            cancel_flag: None,
+            analyzers: vec![],
+            full_feed_thresholds: (DEFAULT_FULL_FEED_V4, DEFAULT_FULL_FEED_V6),
+            label: None,
+            log_brief_summaries: false,
+        }
+    }
+}
+
+/// Remove a downloaded MRT file once processed.
+fn remove_downloaded(path: &str) {
+    if let Err(e) = std::fs::remove_file(path) {
+        warn!(
+            phase = "download",
+            "failed to remove downloaded {}: {}", path, e
+        );
+    }
+}
+
+/// Timestamp of the RIB dump an existing output was produced from, if recorded in it.
+fn output_rib_timestamp(sink: &dyn OutputSink, path: &str) -> Option<i64> {
+    sink.read_json(path)
+        .ok()?
+        .get(RIB_TIMESTAMP_FIELD)?
+        .as_i64()
+}
 
 impl RibEye {
     pub fn new() -> Self {
@@ -37,26 +338,109 @@ impl RibEye {
     /// - As2relProcessor
     /// - Prefix2DistProcessor
     pub fn default_processors(output_dir: &str) -> Vec<Box<dyn MessageProcessor>> {
+        DEFAULT_PROCESSORS
+            .iter()
+            .filter_map(|name| Self::get_processor(name, output_dir))
+            .collect()
+    }
+
+    /// Names of all built-in processors accepted by [RibEye::get_processor].
+    ///
+    /// `get_processor` also accepts the `pfx2as` variants `pfx2as-aggregated`, with an aggregated
+    /// summary, and `pfx2as-peers`, recording the peers of each prefix-origin pair, and the
+    /// `peer-stats` variant `peer-stats-approx`, counting prefixes approximately.
+    pub fn available_processors() -> Vec<&'static str> {
         vec![
-            Box::new(processors::PeerStatsProcessor::new(output_dir)),
-            Box::new(processors::Prefix2AsProcessor::new(output_dir)),
-            Box::new(processors::As2relProcessor::new(output_dir)),
-            // Prefix2DistProcessor is disabled by default due to high RAM usage
-            // Box::new(processors::Prefix2DistProcessor::new(output_dir)),
+            "peer-stats",
+            "pfx2as",
+            "as2rel",
+            "pfx2dist",
+            "pfxlen",
+            "footprint",
+            "reachability",
+            "communities",
+            "pfxscope",
+            "defaults",
+            "duplicates",
+            "pathdiv",
+            "vantage",
+            "firsthop",
         ]
     }
 
     pub fn get_processor(
         processor_name: &str,
         output_dir: &str,
+    ) -> Option<Box<dyn MessageProcessor>> {
+        Self::get_processor_with_config(processor_name, output_dir, &ProcessorConfig::default())
+    }
+
+    /// Create a built-in processor by name, with the given options. The processor writes under
+    /// its own output directory if [ProcessorConfig::output_dir] overrides it for the requested or
+    /// the output name.
+    pub fn get_processor_with_config(
+        processor_name: &str,
+        output_dir: &str,
+        config: &ProcessorConfig,
+    ) -> Option<Box<dyn MessageProcessor>> {
+        let output_dir = config.output_dir(processor_name).unwrap_or(output_dir);
+        let processor = Self::new_builtin_processor(processor_name, output_dir, config)?;
+        match config.output_dir(processor.name().as_str()) {
+            Some(dir) if dir != output_dir => {
+                Self::new_builtin_processor(processor_name, dir, config)
+            }
+            _ => Some(processor),
+        }
+    }
+
+    fn new_builtin_processor(
+        processor_name: &str,
+        output_dir: &str,
+        config: &ProcessorConfig,
     ) -> Option<Box<dyn MessageProcessor>> {
         match processor_name.to_lowercase().as_str() {
             "peerstats" | "peer_stats" | "peer-stats" => {
                 Some(Box::new(processors::PeerStatsProcessor::new(output_dir)))
             }
+            "peer-stats-approx" => Some(Box::new(
+                processors::PeerStatsProcessor::new(output_dir).with_approximate(true),
+            )),
             "pfx2as" => Some(Box::new(processors::Prefix2AsProcessor::new(output_dir))),
-            "as2rel" => Some(Box::new(processors::As2relProcessor::new(output_dir))),
+            "pfx2as-aggregated" => Some(Box::new(
+                processors::Prefix2AsProcessor::new(output_dir).with_aggregation(true),
+            )),
+            "pfx2as-peers" => Some(Box::new(
+                processors::Prefix2AsProcessor::new(output_dir).with_peers(true),
+            )),
+            "as2rel" => Some(Box::new(
+                processors::As2relProcessor::new(output_dir)
+                    .with_ixp_asns(config.ixp_asns.clone(), config.ixp_policy),
+            )),
             "pfx2dist" => Some(Box::new(processors::Prefix2DistProcessor::new(output_dir))),
+            "pfxlen" => Some(Box::new(processors::PrefixLenProcessor::new(output_dir))),
+            "footprint" => Some(Box::new(
+                processors::FootprintProcessor::new(output_dir)
+                    .with_prefix_countries(config.prefix_countries.clone()),
+            )),
+            "reachability" => Some(Box::new(processors::ReachabilityProcessor::new(output_dir))),
+            "communities" => Some(Box::new(processors::CommunitiesProcessor::new(output_dir))),
+            "pfxscope" => Some(Box::new(
+                processors::PrefixScopeProcessor::new(output_dir)
+                    .with_prefix_countries(config.prefix_countries.clone()),
+            )),
+            "defaults" => Some(Box::new(processors::DefaultRouteProcessor::new(output_dir))),
+            "duplicates" => Some(Box::new(processors::DuplicatesProcessor::new(output_dir))),
+            "pathdiv" => Some(Box::new(processors::PathDiversityProcessor::new(
+                output_dir,
+            ))),
+            "vantage" => {
+                let processor = processors::VantageProcessor::new(output_dir);
+                Some(Box::new(match config.vantage_coverage {
+                    Some(coverage) => processor.with_coverage(coverage),
+                    None => processor,
+                }))
+            }
+            "firsthop" => Some(Box::new(processors::FirstHopProcessor::new(output_dir))),
             _ => None,
         }
     }
@@ -64,10 +448,20 @@ impl RibEye {
     pub fn get_processors(
         processor_names: &[String],
         output_dir: &str,
+    ) -> Result<Vec<Box<dyn MessageProcessor>>> {
+        Self::get_processors_with_config(processor_names, output_dir, &ProcessorConfig::default())
+    }
+
+    pub fn get_processors_with_config(
+        processor_names: &[String],
+        output_dir: &str,
+        config: &ProcessorConfig,
     ) -> Result<Vec<Box<dyn MessageProcessor>>> {
         let mut processors = Vec::new();
         for processor_name in processor_names {
-            if let Some(processor) = Self::get_processor(processor_name, output_dir) {
+            if let Some(processor) =
+                Self::get_processor_with_config(processor_name, output_dir, config)
+            {
                 processors.push(processor);
             } else {
                 return Err(anyhow::anyhow!("unknown processor: {}", processor_name));
@@ -76,73 +470,1570 @@ impl RibEye {
         Ok(processors)
     }
 
+    /// Use the named processors (all built-in ones if empty), writing under `output_dir`.
+    ///
+    /// An `output_dir` of `-` streams results to stdout through a [StdoutSink].
     pub fn with_processor_names(
+        self,
+        processor_names: &Vec<String>,
+        output_dir: &str,
+    ) -> Result<Self> {
+        self.with_processor_names_and_config(
+            processor_names,
+            output_dir,
+            &ProcessorConfig::default(),
+        )
+    }
+
+    /// Use the named processors like [RibEye::with_processor_names], with the given options.
+    pub fn with_processor_names_and_config(
         mut self,
         processor_names: &Vec<String>,
         output_dir: &str,
+        config: &ProcessorConfig,
     ) -> Result<Self> {
         if processor_names.is_empty() {
-            self.processors = Self::default_processors(output_dir);
+            let default_names: Vec<String> =
+                DEFAULT_PROCESSORS.iter().map(|n| n.to_string()).collect();
+            self.processors =
+                Self::get_processors_with_config(default_names.as_slice(), output_dir, config)?;
         } else {
-            self.processors = Self::get_processors(processor_names.as_slice(), output_dir)?;
+            self.processors =
+                Self::get_processors_with_config(processor_names.as_slice(), output_dir, config)?;
+        }
+        if processors::is_stdout(output_dir) {
+            self.sink = Arc::new(StdoutSink);
+        }
+        self.route_policies.extend(config.route_policies.clone());
+        self.processor_policies.clear();
+        if config.label.is_some() {
+            self.label = config.label.clone();
         }
         Ok(self)
     }
 
     pub fn with_processors(mut self, processors: Vec<Box<dyn MessageProcessor>>) -> Self {
         self.processors = processors;
+        self.processor_policies.clear();
+        self
+    }
+
+    /// Skip the elements of the given route policy for a processor, by output name (e.g.
+    /// `pfx2as`), instead of the processor's own [MessageProcessor::route_policy].
+    pub fn with_route_policy(mut self, processor: &str, policy: RoutePolicy) -> Self {
+        self.route_policies.insert(processor.to_lowercase(), policy);
+        self.processor_policies.clear();
         self
     }
 
     pub fn with_rib_meta(mut self, rib_meta: &RibMeta) -> Self {
-        for processor in &mut self.processors {
-            processor.reset_processor(rib_meta);
+        self.set_rib_meta(rib_meta);
+        self.reset_counters();
+        self
+    }
+
+    /// Name of the collector currently being processed, used as a structured log field.
+    fn collector(&self) -> &str {
+        self.rib_meta
+            .as_ref()
+            .map(|m| m.collector.as_str())
+            .unwrap_or("unknown")
+    }
+
+    /// Set the sink that stores processor results and summaries, [FileSink] by default.
+    pub fn with_sink(mut self, sink: Box<dyn OutputSink>) -> Self {
+        self.sink = Arc::from(sink);
+        self.custom_sink = true;
+        self
+    }
+
+    /// Write outputs and summaries encrypted to local files or object store objects, see
+    /// [encryption::EncryptingSink]. Replaces the default [FileSink], unless the sink streams to
+    /// stdout; call before signing, throttling or bundling outputs, which wrap the sink.
+    ///
+    /// Fails if the sink was already replaced or wrapped, since encryption would drop it.
+    #[cfg(feature = "encryption")]
+    pub fn with_encryption_key(mut self, key: Arc<encryption::EncryptionKey>) -> Result<Self> {
+        if self.sink.is_stream() {
+            return Ok(self);
+        }
+        if self.custom_sink {
+            return Err(anyhow!(
+                "output encryption replaces the output sink: set the encryption key before a \
+                 custom sink, signing, throttling or bundling"
+            ));
         }
+        self.sink = Arc::new(encryption::EncryptingSink::new(key));
+        self.custom_sink = true;
+        Ok(self)
+    }
+
+    /// Sign every file written by the current sink with a detached signature, see
+    /// [signing::SigningSink]. Call after setting the sink.
+    #[cfg(feature = "signing")]
+    pub fn with_signing_key(mut self, key: Arc<signing::SigningKey>) -> Self {
+        self.sink = Arc::new(signing::SigningSink::new(self.sink.clone(), key));
+        self.custom_sink = true;
+        self
+    }
+
+    /// Limit reads of remote MRT files (HTTP(S), FTP, S3). A permit is held while a file is
+    /// streamed; share the limiter between pipelines to cap a whole cook.
+    pub fn with_download_limiter(mut self, limiter: Arc<IoLimiter>) -> Self {
+        self.download_limiter = Some(limiter);
         self
     }
 
+    /// Download remote HTTP(S) MRT files into the downloader's cache directory before processing
+    /// them instead of streaming them, resuming interrupted downloads. Downloaded files are
+    /// removed once processed, and kept if processing fails so that a retry does not download
+    /// them again.
+    pub fn with_downloader(mut self, downloader: Downloader) -> Self {
+        self.downloader = Some(downloader);
+        self
+    }
+
+    /// Refuse to download remote MRT files once the cap's bytes are downloaded, failing the RIB
+    /// instead; share the cap between pipelines to cap a whole cook.
+    pub fn with_download_cap(mut self, cap: Arc<ByteCap>) -> Self {
+        self.download_cap = Some(cap);
+        self
+    }
+
+    /// Limit writes to remote (S3) paths through the current sink, see [throttle::ThrottledSink].
+    /// Call after setting the sink.
+    pub fn with_upload_limiter(mut self, limiter: Arc<IoLimiter>) -> Self {
+        self.sink = Arc::new(throttle::ThrottledSink::new(self.sink.clone(), limiter));
+        self.custom_sink = true;
+        self
+    }
+
+    /// Write dated per-collector outputs into a bundle shared by all pipelines of the run instead
+    /// of individual files, see [bundle]. Call after setting the sink, and
+    /// [finish](bundle::OutputBundle::finish) the bundle once all pipelines are done.
+    pub fn with_bundle(mut self, bundle: Arc<bundle::OutputBundle>) -> Self {
+        if !self.sink.is_stream() {
+            self.sink = Arc::new(bundle::BundlingSink::new(self.sink.clone(), bundle));
+            self.custom_sink = true;
+        }
+        self
+    }
+
+    /// Keep the parsed latest outputs of each processor in a summary cache and only read the latest
+    /// files that changed since the last summary, see [processors::cache]. Summaries are still
+    /// merged from the outputs of all collectors.
+    pub fn with_incremental_summaries(mut self, incremental: bool) -> Self {
+        self.incremental_summaries = incremental;
+        self
+    }
+
+    /// Append compact time-series records of each processor's output to a dataset partitioned by
+    /// date under the given output root directory, see [timeseries].
+    pub fn with_timeseries(mut self, output_dir: &str) -> Self {
+        self.timeseries_dir = Some(output_dir.trim_end_matches('/').to_string());
+        self
+    }
+
+    /// Whether per-collector outputs also overwrite the collector's latest output (the default),
+    /// e.g. disabled when backfilling historical RIBs.
+    pub fn with_latest_outputs(mut self, latest_outputs: bool) -> Self {
+        self.output_selection = match latest_outputs {
+            true => OutputSelection::All,
+            false => OutputSelection::DatedOnly,
+        };
+        self
+    }
+
+    /// Replace latest outputs even if the existing ones come from a newer RIB dump, as recorded in
+    /// their `rib_timestamp` field. By default, such outputs are kept.
+    pub fn with_force_latest(mut self, force_latest: bool) -> Self {
+        self.force_latest = force_latest;
+        self
+    }
+
+    /// Write the dated and latest per-collector outputs (the default), only the dated ones or only
+    /// the latest ones.
+    pub fn with_output_selection(mut self, output_selection: OutputSelection) -> Self {
+        self.output_selection = output_selection;
+        self
+    }
+
+    /// Leases taken on each processor's summary path while summarizing it, so that concurrent
+    /// runs never interleave their summary writes (enabled by default), see [lock]. `None`
+    /// disables them.
+    pub fn with_summary_lock(mut self, options: Option<LockOptions>) -> Self {
+        self.summary_lock = options;
+        self
+    }
+
+    /// Set the copies of each summary published next to its canonical `latest.json.bz2`, by
+    /// default an uncompressed `latest.json`.
+    pub fn with_summary_writer(mut self, summary_writer: SummaryWriter) -> Self {
+        self.summary_writer = summary_writer;
+        self
+    }
+
+    /// Flag summarized collectors whose latest RIB dump is older than the given age as stale in
+    /// the summaries' `collector_dumps` (default: 48 hours).
+    pub fn with_stale_after(mut self, stale_after: Duration) -> Self {
+        self.stale_after = stale_after;
+        self
+    }
+
+    /// Create an independent pipeline from this one as a prototype.
+    ///
+    /// The new pipeline has fresh instances of all processors and shares the output sink and
+    /// stdin compression setting, so several RIBs can be processed concurrently, e.g. one pipeline
+    /// per worker thread.
+    pub fn clone_pipeline(&self) -> RibEye {
+        RibEye {
+            processors: self.processors.iter().map(|p| p.new_instance()).collect(),
+            sink: self.sink.clone(),
+            custom_sink: self.custom_sink,
+            stdin_compression: self.stdin_compression,
+            rib_meta: None,
+            flush_interval: self.flush_interval,
+            elem_count: 0,
+            error_policy: self.error_policy,
+            error_counts: vec![],
+            written_bytes: vec![],
+            asn_info: self.asn_info.clone(),
+            country_matrix: self.country_matrix.clone(),
+            health_report: self.health_report.clone(),
+            ip2asn: self.ip2asn.clone(),
+            redis: self.redis.clone(),
+            artifact_path: self.artifact_path.clone(),
+            top_n: self.top_n,
+            delta_archive: self.delta_archive.clone(),
+            alert_rules: self.alert_rules.clone(),
+            notification_hooks: self.notification_hooks.clone(),
+            alerts: vec![],
+            download_limiter: self.download_limiter.clone(),
+            downloader: self.downloader.clone(),
+            download_cap: self.download_cap.clone(),
+            streamed: None,
+            incremental_summaries: self.incremental_summaries,
+            timeseries_dir: self.timeseries_dir.clone(),
+            output_selection: self.output_selection,
+            force_latest: self.force_latest,
+            summary_lock: self.summary_lock,
+            summary_writer: self.summary_writer.clone(),
+            stale_after: self.stale_after,
+            stats: ProcessingStats::default(),
+            peers: self.peer_tracker(),
+            address_family: self.address_family,
+            as_trans: self.as_trans.as_ref().map(|_| AsTransResolver::new()),
+            route_policies: self.route_policies.clone(),
+            processor_policies: vec![],
+            route_skips: vec![],
+            budget: self.budget,
+            optional_processors: self.optional_processors.clone(),
+            budget_start: None,
+            budget_decisions: vec![],
+            output_granularity: self.output_granularity,
+            output_encoding: self.output_encoding,
+            cancel_flag: None,
+            analyzers: self.analyzers.clone(),
+            full_feed_thresholds: self.full_feed_thresholds,
+            label: self.label.clone(),
+            log_brief_summaries: self.log_brief_summaries,
+        }
+    }
+
+    /// Limit processing to elements of one address family, e.g. only IPv6 prefixes. Other
+    /// elements are dropped before reaching the processors, and outputs and summaries are tagged
+    /// with the address family.
+    pub fn with_address_family(mut self, address_family: Option<AddressFamily>) -> Self {
+        self.address_family = address_family;
+        self
+    }
+
+    /// Correct elements of peers reported as AS_TRANS (AS23456) with the peer's real ASN from its
+    /// AS paths before dispatch, so that per-peer and AS relationship outputs have no bogus
+    /// AS23456 entries (default: enabled).
+    pub fn with_as_trans_correction(mut self, enabled: bool) -> Self {
+        self.as_trans = enabled.then(AsTransResolver::new);
+        self
+    }
+
+    /// Tag per-collector outputs, summaries and run reports with the label of the run. Processors
+    /// created from a [ProcessorConfig] with a label are tagged with it, see
+    /// [ProcessorConfig::with_label] for the output paths of labeled runs.
+    pub fn with_label(mut self, label: &str) -> Self {
+        self.label = Some(label.to_string());
+        self
+    }
+
+    /// Log the brief summary of each processor, e.g. `pfx2as: 950k prefixes, 78k origins`, once
+    /// its output of a RIB is written, see [MessageProcessor::brief_summary].
+    pub fn with_brief_summaries(mut self, log: bool) -> Self {
+        self.log_brief_summaries = log;
+        self
+    }
+
+    /// Brief summaries of the processors' current results, see [MessageProcessor::brief_summary].
+    pub fn brief_summaries(&self) -> Vec<String> {
+        self.processors.iter().map(|p| p.brief_summary()).collect()
+    }
+
+    /// Set the minimum numbers of IPv4 and IPv6 prefixes announced by the full-feed peers shared
+    /// with processors, see [processors::shared].
+    pub fn with_full_feed_thresholds(mut self, v4: u64, v6: u64) -> Self {
+        self.full_feed_thresholds = (v4, v6);
+        self.peers = self.peer_tracker();
+        self
+    }
+
+    /// Fresh peer tracker, tracking the peers of the current RIB's collector.
+    fn peer_tracker(&self) -> PeerTracker {
+        let (v4, v6) = self.full_feed_thresholds;
+        let mut tracker = PeerTracker::new().with_full_feed_thresholds(v4, v6);
+        if let Some(rib_meta) = &self.rib_meta {
+            tracker.set_collector(Some(rib_meta.collector.clone()));
+        }
+        tracker
+    }
+
+    /// Name per-dump outputs by day, replacing earlier outputs of the same day, or by exact dump
+    /// time (default).
+    pub fn with_output_granularity(mut self, granularity: OutputGranularity) -> Self {
+        self.output_granularity = Some(granularity);
+        self
+    }
+
+    /// Encode dated per-collector outputs as CBOR (`.cbor.bz2`) instead of JSON (default).
+    /// Latest outputs and summaries stay JSON, as the pipeline reads them back.
+    pub fn with_output_encoding(mut self, encoding: OutputEncoding) -> Self {
+        self.output_encoding = encoding;
+        self
+    }
+
+    /// Degrade optional processors once processing a RIB exceeds the given budget.
+    pub fn with_budget(mut self, budget: ProcessingBudget) -> Self {
+        self.budget = Some(budget);
+        self
+    }
+
+    /// Mark processors, by output name (e.g. `pfx2dist`), as optional: they are sampled or skipped when over budget.
+    pub fn with_optional_processors(mut self, processor_names: &[String]) -> Self {
+        self.optional_processors = processor_names.iter().map(|n| n.to_lowercase()).collect();
+        self
+    }
+
+    /// Abort processing the current RIB, without writing its outputs, once the flag is set, e.g.
+    /// from another thread. The flag is checked before each batch of elements.
+    pub fn with_cancel_flag(mut self, cancel_flag: Arc<AtomicBool>) -> Self {
+        self.cancel_flag = Some(cancel_flag);
+        self
+    }
+
+    fn is_cancelled(&self) -> bool {
+        self.cancel_flag
+            .as_ref()
+            .is_some_and(|flag| flag.load(Ordering::Relaxed))
+    }
+
+    /// Set the compression of MRT data read from stdin, i.e. when processing path `-`.
+    pub fn with_stdin_compression(mut self, compression: Compression) -> Self {
+        self.stdin_compression = compression;
+        self
+    }
+
+    /// Write intermediate outputs of all processors every `interval` processed elements.
+    ///
+    /// Intermediate outputs go to each processor's partial output path (see
+    /// [processors::get_partial_output_path]) and are overwritten on every flush.
+    pub fn with_flush_interval(mut self, interval: u64) -> Self {
+        self.flush_interval = match interval {
+            0 => None,
+            i => Some(i),
+        };
+        self
+    }
+
+    /// Set how errors returned by processors are handled, [ErrorPolicy::Abort] by default.
+    pub fn with_error_policy(mut self, error_policy: ErrorPolicy) -> Self {
+        self.error_policy = error_policy;
+        self
+    }
+
+    /// Attach AS names and countries to the summaries of the processors in
+    /// [enrich::ENRICHABLE_PROCESSORS], as an `asn_info` object.
+    pub fn with_asn_info(mut self, asn_info: Arc<AsnInfoData>) -> Self {
+        self.asn_info = Some(asn_info);
+        self
+    }
+
+    /// Write a country-to-country matrix of AS relationships (see [country::country_matrix]) to
+    /// `output_path` when summarizing the as2rel processor.
+    pub fn with_country_matrix(mut self, asn_info: Arc<AsnInfoData>, output_path: &str) -> Self {
+        self.country_matrix = Some((asn_info, output_path.to_string()));
+        self
+    }
+
+    /// Write a collector health report (see [health::collector_health]) to `output_path` when
+    /// summarizing the peer-stats processor, flagging collectors that lost more than `drop_ratio`
+    /// of their peers since the previous summary.
+    pub fn with_health_report(mut self, output_path: &str, drop_ratio: f64) -> Self {
+        self.health_report = Some((output_path.to_string(), drop_ratio));
+        self
+    }
+
+    /// Write the pfx2as summary as an ip2asn TSV table (see [ip2asn::ip2asn_ranges]) to
+    /// `output_path` when summarizing the pfx2as processor, with AS names and countries if given.
+    pub fn with_ip2asn(mut self, output_path: &str, asn_info: Option<Arc<AsnInfoData>>) -> Self {
+        self.ip2asn = Some((output_path.to_string(), asn_info));
+        self
+    }
+
+    /// Load the pfx2as and as2rel summaries into Redis after summarizing them, see [redis].
+    /// Export failures are logged without failing the summarization.
+    pub fn with_redis(mut self, exporter: RedisExporter) -> Self {
+        self.redis = Some(exporter);
+        self
+    }
+
+    /// Bundle the summaries of all processors into a `.ribeye` artifact indexed for prefix and
+    /// ASN lookups (see [artifact]), written to `output_path` after summarizing.
+    pub fn with_artifact(mut self, output_path: &str) -> Self {
+        self.artifact_path = Some(output_path.to_string());
+        self
+    }
+
+    /// Attach the `n` largest entries of the views in [top] to the summaries of the processors in
+    /// [top::TOP_VIEW_PROCESSORS], as a `top` object.
+    pub fn with_top_views(mut self, n: usize) -> Self {
+        self.top_n = Some(n);
+        self
+    }
+
+    /// Append each pfx2as summary to a delta-encoded archive (see [archive]), dated by the newest
+    /// summarized RIB dump.
+    pub fn with_delta_archive(mut self, archive: DeltaArchive) -> Self {
+        self.delta_archive = Some(archive);
+        self
+    }
+
+    /// Evaluate alerting rules when summarizing, comparing each processor's summary with its
+    /// previous one. Raised alerts are sent to the notification hooks and available through
+    /// [RibEye::alerts].
+    pub fn with_alert_rules(mut self, rules: Vec<AlertRule>) -> Self {
+        self.alert_rules = rules;
+        self
+    }
+
+    /// Add a destination for raised alerts.
+    pub fn with_notification_hook(mut self, hook: Arc<dyn NotificationHook>) -> Self {
+        self.notification_hooks.push(hook);
+        self
+    }
+
+    /// Use the rules and hooks of an alerting configuration file.
+    pub fn with_alert_config(mut self, config: &AlertConfig) -> Self {
+        self.alert_rules = config.rules.clone();
+        self.notification_hooks.extend(config.hooks());
+        self
+    }
+
+    /// Alerts raised by the last call to [RibEye::summarize_latest_files].
+    pub fn alerts(&self) -> &[Alert] {
+        &self.alerts
+    }
+
+    /// Run analyzers over the processor results of each RIB dump after writing the outputs, see
+    /// [analyzers]. Fails if a dependency of an analyzer is neither a processor of the pipeline
+    /// nor another analyzer, or if analyzers depend on each other in a cycle.
+    pub fn with_analyzers(mut self, analyzers: Vec<Arc<dyn Analyzer>>) -> Result<Self> {
+        self.analyzers = order_analyzers(analyzers.as_slice(), &self.processor_names())?;
+        Ok(self)
+    }
+
     /// Add a processor to the pipeline
     pub fn add_processor(&mut self, processor: Box<dyn MessageProcessor>) {
         self.processors.push(processor);
     }
 
     pub fn initialize_processors(&mut self, rib_meta: &RibMeta) -> Result<()> {
+        self.set_rib_meta(rib_meta);
+        self.reset_counters();
+        Ok(())
+    }
+
+    /// Reset the element and error counts and statistics for a new RIB.
+    fn reset_counters(&mut self) {
+        self.elem_count = 0;
+        self.error_counts.clear();
+        self.written_bytes.clear();
+        self.route_skips.clear();
+        self.stats = ProcessingStats::default();
+        self.peers = self.peer_tracker();
+        if let Some(as_trans) = self.as_trans.as_mut() {
+            *as_trans = AsTransResolver::new();
+        }
+        self.budget_start = None;
+        self.budget_decisions.clear();
+    }
+
+    /// Pass the meta information to all processors without resetting the pipeline's counters.
+    fn set_rib_meta(&mut self, rib_meta: &RibMeta) {
+        let mut rib_meta = rib_meta.clone();
+        if let Some(granularity) = self.output_granularity {
+            rib_meta.output_granularity = granularity;
+        }
         for processor in &mut self.processors {
-            processor.reset_processor(rib_meta);
+            processor.reset_processor(&rib_meta);
         }
-        Ok(())
+        self.peers.set_collector(Some(rib_meta.collector.clone()));
+        self.rib_meta = Some(rib_meta);
     }
 
-    /// Process each entry in
-    pub fn process_mrt_file(&mut self, file_path: &str) -> Result<()> {
+    /// Process each entry in the given MRT file and write out processor results.
+    ///
+    /// The file path can be a local path, a remote URL, or `-` to read from stdin. The
+    /// compression of stdin data is set by [RibEye::with_stdin_compression]. Returns the
+    /// statistics of the current RIB.
+    pub fn process_mrt_file(&mut self, file_path: &str) -> Result<ProcessingStats> {
+        self.process_mrt_file_with(file_path, |reader| reader)
+    }
+
+    /// Process an MRT file like [RibEye::process_mrt_file], reading it through the reader `wrap`
+    /// returns for the opened file, e.g. to observe the progress of the download.
+    pub(crate) fn process_mrt_file_with<R: Read>(
+        &mut self,
+        file_path: &str,
+        wrap: impl FnOnce(Box<dyn Read + Send>) -> R,
+    ) -> Result<ProcessingStats> {
         if self.processors.is_empty() {
             info!("no processors added, skip processing: {}", file_path);
+            return Ok(self.stats());
+        }
+
+        let downloaded = self.download_mrt_file(file_path)?;
+        let local_path = downloaded.as_deref().unwrap_or(file_path);
+        let reader = self.open_mrt_file(local_path)?;
+        self.stats.file_bytes = self.file_bytes(local_path);
+        let stats = self.process_mrt_reader(wrap(reader))?;
+        if let Some(path) = downloaded {
+            remove_downloaded(path.as_str());
+        }
+        Ok(stats)
+    }
+
+    /// Process several RIBs through the same processor instances and write out their merged
+    /// results once, e.g. all collectors of one day.
+    ///
+    /// Processors see the meta information of each RIB while processing it. The merged results
+    /// are written with the meta information from [RibMeta::merged], without intermediate
+    /// per-collector outputs.
+    pub fn process_mrt_files(&mut self, rib_metas: &[RibMeta]) -> Result<ProcessingStats> {
+        if self.processors.is_empty() || rib_metas.is_empty() {
+            info!("no processors or RIBs, skip processing");
+            return Ok(self.stats());
+        }
+
+        self.reset_counters();
+        let mut file_bytes = Some(0);
+        let mut downloads = vec![];
+        for rib_meta in rib_metas {
+            self.set_rib_meta(rib_meta);
+            let downloaded = self.download_mrt_file(rib_meta.rib_dump_url.as_str())?;
+            let local_path = downloaded
+                .clone()
+                .unwrap_or_else(|| rib_meta.rib_dump_url.clone());
+            let reader = self.open_mrt_file(local_path.as_str())?;
+            file_bytes = file_bytes
+                .zip(self.file_bytes(local_path.as_str()))
+                .map(|(a, b)| a + b);
+            self.read_mrt(reader)?;
+            downloads.extend(downloaded);
+        }
+        self.stats.file_bytes = file_bytes;
+
+        self.set_rib_meta(&RibMeta::merged(rib_metas));
+        self.log_report();
+        self.output()?;
+        for path in downloads {
+            remove_downloaded(path.as_str());
+        }
+        Ok(self.stats())
+    }
+
+    /// Account for a remote MRT file under the download cap and download it with the downloader,
+    /// if any and the file is an HTTP(S) URL, returning the local path of the downloaded file.
+    /// Streamed HTTP(S) files reserve their stored size, if known, and count the bytes read from
+    /// them, see [throttle::CappedReader]; other streamed files count with their stored size.
+    fn download_mrt_file(&mut self, file_path: &str) -> Result<Option<String>> {
+        if !throttle::is_remote(file_path) {
+            return Ok(None);
+        }
+        let size = self.file_bytes(file_path).unwrap_or(0);
+        if let Some(cap) = &self.download_cap {
+            cap.reserve(size)?;
+        }
+        let downloader = match &self.downloader {
+            Some(downloader) if download::is_resumable(file_path) => downloader,
+            _ => {
+                if !download::is_resumable(file_path) {
+                    self.stats.downloaded_bytes += size;
+                }
+                return Ok(None);
+            }
+        };
+        let _permit = self.download_limiter.as_ref().map(|l| l.acquire());
+        let downloaded = downloader.download(file_path)?;
+        if let Some(cap) = &self.download_cap {
+            cap.settle(size, downloaded.bytes);
+        }
+        self.stats.downloaded_bytes += downloaded.bytes;
+        Ok(Some(downloaded.path))
+    }
+
+    /// Size of an MRT file as stored: from the file system for local files, otherwise from the
+    /// current RIB's meta information.
+    fn file_bytes(&self, file_path: &str) -> Option<u64> {
+        if input::is_stdin(file_path) {
+            return None;
+        }
+        if !throttle::is_remote(file_path) {
+            return std::fs::metadata(file_path).ok().map(|m| m.len());
+        }
+        self.rib_meta
+            .as_ref()
+            .filter(|m| m.rib_dump_url == file_path)
+            .and_then(|m| m.file_size)
+    }
+
+    /// Open an MRT file path, URL, or stdin (`-`) for reading.
+    fn open_mrt_file(&mut self, file_path: &str) -> Result<Box<dyn Read + Send>> {
+        if input::is_stdin(file_path) {
+            info!(
+                collector = self.collector(),
+                phase = "process",
+                "processing RIB data from stdin (compression: {})",
+                self.stdin_compression
+            );
+            return Ok(input::get_stdin_reader(self.stdin_compression));
+        }
+
+        info!(
+            collector = self.collector(),
+            phase = "process",
+            "processing RIB file: {}",
+            file_path
+        );
+        let permit = match &self.download_limiter {
+            Some(limiter) if throttle::is_remote(file_path) => Some(limiter.acquire()),
+            _ => None,
+        };
+        let reader = match download::is_resumable(file_path) {
+            true => {
+                let streamed = Arc::new(StreamedBytes::default());
+                let raw = throttle::CappedReader::new(
+                    download::stream(file_path)?,
+                    self.download_cap.clone(),
+                    self.file_bytes(file_path).unwrap_or(0),
+                    streamed.clone(),
+                );
+                self.streamed = Some(streamed);
+                input::get_decompress_reader(Box::new(raw), Compression::from_path(file_path))
+            }
+            false => oneio::get_reader(file_path)?,
+        };
+        match permit {
+            Some(permit) => Ok(Box::new(throttle::PermitReader::new(reader, permit))),
+            None => Ok(reader),
+        }
+    }
+
+    /// Process each entry in uncompressed MRT data from the given reader and write out processor
+    /// results. Returns the statistics of the current RIB.
+    pub fn process_mrt_reader<R: Read>(&mut self, reader: R) -> Result<ProcessingStats> {
+        self.read_mrt(reader)?;
+        self.log_report();
+        self.output()?;
+        Ok(self.stats())
+    }
+
+    /// Feed all entries of uncompressed MRT data through the processors.
+    fn read_mrt<R: Read>(&mut self, reader: R) -> Result<()> {
+        let start = Instant::now();
+        let mut reader = CountingReader {
+            inner: reader,
+            count: 0,
+        };
+        let mut elems = legacy::MrtElems::new(&mut reader);
+        let mut batch = Vec::with_capacity(BATCH_SIZE);
+        let mut result = Ok(());
+        while result.is_ok() {
+            if self.is_cancelled() {
+                break;
+            }
+            batch.clear();
+            batch.extend(elems.by_ref().take(BATCH_SIZE));
+            if batch.is_empty() {
+                break;
+            }
+            result = self.process_elems(&batch);
+        }
+        let table_dump_v1_records = elems.table_dump_v1_records();
+        drop(elems);
+        self.stats.mrt_bytes += reader.count;
+        self.stats.duration_ms += start.elapsed().as_millis() as u64;
+        self.count_streamed()?;
+        result?;
+        // reading may also end early as the reader fails once cancelled
+        if self.is_cancelled() {
+            return Err(anyhow!("processing {} cancelled", self.collector()));
+        }
+        if table_dump_v1_records > 0 {
+            info!(
+                collector = self.collector(),
+                phase = "process",
+                "read {} TABLE_DUMP (v1) records",
+                table_dump_v1_records
+            );
+        }
+        info!(
+            collector = self.collector(),
+            phase = "process",
+            duration_ms = start.elapsed().as_millis() as u64,
+            downloaded_bytes = self.stats.downloaded_bytes,
+            "finished processing RIB data: {} elements ({} announcements, {} withdrawals) from {} \
+             peers, {} MRT bytes",
+            self.elem_count,
+            self.stats.announcements,
+            self.stats.withdrawals,
+            self.peers.len(),
+            reader.count
+        );
+        Ok(())
+    }
+
+    /// Count the bytes read from the remote MRT file just streamed, if any, as downloaded, failing
+    /// if reading stopped at the download cap.
+    fn count_streamed(&mut self) -> Result<()> {
+        let Some(streamed) = self.streamed.take() else {
             return Ok(());
+        };
+        self.stats.downloaded_bytes += streamed.bytes();
+        match streamed.over_cap() {
+            true => Err(anyhow!(
+                "download cap reached while streaming {} after {} bytes",
+                self.collector(),
+                streamed.bytes()
+            )),
+            false => Ok(()),
+        }
+    }
+
+    /// Log the processors' error counts, if any.
+    fn log_report(&self) {
+        let report = self.report();
+        for e in report.errors.iter().filter(|e| e.errors > 0) {
+            warn!(
+                collector = report.collector.as_str(),
+                processor = e.processor.as_str(),
+                phase = "process",
+                "skipped {} of {} elements due to errors",
+                e.errors,
+                report.elems
+            );
+        }
+    }
+
+    /// Feed a single BGP element through all processors in the pipeline.
+    ///
+    /// This is the entry point for streaming sources that do not come as MRT files.
+    pub fn process_elem(&mut self, elem: &BgpElem) -> Result<()> {
+        self.process_elems(std::slice::from_ref(elem))
+    }
+
+    /// Feed a batch of BGP elements through all processors in the pipeline, handing each
+    /// processor the whole batch through [MessageProcessor::process_batch].
+    ///
+    /// Batches are split at the flush interval, so intermediate outputs are still written after
+    /// exactly every N elements.
+    pub fn process_elems(&mut self, elems: &[BgpElem]) -> Result<()> {
+        if self.error_counts.len() != self.processors.len() {
+            self.error_counts.resize(self.processors.len(), 0);
+        }
+        self.resolve_route_policies();
+        let mut start = 0;
+        while start < elems.len() {
+            let end = match self.flush_interval {
+                Some(interval) => {
+                    let until_flush = interval - self.elem_count % interval;
+                    start + (elems.len() - start).min(until_flush as usize)
+                }
+                None => elems.len(),
+            };
+            // only elements of AS_TRANS peers are copied, with their real peer ASN
+            let corrected: Vec<Option<BgpElem>> = match self.as_trans.as_mut() {
+                Some(as_trans) => elems[start..end]
+                    .iter()
+                    .map(|elem| as_trans.correct(elem))
+                    .collect(),
+                None => vec![],
+            };
+            // derived attributes are computed once and shared by all processors
+            let address_family = self.address_family;
+            let shared_inputs = self.shared_inputs();
+            let views: Vec<ElemView> = elems[start..end]
+                .iter()
+                .enumerate()
+                .map(|(i, elem)| match corrected.get(i) {
+                    Some(Some(corrected)) => corrected,
+                    _ => elem,
+                })
+                .filter(|elem| address_family.is_none_or(|af| af.matches(elem)))
+                .map(|elem| {
+                    let view = ElemView::new(elem).with_peer_id(self.peers.observe(elem));
+                    prepare_view(&shared_inputs, &view);
+                    view
+                })
+                .collect();
+            self.stats.as_trans_corrected += corrected.iter().flatten().count() as u64;
+            // views passing each distinct route policy, filtered once for all its processors
+            let mut policy_views: Vec<(RoutePolicy, Vec<ElemView>, RouteSkipCounts)> = vec![];
+            for policy in &self.processor_policies {
+                if policy.is_noop() || policy_views.iter().any(|(p, _, _)| p == policy) {
+                    continue;
+                }
+                let mut skips = RouteSkipCounts::default();
+                let passed = views
+                    .iter()
+                    .filter(|view| match policy.skip_reason(view) {
+                        Some(reason) => {
+                            skips.add(reason);
+                            false
+                        }
+                        None => true,
+                    })
+                    .cloned()
+                    .collect();
+                policy_views.push((*policy, passed, skips));
+            }
+            if self.budget_decisions.is_empty() {
+                self.check_budget();
+            }
+            let error_policy = self.error_policy;
+            for (i, (processor, error_count)) in self
+                .processors
+                .iter_mut()
+                .zip(&mut self.error_counts)
+                .enumerate()
+            {
+                let policy = self.processor_policies[i];
+                let views = match policy_views.iter().find(|(p, _, _)| *p == policy) {
+                    Some((_, passed, skips)) => {
+                        self.route_skips[i].merge(skips);
+                        passed
+                    }
+                    None => &views,
+                };
+                let name = match error_policy {
+                    ErrorPolicy::SkipAndCount => processor.name(),
+                    _ => String::new(),
+                };
+                let decision = match self.budget_decisions.is_empty() {
+                    true => None,
+                    false => {
+                        let processor_name = processor.name();
+                        self.budget_decisions
+                            .iter()
+                            .find(|d| d.processor == processor_name)
+                    }
+                };
+                let sampled: Vec<ElemView>;
+                let views = match decision.map(|d| (d.action, d.sample_every)) {
+                    None => views,
+                    Some((BudgetAction::Skipped, _)) => continue,
+                    Some((BudgetAction::Sampled, sample_every)) => {
+                        let sample_every = sample_every.unwrap_or(1).max(1);
+                        let elem_count = self.elem_count;
+                        sampled = views
+                            .iter()
+                            .enumerate()
+                            .filter(|(i, _)| (elem_count + *i as u64).is_multiple_of(sample_every))
+                            .map(|(_, view)| view.clone())
+                            .collect();
+                        &sampled
+                    }
+                };
+                processor.process_batch(views, &mut |e| match error_policy {
+                    ErrorPolicy::Abort => Err(e),
+                    ErrorPolicy::SkipAndCount => {
+                        debug!(
+                            processor = name.as_str(),
+                            phase = "process",
+                            "skipping element: {}",
+                            e
+                        );
+                        *error_count += 1;
+                        Ok(())
+                    }
+                    ErrorPolicy::SkipSilently => Ok(()),
+                })?;
+            }
+            for view in &views {
+                match view.elem().elem_type {
+                    ElemType::ANNOUNCE => self.stats.announcements += 1,
+                    ElemType::WITHDRAW => self.stats.withdrawals += 1,
+                }
+                match view.is_ipv4() {
+                    true => self.stats.ipv4_elems += 1,
+                    false => self.stats.ipv6_elems += 1,
+                }
+            }
+            self.elem_count += views.len() as u64;
+            if let Some(interval) = self.flush_interval {
+                if !views.is_empty() && self.elem_count.is_multiple_of(interval) {
+                    self.flush_partial()?;
+                }
+            }
+            start = end;
         }
+        Ok(())
+    }
 
-        info!("processing RIB file: {}", file_path);
+    /// Shared inputs declared by any processor of the pipeline.
+    fn shared_inputs(&self) -> HashSet<SharedInput> {
+        self.processors
+            .iter()
+            .flat_map(|p| p.shared_inputs())
+            .collect()
+    }
 
-        let parser = bgpkit_parser::BgpkitParser::new(file_path)?;
-        for msg in parser {
-            for processor in &mut self.processors {
-                processor.process_entry(&msg)?;
+    /// Hand the per-RIB shared data to the processors declaring shared inputs.
+    fn share_data(&mut self) {
+        let inputs = self.shared_inputs();
+        if inputs.is_empty() {
+            return;
+        }
+        let shared = SharedData::compute(&inputs, &self.peers);
+        for processor in self.processors.iter_mut() {
+            if !processor.shared_inputs().is_empty() {
+                processor.set_shared_data(&shared);
             }
         }
+    }
+
+    /// Resolve the effective route policy of each processor, if not done since the processors or
+    /// policies changed.
+    fn resolve_route_policies(&mut self) {
+        if self.processor_policies.len() != self.processors.len() {
+            self.processor_policies = self
+                .processors
+                .iter()
+                .map(|p| match self.route_policies.get(&p.name()) {
+                    Some(policy) => *policy,
+                    None => p.route_policy(),
+                })
+                .collect();
+        }
+        if self.route_skips.len() != self.processors.len() {
+            self.route_skips
+                .resize(self.processors.len(), RouteSkipCounts::default());
+        }
+    }
 
+    /// Degrade the optional processors if the current RIB is over the pipeline's budget.
+    fn check_budget(&mut self) {
+        let Some(budget) = self.budget else {
+            return;
+        };
+        let start = *self.budget_start.get_or_insert_with(Instant::now);
+        let Some(limit) = budget.exceeded(start.elapsed()) else {
+            return;
+        };
+        let action = budget.action();
+        for processor in &self.processors {
+            let name = processor.name();
+            if !self.optional_processors.contains(&name) {
+                continue;
+            }
+            warn!(
+                collector = self.collector(),
+                processor = name.as_str(),
+                phase = "process",
+                "over the {} budget after {} elements, {} from now on",
+                limit,
+                self.elem_count,
+                action
+            );
+            self.budget_decisions.push(BudgetDecision {
+                processor: name,
+                action,
+                limit,
+                after_elems: self.elem_count,
+                sample_every: (action == BudgetAction::Sampled).then_some(budget.sample_every),
+            });
+        }
+    }
+
+    /// Seed all processors from their previous outputs for the current RIB's collector.
+    ///
+    /// Must be called after setting the RIB meta information. Processors without a previous
+    /// output start empty.
+    pub fn warm_start(&mut self) -> Result<()> {
+        let collector = self.collector().to_string();
         for processor in &mut self.processors {
-            processor.output()?;
+            let path = match processor.latest_output_path() {
+                None => continue,
+                Some(p) => p,
+            };
+            match processor.load_previous(path.as_str()) {
+                Ok(()) => info!(
+                    collector = collector.as_str(),
+                    processor = processor.name().as_str(),
+                    phase = "warm_start",
+                    "loaded previous output {}",
+                    path
+                ),
+                Err(e) => warn!(
+                    collector = collector.as_str(),
+                    processor = processor.name().as_str(),
+                    phase = "warm_start",
+                    "no usable previous output, starting empty: {}",
+                    e
+                ),
+            }
         }
         Ok(())
     }
 
+    /// Names of the processors in the pipeline.
+    pub fn processor_names(&self) -> Vec<String> {
+        self.processors.iter().map(|p| p.name()).collect()
+    }
+
+    /// Statistics of the elements processed for the current RIB.
+    pub fn stats(&self) -> ProcessingStats {
+        ProcessingStats {
+            elems: self.elem_count,
+            peers: self.peers.len() as u64,
+            written_bytes: self.written_bytes.iter().sum(),
+            ..self.stats.clone()
+        }
+    }
+
+    /// Report of processing the current RIB: number of elements, per-processor error counts and
+    /// statistics.
+    pub fn report(&self) -> RunReport {
+        RunReport {
+            collector: self.collector().to_string(),
+            label: self.label.clone(),
+            stats: self.stats(),
+            elems: self.elem_count,
+            errors: match self.error_policy {
+                ErrorPolicy::SkipAndCount => self
+                    .processors
+                    .iter()
+                    .enumerate()
+                    .map(|(i, p)| ProcessorErrors {
+                        processor: p.name(),
+                        errors: self.error_counts.get(i).copied().unwrap_or(0),
+                    })
+                    .collect(),
+                _ => vec![],
+            },
+            budget_decisions: self.budget_decisions.clone(),
+            written_bytes: self
+                .processors
+                .iter()
+                .enumerate()
+                .map(|(i, p)| ProcessorBytes {
+                    processor: p.name(),
+                    bytes: self.written_bytes.get(i).copied().unwrap_or(0),
+                })
+                .collect(),
+        }
+    }
+
+    /// Write the intermediate outputs of all processors in the pipeline.
+    pub fn flush_partial(&mut self) -> Result<()> {
+        let collector = self.collector().to_string();
+        let start = Instant::now();
+        self.share_data();
+        for processor in &mut self.processors {
+            processor.flush_partial(self.sink.as_ref())?;
+        }
+        info!(
+            collector = collector.as_str(),
+            phase = "flush",
+            duration_ms = start.elapsed().as_millis() as u64,
+            "flushed intermediate outputs after {} elements",
+            self.elem_count
+        );
+        Ok(())
+    }
+
+    /// Output fields tagging results with the pipeline's address family and label, if any.
+    fn pipeline_fields(&self) -> Vec<(&'static str, Value)> {
+        let address_family = self
+            .address_family
+            .map(|af| (ADDRESS_FAMILY_FIELD, Value::from(af.to_string())));
+        let label = self
+            .label
+            .as_ref()
+            .map(|label| (LABEL_FIELD, Value::from(label.as_str())));
+        address_family.into_iter().chain(label).collect()
+    }
+
+    /// Write out the current results of all processors in the pipeline.
+    pub fn output(&mut self) -> Result<()> {
+        let collector = self.collector().to_string();
+        let counting = self.error_policy == ErrorPolicy::SkipAndCount;
+        let rib_timestamp = self
+            .rib_meta
+            .as_ref()
+            .map(|m| m.timestamp.and_utc().timestamp());
+        let mut fields = self.pipeline_fields();
+        if let Some(ts) = rib_timestamp {
+            fields.push((RIB_TIMESTAMP_FIELD, ts.into()));
+        }
+        self.resolve_route_policies();
+        self.share_data();
+        if self.written_bytes.len() != self.processors.len() {
+            self.written_bytes.resize(self.processors.len(), 0);
+        }
+        for (i, processor) in self.processors.iter_mut().enumerate() {
+            let start = Instant::now();
+            let counted = ByteCountSink {
+                inner: self.sink.as_ref(),
+                bytes: AtomicU64::new(0),
+            };
+            let mut fields = fields.clone();
+            if !self.processor_policies[i].is_noop() {
+                fields.push((
+                    ROUTE_POLICY_SKIPPED_FIELD,
+                    serde_json::to_value(self.route_skips[i])?,
+                ));
+            }
+            let latest = processor.latest_output_path();
+            let encoded = EncodingSink {
+                inner: &counted,
+                encoding: self.output_encoding,
+                keep_json: latest.iter().cloned().collect(),
+            };
+            let sink = FieldSink {
+                inner: &encoded,
+                fields,
+            };
+            let mut skip = match self.output_selection {
+                OutputSelection::All => vec![],
+                OutputSelection::DatedOnly => latest.iter().cloned().collect(),
+                OutputSelection::LatestOnly => processor
+                    .output_paths()
+                    .unwrap_or_default()
+                    .into_iter()
+                    .filter(|p| Some(p) != latest.as_ref())
+                    .collect(),
+            };
+            // never replace a latest output of a newer RIB dump, e.g. when backfilling
+            if let (Some(latest), Some(ts), false, false) = (
+                &latest,
+                rib_timestamp,
+                self.force_latest,
+                self.sink.is_stream() || self.output_selection == OutputSelection::DatedOnly,
+            ) {
+                if let Some(latest_ts) = output_rib_timestamp(self.sink.as_ref(), latest) {
+                    if latest_ts > ts {
+                        warn!(
+                            collector = collector.as_str(),
+                            processor = processor.name().as_str(),
+                            phase = "output",
+                            "keeping latest output of a newer RIB dump ({} > {}): {}",
+                            latest_ts,
+                            ts,
+                            latest
+                        );
+                        skip.push(latest.clone());
+                    }
+                }
+            }
+            let sink = SkipPathSink { inner: &sink, skip };
+            if counting {
+                let sink = ErrorCountSink {
+                    inner: &sink,
+                    errors: self.error_counts.get(i).copied().unwrap_or(0),
+                };
+                processor.output(&sink)?;
+            } else {
+                processor.output(&sink)?;
+            }
+            if self.log_brief_summaries {
+                info!(
+                    collector = collector.as_str(),
+                    processor = processor.name().as_str(),
+                    phase = "output",
+                    "{}",
+                    processor.brief_summary()
+                );
+            }
+            if let (Some(dir), Some(rib_meta)) = (&self.timeseries_dir, &self.rib_meta) {
+                if let (false, Some(result)) = (self.sink.is_stream(), processor.to_result()) {
+                    let count = timeseries::write_timeseries(
+                        &counted,
+                        dir.as_str(),
+                        processor.name().as_str(),
+                        rib_meta,
+                        &result,
+                    )?;
+                    debug!(
+                        collector = collector.as_str(),
+                        processor = processor.name().as_str(),
+                        phase = "output",
+                        "appended {} time-series records",
+                        count
+                    );
+                }
+            }
+            let written = counted.bytes.load(Ordering::Relaxed);
+            self.written_bytes[i] += written;
+            info!(
+                collector = collector.as_str(),
+                processor = processor.name().as_str(),
+                phase = "output",
+                duration_ms = start.elapsed().as_millis() as u64,
+                written_bytes = written,
+                "finished writing output"
+            );
+        }
+        self.run_analyzers()
+    }
+
+    /// Run the analyzers over the results of the processors and write their outputs.
+    fn run_analyzers(&mut self) -> Result<()> {
+        let Some(rib_meta) = self.rib_meta.clone() else {
+            return Ok(());
+        };
+        if self.analyzers.is_empty() {
+            return Ok(());
+        }
+        let mut inputs = AnalyzerInputs::default();
+        for processor in &self.processors {
+            if let Some(result) = processor.to_result() {
+                inputs.insert(processor.name().as_str(), result);
+            }
+        }
+        for analyzer in &self.analyzers {
+            let name = analyzer.name();
+            if let Some(missing) = analyzer
+                .dependencies()
+                .into_iter()
+                .find(|d| !inputs.contains(d))
+            {
+                debug!(
+                    collector = rib_meta.collector.as_str(),
+                    processor = name.as_str(),
+                    phase = "analyze",
+                    "skipping analyzer without {} result",
+                    missing
+                );
+                continue;
+            }
+            let start = Instant::now();
+            let Some(result) = analyzer.analyze(&rib_meta, &inputs)? else {
+                continue;
+            };
+            if let Some(output_path) = analyzer.output_path(&rib_meta) {
+                self.sink.write_json(output_path.as_str(), &result)?;
+                info!(
+                    collector = rib_meta.collector.as_str(),
+                    processor = name.as_str(),
+                    phase = "analyze",
+                    duration_ms = start.elapsed().as_millis() as u64,
+                    "wrote analysis to {}",
+                    output_path
+                );
+            }
+            inputs.insert(name.as_str(), result);
+        }
+        Ok(())
+    }
+
+    /// Re-stamp all processors with the given meta information and write out their current
+    /// results, without resetting their state.
+    pub fn emit_snapshot(&mut self, rib_meta: &RibMeta) -> Result<()> {
+        self.initialize_processors(rib_meta)?;
+        self.output()
+    }
+
+    /// Rebuild all processors from the routes of a route table and write out their results,
+    /// stamped with the given meta information.
+    ///
+    /// Streaming sources keep their current routes in a [RouteTable] and use this to periodically
+    /// emit refreshed snapshots. Any previous processor state, including a warm start, is
+    /// discarded.
+    pub fn emit_table_snapshot(&mut self, rib_meta: &RibMeta, table: &RouteTable) -> Result<()> {
+        self.processors = self.processors.iter().map(|p| p.new_instance()).collect();
+        self.initialize_processors(rib_meta)?;
+        self.process_elems(&table.elems())?;
+        self.output()
+    }
+
+    /// Read the routes of an MRT file into a route table instead of the processors, e.g. to
+    /// bootstrap a streaming source from the collector's latest RIB dump.
+    pub fn load_route_table(&mut self, file_path: &str, table: &mut RouteTable) -> Result<()> {
+        let downloaded = self.download_mrt_file(file_path)?;
+        let local_path = downloaded.as_deref().unwrap_or(file_path);
+        let reader = self.open_mrt_file(local_path)?;
+        for elem in legacy::MrtElems::new(reader) {
+            table.apply(elem);
+        }
+        self.count_streamed()?;
+        if let Some(path) = downloaded {
+            remove_downloaded(path.as_str());
+        }
+        info!(
+            collector = self.collector(),
+            phase = "bootstrap",
+            "loaded {} routes from {}",
+            table.len(),
+            file_path
+        );
+        Ok(())
+    }
+
     pub fn summarize_latest_files(&mut self, rib_metas: &[RibMeta]) -> Result<()> {
+        let mut alerts = vec![];
+        let mut fields = self.pipeline_fields();
+        let dumps = collector_dumps(rib_metas, self.stale_after, Utc::now().timestamp());
+        for dump in dumps.iter().filter(|d| d.stale) {
+            warn!(
+                collector = dump.collector.as_str(),
+                phase = "summarize",
+                "latest RIB dump is stale: {}",
+                DateTime::from_timestamp(dump.rib_timestamp, 0).unwrap_or_default()
+            );
+        }
+        fields.push((COLLECTOR_DUMPS_FIELD, serde_json::to_value(&dumps)?));
+        let mut summaries = BTreeMap::new();
         for processor in &mut self.processors {
             info!(
+                processor = processor.name().as_str(),
+                phase = "summarize",
                 "summarizing latest files for processor: {}",
                 processor.name()
             );
-            if let Err(e) = processor.summarize_latest(rib_metas, true) {
-                info!("failed to summarize latest files: {}", e);
+            let start = Instant::now();
+            // held until the summary and its derived outputs are written
+            let _lock = match (&self.summary_lock, processor.summary_paths().first()) {
+                (Some(options), Some(path)) if !self.sink.is_stream() => {
+                    match SummaryLock::acquire(path.as_str(), options) {
+                        Ok(lock) => Some(lock),
+                        Err(e) => {
+                            warn!(
+                                processor = processor.name().as_str(),
+                                phase = "summarize",
+                                "skipping summary: {}",
+                                e
+                            );
+                            continue;
+                        }
+                    }
+                }
+                _ => None,
+            };
+            let summary = match self.incremental_summaries {
+                true => processor.summarize_latest_incremental(self.sink.as_ref(), rib_metas, true),
+                false => processor.summarize_latest_with_sink(self.sink.as_ref(), rib_metas, true),
+            };
+            let mut summary = match summary {
+                Ok(Some(s)) => s,
+                Ok(None) => continue,
+                Err(e) => {
+                    info!(
+                        processor = processor.name().as_str(),
+                        phase = "summarize",
+                        "failed to summarize latest files: {}",
+                        e
+                    );
+                    continue;
+                }
+            };
+            let rules: Vec<&AlertRule> = self
+                .alert_rules
+                .iter()
+                .filter(|r| r.processor() == processor.name())
+                .collect();
+            // read the previous summary before it is overwritten, if anything compares with it
+            let previous: Option<Value> = match !rules.is_empty()
+                || (self.health_report.is_some() && processor.name() == "peer-stats")
+            {
+                true => processor
+                    .summary_paths()
+                    .first()
+                    .and_then(|p| self.sink.read_json(p.as_str()).ok()),
+                false => None,
+            };
+            if let Some(previous) = &previous {
+                // rules see the collector dumps the summary is written with, like the previous one
+                let dumps_added = match &mut summary {
+                    Value::Object(map) if !map.contains_key(COLLECTOR_DUMPS_FIELD) => {
+                        map.insert(
+                            COLLECTOR_DUMPS_FIELD.to_string(),
+                            serde_json::to_value(&dumps)?,
+                        );
+                        true
+                    }
+                    _ => false,
+                };
+                for rule in rules {
+                    alerts.extend(rule.evaluate(&summary, previous)?);
+                }
+                if let (true, Value::Object(map)) = (dumps_added, &mut summary) {
+                    map.remove(COLLECTOR_DUMPS_FIELD);
+                }
+            }
+            if let (Some((asn_info, path)), "as2rel") =
+                (&self.country_matrix, processor.name().as_str())
+            {
+                let as2rel = As2relSummaryJson::deserialize(&summary)?;
+                let matrix = country::country_matrix(&as2rel, asn_info);
+                self.sink
+                    .write_json(path.as_str(), &serde_json::to_value(matrix)?)?;
+            }
+            if let (Some((path, asn_info)), "pfx2as") = (&self.ip2asn, processor.name().as_str()) {
+                let pfx2as = Prefix2AsSummaryJson::deserialize(&summary)?;
+                let ranges = ip2asn::ip2asn_ranges(&pfx2as, asn_info.as_deref());
+                self.sink
+                    .write_bytes(path.as_str(), ip2asn::to_tsv(&ranges).as_bytes())?;
+            }
+            if let (Some(archive), "pfx2as") = (&self.delta_archive, processor.name().as_str()) {
+                let date = rib_metas
+                    .iter()
+                    .map(|m| m.timestamp.date())
+                    .max()
+                    .unwrap_or_else(|| Utc::now().date_naive());
+                let pfx2as = Prefix2AsSummaryJson::deserialize(&summary)?;
+                let snapshot = archive.append(self.sink.as_ref(), date, &pfx2as)?;
+                info!(
+                    processor = processor.name().as_str(),
+                    phase = "summarize",
+                    "archived {} snapshot of {} entries for {}",
+                    match snapshot.kind {
+                        archive::SnapshotKind::Full => "full",
+                        archive::SnapshotKind::Delta => "delta",
+                    },
+                    snapshot.entries,
+                    date
+                );
+            }
+            if let Some(redis) = &self.redis {
+                match redis.export(processor.name().as_str(), &summary) {
+                    Ok(Some(count)) => info!(
+                        processor = processor.name().as_str(),
+                        phase = "summarize",
+                        "exported summary to redis with {} commands",
+                        count
+                    ),
+                    Ok(None) => {}
+                    Err(e) => warn!(
+                        processor = processor.name().as_str(),
+                        phase = "summarize",
+                        "failed to export summary to redis: {}",
+                        e
+                    ),
+                }
+            }
+            if let (Some((path, drop_ratio)), "peer-stats") =
+                (&self.health_report, processor.name().as_str())
+            {
+                let peer_stats = PeerInfoSummaryJson::deserialize(&summary)?;
+                let previous = previous
+                    .as_ref()
+                    .map(PeerInfoSummaryJson::deserialize)
+                    .transpose()?;
+                let report = health::collector_health(&peer_stats, previous.as_ref(), *drop_ratio);
+                for entry in report.collectors.iter().filter(|e| e.dropped) {
+                    warn!(
+                        collector = entry.collector.as_str(),
+                        processor = processor.name().as_str(),
+                        phase = "summarize",
+                        "peer count dropped from {} to {}",
+                        entry.previous_peers_count.unwrap_or_default(),
+                        entry.peers_count
+                    );
+                }
+                self.sink
+                    .write_json(path.as_str(), &serde_json::to_value(report)?)?;
+            }
+            if let Some(n) = self.top_n {
+                match top::attach_top_views(processor.name().as_str(), &mut summary, n) {
+                    Ok(true) => debug!(
+                        processor = processor.name().as_str(),
+                        phase = "summarize",
+                        "attached top {} views",
+                        n
+                    ),
+                    Ok(false) => {}
+                    Err(e) => warn!(
+                        processor = processor.name().as_str(),
+                        phase = "summarize",
+                        "failed to compute top views: {}",
+                        e
+                    ),
+                }
+            }
+            if let Some(asn_info) = &self.asn_info {
+                if enrich::ENRICHABLE_PROCESSORS.contains(&processor.name().as_str()) {
+                    let count = enrich::attach_asn_info(&mut summary, asn_info);
+                    debug!(
+                        processor = processor.name().as_str(),
+                        phase = "summarize",
+                        "attached AS information of {} ASNs",
+                        count
+                    );
+                }
+            }
+            let sink = FieldSink {
+                inner: self.sink.as_ref(),
+                fields: fields.clone(),
+            };
+            self.summary_writer
+                .write(&sink, &processor.summary_paths(), &summary)?;
+            self.summary_writer.write_csv(
+                self.sink.as_ref(),
+                processor.name().as_str(),
+                &processor.summary_paths(),
+                &summary,
+            )?;
+            self.summary_writer.write_protobuf(
+                self.sink.as_ref(),
+                processor.name().as_str(),
+                &processor.summary_paths(),
+                &summary,
+            )?;
+            if self.artifact_path.is_some() {
+                summaries.insert(processor.name(), summary);
+            }
+            info!(
+                processor = processor.name().as_str(),
+                phase = "summarize",
+                duration_ms = start.elapsed().as_millis() as u64,
+                "finished summarizing latest files"
+            );
+        }
+        if !alerts.is_empty() {
+            for hook in &self.notification_hooks {
+                if let Err(e) = hook.notify(&alerts) {
+                    warn!(phase = "summarize", "failed to send alerts: {}", e);
+                }
+            }
+        }
+        self.alerts = alerts;
+        if let Some(path) = &self.artifact_path {
+            if !summaries.is_empty() {
+                let data = artifact::build_artifact(&summaries)?;
+                self.sink.write_bytes(path.as_str(), &data)?;
+                info!(
+                    phase = "summarize",
+                    "wrote artifact of {} summaries to {}",
+                    summaries.len(),
+                    path
+                );
             }
         }
         Ok(())
@@ -0,0 +1,243 @@
+//! Discovery of the outputs under an output root directory, local or on an object store.
+//!
+//! [list_outputs] lists the per-collector outputs of an output root directory, latest and dated,
+//! following the output layout `<processor>/<collector>/latest.json.bz2` and
+//! `<processor>/<collector>/<YYYY>/<MM>/<file>`. Object store prefixes (S3, Google Cloud Storage,
+//! Azure Blob Storage) are listed with [storage::list], so remote storage is discovered without
+//! reading any output. This powers the `list` and `prune`
+//! subcommands, the output index and the discovery of the collectors to summarize
+//! ([discover_rib_metas]).
+
+use crate::processors::{FileSink, OutputSink, RibMeta, RIB_TIMESTAMP_FIELD};
+use crate::storage;
+use anyhow::Result;
+use chrono::DateTime;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::Path;
+use tracing::{info, warn};
+
+/// A per-collector output found under an output root directory.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ListedOutput {
+    pub processor: String,
+    pub collector: String,
+    /// path relative to the output root directory
+    pub path: String,
+    /// RIB dump Unix timestamp from the file name, none for latest outputs
+    pub ts: Option<i64>,
+}
+
+/// Outputs of a collector for a processor, as printed by `ribeye list`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CollectorListing {
+    pub processor: String,
+    pub collector: String,
+    pub latest: bool,
+    /// number of dated outputs
+    pub files: usize,
+    /// RIB dump Unix timestamps of the oldest and newest dated outputs
+    pub first_ts: Option<i64>,
+    pub last_ts: Option<i64>,
+}
+
+/// Relative paths of all files under an output root directory.
+pub fn list_files(output_dir: &str) -> Result<Vec<String>> {
+    let output_dir = output_dir.trim_end_matches('/');
+    if storage::is_object_store(output_dir) {
+        let (_, _, prefix) = storage::parse_object_url(output_dir)?;
+        let prefix = match prefix.trim_matches('/') {
+            "" => String::new(),
+            prefix => format!("{}/", prefix),
+        };
+        let keys = storage::list(format!("{}/", output_dir).as_str())?;
+        return Ok(keys
+            .into_iter()
+            .filter_map(|key| key.strip_prefix(prefix.as_str()).map(|k| k.to_string()))
+            .collect());
+    }
+
+    let mut files = vec![];
+    let mut dirs = vec![Path::new(output_dir).to_path_buf()];
+    while let Some(dir) = dirs.pop() {
+        for entry in std::fs::read_dir(dir)? {
+            let path = entry?.path();
+            if path.is_dir() {
+                dirs.push(path);
+            } else if let Ok(relative) = path.strip_prefix(output_dir) {
+                files.push(relative.to_string_lossy().replace('\\', "/"));
+            }
+        }
+    }
+    Ok(files)
+}
+
+/// Timestamp of a dated output file name, `<dataset>_<collector>_<date>[_<HHMM>]_<ts>.json.bz2`
+/// (or `.cbor.bz2`).
+pub fn file_timestamp(file_name: &str) -> Option<i64> {
+    let stem = file_name.split('.').next()?;
+    stem.rsplit('_').next()?.parse().ok()
+}
+
+/// Parse a path relative to an output root directory into a per-collector output, if it follows
+/// the output layout.
+pub fn parse_output_path(path: &str) -> Option<ListedOutput> {
+    let segments: Vec<&str> = path.split('/').collect();
+    let (processor, collector, ts) = match segments.as_slice() {
+        [processor, collector, "latest.json.bz2"] => (processor, collector, None),
+        // detached signatures and other sidecar files are not outputs
+        [processor, collector, _, _, file]
+            if file.ends_with(".json.bz2") || file.ends_with(".cbor.bz2") =>
+        {
+            (processor, collector, Some(file_timestamp(file)?))
+        }
+        _ => return None,
+    };
+    Some(ListedOutput {
+        processor: processor.to_string(),
+        collector: collector.to_string(),
+        path: path.to_string(),
+        ts,
+    })
+}
+
+/// Per-collector outputs under an output root directory, sorted by path.
+pub fn list_outputs(output_dir: &str) -> Result<Vec<ListedOutput>> {
+    let mut outputs: Vec<ListedOutput> = list_files(output_dir)?
+        .iter()
+        .filter_map(|path| parse_output_path(path))
+        .collect();
+    outputs.sort_by(|a, b| a.path.cmp(&b.path));
+    Ok(outputs)
+}
+
+/// Group outputs per processor and collector, sorted by processor and collector.
+pub fn collector_listings(outputs: &[ListedOutput]) -> Vec<CollectorListing> {
+    let mut listings: BTreeMap<(&str, &str), CollectorListing> = BTreeMap::new();
+    for output in outputs {
+        let listing = listings
+            .entry((output.processor.as_str(), output.collector.as_str()))
+            .or_insert_with(|| CollectorListing {
+                processor: output.processor.clone(),
+                collector: output.collector.clone(),
+                latest: false,
+                files: 0,
+                first_ts: None,
+                last_ts: None,
+            });
+        match output.ts {
+            None => listing.latest = true,
+            Some(ts) => {
+                listing.files += 1;
+                listing.first_ts = Some(listing.first_ts.map_or(ts, |t| t.min(ts)));
+                listing.last_ts = Some(listing.last_ts.map_or(ts, |t| t.max(ts)));
+            }
+        }
+    }
+    listings.into_values().collect()
+}
+
+/// Collectors with a latest output of the given processor, sorted by name.
+pub fn latest_collectors(output_dir: &str, processor: &str) -> Result<Vec<String>> {
+    // only list the processor's prefix, paths are relative to it
+    let prefix = format!("{}/{}", output_dir.trim_end_matches('/'), processor);
+    let mut collectors: Vec<String> = list_files(prefix.as_str())?
+        .into_iter()
+        .filter_map(|path| parse_output_path(format!("{}/{}", processor, path).as_str()))
+        .filter(|o| o.ts.is_none())
+        .map(|o| o.collector)
+        .collect();
+    collectors.sort();
+    Ok(collectors)
+}
+
+/// RIB metadata of the collectors with a latest output of the given processor, read from the
+/// latest outputs, so that collectors cooked by earlier runs can be summarized again.
+pub fn discover_rib_metas(output_dir: &str, processor: &str) -> Result<Vec<RibMeta>> {
+    let mut rib_metas = vec![];
+    for collector in latest_collectors(output_dir, processor)? {
+        let path = format!(
+            "{}/{}/{}/latest.json.bz2",
+            output_dir.trim_end_matches('/'),
+            processor,
+            collector
+        );
+        let value = match FileSink.read_json(path.as_str()) {
+            Ok(v) => v,
+            Err(e) => {
+                warn!(
+                    collector = collector.as_str(),
+                    processor = processor,
+                    phase = "summarize",
+                    "skipping unreadable latest output {}: {}",
+                    path,
+                    e
+                );
+                continue;
+            }
+        };
+        let field = |name: &str| value[name].as_str().unwrap_or_default().to_string();
+        let timestamp = value[RIB_TIMESTAMP_FIELD]
+            .as_i64()
+            .and_then(|ts| DateTime::from_timestamp(ts, 0))
+            .map(|dt| dt.naive_utc())
+            .unwrap_or_default();
+        rib_metas.push(RibMeta {
+            project: field("project"),
+            collector,
+            rib_dump_url: field("rib_dump_url"),
+            timestamp,
+            ..Default::default()
+        });
+    }
+    Ok(rib_metas)
+}
+
+/// Dated outputs of RIB dumps before the given Unix timestamp. Latest outputs are never expired.
+pub fn expired_outputs(outputs: &[ListedOutput], before: i64) -> Vec<ListedOutput> {
+    outputs
+        .iter()
+        .filter(|o| o.ts.is_some_and(|ts| ts < before))
+        .cloned()
+        .collect()
+}
+
+/// Delete the dated outputs of RIB dumps before the given Unix timestamp under an output root
+/// directory, with their detached signatures, if any. Returns the deleted outputs; nothing is
+/// deleted on a dry run.
+pub fn prune_outputs(output_dir: &str, before: i64, dry_run: bool) -> Result<Vec<ListedOutput>> {
+    let output_dir = output_dir.trim_end_matches('/');
+    let files = list_files(output_dir)?;
+    let mut outputs: Vec<ListedOutput> = files
+        .iter()
+        .filter_map(|path| parse_output_path(path))
+        .collect();
+    outputs.sort_by(|a, b| a.path.cmp(&b.path));
+    let expired = expired_outputs(&outputs, before);
+    if dry_run {
+        return Ok(expired);
+    }
+    for output in &expired {
+        let signature = format!("{}.minisig", output.path);
+        delete_file(format!("{}/{}", output_dir, output.path).as_str())?;
+        if files.contains(&signature) {
+            delete_file(format!("{}/{}", output_dir, signature).as_str())?;
+        }
+    }
+    info!(
+        phase = "prune",
+        "deleted {} outputs under {}",
+        expired.len(),
+        output_dir
+    );
+    Ok(expired)
+}
+
+/// Delete a local file or object store object.
+fn delete_file(path: &str) -> Result<()> {
+    match storage::is_object_store(path) {
+        true => storage::delete(path)?,
+        false => std::fs::remove_file(path)?,
+    }
+    Ok(())
+}
@@ -0,0 +1,201 @@
+//! RIS Live streaming integration.
+//!
+//! [run_ris_live] connects to the [RIS Live](https://ris-live.ripe.net) websocket API, subscribes
+//! to the BGP updates of one route collector, and applies the updates to a [RouteTable] of the
+//! collector's current routes; peers whose session goes down are removed from the table. The
+//! processors of a [RibEye] pipeline are rebuilt from the table and
+//! their results written out periodically, each snapshot stamped with the time it was emitted.
+//! Bootstrapping the table from the collector's latest RIB dump beforehand keeps the emitted
+//! snapshots complete.
+
+use crate::util::RouteTable;
+use crate::{RibEye, RibMeta};
+use anyhow::{anyhow, Result};
+use bgpkit_parser::{parse_ris_live_message, BgpElem};
+use std::net::IpAddr;
+use std::sync::mpsc::{channel, RecvTimeoutError};
+use std::time::{Duration, Instant};
+use tracing::{info, warn};
+use tungstenite::Message;
+
+/// Default RIS Live websocket endpoint.
+pub const RIS_LIVE_URL: &str = "wss://ris-live.ripe.net/v1/ws/?client=ribeye";
+
+/// Configuration of a RIS Live streaming session.
+#[derive(Debug, Clone)]
+pub struct RisLiveConfig {
+    /// RIS Live websocket URL
+    pub url: String,
+    /// RIS route collector to subscribe to (e.g. rrc00)
+    pub collector: String,
+    /// interval between two emitted snapshots
+    pub output_interval: Duration,
+}
+
+impl RisLiveConfig {
+    pub fn new(collector: &str, output_interval: Duration) -> Self {
+        RisLiveConfig {
+            url: RIS_LIVE_URL.to_string(),
+            collector: collector.to_string(),
+            output_interval,
+        }
+    }
+
+    /// URL of the latest RIB dump of the collector, used to bootstrap processor state.
+    pub fn latest_rib_url(&self) -> String {
+        format!(
+            "https://data.ris.ripe.net/{}/latest-bview.gz",
+            self.collector
+        )
+    }
+
+    fn snapshot_meta(&self) -> RibMeta {
+        RibMeta {
+            project: "riperis".to_string(),
+            collector: self.collector.clone(),
+            rib_dump_url: self.url.clone(),
+            timestamp: chrono::Utc::now().naive_utc(),
+            ..Default::default()
+        }
+    }
+}
+
+/// Peer of a RIS Live `RIS_PEER_STATE` message reporting that its session went down, or `None` for
+/// any other message.
+pub fn ris_peer_down(msg_str: &str) -> Option<IpAddr> {
+    let msg: serde_json::Value = serde_json::from_str(msg_str).ok()?;
+    let data = &msg["data"];
+    if msg["type"] != "ris_message" || data["type"] != "RIS_PEER_STATE" || data["state"] != "down" {
+        return None;
+    }
+    data["peer"].as_str()?.parse().ok()
+}
+
+/// Changes to the route table carried by one RIS Live message.
+enum LiveUpdate {
+    Routes(Vec<BgpElem>),
+    PeerDown(IpAddr),
+}
+
+/// Stream RIS Live updates into `table` and emit outputs of the processors of `ribeye` rebuilt
+/// from it every `config.output_interval`.
+///
+/// `table` holds the routes to start from, e.g. the collector's latest RIB dump loaded with
+/// [RibEye::load_route_table]. Messages are read on a separate thread, so snapshots are emitted on
+/// time even if the collector is quiet. The function runs until the websocket connection is
+/// closed by the server, and returns an error if the connection fails.
+pub fn run_ris_live(
+    ribeye: &mut RibEye,
+    config: &RisLiveConfig,
+    table: &mut RouteTable,
+) -> Result<()> {
+    let (mut socket, _response) = tungstenite::connect(config.url.as_str())
+        .map_err(|e| anyhow!("failed to connect to {}: {}", config.url, e))?;
+    info!(
+        collector = config.collector.as_str(),
+        phase = "live",
+        "connected to RIS Live at {}",
+        config.url
+    );
+
+    for msg_type in ["UPDATE", "RIS_PEER_STATE"] {
+        let subscribe = serde_json::json!({
+            "type": "ris_subscribe",
+            "data": {
+                "host": config.collector,
+                "type": msg_type,
+            }
+        });
+        socket.send(Message::Text(subscribe.to_string()))?;
+    }
+    info!(
+        collector = config.collector.as_str(),
+        phase = "live",
+        "subscribed to RIS Live updates and peer states of {}",
+        config.collector
+    );
+
+    let (sender, receiver) = channel::<Result<LiveUpdate>>();
+    let collector = config.collector.clone();
+    std::thread::spawn(move || loop {
+        let msg = match socket.read() {
+            Ok(m) => m,
+            Err(tungstenite::Error::ConnectionClosed) => {
+                info!(
+                    collector = collector.as_str(),
+                    phase = "live",
+                    "RIS Live connection closed"
+                );
+                break;
+            }
+            Err(e) => {
+                let _ = sender.send(Err(anyhow!("failed to read from RIS Live: {}", e)));
+                break;
+            }
+        };
+
+        if let Message::Text(text) = msg {
+            if let Some(peer_ip) = ris_peer_down(text.as_str()) {
+                if sender.send(Ok(LiveUpdate::PeerDown(peer_ip))).is_err() {
+                    break;
+                }
+                continue;
+            }
+            match parse_ris_live_message(text.as_str()) {
+                Ok(elems) => {
+                    if sender.send(Ok(LiveUpdate::Routes(elems))).is_err() {
+                        break;
+                    }
+                }
+                Err(e) => {
+                    warn!(
+                        collector = collector.as_str(),
+                        phase = "live",
+                        "failed to parse RIS Live message: {}",
+                        e
+                    );
+                }
+            }
+        }
+    });
+
+    let mut last_output = Instant::now();
+    loop {
+        let timeout = config.output_interval.saturating_sub(last_output.elapsed());
+        match receiver.recv_timeout(timeout) {
+            Ok(update) => match update? {
+                LiveUpdate::Routes(elems) => {
+                    for elem in elems {
+                        table.apply(elem);
+                    }
+                }
+                LiveUpdate::PeerDown(peer_ip) => {
+                    let removed = table.remove_peer(peer_ip);
+                    info!(
+                        collector = config.collector.as_str(),
+                        phase = "live",
+                        "RIS Live peer {} down, removed {} routes",
+                        peer_ip,
+                        removed
+                    );
+                }
+            },
+            Err(RecvTimeoutError::Timeout) => {}
+            Err(RecvTimeoutError::Disconnected) => break,
+        }
+
+        if last_output.elapsed() >= config.output_interval {
+            info!(
+                collector = config.collector.as_str(),
+                phase = "output",
+                "emitting refreshed snapshot for {} with {} routes",
+                config.collector,
+                table.len()
+            );
+            ribeye.emit_table_snapshot(&config.snapshot_meta(), table)?;
+            last_output = Instant::now();
+        }
+    }
+
+    ribeye.emit_table_snapshot(&config.snapshot_meta(), table)
+}
@@ -0,0 +1,232 @@
+//! Leases guarding the shared summary outputs against concurrent writers.
+//!
+//! Overlapping `cook` runs (e.g. a cronjob overrunning its interval) summarize into the same
+//! `{dir}/{processor}/latest.json.bz2`. Before summarizing a processor, [SummaryLock::acquire]
+//! takes a lease stored next to the summary (`latest.json.bz2.lock`):
+//!
+//! - local directories: the lock file is created exclusively, so only one writer succeeds;
+//! - object stores (S3, Google Cloud Storage, Azure Blob Storage): there is no atomic create
+//!   through the upload APIs used here, so the lease object is uploaded when absent or expired,
+//!   and the writer only holds it if it still reads back its own token after a short settle delay
+//!   (reads of these stores are strongly consistent after writes).
+//!
+//! A lease expires after its time-to-live, so that a crashed run does not block later runs
+//! forever. It is released when the lock is dropped.
+
+use crate::processors::read_raw;
+use crate::storage;
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Duration as ChronoDuration, Utc};
+use serde::{Deserialize, Serialize};
+use std::io::{ErrorKind, Write};
+use std::path::Path;
+use std::time::{Duration, Instant};
+use tracing::{debug, warn};
+
+/// Default time after which a lease is considered abandoned.
+pub const DEFAULT_LEASE_TTL: Duration = Duration::from_secs(3600);
+
+/// Default time to wait for a lease held by another writer.
+pub const DEFAULT_LOCK_WAIT: Duration = Duration::from_secs(600);
+
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+const REMOTE_SETTLE_DELAY: Duration = Duration::from_secs(1);
+
+/// Path of the lease guarding an output path.
+pub fn get_lock_path(path: &str) -> String {
+    format!("{}.lock", path)
+}
+
+/// Options of the summary leases, see [RibEye::with_summary_lock](crate::RibEye::with_summary_lock).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LockOptions {
+    /// time after which a lease is considered abandoned
+    pub ttl: Duration,
+    /// time to wait for a lease held by another writer before giving up
+    pub wait: Duration,
+}
+
+impl Default for LockOptions {
+    fn default() -> Self {
+        LockOptions {
+            ttl: DEFAULT_LEASE_TTL,
+            wait: DEFAULT_LOCK_WAIT,
+        }
+    }
+}
+
+/// Content of a lease.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Lease {
+    /// unique token of the holder
+    pub owner: String,
+    pub host: String,
+    pub pid: u32,
+    pub acquired_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+}
+
+impl Lease {
+    fn new(ttl: Duration) -> Self {
+        let now = Utc::now();
+        let host = std::env::var("HOSTNAME").unwrap_or_else(|_| "unknown".to_string());
+        let pid = std::process::id();
+        Lease {
+            owner: format!(
+                "{}-{}-{}",
+                host,
+                pid,
+                now.timestamp_nanos_opt().unwrap_or_default()
+            ),
+            host,
+            pid,
+            acquired_at: now,
+            expires_at: now + ChronoDuration::from_std(ttl).unwrap_or(ChronoDuration::zero()),
+        }
+    }
+
+    pub fn is_expired(&self) -> bool {
+        self.expires_at <= Utc::now()
+    }
+}
+
+/// A held lease on an output path, released on drop.
+#[derive(Debug)]
+pub struct SummaryLock {
+    lock_path: String,
+    lease: Lease,
+}
+
+impl SummaryLock {
+    /// Take the lease on an output path, waiting up to `options.wait` while another writer holds
+    /// an unexpired lease.
+    pub fn acquire(path: &str, options: &LockOptions) -> Result<Self> {
+        let lock_path = get_lock_path(path);
+        let start = Instant::now();
+        loop {
+            let lease = Lease::new(options.ttl);
+            let held = match storage::is_object_store(lock_path.as_str()) {
+                true => try_acquire_remote(lock_path.as_str(), &lease)?,
+                false => try_acquire_local(lock_path.as_str(), &lease)?,
+            };
+            match held {
+                None => {
+                    debug!(phase = "summarize", "acquired lease {}", lock_path);
+                    return Ok(SummaryLock { lock_path, lease });
+                }
+                Some(holder) => {
+                    if start.elapsed() >= options.wait {
+                        return Err(anyhow!(
+                            "{} is locked by {} (pid {}) until {}",
+                            path,
+                            holder.host,
+                            holder.pid,
+                            holder.expires_at
+                        ));
+                    }
+                    debug!(
+                        phase = "summarize",
+                        "waiting for lease {} held by {}", lock_path, holder.owner
+                    );
+                    std::thread::sleep(POLL_INTERVAL.min(options.wait));
+                }
+            }
+        }
+    }
+
+    pub fn lease(&self) -> &Lease {
+        &self.lease
+    }
+
+    fn release(&self) -> Result<()> {
+        // do not remove a lease taken over by another writer after ours expired
+        if read_lease(self.lock_path.as_str()).map(|l| l.owner) != Some(self.lease.owner.clone()) {
+            return Ok(());
+        }
+        match storage::is_object_store(self.lock_path.as_str()) {
+            true => storage::delete(self.lock_path.as_str())?,
+            false => std::fs::remove_file(self.lock_path.as_str())?,
+        }
+        Ok(())
+    }
+}
+
+impl Drop for SummaryLock {
+    fn drop(&mut self) {
+        if let Err(e) = self.release() {
+            warn!(
+                phase = "summarize",
+                "failed to release lease {}: {}", self.lock_path, e
+            );
+        }
+    }
+}
+
+/// The current lease of a lock path, if any.
+pub fn read_lease(lock_path: &str) -> Option<Lease> {
+    let content = read_raw(lock_path).ok()?;
+    serde_json::from_slice(content.as_slice()).ok()
+}
+
+/// Create the lock file exclusively. Returns the current holder if the lease is taken.
+fn try_acquire_local(lock_path: &str, lease: &Lease) -> Result<Option<Lease>> {
+    if let Some(parent) = Path::new(lock_path).parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let mut file = match std::fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(lock_path)
+    {
+        Ok(f) => f,
+        Err(e) if e.kind() == ErrorKind::AlreadyExists => {
+            let holder = match read_lease(lock_path) {
+                Some(holder) => holder,
+                // still being written by its holder, or left unreadable by a crash
+                None => {
+                    let acquired_at =
+                        DateTime::<Utc>::from(std::fs::metadata(lock_path)?.modified()?);
+                    Lease {
+                        owner: "unknown".to_string(),
+                        host: "unknown".to_string(),
+                        pid: 0,
+                        acquired_at,
+                        expires_at: acquired_at + (lease.expires_at - lease.acquired_at),
+                    }
+                }
+            };
+            if !holder.is_expired() {
+                return Ok(Some(holder));
+            }
+            warn!(
+                phase = "summarize",
+                "taking over expired lease {} of {}", lock_path, holder.owner
+            );
+            std::fs::remove_file(lock_path)?;
+            return try_acquire_local(lock_path, lease);
+        }
+        Err(e) => return Err(e.into()),
+    };
+    file.write_all(serde_json::to_string(lease)?.as_bytes())?;
+    Ok(None)
+}
+
+/// Upload the lease object if absent or expired, then check that it was not overwritten by a
+/// concurrent writer. Returns the current holder if the lease is taken.
+fn try_acquire_remote(lock_path: &str, lease: &Lease) -> Result<Option<Lease>> {
+    if let Some(holder) = read_lease(lock_path).filter(|l| !l.is_expired()) {
+        return Ok(Some(holder));
+    }
+    let temp_dir = tempfile::tempdir()?;
+    let temp_path = temp_dir.path().join("lease");
+    let temp_path = temp_path.to_string_lossy().to_string();
+    std::fs::write(temp_path.as_str(), serde_json::to_string(lease)?)?;
+    storage::upload(lock_path, temp_path.as_str())?;
+
+    std::thread::sleep(REMOTE_SETTLE_DELAY);
+    match read_lease(lock_path) {
+        Some(current) if current.owner == lease.owner => Ok(None),
+        Some(current) => Ok(Some(current)),
+        None => Err(anyhow!("lease {} vanished after upload", lock_path)),
+    }
+}
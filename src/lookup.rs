@@ -0,0 +1,185 @@
+//! IP and ASN lookups against summarized outputs.
+//!
+//! [LookupData] loads the summarized `pfx2as`, `as2rel` and `peer-stats` outputs from an output
+//! root directory (local or S3) and answers operator-facing queries: which origins and covering
+//! prefixes an IP address has, and what an ASN originates, who it is connected to, and whether it
+//! peers with route collectors.
+
+use crate::processors::readers::{
+    read_as2rel_summary, read_peer_stats_summary, read_pfx2as_summary,
+};
+use crate::processors::{
+    get_summary_output_path, As2relSummaryJson, PeerInfoEntry, Prefix2AsCount,
+};
+use anyhow::Result;
+use ipnet::IpNet;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use std::net::IpAddr;
+use tracing::warn;
+
+/// Summarized data used for lookups. Datasets that are not available are left empty.
+#[derive(Debug, Default, Clone)]
+pub struct LookupData {
+    pub pfx2as: Vec<Prefix2AsCount>,
+    pub as2rel: Option<As2relSummaryJson>,
+    pub peers: Vec<PeerInfoEntry>,
+}
+
+/// A prefix covering the queried IP address and the origin it is announced by.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CoveringPrefix {
+    pub prefix: String,
+    pub origin: u32,
+    /// number of RIB entries (i.e. peers) the prefix-origin pair was observed in
+    pub visibility: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IpLookupResult {
+    pub ip: IpAddr,
+    /// origins of the most specific covering prefix
+    pub origins: Vec<u32>,
+    /// all covering prefixes, most specific first
+    pub covering_prefixes: Vec<CoveringPrefix>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AsnLookupResult {
+    pub asn: u32,
+    /// prefixes originated by the ASN with their visibility
+    pub prefixes: Vec<CoveringPrefix>,
+    /// ASNs adjacent to the ASN on observed paths
+    pub neighbors: BTreeSet<u32>,
+    /// inferred providers of the ASN
+    pub providers: BTreeSet<u32>,
+    /// inferred customers of the ASN
+    pub customers: BTreeSet<u32>,
+    /// route collector peer sessions of the ASN
+    pub collector_peers: Vec<PeerInfoEntry>,
+}
+
+impl LookupData {
+    /// Load the summarized outputs under `output_dir`. Missing datasets are skipped with a warning.
+    pub fn load(output_dir: &str) -> Result<Self> {
+        let mut data = LookupData::default();
+
+        let path = get_summary_output_path(output_dir, "pfx2as");
+        match read_pfx2as_summary(path.as_str()) {
+            Ok(d) => data.pfx2as = d.pfx2as,
+            Err(e) => warn!("skipping unavailable summary: {}", e),
+        }
+
+        let path = get_summary_output_path(output_dir, "as2rel");
+        match read_as2rel_summary(path.as_str()) {
+            Ok(d) => data.as2rel = Some(d),
+            Err(e) => warn!("skipping unavailable summary: {}", e),
+        }
+
+        let path = get_summary_output_path(output_dir, "peer-stats");
+        match read_peer_stats_summary(path.as_str()) {
+            Ok(d) => data.peers = d.peers.into_iter().collect(),
+            Err(e) => warn!("skipping unavailable summary: {}", e),
+        }
+
+        if data.pfx2as.is_empty() && data.as2rel.is_none() && data.peers.is_empty() {
+            return Err(anyhow::anyhow!(
+                "no summarized outputs found under {}",
+                output_dir
+            ));
+        }
+        Ok(data)
+    }
+
+    pub fn lookup_ip(&self, ip: IpAddr) -> IpLookupResult {
+        let mut covering: Vec<(IpNet, CoveringPrefix)> = self
+            .pfx2as
+            .iter()
+            .filter_map(|entry| {
+                let prefix = entry.prefix.parse::<IpNet>().ok()?;
+                match prefix.contains(&ip) {
+                    true => Some((
+                        prefix,
+                        CoveringPrefix {
+                            prefix: entry.prefix.clone(),
+                            origin: entry.asn,
+                            visibility: entry.count,
+                        },
+                    )),
+                    false => None,
+                }
+            })
+            .collect();
+        covering
+            .sort_by(|(a, _), (b, _)| b.prefix_len().cmp(&a.prefix_len()).then_with(|| a.cmp(b)));
+
+        let origins = match covering.first() {
+            None => vec![],
+            Some((most_specific, _)) => covering
+                .iter()
+                .filter(|(p, _)| p == most_specific)
+                .map(|(_, c)| c.origin)
+                .collect(),
+        };
+
+        IpLookupResult {
+            ip,
+            origins,
+            covering_prefixes: covering.into_iter().map(|(_, c)| c).collect(),
+        }
+    }
+
+    pub fn lookup_asn(&self, asn: u32) -> AsnLookupResult {
+        let mut prefixes: Vec<CoveringPrefix> = self
+            .pfx2as
+            .iter()
+            .filter(|entry| entry.asn == asn)
+            .map(|entry| CoveringPrefix {
+                prefix: entry.prefix.clone(),
+                origin: entry.asn,
+                visibility: entry.count,
+            })
+            .collect();
+        prefixes.sort_by(|a, b| a.prefix.cmp(&b.prefix));
+
+        let mut neighbors = BTreeSet::new();
+        let mut providers = BTreeSet::new();
+        let mut customers = BTreeSet::new();
+        if let Some(as2rel) = &self.as2rel {
+            for entry in &as2rel.as2rel {
+                match entry.rel {
+                    // asn1 is the provider of asn2
+                    1 if entry.asn1 == asn => {
+                        customers.insert(entry.asn2);
+                    }
+                    1 if entry.asn2 == asn => {
+                        providers.insert(entry.asn1);
+                    }
+                    0 if entry.asn1 == asn => {
+                        neighbors.insert(entry.asn2);
+                    }
+                    0 if entry.asn2 == asn => {
+                        neighbors.insert(entry.asn1);
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        let collector_peers = self
+            .peers
+            .iter()
+            .filter(|p| p.asn == asn)
+            .cloned()
+            .collect();
+
+        AsnLookupResult {
+            asn,
+            prefixes,
+            neighbors,
+            providers,
+            customers,
+            collector_peers,
+        }
+    }
+}
@@ -0,0 +1,203 @@
+//! Source manifests of MRT files from outside the broker.
+//!
+//! Archives the BGPKIT Broker does not index, e.g. PCH daily snapshots or Isolario, are processed
+//! from a [SourceManifest] listing the files with their meta information, e.g. with
+//! `cook --source-manifest manifest.json`:
+//!
+//! ```json
+//! {
+//!   "profiles": [{"project": "pch", "collectors": ["route-collector.*.pch.net"]}],
+//!   "files": [
+//!     {"url": "/data/isolario/Alderaan/2024_01/rib.20240101.0000.bz2"},
+//!     {"url": "https://example.org/snapshot.mrt.gz", "collector": "route-collector.sfo.pch.net",
+//!      "timestamp": "2024-01-01 00:00"}
+//!   ],
+//!   "patterns": [
+//!     {"url": "https://example.org/{collector}/{year}/{month}/rib.{year}{month}{day}.{hour}00.gz",
+//!      "collectors": ["route-collector.ams.pch.net"], "from": "2024-01-01", "to": "2024-01-07"}
+//!   ]
+//! }
+//! ```
+//!
+//! Meta information missing from a file entry is inferred from its path like
+//! [RibMeta::from_file_path_with], with the manifest's collector profiles added to the built-in
+//! ones. URL patterns expand to one file per collector and day from `from` to `to`, at `hour`
+//! (default: midnight), replacing `{collector}`, `{year}`, `{month}`, `{day}` and `{hour}`.
+
+use crate::collectors::{CollectorProfile, CollectorProfiles};
+use crate::processors::{dump_type_from_path, RibMeta};
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Days, NaiveDate, NaiveDateTime, NaiveTime};
+use serde::{Deserialize, Serialize};
+
+/// An MRT file of a manifest, with its meta information if not inferred from the path.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ManifestFile {
+    /// local path or URL
+    pub url: String,
+    pub collector: Option<String>,
+    /// project of the collector, by default that of its collector profile
+    pub project: Option<String>,
+    /// dump time in UTC, RFC 3339 or `YYYY-MM-DD HH:MM`
+    pub timestamp: Option<String>,
+    pub dump_type: Option<String>,
+    pub file_size: Option<u64>,
+}
+
+/// URL template of daily dumps, expanded for each collector and day of a date range.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UrlPattern {
+    /// URL with `{collector}`, `{year}`, `{month}`, `{day}` and `{hour}` placeholders
+    pub url: String,
+    pub collectors: Vec<String>,
+    pub project: Option<String>,
+    /// first day, `YYYY-MM-DD`
+    pub from: String,
+    /// last day, `YYYY-MM-DD`
+    pub to: String,
+    /// hour (UTC) of the daily dump
+    #[serde(default)]
+    pub hour: u32,
+}
+
+/// MRT files to process with their meta information, see the [module documentation](self).
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SourceManifest {
+    /// collector profiles added to the built-in ones
+    #[serde(default)]
+    pub profiles: Vec<CollectorProfile>,
+    #[serde(default)]
+    pub files: Vec<ManifestFile>,
+    #[serde(default)]
+    pub patterns: Vec<UrlPattern>,
+}
+
+fn parse_timestamp(s: &str) -> Result<NaiveDateTime> {
+    DateTime::parse_from_rfc3339(s)
+        .map(|t| t.naive_utc())
+        .or_else(|_| NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M"))
+        .map_err(|_| {
+            anyhow!(
+                "invalid timestamp {}, expected RFC 3339 or YYYY-MM-DD HH:MM",
+                s
+            )
+        })
+}
+
+fn parse_date(s: &str) -> Result<NaiveDate> {
+    NaiveDate::parse_from_str(s, "%Y-%m-%d")
+        .map_err(|_| anyhow!("invalid date {}, expected YYYY-MM-DD", s))
+}
+
+impl SourceManifest {
+    pub fn load(path: &str) -> Result<Self> {
+        let content = oneio::read_to_string(path)
+            .map_err(|e| anyhow!("failed to read source manifest {}: {}", path, e))?;
+        Self::from_json_str(content.as_str())
+            .map_err(|e| anyhow!("invalid source manifest {}: {}", path, e))
+    }
+
+    pub fn from_json_str(content: &str) -> Result<Self> {
+        Ok(serde_json::from_str(content)?)
+    }
+
+    /// The built-in collector profiles extended with the manifest's.
+    pub fn collector_profiles(&self) -> CollectorProfiles {
+        self.profiles
+            .iter()
+            .rev()
+            .fold(CollectorProfiles::new(), |profiles, profile| {
+                profiles.with_profile(profile.clone())
+            })
+    }
+
+    /// Meta information of all files of the manifest, listed files first and then the expanded
+    /// URL patterns.
+    pub fn rib_metas(&self) -> Result<Vec<RibMeta>> {
+        let profiles = self.collector_profiles();
+        let mut rib_metas = vec![];
+        for file in &self.files {
+            rib_metas.push(file_rib_meta(file, &profiles)?);
+        }
+        for pattern in &self.patterns {
+            for file in expand_pattern(pattern)? {
+                rib_metas.push(file_rib_meta(&file, &profiles)?);
+            }
+        }
+        Ok(rib_metas)
+    }
+}
+
+/// Meta information of a manifest file, inferring missing fields from its path.
+fn file_rib_meta(file: &ManifestFile, profiles: &CollectorProfiles) -> Result<RibMeta> {
+    let inferred = RibMeta::from_file_path_with(file.url.as_str(), profiles);
+    let timestamp = match (&file.timestamp, &inferred) {
+        (Some(ts), _) => parse_timestamp(ts.as_str())?,
+        (None, Some(inferred)) => inferred.timestamp,
+        (None, None) => {
+            return Err(anyhow!(
+                "cannot infer the dump time of {}, set its timestamp",
+                file.url
+            ))
+        }
+    };
+    let collector = file
+        .collector
+        .clone()
+        .or_else(|| inferred.as_ref().map(|m| m.collector.clone()))
+        .unwrap_or_else(|| "unknown".to_string());
+    let project = file
+        .project
+        .clone()
+        .unwrap_or_else(|| profiles.project(collector.as_str(), file.url.as_str()));
+    Ok(RibMeta {
+        project,
+        collector,
+        rib_dump_url: file.url.clone(),
+        timestamp,
+        file_size: file
+            .file_size
+            .or_else(|| inferred.as_ref().and_then(|m| m.file_size)),
+        dump_type: file
+            .dump_type
+            .clone()
+            .or_else(|| dump_type_from_path(file.url.as_str())),
+        ..Default::default()
+    })
+}
+
+/// Files of a URL pattern, one per collector and day.
+fn expand_pattern(pattern: &UrlPattern) -> Result<Vec<ManifestFile>> {
+    let (from, to) = (
+        parse_date(pattern.from.as_str())?,
+        parse_date(pattern.to.as_str())?,
+    );
+    let time = NaiveTime::from_hms_opt(pattern.hour, 0, 0)
+        .ok_or_else(|| anyhow!("invalid hour {} of {}", pattern.hour, pattern.url))?;
+    let mut files = vec![];
+    let mut day = from;
+    while day <= to {
+        let timestamp = day.and_time(time);
+        for collector in &pattern.collectors {
+            let url = pattern
+                .url
+                .replace("{collector}", collector)
+                .replace("{year}", timestamp.format("%Y").to_string().as_str())
+                .replace("{month}", timestamp.format("%m").to_string().as_str())
+                .replace("{day}", timestamp.format("%d").to_string().as_str())
+                .replace("{hour}", timestamp.format("%H").to_string().as_str());
+            files.push(ManifestFile {
+                url,
+                collector: Some(collector.clone()),
+                project: pattern.project.clone(),
+                timestamp: Some(timestamp.format("%Y-%m-%d %H:%M").to_string()),
+                ..Default::default()
+            });
+        }
+        day = match day.checked_add_days(Days::new(1)) {
+            Some(next) => next,
+            None => break,
+        };
+    }
+    Ok(files)
+}
@@ -0,0 +1,277 @@
+//! Parallel processing of RIB dumps as jobs.
+//!
+//! A [JobPool] processes RIB dumps, one job per dump, on its own thread pool, each with a fresh
+//! [RibEye] pipeline. Every job goes through the [JobStatus]es `queued`, `downloading` (opening the
+//! file until its first bytes arrive), `processing` and `output` (writing the processor results)
+//! before it is `done`, `failed` or `cancelled`. A callback registered with
+//! [JobPool::with_status_callback] is called on every status change, and individual jobs are
+//! cancelled through their [JobHandle], also while they are running: a cancelled job stops reading
+//! its file and writes no outputs.
+//!
+//! ```no_run
+//! use ribeye::pipeline::JobPool;
+//! use ribeye::{RibEye, RibMeta};
+//!
+//! let mut pool = JobPool::new(Some(4))
+//!     .unwrap()
+//!     .with_status_callback(|job, status| println!("{}: {}", job.rib_meta().collector, status));
+//! let job = pool.submit(RibMeta::default());
+//! job.cancel();
+//! let processors = vec!["pfx2as".to_string()];
+//! let results = pool.run(|_| RibEye::new().with_processor_names(&processors, "/tmp/ribeye"));
+//! ```
+
+use crate::{ProcessingStats, RibEye, RibMeta};
+use anyhow::{anyhow, Result};
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::fmt::{Display, Formatter};
+use std::io::Read;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use tracing::info;
+
+/// Identifier of a job, its index in the pool in order of submission.
+pub type JobId = usize;
+
+/// Status of a job.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JobStatus {
+    /// submitted and waiting for a worker
+    Queued,
+    /// opening the RIB dump file, until its first bytes are read
+    Downloading,
+    /// reading the RIB dump through the processors
+    Processing,
+    /// writing the processor results
+    Output,
+    Done,
+    /// failed with the given error
+    Failed(String),
+    Cancelled,
+}
+
+impl JobStatus {
+    /// Whether the job finished, successfully or not.
+    pub fn is_finished(&self) -> bool {
+        matches!(
+            self,
+            JobStatus::Done | JobStatus::Failed(_) | JobStatus::Cancelled
+        )
+    }
+}
+
+impl Display for JobStatus {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            JobStatus::Queued => write!(f, "queued"),
+            JobStatus::Downloading => write!(f, "downloading"),
+            JobStatus::Processing => write!(f, "processing"),
+            JobStatus::Output => write!(f, "output"),
+            JobStatus::Done => write!(f, "done"),
+            JobStatus::Failed(e) => write!(f, "failed: {}", e),
+            JobStatus::Cancelled => write!(f, "cancelled"),
+        }
+    }
+}
+
+type StatusCallback = Arc<dyn Fn(&JobHandle, &JobStatus) + Send + Sync>;
+
+/// Shared handle of a submitted job, to follow its status and cancel it from any thread.
+#[derive(Clone)]
+pub struct JobHandle {
+    id: JobId,
+    rib_meta: Arc<RibMeta>,
+    status: Arc<Mutex<JobStatus>>,
+    cancelled: Arc<AtomicBool>,
+    callback: Option<StatusCallback>,
+}
+
+impl JobHandle {
+    pub fn id(&self) -> JobId {
+        self.id
+    }
+
+    pub fn rib_meta(&self) -> &RibMeta {
+        &self.rib_meta
+    }
+
+    pub fn status(&self) -> JobStatus {
+        self.status.lock().unwrap().clone()
+    }
+
+    /// Cancel the job: a queued job is skipped and a running one stops without writing outputs.
+    /// Finished jobs are not affected.
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+
+    fn set_status(&self, status: JobStatus) {
+        {
+            let mut current = self.status.lock().unwrap();
+            if *current == status {
+                return;
+            }
+            *current = status.clone();
+        }
+        if let Some(callback) = &self.callback {
+            callback(self, &status);
+        }
+    }
+}
+
+/// Result of a job run by [JobPool::run].
+pub struct JobResult {
+    pub id: JobId,
+    pub rib_meta: RibMeta,
+    /// statistics of the processed RIB, or the error the job failed or was cancelled with
+    pub result: Result<ProcessingStats>,
+}
+
+/// Reader of a job's RIB dump file, moving the job from downloading to processing on the first
+/// bytes and to output at the end of the file, and failing once the job is cancelled.
+struct JobReader<R> {
+    inner: R,
+    job: JobHandle,
+    started: bool,
+}
+
+impl<R: Read> Read for JobReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        if self.job.is_cancelled() {
+            return Err(std::io::Error::other("job cancelled"));
+        }
+        let n = self.inner.read(buf)?;
+        match n {
+            0 => self.job.set_status(JobStatus::Output),
+            _ if !self.started => {
+                self.started = true;
+                self.job.set_status(JobStatus::Processing);
+            }
+            _ => {}
+        }
+        Ok(n)
+    }
+}
+
+/// Pool processing RIB dumps in parallel, see the [module documentation](self).
+pub struct JobPool {
+    pool: rayon::ThreadPool,
+    jobs: Vec<JobHandle>,
+    callback: Option<StatusCallback>,
+}
+
+impl JobPool {
+    /// Create a pool processing up to `parallel` RIB dumps concurrently, by default as many as the
+    /// global rayon thread pool has threads.
+    pub fn new(parallel: Option<usize>) -> Result<Self> {
+        let parallel = parallel.unwrap_or_else(rayon::current_num_threads).max(1);
+        info!("processing up to {} RIB files concurrently", parallel);
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(parallel)
+            .thread_name(|i| format!("ribeye-file-{}", i))
+            .build()?;
+        Ok(JobPool {
+            pool,
+            jobs: vec![],
+            callback: None,
+        })
+    }
+
+    /// Call `callback` with the job and its new status on every status change of the jobs
+    /// submitted afterwards. It is called from the worker threads.
+    pub fn with_status_callback(
+        mut self,
+        callback: impl Fn(&JobHandle, &JobStatus) + Send + Sync + 'static,
+    ) -> Self {
+        self.callback = Some(Arc::new(callback));
+        self
+    }
+
+    /// Queue a job processing the RIB dump of `rib_meta`.
+    pub fn submit(&mut self, rib_meta: RibMeta) -> JobHandle {
+        let job = JobHandle {
+            id: self.jobs.len(),
+            rib_meta: Arc::new(rib_meta),
+            status: Arc::new(Mutex::new(JobStatus::Queued)),
+            cancelled: Arc::new(AtomicBool::new(false)),
+            callback: self.callback.clone(),
+        };
+        if let Some(callback) = &self.callback {
+            callback(&job, &JobStatus::Queued);
+        }
+        self.jobs.push(job.clone());
+        job
+    }
+
+    /// All submitted jobs, by ID.
+    pub fn jobs(&self) -> &[JobHandle] {
+        &self.jobs
+    }
+
+    pub fn job(&self, id: JobId) -> Option<&JobHandle> {
+        self.jobs.get(id)
+    }
+
+    /// Cancel a job by ID, returning whether it exists.
+    pub fn cancel(&self, id: JobId) -> bool {
+        self.job(id).inspect(|job| job.cancel()).is_some()
+    }
+
+    /// Run all queued jobs and wait for them to finish. Each job processes its RIB dump with the
+    /// pipeline `ribeye` creates for its RIB meta information.
+    pub fn run<F>(&self, ribeye: F) -> Vec<JobResult>
+    where
+        F: Fn(&RibMeta) -> Result<RibEye> + Sync,
+    {
+        let queued: Vec<&JobHandle> = self
+            .jobs
+            .iter()
+            .filter(|job| job.status() == JobStatus::Queued)
+            .collect();
+        self.pool.install(|| {
+            queued
+                .par_iter()
+                .map(|job| JobResult {
+                    id: job.id,
+                    rib_meta: job.rib_meta().clone(),
+                    result: Self::run_job(job, &ribeye),
+                })
+                .collect()
+        })
+    }
+
+    fn run_job<F>(job: &JobHandle, ribeye: &F) -> Result<ProcessingStats>
+    where
+        F: Fn(&RibMeta) -> Result<RibEye>,
+    {
+        let rib_meta = job.rib_meta();
+        let result = match job.is_cancelled() {
+            true => Err(anyhow!("processing {} cancelled", rib_meta.collector)),
+            false => {
+                job.set_status(JobStatus::Downloading);
+                ribeye(rib_meta).and_then(|ribeye| {
+                    ribeye
+                        .with_rib_meta(rib_meta)
+                        .with_cancel_flag(job.cancelled.clone())
+                        .process_mrt_file_with(rib_meta.rib_dump_url.as_str(), |reader| JobReader {
+                            inner: reader,
+                            job: job.clone(),
+                            started: false,
+                        })
+                })
+            }
+        };
+        job.set_status(match &result {
+            Ok(_) => JobStatus::Done,
+            Err(_) if job.is_cancelled() => JobStatus::Cancelled,
+            Err(e) => JobStatus::Failed(e.to_string()),
+        });
+        result
+    }
+}
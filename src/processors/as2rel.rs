@@ -1,46 +1,61 @@
-use crate::processors::meta::{
-    get_default_output_path, get_latest_output_path, ProcessorMeta, RibMeta,
-};
-use crate::processors::write_output_file;
-use crate::MessageProcessor;
-use bgpkit_parser::models::ElemType;
+use crate::processors::merge::{rib_dump_urls, Mergeable};
+use crate::processors::meta::{ProcessorMeta, RibMeta};
+use crate::processors::peers::{PeerId, PeerTracker};
+use crate::processors::shared::SharedInput;
+use crate::processors::typed::process_entry_view;
+use crate::processors::TypedProcessor;
+use crate::util::{format_count, AsnSet, ElemView, IxpPolicy, RoutePolicy};
 use bgpkit_parser::BgpElem;
-use itertools::Itertools;
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
-use serde_json::json;
 use std::collections::{HashMap, HashSet};
-use std::net::IpAddr;
-use tracing::{info, warn};
+use std::sync::Arc;
 
-#[derive(Serialize, Deserialize)]
-struct As2relEntry {
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct As2relEntry {
     pub asn1: u32,
     pub asn2: u32,
     pub paths_count: usize,
     pub peers_count: usize,
     pub rel: u8,
+    /// one of the ASes is an IXP route server, only set with [IxpPolicy::Tag]
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub ixp: bool,
+    /// paths of the opposite entry: the adjacency in reverse path order (`rel` 0), or the opposite
+    /// provider-to-customer inference (`rel` 1)
+    #[serde(default)]
+    pub reverse_paths_count: usize,
+    /// confidence between 0 and 1, see [score_as2rel]
+    #[serde(default)]
+    pub confidence: f64,
 }
 
-#[derive(Serialize, Deserialize)]
-struct As2relCollectorJson {
-    project: String,
-    collector: String,
-    rib_dump_url: String,
-    as2rel: Vec<As2relEntry>,
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct As2relCollectorJson {
+    pub project: String,
+    pub collector: String,
+    pub rib_dump_url: String,
+    pub as2rel: Vec<As2relEntry>,
 }
 
-#[derive(Serialize, Deserialize)]
-struct As2relSummaryJson {
-    rib_dump_urls: Vec<String>,
-    as2rel: Vec<As2relEntry>,
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct As2relSummaryJson {
+    pub rib_dump_urls: Vec<String>,
+    pub as2rel: Vec<As2relEntry>,
 }
 
 pub struct As2relProcessor {
     rib_meta: Option<RibMeta>,
     processor_meta: ProcessorMeta,
-    as2rel_map: HashMap<(u32, u32, u8), (usize, HashSet<IpAddr>)>,
+    as2rel_map: HashMap<(u32, u32, u8), (usize, HashSet<PeerId>)>,
+    peers: PeerTracker,
+    ixp_asns: Arc<AsnSet>,
+    ixp_policy: IxpPolicy,
 }
 
+/// Number of peers at which an observation counts as seen by enough independent vantage points.
+const CONFIDENT_PEERS: usize = 10;
+
 const TIER1: [u32; 17] = [
     6762, 12956, 2914, 3356, 6453, 1239, 701, 6461, 3257, 1299, 3491, 7018, 3320, 5511, 6830, 174,
     6939,
@@ -57,9 +72,19 @@ impl As2relProcessor {
             rib_meta: None,
             processor_meta,
             as2rel_map: HashMap::new(),
+            peers: PeerTracker::new(),
+            ixp_asns: Arc::new(AsnSet::new()),
+            ixp_policy: IxpPolicy::Keep,
         }
     }
 
+    /// Set the IXP route-server ASNs and how to handle them on AS paths.
+    pub fn with_ixp_asns(mut self, ixp_asns: Arc<AsnSet>, ixp_policy: IxpPolicy) -> Self {
+        self.ixp_asns = ixp_asns;
+        self.ixp_policy = ixp_policy;
+        self
+    }
+
     fn get_count_vec(&self) -> Vec<As2relEntry> {
         let res: Vec<As2relEntry> = self
             .as2rel_map
@@ -70,66 +95,127 @@ impl As2relProcessor {
                 paths_count: *count,
                 peers_count: peers.len(),
                 rel: *rel,
+                ixp: self.ixp_policy == IxpPolicy::Tag
+                    && (self.ixp_asns.contains(*asn1) || self.ixp_asns.contains(*asn2)),
+                reverse_paths_count: 0,
+                confidence: 0.0,
             })
             .collect();
-        res
+        score_as2rel(res)
     }
 }
 
-impl MessageProcessor for As2relProcessor {
-    fn name(&self) -> String {
-        self.processor_meta.name.clone()
+/// Set the reverse path counts and confidence scores of relationship entries.
+///
+/// The confidence is the mean of two scores:
+/// - vantage points: the number of peers observing the entry, saturating at 10;
+/// - consistency: for provider-to-customer inferences, the share of Tier-1 triangulated paths
+///   agreeing with the direction; for adjacencies, 1 if seen in both path directions and 0.5
+///   otherwise.
+pub fn score_as2rel(mut entries: Vec<As2relEntry>) -> Vec<As2relEntry> {
+    let paths: HashMap<(u32, u32, u8), usize> = entries
+        .iter()
+        .map(|e| ((e.asn1, e.asn2, e.rel), e.paths_count))
+        .collect();
+    for entry in entries.iter_mut() {
+        entry.reverse_paths_count = paths
+            .get(&(entry.asn2, entry.asn1, entry.rel))
+            .copied()
+            .unwrap_or(0);
+        let vantage = entry.peers_count.min(CONFIDENT_PEERS) as f64 / CONFIDENT_PEERS as f64;
+        let consistency = match entry.rel {
+            1 => {
+                entry.paths_count as f64
+                    / (entry.paths_count + entry.reverse_paths_count).max(1) as f64
+            }
+            _ => match entry.reverse_paths_count > 0 {
+                true => 1.0,
+                false => 0.5,
+            },
+        };
+        entry.confidence = ((vantage + consistency) / 2.0 * 1000.0).round() / 1000.0;
     }
+    entries
+}
+
+impl TypedProcessor for As2relProcessor {
+    type Output = As2relCollectorJson;
+    type Summary = As2relSummaryJson;
+    const DATASET_FIELD: &'static str = "as2rel";
 
-    fn output_paths(&self) -> Option<Vec<String>> {
-        Some(vec![
-            get_default_output_path(self.rib_meta.as_ref().unwrap(), &self.processor_meta),
-            get_latest_output_path(self.rib_meta.as_ref().unwrap(), &self.processor_meta),
-        ])
+    fn processor_meta(&self) -> &ProcessorMeta {
+        &self.processor_meta
+    }
+
+    fn rib_meta(&self) -> Option<&RibMeta> {
+        self.rib_meta.as_ref()
+    }
+
+    fn new_instance(&self) -> Self {
+        Self::new(self.processor_meta.output_dir.as_str())
+            .with_ixp_asns(self.ixp_asns.clone(), self.ixp_policy)
     }
 
     fn reset_processor(&mut self, rib_meta: &RibMeta) {
         self.rib_meta = Some(rib_meta.clone());
+        self.peers.set_collector(Some(rib_meta.collector.clone()));
+    }
+
+    fn route_policy(&self) -> RoutePolicy {
+        RoutePolicy::new().with_skip_default(true)
+    }
+
+    fn shared_inputs(&self) -> Vec<SharedInput> {
+        vec![SharedInput::StrippedPath]
     }
 
     fn process_entry(&mut self, elem: &BgpElem) -> anyhow::Result<()> {
-        // skip processing non-announce messages
-        if elem.elem_type != ElemType::ANNOUNCE {
-            return Ok(());
-        }
+        process_entry_view(self, elem)
+    }
 
-        // skip default route
-        if elem.prefix.prefix.prefix_len() == 0 {
-            return Ok(());
-        }
+    fn process_view(&mut self, view: &ElemView) -> anyhow::Result<()> {
+        let peer_id = self.peers.observe(view.elem());
 
-        // skip no-path or non-regular path
-        if elem.as_path.is_none() {
+        // skip processing non-announce messages, default routes are skipped by the route policy
+        if !view.is_announce() {
             return Ok(());
         }
 
-        let mut u32_path = match elem.as_path.as_ref().unwrap().to_u32_vec_opt(true) {
+        // skip no-path or non-regular path
+        let path = match view.path() {
             None => return Ok(()),
             Some(p) => p,
         };
+        let stripped;
+        let path = match self.ixp_policy {
+            IxpPolicy::Strip if path.contains_any(&self.ixp_asns) => {
+                stripped = match path.without(&self.ixp_asns) {
+                    None => return Ok(()),
+                    Some(p) => p,
+                };
+                &stripped
+            }
+            _ => path,
+        };
 
         // get peers count
-        for (asn1, asn2) in u32_path.iter().tuple_windows::<(&u32, &u32)>() {
+        for (asn1, asn2) in path.links() {
             let (msg_count, peers) = self
                 .as2rel_map
-                .entry((*asn1, *asn2, 0))
+                .entry((asn1, asn2, 0))
                 .or_insert((0, HashSet::new()));
             *msg_count += 1;
-            peers.insert(elem.peer_ip);
+            peers.insert(peer_id);
         }
 
-        let contains_tier1 = u32_path.iter().any(|x| TIER1.contains(x));
+        let contains_tier1 = path.iter().any(|x| TIER1.contains(&x));
 
         if !contains_tier1 {
             return Ok(());
         }
 
         // reverse path order to make origin first
+        let mut u32_path = path.asns().to_vec();
         u32_path.reverse();
 
         // find the first tier-1 AS index
@@ -150,79 +236,62 @@ impl MessageProcessor for As2relProcessor {
                     .entry((*asn2, *asn1, 1))
                     .or_insert((0, HashSet::new()));
                 *msg_count += 1;
-                peers.insert(elem.peer_ip);
+                peers.insert(peer_id);
             }
         }
 
         Ok(())
     }
 
-    fn to_result_string(&self) -> Option<String> {
-        let rib_meta = self.rib_meta.as_ref().unwrap();
-        let json_data = As2relCollectorJson {
+    fn brief_summary(&self) -> String {
+        let ases: HashSet<u32> = self
+            .as2rel_map
+            .keys()
+            .flat_map(|(asn1, asn2, _)| [*asn1, *asn2])
+            .collect();
+        format!(
+            "{}: {} relationships between {} ASes",
+            self.processor_meta.name,
+            format_count(self.as2rel_map.len()),
+            format_count(ases.len())
+        )
+    }
+
+    fn to_output(&self) -> Option<As2relCollectorJson> {
+        let rib_meta = self.rib_meta.as_ref()?;
+        Some(As2relCollectorJson {
             project: rib_meta.project.clone(),
             collector: rib_meta.collector.clone(),
             rib_dump_url: rib_meta.rib_dump_url.clone(),
             as2rel: self.get_count_vec(),
-        };
-        let value = json!(json_data);
-
-        serde_json::to_string_pretty(&value).ok()
-    }
-
-    fn summarize_latest(&self, rib_metas: &[RibMeta], ignore_error: bool) -> anyhow::Result<()> {
-        let mut as2rel_map = HashMap::<(u32, u32, u8), (usize, usize)>::new();
-
-        for rib_meta in rib_metas {
-            let latest_file_path = get_latest_output_path(rib_meta, &self.processor_meta);
-            info!("summarizing {}...", latest_file_path.as_str());
-            let data =
-                match oneio::read_json_struct::<As2relCollectorJson>(latest_file_path.as_str()) {
-                    Ok(d) => d,
-                    Err(e) => {
-                        if ignore_error {
-                            warn!("failed to read {}, skipping...", latest_file_path.as_str());
-                            continue;
-                        } else {
-                            return Err(anyhow::anyhow!(
-                                "failed to read {}: {}",
-                                latest_file_path.as_str(),
-                                e
-                            ));
-                        }
-                    }
-                };
-            for entry in data.as2rel {
-                let (asn1, asn2, rel) = (entry.asn1, entry.asn2, entry.rel);
-                let (msg_count, peers_count) =
-                    as2rel_map.entry((asn1, asn2, rel)).or_insert((0, 0));
-                *msg_count += entry.paths_count;
-                *peers_count += entry.peers_count;
-            }
-        }
-        let res: Vec<As2relEntry> = as2rel_map
-            .iter()
-            .map(|((asn1, asn2, rel), (count, peers))| As2relEntry {
-                asn1: *asn1,
-                asn2: *asn2,
-                paths_count: *count,
-                peers_count: *peers,
-                rel: *rel,
-            })
-            .collect();
-        let json_data = As2relSummaryJson {
-            rib_dump_urls: rib_metas.iter().map(|r| r.rib_dump_url.clone()).collect(),
-            as2rel: res,
-        };
+        })
+    }
+}
 
-        let output_file_dir = format!(
-            "{}/{}",
-            self.processor_meta.output_dir.as_str(),
-            self.processor_meta.name.as_str(),
-        );
-        let output_content = serde_json::to_string_pretty(&json_data)?;
-        write_output_file(output_file_dir.as_str(), output_content.as_str(), true)?;
+impl Mergeable for As2relCollectorJson {
+    type Entry = As2relEntry;
+    type Key = (u32, u32, u8);
+    type Merged = As2relSummaryJson;
 
-        Ok(())
+    fn into_entries(self) -> Vec<As2relEntry> {
+        self.as2rel
+    }
+
+    fn entry_key(entry: &As2relEntry) -> (u32, u32, u8) {
+        (entry.asn1, entry.asn2, entry.rel)
+    }
+
+    fn merge_entry(entry: &mut As2relEntry, other: As2relEntry) {
+        entry.paths_count += other.paths_count;
+        entry.peers_count += other.peers_count;
+        entry.ixp |= other.ixp;
+    }
+
+    fn merge_files(rib_metas: &[RibMeta], entries: Vec<As2relEntry>) -> As2relSummaryJson {
+        As2relSummaryJson {
+            rib_dump_urls: rib_dump_urls(rib_metas),
+            // scores depend on the opposite entries, rescore with merged counts
+            as2rel: score_as2rel(entries),
+        }
     }
 }
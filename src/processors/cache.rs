@@ -0,0 +1,71 @@
+//! Cache of per-collector outputs for incremental summaries.
+//!
+//! Summarizing rereads the latest output of every collector, even if only a few collectors
+//! produced new data since the last summary. With incremental summaries, each processor keeps the
+//! parsed latest outputs in a gzip-compressed `<output_dir>/<processor>/summary-cache.json.gz`,
+//! keyed by a fingerprint of the latest file (size and modification time locally, the ETag on
+//! object stores). Latest files that did not change are not downloaded and decoded again.
+//!
+//! Only reading is incremental: the summary is still merged from the outputs of every collector,
+//! since merges are not invertible in general (e.g. peer lists) and a changed output cannot be
+//! taken back out of a merged summary.
+
+use crate::processors::OutputSink;
+use crate::storage;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::time::UNIX_EPOCH;
+
+/// File name of a processor's summary cache.
+pub const SUMMARY_CACHE_FILE: &str = "summary-cache.json.gz";
+
+/// Path of the summary cache of a processor.
+pub fn get_summary_cache_path(output_dir: &str, processor_name: &str) -> String {
+    format!("{}/{}/{}", output_dir, processor_name, SUMMARY_CACHE_FILE)
+}
+
+/// Fingerprint of a local file or object store object that changes whenever it is rewritten.
+/// `None` for other remote files, which are always read again.
+pub fn file_fingerprint(path: &str) -> Option<String> {
+    if storage::is_object_store(path) {
+        return storage::etag(path).ok().flatten();
+    }
+    if path.contains("://") {
+        return None;
+    }
+    let metadata = std::fs::metadata(path).ok()?;
+    let modified = metadata.modified().ok()?.duration_since(UNIX_EPOCH).ok()?;
+    Some(format!("{}-{}", metadata.len(), modified.as_nanos()))
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SummaryCacheEntry {
+    /// path of the latest per-collector output
+    pub path: String,
+    pub fingerprint: String,
+    pub output: Value,
+}
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct SummaryCache {
+    pub entries: Vec<SummaryCacheEntry>,
+}
+
+impl SummaryCache {
+    /// Read a cache through the sink that wrote it; empty if missing or unreadable.
+    pub fn load(sink: &dyn OutputSink, path: &str) -> Self {
+        sink.read_bytes(path)
+            .ok()
+            .and_then(|data| serde_json::from_slice(&data).ok())
+            .unwrap_or_default()
+    }
+
+    /// Remove and return the cached output of a latest file, if it did not change since.
+    pub fn take(&mut self, path: &str, fingerprint: &str) -> Option<Value> {
+        let i = self
+            .entries
+            .iter()
+            .position(|e| e.path == path && e.fingerprint == fingerprint)?;
+        Some(self.entries.swap_remove(i).output)
+    }
+}
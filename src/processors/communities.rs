@@ -0,0 +1,521 @@
+//! `communities` processor correlates BGP communities with the paths of the routes carrying them,
+//! to mine candidate community semantics as a research dataset.
+//!
+//! For each community (`asn:value`, or `asn:data1:data2` for large communities), the processor
+//! records, on the routes where its owner AS (the first field) is on the path, whether the owner
+//! prepends its ASN and which neighbor the owner passed the routes to. The same observations are
+//! kept for all routes through each owner as a baseline. Summaries also count the collectors the
+//! community and its owner are seen at, and [community_candidates] flags communities whose routes
+//! deviate from their owner's baseline, e.g. `64500:1000` looks like a no-export-to-AS174 action
+//! if AS64500 commonly exports to AS174 but never does so for routes tagged `64500:1000`.
+//!
+//! Candidates are heuristics: informational communities (e.g. where a route was learned) can
+//! correlate with the same path properties as action communities.
+
+use crate::processors::merge::{merge_outputs, rib_dump_urls, Mergeable};
+use crate::processors::meta::{ProcessorMeta, RibMeta};
+use crate::processors::shared::SharedInput;
+use crate::processors::typed::process_entry_view;
+use crate::processors::TypedProcessor;
+use crate::util::{format_count, ElemView};
+use bgpkit_parser::models::{Community, MetaCommunity};
+use bgpkit_parser::BgpElem;
+use itertools::Itertools;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fmt::{Display, Formatter};
+
+/// Minimum number of routes carrying a community through its owner to derive candidates.
+pub const MIN_CANDIDATE_ROUTES: usize = 10;
+
+/// Share of a community's routes that must show a property for it to be a candidate.
+const STRONG_SHARE: f64 = 0.9;
+
+/// Share of the owner's other routes below which a property is unusual for the owner.
+const WEAK_SHARE: f64 = 0.5;
+
+/// Share of the owner's other routes a neighbor must receive to expect it for a community.
+const NEIGHBOR_SHARE: f64 = 0.1;
+
+/// Minimum number of collectors seeing the owner to flag a community's limited propagation.
+const MIN_SCOPE_COLLECTORS: usize = 3;
+
+/// Observations of the routes carrying a community.
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct CommunityEntry {
+    /// `asn:value`, or `asn:data1:data2` for large communities
+    pub community: String,
+    /// AS defining the community, i.e. its first field
+    pub owner: u32,
+    /// number of routes carrying the community
+    pub routes: usize,
+    /// routes with the owner on the AS path
+    pub owner_routes: usize,
+    /// routes on which the owner prepends its ASN
+    pub owner_prepended: usize,
+    /// route counts by the neighbor the owner passed the routes to, towards the collector
+    pub neighbors: BTreeMap<u32, usize>,
+    /// collectors the community was observed at
+    pub collectors: BTreeSet<String>,
+}
+
+/// Observations of all routes through a community owner, the baseline for its communities.
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct CommunityOwnerEntry {
+    pub asn: u32,
+    /// number of routes with the AS on the path
+    pub routes: usize,
+    /// routes on which the AS prepends its ASN
+    pub prepended: usize,
+    /// route counts by the neighbor the AS passed the routes to, towards the collector
+    pub neighbors: BTreeMap<u32, usize>,
+    /// collectors the AS was observed at
+    pub collectors: BTreeSet<String>,
+}
+
+/// Action a community is suspected to trigger.
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, JsonSchema,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum CommunityAction {
+    /// the owner prepends its ASN
+    Prepend,
+    /// the owner does not export to the target neighbor
+    NoExportTo,
+    /// the owner exports only to the target neighbor
+    ExportOnlyTo,
+    /// routes propagate to far fewer collectors than the owner's other routes
+    LimitedScope,
+}
+
+impl Display for CommunityAction {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let action = match self {
+            CommunityAction::Prepend => "prepend",
+            CommunityAction::NoExportTo => "no-export-to",
+            CommunityAction::ExportOnlyTo => "export-only-to",
+            CommunityAction::LimitedScope => "limited-scope",
+        };
+        write!(f, "{}", action)
+    }
+}
+
+/// Candidate semantics of a community.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct CommunityCandidate {
+    pub community: String,
+    pub owner: u32,
+    pub action: CommunityAction,
+    /// neighbor of the owner the action applies to, if any
+    pub target: Option<u32>,
+    /// share of the community's routes, or of the expected routes or collectors, supporting the
+    /// candidate, between 0 and 1
+    pub support: f64,
+    pub description: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct CommunitiesCollectorJson {
+    pub project: String,
+    pub collector: String,
+    pub rib_dump_url: String,
+    pub communities: Vec<CommunityEntry>,
+    /// baselines of the community owners
+    pub owners: Vec<CommunityOwnerEntry>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct CommunitiesSummaryJson {
+    pub rib_dump_urls: Vec<String>,
+    pub communities: Vec<CommunityEntry>,
+    /// baselines of the community owners
+    pub owners: Vec<CommunityOwnerEntry>,
+    /// candidate community semantics, see [community_candidates]
+    pub candidates: Vec<CommunityCandidate>,
+}
+
+/// Regular or large community with an owner AS.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+enum CommunityKey {
+    Regular(u32, u16),
+    Large(u32, u32, u32),
+}
+
+impl CommunityKey {
+    /// Key of a regular or large community, none for well-known and extended communities.
+    fn new(community: &MetaCommunity) -> Option<CommunityKey> {
+        match community {
+            MetaCommunity::Plain(Community::Custom(asn, value)) => {
+                Some(CommunityKey::Regular(asn.to_u32(), *value))
+            }
+            MetaCommunity::Large(c) => Some(CommunityKey::Large(
+                c.global_admin,
+                c.local_data[0],
+                c.local_data[1],
+            )),
+            _ => None,
+        }
+    }
+
+    fn owner(&self) -> u32 {
+        match self {
+            CommunityKey::Regular(asn, _) | CommunityKey::Large(asn, _, _) => *asn,
+        }
+    }
+}
+
+impl Display for CommunityKey {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CommunityKey::Regular(asn, value) => write!(f, "{}:{}", asn, value),
+            CommunityKey::Large(asn, data1, data2) => write!(f, "{}:{}:{}", asn, data1, data2),
+        }
+    }
+}
+
+/// Route observations of a community or of all routes through an AS.
+#[derive(Debug, Default, Clone)]
+struct RouteStats {
+    routes: usize,
+    owner_routes: usize,
+    prepended: usize,
+    neighbors: HashMap<u32, usize>,
+}
+
+impl RouteStats {
+    /// Record a route through `owner`, at `position` on the path.
+    fn add(&mut self, path: &[u32], position: usize, prepended: bool) {
+        self.owner_routes += 1;
+        if prepended {
+            self.prepended += 1;
+        }
+        if position > 0 {
+            *self.neighbors.entry(path[position - 1]).or_default() += 1;
+        }
+    }
+}
+
+/// Sum route counts by neighbor into `counts`.
+fn add_counts(counts: &mut BTreeMap<u32, usize>, other: BTreeMap<u32, usize>) {
+    for (asn, count) in other {
+        *counts.entry(asn).or_default() += count;
+    }
+}
+
+pub struct CommunitiesProcessor {
+    rib_meta: Option<RibMeta>,
+    processor_meta: ProcessorMeta,
+    communities: HashMap<CommunityKey, RouteStats>,
+    /// baselines of all ASes on paths, only the community owners are written out
+    ases: HashMap<u32, RouteStats>,
+}
+
+impl CommunitiesProcessor {
+    pub fn new(output_dir: &str) -> Self {
+        let processor_meta = ProcessorMeta {
+            name: "communities".to_string(),
+            output_dir: output_dir.to_string(),
+        };
+
+        CommunitiesProcessor {
+            rib_meta: None,
+            processor_meta,
+            communities: HashMap::new(),
+            ases: HashMap::new(),
+        }
+    }
+}
+
+impl TypedProcessor for CommunitiesProcessor {
+    type Output = CommunitiesCollectorJson;
+    type Summary = CommunitiesSummaryJson;
+    const DATASET_FIELD: &'static str = "communities";
+
+    fn processor_meta(&self) -> &ProcessorMeta {
+        &self.processor_meta
+    }
+
+    fn rib_meta(&self) -> Option<&RibMeta> {
+        self.rib_meta.as_ref()
+    }
+
+    fn new_instance(&self) -> Self {
+        Self::new(self.processor_meta.output_dir.as_str())
+    }
+
+    fn reset_processor(&mut self, rib_meta: &RibMeta) {
+        self.rib_meta = Some(rib_meta.clone());
+    }
+
+    fn shared_inputs(&self) -> Vec<SharedInput> {
+        vec![SharedInput::StrippedPath]
+    }
+
+    fn process_entry(&mut self, elem: &BgpElem) -> anyhow::Result<()> {
+        process_entry_view(self, elem)
+    }
+
+    fn process_view(&mut self, view: &ElemView) -> anyhow::Result<()> {
+        if !view.is_announce() {
+            return Ok(());
+        }
+        let Some(path) = view.path() else {
+            return Ok(());
+        };
+        let elem = view.elem();
+
+        // ASNs repeated on the path as sent, before prepending is removed
+        let prepending: Vec<u32> =
+            match path.len() < elem.as_path.as_ref().map_or(0, |p| p.route_len()) {
+                true => elem
+                    .as_path
+                    .as_ref()
+                    .and_then(|p| p.to_u32_vec_opt(false))
+                    .unwrap_or_default()
+                    .windows(2)
+                    .filter(|w| w[0] == w[1])
+                    .map(|w| w[0])
+                    .dedup()
+                    .collect(),
+                false => vec![],
+            };
+
+        let asns = path.asns();
+        for (position, asn) in asns.iter().enumerate() {
+            let stats = self.ases.entry(*asn).or_default();
+            stats.routes += 1;
+            stats.add(asns, position, prepending.contains(asn));
+        }
+
+        let keys: BTreeSet<CommunityKey> = elem
+            .communities
+            .iter()
+            .flatten()
+            .filter_map(CommunityKey::new)
+            .collect();
+        for key in keys {
+            let stats = self.communities.entry(key).or_default();
+            stats.routes += 1;
+            let owner = key.owner();
+            if let Some(position) = asns.iter().position(|asn| *asn == owner) {
+                stats.add(asns, position, prepending.contains(&owner));
+            }
+        }
+
+        Ok(())
+    }
+
+    fn brief_summary(&self) -> String {
+        format!(
+            "{}: {} communities, {} ASes on paths",
+            self.processor_meta.name,
+            format_count(self.communities.len()),
+            format_count(self.ases.len())
+        )
+    }
+
+    fn to_output(&self) -> Option<CommunitiesCollectorJson> {
+        let rib_meta = self.rib_meta.as_ref()?;
+        let collectors = BTreeSet::from([rib_meta.collector.clone()]);
+        let communities: Vec<CommunityEntry> = self
+            .communities
+            .iter()
+            .sorted_by_key(|(key, _)| **key)
+            .map(|(key, stats)| CommunityEntry {
+                community: key.to_string(),
+                owner: key.owner(),
+                routes: stats.routes,
+                owner_routes: stats.owner_routes,
+                owner_prepended: stats.prepended,
+                neighbors: stats.neighbors.iter().map(|(k, v)| (*k, *v)).collect(),
+                collectors: collectors.clone(),
+            })
+            .collect();
+        let owners: BTreeSet<u32> = communities.iter().map(|c| c.owner).collect();
+        let owners = owners
+            .into_iter()
+            .filter_map(|asn| {
+                let stats = self.ases.get(&asn)?;
+                Some(CommunityOwnerEntry {
+                    asn,
+                    routes: stats.routes,
+                    prepended: stats.prepended,
+                    neighbors: stats.neighbors.iter().map(|(k, v)| (*k, *v)).collect(),
+                    collectors: collectors.clone(),
+                })
+            })
+            .collect();
+        Some(CommunitiesCollectorJson {
+            project: rib_meta.project.clone(),
+            collector: rib_meta.collector.clone(),
+            rib_dump_url: rib_meta.rib_dump_url.clone(),
+            communities,
+            owners,
+        })
+    }
+
+    fn summarize(
+        &self,
+        rib_metas: &[RibMeta],
+        outputs: Vec<(RibMeta, CommunitiesCollectorJson)>,
+    ) -> anyhow::Result<CommunitiesSummaryJson> {
+        // owner baselines are merged here, and the community entries by the merge engine
+        let mut owners: BTreeMap<u32, CommunityOwnerEntry> = BTreeMap::new();
+        for (_, output) in &outputs {
+            for owner in &output.owners {
+                let entry = owners
+                    .entry(owner.asn)
+                    .or_insert_with(|| CommunityOwnerEntry {
+                        asn: owner.asn,
+                        ..Default::default()
+                    });
+                entry.routes += owner.routes;
+                entry.prepended += owner.prepended;
+                add_counts(&mut entry.neighbors, owner.neighbors.clone());
+                entry.collectors.extend(owner.collectors.iter().cloned());
+            }
+        }
+        let mut summary: CommunitiesSummaryJson =
+            merge_outputs(rib_metas, outputs.into_iter().map(|(_, output)| output));
+        summary
+            .communities
+            .sort_by(|a, b| a.community.cmp(&b.community));
+        summary.owners = owners.into_values().collect();
+        summary.candidates = community_candidates(&summary.communities, &summary.owners);
+        Ok(summary)
+    }
+}
+
+/// Candidate semantics of communities whose routes deviate from the other routes through their
+/// owner. Only communities seen on at least [MIN_CANDIDATE_ROUTES] routes through their owner are
+/// considered.
+pub fn community_candidates(
+    communities: &[CommunityEntry],
+    owners: &[CommunityOwnerEntry],
+) -> Vec<CommunityCandidate> {
+    let owners: HashMap<u32, &CommunityOwnerEntry> = owners.iter().map(|o| (o.asn, o)).collect();
+    let mut candidates = vec![];
+    for community in communities {
+        if community.owner_routes < MIN_CANDIDATE_ROUTES {
+            continue;
+        }
+        let Some(owner) = owners.get(&community.owner) else {
+            continue;
+        };
+        let candidate = |action: CommunityAction, target: Option<u32>, support: f64| {
+            let description = match target {
+                Some(target) => format!(
+                    "{} looks like a {}-AS{} action",
+                    community.community, action, target
+                ),
+                None => format!("{} looks like a {} action", community.community, action),
+            };
+            CommunityCandidate {
+                community: community.community.clone(),
+                owner: community.owner,
+                action,
+                target,
+                support,
+                description,
+            }
+        };
+
+        // the owner's routes without the community
+        let other_routes = owner.routes.saturating_sub(community.owner_routes);
+        let prepended = community.owner_prepended as f64 / community.owner_routes as f64;
+        if other_routes > 0 {
+            let other_prepended = owner.prepended.saturating_sub(community.owner_prepended) as f64
+                / other_routes as f64;
+            if prepended >= STRONG_SHARE && other_prepended < WEAK_SHARE {
+                candidates.push(candidate(CommunityAction::Prepend, None, prepended));
+            }
+        }
+
+        let exported: usize = community.neighbors.values().sum();
+        let other_neighbors: BTreeMap<u32, usize> = owner
+            .neighbors
+            .iter()
+            .map(|(asn, count)| {
+                let own = community.neighbors.get(asn).copied().unwrap_or_default();
+                (*asn, count.saturating_sub(own))
+            })
+            .filter(|(_, count)| *count > 0)
+            .collect();
+        let other_exported: usize = other_neighbors.values().sum();
+        if exported >= MIN_CANDIDATE_ROUTES && other_exported > 0 {
+            let no_export: Vec<CommunityCandidate> = other_neighbors
+                .iter()
+                .map(|(asn, count)| (*asn, *count as f64 / other_exported as f64))
+                .filter(|(asn, share)| {
+                    *share >= NEIGHBOR_SHARE && !community.neighbors.contains_key(asn)
+                })
+                .map(|(asn, share)| candidate(CommunityAction::NoExportTo, Some(asn), share))
+                .collect();
+            // exporting to a single neighbor explains avoiding several expected ones
+            let export_only = community
+                .neighbors
+                .iter()
+                .max_by_key(|(_, count)| **count)
+                .map(|(asn, count)| {
+                    let share = *count as f64 / exported as f64;
+                    let other_share = other_neighbors.get(asn).copied().unwrap_or_default() as f64
+                        / other_exported as f64;
+                    (*asn, share, other_share)
+                })
+                .filter(|(_, share, other_share)| {
+                    no_export.len() > 1 && *share >= STRONG_SHARE && *other_share < WEAK_SHARE
+                });
+            match export_only {
+                Some((asn, share, _)) => {
+                    candidates.push(candidate(CommunityAction::ExportOnlyTo, Some(asn), share))
+                }
+                None => candidates.extend(no_export),
+            }
+        }
+
+        let (seen, expected) = (community.collectors.len(), owner.collectors.len());
+        if expected >= MIN_SCOPE_COLLECTORS && seen * 2 <= expected {
+            candidates.push(candidate(
+                CommunityAction::LimitedScope,
+                None,
+                1.0 - seen as f64 / expected as f64,
+            ));
+        }
+    }
+    candidates
+}
+
+impl Mergeable for CommunitiesCollectorJson {
+    type Entry = CommunityEntry;
+    type Key = String;
+    type Merged = CommunitiesSummaryJson;
+
+    fn into_entries(self) -> Vec<CommunityEntry> {
+        self.communities
+    }
+
+    fn entry_key(entry: &CommunityEntry) -> String {
+        entry.community.clone()
+    }
+
+    fn merge_entry(entry: &mut CommunityEntry, other: CommunityEntry) {
+        entry.routes += other.routes;
+        entry.owner_routes += other.owner_routes;
+        entry.owner_prepended += other.owner_prepended;
+        add_counts(&mut entry.neighbors, other.neighbors);
+        entry.collectors.extend(other.collectors);
+    }
+
+    /// Merge the community entries; owner baselines and candidates are filled in by
+    /// [CommunitiesProcessor]'s summarization.
+    fn merge_files(rib_metas: &[RibMeta], entries: Vec<CommunityEntry>) -> CommunitiesSummaryJson {
+        CommunitiesSummaryJson {
+            rib_dump_urls: rib_dump_urls(rib_metas),
+            communities: entries,
+            owners: vec![],
+            candidates: vec![],
+        }
+    }
+}
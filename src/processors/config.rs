@@ -0,0 +1,139 @@
+//! Options of the built-in processors.
+
+use crate::processors::meta::{get_labeled_output_dir, validate_label};
+use crate::util::{AsnSet, IxpPolicy, PrefixCountries, RoutePolicy};
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+/// Options applied when creating built-in processors by name, see
+/// [RibEye::get_processor_with_config](crate::RibEye::get_processor_with_config).
+#[derive(Debug, Clone, Default)]
+pub struct ProcessorConfig {
+    /// IXP route-server ASNs
+    pub ixp_asns: Arc<AsnSet>,
+    /// handling of IXP route-server ASNs by path-based processors (`as2rel`)
+    pub ixp_policy: IxpPolicy,
+    /// registration countries of address space (`footprint`)
+    pub prefix_countries: Arc<PrefixCountries>,
+    /// output root directory per processor name, overriding the pipeline's output directory
+    pub output_dirs: BTreeMap<String, String>,
+    /// route policy per processor output name, overriding the processor's own
+    pub route_policies: BTreeMap<String, RoutePolicy>,
+    /// share of prefix-origin pairs and AS links covered by the vantage points (`vantage`)
+    pub vantage_coverage: Option<f64>,
+    /// label of the run, see [ProcessorConfig::with_label]
+    pub label: Option<String>,
+}
+
+impl ProcessorConfig {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_ixp_asns(mut self, ixp_asns: AsnSet, ixp_policy: IxpPolicy) -> Self {
+        self.ixp_asns = Arc::new(ixp_asns);
+        self.ixp_policy = ixp_policy;
+        self
+    }
+
+    pub fn with_prefix_countries(mut self, prefix_countries: PrefixCountries) -> Self {
+        self.prefix_countries = Arc::new(prefix_countries);
+        self
+    }
+
+    /// Write the outputs and summary of a processor, by requested (e.g. `pfx2as-aggregated`) or
+    /// output name (e.g. `peer-stats`), under their own output root directory, local or S3.
+    pub fn with_output_dir(mut self, processor: &str, output_dir: &str) -> Self {
+        let output_dir = self.labeled_output_dir(output_dir.trim_end_matches('/'));
+        self.output_dirs
+            .insert(processor.to_lowercase(), output_dir);
+        self
+    }
+
+    /// Label the run: output root directories given before and after move to their
+    /// [get_labeled_output_dir] and pipelines tag their outputs with the label, so that
+    /// experimental runs do not replace the outputs of production runs.
+    pub fn with_label(mut self, label: &str) -> Result<Self> {
+        validate_label(label)?;
+        self.label = Some(label.to_string());
+        for output_dir in self.output_dirs.values_mut() {
+            *output_dir = get_labeled_output_dir(output_dir, label);
+        }
+        Ok(self)
+    }
+
+    /// Output root directory of the run under an output root directory, labeled if the run is.
+    pub fn labeled_output_dir(&self, output_dir: &str) -> String {
+        match &self.label {
+            Some(label) => get_labeled_output_dir(output_dir, label),
+            None => output_dir.to_string(),
+        }
+    }
+
+    /// Skip the elements of the given route policy for a processor, by output name (e.g.
+    /// `pfx2as`), instead of the processor's own.
+    pub fn with_route_policy(mut self, processor: &str, policy: RoutePolicy) -> Self {
+        self.route_policies.insert(processor.to_lowercase(), policy);
+        self
+    }
+
+    /// Recommend vantage points covering the given share, between 0 and 1, of the prefix-origin
+    /// pairs and AS links instead of [DEFAULT_VANTAGE_COVERAGE](crate::processors::DEFAULT_VANTAGE_COVERAGE).
+    pub fn with_vantage_coverage(mut self, coverage: f64) -> Self {
+        self.vantage_coverage = Some(coverage);
+        self
+    }
+
+    /// Apply the settings of a configuration file.
+    pub fn with_config_file(mut self, config_file: &ProcessorConfigFile) -> Self {
+        for (processor, output_dir) in &config_file.output_dirs {
+            self = self.with_output_dir(processor, output_dir);
+        }
+        for (processor, policy) in &config_file.route_policies {
+            self = self.with_route_policy(processor, *policy);
+        }
+        if let Some(coverage) = config_file.vantage_coverage {
+            self = self.with_vantage_coverage(coverage);
+        }
+        self
+    }
+
+    /// Output root directory of a processor, if overridden.
+    pub fn output_dir(&self, processor: &str) -> Option<&str> {
+        self.output_dirs
+            .get(&processor.to_lowercase())
+            .map(|d| d.as_str())
+    }
+}
+
+/// Processor settings of a JSON configuration file (`ribeye cook --config`), e.g.
+/// `{"output_dirs": {"pfx2as": "s3://public-bucket/ribeye", "pfx2dist": "/scratch/ribeye"}}`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ProcessorConfigFile {
+    /// output root directory per processor name
+    #[serde(default)]
+    pub output_dirs: BTreeMap<String, String>,
+    /// route policy per processor output name, e.g. `{"pfx2as": {"skip_default": true,
+    /// "skip_bogons": true}}`
+    #[serde(default)]
+    pub route_policies: BTreeMap<String, RoutePolicy>,
+    /// share of prefix-origin pairs and AS links covered by the `vantage` vantage points
+    #[serde(default)]
+    pub vantage_coverage: Option<f64>,
+}
+
+impl ProcessorConfigFile {
+    /// Load a configuration file, local path or URL.
+    pub fn load(path: &str) -> Result<Self> {
+        let content = oneio::read_to_string(path)
+            .map_err(|e| anyhow!("failed to read configuration {}: {}", path, e))?;
+        Self::from_json_str(content.as_str())
+            .map_err(|e| anyhow!("invalid configuration {}: {}", path, e))
+    }
+
+    pub fn from_json_str(content: &str) -> Result<Self> {
+        Ok(serde_json::from_str(content)?)
+    }
+}
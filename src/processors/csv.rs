@@ -0,0 +1,221 @@
+//! CSV export of summaries.
+//!
+//! [summary_tables] turns a processor summary into CSV tables, one per array of records (e.g.
+//! `pfx2as`, or the `peers` and `asns` of `peer-stats`) and per columnar table (the `peers` and
+//! `edges` of `reachability`). Columns follow the summary schema rather than the records, so they
+//! are the same for every summary of a processor: required fields in declaration order, then
+//! optional fields by name. Nested values (e.g. lists of collectors) are written as JSON.
+
+use crate::inspect::ASN_FIELDS;
+use crate::processors::schema::processor_schemas;
+use anyhow::{anyhow, Result};
+use serde_json::{Map, Value};
+use std::collections::BTreeSet;
+
+/// A table of a summary, with a header row of column names.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CsvTable {
+    /// name of the summary field the table comes from
+    pub name: String,
+    pub columns: Vec<String>,
+    pub rows: Vec<Vec<Value>>,
+}
+
+impl CsvTable {
+    /// Table of JSON object records, with the given columns or, if none, the record fields: key
+    /// fields (e.g. `prefix`, `asn`) first, then the others by name.
+    pub fn from_records(name: &str, records: &[Value], columns: Option<Vec<String>>) -> Self {
+        let columns = columns.unwrap_or_else(|| {
+            let fields: BTreeSet<&str> = records
+                .iter()
+                .filter_map(Value::as_object)
+                .flat_map(|record| record.keys().map(|k| k.as_str()))
+                .collect();
+            let mut columns: Vec<String> = ["prefix", "collector"]
+                .iter()
+                .chain(ASN_FIELDS.iter())
+                .filter(|f| fields.contains(*f))
+                .map(|f| f.to_string())
+                .collect();
+            for field in fields {
+                if !columns.iter().any(|c| c == field) {
+                    columns.push(field.to_string());
+                }
+            }
+            columns
+        });
+        let rows = records
+            .iter()
+            .map(|record| {
+                columns
+                    .iter()
+                    .map(|c| record.get(c).cloned().unwrap_or(Value::Null))
+                    .collect()
+            })
+            .collect();
+        CsvTable {
+            name: name.to_string(),
+            columns,
+            rows,
+        }
+    }
+
+    /// CSV text of the table (RFC 4180), header first.
+    pub fn to_csv_string(&self) -> String {
+        let mut csv = String::new();
+        let header: Vec<Value> = self
+            .columns
+            .iter()
+            .map(|c| Value::from(c.as_str()))
+            .collect();
+        for row in std::iter::once(&header).chain(&self.rows) {
+            let cells: Vec<String> = row.iter().map(csv_cell).collect();
+            csv.push_str(cells.join(",").as_str());
+            csv.push_str("\r\n");
+        }
+        csv
+    }
+}
+
+/// CSV cell of a JSON value: strings and numbers as they are, nested values as JSON, quoted if
+/// needed.
+fn csv_cell(value: &Value) -> String {
+    let text = match value {
+        Value::Null => String::new(),
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    };
+    match text.contains([',', '"', '\n', '\r']) {
+        true => format!("\"{}\"", text.replace('"', "\"\"")),
+        false => text,
+    }
+}
+
+/// Path of a CSV table of a summary, next to its canonical path, e.g. `pfx2as/latest.pfx2as.csv`
+/// for `pfx2as/latest.json.bz2`.
+pub fn get_summary_csv_path(summary_path: &str, table: &str) -> String {
+    let (dir, file) = summary_path.rsplit_once('/').unwrap_or(("", summary_path));
+    let stem = file.split('.').next().unwrap_or(file);
+    match dir.is_empty() {
+        true => format!("{}.{}.csv", stem, table),
+        false => format!("{}/{}.{}.csv", dir, stem, table),
+    }
+}
+
+/// CSV tables of a summary of a built-in processor, sorted by name.
+pub fn summary_tables(processor: &str, summary: &Value) -> Result<Vec<CsvTable>> {
+    let schemas =
+        processor_schemas(processor).ok_or_else(|| anyhow!("no schemas for {}", processor))?;
+    let root = schemas.summary.as_value();
+    let properties = resolve(root, root)
+        .get("properties")
+        .and_then(Value::as_object)
+        .ok_or_else(|| anyhow!("summary schema of {} has no properties", processor))?;
+    let mut tables = vec![];
+    for (name, schema) in properties {
+        let schema = resolve(schema, root);
+        match (summary.get(name), has_type(schema, "array")) {
+            (Some(Value::Array(records)), true) => {
+                let Some(item) = schema.get("items").map(|i| resolve(i, root)) else {
+                    continue;
+                };
+                if !has_type(item, "object") {
+                    continue;
+                }
+                let columns = schema_columns(item);
+                tables.push(CsvTable::from_records(name, records, Some(columns)));
+            }
+            (Some(Value::Object(columnar)), false) if is_columnar(schema, root) => {
+                let columns = schema_columns(schema);
+                tables.push(columnar_table(name, columnar, columns));
+            }
+            _ => {}
+        }
+    }
+    Ok(tables)
+}
+
+/// Table of a columnar object, one array of values per column.
+fn columnar_table(name: &str, columnar: &Map<String, Value>, columns: Vec<String>) -> CsvTable {
+    let column = |c: &String| columnar.get(c).and_then(Value::as_array);
+    let len = columns
+        .iter()
+        .filter_map(column)
+        .map(|values| values.len())
+        .max()
+        .unwrap_or(0);
+    let rows = (0..len)
+        .map(|i| {
+            columns
+                .iter()
+                .map(|c| {
+                    column(c)
+                        .and_then(|values| values.get(i))
+                        .cloned()
+                        .unwrap_or(Value::Null)
+                })
+                .collect()
+        })
+        .collect();
+    CsvTable {
+        name: name.to_string(),
+        columns,
+        rows,
+    }
+}
+
+/// Columns of an object schema: required properties in declaration order, then the others by
+/// name.
+fn schema_columns(schema: &Value) -> Vec<String> {
+    let Some(properties) = schema.get("properties").and_then(Value::as_object) else {
+        return vec![];
+    };
+    let mut columns: Vec<String> = schema
+        .get("required")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+        .filter_map(|r| r.as_str().map(|r| r.to_string()))
+        .collect();
+    for name in properties.keys() {
+        if !columns.contains(name) {
+            columns.push(name.clone());
+        }
+    }
+    columns
+}
+
+/// Schema a local `$ref` points to, or the schema itself.
+fn resolve<'a>(schema: &'a Value, root: &'a Value) -> &'a Value {
+    match schema
+        .get("$ref")
+        .and_then(Value::as_str)
+        .and_then(|r| r.strip_prefix('#'))
+        .and_then(|path| root.pointer(path))
+    {
+        Some(target) => resolve(target, root),
+        None => schema,
+    }
+}
+
+/// Whether an object schema is a columnar table, i.e. all its properties are arrays.
+fn is_columnar(schema: &Value, root: &Value) -> bool {
+    has_type(schema, "object")
+        && schema
+            .get("properties")
+            .and_then(Value::as_object)
+            .is_some_and(|properties| {
+                !properties.is_empty()
+                    && properties
+                        .values()
+                        .all(|p| has_type(resolve(p, root), "array"))
+            })
+}
+
+fn has_type(schema: &Value, name: &str) -> bool {
+    match schema.get("type") {
+        Some(Value::String(t)) => t == name,
+        Some(Value::Array(ts)) => ts.iter().any(|t| t == name),
+        _ => false,
+    }
+}
@@ -0,0 +1,226 @@
+//! `defaults` processor reports the default routes announced to route collectors.
+//!
+//! `peer-stats` only flags whether a peer announces `0.0.0.0/0` or `::/0`. This processor records
+//! each default route with its peer, origin and full AS path, prepending and AS sets included, and
+//! the summary counts the peers attributing a default route to each origin across collectors.
+//! Collector peers are expected to send full tables, so a default route usually points at a
+//! misconfigured feed.
+
+use crate::processors::merge::{rib_dump_urls, Mergeable};
+use crate::processors::meta::{ProcessorMeta, RibMeta};
+use crate::processors::shared::SharedInput;
+use crate::processors::typed::process_entry_view;
+use crate::processors::TypedProcessor;
+use crate::util::{format_count, ElemView};
+use bgpkit_parser::BgpElem;
+use ipnet::IpNet;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::net::IpAddr;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct DefaultRouteEntry {
+    /// `0.0.0.0/0` or `::/0`
+    #[schemars(with = "String")]
+    pub prefix: IpNet,
+    pub collector: String,
+    pub peer_ip: IpAddr,
+    pub peer_asn: u32,
+    /// origin AS, none for paths with AS sets or confederation segments
+    pub origin: Option<u32>,
+    /// AS path as announced
+    pub as_path: String,
+}
+
+/// Peers attributing a default route to an origin.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct DefaultOriginEntry {
+    #[schemars(with = "String")]
+    pub prefix: IpNet,
+    pub origin: u32,
+    pub peers_count: usize,
+    pub collectors: BTreeSet<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct DefaultRouteCollectorJson {
+    pub project: String,
+    pub collector: String,
+    pub rib_dump_url: String,
+    pub default_routes: Vec<DefaultRouteEntry>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct DefaultRouteSummaryJson {
+    pub rib_dump_urls: Vec<String>,
+    /// default routes of all collectors, sorted by prefix, collector and peer IP
+    pub default_routes: Vec<DefaultRouteEntry>,
+    /// origins of the default routes, sorted by prefix and number of peers, most first
+    pub origins: Vec<DefaultOriginEntry>,
+}
+
+pub struct DefaultRouteProcessor {
+    rib_meta: Option<RibMeta>,
+    processor_meta: ProcessorMeta,
+    /// origin and AS path by (prefix, peer IP, peer ASN)
+    routes: HashMap<(IpNet, IpAddr, u32), (Option<u32>, String)>,
+}
+
+impl DefaultRouteProcessor {
+    pub fn new(output_dir: &str) -> Self {
+        let processor_meta = ProcessorMeta {
+            name: "defaults".to_string(),
+            output_dir: output_dir.to_string(),
+        };
+
+        DefaultRouteProcessor {
+            rib_meta: None,
+            processor_meta,
+            routes: HashMap::new(),
+        }
+    }
+}
+
+impl TypedProcessor for DefaultRouteProcessor {
+    type Output = DefaultRouteCollectorJson;
+    type Summary = DefaultRouteSummaryJson;
+    const DATASET_FIELD: &'static str = "default_routes";
+
+    fn processor_meta(&self) -> &ProcessorMeta {
+        &self.processor_meta
+    }
+
+    fn rib_meta(&self) -> Option<&RibMeta> {
+        self.rib_meta.as_ref()
+    }
+
+    fn new_instance(&self) -> Self {
+        Self::new(self.processor_meta.output_dir.as_str())
+    }
+
+    fn reset_processor(&mut self, rib_meta: &RibMeta) {
+        self.rib_meta = Some(rib_meta.clone());
+    }
+
+    fn shared_inputs(&self) -> Vec<SharedInput> {
+        vec![SharedInput::StrippedPath]
+    }
+
+    fn process_entry(&mut self, elem: &BgpElem) -> anyhow::Result<()> {
+        process_entry_view(self, elem)
+    }
+
+    fn process_view(&mut self, view: &ElemView) -> anyhow::Result<()> {
+        if !view.is_announce() || !view.is_default_route() {
+            return Ok(());
+        }
+        let elem = view.elem();
+        let as_path = elem
+            .as_path
+            .as_ref()
+            .map(|path| path.to_string())
+            .unwrap_or_default();
+        self.routes.insert(
+            (elem.prefix.prefix, elem.peer_ip, elem.peer_asn.to_u32()),
+            (view.origin(), as_path),
+        );
+        Ok(())
+    }
+
+    fn brief_summary(&self) -> String {
+        format!(
+            "{}: {} default routes",
+            self.processor_meta.name,
+            format_count(self.routes.len())
+        )
+    }
+
+    fn to_output(&self) -> Option<DefaultRouteCollectorJson> {
+        let rib_meta = self.rib_meta.as_ref()?;
+        let mut default_routes: Vec<DefaultRouteEntry> = self
+            .routes
+            .iter()
+            .map(
+                |((prefix, peer_ip, peer_asn), (origin, as_path))| DefaultRouteEntry {
+                    prefix: *prefix,
+                    collector: rib_meta.collector.clone(),
+                    peer_ip: *peer_ip,
+                    peer_asn: *peer_asn,
+                    origin: *origin,
+                    as_path: as_path.clone(),
+                },
+            )
+            .collect();
+        default_routes.sort_by_key(|entry| (entry.prefix, entry.peer_ip));
+        Some(DefaultRouteCollectorJson {
+            project: rib_meta.project.clone(),
+            collector: rib_meta.collector.clone(),
+            rib_dump_url: rib_meta.rib_dump_url.clone(),
+            default_routes,
+        })
+    }
+}
+
+impl Mergeable for DefaultRouteCollectorJson {
+    type Entry = DefaultRouteEntry;
+    type Key = (IpNet, String, IpAddr, u32);
+    type Merged = DefaultRouteSummaryJson;
+
+    fn into_entries(self) -> Vec<DefaultRouteEntry> {
+        self.default_routes
+    }
+
+    fn entry_key(entry: &DefaultRouteEntry) -> (IpNet, String, IpAddr, u32) {
+        (
+            entry.prefix,
+            entry.collector.clone(),
+            entry.peer_ip,
+            entry.peer_asn,
+        )
+    }
+
+    fn merge_entry(_entry: &mut DefaultRouteEntry, _other: DefaultRouteEntry) {
+        // outputs of the same collector are not merged in practice, keep the first route
+    }
+
+    fn merge_files(
+        rib_metas: &[RibMeta],
+        mut entries: Vec<DefaultRouteEntry>,
+    ) -> DefaultRouteSummaryJson {
+        entries.sort_by(|a, b| {
+            (a.prefix, &a.collector, a.peer_ip).cmp(&(b.prefix, &b.collector, b.peer_ip))
+        });
+
+        let mut origins: BTreeMap<(IpNet, u32), DefaultOriginEntry> = BTreeMap::new();
+        for entry in &entries {
+            let Some(origin) = entry.origin else {
+                continue;
+            };
+            let origin_entry =
+                origins
+                    .entry((entry.prefix, origin))
+                    .or_insert_with(|| DefaultOriginEntry {
+                        prefix: entry.prefix,
+                        origin,
+                        peers_count: 0,
+                        collectors: BTreeSet::new(),
+                    });
+            origin_entry.peers_count += 1;
+            origin_entry.collectors.insert(entry.collector.clone());
+        }
+        let mut origins: Vec<DefaultOriginEntry> = origins.into_values().collect();
+        origins.sort_by(|a, b| {
+            a.prefix
+                .cmp(&b.prefix)
+                .then(b.peers_count.cmp(&a.peers_count))
+                .then(a.origin.cmp(&b.origin))
+        });
+
+        DefaultRouteSummaryJson {
+            rib_dump_urls: rib_dump_urls(rib_metas),
+            default_routes: entries,
+            origins,
+        }
+    }
+}
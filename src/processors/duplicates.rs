@@ -0,0 +1,245 @@
+//! `duplicates` processor flags peers with multiple entries for the same prefix in a RIB.
+//!
+//! A RIB dump should hold a single entry per peer, prefix and ADD-PATH path ID. Repeated entries
+//! are counted per peer as duplicates when their attributes (AS path, next hop, origin, MED and
+//! local preference) are identical, and as conflicts when they differ, with a few example
+//! prefixes of each conflicting peer. Only peers with repeated entries are reported.
+
+use crate::processors::merge::{rib_dump_urls, Mergeable};
+use crate::processors::meta::{ProcessorMeta, RibMeta};
+use crate::processors::peers::PeerIndex;
+use crate::processors::typed::process_entry_view;
+use crate::processors::TypedProcessor;
+use crate::util::{format_count, ElemView};
+use bgpkit_parser::BgpElem;
+use ipnet::IpNet;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::hash_map::{DefaultHasher, Entry};
+use std::collections::{BTreeSet, HashMap};
+use std::hash::{Hash, Hasher};
+use std::net::IpAddr;
+
+/// Maximum number of example conflicting prefixes reported per peer.
+pub const MAX_CONFLICT_EXAMPLES: usize = 10;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct DuplicatePeerEntry {
+    pub collector: String,
+    pub ip: IpAddr,
+    pub asn: u32,
+    /// number of distinct prefix and path ID pairs announced by the peer
+    pub prefixes: usize,
+    /// prefixes with repeated identical entries
+    pub duplicate_prefixes: usize,
+    /// prefixes with repeated entries of different attributes
+    pub conflicting_prefixes: usize,
+    /// up to [MAX_CONFLICT_EXAMPLES] conflicting prefixes
+    #[schemars(with = "BTreeSet<String>")]
+    pub conflict_examples: BTreeSet<IpNet>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct DuplicatesCollectorJson {
+    pub project: String,
+    pub collector: String,
+    pub rib_dump_url: String,
+    pub peers: Vec<DuplicatePeerEntry>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct DuplicatesSummaryJson {
+    pub rib_dump_urls: Vec<String>,
+    /// peers of all collectors, sorted by collector and IP address
+    pub peers: Vec<DuplicatePeerEntry>,
+}
+
+/// Repetition state of a prefix entry.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Repetition {
+    Single,
+    Duplicate,
+    Conflict,
+}
+
+/// Hash of the attributes compared between entries of the same prefix.
+fn attributes_hash(elem: &BgpElem) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    elem.as_path
+        .as_ref()
+        .map(|p| p.to_string())
+        .hash(&mut hasher);
+    elem.next_hop.hash(&mut hasher);
+    elem.origin.map(|o| o.to_string()).hash(&mut hasher);
+    elem.med.hash(&mut hasher);
+    elem.local_pref.hash(&mut hasher);
+    hasher.finish()
+}
+
+pub struct DuplicatesProcessor {
+    rib_meta: Option<RibMeta>,
+    processor_meta: ProcessorMeta,
+    peers: PeerIndex,
+    /// attributes hash and repetition state by (peer index, prefix, path ID); peers are indexed by
+    /// collector and IP address
+    entries: HashMap<(u32, IpNet, u32), (u64, Repetition)>,
+}
+
+impl DuplicatesProcessor {
+    pub fn new(output_dir: &str) -> Self {
+        let processor_meta = ProcessorMeta {
+            name: "duplicates".to_string(),
+            output_dir: output_dir.to_string(),
+        };
+
+        DuplicatesProcessor {
+            rib_meta: None,
+            processor_meta,
+            peers: PeerIndex::new(),
+            entries: HashMap::new(),
+        }
+    }
+}
+
+impl TypedProcessor for DuplicatesProcessor {
+    type Output = DuplicatesCollectorJson;
+    type Summary = DuplicatesSummaryJson;
+    const DATASET_FIELD: &'static str = "peers";
+
+    fn processor_meta(&self) -> &ProcessorMeta {
+        &self.processor_meta
+    }
+
+    fn rib_meta(&self) -> Option<&RibMeta> {
+        self.rib_meta.as_ref()
+    }
+
+    fn new_instance(&self) -> Self {
+        Self::new(self.processor_meta.output_dir.as_str())
+    }
+
+    fn reset_processor(&mut self, rib_meta: &RibMeta) {
+        self.rib_meta = Some(rib_meta.clone());
+        self.peers.reset(rib_meta.collector.as_str());
+    }
+
+    fn process_entry(&mut self, elem: &BgpElem) -> anyhow::Result<()> {
+        process_entry_view(self, elem)
+    }
+
+    fn process_view(&mut self, view: &ElemView) -> anyhow::Result<()> {
+        if !view.is_announce() {
+            return Ok(());
+        }
+        let elem = view.elem();
+        let peer = self.peers.index(view);
+        let hash = attributes_hash(elem);
+        match self
+            .entries
+            .entry((peer, elem.prefix.prefix, elem.prefix.path_id))
+        {
+            Entry::Vacant(entry) => {
+                entry.insert((hash, Repetition::Single));
+            }
+            Entry::Occupied(mut entry) => {
+                let (first_hash, repetition) = entry.get_mut();
+                *repetition = match (*repetition, *first_hash == hash) {
+                    (Repetition::Conflict, _) | (_, false) => Repetition::Conflict,
+                    (_, true) => Repetition::Duplicate,
+                };
+            }
+        }
+        Ok(())
+    }
+
+    fn brief_summary(&self) -> String {
+        let count = |repetition: Repetition| {
+            self.entries
+                .values()
+                .filter(|(_, r)| *r == repetition)
+                .count()
+        };
+        format!(
+            "{}: {} routes, {} duplicates, {} conflicts",
+            self.processor_meta.name,
+            format_count(self.entries.len()),
+            format_count(count(Repetition::Duplicate)),
+            format_count(count(Repetition::Conflict))
+        )
+    }
+
+    fn to_output(&self) -> Option<DuplicatesCollectorJson> {
+        let rib_meta = self.rib_meta.as_ref()?;
+        let mut peers: Vec<DuplicatePeerEntry> = (0..self.peers.len() as u32)
+            .filter_map(|index| Some((index, self.peers.get(index)?)))
+            .map(|(index, (ip, asn))| DuplicatePeerEntry {
+                collector: self
+                    .peers
+                    .collector(index)
+                    .unwrap_or(rib_meta.collector.as_str())
+                    .to_string(),
+                ip,
+                asn,
+                prefixes: 0,
+                duplicate_prefixes: 0,
+                conflicting_prefixes: 0,
+                conflict_examples: BTreeSet::new(),
+            })
+            .collect();
+        for ((peer, prefix, _), (_, repetition)) in &self.entries {
+            let Some(entry) = peers.get_mut(*peer as usize) else {
+                continue;
+            };
+            entry.prefixes += 1;
+            match repetition {
+                Repetition::Single => {}
+                Repetition::Duplicate => entry.duplicate_prefixes += 1,
+                Repetition::Conflict => {
+                    entry.conflicting_prefixes += 1;
+                    entry.conflict_examples.insert(*prefix);
+                    if entry.conflict_examples.len() > MAX_CONFLICT_EXAMPLES {
+                        entry.conflict_examples.pop_last();
+                    }
+                }
+            }
+        }
+
+        peers.retain(|p| p.duplicate_prefixes > 0 || p.conflicting_prefixes > 0);
+        peers.sort_by(|a, b| (a.ip, &a.collector).cmp(&(b.ip, &b.collector)));
+        Some(DuplicatesCollectorJson {
+            project: rib_meta.project.clone(),
+            collector: rib_meta.collector.clone(),
+            rib_dump_url: rib_meta.rib_dump_url.clone(),
+            peers,
+        })
+    }
+}
+
+impl Mergeable for DuplicatesCollectorJson {
+    type Entry = DuplicatePeerEntry;
+    type Key = (String, IpAddr);
+    type Merged = DuplicatesSummaryJson;
+
+    fn into_entries(self) -> Vec<DuplicatePeerEntry> {
+        self.peers
+    }
+
+    fn entry_key(entry: &DuplicatePeerEntry) -> (String, IpAddr) {
+        (entry.collector.clone(), entry.ip)
+    }
+
+    fn merge_entry(_entry: &mut DuplicatePeerEntry, _other: DuplicatePeerEntry) {
+        // outputs of the same collector are not merged in practice, keep the first peer
+    }
+
+    fn merge_files(
+        rib_metas: &[RibMeta],
+        mut entries: Vec<DuplicatePeerEntry>,
+    ) -> DuplicatesSummaryJson {
+        entries.sort_by(|a, b| (&a.collector, a.ip).cmp(&(&b.collector, b.ip)));
+        DuplicatesSummaryJson {
+            rib_dump_urls: rib_dump_urls(rib_metas),
+            peers: entries,
+        }
+    }
+}
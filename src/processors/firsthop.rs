@@ -0,0 +1,290 @@
+//! `firsthop` processor measures the unique contribution of each peer ASN to a collector's view.
+//!
+//! For each peer ASN, i.e. the first hop of the AS paths, the processor counts the prefixes and
+//! origin ASes it reaches and how many of them are reached exclusively through it, i.e. through no
+//! other peer ASN of the collector, rather than redundantly. The exclusive share of all prefixes
+//! and origins visible at the collector is the visibility lost without that feed. Sessions of the
+//! same peer ASN, e.g. IPv4 and IPv6 or several routers, count as one feed.
+//!
+//! Exclusivity is relative to a collector, as outputs do not keep the prefixes of each peer ASN;
+//! the summary lists the entries of all collectors.
+
+use crate::processors::merge::{rib_dump_urls, Mergeable};
+use crate::processors::meta::{ProcessorMeta, RibMeta};
+use crate::processors::shared::SharedInput;
+use crate::processors::typed::process_entry_view;
+use crate::processors::TypedProcessor;
+use crate::util::{format_count, ElemView, RoutePolicy};
+use bgpkit_parser::BgpElem;
+use ipnet::IpNet;
+use roaring::RoaringBitmap;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::net::IpAddr;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct FirstHopEntry {
+    pub collector: String,
+    pub peer_asn: u32,
+    /// number of peer IPs with the ASN
+    pub peers_count: usize,
+    /// prefixes reached through the peer ASN
+    pub prefixes: u64,
+    /// prefixes reached through no other peer ASN
+    pub exclusive_prefixes: u64,
+    /// prefixes also reached through another peer ASN
+    pub redundant_prefixes: u64,
+    /// origin ASes reached through the peer ASN
+    pub origins: u64,
+    /// origin ASes reached through no other peer ASN
+    pub exclusive_origins: u64,
+    /// origin ASes also reached through another peer ASN
+    pub redundant_origins: u64,
+    /// share of the prefixes visible at the collector only reached through the peer ASN
+    pub exclusive_prefixes_share: f64,
+    /// share of the origin ASes visible at the collector only reached through the peer ASN
+    pub exclusive_origins_share: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct FirstHopCollectorJson {
+    pub project: String,
+    pub collector: String,
+    pub rib_dump_url: String,
+    /// number of prefixes visible at the collector
+    pub prefixes_count: u64,
+    /// number of origin ASes visible at the collector
+    pub origins_count: u64,
+    /// peer ASNs sorted by exclusive prefixes, largest first
+    pub peer_asns: Vec<FirstHopEntry>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct FirstHopSummaryJson {
+    pub rib_dump_urls: Vec<String>,
+    /// peer ASNs of all collectors, sorted by collector and exclusive prefixes, largest first
+    pub peer_asns: Vec<FirstHopEntry>,
+}
+
+/// Peer ASNs reaching a prefix or origin.
+#[derive(Debug, Clone, Copy)]
+enum Reach {
+    Exclusive(u32),
+    Redundant,
+}
+
+impl Reach {
+    fn observe(&mut self, peer_asn: u32) {
+        if let Reach::Exclusive(asn) = self {
+            if *asn != peer_asn {
+                *self = Reach::Redundant;
+            }
+        }
+    }
+}
+
+/// Prefixes, by index, and origins reached through a peer ASN.
+#[derive(Debug, Default)]
+struct PeerAsnReach {
+    peers: BTreeSet<IpAddr>,
+    prefixes: RoaringBitmap,
+    origins: RoaringBitmap,
+}
+
+pub struct FirstHopProcessor {
+    rib_meta: Option<RibMeta>,
+    processor_meta: ProcessorMeta,
+    prefix_index: HashMap<IpNet, u32>,
+    /// peer ASNs reaching each prefix, by prefix index
+    prefixes: Vec<Reach>,
+    origins: HashMap<u32, Reach>,
+    peer_asns: BTreeMap<u32, PeerAsnReach>,
+}
+
+impl FirstHopProcessor {
+    pub fn new(output_dir: &str) -> Self {
+        let processor_meta = ProcessorMeta {
+            name: "firsthop".to_string(),
+            output_dir: output_dir.to_string(),
+        };
+
+        FirstHopProcessor {
+            rib_meta: None,
+            processor_meta,
+            prefix_index: HashMap::new(),
+            prefixes: vec![],
+            origins: HashMap::new(),
+            peer_asns: BTreeMap::new(),
+        }
+    }
+}
+
+/// Share of `total` items `count` represents, 0 if there are no items.
+fn share(count: u64, total: u64) -> f64 {
+    match total {
+        0 => 0.0,
+        total => count as f64 / total as f64,
+    }
+}
+
+/// Number of items reached exclusively through each peer ASN.
+fn exclusive_counts<'a>(reaches: impl Iterator<Item = &'a Reach>) -> HashMap<u32, u64> {
+    let mut counts = HashMap::new();
+    for reach in reaches {
+        if let Reach::Exclusive(asn) = reach {
+            *counts.entry(*asn).or_default() += 1;
+        }
+    }
+    counts
+}
+
+impl TypedProcessor for FirstHopProcessor {
+    type Output = FirstHopCollectorJson;
+    type Summary = FirstHopSummaryJson;
+    const DATASET_FIELD: &'static str = "peer_asns";
+
+    fn processor_meta(&self) -> &ProcessorMeta {
+        &self.processor_meta
+    }
+
+    fn rib_meta(&self) -> Option<&RibMeta> {
+        self.rib_meta.as_ref()
+    }
+
+    fn new_instance(&self) -> Self {
+        Self::new(self.processor_meta.output_dir.as_str())
+    }
+
+    fn reset_processor(&mut self, rib_meta: &RibMeta) {
+        self.rib_meta = Some(rib_meta.clone());
+    }
+
+    fn route_policy(&self) -> RoutePolicy {
+        RoutePolicy::new().with_skip_default(true)
+    }
+
+    fn shared_inputs(&self) -> Vec<SharedInput> {
+        vec![SharedInput::StrippedPath]
+    }
+
+    fn process_entry(&mut self, elem: &BgpElem) -> anyhow::Result<()> {
+        process_entry_view(self, elem)
+    }
+
+    fn process_view(&mut self, view: &ElemView) -> anyhow::Result<()> {
+        // skip processing non-announce messages, default routes are skipped by the route policy
+        if !view.is_announce() {
+            return Ok(());
+        }
+        let Some(origin) = view.origin() else {
+            return Ok(());
+        };
+        let elem = view.elem();
+        let peer_asn = elem.peer_asn.to_u32();
+        let prefix = match self.prefix_index.get(&elem.prefix.prefix) {
+            Some(index) => {
+                self.prefixes[*index as usize].observe(peer_asn);
+                *index
+            }
+            None => {
+                let index = self.prefixes.len() as u32;
+                self.prefix_index.insert(elem.prefix.prefix, index);
+                self.prefixes.push(Reach::Exclusive(peer_asn));
+                index
+            }
+        };
+        self.origins
+            .entry(origin)
+            .and_modify(|reach| reach.observe(peer_asn))
+            .or_insert(Reach::Exclusive(peer_asn));
+        let reach = self.peer_asns.entry(peer_asn).or_default();
+        reach.peers.insert(elem.peer_ip);
+        reach.prefixes.insert(prefix);
+        reach.origins.insert(origin);
+        Ok(())
+    }
+
+    fn brief_summary(&self) -> String {
+        format!(
+            "{}: {} prefixes, {} origins, {} peer ASNs",
+            self.processor_meta.name,
+            format_count(self.prefix_index.len()),
+            format_count(self.origins.len()),
+            format_count(self.peer_asns.len())
+        )
+    }
+
+    fn to_output(&self) -> Option<FirstHopCollectorJson> {
+        let rib_meta = self.rib_meta.as_ref()?;
+        let prefixes_count = self.prefixes.len() as u64;
+        let origins_count = self.origins.len() as u64;
+        let exclusive_prefixes = exclusive_counts(self.prefixes.iter());
+        let exclusive_origins = exclusive_counts(self.origins.values());
+        let mut peer_asns: Vec<FirstHopEntry> = self
+            .peer_asns
+            .iter()
+            .map(|(peer_asn, reach)| {
+                let exclusive_prefixes = exclusive_prefixes.get(peer_asn).copied().unwrap_or(0);
+                let exclusive_origins = exclusive_origins.get(peer_asn).copied().unwrap_or(0);
+                FirstHopEntry {
+                    collector: rib_meta.collector.clone(),
+                    peer_asn: *peer_asn,
+                    peers_count: reach.peers.len(),
+                    prefixes: reach.prefixes.len(),
+                    exclusive_prefixes,
+                    redundant_prefixes: reach.prefixes.len() - exclusive_prefixes,
+                    origins: reach.origins.len(),
+                    exclusive_origins,
+                    redundant_origins: reach.origins.len() - exclusive_origins,
+                    exclusive_prefixes_share: share(exclusive_prefixes, prefixes_count),
+                    exclusive_origins_share: share(exclusive_origins, origins_count),
+                }
+            })
+            .collect();
+        peer_asns.sort_by(|a, b| {
+            b.exclusive_prefixes
+                .cmp(&a.exclusive_prefixes)
+                .then(a.peer_asn.cmp(&b.peer_asn))
+        });
+        Some(FirstHopCollectorJson {
+            project: rib_meta.project.clone(),
+            collector: rib_meta.collector.clone(),
+            rib_dump_url: rib_meta.rib_dump_url.clone(),
+            prefixes_count,
+            origins_count,
+            peer_asns,
+        })
+    }
+}
+
+impl Mergeable for FirstHopCollectorJson {
+    type Entry = FirstHopEntry;
+    type Key = (String, u32);
+    type Merged = FirstHopSummaryJson;
+
+    fn into_entries(self) -> Vec<FirstHopEntry> {
+        self.peer_asns
+    }
+
+    fn entry_key(entry: &FirstHopEntry) -> (String, u32) {
+        (entry.collector.clone(), entry.peer_asn)
+    }
+
+    fn merge_entry(_entry: &mut FirstHopEntry, _other: FirstHopEntry) {
+        // exclusivity is relative to a collector, keep the first output's entry
+    }
+
+    fn merge_files(rib_metas: &[RibMeta], mut entries: Vec<FirstHopEntry>) -> FirstHopSummaryJson {
+        entries.sort_by(|a, b| {
+            a.collector
+                .cmp(&b.collector)
+                .then(b.exclusive_prefixes.cmp(&a.exclusive_prefixes))
+                .then(a.peer_asn.cmp(&b.peer_asn))
+        });
+        FirstHopSummaryJson {
+            rib_dump_urls: rib_dump_urls(rib_metas),
+            peer_asns: entries,
+        }
+    }
+}
@@ -0,0 +1,231 @@
+//! `footprint` processor reports the geographic footprint of each origin AS.
+//!
+//! Announced prefixes are mapped to the countries their address space is registered in, using the
+//! RIR delegated statistics loaded into [PrefixCountries]. Origins are listed by the number of
+//! distinct countries of their announced space, most countries first, which highlights origins
+//! announcing space registered to many different countries.
+
+use crate::processors::merge::{rib_dump_urls, Mergeable};
+use crate::processors::meta::{ProcessorMeta, RibMeta};
+use crate::processors::shared::SharedInput;
+use crate::processors::typed::process_entry_view;
+use crate::processors::TypedProcessor;
+use crate::util::{format_count, ElemView, PrefixCountries, RoutePolicy};
+use bgpkit_parser::BgpElem;
+use ipnet::IpNet;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::sync::Arc;
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct FootprintEntry {
+    pub asn: u32,
+    /// countries the announced address space is registered in
+    pub countries: BTreeSet<String>,
+    pub num_countries: usize,
+    /// number of distinct announced prefixes
+    pub prefixes_count: usize,
+    /// number of announced prefixes without any registered address space
+    pub unregistered_count: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct FootprintCollectorJson {
+    pub project: String,
+    pub collector: String,
+    pub rib_dump_url: String,
+    pub footprints: Vec<FootprintEntry>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct FootprintSummaryJson {
+    pub rib_dump_urls: Vec<String>,
+    /// per-origin footprints, with countries seen at any collector and the largest prefix counts
+    /// among collectors
+    pub footprints: Vec<FootprintEntry>,
+}
+
+pub struct FootprintProcessor {
+    rib_meta: Option<RibMeta>,
+    processor_meta: ProcessorMeta,
+    prefix_countries: Arc<PrefixCountries>,
+    /// distinct prefix-origin pairs
+    prefix_origins: HashSet<(IpNet, u32)>,
+}
+
+impl FootprintProcessor {
+    pub fn new(output_dir: &str) -> Self {
+        let processor_meta = ProcessorMeta {
+            name: "footprint".to_string(),
+            output_dir: output_dir.to_string(),
+        };
+
+        FootprintProcessor {
+            rib_meta: None,
+            processor_meta,
+            prefix_countries: Arc::new(PrefixCountries::new()),
+            prefix_origins: HashSet::new(),
+        }
+    }
+
+    /// Set the registration countries of address space; without them, all prefixes are counted as
+    /// unregistered.
+    pub fn with_prefix_countries(mut self, prefix_countries: Arc<PrefixCountries>) -> Self {
+        self.prefix_countries = prefix_countries;
+        self
+    }
+
+    pub fn get_footprint_vec(&self) -> Vec<FootprintEntry> {
+        let mut origins: HashMap<u32, FootprintEntry> = HashMap::new();
+        for (prefix, asn) in &self.prefix_origins {
+            let entry = origins.entry(*asn).or_insert_with(|| FootprintEntry {
+                asn: *asn,
+                countries: BTreeSet::new(),
+                num_countries: 0,
+                prefixes_count: 0,
+                unregistered_count: 0,
+            });
+            let countries = self.prefix_countries.countries(prefix);
+            entry.prefixes_count += 1;
+            if countries.is_empty() {
+                entry.unregistered_count += 1;
+            }
+            entry
+                .countries
+                .extend(countries.into_iter().map(|c| c.to_string()));
+        }
+        sorted_footprints(origins.into_values())
+    }
+}
+
+/// Footprints with their country counts set, most countries first.
+fn sorted_footprints(entries: impl IntoIterator<Item = FootprintEntry>) -> Vec<FootprintEntry> {
+    let mut entries: Vec<FootprintEntry> = entries
+        .into_iter()
+        .map(|mut e| {
+            e.num_countries = e.countries.len();
+            e
+        })
+        .collect();
+    entries.sort_by(|a, b| {
+        b.num_countries
+            .cmp(&a.num_countries)
+            .then(a.asn.cmp(&b.asn))
+    });
+    entries
+}
+
+impl TypedProcessor for FootprintProcessor {
+    type Output = FootprintCollectorJson;
+    type Summary = FootprintSummaryJson;
+    const DATASET_FIELD: &'static str = "footprints";
+
+    fn processor_meta(&self) -> &ProcessorMeta {
+        &self.processor_meta
+    }
+
+    fn rib_meta(&self) -> Option<&RibMeta> {
+        self.rib_meta.as_ref()
+    }
+
+    fn new_instance(&self) -> Self {
+        Self::new(self.processor_meta.output_dir.as_str())
+            .with_prefix_countries(self.prefix_countries.clone())
+    }
+
+    fn reset_processor(&mut self, rib_meta: &RibMeta) {
+        self.rib_meta = Some(rib_meta.clone());
+    }
+
+    fn route_policy(&self) -> RoutePolicy {
+        RoutePolicy::new().with_skip_default(true)
+    }
+
+    fn shared_inputs(&self) -> Vec<SharedInput> {
+        vec![SharedInput::StrippedPath]
+    }
+
+    fn process_entry(&mut self, elem: &BgpElem) -> anyhow::Result<()> {
+        process_entry_view(self, elem)
+    }
+
+    fn process_view(&mut self, view: &ElemView) -> anyhow::Result<()> {
+        // skip processing non-announce messages, default routes are skipped by the route policy
+        if !view.is_announce() {
+            return Ok(());
+        }
+
+        if let Some(origin) = view.origin() {
+            self.prefix_origins
+                .insert((view.elem().prefix.prefix, origin));
+        }
+
+        Ok(())
+    }
+
+    fn process_batch(
+        &mut self,
+        views: &[ElemView],
+        _on_error: &mut dyn FnMut(anyhow::Error) -> anyhow::Result<()>,
+    ) -> anyhow::Result<()> {
+        self.prefix_origins.extend(
+            views
+                .iter()
+                .filter(|v| v.is_announce())
+                .filter_map(|v| Some((v.elem().prefix.prefix, v.origin()?))),
+        );
+        Ok(())
+    }
+
+    fn brief_summary(&self) -> String {
+        let origins: HashSet<u32> = self
+            .prefix_origins
+            .iter()
+            .map(|(_, origin)| *origin)
+            .collect();
+        format!(
+            "{}: {} prefix-origin pairs, {} origins",
+            self.processor_meta.name,
+            format_count(self.prefix_origins.len()),
+            format_count(origins.len())
+        )
+    }
+
+    fn to_output(&self) -> Option<FootprintCollectorJson> {
+        let rib_meta = self.rib_meta.as_ref()?;
+        Some(FootprintCollectorJson {
+            project: rib_meta.project.clone(),
+            collector: rib_meta.collector.clone(),
+            rib_dump_url: rib_meta.rib_dump_url.clone(),
+            footprints: self.get_footprint_vec(),
+        })
+    }
+}
+
+impl Mergeable for FootprintCollectorJson {
+    type Entry = FootprintEntry;
+    type Key = u32;
+    type Merged = FootprintSummaryJson;
+
+    fn into_entries(self) -> Vec<FootprintEntry> {
+        self.footprints
+    }
+
+    fn entry_key(entry: &FootprintEntry) -> u32 {
+        entry.asn
+    }
+
+    fn merge_entry(entry: &mut FootprintEntry, other: FootprintEntry) {
+        entry.countries.extend(other.countries);
+        entry.prefixes_count = entry.prefixes_count.max(other.prefixes_count);
+        entry.unregistered_count = entry.unregistered_count.max(other.unregistered_count);
+    }
+
+    fn merge_files(rib_metas: &[RibMeta], entries: Vec<FootprintEntry>) -> FootprintSummaryJson {
+        FootprintSummaryJson {
+            rib_dump_urls: rib_dump_urls(rib_metas),
+            footprints: sorted_footprints(entries),
+        }
+    }
+}
@@ -0,0 +1,58 @@
+//! Default summarization engine.
+//!
+//! Per-collector outputs implement [Mergeable] to describe how their entries are keyed and
+//! combined across collectors; [merge_outputs] then merges any number of outputs into a summary.
+//! [TypedProcessor](crate::processors::TypedProcessor) uses it as its default `summarize`, while
+//! file discovery, error tolerance and writing are handled by the framework.
+
+use crate::processors::RibMeta;
+use std::collections::hash_map::Entry as MapEntry;
+use std::collections::HashMap;
+use std::hash::Hash;
+
+pub trait Mergeable: Sized {
+    /// Entry type of the output, e.g. a single prefix-to-origin count
+    type Entry;
+
+    /// Key identifying entries that are merged together
+    type Key: Eq + Hash;
+
+    /// Summary type produced by merging outputs
+    type Merged;
+
+    /// Consume the output and return its entries
+    fn into_entries(self) -> Vec<Self::Entry>;
+
+    /// Key of the given entry
+    fn entry_key(entry: &Self::Entry) -> Self::Key;
+
+    /// Merge `other` into `entry`; both entries share the same key
+    fn merge_entry(entry: &mut Self::Entry, other: Self::Entry);
+
+    /// Build the summary from the merged entries of the given RIBs
+    fn merge_files(rib_metas: &[RibMeta], entries: Vec<Self::Entry>) -> Self::Merged;
+}
+
+/// Merge the entries of all given outputs by key and build the summary.
+pub fn merge_outputs<M: Mergeable>(
+    rib_metas: &[RibMeta],
+    outputs: impl IntoIterator<Item = M>,
+) -> M::Merged {
+    let mut merged = HashMap::<M::Key, M::Entry>::new();
+    for output in outputs {
+        for entry in output.into_entries() {
+            match merged.entry(M::entry_key(&entry)) {
+                MapEntry::Occupied(mut e) => M::merge_entry(e.get_mut(), entry),
+                MapEntry::Vacant(e) => {
+                    e.insert(entry);
+                }
+            }
+        }
+    }
+    M::merge_files(rib_metas, merged.into_values().collect())
+}
+
+/// RIB dump URLs of the given RIBs, as listed in summaries.
+pub fn rib_dump_urls(rib_metas: &[RibMeta]) -> Vec<String> {
+    rib_metas.iter().map(|r| r.rib_dump_url.clone()).collect()
+}
@@ -1,5 +1,45 @@
+use crate::collectors::{CollectorProfiles, UNKNOWN_PROJECT};
+use crate::processors::is_stdout;
+use anyhow::anyhow;
 use bgpkit_broker::BrokerItem;
-use chrono::{Datelike, NaiveDateTime};
+use chrono::{Datelike, NaiveDateTime, Timelike};
+use itertools::Itertools;
+use std::fmt::{Display, Formatter};
+#[cfg(feature = "glob")]
+use std::path::Path;
+use std::str::FromStr;
+
+/// Granularity of the dump time in the file names of per-dump outputs.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum OutputGranularity {
+    /// one output per day, later dumps of a day replacing earlier ones:
+    /// `<name>_<collector>_<YYYY-MM-DD>_<midnight ts>.json.bz2`
+    Daily,
+    /// one output per dump, with its hour and minute: `<name>_<collector>_<YYYY-MM-DD>_<HHMM>_<ts>.json.bz2`
+    #[default]
+    Exact,
+}
+
+impl FromStr for OutputGranularity {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        match s.to_lowercase().as_str() {
+            "daily" | "day" => Ok(OutputGranularity::Daily),
+            "exact" => Ok(OutputGranularity::Exact),
+            _ => Err(anyhow!("unknown output granularity: {}", s)),
+        }
+    }
+}
+
+impl Display for OutputGranularity {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            OutputGranularity::Daily => write!(f, "daily"),
+            OutputGranularity::Exact => write!(f, "exact"),
+        }
+    }
+}
 
 /// RibMeta contains the meta information of a RIB dump file.
 #[derive(Debug, Default, Clone)]
@@ -10,23 +50,176 @@ pub struct RibMeta {
     pub collector: String,
     /// RIB dump file URL
     pub rib_dump_url: String,
-    /// RIB dump file timestamp
+    /// RIB dump file timestamp, in UTC
     pub timestamp: NaiveDateTime,
+    /// RIB dump file size in bytes, if known
+    pub file_size: Option<u64>,
+    /// dump type from the file name (e.g., rib, bview), if known
+    pub dump_type: Option<String>,
+    /// identifier of the BGPKIT Broker item the RIB dump was found through
+    pub broker_item_id: Option<String>,
+    /// granularity of the dump time in output file names
+    pub output_granularity: OutputGranularity,
 }
 
 impl From<&BrokerItem> for RibMeta {
     fn from(item: &BrokerItem) -> Self {
-        let project = match item.collector_id.starts_with("rrc") {
-            true => "riperis".to_string(),
-            false => "route-views".to_string(),
+        RibMeta::from_broker_item(item, CollectorProfiles::builtin())
+    }
+}
+
+impl RibMeta {
+    /// Meta information of a broker item, labelled with the project of the matching collector
+    /// profile.
+    pub fn from_broker_item(item: &BrokerItem, profiles: &CollectorProfiles) -> Self {
+        let project = profiles.project(item.collector_id.as_str(), item.url.as_str());
+        // prefer the exact file size, the broker reports 0 if it has not been crawled
+        let file_size = match item.exact_size > 0 {
+            true => item.exact_size,
+            false => item.rough_size,
         };
         RibMeta {
             project,
             collector: item.collector_id.clone(),
             rib_dump_url: item.url.clone(),
             timestamp: item.ts_start,
+            file_size: u64::try_from(file_size).ok().filter(|s| *s > 0),
+            dump_type: dump_type_from_path(item.url.as_str()),
+            // the broker identifies items by collector, data type and start time
+            broker_item_id: Some(format!(
+                "{}-{}-{}",
+                item.collector_id,
+                item.data_type,
+                item.ts_start.and_utc().timestamp()
+            )),
+            output_granularity: OutputGranularity::default(),
+        }
+    }
+
+    /// Meta information of the merged result of several RIBs.
+    ///
+    /// The project and collector are kept if shared by all RIBs and set to `all` otherwise; the
+    /// dump URLs are joined with commas, the timestamp is the earliest dump time and the output
+    /// granularity that of the first RIB.
+    pub fn merged(rib_metas: &[RibMeta]) -> RibMeta {
+        let common = |f: fn(&RibMeta) -> &String| -> String {
+            match rib_metas.iter().map(f).all_equal_value() {
+                Ok(v) => v.clone(),
+                Err(_) => "all".to_string(),
+            }
+        };
+        RibMeta {
+            project: common(|m| &m.project),
+            collector: common(|m| &m.collector),
+            rib_dump_url: rib_metas.iter().map(|m| m.rib_dump_url.as_str()).join(","),
+            timestamp: rib_metas
+                .iter()
+                .map(|m| m.timestamp)
+                .min()
+                .unwrap_or_default(),
+            file_size: rib_metas.iter().map(|m| m.file_size).sum(),
+            output_granularity: rib_metas
+                .first()
+                .map(|m| m.output_granularity)
+                .unwrap_or_default(),
+            ..Default::default()
+        }
+    }
+
+    /// Infer RIB meta information from an MRT file path following the public archive layouts.
+    ///
+    /// Supported layouts:
+    /// - RouteViews: `.../route-views.sg/bgpdata/2024.01/RIBS/rib.20240101.0000.bz2`
+    ///   (files directly under `bgpdata` belong to `route-views2`)
+    /// - RIPE RIS: `.../rrc00/2024.01/bview.20240101.0000.gz`
+    /// - any layout with a directory named after a collector of the built-in
+    ///   [CollectorProfiles], e.g. `.../route-collector.sfo.pch.net/2024/01/rib.20240101.0000.gz`
+    ///
+    /// The timestamp is taken from the file name (`<type>.YYYYMMDD.HHMM.<ext>`), and the function
+    /// returns `None` if it cannot be found. Unrecognized collectors are named `unknown`.
+    pub fn from_file_path(path: &str) -> Option<RibMeta> {
+        Self::from_file_path_with(path, CollectorProfiles::builtin())
+    }
+
+    /// Infer RIB meta information from an MRT file path like [RibMeta::from_file_path],
+    /// recognizing the collectors of the given profiles.
+    pub fn from_file_path_with(path: &str, profiles: &CollectorProfiles) -> Option<RibMeta> {
+        let segments: Vec<&str> = path.split('/').filter(|s| !s.is_empty()).collect();
+        let file_name = segments.last()?;
+
+        let mut name_parts = file_name.split('.');
+        let dump_type = name_parts.next()?;
+        let date_str = name_parts.next()?;
+        let time_str = name_parts.next()?;
+        let timestamp = NaiveDateTime::parse_from_str(
+            format!("{}{}", date_str, time_str).as_str(),
+            "%Y%m%d%H%M",
+        )
+        .ok()?;
+
+        let (project, collector) = if let Some(found) = profiles.collector_from_path(path) {
+            found
+        } else if segments.contains(&"bgpdata") {
+            ("route-views".to_string(), "route-views2".to_string())
+        } else {
+            (UNKNOWN_PROJECT.to_string(), "unknown".to_string())
+        };
+
+        Some(RibMeta {
+            project,
+            collector,
+            rib_dump_url: path.to_string(),
+            timestamp,
+            file_size: std::fs::metadata(path).ok().map(|m| m.len()),
+            dump_type: Some(dump_type.to_string()),
+            broker_item_id: None,
+            output_granularity: OutputGranularity::default(),
+        })
+    }
+}
+
+/// Dump type from the file name of an MRT file path or URL, e.g. `rib` or `bview`.
+pub fn dump_type_from_path(path: &str) -> Option<String> {
+    let file_name = path.rsplit('/').next()?;
+    let dump_type = file_name.split('.').next()?;
+    match !dump_type.is_empty() && dump_type.chars().all(|c| c.is_ascii_alphabetic()) {
+        true => Some(dump_type.to_string()),
+        false => None,
+    }
+}
+
+/// Recursively collect all files under a directory.
+#[cfg(feature = "glob")]
+fn collect_dir_files(dir: &Path, files: &mut Vec<String>) -> std::io::Result<()> {
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            collect_dir_files(path.as_path(), files)?;
+        } else {
+            files.push(path.to_string_lossy().to_string());
         }
     }
+    Ok(())
+}
+
+/// Expand glob patterns and directories into a sorted list of file paths, e.g. the inputs of
+/// `ribeye files`. Patterns matching no files expand to nothing.
+#[cfg(feature = "glob")]
+pub fn expand_input_paths(paths: &[String]) -> anyhow::Result<Vec<String>> {
+    let mut files = vec![];
+    for path in paths {
+        if Path::new(path).is_dir() {
+            collect_dir_files(Path::new(path), &mut files)?;
+            continue;
+        }
+        for entry in glob::glob(path.as_str())? {
+            let entry = entry?;
+            if entry.is_file() {
+                files.push(entry.to_string_lossy().to_string());
+            }
+        }
+    }
+    Ok(files.into_iter().sorted().dedup().collect())
 }
 
 pub fn get_default_output_path(rib_meta: &RibMeta, processor_meta: &ProcessorMeta) -> String {
@@ -38,20 +231,31 @@ pub fn get_default_output_path(rib_meta: &RibMeta, processor_meta: &ProcessorMet
         rib_meta.timestamp.year(),
         rib_meta.timestamp.month(),
     );
-    if !output_file_dir.starts_with("s3://") {
-        std::fs::create_dir_all(output_file_dir.as_str()).unwrap();
-    }
-    let output_path = format!(
+    let ts = rib_meta.timestamp;
+    let time = match rib_meta.output_granularity {
+        OutputGranularity::Daily => ts
+            .date()
+            .and_time(Default::default())
+            .and_utc()
+            .timestamp()
+            .to_string(),
+        OutputGranularity::Exact => format!(
+            "{:02}{:02}_{}",
+            ts.hour(),
+            ts.minute(),
+            ts.and_utc().timestamp()
+        ),
+    };
+    format!(
         "{}/{}_{}_{:04}-{:02}-{:02}_{}.json.bz2",
         output_file_dir.as_str(),
         processor_meta.name.as_str(),
         rib_meta.collector,
-        rib_meta.timestamp.year(),
-        rib_meta.timestamp.month(),
-        rib_meta.timestamp.day(),
-        rib_meta.timestamp.and_utc().timestamp(),
-    );
-    output_path
+        ts.year(),
+        ts.month(),
+        ts.day(),
+        time,
+    )
 }
 
 pub fn get_latest_output_path(rib_meta: &RibMeta, processor_meta: &ProcessorMeta) -> String {
@@ -61,12 +265,51 @@ pub fn get_latest_output_path(rib_meta: &RibMeta, processor_meta: &ProcessorMeta
         processor_meta.name.as_str(),
         rib_meta.collector,
     );
-    if !output_file_dir.starts_with("s3://") {
-        std::fs::create_dir_all(output_file_dir.as_str()).unwrap();
-    }
     format!("{}/latest.json.bz2", output_file_dir.as_str())
 }
 
+/// Path of the intermediate output written while a RIB is still being processed.
+pub fn get_partial_output_path(rib_meta: &RibMeta, processor_meta: &ProcessorMeta) -> String {
+    format!(
+        "{}/{}/{}/partial.json.bz2",
+        processor_meta.output_dir.as_str(),
+        processor_meta.name.as_str(),
+        rib_meta.collector,
+    )
+}
+
+/// Path of the summarized latest output of a processor under an output root directory.
+pub fn get_summary_output_path(output_dir: &str, processor_name: &str) -> String {
+    format!("{}/{}/latest.json.bz2", output_dir, processor_name)
+}
+
+/// Output root directory of a labeled run under an output root directory,
+/// `<output_dir>/labels/<label>`, keeping the outputs of experimental runs apart. Outputs streamed
+/// to stdout stay there.
+pub fn get_labeled_output_dir(output_dir: &str, label: &str) -> String {
+    if is_stdout(output_dir) {
+        return output_dir.to_string();
+    }
+    format!("{}/labels/{}", output_dir.trim_end_matches('/'), label)
+}
+
+/// Check that a run label is a single path segment of ASCII letters, digits, `.`, `_` and `-`,
+/// not starting with `.`.
+pub fn validate_label(label: &str) -> anyhow::Result<()> {
+    let valid = !label.is_empty()
+        && !label.starts_with('.')
+        && label
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '_' | '-'));
+    match valid {
+        true => Ok(()),
+        false => Err(anyhow!(
+            "invalid label {:?}, expected ASCII letters, digits, '.', '_' and '-'",
+            label
+        )),
+    }
+}
+
 /// ProcessorMeta contains the meta information of a RIB processor.
 #[derive(Debug, Clone)]
 pub struct ProcessorMeta {
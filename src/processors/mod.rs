@@ -3,27 +3,120 @@
 //! This module contains the processors that are used to process RIB data.
 
 mod as2rel;
+pub mod cache;
+mod communities;
+mod config;
+pub mod csv;
+mod defaults;
+mod duplicates;
+mod firsthop;
+mod footprint;
+pub mod merge;
 mod meta;
+mod pathdiv;
 mod peer_stats;
+pub mod peers;
 mod pfx2as;
 mod pfx2dist;
+mod pfxlen;
+mod pfxscope;
+pub mod proto;
+mod reachability;
+pub mod readers;
+pub mod schema;
+pub mod shared;
+mod sink;
+pub mod summary;
+mod typed;
+mod vantage;
 
-pub use as2rel::As2relProcessor;
-pub use meta::RibMeta;
-pub use peer_stats::PeerStatsProcessor;
-pub use pfx2as::Prefix2AsProcessor;
-pub use pfx2dist::Prefix2DistProcessor;
+pub use as2rel::{
+    score_as2rel, As2relCollectorJson, As2relEntry, As2relProcessor, As2relSummaryJson,
+};
+pub use communities::{
+    community_candidates, CommunitiesCollectorJson, CommunitiesProcessor, CommunitiesSummaryJson,
+    CommunityAction, CommunityCandidate, CommunityEntry, CommunityOwnerEntry, MIN_CANDIDATE_ROUTES,
+};
+pub use config::{ProcessorConfig, ProcessorConfigFile};
+pub use defaults::{
+    DefaultOriginEntry, DefaultRouteCollectorJson, DefaultRouteEntry, DefaultRouteProcessor,
+    DefaultRouteSummaryJson,
+};
+pub use duplicates::{
+    DuplicatePeerEntry, DuplicatesCollectorJson, DuplicatesProcessor, DuplicatesSummaryJson,
+    MAX_CONFLICT_EXAMPLES,
+};
+pub use firsthop::{FirstHopCollectorJson, FirstHopEntry, FirstHopProcessor, FirstHopSummaryJson};
+pub use footprint::{
+    FootprintCollectorJson, FootprintEntry, FootprintProcessor, FootprintSummaryJson,
+};
+#[cfg(feature = "glob")]
+pub use meta::expand_input_paths;
+pub use meta::{
+    dump_type_from_path, get_default_output_path, get_labeled_output_dir, get_partial_output_path,
+    get_summary_output_path, validate_label, OutputGranularity, ProcessorMeta, RibMeta,
+};
+pub use pathdiv::{
+    as_path_edit_distance, PathDiversityCollectorJson, PathDiversityEntry, PathDiversityProcessor,
+    PathDiversitySummaryJson, HETEROGENEOUS_STDDEVS,
+};
+pub use peer_stats::{
+    group_peers_by_asn, PeerAsnEntry, PeerInfoCollectorJson, PeerInfoEntry, PeerInfoSummaryJson,
+    PeerStatsProcessor,
+};
+pub use pfx2as::{
+    aggregate_pfx2as, Prefix2AsCollectorJson, Prefix2AsCount, Prefix2AsPeer, Prefix2AsProcessor,
+    Prefix2AsSummaryJson,
+};
+pub use pfx2dist::{
+    Prefix2Dist, Prefix2DistCollectorJson, Prefix2DistProcessor, Prefix2DistSummaryJson,
+};
+pub use pfxlen::{
+    is_too_long, PrefixLenCollectorJson, PrefixLenCounts, PrefixLenOriginEntry, PrefixLenProcessor,
+    PrefixLenSummaryJson, TooLongPrefix,
+};
+pub use pfxscope::{
+    PrefixScopeCollectorJson, PrefixScopeEntry, PrefixScopeProcessor, PrefixScopeSummaryJson,
+    SCOPED_VISIBILITY,
+};
+pub use reachability::{
+    ReachabilityCollectorJson, ReachabilityEdges, ReachabilityPeers, ReachabilityProcessor,
+    ReachabilityRow, ReachabilitySummaryJson,
+};
+pub use sink::{
+    decode_content, get_encoded_path, is_cbor_path, is_stdout, read_raw, FileSink, OutputEncoding,
+    OutputSelection, OutputSink, StdoutSink, ADDRESS_FAMILY_FIELD, LABEL_FIELD,
+    RIB_TIMESTAMP_FIELD, ROUTE_POLICY_SKIPPED_FIELD, STDOUT_DIR,
+};
+pub(crate) use sink::{EncodingSink, FieldSink, SkipPathSink};
+pub use summary::{
+    collector_dumps, CollectorDump, SummaryWriter, COLLECTOR_DUMPS_FIELD, DEFAULT_STALE_AFTER,
+};
+pub use typed::TypedProcessor;
+pub use vantage::{
+    VantageCollectorEntry, VantageCollectorJson, VantagePointEntry, VantageProcessor,
+    VantageSummaryJson, DEFAULT_VANTAGE_COVERAGE,
+};
 
+use crate::processors::shared::{SharedData, SharedInput};
+use crate::util::{ElemView, RoutePolicy};
 use anyhow::Result;
 use bgpkit_parser::BgpElem;
-use std::io::Write;
-use tempfile::tempdir;
+use serde_json::Value;
 use tracing::info;
 
-pub trait MessageProcessor {
+/// Processor of RIB entries.
+///
+/// Processors are `Send`, so pipelines can be moved to worker threads and process several RIBs
+/// concurrently.
+pub trait MessageProcessor: Send {
     /// Get the name of the processor
     fn name(&self) -> String;
 
+    /// Create a fresh processor with the same configuration (e.g. output directory) but none of
+    /// the processing state
+    fn new_instance(&self) -> Box<dyn MessageProcessor>;
+
     /// Output paths of the processor. An output path can be a local file path or an S3 path.
     fn output_paths(&self) -> Option<Vec<String>>;
 
@@ -32,65 +125,133 @@ pub trait MessageProcessor {
     /// Process a single entry in the RIB
     fn process_entry(&mut self, elem: &BgpElem) -> Result<()>;
 
-    /// Generate final result in String to be written to output file
-    fn to_result_string(&self) -> Option<String> {
-        None
+    /// Elements skipped by the pipeline before they reach the processor, unless overridden with
+    /// [RibEye::with_route_policy](crate::RibEye::with_route_policy). Skips nothing by default.
+    fn route_policy(&self) -> RoutePolicy {
+        RoutePolicy::default()
     }
 
-    /// Finalize the processor, including producing the output and storing it
-    fn output(&mut self) -> Result<()> {
-        if self.output_paths().is_none() {
-            // no output path, skip
-            return Ok(());
+    /// Process a single entry in the RIB through a view shared with the other processors of the
+    /// pipeline. Calls [MessageProcessor::process_entry] by default.
+    fn process_view(&mut self, view: &ElemView) -> Result<()> {
+        self.process_entry(view.elem())
+    }
+
+    /// Process a batch of entries. Errors of single entries are passed to `on_error`, which
+    /// applies the pipeline's error policy: it returns the error to abort the batch, or `Ok` to
+    /// skip the entry. Calls [MessageProcessor::process_view] for each entry by default.
+    fn process_batch(
+        &mut self,
+        views: &[ElemView],
+        on_error: &mut dyn FnMut(anyhow::Error) -> Result<()>,
+    ) -> Result<()> {
+        for view in views {
+            if let Err(e) = self.process_view(view) {
+                on_error(e)?;
+            }
         }
+        Ok(())
+    }
+
+    /// Intermediate data the processor needs, computed once by the pipeline for all processors,
+    /// see [shared]. None by default.
+    fn shared_inputs(&self) -> Vec<SharedInput> {
+        vec![]
+    }
 
-        let output_string = match self.to_result_string() {
+    /// Receive the per-RIB data of the declared shared inputs, set by the pipeline before the
+    /// outputs are written. Does nothing by default.
+    fn set_shared_data(&mut self, _shared: &SharedData) {}
+
+    /// One-line summary of the processed RIB for logs, e.g. `pfx2as: 950k prefixes, 78k origins`,
+    /// see [RibEye::with_brief_summaries](crate::RibEye::with_brief_summaries). The processor's
+    /// name by default.
+    fn brief_summary(&self) -> String {
+        self.name()
+    }
+
+    /// Generate the final per-collector result to be written to the output paths
+    fn to_result(&self) -> Option<Value> {
+        None
+    }
+
+    /// Finalize the processor, writing the result to all output paths through the sink
+    fn output(&mut self, sink: &dyn OutputSink) -> Result<()> {
+        let output_paths = match self.output_paths() {
+            // no output path, skip
             None => return Ok(()),
-            Some(o) => o,
+            Some(p) => p,
         };
 
-        let output_paths = self.output_paths().unwrap();
+        let result = match self.to_result() {
+            None => return Ok(()),
+            Some(r) => r,
+        };
 
         for output_path in output_paths {
-            // if output_path starts with s3://, upload to S3
-            if output_path.starts_with("s3://") {
-                info!(
-                    "finalizing {} processing, writing output to {}",
-                    self.name(),
-                    output_path.as_str(),
-                );
-
-                let temp_dir = tempfile::tempdir().unwrap();
-                let file_path = temp_dir
-                    .path()
-                    .join("temp.bz2")
-                    .to_str()
-                    .unwrap()
-                    .to_string();
-                let mut writer = oneio::get_writer(file_path.as_str()).unwrap();
-                writer.write_all(output_string.as_ref())?;
-                drop(writer);
-
-                let (bucket, p) = oneio::s3_url_parse(output_path.as_str())?;
-                oneio::s3_upload(bucket.as_str(), p.as_str(), file_path.as_str()).unwrap();
-                temp_dir.close().unwrap();
-            } else {
-                info!(
-                    "finalizing {} processing, writing output to {}",
-                    self.name(),
-                    output_path.as_str()
-                );
-
-                let mut writer = oneio::get_writer(output_path.as_str())?;
-                writer.write_all(output_string.as_ref())?;
-                drop(writer);
+            info!(
+                processor = self.name().as_str(),
+                phase = "output",
+                "finalizing {} processing, writing output to {}",
+                self.name(),
+                output_path.as_str(),
+            );
+            sink.write_json(output_path.as_str(), &result)?;
+            if sink.is_stream() {
+                break;
             }
         }
         Ok(())
     }
 
-    /// Summarize the latest RIBEye result files
-    fn summarize_latest(&self, rib_metas: &[RibMeta], ignore_error: bool) -> Result<()>;
+    /// Path of the latest output for the current RIB's collector, i.e. the previous snapshot before
+    /// this RIB's output is written. `None` by default.
+    fn latest_output_path(&self) -> Option<String> {
+        None
+    }
+
+    /// Seed the processor state from a previous output at the given path, for stateful analyses
+    /// that build on the prior snapshot instead of starting empty. Does nothing by default.
+    fn load_previous(&mut self, _path: &str) -> Result<()> {
+        Ok(())
+    }
+
+    /// Write the intermediate result of a RIB that is still being processed, e.g. to inspect a
+    /// long run or to keep progress in case of a crash. Does nothing by default.
+    fn flush_partial(&mut self, _sink: &dyn OutputSink) -> Result<()> {
+        Ok(())
+    }
+
+    /// Paths the summary of latest results is written to
+    fn summary_paths(&self) -> Vec<String> {
+        vec![]
+    }
+
+    /// Summarize the latest RIBEye result files of the given RIBs
+    fn summarize_latest(&self, rib_metas: &[RibMeta], ignore_error: bool) -> Result<Option<Value>>;
+
+    /// Summarize the latest RIBEye result files of the given RIBs, reading them back through the
+    /// sink that wrote them. Falls back to [MessageProcessor::summarize_latest] by default.
+    fn summarize_latest_with_sink(
+        &self,
+        _sink: &dyn OutputSink,
+        rib_metas: &[RibMeta],
+        ignore_error: bool,
+    ) -> Result<Option<Value>> {
+        self.summarize_latest(rib_metas, ignore_error)
+    }
+
+    /// Summarize the latest RIBEye result files of the given RIBs, reading only the files that
+    /// changed since the last incremental summary, see [cache]. Falls back to
+    /// [MessageProcessor::summarize_latest_with_sink] by default.
+    fn summarize_latest_incremental(
+        &self,
+        sink: &dyn OutputSink,
+        rib_metas: &[RibMeta],
+        ignore_error: bool,
+    ) -> Result<Option<Value>> {
+        self.summarize_latest_with_sink(sink, rib_metas, ignore_error)
+    }
 
     fn to_boxed(self) -> Box<dyn MessageProcessor>
     where
@@ -99,38 +260,3 @@ pub trait MessageProcessor {
         Box::new(self)
     }
 }
-
-pub(crate) fn write_output_file(
-    output_file_dir: &str,
-    output_content: &str,
-    compress: bool,
-) -> Result<()> {
-    let output_file_path = match compress {
-        true => format!("{}/latest.json.bz2", output_file_dir),
-        false => format!("{}/latest.json", output_file_dir),
-    };
-    match output_file_dir.starts_with("s3://") {
-        true => {
-            // write to a temporary file first
-            let tmp_dir = tempdir()?;
-            let file_path = tmp_dir
-                .path()
-                .join("latest.json.bz2")
-                .to_string_lossy()
-                .to_string();
-            let mut writer = oneio::get_writer(file_path.as_str())?;
-            write!(writer, "{}", output_content)?;
-            drop(writer);
-
-            let (bucket, p) = oneio::s3_url_parse(output_file_path.as_str())?;
-            oneio::s3_upload(bucket.as_str(), p.as_str(), file_path.as_str())?;
-        }
-        false => {
-            let mut writer = oneio::get_writer(output_file_path.as_str())?;
-            write!(writer, "{}", output_content)?;
-            drop(writer);
-        }
-    }
-
-    Ok(())
-}
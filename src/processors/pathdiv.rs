@@ -0,0 +1,313 @@
+//! `pathdiv` processor measures how much the AS paths of each prefix differ across peers.
+//!
+//! For each prefix seen by at least two peers, the processor compares the AS paths (with
+//! prepending removed) of all pairs of peers by their edit distance, i.e. the number of ASNs
+//! inserted, removed or replaced to turn one path into the other. Entries report the number of
+//! distinct paths and the average and largest pairwise distance. Prefixes whose average distance
+//! exceeds the mean over all prefixes by more than [HETEROGENEOUS_STDDEVS] standard deviations are
+//! flagged as `heterogeneous`, i.e. routed unusually differently by different peers.
+
+use crate::processors::merge::{merge_outputs, rib_dump_urls, Mergeable};
+use crate::processors::meta::{ProcessorMeta, RibMeta};
+use crate::processors::peers::{PeerIndex, PrefixPeerState};
+use crate::processors::shared::SharedInput;
+use crate::processors::typed::process_entry_view;
+use crate::processors::TypedProcessor;
+use crate::util::{format_count, ElemView};
+use bgpkit_parser::BgpElem;
+use ipnet::IpNet;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::cell::RefCell;
+use std::collections::{BTreeSet, HashMap};
+
+/// Standard deviations above the mean average edit distance at which a prefix is flagged as
+/// heterogeneous.
+pub const HETEROGENEOUS_STDDEVS: f64 = 2.0;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct PathDiversityEntry {
+    #[schemars(with = "String")]
+    pub prefix: IpNet,
+    /// collectors the prefix is visible at
+    pub collectors: BTreeSet<String>,
+    /// number of peers with an AS path for the prefix
+    pub peers_count: usize,
+    /// number of distinct AS paths; in summaries, the largest number at a single collector
+    pub distinct_paths: usize,
+    /// number of compared pairs of peers of the same collector
+    pub pairs: usize,
+    /// average edit distance between the AS paths of the compared pairs of peers
+    pub avg_edit_distance: f64,
+    /// largest edit distance between the AS paths of two peers
+    pub max_edit_distance: usize,
+    /// average edit distance more than [HETEROGENEOUS_STDDEVS] standard deviations above the mean
+    pub heterogeneous: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct PathDiversityCollectorJson {
+    pub project: String,
+    pub collector: String,
+    pub rib_dump_url: String,
+    /// mean of the average edit distances of all prefixes
+    pub mean_edit_distance: f64,
+    /// standard deviation of the average edit distances of all prefixes
+    pub stddev_edit_distance: f64,
+    /// prefixes seen by at least two peers
+    pub prefixes: Vec<PathDiversityEntry>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct PathDiversitySummaryJson {
+    pub rib_dump_urls: Vec<String>,
+    /// mean of the average edit distances of all prefixes
+    pub mean_edit_distance: f64,
+    /// standard deviation of the average edit distances of all prefixes
+    pub stddev_edit_distance: f64,
+    pub prefixes: Vec<PathDiversityEntry>,
+}
+
+/// Edit distance between two AS paths: the minimum number of ASNs inserted, removed or replaced
+/// to turn one path into the other.
+pub fn as_path_edit_distance(a: &[u32], b: &[u32]) -> usize {
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    let mut current = vec![0; b.len() + 1];
+    for (i, asn_a) in a.iter().enumerate() {
+        current[0] = i + 1;
+        for (j, asn_b) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(asn_a != asn_b);
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        std::mem::swap(&mut previous, &mut current);
+    }
+    previous[b.len()]
+}
+
+/// Flag the heterogeneous entries and return the mean and standard deviation of their average
+/// edit distances.
+fn set_heterogeneity(entries: &mut [PathDiversityEntry]) -> (f64, f64) {
+    if entries.is_empty() {
+        return (0.0, 0.0);
+    }
+    let n = entries.len() as f64;
+    let mean = entries.iter().map(|e| e.avg_edit_distance).sum::<f64>() / n;
+    let variance = entries
+        .iter()
+        .map(|e| (e.avg_edit_distance - mean).powi(2))
+        .sum::<f64>()
+        / n;
+    let stddev = variance.sqrt();
+    for entry in entries {
+        entry.heterogeneous = entry.avg_edit_distance > mean + HETEROGENEOUS_STDDEVS * stddev;
+    }
+    (mean, stddev)
+}
+
+pub struct PathDiversityProcessor {
+    rib_meta: Option<RibMeta>,
+    processor_meta: ProcessorMeta,
+    peers: PeerIndex,
+    /// index of the AS path of each peer for each prefix
+    prefixes: PrefixPeerState<u32>,
+    /// distinct AS paths by index
+    paths: Vec<Vec<u32>>,
+    path_index: HashMap<Vec<u32>, u32>,
+    /// edit distances by pair of path indexes, shared by prefixes with the same paths
+    distances: RefCell<HashMap<(u32, u32), usize>>,
+}
+
+impl PathDiversityProcessor {
+    pub fn new(output_dir: &str) -> Self {
+        let processor_meta = ProcessorMeta {
+            name: "pathdiv".to_string(),
+            output_dir: output_dir.to_string(),
+        };
+
+        PathDiversityProcessor {
+            rib_meta: None,
+            processor_meta,
+            peers: PeerIndex::new(),
+            prefixes: PrefixPeerState::new(),
+            paths: vec![],
+            path_index: HashMap::new(),
+            distances: RefCell::new(HashMap::new()),
+        }
+    }
+
+    fn distance(&self, a: u32, b: u32) -> usize {
+        let key = (a.min(b), a.max(b));
+        *self.distances.borrow_mut().entry(key).or_insert_with(|| {
+            as_path_edit_distance(&self.paths[a as usize], &self.paths[b as usize])
+        })
+    }
+
+    fn entry(&self, collector: &str, prefix: IpNet, paths: &[(u32, u32)]) -> PathDiversityEntry {
+        let mut counts: HashMap<u32, usize> = HashMap::new();
+        for (_, path) in paths {
+            *counts.entry(*path).or_default() += 1;
+        }
+        let counts: Vec<(u32, usize)> = counts.into_iter().collect();
+        let (mut total, mut max) = (0, 0);
+        for (i, (a, count_a)) in counts.iter().enumerate() {
+            for (b, count_b) in &counts[i + 1..] {
+                let distance = self.distance(*a, *b);
+                total += distance * count_a * count_b;
+                max = max.max(distance);
+            }
+        }
+        let pairs = paths.len() * (paths.len() - 1) / 2;
+        PathDiversityEntry {
+            prefix,
+            collectors: paths
+                .iter()
+                .map(|(peer, _)| self.peers.collector(*peer).unwrap_or(collector).to_string())
+                .collect(),
+            peers_count: paths.len(),
+            distinct_paths: counts.len(),
+            pairs,
+            avg_edit_distance: total as f64 / pairs as f64,
+            max_edit_distance: max,
+            heterogeneous: false,
+        }
+    }
+}
+
+impl TypedProcessor for PathDiversityProcessor {
+    type Output = PathDiversityCollectorJson;
+    type Summary = PathDiversitySummaryJson;
+    const DATASET_FIELD: &'static str = "prefixes";
+
+    fn processor_meta(&self) -> &ProcessorMeta {
+        &self.processor_meta
+    }
+
+    fn rib_meta(&self) -> Option<&RibMeta> {
+        self.rib_meta.as_ref()
+    }
+
+    fn new_instance(&self) -> Self {
+        Self::new(self.processor_meta.output_dir.as_str())
+    }
+
+    fn reset_processor(&mut self, rib_meta: &RibMeta) {
+        self.rib_meta = Some(rib_meta.clone());
+        self.peers.reset(rib_meta.collector.as_str());
+    }
+
+    fn shared_inputs(&self) -> Vec<SharedInput> {
+        vec![SharedInput::StrippedPath]
+    }
+
+    fn process_entry(&mut self, elem: &BgpElem) -> anyhow::Result<()> {
+        process_entry_view(self, elem)
+    }
+
+    fn process_view(&mut self, view: &ElemView) -> anyhow::Result<()> {
+        if !view.is_announce() {
+            return Ok(());
+        }
+        let Some(path) = view.path() else {
+            return Ok(());
+        };
+        let path_index = match self.path_index.get(path.asns()) {
+            Some(index) => *index,
+            None => {
+                let index = self.paths.len() as u32;
+                self.paths.push(path.asns().to_vec());
+                self.path_index.insert(path.asns().to_vec(), index);
+                index
+            }
+        };
+        let peer = self.peers.index(view);
+        self.prefixes
+            .insert(view.elem().prefix.prefix, peer, path_index);
+        Ok(())
+    }
+
+    fn brief_summary(&self) -> String {
+        format!(
+            "{}: {} prefixes, {} distinct AS paths",
+            self.processor_meta.name,
+            format_count(self.prefixes.len()),
+            format_count(self.paths.len())
+        )
+    }
+
+    fn to_output(&self) -> Option<PathDiversityCollectorJson> {
+        let rib_meta = self.rib_meta.as_ref()?;
+        let mut prefixes: Vec<PathDiversityEntry> = self
+            .prefixes
+            .iter()
+            .filter(|(_, paths)| paths.len() >= 2)
+            .map(|(prefix, paths)| self.entry(rib_meta.collector.as_str(), *prefix, paths))
+            .collect();
+        prefixes.sort_by_key(|entry| entry.prefix);
+        let (mean, stddev) = set_heterogeneity(&mut prefixes);
+        Some(PathDiversityCollectorJson {
+            project: rib_meta.project.clone(),
+            collector: rib_meta.collector.clone(),
+            rib_dump_url: rib_meta.rib_dump_url.clone(),
+            mean_edit_distance: mean,
+            stddev_edit_distance: stddev,
+            prefixes,
+        })
+    }
+
+    fn summarize(
+        &self,
+        rib_metas: &[RibMeta],
+        outputs: Vec<(RibMeta, PathDiversityCollectorJson)>,
+    ) -> anyhow::Result<PathDiversitySummaryJson> {
+        let mut summary: PathDiversitySummaryJson =
+            merge_outputs(rib_metas, outputs.into_iter().map(|(_, output)| output));
+        summary.prefixes.sort_by_key(|entry| entry.prefix);
+        let (mean, stddev) = set_heterogeneity(&mut summary.prefixes);
+        summary.mean_edit_distance = mean;
+        summary.stddev_edit_distance = stddev;
+        Ok(summary)
+    }
+}
+
+impl Mergeable for PathDiversityCollectorJson {
+    type Entry = PathDiversityEntry;
+    type Key = IpNet;
+    type Merged = PathDiversitySummaryJson;
+
+    fn into_entries(self) -> Vec<PathDiversityEntry> {
+        self.prefixes
+    }
+
+    fn entry_key(entry: &PathDiversityEntry) -> IpNet {
+        entry.prefix
+    }
+
+    /// Pairs are only compared within a collector, so averages are weighted by their pairs.
+    fn merge_entry(entry: &mut PathDiversityEntry, other: PathDiversityEntry) {
+        let pairs = entry.pairs + other.pairs;
+        if pairs > 0 {
+            entry.avg_edit_distance = (entry.avg_edit_distance * entry.pairs as f64
+                + other.avg_edit_distance * other.pairs as f64)
+                / pairs as f64;
+        }
+        entry.pairs = pairs;
+        entry.collectors.extend(other.collectors);
+        entry.peers_count += other.peers_count;
+        entry.distinct_paths = entry.distinct_paths.max(other.distinct_paths);
+        entry.max_edit_distance = entry.max_edit_distance.max(other.max_edit_distance);
+    }
+
+    /// Merge the prefix entries; the statistics and heterogeneity flags are filled in by
+    /// [PathDiversityProcessor]'s summarization.
+    fn merge_files(
+        rib_metas: &[RibMeta],
+        entries: Vec<PathDiversityEntry>,
+    ) -> PathDiversitySummaryJson {
+        PathDiversitySummaryJson {
+            rib_dump_urls: rib_dump_urls(rib_metas),
+            mean_edit_distance: 0.0,
+            stddev_edit_distance: 0.0,
+            prefixes: entries,
+        }
+    }
+}
@@ -1,43 +1,98 @@
 //! `peer_stats` processor generates basic counting information for route collector peers.
 //!
-//! Each route collector peer has a corresponding counting struct.
-
-use crate::processors::meta::{
-    get_default_output_path, get_latest_output_path, ProcessorMeta, RibMeta,
-};
-use crate::processors::write_output_file;
-use crate::MessageProcessor;
-use bgpkit_parser::models::ElemType;
+//! Peers are identified by the shared [PeerTracker], with the prefixes and neighbors of each
+//! peer counted on top. In approximate mode ([PeerStatsProcessor::with_approximate]), prefixes are
+//! counted with [HyperLogLog] sketches instead of prefix sets, which cuts memory usage by an order
+//! of magnitude on full-feed peers while keeping counts within about 1%.
+
+use crate::processors::merge::{rib_dump_urls, Mergeable};
+use crate::processors::meta::{ProcessorMeta, RibMeta};
+use crate::processors::peers::PeerTracker;
+use crate::processors::shared::{FullFeedPeers, SharedData, SharedInput};
+use crate::processors::typed::process_entry_view;
+use crate::processors::TypedProcessor;
+use crate::util::{format_count, ElemView, HyperLogLog};
 use bgpkit_parser::BgpElem;
 use ipnet::{IpNet, Ipv4Net, Ipv6Net};
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
-use serde_json::json;
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeMap, BTreeSet, HashSet};
 use std::hash::{Hash, Hasher};
 use std::net::IpAddr;
-use tracing::{info, warn};
+use std::sync::Arc;
 
+/// Prefixes and neighbors observed from a route collector peer.
 #[derive(Debug, Clone)]
-pub struct PeerInfo {
-    /// The name of the route collector peer
-    pub collector: Option<String>,
-    /// The IP address of the route collector peer
-    pub ip: IpAddr,
-    /// The ASN of the route collector peer
-    pub asn: u32,
-    /// Number of IPv4 prefixes observed
-    pub ipv4_pfxs: HashSet<Ipv4Net>,
-    /// Number of IPv6 prefixes observed
-    pub ipv6_pfxs: HashSet<Ipv6Net>,
-    /// Number of directly connected ASes
-    pub num_connected_asns: HashSet<u32>,
-    /// Announce IPv4 default route (0.0.0.0/0)
-    pub ipv4_default: bool,
-    /// Announce IPv6 default route (::/0)
-    pub ipv6_default: bool,
+struct PeerPrefixes {
+    prefixes: PrefixCounter,
+    connected_asns: HashSet<u32>,
+    origin_asns: HashSet<u32>,
+    /// distinct (first hop, origin) pairs of paths
+    first_last_hops: HashSet<(u32, u32)>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// Distinct prefixes of a peer, per address family.
+#[derive(Debug, Clone)]
+enum PrefixCounter {
+    Exact {
+        ipv4_pfxs: HashSet<Ipv4Net>,
+        ipv6_pfxs: HashSet<Ipv6Net>,
+    },
+    Approximate {
+        ipv4_pfxs: HyperLogLog,
+        ipv6_pfxs: HyperLogLog,
+    },
+}
+
+impl PeerPrefixes {
+    fn new(approximate: bool) -> Self {
+        let prefixes = match approximate {
+            true => PrefixCounter::Approximate {
+                ipv4_pfxs: HyperLogLog::new(),
+                ipv6_pfxs: HyperLogLog::new(),
+            },
+            false => PrefixCounter::Exact {
+                ipv4_pfxs: HashSet::new(),
+                ipv6_pfxs: HashSet::new(),
+            },
+        };
+        PeerPrefixes {
+            prefixes,
+            connected_asns: HashSet::new(),
+            origin_asns: HashSet::new(),
+            first_last_hops: HashSet::new(),
+        }
+    }
+
+    fn insert(&mut self, prefix: IpNet) {
+        match (&mut self.prefixes, prefix) {
+            (PrefixCounter::Exact { ipv4_pfxs, .. }, IpNet::V4(p)) => {
+                ipv4_pfxs.insert(p);
+            }
+            (PrefixCounter::Exact { ipv6_pfxs, .. }, IpNet::V6(p)) => {
+                ipv6_pfxs.insert(p);
+            }
+            (PrefixCounter::Approximate { ipv4_pfxs, .. }, IpNet::V4(p)) => ipv4_pfxs.insert(&p),
+            (PrefixCounter::Approximate { ipv6_pfxs, .. }, IpNet::V6(p)) => ipv6_pfxs.insert(&p),
+        }
+    }
+
+    /// Number of distinct IPv4 and IPv6 prefixes.
+    fn counts(&self) -> (usize, usize) {
+        match &self.prefixes {
+            PrefixCounter::Exact {
+                ipv4_pfxs,
+                ipv6_pfxs,
+            } => (ipv4_pfxs.len(), ipv6_pfxs.len()),
+            PrefixCounter::Approximate {
+                ipv4_pfxs,
+                ipv6_pfxs,
+            } => (ipv4_pfxs.estimate(), ipv6_pfxs.estimate()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct PeerInfoEntry {
     pub ip: IpAddr,
     pub collector: Option<String>,
@@ -45,11 +100,25 @@ pub struct PeerInfoEntry {
     pub num_v4_pfxs: usize,
     pub num_v6_pfxs: usize,
     pub num_connected_asns: usize,
+    /// number of distinct origin ASNs
+    #[serde(default)]
+    pub num_origin_asns: usize,
+    /// number of distinct combinations of first-hop and origin ASNs, an indicator of path
+    /// diversity
+    #[serde(default)]
+    pub num_first_last_hops: usize,
     pub has_v4_default: bool,
     pub has_v6_default: bool,
+    /// prefix counts are estimates, see [PeerStatsProcessor::with_approximate]
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub approximate: bool,
+    /// announces a full feed in the RIB dump, only set when processed in a pipeline, see
+    /// [SharedInput::FullFeedPeers]
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub full_feed: bool,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
 pub struct PeerInfoCollectorJson {
     pub project: String,
     pub collector: String,
@@ -57,60 +126,95 @@ pub struct PeerInfoCollectorJson {
     pub peers: HashSet<PeerInfoEntry>,
 }
 
+impl PeerInfoEntry {
+    /// Identity of the peer across outputs: the same IP address at different collectors is a
+    /// different peering session.
+    pub fn key(&self) -> (Option<String>, IpAddr) {
+        (self.collector.clone(), self.ip)
+    }
+}
+
 impl PartialEq<Self> for PeerInfoEntry {
     fn eq(&self, other: &Self) -> bool {
-        self.ip == other.ip
+        self.collector == other.collector && self.ip == other.ip
     }
 }
 
 impl Hash for PeerInfoEntry {
     fn hash<H: Hasher>(&self, state: &mut H) {
+        self.collector.hash(state);
         self.ip.hash(state);
     }
 }
 
 impl Eq for PeerInfoEntry {}
 
-#[derive(Debug, Serialize, Deserialize)]
+/// Peers of one ASN across all collectors.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct PeerAsnEntry {
+    pub asn: u32,
+    /// number of peering sessions, i.e. distinct collector and peer IP pairs
+    pub num_sessions: usize,
+    /// collectors the ASN peers with, sorted by name
+    pub collectors: Vec<String>,
+    /// prefix counts summed over all sessions
+    pub num_v4_pfxs: usize,
+    pub num_v6_pfxs: usize,
+}
+
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
 pub struct PeerInfoSummaryJson {
     pub rib_dump_urls: Vec<String>,
     pub peers: HashSet<PeerInfoEntry>,
+    /// peers grouped by ASN, sorted by ASN
+    #[serde(default)]
+    pub asns: Vec<PeerAsnEntry>,
 }
 
-impl PeerInfo {
-    pub fn new_from_ip(ip: IpAddr, asn: u32, collector: Option<String>) -> Self {
-        PeerInfo {
-            collector,
-            ip,
-            asn,
-            ipv4_pfxs: HashSet::new(),
-            ipv6_pfxs: HashSet::new(),
-            num_connected_asns: HashSet::new(),
-            ipv4_default: false,
-            ipv6_default: false,
-        }
-    }
-}
-
-impl From<&PeerInfo> for PeerInfoEntry {
-    fn from(peer_info: &PeerInfo) -> Self {
-        PeerInfoEntry {
-            ip: peer_info.ip,
-            collector: peer_info.collector.clone(),
-            asn: peer_info.asn,
-            num_v4_pfxs: peer_info.ipv4_pfxs.len(),
-            num_v6_pfxs: peer_info.ipv6_pfxs.len(),
-            num_connected_asns: peer_info.num_connected_asns.len(),
-            has_v4_default: peer_info.ipv4_default,
-            has_v6_default: peer_info.ipv6_default,
+/// Group peers by ASN, sorted by ASN. Each collector and peer IP pair counts as one session.
+pub fn group_peers_by_asn<'a>(
+    peers: impl IntoIterator<Item = &'a PeerInfoEntry>,
+) -> Vec<PeerAsnEntry> {
+    let mut groups: BTreeMap<u32, (PeerAsnEntry, BTreeSet<String>)> = BTreeMap::new();
+    let mut sessions: HashSet<(Option<&str>, IpAddr)> = HashSet::new();
+    for peer in peers {
+        if !sessions.insert((peer.collector.as_deref(), peer.ip)) {
+            continue;
         }
+        let (entry, collectors) = groups.entry(peer.asn).or_insert_with(|| {
+            let entry = PeerAsnEntry {
+                asn: peer.asn,
+                num_sessions: 0,
+                collectors: vec![],
+                num_v4_pfxs: 0,
+                num_v6_pfxs: 0,
+            };
+            (entry, BTreeSet::new())
+        });
+        entry.num_sessions += 1;
+        entry.num_v4_pfxs += peer.num_v4_pfxs;
+        entry.num_v6_pfxs += peer.num_v6_pfxs;
+        collectors.extend(peer.collector.clone());
     }
+    groups
+        .into_values()
+        .map(|(mut entry, collectors)| {
+            entry.collectors = collectors.into_iter().collect();
+            entry
+        })
+        .collect()
 }
 
 pub struct PeerStatsProcessor {
     rib_meta: Option<RibMeta>,
     processor_meta: ProcessorMeta,
-    peer_info_map: HashMap<IpAddr, PeerInfo>,
+    peers: PeerTracker,
+    /// prefixes and neighbors of each peer, indexed by peer ID
+    peer_prefixes: Vec<PeerPrefixes>,
+    /// count prefixes with HyperLogLog sketches
+    approximate: bool,
+    /// full-feed peers shared by the pipeline
+    full_feed_peers: Option<Arc<FullFeedPeers>>,
 }
 
 impl PeerStatsProcessor {
@@ -123,134 +227,152 @@ impl PeerStatsProcessor {
         PeerStatsProcessor {
             rib_meta: None,
             processor_meta,
-            peer_info_map: HashMap::new(),
+            peers: PeerTracker::new(),
+            peer_prefixes: vec![],
+            approximate: false,
+            full_feed_peers: None,
         }
     }
+
+    /// Count prefixes approximately, with HyperLogLog sketches instead of prefix sets.
+    pub fn with_approximate(mut self, approximate: bool) -> Self {
+        self.approximate = approximate;
+        self
+    }
 }
 
-impl MessageProcessor for PeerStatsProcessor {
-    fn name(&self) -> String {
-        self.processor_meta.name.clone()
+impl TypedProcessor for PeerStatsProcessor {
+    type Output = PeerInfoCollectorJson;
+    type Summary = PeerInfoSummaryJson;
+    const DATASET_FIELD: &'static str = "peers";
+
+    fn processor_meta(&self) -> &ProcessorMeta {
+        &self.processor_meta
+    }
+
+    fn rib_meta(&self) -> Option<&RibMeta> {
+        self.rib_meta.as_ref()
     }
 
-    fn output_paths(&self) -> Option<Vec<String>> {
-        Some(vec![
-            get_default_output_path(self.rib_meta.as_ref().unwrap(), &self.processor_meta),
-            get_latest_output_path(self.rib_meta.as_ref().unwrap(), &self.processor_meta),
-        ])
+    fn new_instance(&self) -> Self {
+        Self::new(self.processor_meta.output_dir.as_str()).with_approximate(self.approximate)
     }
 
     fn reset_processor(&mut self, rib_meta: &RibMeta) {
         self.rib_meta = Some(rib_meta.clone());
+        self.peers.set_collector(Some(rib_meta.collector.clone()));
+        self.full_feed_peers = None;
+    }
+
+    fn shared_inputs(&self) -> Vec<SharedInput> {
+        vec![SharedInput::StrippedPath, SharedInput::FullFeedPeers]
+    }
+
+    fn set_shared_data(&mut self, shared: &SharedData) {
+        self.full_feed_peers = shared.full_feed_peers.clone();
     }
 
     fn process_entry(&mut self, elem: &BgpElem) -> anyhow::Result<()> {
-        let collector = self
-            .rib_meta
-            .as_ref()
-            .map(|r| Some(r.collector.clone()))
-            .unwrap_or(None);
-        let peer_info = self
-            .peer_info_map
-            .entry(elem.peer_ip)
-            .or_insert(PeerInfo::new_from_ip(
-                elem.peer_ip,
-                elem.peer_asn.to_u32(),
-                collector,
-            ));
-
-        if elem.elem_type != ElemType::ANNOUNCE {
+        process_entry_view(self, elem)
+    }
+
+    fn process_view(&mut self, view: &ElemView) -> anyhow::Result<()> {
+        let elem = view.elem();
+        let peer_id = self.peers.observe(elem);
+        if self.peer_prefixes.len() < self.peers.len() {
+            let approximate = self.approximate;
+            self.peer_prefixes
+                .resize_with(self.peers.len(), || PeerPrefixes::new(approximate));
+        }
+        let peer_prefixes = &mut self.peer_prefixes[peer_id.0 as usize];
+
+        if !view.is_announce() {
             // skip processing non-announce messages
             return Ok(());
         }
 
-        if let Some(path) = &elem.as_path {
-            if let Some(seq) = path.to_u32_vec_opt(true) {
-                if let Some(next_hop) = seq.first() {
-                    peer_info.num_connected_asns.insert(*next_hop);
-                }
-            }
+        if let Some(path) = view.path() {
+            let (next_hop, origin) = (path.first_hop(), path.origin());
+            peer_prefixes.connected_asns.insert(next_hop);
+            peer_prefixes.origin_asns.insert(origin);
+            peer_prefixes.first_last_hops.insert((next_hop, origin));
         }
 
-        match elem.prefix.prefix {
-            IpNet::V4(p) => {
-                if p.prefix_len() == 0 {
-                    peer_info.ipv4_default = true;
-                }
-                peer_info.ipv4_pfxs.insert(p);
-            }
-            IpNet::V6(p) => {
-                if p.prefix_len() == 0 {
-                    peer_info.ipv6_default = true;
-                }
-                peer_info.ipv6_pfxs.insert(p);
-            }
-        }
+        peer_prefixes.insert(elem.prefix.prefix);
 
         Ok(())
     }
 
-    fn to_result_string(&self) -> Option<String> {
-        let rib_meta = self.rib_meta.as_ref().unwrap();
-        let value = json!(PeerInfoCollectorJson {
+    fn brief_summary(&self) -> String {
+        let mut summary = format!(
+            "{}: {} peers",
+            self.processor_meta.name,
+            format_count(self.peers.len())
+        );
+        if let Some(full_feed) = &self.full_feed_peers {
+            summary.push_str(format!(", {} full-feed", format_count(full_feed.len())).as_str());
+        }
+        summary
+    }
+
+    fn to_output(&self) -> Option<PeerInfoCollectorJson> {
+        let rib_meta = self.rib_meta.as_ref()?;
+        Some(PeerInfoCollectorJson {
             project: rib_meta.project.clone(),
             collector: rib_meta.collector.clone(),
             rib_dump_url: rib_meta.rib_dump_url.clone(),
             peers: self
-                .peer_info_map
-                .values()
-                .map(|peer_info| peer_info.into())
+                .peers
+                .peers()
+                .map(|peer| {
+                    let prefixes = &self.peer_prefixes[peer.id.0 as usize];
+                    let (num_v4_pfxs, num_v6_pfxs) = prefixes.counts();
+                    PeerInfoEntry {
+                        ip: peer.ip,
+                        collector: peer.collector.clone(),
+                        asn: peer.asn,
+                        num_v4_pfxs,
+                        num_v6_pfxs,
+                        num_connected_asns: prefixes.connected_asns.len(),
+                        num_origin_asns: prefixes.origin_asns.len(),
+                        num_first_last_hops: prefixes.first_last_hops.len(),
+                        has_v4_default: peer.v4_default,
+                        has_v6_default: peer.v6_default,
+                        approximate: self.approximate,
+                        full_feed: self
+                            .full_feed_peers
+                            .as_ref()
+                            .is_some_and(|f| f.contains(peer.collector.as_deref(), &peer.ip)),
+                    }
+                })
                 .collect(),
-        });
-
-        serde_json::to_string_pretty(&value).ok()
+        })
     }
+}
 
-    fn summarize_latest(&self, rib_metas: &[RibMeta], ignore_error: bool) -> anyhow::Result<()> {
-        let mut peer_info_map = HashMap::<IpAddr, PeerInfoEntry>::new();
-
-        for rib_meta in rib_metas {
-            let latest_file_path = get_latest_output_path(rib_meta, &self.processor_meta);
-            info!("summarizing {}...", latest_file_path.as_str());
-            let data =
-                match oneio::read_json_struct::<PeerInfoCollectorJson>(latest_file_path.as_str()) {
-                    Ok(d) => d,
-                    Err(e) => {
-                        if ignore_error {
-                            warn!("failed to read {}, skipping...", latest_file_path.as_str());
-                            continue;
-                        } else {
-                            return Err(anyhow::anyhow!(
-                                "failed to read {}: {}",
-                                latest_file_path.as_str(),
-                                e
-                            ));
-                        }
-                    }
-                };
+impl Mergeable for PeerInfoCollectorJson {
+    type Entry = PeerInfoEntry;
+    type Key = (Option<String>, IpAddr);
+    type Merged = PeerInfoSummaryJson;
 
-            for entry in data.peers {
-                peer_info_map.insert(entry.ip, entry);
-            }
-        }
-
-        let peers = peer_info_map.into_values().collect();
+    fn into_entries(self) -> Vec<PeerInfoEntry> {
+        self.peers.into_iter().collect()
+    }
 
-        let json_data = PeerInfoSummaryJson {
-            peers,
-            rib_dump_urls: rib_metas.iter().map(|r| r.rib_dump_url.clone()).collect(),
-        };
+    fn entry_key(entry: &PeerInfoEntry) -> (Option<String>, IpAddr) {
+        entry.key()
+    }
 
-        let output_file_dir = format!(
-            "{}/{}",
-            self.processor_meta.output_dir.as_str(),
-            self.processor_meta.name.as_str(),
-        );
-        let output_content = serde_json::to_string_pretty(&json_data)?;
+    fn merge_entry(entry: &mut PeerInfoEntry, other: PeerInfoEntry) {
+        // the latest seen information of the session wins
+        *entry = other;
+    }
 
-        // output both compressed and uncompressed latest.json file
-        write_output_file(output_file_dir.as_str(), output_content.as_str(), true)?;
-        write_output_file(output_file_dir.as_str(), output_content.as_str(), false)?;
-        Ok(())
+    fn merge_files(rib_metas: &[RibMeta], entries: Vec<PeerInfoEntry>) -> PeerInfoSummaryJson {
+        PeerInfoSummaryJson {
+            asns: group_peers_by_asn(&entries),
+            peers: entries.into_iter().collect(),
+            rib_dump_urls: rib_dump_urls(rib_metas),
+        }
     }
 }
@@ -0,0 +1,367 @@
+//! Shared tracking of route collector peers.
+//!
+//! [PeerTracker] gives processors one consistent notion of a "peer": a peer is identified by its
+//! collector and IP address, so that a router peering with several collectors counts as one
+//! session per collector, is assigned a compact [PeerId] on first sight, and has per-peer counters
+//! used for full-feed detection. Processors that need peer
+//! information embed a tracker and call [PeerTracker::observe] for each element.
+//!
+//! Processors that only need a compact index of peers embed a [PeerIndex] instead, which resolves
+//! the peer IDs [RibEye](crate::RibEye) tags element views with and only hashes peer IPs on first
+//! sight of a peer. Like the tracker, it tells peers of different collectors apart.
+//!
+//! Processors comparing the routes of a prefix across peers keep a [PrefixPeerState], holding one
+//! value per peer index and prefix.
+
+use crate::util::ElemView;
+use bgpkit_parser::models::ElemType;
+use bgpkit_parser::BgpElem;
+use ipnet::IpNet;
+use std::collections::HashMap;
+use std::net::IpAddr;
+
+/// Default minimum number of IPv4 prefixes announced by a full-feed peer.
+pub const DEFAULT_FULL_FEED_V4: u64 = 800_000;
+
+/// Default minimum number of IPv6 prefixes announced by a full-feed peer.
+pub const DEFAULT_FULL_FEED_V6: u64 = 150_000;
+
+/// Compact identifier of a peer within a [PeerTracker], assigned in order of first sight.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct PeerId(pub u32);
+
+/// State of a single route collector peer.
+#[derive(Debug, Clone)]
+pub struct PeerState {
+    pub id: PeerId,
+    pub ip: IpAddr,
+    pub asn: u32,
+    /// collector the peer is seen at
+    pub collector: Option<String>,
+    /// number of IPv4 announcements
+    pub v4_announcements: u64,
+    /// number of IPv6 announcements
+    pub v6_announcements: u64,
+    /// number of withdrawals
+    pub withdrawals: u64,
+    /// announces the IPv4 default route (0.0.0.0/0)
+    pub v4_default: bool,
+    /// announces the IPv6 default route (::/0)
+    pub v6_default: bool,
+}
+
+/// Tracker of all peers seen in the processed elements.
+///
+/// Full-feed detection compares per-family announcement counts against thresholds, which
+/// matches distinct prefixes for RIB dumps where each peer announces a prefix once.
+#[derive(Debug, Clone)]
+pub struct PeerTracker {
+    peers: Vec<PeerState>,
+    /// peers by collector index and IP address
+    index: HashMap<(usize, IpAddr), PeerId>,
+    /// collectors seen, by index
+    collectors: Vec<Option<String>>,
+    /// index of the current collector
+    collector: usize,
+    full_feed_v4: u64,
+    full_feed_v6: u64,
+}
+
+impl Default for PeerTracker {
+    fn default() -> Self {
+        PeerTracker {
+            peers: vec![],
+            index: HashMap::new(),
+            collectors: vec![None],
+            collector: 0,
+            full_feed_v4: DEFAULT_FULL_FEED_V4,
+            full_feed_v6: DEFAULT_FULL_FEED_V6,
+        }
+    }
+}
+
+impl PeerTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the minimum numbers of IPv4 and IPv6 prefixes a full-feed peer announces.
+    pub fn with_full_feed_thresholds(mut self, v4: u64, v6: u64) -> Self {
+        self.full_feed_v4 = v4;
+        self.full_feed_v6 = v6;
+        self
+    }
+
+    /// Set the collector of the following elements. Peers with the same IP address at different
+    /// collectors are tracked separately.
+    pub fn set_collector(&mut self, collector: Option<String>) {
+        self.collector = match self.collectors.iter().position(|c| *c == collector) {
+            Some(i) => i,
+            None => {
+                self.collectors.push(collector);
+                self.collectors.len() - 1
+            }
+        };
+    }
+
+    /// Record an element of the current collector and return the ID of its peer.
+    pub fn observe(&mut self, elem: &BgpElem) -> PeerId {
+        let key = (self.collector, elem.peer_ip);
+        let id = match self.index.get(&key) {
+            Some(id) => *id,
+            None => {
+                let id = PeerId(self.peers.len() as u32);
+                self.peers.push(PeerState {
+                    id,
+                    ip: elem.peer_ip,
+                    asn: elem.peer_asn.to_u32(),
+                    collector: self.collectors[self.collector].clone(),
+                    v4_announcements: 0,
+                    v6_announcements: 0,
+                    withdrawals: 0,
+                    v4_default: false,
+                    v6_default: false,
+                });
+                self.index.insert(key, id);
+                id
+            }
+        };
+
+        let state = &mut self.peers[id.0 as usize];
+        match elem.elem_type {
+            ElemType::WITHDRAW => state.withdrawals += 1,
+            ElemType::ANNOUNCE => {
+                let is_default = elem.prefix.prefix.prefix_len() == 0;
+                match elem.prefix.prefix {
+                    IpNet::V4(_) => {
+                        state.v4_announcements += 1;
+                        state.v4_default |= is_default;
+                    }
+                    IpNet::V6(_) => {
+                        state.v6_announcements += 1;
+                        state.v6_default |= is_default;
+                    }
+                }
+            }
+        }
+        id
+    }
+
+    /// Get a peer by ID.
+    pub fn get(&self, id: PeerId) -> Option<&PeerState> {
+        self.peers.get(id.0 as usize)
+    }
+
+    /// Get a peer of the current collector by IP address.
+    pub fn get_by_ip(&self, ip: &IpAddr) -> Option<&PeerState> {
+        self.index
+            .get(&(self.collector, *ip))
+            .and_then(|id| self.get(*id))
+    }
+
+    /// All peers in order of first sight.
+    pub fn peers(&self) -> impl Iterator<Item = &PeerState> {
+        self.peers.iter()
+    }
+
+    /// Check if the peer is a full-feed peer for IPv4 or IPv6.
+    pub fn is_full_feed(&self, id: PeerId) -> bool {
+        self.get(id)
+            .map(|p| {
+                p.v4_announcements >= self.full_feed_v4 || p.v6_announcements >= self.full_feed_v6
+            })
+            .unwrap_or(false)
+    }
+
+    /// IDs of all full-feed peers.
+    pub fn full_feed_peers(&self) -> Vec<PeerId> {
+        self.peers
+            .iter()
+            .map(|p| p.id)
+            .filter(|id| self.is_full_feed(*id))
+            .collect()
+    }
+
+    pub fn len(&self) -> usize {
+        self.peers.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.peers.is_empty()
+    }
+}
+
+/// Compact index of the peers seen by a processor, assigned in order of first sight.
+///
+/// Peers are identified by collector and IP address, like in a [PeerTracker].
+#[derive(Debug, Clone)]
+pub struct PeerIndex {
+    /// IP and ASN of each peer, by index
+    peers: Vec<(IpAddr, u32)>,
+    /// collector index of each peer, by index
+    peer_collectors: Vec<usize>,
+    /// index by pipeline peer ID, `u32::MAX` for IDs not seen yet
+    by_id: Vec<u32>,
+    /// index by collector index and IP address
+    by_ip: HashMap<(usize, IpAddr), u32>,
+    /// collectors seen, by index
+    collectors: Vec<Option<String>>,
+    /// index of the current collector
+    collector: usize,
+}
+
+impl Default for PeerIndex {
+    fn default() -> Self {
+        PeerIndex {
+            peers: vec![],
+            peer_collectors: vec![],
+            by_id: vec![],
+            by_ip: HashMap::new(),
+            collectors: vec![None],
+            collector: 0,
+        }
+    }
+}
+
+impl PeerIndex {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the collector of the following views. Peers with the same IP address at different
+    /// collectors get different indexes.
+    pub fn set_collector(&mut self, collector: &str) {
+        let collector = Some(collector.to_string());
+        self.collector = match self.collectors.iter().position(|c| *c == collector) {
+            Some(i) => i,
+            None => {
+                self.collectors.push(collector);
+                self.collectors.len() - 1
+            }
+        };
+    }
+
+    /// Index of the view's peer, looked up by pipeline peer ID when available.
+    pub fn index(&mut self, view: &ElemView) -> u32 {
+        if let Some(id) = view.peer_id() {
+            if let Some(index) = self.by_id.get(id.0 as usize) {
+                if *index != u32::MAX {
+                    return *index;
+                }
+            }
+        }
+
+        let elem = view.elem();
+        let (peers, peer_collectors) = (&mut self.peers, &mut self.peer_collectors);
+        let collector = self.collector;
+        let index = *self
+            .by_ip
+            .entry((collector, elem.peer_ip))
+            .or_insert_with(|| {
+                peers.push((elem.peer_ip, elem.peer_asn.to_u32()));
+                peer_collectors.push(collector);
+                peers.len() as u32 - 1
+            });
+        if let Some(id) = view.peer_id() {
+            let id = id.0 as usize;
+            if self.by_id.len() <= id {
+                self.by_id.resize(id + 1, u32::MAX);
+            }
+            self.by_id[id] = index;
+        }
+        index
+    }
+
+    /// Set the collector of a new RIB and forget the pipeline peer IDs, which are assigned anew for
+    /// each RIB. Peer indexes are kept.
+    pub fn reset(&mut self, collector: &str) {
+        self.set_collector(collector);
+        self.clear_peer_ids();
+    }
+
+    /// Forget the pipeline peer IDs, which are assigned anew for each RIB. Peer indexes are kept.
+    pub fn clear_peer_ids(&mut self) {
+        self.by_id.clear();
+    }
+
+    /// IP and ASN of the peer with the given index.
+    pub fn get(&self, index: u32) -> Option<(IpAddr, u32)> {
+        self.peers.get(index as usize).copied()
+    }
+
+    /// Collector of the peer with the given index, none if it was indexed before any collector was
+    /// set.
+    pub fn collector(&self, index: u32) -> Option<&str> {
+        let collector = *self.peer_collectors.get(index as usize)?;
+        self.collectors[collector].as_deref()
+    }
+
+    pub fn len(&self) -> usize {
+        self.peers.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.peers.is_empty()
+    }
+}
+
+/// Per-prefix state of each peer, keyed by [PeerIndex] indexes.
+///
+/// Holds one value per peer and prefix: a repeated entry of a peer for a prefix replaces its
+/// value. Values of a prefix are kept in order of first sight of their peer.
+#[derive(Debug, Clone)]
+pub struct PrefixPeerState<T> {
+    prefixes: HashMap<IpNet, Vec<(u32, T)>>,
+}
+
+impl<T> Default for PrefixPeerState<T> {
+    fn default() -> Self {
+        PrefixPeerState {
+            prefixes: HashMap::new(),
+        }
+    }
+}
+
+impl<T> PrefixPeerState<T> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the value of a peer for a prefix, returning its previous value.
+    pub fn insert(&mut self, prefix: IpNet, peer: u32, value: T) -> Option<T> {
+        let values = self.prefixes.entry(prefix).or_default();
+        match values.iter_mut().find(|(p, _)| *p == peer) {
+            Some((_, existing)) => Some(std::mem::replace(existing, value)),
+            None => {
+                values.push((peer, value));
+                None
+            }
+        }
+    }
+
+    /// Values of each peer for a prefix.
+    pub fn get(&self, prefix: &IpNet) -> &[(u32, T)] {
+        self.prefixes
+            .get(prefix)
+            .map(|v| v.as_slice())
+            .unwrap_or(&[])
+    }
+
+    /// Prefixes with their values of each peer, in arbitrary order.
+    pub fn iter(&self) -> impl Iterator<Item = (&IpNet, &[(u32, T)])> {
+        self.prefixes.iter().map(|(p, v)| (p, v.as_slice()))
+    }
+
+    /// Number of prefixes.
+    pub fn len(&self) -> usize {
+        self.prefixes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.prefixes.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.prefixes.clear();
+    }
+}
@@ -1,40 +1,84 @@
-use crate::processors::meta::{
-    get_default_output_path, get_latest_output_path, ProcessorMeta, RibMeta,
-};
-use crate::processors::write_output_file;
-use crate::MessageProcessor;
-use bgpkit_parser::models::ElemType;
+use crate::processors::merge::{merge_outputs, rib_dump_urls, Mergeable};
+use crate::processors::meta::{ProcessorMeta, RibMeta};
+use crate::processors::peers::{PeerId, PeerTracker};
+use crate::processors::shared::SharedInput;
+use crate::processors::typed::process_entry_view;
+use crate::processors::TypedProcessor;
+use crate::util::{format_count, ElemView, RoutePolicy};
 use bgpkit_parser::BgpElem;
+use ipnet::IpNet;
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
-use serde_json::json;
-use std::collections::HashMap;
-use tracing::{info, warn};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::net::IpAddr;
+use std::str::FromStr;
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct Prefix2AsCount {
     pub prefix: String,
     pub asn: u32,
+    /// number of RIB entries, i.e. peers, the prefix-origin pair was observed in
     pub count: usize,
+    /// peers that observed the prefix with this origin, only recorded with
+    /// [Prefix2AsProcessor::with_peers]
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub peers: Vec<Prefix2AsPeer>,
+    /// number of collectors that observed the prefix-origin pair, only set in summaries
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub collectors_count: Option<usize>,
+    /// sum over collectors of the share of each collector's peers that observed the prefix-origin
+    /// pair, only set in summaries; unlike `count`, not dominated by collectors with many peers
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub weighted_count: Option<f64>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// Route collector peer that observed a prefix-origin pair.
+#[derive(
+    Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize, JsonSchema,
+)]
+pub struct Prefix2AsPeer {
+    pub collector: Option<String>,
+    pub ip: IpAddr,
+    pub asn: u32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct Prefix2AsCollectorJson {
     pub project: String,
     pub collector: String,
     pub rib_dump_url: String,
+    /// number of peers with announcements in the RIB
+    #[serde(default)]
+    pub peers_count: usize,
     pub pfx2as: Vec<Prefix2AsCount>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct Prefix2AsSummaryJson {
-    rib_dump_urls: Vec<String>,
-    pfx2as: Vec<Prefix2AsCount>,
+    pub rib_dump_urls: Vec<String>,
+    pub pfx2as: Vec<Prefix2AsCount>,
+    /// whether prefixes are aggregated, see [aggregate_pfx2as]
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub aggregated: bool,
 }
 
 pub struct Prefix2AsProcessor {
     rib_meta: Option<RibMeta>,
     processor_meta: ProcessorMeta,
     pfx2as_map: HashMap<(String, u32), u32>,
+    /// peers with announcements
+    announcing_peers: HashSet<(IpAddr, u32)>,
+    /// aggregate prefixes of the summary
+    aggregate: bool,
+    /// peers observing each prefix-origin pair, only tracked with [Self::with_peers]
+    pfx2as_peers: Option<PeerObservations>,
+}
+
+/// Peers that observed each prefix-origin pair.
+#[derive(Default)]
+struct PeerObservations {
+    tracker: PeerTracker,
+    peers: HashMap<(String, u32), HashSet<PeerId>>,
 }
 
 impl Prefix2AsProcessor {
@@ -48,127 +92,375 @@ impl Prefix2AsProcessor {
             rib_meta: None,
             processor_meta,
             pfx2as_map: HashMap::new(),
+            announcing_peers: HashSet::new(),
+            aggregate: false,
+            pfx2as_peers: None,
         }
     }
 
+    /// Aggregate prefixes with identical origins in the summary, see [aggregate_pfx2as].
+    pub fn with_aggregation(mut self, aggregate: bool) -> Self {
+        self.aggregate = aggregate;
+        self
+    }
+
+    /// Record the peers that observed each prefix-origin pair, e.g. to find peers that disagree
+    /// about origins. Increases memory usage considerably.
+    pub fn with_peers(mut self, peers: bool) -> Self {
+        self.pfx2as_peers = match peers {
+            true => Some(PeerObservations::default()),
+            false => None,
+        };
+        self
+    }
+
+    /// Peers that observed a prefix-origin pair, sorted.
+    fn get_peers(&self, key: &(String, u32)) -> Vec<Prefix2AsPeer> {
+        let observations = match &self.pfx2as_peers {
+            None => return vec![],
+            Some(o) => o,
+        };
+        let mut peers: Vec<Prefix2AsPeer> = observations
+            .peers
+            .get(key)
+            .into_iter()
+            .flatten()
+            .filter_map(|id| observations.tracker.get(*id))
+            .map(|peer| Prefix2AsPeer {
+                collector: peer.collector.clone(),
+                ip: peer.ip,
+                asn: peer.asn,
+            })
+            .collect();
+        peers.sort();
+        peers
+    }
+
     pub fn get_count_vec(&self) -> Vec<Prefix2AsCount> {
         let res: Vec<Prefix2AsCount> = self
             .pfx2as_map
             .iter()
-            .map(|((prefix, asn), count)| Prefix2AsCount {
-                prefix: prefix.clone(),
-                asn: *asn,
+            .map(|(key, count)| Prefix2AsCount {
+                prefix: key.0.clone(),
+                asn: key.1,
                 count: *count as usize,
+                peers: self.get_peers(key),
+                collectors_count: None,
+                weighted_count: None,
             })
             .collect();
         res
     }
 }
 
-impl MessageProcessor for Prefix2AsProcessor {
-    fn name(&self) -> String {
-        self.processor_meta.name.clone()
+impl TypedProcessor for Prefix2AsProcessor {
+    type Output = Prefix2AsCollectorJson;
+    type Summary = Prefix2AsSummaryJson;
+    const DATASET_FIELD: &'static str = "pfx2as";
+
+    fn processor_meta(&self) -> &ProcessorMeta {
+        &self.processor_meta
+    }
+
+    fn rib_meta(&self) -> Option<&RibMeta> {
+        self.rib_meta.as_ref()
     }
 
-    fn output_paths(&self) -> Option<Vec<String>> {
-        Some(vec![
-            get_default_output_path(self.rib_meta.as_ref().unwrap(), &self.processor_meta),
-            get_latest_output_path(self.rib_meta.as_ref().unwrap(), &self.processor_meta),
-        ])
+    fn new_instance(&self) -> Self {
+        Self::new(self.processor_meta.output_dir.as_str())
+            .with_aggregation(self.aggregate)
+            .with_peers(self.pfx2as_peers.is_some())
     }
 
     fn reset_processor(&mut self, rib_meta: &RibMeta) {
         self.rib_meta = Some(rib_meta.clone());
+        if let Some(observations) = self.pfx2as_peers.as_mut() {
+            observations
+                .tracker
+                .set_collector(Some(rib_meta.collector.clone()));
+        }
+    }
+
+    fn route_policy(&self) -> RoutePolicy {
+        RoutePolicy::new().with_skip_default(true)
+    }
+
+    fn shared_inputs(&self) -> Vec<SharedInput> {
+        vec![SharedInput::StrippedPath]
     }
 
     fn process_entry(&mut self, elem: &BgpElem) -> anyhow::Result<()> {
-        if elem.elem_type != ElemType::ANNOUNCE {
-            // skip processing non-announce messages
-            return Ok(());
-        }
+        process_entry_view(self, elem)
+    }
 
-        // skip default route
-        if elem.prefix.prefix.prefix_len() == 0 {
+    fn process_view(&mut self, view: &ElemView) -> anyhow::Result<()> {
+        // skip processing non-announce messages, default routes are skipped by the route policy
+        if !view.is_announce() {
             return Ok(());
         }
 
-        if let Some(path) = &elem.as_path {
-            if let Some(p) = path.to_u32_vec_opt(false) {
-                if let Some(origin) = p.last() {
-                    let prefix = elem.prefix.to_string();
-                    let count = self.pfx2as_map.entry((prefix, *origin)).or_insert(0);
-                    *count += 1;
-                }
+        if let Some(origin) = view.origin() {
+            let elem = view.elem();
+            let key = (elem.prefix.to_string(), origin);
+            if let Some(observations) = self.pfx2as_peers.as_mut() {
+                let peer_id = observations.tracker.observe(elem);
+                observations
+                    .peers
+                    .entry(key.clone())
+                    .or_default()
+                    .insert(peer_id);
             }
+            let count = self.pfx2as_map.entry(key).or_insert(0);
+            *count += 1;
+            self.announcing_peers
+                .insert((elem.peer_ip, elem.peer_asn.to_u32()));
         }
 
         Ok(())
     }
 
-    fn to_result_string(&self) -> Option<String> {
-        let rib_meta = self.rib_meta.as_ref().unwrap();
-        let value = json!(Prefix2AsCollectorJson {
+    fn brief_summary(&self) -> String {
+        let prefixes: HashSet<&String> = self.pfx2as_map.keys().map(|(prefix, _)| prefix).collect();
+        let mut origins: HashMap<u32, usize> = HashMap::new();
+        for (_, origin) in self.pfx2as_map.keys() {
+            *origins.entry(*origin).or_default() += 1;
+        }
+        let mut summary = format!(
+            "{}: {} prefixes, {} origins",
+            self.processor_meta.name,
+            format_count(prefixes.len()),
+            format_count(origins.len())
+        );
+        if let Some((origin, count)) = origins
+            .iter()
+            .max_by_key(|(origin, count)| (**count, std::cmp::Reverse(**origin)))
+        {
+            summary.push_str(
+                format!(
+                    ", top origin AS{} ({} prefixes)",
+                    origin,
+                    format_count(*count)
+                )
+                .as_str(),
+            );
+        }
+        summary
+    }
+
+    fn to_output(&self) -> Option<Prefix2AsCollectorJson> {
+        let rib_meta = self.rib_meta.as_ref()?;
+        Some(Prefix2AsCollectorJson {
             project: rib_meta.project.clone(),
             collector: rib_meta.collector.clone(),
             rib_dump_url: rib_meta.rib_dump_url.clone(),
+            peers_count: self.announcing_peers.len(),
             pfx2as: self.get_count_vec(),
-        });
-
-        serde_json::to_string_pretty(&value).ok()
-    }
-
-    fn summarize_latest(&self, rib_metas: &[RibMeta], ignore_error: bool) -> anyhow::Result<()> {
-        let mut pfx2as_map = HashMap::<(String, u32), u32>::new();
-
-        for rib_meta in rib_metas {
-            let latest_file_path = get_latest_output_path(rib_meta, &self.processor_meta);
-            info!("summarizing {}...", latest_file_path.as_str());
-            let data = match oneio::read_json_struct::<Prefix2AsCollectorJson>(
-                latest_file_path.as_str(),
-            ) {
-                Ok(d) => d,
-                Err(e) => {
-                    if ignore_error {
-                        warn!("failed to read {}, skipping...", latest_file_path.as_str());
-                        continue;
-                    } else {
-                        return Err(anyhow::anyhow!(
-                            "failed to read {}: {}",
-                            latest_file_path.as_str(),
-                            e
-                        ));
-                    }
-                }
-            };
+        })
+    }
+
+    fn summarize(
+        &self,
+        rib_metas: &[RibMeta],
+        outputs: Vec<(RibMeta, Prefix2AsCollectorJson)>,
+    ) -> anyhow::Result<Prefix2AsSummaryJson> {
+        let mut summary: Prefix2AsSummaryJson = merge_outputs(
+            rib_metas,
+            outputs
+                .into_iter()
+                .map(|(_, output)| with_collector_weights(output)),
+        );
+        for entry in summary.pfx2as.iter_mut() {
+            entry.weighted_count = entry
+                .weighted_count
+                .map(|w| (w * 10000.0).round() / 10000.0);
+        }
+        if self.aggregate {
+            summary.pfx2as = aggregate_pfx2as(summary.pfx2as);
+            summary.aggregated = true;
+        }
+        Ok(summary)
+    }
+}
 
-            for entry in data.pfx2as {
-                let count = pfx2as_map.entry((entry.prefix, entry.asn)).or_insert(0);
-                *count += entry.count as u32;
+/// Set the per-collector visibility of each entry of a collector output, to be summed up by the
+/// summary merge.
+///
+/// Outputs written before peers were counted weigh entries by the largest count in the output,
+/// which approximates the number of full-feed peers.
+fn with_collector_weights(mut output: Prefix2AsCollectorJson) -> Prefix2AsCollectorJson {
+    let peers = match output.peers_count {
+        0 => output.pfx2as.iter().map(|e| e.count).max().unwrap_or(1),
+        n => n,
+    };
+    for entry in output.pfx2as.iter_mut() {
+        entry.collectors_count = Some(1);
+        entry.weighted_count = Some(entry.count as f64 / peers as f64);
+    }
+    output
+}
+
+/// Sum of two optional figures, set if either is set.
+fn add_opt<T: std::ops::Add<Output = T>>(a: Option<T>, b: Option<T>) -> Option<T> {
+    match (a, b) {
+        (Some(a), Some(b)) => Some(a + b),
+        (a, b) => a.or(b),
+    }
+}
+
+/// Aggregate pfx2as entries with identical origin sets, for longest-prefix-match lookups.
+///
+/// Prefixes are only aggregated where longest-prefix matching yields the same origins as before:
+/// - a prefix is dropped if its closest covering prefix has the same origins;
+/// - two sibling prefixes with the same origins are replaced by their parent prefix, unless the
+///   parent is announced with other origins.
+///
+/// The counts of an aggregated entry are the largest counts among its components, and its peers
+/// are the union of theirs. Entries with invalid prefixes are kept as they are.
+pub fn aggregate_pfx2as(entries: Vec<Prefix2AsCount>) -> Vec<Prefix2AsCount> {
+    let mut result = vec![];
+    let mut origins: BTreeMap<IpNet, BTreeMap<u32, OriginStats>> = BTreeMap::new();
+    for entry in entries {
+        match IpNet::from_str(entry.prefix.as_str()) {
+            Ok(prefix) => {
+                let stats = origins
+                    .entry(prefix.trunc())
+                    .or_default()
+                    .entry(entry.asn)
+                    .or_default();
+                stats.count += entry.count;
+                stats.peers.extend(entry.peers);
+                stats.collectors_count = add_opt(stats.collectors_count, entry.collectors_count);
+                stats.weighted_count = add_opt(stats.weighted_count, entry.weighted_count);
             }
+            Err(_) => result.push(entry),
         }
-        let json_data = Prefix2AsSummaryJson {
-            rib_dump_urls: rib_metas
-                .iter()
-                .map(|rib_meta| rib_meta.rib_dump_url.clone())
-                .collect(),
-            pfx2as: pfx2as_map
-                .iter()
-                .map(|((prefix, asn), count)| Prefix2AsCount {
-                    prefix: prefix.clone(),
-                    asn: *asn,
-                    count: *count as usize,
-                })
-                .collect(),
+    }
+
+    // drop covered prefixes, most-specific first so that counts propagate to the remaining cover
+    let mut covered: Vec<IpNet> = origins
+        .iter()
+        .filter(|(prefix, counts)| {
+            closest_cover(&origins, prefix)
+                .is_some_and(|cover| same_origins(&origins[&cover], counts))
+        })
+        .map(|(prefix, _)| *prefix)
+        .collect();
+    covered.sort_by_key(|prefix| std::cmp::Reverse(prefix.prefix_len()));
+    for prefix in covered {
+        let counts = origins.remove(&prefix).unwrap();
+        let cover = closest_cover(&origins, &prefix).unwrap();
+        merge_origins(origins.get_mut(&cover).unwrap(), counts);
+    }
+
+    // merge siblings bottom-up, so that merged parents can be merged again
+    for prefix_len in (1..=128u8).rev() {
+        let candidates: Vec<IpNet> = origins
+            .keys()
+            .filter(|prefix| prefix.prefix_len() == prefix_len)
+            .copied()
+            .collect();
+        for prefix in candidates {
+            let parent = match prefix.supernet() {
+                Some(parent) if !origins.contains_key(&parent) => parent,
+                _ => continue,
+            };
+            let sibling = match parent.subnets(prefix_len) {
+                Ok(mut subnets) => subnets.find(|p| *p != prefix).unwrap(),
+                Err(_) => continue,
+            };
+            let mergeable = match (origins.get(&prefix), origins.get(&sibling)) {
+                (Some(a), Some(b)) => same_origins(a, b),
+                _ => false,
+            };
+            if mergeable {
+                let mut counts = origins.remove(&prefix).unwrap();
+                merge_origins(&mut counts, origins.remove(&sibling).unwrap());
+                origins.insert(parent, counts);
+            }
+        }
+    }
+
+    for (prefix, counts) in origins {
+        result.extend(counts.into_iter().map(|(asn, stats)| Prefix2AsCount {
+            prefix: prefix.to_string(),
+            asn,
+            count: stats.count,
+            peers: stats.peers.into_iter().collect(),
+            collectors_count: stats.collectors_count,
+            weighted_count: stats.weighted_count,
+        }));
+    }
+    result
+}
+
+/// Closest less-specific prefix of `prefix` in `origins`.
+fn closest_cover<V>(origins: &BTreeMap<IpNet, V>, prefix: &IpNet) -> Option<IpNet> {
+    let mut net = prefix.supernet();
+    while let Some(n) = net {
+        if origins.contains_key(&n) {
+            return Some(n);
+        }
+        net = n.supernet();
+    }
+    None
+}
+
+/// Counts and peers of an origin of an aggregated prefix.
+#[derive(Default)]
+struct OriginStats {
+    count: usize,
+    peers: BTreeSet<Prefix2AsPeer>,
+    collectors_count: Option<usize>,
+    weighted_count: Option<f64>,
+}
+
+fn same_origins(a: &BTreeMap<u32, OriginStats>, b: &BTreeMap<u32, OriginStats>) -> bool {
+    a.keys().eq(b.keys())
+}
+
+/// Merge per-origin stats, keeping the largest counts and all peers of each origin.
+fn merge_origins(origins: &mut BTreeMap<u32, OriginStats>, other: BTreeMap<u32, OriginStats>) {
+    for (asn, stats) in other {
+        let entry = origins.entry(asn).or_default();
+        entry.count = entry.count.max(stats.count);
+        entry.peers.extend(stats.peers);
+        entry.collectors_count = entry.collectors_count.max(stats.collectors_count);
+        entry.weighted_count = match (entry.weighted_count, stats.weighted_count) {
+            (Some(a), Some(b)) => Some(a.max(b)),
+            (a, b) => a.or(b),
         };
+    }
+}
 
-        let output_file_dir = format!(
-            "{}/{}",
-            self.processor_meta.output_dir.as_str(),
-            self.processor_meta.name.as_str(),
-        );
-        let output_content = serde_json::to_string_pretty(&json_data)?;
-        write_output_file(output_file_dir.as_str(), output_content.as_str(), true)?;
+impl Mergeable for Prefix2AsCollectorJson {
+    type Entry = Prefix2AsCount;
+    type Key = (String, u32);
+    type Merged = Prefix2AsSummaryJson;
 
-        Ok(())
+    fn into_entries(self) -> Vec<Prefix2AsCount> {
+        self.pfx2as
+    }
+
+    fn entry_key(entry: &Prefix2AsCount) -> (String, u32) {
+        (entry.prefix.clone(), entry.asn)
+    }
+
+    fn merge_entry(entry: &mut Prefix2AsCount, other: Prefix2AsCount) {
+        entry.count += other.count;
+        entry.collectors_count = add_opt(entry.collectors_count, other.collectors_count);
+        entry.weighted_count = add_opt(entry.weighted_count, other.weighted_count);
+        if !other.peers.is_empty() {
+            entry.peers.extend(other.peers);
+            entry.peers.sort();
+            entry.peers.dedup();
+        }
+    }
+
+    fn merge_files(rib_metas: &[RibMeta], entries: Vec<Prefix2AsCount>) -> Prefix2AsSummaryJson {
+        Prefix2AsSummaryJson {
+            rib_dump_urls: rib_dump_urls(rib_metas),
+            pfx2as: entries,
+            aggregated: false,
+        }
     }
 }
@@ -1,27 +1,27 @@
 //! prefix-to-distnace processor
 //!
 //! This processor is used to calculate the distance of each prefix to the collector AS.
-use crate::processors::meta::{
-    get_default_output_path, get_latest_output_path, ProcessorMeta, RibMeta,
-};
-use crate::processors::write_output_file;
-use crate::MessageProcessor;
-use bgpkit_parser::models::ElemType;
+use crate::processors::merge::{rib_dump_urls, Mergeable};
+use crate::processors::meta::{ProcessorMeta, RibMeta};
+use crate::processors::shared::SharedInput;
+use crate::processors::typed::process_entry_view;
+use crate::processors::TypedProcessor;
+use crate::util::{format_count, ElemView, RoutePolicy};
 use bgpkit_parser::BgpElem;
 use ipnet::IpNet;
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
-use serde_json::json;
 use std::collections::HashMap;
-use tracing::{info, warn};
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct Prefix2Dist {
+    #[schemars(with = "String")]
     pub prefix: IpNet,
     pub collector_asn: u32,
     pub distance: u32,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct Prefix2DistCollectorJson {
     pub project: String,
     pub collector: String,
@@ -29,10 +29,10 @@ pub struct Prefix2DistCollectorJson {
     pub pfx2dist: Vec<Prefix2Dist>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct Prefix2DistSummaryJson {
-    rib_dump_urls: Vec<String>,
-    pfx2dist: Vec<Prefix2Dist>,
+    pub rib_dump_urls: Vec<String>,
+    pub pfx2dist: Vec<Prefix2Dist>,
 }
 
 pub struct Prefix2DistProcessor {
@@ -60,7 +60,7 @@ impl Prefix2DistProcessor {
             .pfx2dist_map
             .iter()
             .map(|((prefix, asn), count)| Prefix2Dist {
-                prefix: prefix.clone(),
+                prefix: *prefix,
                 collector_asn: *asn,
                 distance: *count,
             })
@@ -69,121 +69,101 @@ impl Prefix2DistProcessor {
     }
 }
 
-impl MessageProcessor for Prefix2DistProcessor {
-    fn name(&self) -> String {
-        self.processor_meta.name.clone()
+impl TypedProcessor for Prefix2DistProcessor {
+    type Output = Prefix2DistCollectorJson;
+    type Summary = Prefix2DistSummaryJson;
+    const DATASET_FIELD: &'static str = "pfx2dist";
+
+    fn processor_meta(&self) -> &ProcessorMeta {
+        &self.processor_meta
+    }
+
+    fn rib_meta(&self) -> Option<&RibMeta> {
+        self.rib_meta.as_ref()
     }
 
-    fn output_paths(&self) -> Option<Vec<String>> {
-        Some(vec![
-            get_default_output_path(self.rib_meta.as_ref().unwrap(), &self.processor_meta),
-            get_latest_output_path(self.rib_meta.as_ref().unwrap(), &self.processor_meta),
-        ])
+    fn new_instance(&self) -> Self {
+        Self::new(self.processor_meta.output_dir.as_str())
     }
 
     fn reset_processor(&mut self, rib_meta: &RibMeta) {
         self.rib_meta = Some(rib_meta.clone());
     }
 
+    fn route_policy(&self) -> RoutePolicy {
+        RoutePolicy::new().with_skip_default(true)
+    }
+
+    fn shared_inputs(&self) -> Vec<SharedInput> {
+        vec![SharedInput::StrippedPath]
+    }
+
     fn process_entry(&mut self, elem: &BgpElem) -> anyhow::Result<()> {
-        if elem.elem_type != ElemType::ANNOUNCE {
-            // skip processing non-announce messages
-            return Ok(());
-        }
+        process_entry_view(self, elem)
+    }
 
-        // skip default route
-        if elem.prefix.prefix.prefix_len() == 0 {
+    fn process_view(&mut self, view: &ElemView) -> anyhow::Result<()> {
+        // skip processing non-announce messages, default routes are skipped by the route policy
+        if !view.is_announce() {
             return Ok(());
         }
 
-        if let Some(path) = &elem.as_path {
-            if let Some(p) = path.to_u32_vec_opt(true) {
-                if let Some(collector) = p.first() {
-                    let prefix = elem.prefix.prefix;
-                    let distance = self
-                        .pfx2dist_map
-                        .entry((prefix, *collector))
-                        .or_insert(u32::MAX);
-                    if (p.len() as u32) < *distance {
-                        // if the distance is smaller, update it
-                        *distance = p.len() as u32;
-                    }
-                }
+        if let Some(path) = view.path() {
+            let prefix = view.elem().prefix.prefix;
+            let distance = self
+                .pfx2dist_map
+                .entry((prefix, path.first_hop()))
+                .or_insert(u32::MAX);
+            if (path.len() as u32) < *distance {
+                // if the distance is smaller, update it
+                *distance = path.len() as u32;
             }
         }
 
         Ok(())
     }
 
-    fn to_result_string(&self) -> Option<String> {
-        let rib_meta = self.rib_meta.as_ref().unwrap();
-        let value = json!(Prefix2DistCollectorJson {
+    fn brief_summary(&self) -> String {
+        format!(
+            "{}: {} prefix-origin pairs",
+            self.processor_meta.name,
+            format_count(self.pfx2dist_map.len())
+        )
+    }
+
+    fn to_output(&self) -> Option<Prefix2DistCollectorJson> {
+        let rib_meta = self.rib_meta.as_ref()?;
+        Some(Prefix2DistCollectorJson {
             project: rib_meta.project.clone(),
             collector: rib_meta.collector.clone(),
             rib_dump_url: rib_meta.rib_dump_url.clone(),
             pfx2dist: self.get_count_vec(),
-        });
-
-        serde_json::to_string_pretty(&value).ok()
-    }
-
-    fn summarize_latest(&self, rib_metas: &[RibMeta], ignore_error: bool) -> anyhow::Result<()> {
-        let mut pfx2dist_map = HashMap::<(IpNet, u32), u32>::new();
-
-        for rib_meta in rib_metas {
-            let latest_file_path = get_latest_output_path(rib_meta, &self.processor_meta);
-            info!("summarizing {}...", latest_file_path.as_str());
-            let data = match oneio::read_json_struct::<Prefix2DistCollectorJson>(
-                latest_file_path.as_str(),
-            ) {
-                Ok(d) => d,
-                Err(e) => {
-                    if ignore_error {
-                        warn!("failed to read {}, skipping...", latest_file_path.as_str());
-                        continue;
-                    } else {
-                        return Err(anyhow::anyhow!(
-                            "failed to read {}: {}",
-                            latest_file_path.as_str(),
-                            e
-                        ));
-                    }
-                }
-            };
-
-            for entry in data.pfx2dist {
-                let distance = pfx2dist_map
-                    .entry((entry.prefix, entry.collector_asn))
-                    .or_insert(u32::MAX);
-                if entry.distance < *distance {
-                    // if the distance is smaller, update it
-                    *distance = entry.distance;
-                }
-            }
-        }
-        let json_data = Prefix2DistSummaryJson {
-            rib_dump_urls: rib_metas
-                .iter()
-                .map(|rib_meta| rib_meta.rib_dump_url.clone())
-                .collect(),
-            pfx2dist: pfx2dist_map
-                .iter()
-                .map(|((prefix, asn), distance)| Prefix2Dist {
-                    prefix: prefix.clone(),
-                    collector_asn: *asn,
-                    distance: *distance,
-                })
-                .collect(),
-        };
+        })
+    }
+}
 
-        let output_file_dir = format!(
-            "{}/{}",
-            self.processor_meta.output_dir.as_str(),
-            self.processor_meta.name.as_str(),
-        );
-        let output_content = serde_json::to_string_pretty(&json_data)?;
-        write_output_file(output_file_dir.as_str(), output_content.as_str(), true)?;
+impl Mergeable for Prefix2DistCollectorJson {
+    type Entry = Prefix2Dist;
+    type Key = (IpNet, u32);
+    type Merged = Prefix2DistSummaryJson;
 
-        Ok(())
+    fn into_entries(self) -> Vec<Prefix2Dist> {
+        self.pfx2dist
+    }
+
+    fn entry_key(entry: &Prefix2Dist) -> (IpNet, u32) {
+        (entry.prefix, entry.collector_asn)
+    }
+
+    fn merge_entry(entry: &mut Prefix2Dist, other: Prefix2Dist) {
+        // keep the shortest distance
+        entry.distance = entry.distance.min(other.distance);
+    }
+
+    fn merge_files(rib_metas: &[RibMeta], entries: Vec<Prefix2Dist>) -> Prefix2DistSummaryJson {
+        Prefix2DistSummaryJson {
+            rib_dump_urls: rib_dump_urls(rib_metas),
+            pfx2dist: entries,
+        }
     }
 }
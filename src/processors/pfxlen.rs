@@ -0,0 +1,319 @@
+//! `pfxlen` processor generates the distribution of announced prefix lengths.
+//!
+//! Distinct prefixes are counted per prefix length, overall and per origin AS. IPv4 prefixes
+//! longer than /24 and IPv6 prefixes longer than /48 are flagged, as they are commonly filtered
+//! and often indicate leaks or misconfigurations.
+
+use crate::processors::merge::{merge_outputs, rib_dump_urls, Mergeable};
+use crate::processors::meta::{ProcessorMeta, RibMeta};
+use crate::processors::shared::SharedInput;
+use crate::processors::typed::process_entry_view;
+use crate::processors::TypedProcessor;
+use crate::util::{format_count, ElemView, RoutePolicy};
+use bgpkit_parser::BgpElem;
+use ipnet::IpNet;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+
+/// Longest IPv4 prefix length that is not flagged.
+pub const MAX_V4_PREFIX_LEN: u8 = 24;
+
+/// Longest IPv6 prefix length that is not flagged.
+pub const MAX_V6_PREFIX_LEN: u8 = 48;
+
+/// Number of distinct prefixes per prefix length.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct PrefixLenCounts {
+    pub v4: BTreeMap<u8, usize>,
+    pub v6: BTreeMap<u8, usize>,
+}
+
+impl PrefixLenCounts {
+    fn add(&mut self, prefix: &IpNet) {
+        let counts = match prefix {
+            IpNet::V4(_) => &mut self.v4,
+            IpNet::V6(_) => &mut self.v6,
+        };
+        *counts.entry(prefix.prefix_len()).or_insert(0) += 1;
+    }
+
+    /// Keep the larger count of each prefix length.
+    fn merge_max(&mut self, other: PrefixLenCounts) {
+        for (counts, other_counts) in [(&mut self.v4, other.v4), (&mut self.v6, other.v6)] {
+            for (len, count) in other_counts {
+                let entry = counts.entry(len).or_insert(0);
+                *entry = (*entry).max(count);
+            }
+        }
+    }
+
+    /// Number of IPv4 prefixes longer than /24.
+    pub fn v4_too_long(&self) -> usize {
+        self.v4.range(MAX_V4_PREFIX_LEN + 1..).map(|(_, c)| c).sum()
+    }
+
+    /// Number of IPv6 prefixes longer than /48.
+    pub fn v6_too_long(&self) -> usize {
+        self.v6.range(MAX_V6_PREFIX_LEN + 1..).map(|(_, c)| c).sum()
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct PrefixLenOriginEntry {
+    pub asn: u32,
+    pub lengths: PrefixLenCounts,
+    /// number of IPv4 prefixes longer than /24
+    pub v4_too_long: usize,
+    /// number of IPv6 prefixes longer than /48
+    pub v6_too_long: usize,
+}
+
+/// Flagged announcement of an IPv4 prefix longer than /24 or an IPv6 prefix longer than /48.
+#[derive(
+    Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize, JsonSchema,
+)]
+pub struct TooLongPrefix {
+    #[schemars(with = "String")]
+    pub prefix: IpNet,
+    pub asn: u32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct PrefixLenCollectorJson {
+    pub project: String,
+    pub collector: String,
+    pub rib_dump_url: String,
+    pub lengths: PrefixLenCounts,
+    pub origins: Vec<PrefixLenOriginEntry>,
+    pub too_long: Vec<TooLongPrefix>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct PrefixLenSummaryJson {
+    pub rib_dump_urls: Vec<String>,
+    /// largest count of each prefix length among collectors
+    pub lengths: PrefixLenCounts,
+    /// per-origin distributions, with the largest count of each prefix length among collectors
+    pub origins: Vec<PrefixLenOriginEntry>,
+    /// flagged announcements seen at any collector
+    pub too_long: Vec<TooLongPrefix>,
+}
+
+pub struct PrefixLenProcessor {
+    rib_meta: Option<RibMeta>,
+    processor_meta: ProcessorMeta,
+    /// distinct prefix-origin pairs
+    prefix_origins: HashSet<(IpNet, u32)>,
+}
+
+impl PrefixLenProcessor {
+    pub fn new(output_dir: &str) -> Self {
+        let processor_meta = ProcessorMeta {
+            name: "pfxlen".to_string(),
+            output_dir: output_dir.to_string(),
+        };
+
+        PrefixLenProcessor {
+            rib_meta: None,
+            processor_meta,
+            prefix_origins: HashSet::new(),
+        }
+    }
+
+    /// Distribution of distinct prefixes, regardless of origin.
+    pub fn get_lengths(&self) -> PrefixLenCounts {
+        let prefixes: HashSet<&IpNet> = self.prefix_origins.iter().map(|(p, _)| p).collect();
+        let mut counts = PrefixLenCounts::default();
+        for prefix in prefixes {
+            counts.add(prefix);
+        }
+        counts
+    }
+
+    pub fn get_origin_vec(&self) -> Vec<PrefixLenOriginEntry> {
+        let mut origins: HashMap<u32, PrefixLenCounts> = HashMap::new();
+        for (prefix, asn) in &self.prefix_origins {
+            origins.entry(*asn).or_default().add(prefix);
+        }
+        origins
+            .into_iter()
+            .map(|(asn, lengths)| new_origin_entry(asn, lengths))
+            .collect()
+    }
+
+    pub fn get_too_long_vec(&self) -> Vec<TooLongPrefix> {
+        self.prefix_origins
+            .iter()
+            .filter(|(prefix, _)| is_too_long(prefix))
+            .map(|(prefix, asn)| TooLongPrefix {
+                prefix: *prefix,
+                asn: *asn,
+            })
+            .collect()
+    }
+}
+
+fn new_origin_entry(asn: u32, lengths: PrefixLenCounts) -> PrefixLenOriginEntry {
+    PrefixLenOriginEntry {
+        asn,
+        v4_too_long: lengths.v4_too_long(),
+        v6_too_long: lengths.v6_too_long(),
+        lengths,
+    }
+}
+
+/// Check if a prefix is longer than /24 (IPv4) or /48 (IPv6).
+pub fn is_too_long(prefix: &IpNet) -> bool {
+    match prefix {
+        IpNet::V4(p) => p.prefix_len() > MAX_V4_PREFIX_LEN,
+        IpNet::V6(p) => p.prefix_len() > MAX_V6_PREFIX_LEN,
+    }
+}
+
+impl TypedProcessor for PrefixLenProcessor {
+    type Output = PrefixLenCollectorJson;
+    type Summary = PrefixLenSummaryJson;
+    const DATASET_FIELD: &'static str = "origins";
+
+    fn processor_meta(&self) -> &ProcessorMeta {
+        &self.processor_meta
+    }
+
+    fn rib_meta(&self) -> Option<&RibMeta> {
+        self.rib_meta.as_ref()
+    }
+
+    fn new_instance(&self) -> Self {
+        Self::new(self.processor_meta.output_dir.as_str())
+    }
+
+    fn reset_processor(&mut self, rib_meta: &RibMeta) {
+        self.rib_meta = Some(rib_meta.clone());
+    }
+
+    fn route_policy(&self) -> RoutePolicy {
+        RoutePolicy::new().with_skip_default(true)
+    }
+
+    fn shared_inputs(&self) -> Vec<SharedInput> {
+        vec![SharedInput::StrippedPath]
+    }
+
+    fn process_entry(&mut self, elem: &BgpElem) -> anyhow::Result<()> {
+        process_entry_view(self, elem)
+    }
+
+    fn process_view(&mut self, view: &ElemView) -> anyhow::Result<()> {
+        // skip processing non-announce messages, default routes are skipped by the route policy
+        if !view.is_announce() {
+            return Ok(());
+        }
+
+        if let Some(origin) = view.origin() {
+            self.prefix_origins
+                .insert((view.elem().prefix.prefix, origin));
+        }
+
+        Ok(())
+    }
+
+    fn process_batch(
+        &mut self,
+        views: &[ElemView],
+        _on_error: &mut dyn FnMut(anyhow::Error) -> anyhow::Result<()>,
+    ) -> anyhow::Result<()> {
+        self.prefix_origins.extend(
+            views
+                .iter()
+                .filter(|v| v.is_announce())
+                .filter_map(|v| Some((v.elem().prefix.prefix, v.origin()?))),
+        );
+        Ok(())
+    }
+
+    fn brief_summary(&self) -> String {
+        let mut lengths: HashMap<u8, usize> = HashMap::new();
+        for (prefix, _) in &self.prefix_origins {
+            *lengths.entry(prefix.prefix_len()).or_default() += 1;
+        }
+        let mut summary = format!(
+            "{}: {} prefix-origin pairs",
+            self.processor_meta.name,
+            format_count(self.prefix_origins.len())
+        );
+        if let Some((len, count)) = lengths
+            .iter()
+            .max_by_key(|(len, count)| (**count, std::cmp::Reverse(**len)))
+        {
+            summary.push_str(format!(", most common /{} ({})", len, format_count(*count)).as_str());
+        }
+        summary
+    }
+
+    fn to_output(&self) -> Option<PrefixLenCollectorJson> {
+        let rib_meta = self.rib_meta.as_ref()?;
+        Some(PrefixLenCollectorJson {
+            project: rib_meta.project.clone(),
+            collector: rib_meta.collector.clone(),
+            rib_dump_url: rib_meta.rib_dump_url.clone(),
+            lengths: self.get_lengths(),
+            origins: self.get_origin_vec(),
+            too_long: self.get_too_long_vec(),
+        })
+    }
+
+    fn summarize(
+        &self,
+        rib_metas: &[RibMeta],
+        outputs: Vec<(RibMeta, PrefixLenCollectorJson)>,
+    ) -> anyhow::Result<PrefixLenSummaryJson> {
+        // distinct prefixes are not known across collectors, so the overall distribution and the
+        // flagged prefixes are merged here, and the per-origin entries by the merge engine
+        let mut lengths = PrefixLenCounts::default();
+        let mut too_long = BTreeSet::new();
+        for (_, output) in &outputs {
+            lengths.merge_max(output.lengths.clone());
+            too_long.extend(output.too_long.iter().cloned());
+        }
+        let mut summary: PrefixLenSummaryJson =
+            merge_outputs(rib_metas, outputs.into_iter().map(|(_, output)| output));
+        summary.lengths = lengths;
+        summary.too_long = too_long.into_iter().collect();
+        Ok(summary)
+    }
+}
+
+impl Mergeable for PrefixLenCollectorJson {
+    type Entry = PrefixLenOriginEntry;
+    type Key = u32;
+    type Merged = PrefixLenSummaryJson;
+
+    fn into_entries(self) -> Vec<PrefixLenOriginEntry> {
+        self.origins
+    }
+
+    fn entry_key(entry: &PrefixLenOriginEntry) -> u32 {
+        entry.asn
+    }
+
+    fn merge_entry(entry: &mut PrefixLenOriginEntry, other: PrefixLenOriginEntry) {
+        let mut lengths = std::mem::take(&mut entry.lengths);
+        lengths.merge_max(other.lengths);
+        *entry = new_origin_entry(entry.asn, lengths);
+    }
+
+    /// Merge the per-origin entries; the overall distribution and flagged prefixes are filled in by
+    /// [PrefixLenProcessor]'s summarization.
+    fn merge_files(
+        rib_metas: &[RibMeta],
+        entries: Vec<PrefixLenOriginEntry>,
+    ) -> PrefixLenSummaryJson {
+        PrefixLenSummaryJson {
+            rib_dump_urls: rib_dump_urls(rib_metas),
+            lengths: PrefixLenCounts::default(),
+            origins: entries,
+            too_long: vec![],
+        }
+    }
+}
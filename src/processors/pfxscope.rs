@@ -0,0 +1,268 @@
+//! `pfxscope` processor computes the propagation scope of each prefix.
+//!
+//! For each announced prefix, the processor records the collectors and the number of peers it is
+//! visible at, and the registration countries of those peers' IP addresses, from RIR delegated
+//! statistics files (see [PrefixCountries]; without them, countries are left empty). Visibility
+//! is the share of all peers that see the prefix; prefixes visible to at most
+//! [SCOPED_VISIBILITY] of the peers are flagged as `scoped`, which covers regionally scoped
+//! announcements as well as selective leaks of more-specifics.
+
+use crate::processors::merge::{merge_outputs, rib_dump_urls, Mergeable};
+use crate::processors::meta::{ProcessorMeta, RibMeta};
+use crate::processors::peers::PeerIndex;
+use crate::processors::typed::process_entry_view;
+use crate::processors::TypedProcessor;
+use crate::util::{format_count, ElemView, PrefixCountries, RoutePolicy};
+use bgpkit_parser::BgpElem;
+use ipnet::IpNet;
+use roaring::RoaringBitmap;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, HashMap};
+use std::sync::Arc;
+
+/// Share of peers at or below which a prefix is flagged as scoped.
+pub const SCOPED_VISIBILITY: f64 = 0.1;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct PrefixScopeEntry {
+    #[schemars(with = "String")]
+    pub prefix: IpNet,
+    /// collectors the prefix is visible at
+    pub collectors: BTreeSet<String>,
+    /// number of peers the prefix is visible at
+    pub peers_count: usize,
+    /// registration countries of the IP addresses of those peers
+    pub countries: BTreeSet<String>,
+    /// share of all peers the prefix is visible at
+    pub visibility: f64,
+    /// visible to at most [SCOPED_VISIBILITY] of the peers
+    pub scoped: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct PrefixScopeCollectorJson {
+    pub project: String,
+    pub collector: String,
+    pub rib_dump_url: String,
+    /// number of peers announcing any prefix
+    pub peers_count: usize,
+    /// registration countries of the IP addresses of all peers
+    pub countries: BTreeSet<String>,
+    pub prefixes: Vec<PrefixScopeEntry>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct PrefixScopeSummaryJson {
+    pub rib_dump_urls: Vec<String>,
+    /// number of peers announcing any prefix, summed over collectors
+    pub peers_count: usize,
+    /// registration countries of the IP addresses of all peers
+    pub countries: BTreeSet<String>,
+    pub prefixes: Vec<PrefixScopeEntry>,
+}
+
+/// Set the visibility and scoped flag of entries from the total number of peers.
+fn set_visibility(entries: &mut [PrefixScopeEntry], peers_count: usize) {
+    for entry in entries {
+        entry.visibility = match peers_count {
+            0 => 0.0,
+            n => entry.peers_count as f64 / n as f64,
+        };
+        entry.scoped = entry.visibility <= SCOPED_VISIBILITY;
+    }
+}
+
+pub struct PrefixScopeProcessor {
+    rib_meta: Option<RibMeta>,
+    processor_meta: ProcessorMeta,
+    peers: PeerIndex,
+    /// indexes of the peers each prefix is visible at
+    prefixes: HashMap<IpNet, RoaringBitmap>,
+    prefix_countries: Arc<PrefixCountries>,
+}
+
+impl PrefixScopeProcessor {
+    pub fn new(output_dir: &str) -> Self {
+        let processor_meta = ProcessorMeta {
+            name: "pfxscope".to_string(),
+            output_dir: output_dir.to_string(),
+        };
+
+        PrefixScopeProcessor {
+            rib_meta: None,
+            processor_meta,
+            peers: PeerIndex::new(),
+            prefixes: HashMap::new(),
+            prefix_countries: Arc::new(PrefixCountries::new()),
+        }
+    }
+
+    /// Map peer IP addresses to the registration countries of these RIR delegated statistics.
+    pub fn with_prefix_countries(mut self, prefix_countries: Arc<PrefixCountries>) -> Self {
+        self.prefix_countries = prefix_countries;
+        self
+    }
+}
+
+impl TypedProcessor for PrefixScopeProcessor {
+    type Output = PrefixScopeCollectorJson;
+    type Summary = PrefixScopeSummaryJson;
+    const DATASET_FIELD: &'static str = "prefixes";
+
+    fn processor_meta(&self) -> &ProcessorMeta {
+        &self.processor_meta
+    }
+
+    fn rib_meta(&self) -> Option<&RibMeta> {
+        self.rib_meta.as_ref()
+    }
+
+    fn new_instance(&self) -> Self {
+        Self::new(self.processor_meta.output_dir.as_str())
+            .with_prefix_countries(self.prefix_countries.clone())
+    }
+
+    fn reset_processor(&mut self, rib_meta: &RibMeta) {
+        self.rib_meta = Some(rib_meta.clone());
+        self.peers.reset(rib_meta.collector.as_str());
+    }
+
+    fn route_policy(&self) -> RoutePolicy {
+        RoutePolicy::new().with_skip_default(true)
+    }
+
+    fn process_entry(&mut self, elem: &BgpElem) -> anyhow::Result<()> {
+        process_entry_view(self, elem)
+    }
+
+    fn process_view(&mut self, view: &ElemView) -> anyhow::Result<()> {
+        // skip processing non-announce messages, default routes are skipped by the route policy
+        if !view.is_announce() {
+            return Ok(());
+        }
+        let peer = self.peers.index(view);
+        self.prefixes
+            .entry(view.elem().prefix.prefix)
+            .or_default()
+            .insert(peer);
+        Ok(())
+    }
+
+    fn brief_summary(&self) -> String {
+        format!(
+            "{}: {} prefixes seen by {} peers",
+            self.processor_meta.name,
+            format_count(self.prefixes.len()),
+            format_count(self.peers.len())
+        )
+    }
+
+    fn to_output(&self) -> Option<PrefixScopeCollectorJson> {
+        let rib_meta = self.rib_meta.as_ref()?;
+        let peer_countries: Vec<BTreeSet<&str>> = (0..self.peers.len() as u32)
+            .filter_map(|index| self.peers.get(index))
+            .map(|(ip, _)| self.prefix_countries.countries(&IpNet::from(ip)))
+            .collect();
+
+        let mut prefixes: Vec<PrefixScopeEntry> = self
+            .prefixes
+            .iter()
+            .map(|(prefix, peers)| PrefixScopeEntry {
+                prefix: *prefix,
+                collectors: peers
+                    .iter()
+                    .map(|peer| {
+                        self.peers
+                            .collector(peer)
+                            .unwrap_or(rib_meta.collector.as_str())
+                            .to_string()
+                    })
+                    .collect(),
+                peers_count: peers.len() as usize,
+                countries: peers
+                    .iter()
+                    .flat_map(|peer| peer_countries[peer as usize].iter())
+                    .map(|country| country.to_string())
+                    .collect(),
+                visibility: 0.0,
+                scoped: false,
+            })
+            .collect();
+        prefixes.sort_by_key(|entry| entry.prefix);
+
+        // peers with a prefix other than a default route
+        let peers: RoaringBitmap = self
+            .prefixes
+            .values()
+            .fold(RoaringBitmap::new(), |a, b| a | b);
+        set_visibility(&mut prefixes, peers.len() as usize);
+        Some(PrefixScopeCollectorJson {
+            project: rib_meta.project.clone(),
+            collector: rib_meta.collector.clone(),
+            rib_dump_url: rib_meta.rib_dump_url.clone(),
+            peers_count: peers.len() as usize,
+            countries: peers
+                .iter()
+                .flat_map(|peer| peer_countries[peer as usize].iter())
+                .map(|country| country.to_string())
+                .collect(),
+            prefixes,
+        })
+    }
+
+    fn summarize(
+        &self,
+        rib_metas: &[RibMeta],
+        outputs: Vec<(RibMeta, PrefixScopeCollectorJson)>,
+    ) -> anyhow::Result<PrefixScopeSummaryJson> {
+        // the peers of all collectors are counted here, and the prefix entries merged by the merge
+        // engine
+        let peers_count = outputs.iter().map(|(_, o)| o.peers_count).sum();
+        let countries = outputs
+            .iter()
+            .flat_map(|(_, o)| o.countries.iter().cloned())
+            .collect();
+        let mut summary: PrefixScopeSummaryJson =
+            merge_outputs(rib_metas, outputs.into_iter().map(|(_, output)| output));
+        summary.prefixes.sort_by_key(|entry| entry.prefix);
+        set_visibility(&mut summary.prefixes, peers_count);
+        summary.peers_count = peers_count;
+        summary.countries = countries;
+        Ok(summary)
+    }
+}
+
+impl Mergeable for PrefixScopeCollectorJson {
+    type Entry = PrefixScopeEntry;
+    type Key = IpNet;
+    type Merged = PrefixScopeSummaryJson;
+
+    fn into_entries(self) -> Vec<PrefixScopeEntry> {
+        self.prefixes
+    }
+
+    fn entry_key(entry: &PrefixScopeEntry) -> IpNet {
+        entry.prefix
+    }
+
+    fn merge_entry(entry: &mut PrefixScopeEntry, other: PrefixScopeEntry) {
+        entry.collectors.extend(other.collectors);
+        entry.peers_count += other.peers_count;
+        entry.countries.extend(other.countries);
+    }
+
+    /// Merge the prefix entries; the peer count, countries and visibility are filled in by
+    /// [PrefixScopeProcessor]'s summarization.
+    fn merge_files(
+        rib_metas: &[RibMeta],
+        entries: Vec<PrefixScopeEntry>,
+    ) -> PrefixScopeSummaryJson {
+        PrefixScopeSummaryJson {
+            rib_dump_urls: rib_dump_urls(rib_metas),
+            peers_count: 0,
+            countries: BTreeSet::new(),
+            prefixes: entries,
+        }
+    }
+}
@@ -0,0 +1,381 @@
+//! Protocol Buffers encoding of the pfx2as and as2rel summaries.
+//!
+//! The messages are defined in `proto/ribeye.proto` ([PROTO_SCHEMA]); the summary types
+//! themselves implement [ProtoMessage], so Rust consumers decode `latest.pb` files into the same
+//! structs as the JSON summaries, and other languages generate their types from the schema.
+
+use crate::processors::schema::processor_schemas;
+use crate::processors::{
+    As2relEntry, As2relSummaryJson, Prefix2AsCount, Prefix2AsPeer, Prefix2AsSummaryJson,
+};
+use anyhow::{anyhow, Result};
+use serde_json::Value;
+use std::net::IpAddr;
+
+/// The `.proto` schema of the binary summaries.
+pub const PROTO_SCHEMA: &str = include_str!("../../proto/ribeye.proto");
+
+/// Processors whose summaries can be encoded, see [encode_summary].
+pub const PROTO_PROCESSORS: [&str; 2] = ["pfx2as", "as2rel"];
+
+/// A type encoded as a message of [PROTO_SCHEMA].
+pub trait ProtoMessage: Sized {
+    /// Append the encoded message to `buf`.
+    fn encode(&self, buf: &mut Vec<u8>);
+
+    /// Decode a message, skipping unknown fields.
+    fn decode(data: &[u8]) -> Result<Self>;
+
+    fn encode_to_vec(&self) -> Vec<u8> {
+        let mut buf = vec![];
+        self.encode(&mut buf);
+        buf
+    }
+}
+
+/// Encoded summary of a processor in [PROTO_PROCESSORS], none for other processors.
+pub fn encode_summary(processor: &str, summary: &Value) -> Result<Option<Vec<u8>>> {
+    let Some(schemas) = processor_schemas(processor) else {
+        return Ok(None);
+    };
+    let data = match schemas.processor.as_str() {
+        "pfx2as" => {
+            serde_json::from_value::<Prefix2AsSummaryJson>(summary.clone())?.encode_to_vec()
+        }
+        "as2rel" => serde_json::from_value::<As2relSummaryJson>(summary.clone())?.encode_to_vec(),
+        _ => return Ok(None),
+    };
+    Ok(Some(data))
+}
+
+/// Path of the binary summary next to its canonical path, e.g. `pfx2as/latest.pb` for
+/// `pfx2as/latest.json.bz2`.
+pub fn get_summary_proto_path(summary_path: &str) -> String {
+    let (dir, file) = summary_path.rsplit_once('/').unwrap_or(("", summary_path));
+    let stem = file.split('.').next().unwrap_or(file);
+    match dir.is_empty() {
+        true => format!("{}.pb", stem),
+        false => format!("{}/{}.pb", dir, stem),
+    }
+}
+
+const VARINT: u8 = 0;
+const FIXED64: u8 = 1;
+const LEN: u8 = 2;
+const FIXED32: u8 = 5;
+
+fn put_varint(buf: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        buf.push((value as u8) | 0x80);
+        value >>= 7;
+    }
+    buf.push(value as u8);
+}
+
+fn put_key(buf: &mut Vec<u8>, field: u32, wire_type: u8) {
+    put_varint(buf, ((field as u64) << 3) | wire_type as u64);
+}
+
+/// Unsigned integer field, omitted if zero unless `present`.
+fn put_uint(buf: &mut Vec<u8>, field: u32, value: u64, present: bool) {
+    if value != 0 || present {
+        put_key(buf, field, VARINT);
+        put_varint(buf, value);
+    }
+}
+
+/// Double field, omitted if zero unless `present`.
+fn put_double(buf: &mut Vec<u8>, field: u32, value: f64, present: bool) {
+    if value != 0.0 || present {
+        put_key(buf, field, FIXED64);
+        buf.extend_from_slice(&value.to_bits().to_le_bytes());
+    }
+}
+
+fn put_bytes(buf: &mut Vec<u8>, field: u32, data: &[u8]) {
+    put_key(buf, field, LEN);
+    put_varint(buf, data.len() as u64);
+    buf.extend_from_slice(data);
+}
+
+/// String field, omitted if empty unless `present`.
+fn put_string(buf: &mut Vec<u8>, field: u32, value: &str, present: bool) {
+    if !value.is_empty() || present {
+        put_bytes(buf, field, value.as_bytes());
+    }
+}
+
+fn put_message<M: ProtoMessage>(buf: &mut Vec<u8>, field: u32, message: &M) {
+    put_bytes(buf, field, message.encode_to_vec().as_slice());
+}
+
+/// Value of a decoded field.
+enum WireValue<'a> {
+    Varint(u64),
+    Fixed64(u64),
+    Bytes(&'a [u8]),
+    Fixed32,
+}
+
+impl<'a> WireValue<'a> {
+    fn uint(&self) -> Result<u64> {
+        match self {
+            WireValue::Varint(v) => Ok(*v),
+            _ => Err(anyhow!("expected a varint field")),
+        }
+    }
+
+    fn uint32(&self) -> Result<u32> {
+        Ok(u32::try_from(self.uint()?)?)
+    }
+
+    fn double(&self) -> Result<f64> {
+        match self {
+            WireValue::Fixed64(v) => Ok(f64::from_bits(*v)),
+            _ => Err(anyhow!("expected a double field")),
+        }
+    }
+
+    fn string(&self) -> Result<String> {
+        match self {
+            WireValue::Bytes(data) => Ok(std::str::from_utf8(data)?.to_string()),
+            _ => Err(anyhow!("expected a string field")),
+        }
+    }
+
+    fn message<M: ProtoMessage>(&self) -> Result<M> {
+        match self {
+            WireValue::Bytes(data) => M::decode(data),
+            _ => Err(anyhow!("expected a message field")),
+        }
+    }
+}
+
+/// Reader of the fields of an encoded message.
+struct ProtoReader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> ProtoReader<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        ProtoReader { data, pos: 0 }
+    }
+
+    fn varint(&mut self) -> Result<u64> {
+        let mut value = 0u64;
+        for shift in (0..64).step_by(7) {
+            let byte = *self
+                .data
+                .get(self.pos)
+                .ok_or_else(|| anyhow!("truncated varint"))?;
+            self.pos += 1;
+            value |= ((byte & 0x7f) as u64) << shift;
+            if byte < 0x80 {
+                return Ok(value);
+            }
+        }
+        Err(anyhow!("varint too long"))
+    }
+
+    fn take(&mut self, len: usize) -> Result<&'a [u8]> {
+        let end = self
+            .pos
+            .checked_add(len)
+            .filter(|end| *end <= self.data.len())
+            .ok_or_else(|| anyhow!("truncated field"))?;
+        let data = &self.data[self.pos..end];
+        self.pos = end;
+        Ok(data)
+    }
+
+    /// Next field number and value, none at the end of the message.
+    fn next_field(&mut self) -> Result<Option<(u32, WireValue<'a>)>> {
+        if self.pos >= self.data.len() {
+            return Ok(None);
+        }
+        let key = self.varint()?;
+        let field = u32::try_from(key >> 3)?;
+        let value = match (key & 0x7) as u8 {
+            VARINT => WireValue::Varint(self.varint()?),
+            FIXED64 => WireValue::Fixed64(u64::from_le_bytes(self.take(8)?.try_into().unwrap())),
+            LEN => {
+                let len = usize::try_from(self.varint()?)?;
+                WireValue::Bytes(self.take(len)?)
+            }
+            FIXED32 => {
+                self.take(4)?;
+                WireValue::Fixed32
+            }
+            wire_type => return Err(anyhow!("unsupported wire type {}", wire_type)),
+        };
+        Ok(Some((field, value)))
+    }
+}
+
+impl ProtoMessage for Prefix2AsPeer {
+    fn encode(&self, buf: &mut Vec<u8>) {
+        if let Some(collector) = &self.collector {
+            put_string(buf, 1, collector, true);
+        }
+        put_string(buf, 2, self.ip.to_string().as_str(), false);
+        put_uint(buf, 3, self.asn as u64, false);
+    }
+
+    fn decode(data: &[u8]) -> Result<Self> {
+        let (mut collector, mut ip, mut asn) = (None, None, 0);
+        let mut reader = ProtoReader::new(data);
+        while let Some((field, value)) = reader.next_field()? {
+            match field {
+                1 => collector = Some(value.string()?),
+                2 => ip = Some(value.string()?.parse::<IpAddr>()?),
+                3 => asn = value.uint32()?,
+                _ => {}
+            }
+        }
+        Ok(Prefix2AsPeer {
+            collector,
+            ip: ip.ok_or_else(|| anyhow!("peer without ip"))?,
+            asn,
+        })
+    }
+}
+
+impl ProtoMessage for Prefix2AsCount {
+    fn encode(&self, buf: &mut Vec<u8>) {
+        put_string(buf, 1, self.prefix.as_str(), false);
+        put_uint(buf, 2, self.asn as u64, false);
+        put_uint(buf, 3, self.count as u64, false);
+        for peer in &self.peers {
+            put_message(buf, 4, peer);
+        }
+        if let Some(collectors_count) = self.collectors_count {
+            put_uint(buf, 5, collectors_count as u64, true);
+        }
+        if let Some(weighted_count) = self.weighted_count {
+            put_double(buf, 6, weighted_count, true);
+        }
+    }
+
+    fn decode(data: &[u8]) -> Result<Self> {
+        let mut entry = Prefix2AsCount {
+            prefix: String::new(),
+            asn: 0,
+            count: 0,
+            peers: vec![],
+            collectors_count: None,
+            weighted_count: None,
+        };
+        let mut reader = ProtoReader::new(data);
+        while let Some((field, value)) = reader.next_field()? {
+            match field {
+                1 => entry.prefix = value.string()?,
+                2 => entry.asn = value.uint32()?,
+                3 => entry.count = usize::try_from(value.uint()?)?,
+                4 => entry.peers.push(value.message()?),
+                5 => entry.collectors_count = Some(usize::try_from(value.uint()?)?),
+                6 => entry.weighted_count = Some(value.double()?),
+                _ => {}
+            }
+        }
+        Ok(entry)
+    }
+}
+
+impl ProtoMessage for Prefix2AsSummaryJson {
+    fn encode(&self, buf: &mut Vec<u8>) {
+        for url in &self.rib_dump_urls {
+            put_string(buf, 1, url, true);
+        }
+        for entry in &self.pfx2as {
+            put_message(buf, 2, entry);
+        }
+        put_uint(buf, 3, self.aggregated as u64, false);
+    }
+
+    fn decode(data: &[u8]) -> Result<Self> {
+        let mut summary = Prefix2AsSummaryJson {
+            rib_dump_urls: vec![],
+            pfx2as: vec![],
+            aggregated: false,
+        };
+        let mut reader = ProtoReader::new(data);
+        while let Some((field, value)) = reader.next_field()? {
+            match field {
+                1 => summary.rib_dump_urls.push(value.string()?),
+                2 => summary.pfx2as.push(value.message()?),
+                3 => summary.aggregated = value.uint()? != 0,
+                _ => {}
+            }
+        }
+        Ok(summary)
+    }
+}
+
+impl ProtoMessage for As2relEntry {
+    fn encode(&self, buf: &mut Vec<u8>) {
+        put_uint(buf, 1, self.asn1 as u64, false);
+        put_uint(buf, 2, self.asn2 as u64, false);
+        put_uint(buf, 3, self.paths_count as u64, false);
+        put_uint(buf, 4, self.peers_count as u64, false);
+        put_uint(buf, 5, self.rel as u64, false);
+        put_uint(buf, 6, self.ixp as u64, false);
+        put_uint(buf, 7, self.reverse_paths_count as u64, false);
+        put_double(buf, 8, self.confidence, false);
+    }
+
+    fn decode(data: &[u8]) -> Result<Self> {
+        let mut entry = As2relEntry {
+            asn1: 0,
+            asn2: 0,
+            paths_count: 0,
+            peers_count: 0,
+            rel: 0,
+            ixp: false,
+            reverse_paths_count: 0,
+            confidence: 0.0,
+        };
+        let mut reader = ProtoReader::new(data);
+        while let Some((field, value)) = reader.next_field()? {
+            match field {
+                1 => entry.asn1 = value.uint32()?,
+                2 => entry.asn2 = value.uint32()?,
+                3 => entry.paths_count = usize::try_from(value.uint()?)?,
+                4 => entry.peers_count = usize::try_from(value.uint()?)?,
+                5 => entry.rel = u8::try_from(value.uint()?)?,
+                6 => entry.ixp = value.uint()? != 0,
+                7 => entry.reverse_paths_count = usize::try_from(value.uint()?)?,
+                8 => entry.confidence = value.double()?,
+                _ => {}
+            }
+        }
+        Ok(entry)
+    }
+}
+
+impl ProtoMessage for As2relSummaryJson {
+    fn encode(&self, buf: &mut Vec<u8>) {
+        for url in &self.rib_dump_urls {
+            put_string(buf, 1, url, true);
+        }
+        for entry in &self.as2rel {
+            put_message(buf, 2, entry);
+        }
+    }
+
+    fn decode(data: &[u8]) -> Result<Self> {
+        let mut summary = As2relSummaryJson {
+            rib_dump_urls: vec![],
+            as2rel: vec![],
+        };
+        let mut reader = ProtoReader::new(data);
+        while let Some((field, value)) = reader.next_field()? {
+            match field {
+                1 => summary.rib_dump_urls.push(value.string()?),
+                2 => summary.as2rel.push(value.message()?),
+                _ => {}
+            }
+        }
+        Ok(summary)
+    }
+}
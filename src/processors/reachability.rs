@@ -0,0 +1,279 @@
+//! `reachability` processor records which origin ASes are visible via which peers at which
+//! collectors.
+//!
+//! The output is a tripartite collector → peer → origin structure in a columnar layout: a table of
+//! peers, with the index of their collector, and a table of peer-origin edges referencing peers by
+//! row index, each column being a plain array. This keeps full-feed RIBs with millions of edges
+//! compact and loads directly into dataframes, e.g. for selecting vantage points that maximize
+//! origin coverage.
+//!
+//! Peers are indexed with a [PeerIndex], so peer IPs are only hashed on first sight of a peer.
+
+use crate::processors::merge::{rib_dump_urls, Mergeable};
+use crate::processors::meta::{ProcessorMeta, RibMeta};
+use crate::processors::peers::PeerIndex;
+use crate::processors::shared::SharedInput;
+use crate::processors::typed::process_entry_view;
+use crate::processors::TypedProcessor;
+use crate::util::{format_count, ElemView};
+use bgpkit_parser::BgpElem;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::net::IpAddr;
+
+/// Peers as columns, row `i` of each column describing the peer with index `i`.
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct ReachabilityPeers {
+    /// index of the peer's collector in `collectors`
+    pub collector: Vec<u32>,
+    pub ip: Vec<IpAddr>,
+    pub asn: Vec<u32>,
+}
+
+/// Peer-origin edges as columns, sorted by peer and origin.
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct ReachabilityEdges {
+    /// index of the peer in `peers`
+    pub peer: Vec<u32>,
+    pub origin: Vec<u32>,
+    /// number of prefixes of the origin observed via the peer
+    pub prefixes: Vec<u32>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct ReachabilityCollectorJson {
+    pub project: String,
+    pub collector: String,
+    pub rib_dump_url: String,
+    /// collectors referenced by `peers.collector`: the output's collector, or those of the RIBs
+    /// processed together
+    pub collectors: Vec<String>,
+    pub peers: ReachabilityPeers,
+    pub edges: ReachabilityEdges,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct ReachabilitySummaryJson {
+    pub rib_dump_urls: Vec<String>,
+    /// collectors referenced by `peers.collector`, sorted by name
+    pub collectors: Vec<String>,
+    pub peers: ReachabilityPeers,
+    pub edges: ReachabilityEdges,
+}
+
+/// A single peer-origin edge at a collector, the row form of the columnar outputs.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReachabilityRow {
+    pub collector: String,
+    pub peer_ip: IpAddr,
+    pub peer_asn: u32,
+    pub origin: u32,
+    pub prefixes: u32,
+}
+
+/// Expand columnar peers and edges into rows.
+fn to_rows(
+    collectors: &[String],
+    peers: &ReachabilityPeers,
+    edges: ReachabilityEdges,
+) -> Vec<ReachabilityRow> {
+    edges
+        .peer
+        .into_iter()
+        .zip(edges.origin)
+        .zip(edges.prefixes)
+        .map(|((peer, origin), prefixes)| {
+            let peer = peer as usize;
+            ReachabilityRow {
+                collector: collectors[peers.collector[peer] as usize].clone(),
+                peer_ip: peers.ip[peer],
+                peer_asn: peers.asn[peer],
+                origin,
+                prefixes,
+            }
+        })
+        .collect()
+}
+
+/// ASN and (origin, prefix count) edges of each peer IP, by collector.
+type GroupedRows<'a> = BTreeMap<&'a str, BTreeMap<IpAddr, (u32, Vec<(u32, u32)>)>>;
+
+/// Build columnar collectors, peers and edges from rows, ordered by collector, peer IP and origin.
+fn to_columns(rows: Vec<ReachabilityRow>) -> (Vec<String>, ReachabilityPeers, ReachabilityEdges) {
+    let mut grouped: GroupedRows = BTreeMap::new();
+    for row in &rows {
+        grouped
+            .entry(row.collector.as_str())
+            .or_default()
+            .entry(row.peer_ip)
+            .or_insert_with(|| (row.peer_asn, vec![]))
+            .1
+            .push((row.origin, row.prefixes));
+    }
+
+    let mut collectors = vec![];
+    let mut peers = ReachabilityPeers::default();
+    let mut edges = ReachabilityEdges::default();
+    for (collector, collector_peers) in grouped {
+        let collector_index = collectors.len() as u32;
+        collectors.push(collector.to_string());
+        for (ip, (asn, mut origins)) in collector_peers {
+            let peer_index = peers.ip.len() as u32;
+            peers.collector.push(collector_index);
+            peers.ip.push(ip);
+            peers.asn.push(asn);
+            origins.sort_unstable();
+            for (origin, prefixes) in origins {
+                edges.peer.push(peer_index);
+                edges.origin.push(origin);
+                edges.prefixes.push(prefixes);
+            }
+        }
+    }
+    (collectors, peers, edges)
+}
+
+pub struct ReachabilityProcessor {
+    rib_meta: Option<RibMeta>,
+    processor_meta: ProcessorMeta,
+    peers: PeerIndex,
+    /// number of prefixes by (peer index, origin)
+    edges: HashMap<(u32, u32), u32>,
+}
+
+impl ReachabilityProcessor {
+    pub fn new(output_dir: &str) -> Self {
+        let processor_meta = ProcessorMeta {
+            name: "reachability".to_string(),
+            output_dir: output_dir.to_string(),
+        };
+
+        ReachabilityProcessor {
+            rib_meta: None,
+            processor_meta,
+            peers: PeerIndex::new(),
+            edges: HashMap::new(),
+        }
+    }
+}
+
+impl TypedProcessor for ReachabilityProcessor {
+    type Output = ReachabilityCollectorJson;
+    type Summary = ReachabilitySummaryJson;
+    const DATASET_FIELD: &'static str = "collectors";
+
+    fn processor_meta(&self) -> &ProcessorMeta {
+        &self.processor_meta
+    }
+
+    fn rib_meta(&self) -> Option<&RibMeta> {
+        self.rib_meta.as_ref()
+    }
+
+    fn new_instance(&self) -> Self {
+        Self::new(self.processor_meta.output_dir.as_str())
+    }
+
+    fn reset_processor(&mut self, rib_meta: &RibMeta) {
+        self.rib_meta = Some(rib_meta.clone());
+        self.peers.reset(rib_meta.collector.as_str());
+    }
+
+    fn shared_inputs(&self) -> Vec<SharedInput> {
+        vec![SharedInput::StrippedPath]
+    }
+
+    fn process_entry(&mut self, elem: &BgpElem) -> anyhow::Result<()> {
+        process_entry_view(self, elem)
+    }
+
+    fn process_view(&mut self, view: &ElemView) -> anyhow::Result<()> {
+        if !view.is_announce() {
+            return Ok(());
+        }
+        let Some(origin) = view.origin() else {
+            return Ok(());
+        };
+        let peer = self.peers.index(view);
+        *self.edges.entry((peer, origin)).or_default() += 1;
+        Ok(())
+    }
+
+    fn brief_summary(&self) -> String {
+        let origins: HashSet<u32> = self.edges.keys().map(|(_, origin)| *origin).collect();
+        format!(
+            "{}: {} peers reaching {} origins",
+            self.processor_meta.name,
+            format_count(self.peers.len()),
+            format_count(origins.len())
+        )
+    }
+
+    fn to_output(&self) -> Option<ReachabilityCollectorJson> {
+        let rib_meta = self.rib_meta.as_ref()?;
+        let rows = self
+            .edges
+            .iter()
+            .filter_map(|((peer, origin), prefixes)| {
+                let (peer_ip, peer_asn) = self.peers.get(*peer)?;
+                let collector = self.peers.collector(*peer);
+                Some(ReachabilityRow {
+                    collector: collector.unwrap_or(rib_meta.collector.as_str()).to_string(),
+                    peer_ip,
+                    peer_asn,
+                    origin: *origin,
+                    prefixes: *prefixes,
+                })
+            })
+            .collect();
+        let (collectors, peers, edges) = to_columns(rows);
+        Some(ReachabilityCollectorJson {
+            project: rib_meta.project.clone(),
+            collector: rib_meta.collector.clone(),
+            rib_dump_url: rib_meta.rib_dump_url.clone(),
+            collectors,
+            peers,
+            edges,
+        })
+    }
+}
+
+impl ReachabilitySummaryJson {
+    /// Expand the summary into one row per peer-origin edge.
+    pub fn rows(&self) -> Vec<ReachabilityRow> {
+        to_rows(&self.collectors, &self.peers, self.edges.clone())
+    }
+}
+
+impl Mergeable for ReachabilityCollectorJson {
+    type Entry = ReachabilityRow;
+    type Key = (String, IpAddr, u32);
+    type Merged = ReachabilitySummaryJson;
+
+    fn into_entries(self) -> Vec<ReachabilityRow> {
+        to_rows(&self.collectors, &self.peers, self.edges)
+    }
+
+    fn entry_key(entry: &ReachabilityRow) -> (String, IpAddr, u32) {
+        (entry.collector.clone(), entry.peer_ip, entry.origin)
+    }
+
+    fn merge_entry(entry: &mut ReachabilityRow, other: ReachabilityRow) {
+        // outputs of the same collector are not merged in practice, keep the larger count
+        entry.prefixes = entry.prefixes.max(other.prefixes);
+    }
+
+    fn merge_files(
+        rib_metas: &[RibMeta],
+        entries: Vec<ReachabilityRow>,
+    ) -> ReachabilitySummaryJson {
+        let (collectors, peers, edges) = to_columns(entries);
+        ReachabilitySummaryJson {
+            rib_dump_urls: rib_dump_urls(rib_metas),
+            collectors,
+            peers,
+            edges,
+        }
+    }
+}
@@ -0,0 +1,163 @@
+//! Typed readers of processor outputs.
+//!
+//! Each built-in processor's per-collector outputs and summaries can be read into their output
+//! structs from local paths, URLs or object stores (S3, Google Cloud Storage, Azure Blob
+//! Storage), with the compression given by the file extension, e.g.
+//! `read_pfx2as_summary("https://example.com/ribeye/pfx2as/latest.json.bz2")`. JSON schemas of
+//! the same structs are available through [processor_schemas](crate::processors::schema::processor_schemas)
+//! and `ribeye schema`.
+
+use crate::processors::{
+    As2relCollectorJson, As2relProcessor, As2relSummaryJson, CommunitiesCollectorJson,
+    CommunitiesProcessor, CommunitiesSummaryJson, DefaultRouteCollectorJson, DefaultRouteProcessor,
+    DefaultRouteSummaryJson, DuplicatesCollectorJson, DuplicatesProcessor, DuplicatesSummaryJson,
+    FirstHopCollectorJson, FirstHopProcessor, FirstHopSummaryJson, FootprintCollectorJson,
+    FootprintProcessor, FootprintSummaryJson, PathDiversityCollectorJson, PathDiversityProcessor,
+    PathDiversitySummaryJson, PeerInfoCollectorJson, PeerInfoSummaryJson, PeerStatsProcessor,
+    Prefix2AsCollectorJson, Prefix2AsProcessor, Prefix2AsSummaryJson, Prefix2DistCollectorJson,
+    Prefix2DistProcessor, Prefix2DistSummaryJson, PrefixLenCollectorJson, PrefixLenProcessor,
+    PrefixLenSummaryJson, PrefixScopeCollectorJson, PrefixScopeProcessor, PrefixScopeSummaryJson,
+    ReachabilityCollectorJson, ReachabilityProcessor, ReachabilitySummaryJson, TypedProcessor,
+    VantageCollectorJson, VantageProcessor, VantageSummaryJson,
+};
+use anyhow::Result;
+
+/// Read a per-collector `peer-stats` output.
+pub fn read_peer_stats_output(path: &str) -> Result<PeerInfoCollectorJson> {
+    PeerStatsProcessor::read_output(path)
+}
+
+/// Read a `peer-stats` summary.
+pub fn read_peer_stats_summary(path: &str) -> Result<PeerInfoSummaryJson> {
+    PeerStatsProcessor::read_summary(path)
+}
+
+/// Read a per-collector `pfx2as` output.
+pub fn read_pfx2as_output(path: &str) -> Result<Prefix2AsCollectorJson> {
+    Prefix2AsProcessor::read_output(path)
+}
+
+/// Read a `pfx2as` summary.
+pub fn read_pfx2as_summary(path: &str) -> Result<Prefix2AsSummaryJson> {
+    Prefix2AsProcessor::read_summary(path)
+}
+
+/// Read a per-collector `as2rel` output.
+pub fn read_as2rel_output(path: &str) -> Result<As2relCollectorJson> {
+    As2relProcessor::read_output(path)
+}
+
+/// Read an `as2rel` summary.
+pub fn read_as2rel_summary(path: &str) -> Result<As2relSummaryJson> {
+    As2relProcessor::read_summary(path)
+}
+
+/// Read a per-collector `pfx2dist` output.
+pub fn read_pfx2dist_output(path: &str) -> Result<Prefix2DistCollectorJson> {
+    Prefix2DistProcessor::read_output(path)
+}
+
+/// Read a `pfx2dist` summary.
+pub fn read_pfx2dist_summary(path: &str) -> Result<Prefix2DistSummaryJson> {
+    Prefix2DistProcessor::read_summary(path)
+}
+
+/// Read a per-collector `pfxlen` output.
+pub fn read_pfxlen_output(path: &str) -> Result<PrefixLenCollectorJson> {
+    PrefixLenProcessor::read_output(path)
+}
+
+/// Read a `pfxlen` summary.
+pub fn read_pfxlen_summary(path: &str) -> Result<PrefixLenSummaryJson> {
+    PrefixLenProcessor::read_summary(path)
+}
+
+/// Read a per-collector `footprint` output.
+pub fn read_footprint_output(path: &str) -> Result<FootprintCollectorJson> {
+    FootprintProcessor::read_output(path)
+}
+
+/// Read a `footprint` summary.
+pub fn read_footprint_summary(path: &str) -> Result<FootprintSummaryJson> {
+    FootprintProcessor::read_summary(path)
+}
+
+/// Read a per-collector `reachability` output.
+pub fn read_reachability_output(path: &str) -> Result<ReachabilityCollectorJson> {
+    ReachabilityProcessor::read_output(path)
+}
+
+/// Read a `reachability` summary.
+pub fn read_reachability_summary(path: &str) -> Result<ReachabilitySummaryJson> {
+    ReachabilityProcessor::read_summary(path)
+}
+
+/// Read a per-collector `communities` output.
+pub fn read_communities_output(path: &str) -> Result<CommunitiesCollectorJson> {
+    CommunitiesProcessor::read_output(path)
+}
+
+/// Read a `communities` summary.
+pub fn read_communities_summary(path: &str) -> Result<CommunitiesSummaryJson> {
+    CommunitiesProcessor::read_summary(path)
+}
+
+/// Read a per-collector `pfxscope` output.
+pub fn read_pfxscope_output(path: &str) -> Result<PrefixScopeCollectorJson> {
+    PrefixScopeProcessor::read_output(path)
+}
+
+/// Read a `pfxscope` summary.
+pub fn read_pfxscope_summary(path: &str) -> Result<PrefixScopeSummaryJson> {
+    PrefixScopeProcessor::read_summary(path)
+}
+
+/// Read a per-collector `defaults` output.
+pub fn read_defaults_output(path: &str) -> Result<DefaultRouteCollectorJson> {
+    DefaultRouteProcessor::read_output(path)
+}
+
+/// Read a `defaults` summary.
+pub fn read_defaults_summary(path: &str) -> Result<DefaultRouteSummaryJson> {
+    DefaultRouteProcessor::read_summary(path)
+}
+
+/// Read a per-collector `duplicates` output.
+pub fn read_duplicates_output(path: &str) -> Result<DuplicatesCollectorJson> {
+    DuplicatesProcessor::read_output(path)
+}
+
+/// Read a `duplicates` summary.
+pub fn read_duplicates_summary(path: &str) -> Result<DuplicatesSummaryJson> {
+    DuplicatesProcessor::read_summary(path)
+}
+
+/// Read a per-collector `pathdiv` output.
+pub fn read_pathdiv_output(path: &str) -> Result<PathDiversityCollectorJson> {
+    PathDiversityProcessor::read_output(path)
+}
+
+/// Read a `pathdiv` summary.
+pub fn read_pathdiv_summary(path: &str) -> Result<PathDiversitySummaryJson> {
+    PathDiversityProcessor::read_summary(path)
+}
+
+/// Read a per-collector `vantage` output.
+pub fn read_vantage_output(path: &str) -> Result<VantageCollectorJson> {
+    VantageProcessor::read_output(path)
+}
+
+/// Read a `vantage` summary.
+pub fn read_vantage_summary(path: &str) -> Result<VantageSummaryJson> {
+    VantageProcessor::read_summary(path)
+}
+
+/// Read a per-collector `firsthop` output.
+pub fn read_firsthop_output(path: &str) -> Result<FirstHopCollectorJson> {
+    FirstHopProcessor::read_output(path)
+}
+
+/// Read a `firsthop` summary.
+pub fn read_firsthop_summary(path: &str) -> Result<FirstHopSummaryJson> {
+    FirstHopProcessor::read_summary(path)
+}
@@ -0,0 +1,263 @@
+//! JSON schemas of processor outputs.
+
+use crate::processors::{
+    As2relProcessor, CommunitiesProcessor, DefaultRouteProcessor, DuplicatesProcessor,
+    FirstHopProcessor, FootprintProcessor, PathDiversityProcessor, PeerStatsProcessor,
+    Prefix2AsProcessor, Prefix2DistProcessor, PrefixLenProcessor, PrefixScopeProcessor,
+    ReachabilityProcessor, TypedProcessor, VantageProcessor,
+};
+use crate::RibEye;
+use schemars::Schema;
+use serde::Serialize;
+use serde_json::Value;
+
+/// JSON schemas of the outputs of a processor.
+#[derive(Debug, Clone, Serialize)]
+pub struct ProcessorSchemas {
+    /// output name of the processor, e.g. `peer-stats` for `peer_stats`
+    pub processor: String,
+    /// array field holding the records, see [TypedProcessor::DATASET_FIELD]
+    pub dataset: String,
+    /// schema of the per-collector outputs
+    pub output: Schema,
+    /// schema of the summary
+    pub summary: Schema,
+}
+
+/// JSON schemas of a built-in processor by any name accepted by [RibEye::get_processor],
+/// including variants sharing their outputs' structure (e.g. `pfx2as-aggregated`).
+pub fn processor_schemas(processor_name: &str) -> Option<ProcessorSchemas> {
+    let processor = RibEye::get_processor(processor_name, "")?.name();
+    let (dataset, output, summary) = match processor.as_str() {
+        "peer-stats" => (
+            PeerStatsProcessor::DATASET_FIELD,
+            PeerStatsProcessor::output_schema(),
+            PeerStatsProcessor::summary_schema(),
+        ),
+        "pfx2as" => (
+            Prefix2AsProcessor::DATASET_FIELD,
+            Prefix2AsProcessor::output_schema(),
+            Prefix2AsProcessor::summary_schema(),
+        ),
+        "as2rel" => (
+            As2relProcessor::DATASET_FIELD,
+            As2relProcessor::output_schema(),
+            As2relProcessor::summary_schema(),
+        ),
+        "pfx2dist" => (
+            Prefix2DistProcessor::DATASET_FIELD,
+            Prefix2DistProcessor::output_schema(),
+            Prefix2DistProcessor::summary_schema(),
+        ),
+        "pfxlen" => (
+            PrefixLenProcessor::DATASET_FIELD,
+            PrefixLenProcessor::output_schema(),
+            PrefixLenProcessor::summary_schema(),
+        ),
+        "footprint" => (
+            FootprintProcessor::DATASET_FIELD,
+            FootprintProcessor::output_schema(),
+            FootprintProcessor::summary_schema(),
+        ),
+        "reachability" => (
+            ReachabilityProcessor::DATASET_FIELD,
+            ReachabilityProcessor::output_schema(),
+            ReachabilityProcessor::summary_schema(),
+        ),
+        "communities" => (
+            CommunitiesProcessor::DATASET_FIELD,
+            CommunitiesProcessor::output_schema(),
+            CommunitiesProcessor::summary_schema(),
+        ),
+        "pfxscope" => (
+            PrefixScopeProcessor::DATASET_FIELD,
+            PrefixScopeProcessor::output_schema(),
+            PrefixScopeProcessor::summary_schema(),
+        ),
+        "defaults" => (
+            DefaultRouteProcessor::DATASET_FIELD,
+            DefaultRouteProcessor::output_schema(),
+            DefaultRouteProcessor::summary_schema(),
+        ),
+        "duplicates" => (
+            DuplicatesProcessor::DATASET_FIELD,
+            DuplicatesProcessor::output_schema(),
+            DuplicatesProcessor::summary_schema(),
+        ),
+        "pathdiv" => (
+            PathDiversityProcessor::DATASET_FIELD,
+            PathDiversityProcessor::output_schema(),
+            PathDiversityProcessor::summary_schema(),
+        ),
+        "vantage" => (
+            VantageProcessor::DATASET_FIELD,
+            VantageProcessor::output_schema(),
+            VantageProcessor::summary_schema(),
+        ),
+        "firsthop" => (
+            FirstHopProcessor::DATASET_FIELD,
+            FirstHopProcessor::output_schema(),
+            FirstHopProcessor::summary_schema(),
+        ),
+        _ => return None,
+    };
+    Some(ProcessorSchemas {
+        processor,
+        dataset: dataset.to_string(),
+        output,
+        summary,
+    })
+}
+
+/// Check a JSON value against a processor output schema, see [processor_schemas]. Returns the
+/// JSON pointers of mismatching values with what was expected.
+///
+/// Only the subset of JSON Schema generated for the output structs is checked: `type`,
+/// `properties`, `patternProperties` of integer map keys, `required`, `additionalProperties`,
+/// `items`, `prefixItems`, `enum`, `const`, `anyOf`, `oneOf`, `allOf` and local `$ref`s; formats
+/// and numeric bounds are not.
+pub fn validate(value: &Value, schema: &Schema) -> Vec<String> {
+    let root = schema.as_value();
+    let mut errors = vec![];
+    validate_at(value, root, root, "", &mut errors);
+    errors
+}
+
+fn validate_at(
+    value: &Value,
+    schema: &Value,
+    root: &Value,
+    pointer: &str,
+    errors: &mut Vec<String>,
+) {
+    let schema = match schema {
+        Value::Bool(true) => return,
+        Value::Bool(false) => {
+            errors.push(format!("{}: not allowed", pointer));
+            return;
+        }
+        Value::Object(schema) => schema,
+        _ => return,
+    };
+    if let Some(reference) = schema.get("$ref").and_then(Value::as_str) {
+        match reference
+            .strip_prefix('#')
+            .and_then(|path| root.pointer(path))
+        {
+            Some(target) => validate_at(value, target, root, pointer, errors),
+            None => errors.push(format!("{}: unresolved reference {}", pointer, reference)),
+        }
+    }
+    if let Some(types) = schema.get("type") {
+        let types: Vec<&str> = match types {
+            Value::String(t) => vec![t.as_str()],
+            Value::Array(ts) => ts.iter().filter_map(Value::as_str).collect(),
+            _ => vec![],
+        };
+        if !types.iter().any(|t| has_type(value, t)) {
+            errors.push(format!("{}: expected {}", pointer, types.join(" or ")));
+            return;
+        }
+    }
+    if let Some(values) = schema.get("enum").and_then(Value::as_array) {
+        if !values.contains(value) {
+            errors.push(format!("{}: not one of the allowed values", pointer));
+        }
+    }
+    if let Some(expected) = schema.get("const") {
+        if expected != value {
+            errors.push(format!("{}: expected {}", pointer, expected));
+        }
+    }
+    for subschema in schema
+        .get("allOf")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+    {
+        validate_at(value, subschema, root, pointer, errors);
+    }
+    for keyword in ["anyOf", "oneOf"] {
+        if let Some(subschemas) = schema.get(keyword).and_then(Value::as_array) {
+            let matches = subschemas.iter().any(|subschema| {
+                let mut sub_errors = vec![];
+                validate_at(value, subschema, root, pointer, &mut sub_errors);
+                sub_errors.is_empty()
+            });
+            if !matches {
+                errors.push(format!("{}: matches none of {}", pointer, keyword));
+            }
+        }
+    }
+    if let Value::Object(object) = value {
+        let properties = schema.get("properties").and_then(Value::as_object);
+        let pattern_properties = schema.get("patternProperties").and_then(Value::as_object);
+        for name in schema
+            .get("required")
+            .and_then(Value::as_array)
+            .into_iter()
+            .flatten()
+        {
+            if let Some(name) = name.as_str() {
+                if !object.contains_key(name) {
+                    errors.push(format!("{}/{}: missing", pointer, name));
+                }
+            }
+        }
+        for (name, field) in object {
+            let field_pointer = format!("{}/{}", pointer, name);
+            let pattern_property = pattern_properties.and_then(|patterns| {
+                patterns
+                    .iter()
+                    .find(|(pattern, _)| matches_key_pattern(pattern, name))
+                    .map(|(_, property)| property)
+            });
+            match (
+                properties.and_then(|p| p.get(name)).or(pattern_property),
+                schema.get("additionalProperties"),
+            ) {
+                (Some(property), _) => validate_at(field, property, root, &field_pointer, errors),
+                (None, Some(additional)) => {
+                    validate_at(field, additional, root, &field_pointer, errors)
+                }
+                (None, None) => {}
+            }
+        }
+    }
+    if let Value::Array(items) = value {
+        let prefix_items = schema.get("prefixItems").and_then(Value::as_array);
+        for (i, item) in items.iter().enumerate() {
+            let item_pointer = format!("{}/{}", pointer, i);
+            match (prefix_items.and_then(|p| p.get(i)), schema.get("items")) {
+                (Some(item_schema), _) | (None, Some(item_schema)) => {
+                    validate_at(item, item_schema, root, &item_pointer, errors)
+                }
+                (None, None) => {}
+            }
+        }
+    }
+}
+
+fn has_type(value: &Value, name: &str) -> bool {
+    match name {
+        "null" => value.is_null(),
+        "boolean" => value.is_boolean(),
+        "object" => value.is_object(),
+        "array" => value.is_array(),
+        "string" => value.is_string(),
+        "number" => value.is_number(),
+        "integer" => value.is_i64() || value.is_u64(),
+        _ => true,
+    }
+}
+
+/// Whether a map key matches a `patternProperties` pattern. Only the integer key patterns of
+/// generated schemas are known, other patterns match any key.
+fn matches_key_pattern(pattern: &str, key: &str) -> bool {
+    let digits = match pattern {
+        r"^\d+$" => key,
+        r"^-?\d+$" => key.strip_prefix('-').unwrap_or(key),
+        _ => return true,
+    };
+    !digits.is_empty() && digits.bytes().all(|b| b.is_ascii_digit())
+}
@@ -0,0 +1,88 @@
+//! Intermediate data shared by processors.
+//!
+//! Processors declare the intermediate computations they need with
+//! [MessageProcessor::shared_inputs](crate::processors::MessageProcessor::shared_inputs), and the
+//! pipeline computes each declared [SharedInput] once for all processors instead of each processor
+//! recomputing it:
+//!
+//! - per element, [SharedInput::StrippedPath] is computed on the [ElemView] before the views are
+//!   filtered by route policy or sampled, so that all processors read the same cached path
+//! - per RIB dump, [SharedInput::FullFeedPeers] is derived from the pipeline's peer tracker once
+//!   all elements are processed and handed to the processors in a [SharedData] with
+//!   [MessageProcessor::set_shared_data](crate::processors::MessageProcessor::set_shared_data)
+//!   before they write their outputs
+//!
+//! Processors used outside a pipeline receive no shared data and fall back to their own state.
+
+use crate::processors::peers::PeerTracker;
+use crate::util::ElemView;
+use std::collections::HashSet;
+use std::net::IpAddr;
+use std::sync::Arc;
+
+/// Intermediate computation a processor needs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SharedInput {
+    /// AS path of each element with prepending removed, see [ElemView::path]
+    StrippedPath,
+    /// peers with a full feed in the RIB dump, see [FullFeedPeers]
+    FullFeedPeers,
+}
+
+/// Peers announcing at least the full-feed number of IPv4 or IPv6 prefixes in a RIB dump, over all
+/// elements of the pipeline regardless of the processors' route policies. Peers are identified by
+/// collector and IP address, like in the [PeerTracker].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FullFeedPeers {
+    peers: HashSet<(Option<String>, IpAddr)>,
+}
+
+impl FullFeedPeers {
+    pub fn from_tracker(tracker: &PeerTracker) -> Self {
+        FullFeedPeers {
+            peers: tracker
+                .full_feed_peers()
+                .into_iter()
+                .filter_map(|id| tracker.get(id).map(|p| (p.collector.clone(), p.ip)))
+                .collect(),
+        }
+    }
+
+    /// Whether the peer with the given IP address at the given collector has a full feed.
+    pub fn contains(&self, collector: Option<&str>, ip: &IpAddr) -> bool {
+        self.peers
+            .contains(&(collector.map(|c| c.to_string()), *ip))
+    }
+
+    pub fn len(&self) -> usize {
+        self.peers.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.peers.is_empty()
+    }
+}
+
+/// Per-RIB intermediate data computed by the pipeline, only set for declared inputs.
+#[derive(Debug, Clone, Default)]
+pub struct SharedData {
+    pub full_feed_peers: Option<Arc<FullFeedPeers>>,
+}
+
+impl SharedData {
+    /// Compute the per-RIB inputs declared by any processor.
+    pub fn compute(inputs: &HashSet<SharedInput>, tracker: &PeerTracker) -> Self {
+        SharedData {
+            full_feed_peers: inputs
+                .contains(&SharedInput::FullFeedPeers)
+                .then(|| Arc::new(FullFeedPeers::from_tracker(tracker))),
+        }
+    }
+}
+
+/// Compute the per-element inputs declared by any processor on a view.
+pub fn prepare_view(inputs: &HashSet<SharedInput>, view: &ElemView) {
+    if inputs.contains(&SharedInput::StrippedPath) {
+        view.path();
+    }
+}
@@ -0,0 +1,336 @@
+//! Output sinks.
+//!
+//! Processors only produce serializable results; an [OutputSink] takes care of encoding them,
+//! compressing them and storing them at their output paths. New storage backends only need to
+//! implement [OutputSink::write_bytes].
+//!
+//! Sinks also read back the outputs they wrote, e.g. the latest per-collector outputs when
+//! summarizing, so that encoding choices of a sink stay transparent to the pipeline. Outputs are
+//! JSON, or CBOR for paths with a `.cbor` extension (see [OutputEncoding]).
+//!
+//! An output directory of `-` selects the [StdoutSink], which streams results to standard output
+//! for piping into other tools.
+
+use crate::storage;
+use anyhow::{anyhow, Result};
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+use std::fmt::{Display, Formatter};
+use std::io::{Read, Write};
+use std::path::Path;
+use std::str::FromStr;
+
+/// Output directory value that denotes writing results to standard output.
+pub const STDOUT_DIR: &str = "-";
+
+/// Name of the output field carrying the address family a pipeline is limited to.
+pub const ADDRESS_FAMILY_FIELD: &str = "address_family";
+
+/// Name of the output field carrying the label of the run that wrote it.
+pub const LABEL_FIELD: &str = "label";
+
+/// Name of the per-collector output field carrying the RIB dump timestamp, in unix seconds.
+pub const RIB_TIMESTAMP_FIELD: &str = "rib_timestamp";
+
+/// Name of the per-collector output field carrying the numbers of elements skipped by the
+/// processor's route policy.
+pub const ROUTE_POLICY_SKIPPED_FIELD: &str = "route_policy_skipped";
+
+/// Check if the given output directory refers to standard output.
+pub fn is_stdout(output_dir: &str) -> bool {
+    output_dir == STDOUT_DIR
+}
+
+/// Content of a file as stored, without decompression; local, object store or HTTP(S).
+pub fn read_raw(path: &str) -> Result<Vec<u8>> {
+    if !storage::is_object_store(path)
+        && !path.starts_with("http://")
+        && !path.starts_with("https://")
+    {
+        return Ok(std::fs::read(path)?);
+    }
+    let temp_dir = tempfile::tempdir()?;
+    let temp_path = temp_dir.path().join("download");
+    let temp_path = temp_path.to_string_lossy().to_string();
+    if storage::is_object_store(path) {
+        storage::download(path, temp_path.as_str())?;
+    } else {
+        oneio::download(path, temp_path.as_str(), None)?;
+    }
+    Ok(std::fs::read(temp_path)?)
+}
+
+pub trait OutputSink: Send + Sync {
+    /// Store raw bytes at the given path.
+    fn write_bytes(&self, path: &str, data: &[u8]) -> Result<()>;
+
+    /// Encode a result value and store it at the given path.
+    fn write_json(&self, path: &str, value: &Value) -> Result<()> {
+        let content = serde_json::to_string_pretty(value)?;
+        self.write_bytes(path, content.as_bytes())
+    }
+
+    /// Read back the decoded content stored at the given path. Local files, object store objects
+    /// and URLs are read directly and decompressed according to the path's file extension by
+    /// default.
+    fn read_bytes(&self, path: &str) -> Result<Vec<u8>> {
+        let mut data = vec![];
+        match storage::ObjectStore::of(path) {
+            // oneio reads S3 objects itself
+            Some(storage::ObjectStore::Gcs | storage::ObjectStore::Azure) => {
+                // keep the file name so that the temporary file gets the same compression
+                let file_name = path.rsplit('/').next().unwrap_or("output");
+                let temp_dir = tempfile::tempdir()?;
+                let temp_path = temp_dir.path().join(file_name);
+                let temp_path = temp_path.to_string_lossy().to_string();
+                storage::download(path, temp_path.as_str())?;
+                oneio::get_reader(temp_path.as_str())?.read_to_end(&mut data)?;
+            }
+            _ => {
+                oneio::get_reader(path)?.read_to_end(&mut data)?;
+            }
+        }
+        Ok(data)
+    }
+
+    /// Read back a result value stored at the given path.
+    fn read_json(&self, path: &str) -> Result<Value> {
+        decode_content(path, &self.read_bytes(path)?)
+    }
+
+    /// Whether the sink streams content regardless of paths, so that a result written to several
+    /// paths (e.g. the dated and the latest output) should only be written once.
+    fn is_stream(&self) -> bool {
+        false
+    }
+}
+
+/// Sink writing to local files or object store objects (`s3://`, `gs://` and `az://` paths, see
+/// [storage](crate::storage)).
+///
+/// Content is compressed according to the path's file extension (e.g. `.bz2`, `.gz`), and
+/// missing local directories are created. Objects are written to a temporary file first and then
+/// uploaded.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct FileSink;
+
+impl OutputSink for FileSink {
+    fn write_bytes(&self, path: &str, data: &[u8]) -> Result<()> {
+        if storage::is_object_store(path) {
+            // keep the file name so that the temporary file gets the same compression
+            let file_name = path.rsplit('/').next().unwrap_or("output");
+            let temp_dir = tempfile::tempdir()?;
+            let temp_path = temp_dir.path().join(file_name);
+            let temp_path = temp_path.to_string_lossy().to_string();
+            let mut writer = oneio::get_writer(temp_path.as_str())?;
+            writer.write_all(data)?;
+            drop(writer);
+
+            storage::upload(path, temp_path.as_str())?;
+            temp_dir.close()?;
+        } else {
+            if let Some(parent) = Path::new(path).parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+            let mut writer = oneio::get_writer(path)?;
+            writer.write_all(data)?;
+        }
+        Ok(())
+    }
+}
+
+/// Sink streaming results to standard output, one compact JSON document per line (JSONL).
+///
+/// Paths are ignored and content is never compressed, e.g. for
+/// `ribeye file rib.bz2 -p pfx2as -d - | jq ...`. Each write holds the stdout lock, so results of
+/// concurrent pipelines do not interleave.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct StdoutSink;
+
+impl OutputSink for StdoutSink {
+    fn write_bytes(&self, _path: &str, data: &[u8]) -> Result<()> {
+        let mut stdout = std::io::stdout().lock();
+        stdout.write_all(data)?;
+        if !data.ends_with(b"\n") {
+            stdout.write_all(b"\n")?;
+        }
+        stdout.flush()?;
+        Ok(())
+    }
+
+    fn write_json(&self, path: &str, value: &Value) -> Result<()> {
+        let content = serde_json::to_string(value)?;
+        self.write_bytes(path, content.as_bytes())
+    }
+
+    fn is_stream(&self) -> bool {
+        true
+    }
+}
+
+/// Per-collector outputs written for each RIB.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum OutputSelection {
+    /// the dated output and the collector's latest output
+    #[default]
+    All,
+    /// only the dated output, e.g. when backfilling historical RIBs
+    DatedOnly,
+    /// only the collector's latest output
+    LatestOnly,
+}
+
+/// Encoding of the dated per-collector outputs. Latest outputs and summaries, which the pipeline
+/// reads back, are always JSON.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum OutputEncoding {
+    /// JSON, `.json.bz2`
+    #[default]
+    Json,
+    /// CBOR (RFC 8949) of the same data, `.cbor.bz2`: smaller and faster to parse
+    Cbor,
+}
+
+impl FromStr for OutputEncoding {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "json" => Ok(OutputEncoding::Json),
+            "cbor" => Ok(OutputEncoding::Cbor),
+            _ => Err(anyhow!("unknown output encoding: {}", s)),
+        }
+    }
+}
+
+impl Display for OutputEncoding {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            OutputEncoding::Json => write!(f, "json"),
+            OutputEncoding::Cbor => write!(f, "cbor"),
+        }
+    }
+}
+
+/// Whether the file at a path is CBOR-encoded, by its extension, e.g. `latest.cbor.bz2`.
+pub fn is_cbor_path(path: &str) -> bool {
+    let file_name = path.rsplit('/').next().unwrap_or(path);
+    file_name.split('.').skip(1).any(|ext| ext == "cbor")
+}
+
+/// Deserialize the decompressed content of an output, CBOR or JSON by the path's extension.
+pub fn decode_content<T: DeserializeOwned>(path: &str, data: &[u8]) -> Result<T> {
+    match is_cbor_path(path) {
+        true => Ok(ciborium::from_reader(data)?),
+        false => Ok(serde_json::from_slice(data)?),
+    }
+}
+
+/// Path of a JSON output with the given encoding, e.g. `pfx2as_rrc00_..._<ts>.cbor.bz2` for its
+/// `.json.bz2` path.
+pub fn get_encoded_path(path: &str, encoding: OutputEncoding) -> String {
+    match (encoding, path.rfind(".json")) {
+        (OutputEncoding::Cbor, Some(i)) if !path[i..].contains('/') => {
+            format!("{}.cbor{}", &path[..i], &path[i + ".json".len()..])
+        }
+        _ => path.to_string(),
+    }
+}
+
+/// Sink encoding JSON values written to it, except at some paths, e.g. the latest outputs.
+pub(crate) struct EncodingSink<'a> {
+    pub inner: &'a dyn OutputSink,
+    pub encoding: OutputEncoding,
+    pub keep_json: Vec<String>,
+}
+
+impl OutputSink for EncodingSink<'_> {
+    fn write_bytes(&self, path: &str, data: &[u8]) -> Result<()> {
+        self.inner.write_bytes(path, data)
+    }
+
+    fn write_json(&self, path: &str, value: &Value) -> Result<()> {
+        if self.encoding == OutputEncoding::Json
+            || self.inner.is_stream()
+            || self.keep_json.iter().any(|p| p == path)
+        {
+            return self.inner.write_json(path, value);
+        }
+        let mut data = vec![];
+        ciborium::into_writer(value, &mut data)?;
+        self.inner
+            .write_bytes(get_encoded_path(path, self.encoding).as_str(), &data)
+    }
+
+    fn read_bytes(&self, path: &str) -> Result<Vec<u8>> {
+        self.inner.read_bytes(path)
+    }
+
+    fn is_stream(&self) -> bool {
+        self.inner.is_stream()
+    }
+}
+
+/// Sink skipping writes to some paths, e.g. the latest output of a backfilled RIB.
+pub(crate) struct SkipPathSink<'a> {
+    pub inner: &'a dyn OutputSink,
+    pub skip: Vec<String>,
+}
+
+impl OutputSink for SkipPathSink<'_> {
+    fn write_bytes(&self, path: &str, data: &[u8]) -> Result<()> {
+        match self.skip.iter().any(|p| p == path) {
+            true => Ok(()),
+            false => self.inner.write_bytes(path, data),
+        }
+    }
+
+    fn write_json(&self, path: &str, value: &Value) -> Result<()> {
+        match self.skip.iter().any(|p| p == path) {
+            true => Ok(()),
+            false => self.inner.write_json(path, value),
+        }
+    }
+
+    fn read_bytes(&self, path: &str) -> Result<Vec<u8>> {
+        self.inner.read_bytes(path)
+    }
+
+    fn is_stream(&self) -> bool {
+        self.inner.is_stream()
+    }
+}
+
+/// Sink adding fields to each JSON object it writes, e.g. the address family a pipeline is limited
+/// to.
+pub(crate) struct FieldSink<'a> {
+    pub inner: &'a dyn OutputSink,
+    pub fields: Vec<(&'static str, Value)>,
+}
+
+impl OutputSink for FieldSink<'_> {
+    fn write_bytes(&self, path: &str, data: &[u8]) -> Result<()> {
+        self.inner.write_bytes(path, data)
+    }
+
+    fn write_json(&self, path: &str, value: &Value) -> Result<()> {
+        if self.fields.is_empty() {
+            return self.inner.write_json(path, value);
+        }
+        let mut value = value.clone();
+        if let Some(obj) = value.as_object_mut() {
+            for (name, field) in &self.fields {
+                obj.insert(name.to_string(), field.clone());
+            }
+        }
+        self.inner.write_json(path, &value)
+    }
+
+    fn read_bytes(&self, path: &str) -> Result<Vec<u8>> {
+        self.inner.read_bytes(path)
+    }
+
+    fn is_stream(&self) -> bool {
+        self.inner.is_stream()
+    }
+}
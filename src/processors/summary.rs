@@ -0,0 +1,194 @@
+//! Publishing of processor summaries.
+//!
+//! Summaries are always written to their canonical `latest.json.bz2` paths, which lookups,
+//! enrichment and the output index read back. A [SummaryWriter] additionally publishes copies with
+//! other compressions next to them, by default an uncompressed `latest.json` that can be fetched
+//! with plain `curl`.
+//!
+//! Each summary also lists the dump of every collector it covers ([collector_dumps]), flagging
+//! collectors whose latest RIB dump is older than a threshold, so that consumers can tell when a
+//! summary silently contains stale collectors.
+//!
+//! With [SummaryWriter::with_csv], the tables of built-in processor summaries are also published
+//! as CSV files, see [csv](crate::processors::csv), and with [SummaryWriter::with_protobuf] the
+//! pfx2as and as2rel summaries as Protocol Buffers, see [proto](crate::processors::proto).
+
+use crate::input::Compression;
+use crate::processors::csv::{get_summary_csv_path, summary_tables};
+use crate::processors::proto::{encode_summary, get_summary_proto_path};
+use crate::processors::schema::processor_schemas;
+use crate::processors::{OutputSink, RibMeta};
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::time::Duration;
+
+/// Name of the summary field listing the dumps of the summarized collectors.
+pub const COLLECTOR_DUMPS_FIELD: &str = "collector_dumps";
+
+/// Default age after which a collector's latest RIB dump is stale.
+pub const DEFAULT_STALE_AFTER: Duration = Duration::from_secs(48 * 3600);
+
+/// Latest RIB dump of a collector covered by a summary.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CollectorDump {
+    pub project: String,
+    pub collector: String,
+    /// RIB dump Unix timestamp
+    pub rib_timestamp: i64,
+    /// whether the dump is older than the staleness threshold at summarization time
+    pub stale: bool,
+}
+
+/// Dumps of the given RIBs sorted by collector, stale if older than `stale_after` at Unix time
+/// `now`.
+pub fn collector_dumps(
+    rib_metas: &[RibMeta],
+    stale_after: Duration,
+    now: i64,
+) -> Vec<CollectorDump> {
+    let mut dumps: Vec<CollectorDump> = rib_metas
+        .iter()
+        .map(|rib_meta| {
+            let rib_timestamp = rib_meta.timestamp.and_utc().timestamp();
+            CollectorDump {
+                project: rib_meta.project.clone(),
+                collector: rib_meta.collector.clone(),
+                rib_timestamp,
+                stale: now - rib_timestamp > stale_after.as_secs() as i64,
+            }
+        })
+        .collect();
+    dumps.sort_by(|a, b| a.collector.cmp(&b.collector));
+    dumps
+}
+
+/// Path of a summary with the given compression, replacing the compression extension of its
+/// canonical path.
+pub fn get_summary_copy_path(summary_path: &str, compression: Compression) -> String {
+    let stem = summary_path
+        .strip_suffix(".bz2")
+        .or_else(|| summary_path.strip_suffix(".gz"))
+        .unwrap_or(summary_path);
+    match compression {
+        Compression::None => stem.to_string(),
+        Compression::Gzip => format!("{}.gz", stem),
+        Compression::Bzip2 => format!("{}.bz2", stem),
+    }
+}
+
+/// Writer of processor summaries to their canonical paths and additional copies.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SummaryWriter {
+    /// compressions of the copies published next to each canonical summary
+    copies: Vec<Compression>,
+    /// whether to also publish the summary tables as CSV files
+    csv: bool,
+    /// whether to also publish the pfx2as and as2rel summaries as Protocol Buffers
+    protobuf: bool,
+}
+
+impl Default for SummaryWriter {
+    fn default() -> Self {
+        SummaryWriter {
+            copies: vec![Compression::None],
+            csv: false,
+            protobuf: false,
+        }
+    }
+}
+
+impl SummaryWriter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Publish copies with the given compressions, e.g. none for only the canonical summaries.
+    pub fn with_copies(mut self, copies: &[Compression]) -> Self {
+        self.copies = copies.to_vec();
+        self
+    }
+
+    /// Also publish the tables of built-in processor summaries as CSV files.
+    pub fn with_csv(mut self, csv: bool) -> Self {
+        self.csv = csv;
+        self
+    }
+
+    /// Also publish the pfx2as and as2rel summaries as Protocol Buffers (`latest.pb`).
+    pub fn with_protobuf(mut self, protobuf: bool) -> Self {
+        self.protobuf = protobuf;
+        self
+    }
+
+    /// All paths written for the given canonical summary paths, canonical paths first.
+    pub fn paths(&self, summary_paths: &[String]) -> Vec<String> {
+        let mut paths = summary_paths.to_vec();
+        for summary_path in summary_paths {
+            for compression in &self.copies {
+                let path = get_summary_copy_path(summary_path, *compression);
+                if !paths.contains(&path) {
+                    paths.push(path);
+                }
+            }
+        }
+        paths
+    }
+
+    /// Write a summary to its canonical paths and copies. A stream sink gets it only once.
+    pub fn write(
+        &self,
+        sink: &dyn OutputSink,
+        summary_paths: &[String],
+        summary: &Value,
+    ) -> Result<()> {
+        for path in self.paths(summary_paths) {
+            sink.write_json(path.as_str(), summary)?;
+            if sink.is_stream() {
+                break;
+            }
+        }
+        Ok(())
+    }
+    /// Write the CSV tables of a summary next to its canonical paths, if enabled. Summaries of
+    /// processors without schemas, and summaries written to a stream sink, have no CSV tables.
+    pub fn write_csv(
+        &self,
+        sink: &dyn OutputSink,
+        processor: &str,
+        summary_paths: &[String],
+        summary: &Value,
+    ) -> Result<()> {
+        if !self.csv || sink.is_stream() || processor_schemas(processor).is_none() {
+            return Ok(());
+        }
+        let tables = summary_tables(processor, summary)?;
+        for summary_path in summary_paths {
+            for table in &tables {
+                let path = get_summary_csv_path(summary_path, table.name.as_str());
+                sink.write_bytes(path.as_str(), table.to_csv_string().as_bytes())?;
+            }
+        }
+        Ok(())
+    }
+    /// Write the Protocol Buffers encoding of a summary next to its canonical paths, if enabled.
+    /// Only pfx2as and as2rel summaries are encoded, and none are written to a stream sink.
+    pub fn write_protobuf(
+        &self,
+        sink: &dyn OutputSink,
+        processor: &str,
+        summary_paths: &[String],
+        summary: &Value,
+    ) -> Result<()> {
+        if !self.protobuf || sink.is_stream() {
+            return Ok(());
+        }
+        let Some(data) = encode_summary(processor, summary)? else {
+            return Ok(());
+        };
+        for summary_path in summary_paths {
+            sink.write_bytes(get_summary_proto_path(summary_path).as_str(), &data)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,367 @@
+//! Typed processors.
+//!
+//! [TypedProcessor] is a typed layer over [MessageProcessor]: a processor declares the types of
+//! its per-collector output and of its summary, and the blanket [MessageProcessor] implementation
+//! derives result encoding, the standard output paths, reading of the latest per-collector outputs
+//! for summarization, and JSON schema generation. Summaries are merged by the default engine in
+//! [merge](crate::processors::merge) unless a processor overrides [TypedProcessor::summarize].
+
+use crate::processors::cache::{
+    file_fingerprint, get_summary_cache_path, SummaryCache, SummaryCacheEntry,
+};
+use crate::processors::merge::{merge_outputs, Mergeable};
+use crate::processors::meta::{
+    get_default_output_path, get_latest_output_path, get_partial_output_path,
+    get_summary_output_path, ProcessorMeta,
+};
+use crate::processors::shared::{SharedData, SharedInput};
+use crate::processors::{decode_content, FileSink, MessageProcessor, OutputSink, RibMeta};
+use crate::util::{ElemView, RoutePolicy};
+use anyhow::Result;
+use bgpkit_parser::BgpElem;
+use schemars::{schema_for, JsonSchema, Schema};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tracing::{info, warn};
+
+/// Process a single entry outside a pipeline through a fresh view, skipping the elements the
+/// processor's route policy excludes like a pipeline would.
+pub(crate) fn process_entry_view<T: TypedProcessor>(
+    processor: &mut T,
+    elem: &BgpElem,
+) -> Result<()> {
+    let view = ElemView::new(elem);
+    match processor.route_policy().skip_reason(&view) {
+        Some(_) => Ok(()),
+        None => processor.process_view(&view),
+    }
+}
+
+pub trait TypedProcessor {
+    /// Per-collector output type, merged into the summary by the default summarization engine
+    type Output: Serialize + DeserializeOwned + JsonSchema + Mergeable<Merged = Self::Summary>;
+
+    /// Summary output type
+    type Summary: Serialize + DeserializeOwned + JsonSchema;
+
+    /// Array field holding the records of the outputs and the summary, e.g. `pfx2as`
+    const DATASET_FIELD: &'static str;
+
+    /// Meta information of the processor, i.e. its name and output root directory
+    fn processor_meta(&self) -> &ProcessorMeta;
+
+    /// Meta information of the RIB being processed
+    fn rib_meta(&self) -> Option<&RibMeta>;
+
+    /// Create a fresh processor with the same configuration but none of the processing state
+    fn new_instance(&self) -> Self
+    where
+        Self: Sized;
+
+    fn reset_processor(&mut self, rib_meta: &RibMeta);
+
+    /// Process a single entry in the RIB
+    fn process_entry(&mut self, elem: &BgpElem) -> Result<()>;
+
+    /// Elements skipped by the pipeline before they reach the processor, see
+    /// [MessageProcessor::route_policy]. Skips nothing by default.
+    fn route_policy(&self) -> RoutePolicy {
+        RoutePolicy::default()
+    }
+
+    /// Process a single entry through a view shared with the other processors, reusing its
+    /// derived attributes. Calls [TypedProcessor::process_entry] by default.
+    fn process_view(&mut self, view: &ElemView) -> Result<()> {
+        self.process_entry(view.elem())
+    }
+
+    /// Process a batch of entries, passing errors of single entries to `on_error`, see
+    /// [MessageProcessor::process_batch]. Calls [TypedProcessor::process_view] for each entry by
+    /// default.
+    fn process_batch(
+        &mut self,
+        views: &[ElemView],
+        on_error: &mut dyn FnMut(anyhow::Error) -> Result<()>,
+    ) -> Result<()> {
+        for view in views {
+            if let Err(e) = self.process_view(view) {
+                on_error(e)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Intermediate data the processor needs, see [MessageProcessor::shared_inputs]. None by
+    /// default.
+    fn shared_inputs(&self) -> Vec<SharedInput> {
+        vec![]
+    }
+
+    /// Receive the per-RIB data of the declared shared inputs, see
+    /// [MessageProcessor::set_shared_data]. Does nothing by default.
+    fn set_shared_data(&mut self, _shared: &SharedData) {}
+
+    /// One-line summary of the processed RIB for logs, see [MessageProcessor::brief_summary]. The
+    /// processor's name by default.
+    fn brief_summary(&self) -> String {
+        self.processor_meta().name.clone()
+    }
+
+    /// Seed the processor state from a previous output at the given path, typically read with
+    /// [TypedProcessor::read_output]. Does nothing by default.
+    fn load_previous(&mut self, _path: &str) -> Result<()> {
+        Ok(())
+    }
+
+    /// Read a per-collector output of this processor
+    fn read_output(path: &str) -> Result<Self::Output>
+    where
+        Self: Sized,
+    {
+        FileSink
+            .read_bytes(path)
+            .and_then(|data| decode_content::<Self::Output>(path, &data))
+            .map_err(|e| anyhow::anyhow!("failed to read {}: {}", path, e))
+    }
+
+    /// Read a summary of this processor
+    fn read_summary(path: &str) -> Result<Self::Summary>
+    where
+        Self: Sized,
+    {
+        FileSink
+            .read_bytes(path)
+            .and_then(|data| decode_content::<Self::Summary>(path, &data))
+            .map_err(|e| anyhow::anyhow!("failed to read {}: {}", path, e))
+    }
+
+    /// Generate the per-collector output of the processed RIB
+    fn to_output(&self) -> Option<Self::Output>;
+
+    /// Summarize the latest per-collector outputs of the given RIBs.
+    ///
+    /// `outputs` contains the successfully read outputs along with the meta information of their
+    /// RIBs; `rib_metas` contains all requested RIBs. By default, outputs are merged entry by entry
+    /// as defined by their [Mergeable] implementation.
+    fn summarize(
+        &self,
+        rib_metas: &[RibMeta],
+        outputs: Vec<(RibMeta, Self::Output)>,
+    ) -> Result<Self::Summary> {
+        Ok(merge_outputs(
+            rib_metas,
+            outputs.into_iter().map(|(_, output)| output),
+        ))
+    }
+
+    /// Paths the summary is written to
+    fn summary_paths(&self) -> Vec<String> {
+        let meta = self.processor_meta();
+        vec![get_summary_output_path(
+            meta.output_dir.as_str(),
+            meta.name.as_str(),
+        )]
+    }
+
+    /// JSON schema of the per-collector output
+    fn output_schema() -> Schema
+    where
+        Self: Sized,
+    {
+        schema_for!(Self::Output)
+    }
+
+    /// JSON schema of the summary output
+    fn summary_schema() -> Schema
+    where
+        Self: Sized,
+    {
+        schema_for!(Self::Summary)
+    }
+}
+
+impl<T: TypedProcessor + Send + 'static> MessageProcessor for T {
+    fn name(&self) -> String {
+        self.processor_meta().name.clone()
+    }
+
+    fn new_instance(&self) -> Box<dyn MessageProcessor> {
+        Box::new(TypedProcessor::new_instance(self))
+    }
+
+    fn output_paths(&self) -> Option<Vec<String>> {
+        let rib_meta = self.rib_meta()?;
+        Some(vec![
+            get_default_output_path(rib_meta, self.processor_meta()),
+            get_latest_output_path(rib_meta, self.processor_meta()),
+        ])
+    }
+
+    fn reset_processor(&mut self, rib_meta: &RibMeta) {
+        TypedProcessor::reset_processor(self, rib_meta)
+    }
+
+    fn process_entry(&mut self, elem: &BgpElem) -> Result<()> {
+        TypedProcessor::process_entry(self, elem)
+    }
+
+    fn route_policy(&self) -> RoutePolicy {
+        TypedProcessor::route_policy(self)
+    }
+
+    fn process_view(&mut self, view: &ElemView) -> Result<()> {
+        TypedProcessor::process_view(self, view)
+    }
+
+    fn process_batch(
+        &mut self,
+        views: &[ElemView],
+        on_error: &mut dyn FnMut(anyhow::Error) -> Result<()>,
+    ) -> Result<()> {
+        TypedProcessor::process_batch(self, views, on_error)
+    }
+
+    fn shared_inputs(&self) -> Vec<SharedInput> {
+        TypedProcessor::shared_inputs(self)
+    }
+
+    fn set_shared_data(&mut self, shared: &SharedData) {
+        TypedProcessor::set_shared_data(self, shared)
+    }
+
+    fn brief_summary(&self) -> String {
+        TypedProcessor::brief_summary(self)
+    }
+
+    fn to_result(&self) -> Option<Value> {
+        self.to_output().and_then(|o| serde_json::to_value(o).ok())
+    }
+
+    fn latest_output_path(&self) -> Option<String> {
+        let rib_meta = self.rib_meta()?;
+        Some(get_latest_output_path(rib_meta, self.processor_meta()))
+    }
+
+    fn load_previous(&mut self, path: &str) -> Result<()> {
+        TypedProcessor::load_previous(self, path)
+    }
+
+    fn flush_partial(&mut self, sink: &dyn OutputSink) -> Result<()> {
+        let rib_meta = match self.rib_meta() {
+            None => return Ok(()),
+            Some(m) => m,
+        };
+        let partial_path = get_partial_output_path(rib_meta, self.processor_meta());
+        match self.to_result() {
+            None => Ok(()),
+            Some(result) => sink.write_json(partial_path.as_str(), &result),
+        }
+    }
+
+    fn summary_paths(&self) -> Vec<String> {
+        TypedProcessor::summary_paths(self)
+    }
+
+    fn summarize_latest(&self, rib_metas: &[RibMeta], ignore_error: bool) -> Result<Option<Value>> {
+        self.summarize_latest_with_sink(&FileSink, rib_metas, ignore_error)
+    }
+
+    fn summarize_latest_with_sink(
+        &self,
+        sink: &dyn OutputSink,
+        rib_metas: &[RibMeta],
+        ignore_error: bool,
+    ) -> Result<Option<Value>> {
+        let mut outputs = vec![];
+        for rib_meta in rib_metas {
+            let latest_file_path = get_latest_output_path(rib_meta, self.processor_meta());
+            if let Some(output) = read_latest::<T>(sink, latest_file_path.as_str(), ignore_error)? {
+                outputs.push((rib_meta.clone(), output));
+            }
+        }
+
+        let summary = self.summarize(rib_metas, outputs)?;
+        Ok(Some(serde_json::to_value(&summary)?))
+    }
+
+    fn summarize_latest_incremental(
+        &self,
+        sink: &dyn OutputSink,
+        rib_metas: &[RibMeta],
+        ignore_error: bool,
+    ) -> Result<Option<Value>> {
+        let meta = self.processor_meta();
+        let cache_path = get_summary_cache_path(meta.output_dir.as_str(), meta.name.as_str());
+        let mut cache = SummaryCache::load(sink, cache_path.as_str());
+        let mut new_cache = SummaryCache::default();
+        let mut outputs = vec![];
+        let mut reused = 0;
+        for rib_meta in rib_metas {
+            let path = get_latest_output_path(rib_meta, meta);
+            let fingerprint = file_fingerprint(path.as_str());
+            let cached = fingerprint
+                .as_deref()
+                .and_then(|f| cache.take(path.as_str(), f))
+                .and_then(|value| Some((T::Output::deserialize(&value).ok()?, value)));
+            let (output, value) = match cached {
+                Some(cached) => {
+                    reused += 1;
+                    cached
+                }
+                None => match read_latest::<T>(sink, path.as_str(), ignore_error)? {
+                    Some(output) => {
+                        let value = serde_json::to_value(&output)?;
+                        (output, value)
+                    }
+                    None => continue,
+                },
+            };
+            outputs.push((rib_meta.clone(), output));
+            if let Some(fingerprint) = fingerprint {
+                new_cache.entries.push(SummaryCacheEntry {
+                    path,
+                    fingerprint,
+                    output: value,
+                });
+            }
+        }
+        info!(
+            processor = meta.name.as_str(),
+            phase = "summarize",
+            "reused {} of {} cached latest outputs",
+            reused,
+            rib_metas.len()
+        );
+        if !sink.is_stream() {
+            sink.write_bytes(cache_path.as_str(), &serde_json::to_vec(&new_cache)?)?;
+        }
+
+        let summary = self.summarize(rib_metas, outputs)?;
+        Ok(Some(serde_json::to_value(&summary)?))
+    }
+}
+
+/// Read the latest per-collector output of a processor through a sink; `None` if it cannot be read
+/// and errors are ignored.
+fn read_latest<T: TypedProcessor>(
+    sink: &dyn OutputSink,
+    path: &str,
+    ignore_error: bool,
+) -> Result<Option<T::Output>> {
+    info!("summarizing {}...", path);
+    let output = sink
+        .read_bytes(path)
+        .and_then(|data| decode_content::<T::Output>(path, &data));
+    match output {
+        Ok(d) => Ok(Some(d)),
+        Err(e) => {
+            if ignore_error {
+                warn!("failed to read {}, skipping...", path);
+                Ok(None)
+            } else {
+                Err(anyhow::anyhow!("failed to read {}: {}", path, e))
+            }
+        }
+    }
+}
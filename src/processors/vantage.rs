@@ -0,0 +1,368 @@
+//! `vantage` processor recommends a minimal set of peers covering most of the observed routing.
+//!
+//! Each peer covers the prefix-origin pairs and the AS links (adjacent ASes of AS paths with
+//! prepending removed) of its routes. A greedy set cover repeatedly picks the peer adding the most
+//! uncovered pairs and links, each counted as a share of all observed ones, until both the
+//! prefix-origin pairs and the AS links are covered to the target share (by default
+//! [DEFAULT_VANTAGE_COVERAGE]). The picked peers, in order, with their marginal and cumulative
+//! coverage, are the recommended vantage points for researchers choosing feeds.
+//!
+//! The cover is computed per collector, as outputs do not keep the routes of each peer; the summary
+//! lists the vantage points of all collectors.
+
+use crate::processors::merge::{rib_dump_urls, Mergeable};
+use crate::processors::meta::{ProcessorMeta, RibMeta};
+use crate::processors::peers::PeerIndex;
+use crate::processors::shared::SharedInput;
+use crate::processors::typed::process_entry_view;
+use crate::processors::TypedProcessor;
+use crate::util::{format_count, ElemView, RoutePolicy};
+use bgpkit_parser::BgpElem;
+use ipnet::IpNet;
+use roaring::RoaringBitmap;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::net::IpAddr;
+
+/// Default share of the prefix-origin pairs and AS links covered by the vantage points.
+pub const DEFAULT_VANTAGE_COVERAGE: f64 = 0.95;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct VantagePointEntry {
+    pub collector: String,
+    /// order in which the peer was picked, starting at 1
+    pub rank: usize,
+    pub ip: IpAddr,
+    pub asn: u32,
+    /// prefix-origin pairs observed by the peer
+    pub pairs: u64,
+    /// AS links observed by the peer
+    pub links: u64,
+    /// prefix-origin pairs not covered by the peers picked before
+    pub new_pairs: u64,
+    /// AS links not covered by the peers picked before
+    pub new_links: u64,
+    /// share of all prefix-origin pairs covered by this and the peers picked before
+    pub pairs_coverage: f64,
+    /// share of all AS links covered by this and the peers picked before
+    pub links_coverage: f64,
+}
+
+/// Coverage of the vantage points of a collector.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct VantageCollectorEntry {
+    pub collector: String,
+    /// number of peers with routes
+    pub peers_count: usize,
+    /// number of prefix-origin pairs observed by all peers
+    pub pairs_count: u64,
+    /// number of AS links observed by all peers
+    pub links_count: u64,
+    /// number of recommended vantage points
+    pub vantage_points_count: usize,
+    /// share of the prefix-origin pairs covered by the vantage points
+    pub pairs_coverage: f64,
+    /// share of the AS links covered by the vantage points
+    pub links_coverage: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct VantageCollectorJson {
+    pub project: String,
+    pub collector: String,
+    pub rib_dump_url: String,
+    /// target share of the prefix-origin pairs and AS links to cover
+    pub coverage: f64,
+    pub coverage_summary: VantageCollectorEntry,
+    /// recommended vantage points in the order they were picked
+    pub vantage_points: Vec<VantagePointEntry>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct VantageSummaryJson {
+    pub rib_dump_urls: Vec<String>,
+    /// target share of the prefix-origin pairs and AS links to cover
+    pub coverage: f64,
+    /// coverage of each collector, sorted by collector
+    pub collectors: Vec<VantageCollectorEntry>,
+    /// recommended vantage points of all collectors, sorted by collector and rank
+    pub vantage_points: Vec<VantagePointEntry>,
+}
+
+/// Prefix-origin pairs and AS links observed by a peer, by index.
+#[derive(Debug, Default, Clone)]
+struct PeerCoverage {
+    pairs: RoaringBitmap,
+    links: RoaringBitmap,
+}
+
+/// Interned items, each assigned an index in order of first sight.
+#[derive(Debug, Clone)]
+struct Interner<T> {
+    index: HashMap<T, u32>,
+}
+
+impl<T: std::hash::Hash + Eq> Interner<T> {
+    fn new() -> Self {
+        Interner {
+            index: HashMap::new(),
+        }
+    }
+
+    fn get(&mut self, item: T) -> u32 {
+        let next = self.index.len() as u32;
+        *self.index.entry(item).or_insert(next)
+    }
+
+    fn len(&self) -> u64 {
+        self.index.len() as u64
+    }
+}
+
+pub struct VantageProcessor {
+    rib_meta: Option<RibMeta>,
+    processor_meta: ProcessorMeta,
+    coverage: f64,
+    peers: PeerIndex,
+    pairs: Interner<(IpNet, u32)>,
+    links: Interner<(u32, u32)>,
+    /// items observed by each peer, by peer index
+    coverages: Vec<PeerCoverage>,
+}
+
+impl VantageProcessor {
+    pub fn new(output_dir: &str) -> Self {
+        let processor_meta = ProcessorMeta {
+            name: "vantage".to_string(),
+            output_dir: output_dir.to_string(),
+        };
+
+        VantageProcessor {
+            rib_meta: None,
+            processor_meta,
+            coverage: DEFAULT_VANTAGE_COVERAGE,
+            peers: PeerIndex::new(),
+            pairs: Interner::new(),
+            links: Interner::new(),
+            coverages: vec![],
+        }
+    }
+
+    /// Share of the prefix-origin pairs and AS links the vantage points cover, between 0 and 1.
+    pub fn with_coverage(mut self, coverage: f64) -> Self {
+        self.coverage = coverage.clamp(0.0, 1.0);
+        self
+    }
+}
+
+/// Share of `total` items `count` represents, 1 if there are no items.
+fn share(count: u64, total: u64) -> f64 {
+    match total {
+        0 => 1.0,
+        total => count as f64 / total as f64,
+    }
+}
+
+impl TypedProcessor for VantageProcessor {
+    type Output = VantageCollectorJson;
+    type Summary = VantageSummaryJson;
+    const DATASET_FIELD: &'static str = "vantage_points";
+
+    fn processor_meta(&self) -> &ProcessorMeta {
+        &self.processor_meta
+    }
+
+    fn rib_meta(&self) -> Option<&RibMeta> {
+        self.rib_meta.as_ref()
+    }
+
+    fn new_instance(&self) -> Self {
+        Self::new(self.processor_meta.output_dir.as_str()).with_coverage(self.coverage)
+    }
+
+    fn reset_processor(&mut self, rib_meta: &RibMeta) {
+        self.rib_meta = Some(rib_meta.clone());
+        self.peers.reset(rib_meta.collector.as_str());
+    }
+
+    fn route_policy(&self) -> RoutePolicy {
+        RoutePolicy::new().with_skip_default(true)
+    }
+
+    fn shared_inputs(&self) -> Vec<SharedInput> {
+        vec![SharedInput::StrippedPath]
+    }
+
+    fn process_entry(&mut self, elem: &BgpElem) -> anyhow::Result<()> {
+        process_entry_view(self, elem)
+    }
+
+    fn process_view(&mut self, view: &ElemView) -> anyhow::Result<()> {
+        // skip processing non-announce messages, default routes are skipped by the route policy
+        if !view.is_announce() {
+            return Ok(());
+        }
+        let Some(path) = view.path() else {
+            return Ok(());
+        };
+        let peer = self.peers.index(view) as usize;
+        if self.coverages.len() <= peer {
+            self.coverages.resize(peer + 1, PeerCoverage::default());
+        }
+        let pair = self.pairs.get((view.elem().prefix.prefix, path.origin()));
+        self.coverages[peer].pairs.insert(pair);
+        for (a, b) in path.links() {
+            let link = self.links.get((a.min(b), a.max(b)));
+            self.coverages[peer].links.insert(link);
+        }
+        Ok(())
+    }
+
+    fn brief_summary(&self) -> String {
+        format!(
+            "{}: {} peers, {} prefix-origin pairs, {} AS links",
+            self.processor_meta.name,
+            format_count(self.peers.len()),
+            format_count(self.pairs.len() as usize),
+            format_count(self.links.len() as usize)
+        )
+    }
+
+    fn to_output(&self) -> Option<VantageCollectorJson> {
+        let rib_meta = self.rib_meta.as_ref()?;
+        let collector = rib_meta.collector.clone();
+        let (total_pairs, total_links) = (self.pairs.len(), self.links.len());
+        let mut covered = PeerCoverage::default();
+        let mut picked = vec![false; self.coverages.len()];
+        let mut vantage_points = vec![];
+        while share(covered.pairs.len(), total_pairs) < self.coverage
+            || share(covered.links.len(), total_links) < self.coverage
+        {
+            // peer adding the largest share of uncovered items, the first seen on ties
+            let mut best: Option<(usize, f64, u64, u64)> = None;
+            for (peer, coverage) in self.coverages.iter().enumerate() {
+                if picked[peer] {
+                    continue;
+                }
+                let new_pairs =
+                    coverage.pairs.len() - coverage.pairs.intersection_len(&covered.pairs);
+                let new_links =
+                    coverage.links.len() - coverage.links.intersection_len(&covered.links);
+                let gain = match (total_pairs, total_links) {
+                    (0, _) | (_, 0) => (new_pairs + new_links) as f64,
+                    _ => {
+                        new_pairs as f64 / total_pairs as f64
+                            + new_links as f64 / total_links as f64
+                    }
+                };
+                if gain > 0.0 && best.is_none_or(|(_, best_gain, _, _)| gain > best_gain) {
+                    best = Some((peer, gain, new_pairs, new_links));
+                }
+            }
+            let Some((peer, _, new_pairs, new_links)) = best else {
+                break;
+            };
+            picked[peer] = true;
+            let coverage = &self.coverages[peer];
+            covered.pairs |= &coverage.pairs;
+            covered.links |= &coverage.links;
+            let Some((ip, asn)) = self.peers.get(peer as u32) else {
+                continue;
+            };
+            vantage_points.push(VantagePointEntry {
+                collector: self
+                    .peers
+                    .collector(peer as u32)
+                    .unwrap_or(collector.as_str())
+                    .to_string(),
+                rank: vantage_points.len() + 1,
+                ip,
+                asn,
+                pairs: coverage.pairs.len(),
+                links: coverage.links.len(),
+                new_pairs,
+                new_links,
+                pairs_coverage: share(covered.pairs.len(), total_pairs),
+                links_coverage: share(covered.links.len(), total_links),
+            });
+        }
+
+        Some(VantageCollectorJson {
+            project: rib_meta.project.clone(),
+            collector: collector.clone(),
+            rib_dump_url: rib_meta.rib_dump_url.clone(),
+            coverage: self.coverage,
+            coverage_summary: VantageCollectorEntry {
+                collector,
+                peers_count: self
+                    .coverages
+                    .iter()
+                    .filter(|c| !c.pairs.is_empty())
+                    .count(),
+                pairs_count: total_pairs,
+                links_count: total_links,
+                vantage_points_count: vantage_points.len(),
+                pairs_coverage: share(covered.pairs.len(), total_pairs),
+                links_coverage: share(covered.links.len(), total_links),
+            },
+            vantage_points,
+        })
+    }
+
+    fn summarize(
+        &self,
+        rib_metas: &[RibMeta],
+        outputs: Vec<(RibMeta, VantageCollectorJson)>,
+    ) -> anyhow::Result<VantageSummaryJson> {
+        let mut collectors: Vec<VantageCollectorEntry> = outputs
+            .iter()
+            .map(|(_, o)| o.coverage_summary.clone())
+            .collect();
+        collectors.sort_by(|a, b| a.collector.cmp(&b.collector));
+        let coverage = outputs
+            .first()
+            .map(|(_, o)| o.coverage)
+            .unwrap_or(self.coverage);
+        let mut summary: VantageSummaryJson = crate::processors::merge::merge_outputs(
+            rib_metas,
+            outputs.into_iter().map(|(_, output)| output),
+        );
+        summary.coverage = coverage;
+        summary.collectors = collectors;
+        Ok(summary)
+    }
+}
+
+impl Mergeable for VantageCollectorJson {
+    type Entry = VantagePointEntry;
+    type Key = (String, IpAddr);
+    type Merged = VantageSummaryJson;
+
+    fn into_entries(self) -> Vec<VantagePointEntry> {
+        self.vantage_points
+    }
+
+    fn entry_key(entry: &VantagePointEntry) -> (String, IpAddr) {
+        (entry.collector.clone(), entry.ip)
+    }
+
+    fn merge_entry(_entry: &mut VantagePointEntry, _other: VantagePointEntry) {
+        // outputs of the same collector are not merged in practice, keep the first peer
+    }
+
+    /// Merge the vantage points; the coverage of each collector is filled in by
+    /// [VantageProcessor]'s summarization.
+    fn merge_files(
+        rib_metas: &[RibMeta],
+        mut entries: Vec<VantagePointEntry>,
+    ) -> VantageSummaryJson {
+        entries.sort_by(|a, b| (&a.collector, a.rank).cmp(&(&b.collector, b.rank)));
+        VantageSummaryJson {
+            rib_dump_urls: rib_dump_urls(rib_metas),
+            coverage: DEFAULT_VANTAGE_COVERAGE,
+            collectors: vec![],
+            vantage_points: entries,
+        }
+    }
+}
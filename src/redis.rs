@@ -0,0 +1,309 @@
+//! Export of summarized lookup tables to Redis.
+//!
+//! After each summarization, a [RedisExporter] replaces the tables of the pfx2as and as2rel
+//! summaries in Redis, so that services already querying Redis are fed directly:
+//!
+//! - `<prefix>:pfx2as`: hash from each prefix to its origin ASNs, comma-separated, the origin seen
+//!   by the most peers first (`HGET ribeye:pfx2as 1.1.1.0/24`)
+//! - `<prefix>:as2rel:<asn>:neighbors`, `:customers` and `:providers`: sorted sets of the
+//!   neighbors, customers and providers of an AS, scored by the number of AS paths the
+//!   relationship was inferred from (`ZREVRANGE ribeye:as2rel:13335:providers 0 -1 WITHSCORES`)
+//! - `<prefix>:as2rel:keys`: set of the as2rel keys, to remove stale ones on the next export
+//!
+//! Each table is replaced in a single `MULTI`/`EXEC` transaction, so readers never see a partial
+//! table. The exporter speaks the Redis protocol (RESP) directly over TCP.
+
+use crate::processors::{As2relSummaryJson, Prefix2AsSummaryJson};
+use anyhow::{anyhow, Result};
+use serde::Deserialize;
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::io::{BufRead, BufReader, BufWriter, Read, Write};
+use std::net::TcpStream;
+use std::time::Duration;
+
+/// Default prefix of the exported keys.
+pub const DEFAULT_KEY_PREFIX: &str = "ribeye";
+
+/// Processors whose summaries are exported, see [RedisExporter::export].
+pub const REDIS_PROCESSORS: [&str; 2] = ["pfx2as", "as2rel"];
+
+/// Fields or members set per command, to keep commands of large tables reasonably sized.
+const BATCH_SIZE: usize = 1000;
+
+/// Exporter of summarized lookup tables to a Redis server.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RedisExporter {
+    /// server URL, `redis://[:<password>@]<host>[:<port>][/<db>]`
+    url: String,
+    key_prefix: String,
+}
+
+impl RedisExporter {
+    pub fn new(url: &str) -> Self {
+        RedisExporter {
+            url: url.to_string(),
+            key_prefix: DEFAULT_KEY_PREFIX.to_string(),
+        }
+    }
+
+    /// Prefix of the exported keys (default: `ribeye`).
+    pub fn with_key_prefix(mut self, key_prefix: &str) -> Self {
+        self.key_prefix = key_prefix.to_string();
+        self
+    }
+
+    /// Export the summary of a processor, returning the number of commands of its transaction;
+    /// none for processors without lookup tables.
+    pub fn export(&self, processor: &str, summary: &Value) -> Result<Option<usize>> {
+        if !REDIS_PROCESSORS.contains(&processor) {
+            return Ok(None);
+        }
+        let mut connection = RedisConnection::connect(self.url.as_str())?;
+        let commands = match processor {
+            "pfx2as" => pfx2as_commands(
+                self.key_prefix.as_str(),
+                &Prefix2AsSummaryJson::deserialize(summary)?,
+            ),
+            _ => {
+                let key = format!("{}:as2rel:keys", self.key_prefix);
+                let stale = connection
+                    .pipeline(&[vec!["SMEMBERS".to_string(), key]])?
+                    .pop()
+                    .map(|reply| reply.into_strings())
+                    .unwrap_or_default();
+                as2rel_commands(
+                    self.key_prefix.as_str(),
+                    &As2relSummaryJson::deserialize(summary)?,
+                    &stale,
+                )
+            }
+        };
+        let count = commands.len();
+        connection.transaction(commands)?;
+        Ok(Some(count))
+    }
+}
+
+/// Commands replacing the pfx2as hash.
+pub fn pfx2as_commands(key_prefix: &str, summary: &Prefix2AsSummaryJson) -> Vec<Vec<String>> {
+    let mut origins: BTreeMap<&str, Vec<(usize, u32)>> = BTreeMap::new();
+    for entry in &summary.pfx2as {
+        origins
+            .entry(entry.prefix.as_str())
+            .or_default()
+            .push((entry.count, entry.asn));
+    }
+    let key = format!("{}:pfx2as", key_prefix);
+    let fields: Vec<(String, String)> = origins
+        .into_iter()
+        .map(|(prefix, mut asns)| {
+            asns.sort_by(|a, b| b.0.cmp(&a.0).then(a.1.cmp(&b.1)));
+            let asns: Vec<String> = asns.iter().map(|(_, asn)| asn.to_string()).collect();
+            (prefix.to_string(), asns.join(","))
+        })
+        .collect();
+    let mut commands = vec![vec!["DEL".to_string(), key.clone()]];
+    for batch in fields.chunks(BATCH_SIZE) {
+        let mut command = vec!["HSET".to_string(), key.clone()];
+        for (prefix, asns) in batch {
+            command.push(prefix.clone());
+            command.push(asns.clone());
+        }
+        commands.push(command);
+    }
+    commands
+}
+
+/// Commands replacing the as2rel sorted sets, deleting the `stale` keys of the previous export.
+pub fn as2rel_commands(
+    key_prefix: &str,
+    summary: &As2relSummaryJson,
+    stale: &[String],
+) -> Vec<Vec<String>> {
+    let mut sets: BTreeMap<String, BTreeMap<u32, usize>> = BTreeMap::new();
+    let mut add = |asn: u32, kind: &str, member: u32, score: usize| {
+        let key = format!("{}:as2rel:{}:{}", key_prefix, asn, kind);
+        *sets.entry(key).or_default().entry(member).or_default() += score;
+    };
+    for entry in &summary.as2rel {
+        match entry.rel {
+            // `asn1` is the provider of `asn2`
+            1 => {
+                add(entry.asn1, "customers", entry.asn2, entry.paths_count);
+                add(entry.asn2, "providers", entry.asn1, entry.paths_count);
+            }
+            _ => {
+                add(entry.asn1, "neighbors", entry.asn2, entry.paths_count);
+                add(entry.asn2, "neighbors", entry.asn1, entry.paths_count);
+            }
+        }
+    }
+    let keys_key = format!("{}:as2rel:keys", key_prefix);
+    let mut commands = vec![];
+    for batch in stale.chunks(BATCH_SIZE) {
+        let mut command = vec!["DEL".to_string()];
+        command.extend(batch.iter().cloned());
+        commands.push(command);
+    }
+    commands.push(vec!["DEL".to_string(), keys_key.clone()]);
+    for (key, members) in &sets {
+        let members: Vec<(&u32, &usize)> = members.iter().collect();
+        for batch in members.chunks(BATCH_SIZE) {
+            let mut command = vec!["ZADD".to_string(), key.clone()];
+            for (member, score) in batch {
+                command.push(score.to_string());
+                command.push(member.to_string());
+            }
+            commands.push(command);
+        }
+    }
+    let keys: Vec<&String> = sets.keys().collect();
+    for batch in keys.chunks(BATCH_SIZE) {
+        let mut command = vec!["SADD".to_string(), keys_key.clone()];
+        command.extend(batch.iter().map(|k| k.to_string()));
+        commands.push(command);
+    }
+    commands
+}
+
+/// Reply of a Redis server.
+#[derive(Debug, Clone, PartialEq)]
+enum Reply {
+    Status(String),
+    Integer(i64),
+    Bulk(Option<Vec<u8>>),
+    Array(Option<Vec<Reply>>),
+}
+
+impl Reply {
+    fn into_strings(self) -> Vec<String> {
+        match self {
+            Reply::Array(Some(items)) => items
+                .into_iter()
+                .filter_map(|item| match item {
+                    Reply::Bulk(Some(data)) => String::from_utf8(data).ok(),
+                    Reply::Status(s) => Some(s),
+                    _ => None,
+                })
+                .collect(),
+            _ => vec![],
+        }
+    }
+}
+
+/// Connection to a Redis server.
+struct RedisConnection {
+    reader: BufReader<TcpStream>,
+    writer: BufWriter<TcpStream>,
+}
+
+impl RedisConnection {
+    /// Connect to the server of a `redis://` URL, authenticating and selecting its database.
+    fn connect(url: &str) -> Result<Self> {
+        let rest = url
+            .strip_prefix("redis://")
+            .ok_or_else(|| anyhow!("not a redis:// URL: {}", url))?;
+        let (auth, rest) = match rest.rsplit_once('@') {
+            Some((auth, rest)) => (Some(auth), rest),
+            None => (None, rest),
+        };
+        let (address, db) = match rest.split_once('/') {
+            Some((address, db)) if !db.is_empty() => (address, Some(db)),
+            Some((address, _)) => (address, None),
+            None => (rest, None),
+        };
+        let address = match address.contains(':') {
+            true => address.to_string(),
+            false => format!("{}:6379", address),
+        };
+        let stream = TcpStream::connect(address.as_str())?;
+        stream.set_read_timeout(Some(Duration::from_secs(60)))?;
+        let mut connection = RedisConnection {
+            reader: BufReader::new(stream.try_clone()?),
+            writer: BufWriter::new(stream),
+        };
+        let mut setup = vec![];
+        if let Some(auth) = auth {
+            let mut command = vec!["AUTH".to_string()];
+            match auth.split_once(':') {
+                Some(("", password)) => command.push(password.to_string()),
+                Some((user, password)) => {
+                    command.push(user.to_string());
+                    command.push(password.to_string());
+                }
+                None => command.push(auth.to_string()),
+            }
+            setup.push(command);
+        }
+        if let Some(db) = db {
+            setup.push(vec!["SELECT".to_string(), db.to_string()]);
+        }
+        connection.pipeline(&setup)?;
+        Ok(connection)
+    }
+
+    /// Send commands without waiting for each reply, then read all replies.
+    fn pipeline(&mut self, commands: &[Vec<String>]) -> Result<Vec<Reply>> {
+        for command in commands {
+            write!(self.writer, "*{}\r\n", command.len())?;
+            for arg in command {
+                write!(self.writer, "${}\r\n", arg.len())?;
+                self.writer.write_all(arg.as_bytes())?;
+                self.writer.write_all(b"\r\n")?;
+            }
+        }
+        self.writer.flush()?;
+        commands.iter().map(|_| self.read_reply()).collect()
+    }
+
+    /// Run commands in a `MULTI`/`EXEC` transaction.
+    fn transaction(&mut self, commands: Vec<Vec<String>>) -> Result<()> {
+        let mut all = vec![vec!["MULTI".to_string()]];
+        all.extend(commands);
+        all.push(vec!["EXEC".to_string()]);
+        match self.pipeline(&all)?.pop() {
+            Some(Reply::Array(Some(_))) => Ok(()),
+            _ => Err(anyhow!("redis transaction aborted")),
+        }
+    }
+
+    fn read_line(&mut self) -> Result<String> {
+        let mut line = String::new();
+        if self.reader.read_line(&mut line)? == 0 {
+            return Err(anyhow!("redis connection closed"));
+        }
+        Ok(line.trim_end_matches(['\r', '\n']).to_string())
+    }
+
+    fn read_reply(&mut self) -> Result<Reply> {
+        let line = self.read_line()?;
+        let (kind, rest) = line.split_at(line.len().min(1));
+        match kind {
+            "+" => Ok(Reply::Status(rest.to_string())),
+            "-" => Err(anyhow!("redis error: {}", rest)),
+            ":" => Ok(Reply::Integer(rest.parse()?)),
+            "$" => {
+                let len: i64 = rest.parse()?;
+                if len < 0 {
+                    return Ok(Reply::Bulk(None));
+                }
+                let mut data = vec![0; len as usize + 2];
+                self.reader.read_exact(&mut data)?;
+                data.truncate(len as usize);
+                Ok(Reply::Bulk(Some(data)))
+            }
+            "*" => {
+                let len: i64 = rest.parse()?;
+                if len < 0 {
+                    return Ok(Reply::Array(None));
+                }
+                let items = (0..len)
+                    .map(|_| self.read_reply())
+                    .collect::<Result<Vec<_>>>()?;
+                Ok(Reply::Array(Some(items)))
+            }
+            _ => Err(anyhow!("invalid redis reply: {}", line)),
+        }
+    }
+}
@@ -0,0 +1,214 @@
+//! Error handling policy and run reports.
+//!
+//! By default, an error returned by a processor's `process_entry` aborts processing of the whole
+//! file. An [ErrorPolicy] set with [RibEye::with_error_policy](crate::RibEye::with_error_policy)
+//! allows skipping erroneous elements instead, optionally counting the errors per processor. The
+//! counts are available in the [RunReport] and added to the processors' outputs.
+
+use crate::budget::BudgetDecision;
+use crate::processors::OutputSink;
+use crate::throttle;
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fmt::{Display, Formatter};
+use std::io::Read;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// Name of the output field carrying the number of skipped elements.
+pub const PROCESSING_ERRORS_FIELD: &str = "processing_errors";
+
+/// What to do when a processor fails to process an element.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorPolicy {
+    /// stop processing and return the error
+    #[default]
+    Abort,
+    /// skip the element for the failing processor and count the error
+    SkipAndCount,
+    /// skip the element for the failing processor without keeping track of it
+    SkipSilently,
+}
+
+impl FromStr for ErrorPolicy {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().replace('_', "-").as_str() {
+            "abort" => Ok(ErrorPolicy::Abort),
+            "skip-and-count" | "count" => Ok(ErrorPolicy::SkipAndCount),
+            "skip-silently" | "skip" => Ok(ErrorPolicy::SkipSilently),
+            _ => Err(anyhow!("unknown error policy: {}", s)),
+        }
+    }
+}
+
+impl Display for ErrorPolicy {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ErrorPolicy::Abort => write!(f, "abort"),
+            ErrorPolicy::SkipAndCount => write!(f, "skip-and-count"),
+            ErrorPolicy::SkipSilently => write!(f, "skip-silently"),
+        }
+    }
+}
+
+/// Number of elements a processor failed to process.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProcessorErrors {
+    pub processor: String,
+    pub errors: u64,
+}
+
+/// Number of output bytes a processor wrote.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProcessorBytes {
+    pub processor: String,
+    pub bytes: u64,
+}
+
+/// Statistics of the elements processed for the current RIB.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProcessingStats {
+    /// number of elements processed
+    pub elems: u64,
+    pub announcements: u64,
+    pub withdrawals: u64,
+    /// number of elements with IPv4 prefixes
+    pub ipv4_elems: u64,
+    /// number of elements with IPv6 prefixes
+    pub ipv6_elems: u64,
+    /// number of distinct peers, by collector and IP address
+    pub peers: u64,
+    /// time spent reading and processing MRT data
+    pub duration_ms: u64,
+    /// size of the MRT files as stored (i.e. compressed, as downloaded), if known
+    pub file_bytes: Option<u64>,
+    /// uncompressed MRT data read
+    pub mrt_bytes: u64,
+    /// number of elements of AS_TRANS (AS23456) peers corrected with the peer's real ASN
+    #[serde(default)]
+    pub as_trans_corrected: u64,
+    /// bytes downloaded from remote MRT files: transferred by the downloader, or the stored size
+    /// of streamed files if known
+    #[serde(default)]
+    pub downloaded_bytes: u64,
+    /// bytes of processor outputs written, see [RunReport::written_bytes]
+    #[serde(default)]
+    pub written_bytes: u64,
+}
+
+/// Report of processing the current RIB.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RunReport {
+    pub collector: String,
+    /// label of the run, if any
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>,
+    /// number of elements processed
+    pub elems: u64,
+    /// per-processor error counts, only tracked with [ErrorPolicy::SkipAndCount]
+    pub errors: Vec<ProcessorErrors>,
+    pub stats: ProcessingStats,
+    /// optional processors sampled or skipped for being over budget
+    #[serde(default)]
+    pub budget_decisions: Vec<BudgetDecision>,
+    /// per-processor bytes of outputs written: sizes of local files as stored, otherwise of the
+    /// written content
+    #[serde(default)]
+    pub written_bytes: Vec<ProcessorBytes>,
+}
+
+impl RunReport {
+    /// Total number of errors across all processors.
+    pub fn total_errors(&self) -> u64 {
+        self.errors.iter().map(|e| e.errors).sum()
+    }
+}
+
+/// Reader counting the bytes read through it.
+pub(crate) struct CountingReader<R> {
+    pub inner: R,
+    pub count: u64,
+}
+
+impl<R: Read> Read for CountingReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        self.count += n as u64;
+        Ok(n)
+    }
+}
+
+/// Sink counting the bytes written through it: the size of local files as stored after each write,
+/// e.g. compressed, otherwise the size of the written content.
+pub(crate) struct ByteCountSink<'a> {
+    pub inner: &'a dyn OutputSink,
+    pub bytes: AtomicU64,
+}
+
+impl ByteCountSink<'_> {
+    fn count(&self, path: &str, content_bytes: impl FnOnce() -> u64) {
+        let bytes = match throttle::is_remote(path) || self.inner.is_stream() {
+            true => None,
+            false => std::fs::metadata(path).ok().map(|m| m.len()),
+        };
+        let bytes = bytes.unwrap_or_else(content_bytes);
+        self.bytes.fetch_add(bytes, Ordering::Relaxed);
+    }
+}
+
+impl OutputSink for ByteCountSink<'_> {
+    fn write_bytes(&self, path: &str, data: &[u8]) -> Result<()> {
+        self.inner.write_bytes(path, data)?;
+        self.count(path, || data.len() as u64);
+        Ok(())
+    }
+
+    fn write_json(&self, path: &str, value: &Value) -> Result<()> {
+        self.inner.write_json(path, value)?;
+        self.count(path, || {
+            serde_json::to_vec_pretty(value)
+                .map(|v| v.len() as u64)
+                .unwrap_or(0)
+        });
+        Ok(())
+    }
+
+    fn read_bytes(&self, path: &str) -> Result<Vec<u8>> {
+        self.inner.read_bytes(path)
+    }
+
+    fn is_stream(&self) -> bool {
+        self.inner.is_stream()
+    }
+}
+
+/// Sink adding the error count of a processor to each JSON object it writes.
+pub(crate) struct ErrorCountSink<'a> {
+    pub inner: &'a dyn OutputSink,
+    pub errors: u64,
+}
+
+impl OutputSink for ErrorCountSink<'_> {
+    fn write_bytes(&self, path: &str, data: &[u8]) -> Result<()> {
+        self.inner.write_bytes(path, data)
+    }
+
+    fn write_json(&self, path: &str, value: &Value) -> Result<()> {
+        let mut value = value.clone();
+        if let Some(obj) = value.as_object_mut() {
+            obj.insert(PROCESSING_ERRORS_FIELD.to_string(), self.errors.into());
+        }
+        self.inner.write_json(path, &value)
+    }
+
+    fn read_bytes(&self, path: &str) -> Result<Vec<u8>> {
+        self.inner.read_bytes(path)
+    }
+
+    fn is_stream(&self) -> bool {
+        self.inner.is_stream()
+    }
+}
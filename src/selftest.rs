@@ -0,0 +1,207 @@
+//! End-to-end self-test of a deployment.
+//!
+//! [Selftest::run] processes a tiny RIB dump with every built-in processor into a temporary
+//! directory, summarizes the outputs and validates the per-collector outputs and summaries
+//! against the processors' JSON schemas. The RIB dump is generated on the fly, unless an MRT file
+//! (local or remote) is given. Optionally, it also round-trips a small object through an output
+//! directory, e.g. to check object store credentials before a first `cook`.
+
+use crate::processors::schema::{processor_schemas, validate};
+use crate::processors::{FileSink, OutputSink, RibMeta};
+use crate::storage;
+use crate::testing::SyntheticRibBuilder;
+use crate::RibEye;
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::time::Instant;
+
+/// Collector name of the self-test RIB dump.
+const SELFTEST_COLLECTOR: &str = "selftest";
+
+/// Tiny RIB dump covering every processor: IPv4 and IPv6 routes, default routes, prepending and
+/// several peers announcing the same prefixes.
+pub fn selftest_rib() -> SyntheticRibBuilder {
+    SyntheticRibBuilder::new()
+        .timestamp(1_704_067_200.0)
+        .peer("192.0.2.1", 65001)
+        .communities(&["65001:100", "65001:1:2"])
+        .route("0.0.0.0/0", &[65001])
+        .route("1.1.1.0/24", &[65001, 174, 13335])
+        .route("8.8.8.0/24", &[65001, 3356, 15169])
+        .route("203.0.113.0/24", &[65001, 174, 64496, 64496, 64497])
+        .peer("192.0.2.2", 65002)
+        .communities(&[])
+        .route("1.1.1.0/24", &[65002, 3356, 13335])
+        .route("8.8.8.0/24", &[65002, 15169])
+        .peer("2001:db8::1", 65003)
+        .route("::/0", &[65003])
+        .route("2606:4700::/32", &[65003, 6939, 13335])
+}
+
+/// Outcome of one self-test step.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SelftestCheck {
+    pub name: String,
+    pub passed: bool,
+    pub detail: String,
+    pub duration_ms: u64,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SelftestReport {
+    pub checks: Vec<SelftestCheck>,
+}
+
+impl SelftestReport {
+    /// Whether all checks passed.
+    pub fn passed(&self) -> bool {
+        self.checks.iter().all(|c| c.passed)
+    }
+
+    /// Record the result of a step and return whether it passed.
+    fn check(&mut self, name: &str, start: Instant, result: Result<String>) -> bool {
+        let passed = result.is_ok();
+        self.checks.push(SelftestCheck {
+            name: name.to_string(),
+            passed,
+            detail: result.unwrap_or_else(|e| e.to_string()),
+            duration_ms: start.elapsed().as_millis() as u64,
+        });
+        passed
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Selftest {
+    mrt_file: Option<String>,
+    storage_dir: Option<String>,
+}
+
+impl Selftest {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Process the given MRT file, local or remote, instead of the generated RIB dump.
+    pub fn with_mrt_file(mut self, path: &str) -> Self {
+        self.mrt_file = Some(path.to_string());
+        self
+    }
+
+    /// Also write, read back and delete a small object under the given output directory, local
+    /// or on an object store.
+    pub fn with_storage_dir(mut self, dir: &str) -> Self {
+        self.storage_dir = Some(dir.to_string());
+        self
+    }
+
+    /// Run all steps. Failing steps are recorded in the report; steps depending on them are
+    /// skipped.
+    pub fn run(&self) -> Result<SelftestReport> {
+        let mut report = SelftestReport::default();
+        let temp_dir = tempfile::tempdir()?;
+        let output_dir = temp_dir.path().join("results");
+        let output_dir = output_dir.to_string_lossy().to_string();
+
+        let start = Instant::now();
+        let mrt_path = match &self.mrt_file {
+            Some(path) => path.clone(),
+            None => {
+                let path = temp_dir.path().join("rib.selftest.bz2");
+                let path = path.to_string_lossy().to_string();
+                let written = selftest_rib()
+                    .write_mrt_file(path.as_str())
+                    .map(|_| format!("generated {}", path));
+                if !report.check("fixture", start, written) {
+                    return Ok(report);
+                }
+                path
+            }
+        };
+
+        let start = Instant::now();
+        let rib_meta = RibMeta {
+            collector: SELFTEST_COLLECTOR.to_string(),
+            rib_dump_url: mrt_path.clone(),
+            ..Default::default()
+        };
+        let names: Vec<String> = RibEye::available_processors()
+            .iter()
+            .map(|n| n.to_string())
+            .collect();
+        let processed = RibEye::new()
+            .with_processor_names(&names, output_dir.as_str())
+            .and_then(|ribeye| {
+                let mut ribeye = ribeye.with_rib_meta(&rib_meta);
+                ribeye.process_mrt_file(mrt_path.as_str())?;
+                ribeye.summarize_latest_files(std::slice::from_ref(&rib_meta))?;
+                Ok(format!(
+                    "{} elements with {} processors",
+                    ribeye.stats().elems,
+                    names.len()
+                ))
+            });
+        if !report.check("process", start, processed) {
+            return Ok(report);
+        }
+
+        for name in &names {
+            let start = Instant::now();
+            let result = validate_outputs(output_dir.as_str(), name.as_str());
+            report.check(format!("schema:{}", name).as_str(), start, result);
+        }
+
+        if let Some(dir) = &self.storage_dir {
+            let start = Instant::now();
+            report.check("storage", start, round_trip(dir.as_str()));
+        }
+        Ok(report)
+    }
+}
+
+/// Validate the per-collector output and the summary of a processor against its schemas.
+fn validate_outputs(output_dir: &str, name: &str) -> Result<String> {
+    let schemas = processor_schemas(name).ok_or_else(|| anyhow!("no schemas for {}", name))?;
+    for (path, schema) in [
+        (
+            format!(
+                "{}/{}/{}/latest.json.bz2",
+                output_dir, name, SELFTEST_COLLECTOR
+            ),
+            &schemas.output,
+        ),
+        (
+            format!("{}/{}/latest.json.bz2", output_dir, name),
+            &schemas.summary,
+        ),
+    ] {
+        let value = FileSink.read_json(path.as_str())?;
+        let errors = validate(&value, schema);
+        if !errors.is_empty() {
+            return Err(anyhow!("{}: {}", path, errors.join(", ")));
+        }
+    }
+    Ok("output and summary match their schemas".to_string())
+}
+
+/// Write, read back and delete a small object under an output directory.
+fn round_trip(dir: &str) -> Result<String> {
+    storage::env_check(dir)?;
+    let path = format!(
+        "{}/ribeye-selftest-{}.json",
+        dir.trim_end_matches('/'),
+        std::process::id()
+    );
+    let value = json!({ "selftest": true, "pid": std::process::id() });
+    FileSink.write_json(path.as_str(), &value)?;
+    let read = FileSink.read_json(path.as_str());
+    match storage::is_object_store(path.as_str()) {
+        true => storage::delete(path.as_str())?,
+        false => std::fs::remove_file(path.as_str())?,
+    }
+    if read? != value {
+        return Err(anyhow!("{} read back different content", path));
+    }
+    Ok(format!("wrote, read back and deleted {}", path))
+}
@@ -0,0 +1,261 @@
+//! Detached Ed25519 signatures of output files.
+//!
+//! Outputs written through a [SigningSink] get a `<path>.minisig` signature next to them, in the
+//! minisign format (legacy `Ed` algorithm, signing the file content as stored, i.e. compressed).
+//! Signatures can be checked with `ribeye verify` or with `minisign -V` and the public key file
+//! written by `ribeye keygen`.
+//!
+//! Secret keys are stored unencrypted as `Ed || key id || PKCS#8 document`, base64-encoded after an
+//! untrusted comment line; keep them out of the published output directory.
+
+use crate::processors::{read_raw, OutputSink};
+use anyhow::{anyhow, Result};
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use ring::rand::{SecureRandom, SystemRandom};
+use ring::signature::{Ed25519KeyPair, KeyPair, UnparsedPublicKey, ED25519};
+use std::fmt::{Display, Formatter};
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Extension appended to the path of a signed file for its signature.
+pub const SIGNATURE_EXTENSION: &str = "minisig";
+
+/// minisign algorithm identifier of signatures over the raw file content
+const ALGORITHM: &[u8; 2] = b"Ed";
+const UNTRUSTED_PREFIX: &str = "untrusted comment: ";
+const TRUSTED_PREFIX: &str = "trusted comment: ";
+
+/// Path of the detached signature of a file.
+pub fn signature_path(path: &str) -> String {
+    format!("{}.{}", path, SIGNATURE_EXTENSION)
+}
+
+/// Hexadecimal key ID, as displayed by minisign.
+fn key_id_hex(key_id: &[u8; 8]) -> String {
+    format!("{:016X}", u64::from_le_bytes(*key_id))
+}
+
+/// Decode the base64 line following the untrusted comment of a key or signature file.
+fn decode_payload(content: &str, kind: &str) -> Result<Vec<u8>> {
+    let line = content
+        .lines()
+        .map(|l| l.trim())
+        .find(|l| !l.is_empty() && !l.starts_with(UNTRUSTED_PREFIX))
+        .ok_or_else(|| anyhow!("empty {}", kind))?;
+    let payload = BASE64
+        .decode(line)
+        .map_err(|e| anyhow!("invalid {}: {}", kind, e))?;
+    match payload.starts_with(ALGORITHM) && payload.len() > 10 {
+        true => Ok(payload),
+        false => Err(anyhow!("unsupported {}", kind)),
+    }
+}
+
+/// Ed25519 key pair signing output files.
+pub struct SigningKey {
+    key_id: [u8; 8],
+    pkcs8: Vec<u8>,
+    key_pair: Ed25519KeyPair,
+}
+
+impl SigningKey {
+    /// Generate a new key pair with a random key ID.
+    pub fn generate() -> Result<Self> {
+        let rng = SystemRandom::new();
+        let pkcs8 = Ed25519KeyPair::generate_pkcs8(&rng)
+            .map_err(|_| anyhow!("failed to generate key pair"))?;
+        let mut key_id = [0u8; 8];
+        rng.fill(&mut key_id)
+            .map_err(|_| anyhow!("failed to generate key ID"))?;
+        Self::from_parts(key_id, pkcs8.as_ref())
+    }
+
+    fn from_parts(key_id: [u8; 8], pkcs8: &[u8]) -> Result<Self> {
+        let key_pair =
+            Ed25519KeyPair::from_pkcs8(pkcs8).map_err(|e| anyhow!("invalid secret key: {}", e))?;
+        Ok(SigningKey {
+            key_id,
+            pkcs8: pkcs8.to_vec(),
+            key_pair,
+        })
+    }
+
+    /// Load a secret key file written by `ribeye keygen`.
+    pub fn load(path: &str) -> Result<Self> {
+        let content = std::fs::read_to_string(path)
+            .map_err(|e| anyhow!("failed to read secret key {}: {}", path, e))?;
+        Self::parse(content.as_str())
+    }
+
+    pub fn parse(content: &str) -> Result<Self> {
+        let payload = decode_payload(content, "secret key")?;
+        let key_id: [u8; 8] = payload[2..10].try_into()?;
+        Self::from_parts(key_id, &payload[10..])
+    }
+
+    /// Content of the secret key file.
+    pub fn to_secret_key_string(&self) -> String {
+        let payload = [ALGORITHM.as_slice(), &self.key_id, &self.pkcs8].concat();
+        format!(
+            "{}ribeye secret key {}\n{}\n",
+            UNTRUSTED_PREFIX,
+            key_id_hex(&self.key_id),
+            BASE64.encode(payload)
+        )
+    }
+
+    pub fn public_key(&self) -> PublicKey {
+        let mut key = [0u8; 32];
+        key.copy_from_slice(self.key_pair.public_key().as_ref());
+        PublicKey {
+            key_id: self.key_id,
+            key,
+        }
+    }
+
+    /// Detached minisign signature of a file's content, with the file name and signing time as
+    /// trusted comment.
+    pub fn sign(&self, data: &[u8], file_name: &str) -> String {
+        let signature = self.key_pair.sign(data);
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or_default();
+        let trusted_comment = format!("timestamp:{}\tfile:{}", timestamp, file_name);
+        let global_signature = self
+            .key_pair
+            .sign(&[signature.as_ref(), trusted_comment.as_bytes()].concat());
+        let payload = [ALGORITHM.as_slice(), &self.key_id, signature.as_ref()].concat();
+        format!(
+            "{}signature from ribeye secret key\n{}\n{}{}\n{}\n",
+            UNTRUSTED_PREFIX,
+            BASE64.encode(payload),
+            TRUSTED_PREFIX,
+            trusted_comment,
+            BASE64.encode(global_signature.as_ref())
+        )
+    }
+}
+
+/// Ed25519 public key verifying output files, in the minisign public key format.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PublicKey {
+    key_id: [u8; 8],
+    key: [u8; 32],
+}
+
+impl PublicKey {
+    /// Load a minisign public key file.
+    pub fn load(path: &str) -> Result<Self> {
+        let content = oneio::read_to_string(path)
+            .map_err(|e| anyhow!("failed to read public key {}: {}", path, e))?;
+        Self::parse(content.as_str())
+    }
+
+    /// Parse a minisign public key file, or its base64 line alone.
+    pub fn parse(content: &str) -> Result<Self> {
+        let payload = decode_payload(content, "public key")?;
+        if payload.len() != 42 {
+            return Err(anyhow!("invalid public key length"));
+        }
+        Ok(PublicKey {
+            key_id: payload[2..10].try_into()?,
+            key: payload[10..].try_into()?,
+        })
+    }
+
+    /// Verify a minisign signature of a file's content. Returns the trusted comment.
+    pub fn verify(&self, data: &[u8], signature: &str) -> Result<String> {
+        let payload = decode_payload(signature, "signature")?;
+        if payload.len() != 74 {
+            return Err(anyhow!("invalid signature length"));
+        }
+        if payload[2..10] != self.key_id {
+            return Err(anyhow!(
+                "signature key ID {} does not match public key {}",
+                key_id_hex(payload[2..10].try_into()?),
+                key_id_hex(&self.key_id)
+            ));
+        }
+        let key = UnparsedPublicKey::new(&ED25519, self.key);
+        key.verify(data, &payload[10..])
+            .map_err(|_| anyhow!("invalid signature"))?;
+
+        // the trusted comment is signed together with the signature
+        let mut lines = signature
+            .lines()
+            .skip_while(|l| !l.starts_with(TRUSTED_PREFIX));
+        let trusted_comment = lines
+            .next()
+            .and_then(|l| l.strip_prefix(TRUSTED_PREFIX))
+            .ok_or_else(|| anyhow!("missing trusted comment"))?;
+        let global_signature = lines
+            .next()
+            .and_then(|l| BASE64.decode(l.trim()).ok())
+            .ok_or_else(|| anyhow!("missing global signature"))?;
+        key.verify(
+            &[&payload[10..], trusted_comment.as_bytes()].concat(),
+            &global_signature,
+        )
+        .map_err(|_| anyhow!("invalid trusted comment signature"))?;
+        Ok(trusted_comment.to_string())
+    }
+}
+
+impl Display for PublicKey {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let payload = [ALGORITHM.as_slice(), &self.key_id, &self.key].concat();
+        writeln!(
+            f,
+            "{}minisign public key {}",
+            UNTRUSTED_PREFIX,
+            key_id_hex(&self.key_id)
+        )?;
+        writeln!(f, "{}", BASE64.encode(payload))
+    }
+}
+
+/// Verify a file against its detached signature, `<path>.minisig` unless given. Returns the
+/// trusted comment.
+pub fn verify_file(path: &str, signature: Option<&str>, key: &PublicKey) -> Result<String> {
+    let signature_path = signature
+        .map(|s| s.to_string())
+        .unwrap_or_else(|| signature_path(path));
+    let signature = String::from_utf8(read_raw(signature_path.as_str())?)?;
+    key.verify(&read_raw(path)?, signature.as_str())
+}
+
+/// Sink signing every written file: the file is written through the inner sink, read back as
+/// stored, and its signature written next to it. Streamed outputs are not signed.
+pub struct SigningSink {
+    inner: Arc<dyn OutputSink>,
+    key: Arc<SigningKey>,
+}
+
+impl SigningSink {
+    pub fn new(inner: Arc<dyn OutputSink>, key: Arc<SigningKey>) -> Self {
+        SigningSink { inner, key }
+    }
+}
+
+impl OutputSink for SigningSink {
+    fn write_bytes(&self, path: &str, data: &[u8]) -> Result<()> {
+        self.inner.write_bytes(path, data)?;
+        if self.inner.is_stream() {
+            return Ok(());
+        }
+        let file_name = path.rsplit('/').next().unwrap_or(path);
+        let signature = self.key.sign(&read_raw(path)?, file_name);
+        self.inner
+            .write_bytes(signature_path(path).as_str(), signature.as_bytes())
+    }
+
+    fn read_bytes(&self, path: &str) -> Result<Vec<u8>> {
+        self.inner.read_bytes(path)
+    }
+
+    fn is_stream(&self) -> bool {
+        self.inner.is_stream()
+    }
+}
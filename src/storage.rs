@@ -0,0 +1,313 @@
+//! Object storage output paths: S3 (`s3://bucket/key`), Google Cloud Storage (`gs://bucket/key`)
+//! and Azure Blob Storage (`az://container/blob`).
+//!
+//! Objects are uploaded from and downloaded to temporary files, the same way for each store, so
+//! that sinks, leases, listings and pruning work on any of them. S3 goes through oneio and its
+//! `AWS_*` environment variables. The other stores use their REST APIs:
+//!
+//! - Google Cloud Storage: JSON API with an OAuth 2 access token in `GCS_ACCESS_TOKEN` (e.g. from
+//!   `gcloud auth print-access-token`); `GCS_ENDPOINT` overrides `https://storage.googleapis.com`;
+//! - Azure Blob Storage: storage account `AZURE_STORAGE_ACCOUNT` with a shared access signature
+//!   in `AZURE_STORAGE_SAS_TOKEN`; `AZURE_STORAGE_ENDPOINT` overrides
+//!   `https://<account>.blob.core.windows.net`.
+
+use anyhow::{anyhow, Result};
+use reqwest::blocking::{Client, RequestBuilder, Response};
+use serde_json::Value;
+
+/// Object store of an output path.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ObjectStore {
+    S3,
+    Gcs,
+    Azure,
+}
+
+impl ObjectStore {
+    /// Object store of a path, if it is an object store URL.
+    pub fn of(path: &str) -> Option<ObjectStore> {
+        match path.split_once("://")?.0 {
+            "s3" => Some(ObjectStore::S3),
+            "gs" => Some(ObjectStore::Gcs),
+            "az" => Some(ObjectStore::Azure),
+            _ => None,
+        }
+    }
+}
+
+/// Check if a path refers to an object in S3, Google Cloud Storage or Azure Blob Storage.
+pub fn is_object_store(path: &str) -> bool {
+    ObjectStore::of(path).is_some()
+}
+
+/// Split an object store URL into its store, bucket (or container) and key.
+pub fn parse_object_url(path: &str) -> Result<(ObjectStore, String, String)> {
+    let store =
+        ObjectStore::of(path).ok_or_else(|| anyhow!("not an object store URL: {}", path))?;
+    let (_, rest) = path.split_once("://").unwrap_or_default();
+    let (bucket, key) = rest.split_once('/').unwrap_or((rest, ""));
+    if bucket.is_empty() {
+        return Err(anyhow!("missing bucket in {}", path));
+    }
+    Ok((store, bucket.to_string(), key.to_string()))
+}
+
+/// Check that the credentials of the object store of a path are set.
+pub fn env_check(path: &str) -> Result<()> {
+    let vars: &[&str] = match ObjectStore::of(path) {
+        Some(ObjectStore::S3) => return Ok(oneio::s3_env_check()?),
+        Some(ObjectStore::Gcs) => &["GCS_ACCESS_TOKEN"],
+        Some(ObjectStore::Azure) => &["AZURE_STORAGE_ACCOUNT", "AZURE_STORAGE_SAS_TOKEN"],
+        None => return Ok(()),
+    };
+    match vars.iter().find(|v| std::env::var(v).is_err()) {
+        Some(missing) => Err(anyhow!("{} not set for {}", missing, path)),
+        None => Ok(()),
+    }
+}
+
+/// Upload a local file to an object store URL.
+pub fn upload(path: &str, local_path: &str) -> Result<()> {
+    let (store, bucket, key) = parse_object_url(path)?;
+    match store {
+        ObjectStore::S3 => oneio::s3_upload(bucket.as_str(), key.as_str(), local_path)?,
+        ObjectStore::Gcs => {
+            let url = format!(
+                "{}/upload/storage/v1/b/{}/o?uploadType=media&name={}",
+                gcs_endpoint(),
+                bucket,
+                encode(key.as_str(), false)
+            );
+            send(gcs(Client::new().post(url))?.body(std::fs::read(local_path)?))?;
+        }
+        ObjectStore::Azure => {
+            let request = Client::new()
+                .put(azure_url(bucket.as_str(), key.as_str(), "")?)
+                .header("x-ms-blob-type", "BlockBlob")
+                .body(std::fs::read(local_path)?);
+            send(request)?;
+        }
+    }
+    Ok(())
+}
+
+/// Download an object store URL to a local file.
+pub fn download(path: &str, local_path: &str) -> Result<()> {
+    let (store, bucket, key) = parse_object_url(path)?;
+    let request = match store {
+        ObjectStore::S3 => {
+            oneio::s3_download(bucket.as_str(), key.as_str(), local_path)?;
+            return Ok(());
+        }
+        ObjectStore::Gcs => {
+            gcs(Client::new().get(gcs_object_url(bucket.as_str(), key.as_str()) + "?alt=media"))?
+        }
+        ObjectStore::Azure => Client::new().get(azure_url(bucket.as_str(), key.as_str(), "")?),
+    };
+    std::fs::write(local_path, send(request)?.bytes()?)?;
+    Ok(())
+}
+
+/// Delete an object.
+pub fn delete(path: &str) -> Result<()> {
+    let (store, bucket, key) = parse_object_url(path)?;
+    let request = match store {
+        ObjectStore::S3 => {
+            oneio::s3_delete(bucket.as_str(), key.as_str())?;
+            return Ok(());
+        }
+        ObjectStore::Gcs => {
+            gcs(Client::new().delete(gcs_object_url(bucket.as_str(), key.as_str())))?
+        }
+        ObjectStore::Azure => Client::new().delete(azure_url(bucket.as_str(), key.as_str(), "")?),
+    };
+    send(request)?;
+    Ok(())
+}
+
+/// Entity tag of an object, which changes whenever the object is rewritten; `None` if the store
+/// does not report one.
+pub fn etag(path: &str) -> Result<Option<String>> {
+    let (store, bucket, key) = parse_object_url(path)?;
+    let etag = match store {
+        ObjectStore::S3 => {
+            let stats = oneio::s3_stats(bucket.as_str(), key.as_str())?;
+            match (stats.e_tag, stats.last_modified, stats.content_length) {
+                (Some(e_tag), _, _) => Some(e_tag),
+                (None, Some(modified), Some(length)) => Some(format!("{}-{}", length, modified)),
+                _ => None,
+            }
+        }
+        ObjectStore::Gcs => {
+            let request = gcs(Client::new().get(gcs_object_url(bucket.as_str(), key.as_str())))?;
+            let metadata: Value = send(request)?.json()?;
+            metadata["etag"].as_str().map(|e| e.to_string())
+        }
+        ObjectStore::Azure => {
+            let request = Client::new().head(azure_url(bucket.as_str(), key.as_str(), "")?);
+            send(request)?
+                .headers()
+                .get(reqwest::header::ETAG)
+                .and_then(|e| e.to_str().ok())
+                .map(|e| e.to_string())
+        }
+    };
+    Ok(etag)
+}
+
+/// Keys of all objects whose key starts with the key of an object store URL.
+pub fn list(path: &str) -> Result<Vec<String>> {
+    let (store, bucket, prefix) = parse_object_url(path)?;
+    if store == ObjectStore::S3 {
+        return Ok(oneio::s3_list(
+            bucket.as_str(),
+            prefix.as_str(),
+            None,
+            false,
+        )?);
+    }
+    let mut keys = vec![];
+    let mut page: Option<String> = None;
+    loop {
+        page = match store {
+            ObjectStore::S3 => unreachable!(),
+            ObjectStore::Gcs => {
+                let mut url = format!(
+                    "{}/storage/v1/b/{}/o?prefix={}",
+                    gcs_endpoint(),
+                    bucket,
+                    encode(prefix.as_str(), false)
+                );
+                if let Some(token) = &page {
+                    url = format!("{}&pageToken={}", url, encode(token, false));
+                }
+                let listing: Value = send(gcs(Client::new().get(url))?)?.json()?;
+                keys.extend(
+                    listing["items"]
+                        .as_array()
+                        .into_iter()
+                        .flatten()
+                        .filter_map(|item| item["name"].as_str().map(|n| n.to_string())),
+                );
+                listing["nextPageToken"]
+                    .as_str()
+                    .filter(|t| !t.is_empty())
+                    .map(|t| t.to_string())
+            }
+            ObjectStore::Azure => {
+                let mut query = format!(
+                    "restype=container&comp=list&prefix={}",
+                    encode(prefix.as_str(), false)
+                );
+                if let Some(marker) = &page {
+                    query = format!("{}&marker={}", query, encode(marker, false));
+                }
+                let url = azure_url(bucket.as_str(), "", query.as_str())?;
+                let listing = send(Client::new().get(url))?.text()?;
+                keys.extend(xml_values(listing.as_str(), "Name"));
+                xml_values(listing.as_str(), "NextMarker")
+                    .into_iter()
+                    .find(|m| !m.is_empty())
+            }
+        };
+        if page.is_none() {
+            return Ok(keys);
+        }
+    }
+}
+
+fn gcs_endpoint() -> String {
+    std::env::var("GCS_ENDPOINT")
+        .unwrap_or_else(|_| "https://storage.googleapis.com".to_string())
+        .trim_end_matches('/')
+        .to_string()
+}
+
+fn gcs_object_url(bucket: &str, key: &str) -> String {
+    format!(
+        "{}/storage/v1/b/{}/o/{}",
+        gcs_endpoint(),
+        bucket,
+        encode(key, false)
+    )
+}
+
+/// Authorize a Google Cloud Storage request.
+fn gcs(request: RequestBuilder) -> Result<RequestBuilder> {
+    let token =
+        std::env::var("GCS_ACCESS_TOKEN").map_err(|_| anyhow!("GCS_ACCESS_TOKEN not set"))?;
+    Ok(request.bearer_auth(token))
+}
+
+/// URL of an Azure blob, or of the container for an empty blob name, authorized with the shared
+/// access signature.
+fn azure_url(container: &str, blob: &str, query: &str) -> Result<String> {
+    let account = std::env::var("AZURE_STORAGE_ACCOUNT")
+        .map_err(|_| anyhow!("AZURE_STORAGE_ACCOUNT not set"))?;
+    let sas = std::env::var("AZURE_STORAGE_SAS_TOKEN")
+        .map_err(|_| anyhow!("AZURE_STORAGE_SAS_TOKEN not set"))?;
+    let endpoint = std::env::var("AZURE_STORAGE_ENDPOINT")
+        .unwrap_or_else(|_| format!("https://{}.blob.core.windows.net", account));
+    let mut url = format!("{}/{}", endpoint.trim_end_matches('/'), container);
+    if !blob.is_empty() {
+        url = format!("{}/{}", url, encode(blob, true));
+    }
+    let sas = sas.trim_start_matches('?');
+    Ok(match query.is_empty() {
+        true => format!("{}?{}", url, sas),
+        false => format!("{}?{}&{}", url, query, sas),
+    })
+}
+
+/// Send a request, failing on error statuses.
+fn send(request: RequestBuilder) -> Result<Response> {
+    let response = request.send()?;
+    let status = response.status();
+    if !status.is_success() {
+        let body = response.text().unwrap_or_default();
+        return Err(anyhow!(
+            "object store request failed with {}: {}",
+            status,
+            body
+        ));
+    }
+    Ok(response)
+}
+
+/// Percent-encode a URL component, optionally keeping `/` separators.
+fn encode(value: &str, keep_slash: bool) -> String {
+    let mut encoded = String::new();
+    for byte in value.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                encoded.push(byte as char)
+            }
+            b'/' if keep_slash => encoded.push('/'),
+            _ => encoded.push_str(format!("%{:02X}", byte).as_str()),
+        }
+    }
+    encoded
+}
+
+/// Text of all elements of the given name in an XML document, with entities decoded.
+fn xml_values(xml: &str, name: &str) -> Vec<String> {
+    let (open, close) = (format!("<{}>", name), format!("</{}>", name));
+    let mut values = vec![];
+    let mut rest = xml;
+    while let Some(start) = rest.find(open.as_str()) {
+        rest = &rest[start + open.len()..];
+        let Some(end) = rest.find(close.as_str()) else {
+            break;
+        };
+        values.push(
+            rest[..end]
+                .replace("&lt;", "<")
+                .replace("&gt;", ">")
+                .replace("&quot;", "\"")
+                .replace("&apos;", "'")
+                .replace("&amp;", "&"),
+        );
+        rest = &rest[end + close.len()..];
+    }
+    values
+}
@@ -0,0 +1,444 @@
+//! Test harness for processors.
+//!
+//! This module provides building blocks for unit-testing processors without downloading real RIB
+//! dumps:
+//! - [SyntheticRibBuilder] generates [BgpElem] sequences with controlled peers, prefixes and AS
+//!   paths, and can write them out as small TABLE_DUMP_V2 or legacy TABLE_DUMP (v1) MRT files;
+//! - [RecordingProcessor] records everything the pipeline hands to it;
+//! - [FailingProcessor] returns an error for every N-th element, to exercise error handling;
+//! - [collector_output] runs a [TypedProcessor] over elements of one collector and returns its
+//!   typed output.
+
+use crate::processors::TypedProcessor;
+use crate::{MessageProcessor, OutputSink, RibMeta};
+use anyhow::{anyhow, Result};
+use bgpkit_parser::encoder::MrtRibEncoder;
+use bgpkit_parser::models::{
+    AsPath, Asn, Attribute, AttributeValue, Attributes, CommonHeader, Community, ElemType,
+    EntryType, LargeCommunity, MetaCommunity, NetworkPrefix, Origin, TableDumpMessage,
+};
+use bgpkit_parser::BgpElem;
+use serde_json::Value;
+use std::io::Write;
+use std::net::IpAddr;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+
+/// Builder of synthetic RIB content.
+///
+/// Routes are added for the most recently set peer:
+/// ```
+/// use ribeye::testing::SyntheticRibBuilder;
+///
+/// let elems = SyntheticRibBuilder::new()
+///     .peer("10.0.0.1", 65001)
+///     .route("1.1.1.0/24", &[65001, 174, 13335])
+///     .peer("2001:db8::1", 65002)
+///     .route("2606:4700::/32", &[65002, 13335])
+///     .build();
+/// assert_eq!(elems.len(), 2);
+/// ```
+#[derive(Debug, Clone)]
+pub struct SyntheticRibBuilder {
+    timestamp: f64,
+    peer: Option<(IpAddr, u32)>,
+    communities: Vec<MetaCommunity>,
+    elems: Vec<BgpElem>,
+}
+
+impl Default for SyntheticRibBuilder {
+    fn default() -> Self {
+        SyntheticRibBuilder {
+            timestamp: 1_700_000_000.0,
+            peer: None,
+            communities: vec![],
+            elems: vec![],
+        }
+    }
+}
+
+impl SyntheticRibBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the timestamp of subsequently added elements.
+    pub fn timestamp(mut self, timestamp: f64) -> Self {
+        self.timestamp = timestamp;
+        self
+    }
+
+    /// Set the peer of subsequently added elements.
+    ///
+    /// Panics if `ip` is not a valid IP address.
+    pub fn peer(mut self, ip: &str, asn: u32) -> Self {
+        let ip = IpAddr::from_str(ip).expect("invalid peer IP address");
+        self.peer = Some((ip, asn));
+        self
+    }
+
+    /// Set the communities of subsequently added routes, as `asn:value` or large communities as
+    /// `asn:data1:data2`; an empty slice clears them.
+    ///
+    /// Panics if a community is invalid.
+    pub fn communities(mut self, communities: &[&str]) -> Self {
+        self.communities = communities
+            .iter()
+            .map(|c| {
+                let fields: Vec<u32> = c
+                    .split(':')
+                    .map(|f| f.parse().expect("invalid community"))
+                    .collect();
+                match fields.as_slice() {
+                    [asn, value] => MetaCommunity::Plain(Community::Custom(
+                        (*asn).into(),
+                        u16::try_from(*value).expect("invalid community"),
+                    )),
+                    [asn, data1, data2] => {
+                        MetaCommunity::Large(LargeCommunity::new(*asn, [*data1, *data2]))
+                    }
+                    _ => panic!("invalid community: {}", c),
+                }
+            })
+            .collect();
+        self
+    }
+
+    fn new_elem(&self, elem_type: ElemType, prefix: &str) -> BgpElem {
+        let (peer_ip, peer_asn) = self.peer.expect("peer must be set before adding routes");
+        BgpElem {
+            timestamp: self.timestamp,
+            elem_type,
+            peer_ip,
+            peer_asn: peer_asn.into(),
+            prefix: NetworkPrefix::from_str(prefix).expect("invalid prefix"),
+            next_hop: Some(peer_ip),
+            ..Default::default()
+        }
+    }
+
+    /// Add an announcement of `prefix` with the given AS path (peer first, origin last).
+    ///
+    /// Panics if no peer is set or `prefix` is not a valid prefix.
+    pub fn route(mut self, prefix: &str, path: &[u32]) -> Self {
+        let mut elem = self.new_elem(ElemType::ANNOUNCE, prefix);
+        elem.as_path = Some(AsPath::from_sequence(path));
+        elem.origin_asns = path.last().map(|origin| vec![(*origin).into()]);
+        if !self.communities.is_empty() {
+            elem.communities = Some(self.communities.clone());
+        }
+        self.elems.push(elem);
+        self
+    }
+
+    /// Add a withdrawal of `prefix`.
+    ///
+    /// Withdrawals are not part of RIB dumps and are skipped when writing MRT files.
+    pub fn withdrawal(mut self, prefix: &str) -> Self {
+        let elem = self.new_elem(ElemType::WITHDRAW, prefix);
+        self.elems.push(elem);
+        self
+    }
+
+    /// Add a full mesh of generated routes: each of the `num_peers` peers (AS 65001, 65002, ...)
+    /// announces `num_prefixes` /24 prefixes under 10.0.0.0/8, the i-th prefix originated by
+    /// AS 64512+i through transit AS 64500+(i mod 3).
+    pub fn generated(mut self, num_peers: usize, num_prefixes: usize) -> Self {
+        for p in 0..num_peers {
+            let peer_asn = 65001 + p as u32;
+            self = self.peer(format!("192.0.2.{}", p + 1).as_str(), peer_asn);
+            for i in 0..num_prefixes {
+                let prefix = format!("10.{}.{}.0/24", (i / 256) % 256, i % 256);
+                let path = [peer_asn, 64500 + (i % 3) as u32, 64512 + i as u32];
+                self = self.route(prefix.as_str(), &path);
+            }
+        }
+        self
+    }
+
+    /// Elements added so far.
+    pub fn elems(&self) -> &[BgpElem] {
+        self.elems.as_slice()
+    }
+
+    pub fn build(self) -> Vec<BgpElem> {
+        self.elems
+    }
+
+    /// Encode the announcements as an uncompressed TABLE_DUMP_V2 RIB dump.
+    pub fn to_mrt_bytes(&self) -> Vec<u8> {
+        let mut encoder = MrtRibEncoder::new();
+        for elem in &self.elems {
+            if elem.elem_type == ElemType::ANNOUNCE {
+                encoder.process_elem(elem);
+            }
+        }
+        encoder.export_bytes().to_vec()
+    }
+
+    /// Write the announcements to an MRT file, compressed according to the file extension
+    /// (e.g. `.bz2`, `.gz`).
+    pub fn write_mrt_file(&self, path: &str) -> Result<()> {
+        let mut writer = oneio::get_writer(path)?;
+        writer.write_all(self.to_mrt_bytes().as_slice())?;
+        Ok(())
+    }
+
+    /// Encode the announcements as an uncompressed legacy TABLE_DUMP (v1) RIB dump.
+    ///
+    /// As in real v1 archives, 4-byte ASNs are written as `AS_TRANS` in the peer ASN and AS path,
+    /// with the full path in an AS4_PATH attribute.
+    pub fn to_table_dump_v1_bytes(&self) -> Vec<u8> {
+        let mut bytes = vec![];
+        let announcements = self
+            .elems
+            .iter()
+            .filter(|e| e.elem_type == ElemType::ANNOUNCE);
+        for (seq, elem) in announcements.enumerate() {
+            let msg = table_dump_v1_message(elem, seq as u16);
+            let entry_subtype = match elem.prefix.prefix.addr() {
+                IpAddr::V4(_) => 1,
+                IpAddr::V6(_) => 2,
+            };
+            let msg_bytes = msg.encode();
+            let header = CommonHeader {
+                timestamp: elem.timestamp as u32,
+                microsecond_timestamp: None,
+                entry_type: EntryType::TABLE_DUMP,
+                entry_subtype,
+                length: msg_bytes.len() as u32,
+            };
+            bytes.extend_from_slice(&header.encode());
+            bytes.extend_from_slice(&msg_bytes);
+        }
+        bytes
+    }
+
+    /// Write the announcements to a legacy TABLE_DUMP (v1) MRT file, compressed according to the
+    /// file extension.
+    pub fn write_table_dump_v1_file(&self, path: &str) -> Result<()> {
+        let mut writer = oneio::get_writer(path)?;
+        writer.write_all(self.to_table_dump_v1_bytes().as_slice())?;
+        Ok(())
+    }
+}
+
+/// Build the TABLE_DUMP (v1) entry of an announcement.
+fn table_dump_v1_message(elem: &BgpElem, sequence_number: u16) -> TableDumpMessage {
+    let to_2byte = |asn: Asn| match asn.is_four_byte() {
+        true => Asn::TRANSITION,
+        false => asn,
+    };
+    let path: Vec<u32> = elem
+        .as_path
+        .as_ref()
+        .and_then(|p| p.to_u32_vec_opt(false))
+        .unwrap_or_default();
+    let path_2byte: Vec<u32> = path
+        .iter()
+        .map(|asn| to_2byte((*asn).into()).to_u32())
+        .collect();
+
+    let mut attributes = vec![
+        Attribute::from(AttributeValue::Origin(Origin::IGP)),
+        Attribute::from(AttributeValue::from(AsPath::from_sequence(&path_2byte))),
+    ];
+    if let Some(next_hop) = elem.next_hop {
+        attributes.push(Attribute::from(AttributeValue::NextHop(next_hop)));
+    }
+    if path_2byte != path {
+        attributes.push(Attribute::from(AttributeValue::AsPath {
+            path: AsPath::from_sequence(&path),
+            is_as4: true,
+        }));
+    }
+
+    TableDumpMessage {
+        view_number: 0,
+        sequence_number,
+        prefix: elem.prefix,
+        status: 1,
+        originated_time: elem.timestamp as u64,
+        peer_address: elem.peer_ip,
+        peer_asn: to_2byte(elem.peer_asn),
+        attributes: Attributes::from(attributes),
+    }
+}
+
+/// Everything a [RecordingProcessor] has been handed by the pipeline.
+#[derive(Debug, Default, Clone)]
+pub struct Recording {
+    /// elements passed to `process_entry`
+    pub elems: Vec<BgpElem>,
+    /// meta information passed to `reset_processor`
+    pub rib_metas: Vec<RibMeta>,
+    /// number of `output` calls
+    pub outputs: usize,
+    /// meta information passed to each `summarize_latest` call
+    pub summaries: Vec<Vec<RibMeta>>,
+}
+
+/// Processor that records all calls made to it.
+///
+/// The recording is shared, so it can still be inspected after the processor is boxed into a
+/// pipeline:
+/// ```
+/// use ribeye::testing::{RecordingProcessor, SyntheticRibBuilder};
+/// use ribeye::{MessageProcessor, RibEye};
+///
+/// let processor = RecordingProcessor::new("recorder");
+/// let recording = processor.recording();
+/// let mut ribeye = RibEye::new().with_processors(vec![processor.to_boxed()]);
+/// for elem in SyntheticRibBuilder::new().generated(2, 3).elems() {
+///     ribeye.process_elem(elem).unwrap();
+/// }
+/// assert_eq!(recording.lock().unwrap().elems.len(), 6);
+/// ```
+#[derive(Debug, Clone)]
+pub struct RecordingProcessor {
+    name: String,
+    recording: Arc<Mutex<Recording>>,
+}
+
+impl RecordingProcessor {
+    pub fn new(name: &str) -> Self {
+        RecordingProcessor {
+            name: name.to_string(),
+            recording: Arc::new(Mutex::new(Recording::default())),
+        }
+    }
+
+    /// Shared handle to the recorded calls.
+    pub fn recording(&self) -> Arc<Mutex<Recording>> {
+        self.recording.clone()
+    }
+}
+
+impl MessageProcessor for RecordingProcessor {
+    fn name(&self) -> String {
+        self.name.clone()
+    }
+
+    /// New instances share the recording of this processor.
+    fn new_instance(&self) -> Box<dyn MessageProcessor> {
+        Box::new(RecordingProcessor {
+            name: self.name.clone(),
+            recording: self.recording.clone(),
+        })
+    }
+
+    fn output_paths(&self) -> Option<Vec<String>> {
+        None
+    }
+
+    fn reset_processor(&mut self, rib_meta: &RibMeta) {
+        self.recording
+            .lock()
+            .unwrap()
+            .rib_metas
+            .push(rib_meta.clone());
+    }
+
+    fn process_entry(&mut self, elem: &BgpElem) -> Result<()> {
+        self.recording.lock().unwrap().elems.push(elem.clone());
+        Ok(())
+    }
+
+    fn output(&mut self, _sink: &dyn OutputSink) -> Result<()> {
+        self.recording.lock().unwrap().outputs += 1;
+        Ok(())
+    }
+
+    fn summarize_latest(
+        &self,
+        rib_metas: &[RibMeta],
+        _ignore_error: bool,
+    ) -> Result<Option<Value>> {
+        self.recording
+            .lock()
+            .unwrap()
+            .summaries
+            .push(rib_metas.to_vec());
+        Ok(None)
+    }
+}
+
+/// Processor that fails on every `fail_every`-th element it receives.
+#[derive(Debug, Clone)]
+pub struct FailingProcessor {
+    fail_every: usize,
+    count: usize,
+}
+
+impl FailingProcessor {
+    pub fn new(fail_every: usize) -> Self {
+        FailingProcessor {
+            fail_every: fail_every.max(1),
+            count: 0,
+        }
+    }
+}
+
+impl MessageProcessor for FailingProcessor {
+    fn name(&self) -> String {
+        "failing".to_string()
+    }
+
+    fn new_instance(&self) -> Box<dyn MessageProcessor> {
+        Box::new(FailingProcessor::new(self.fail_every))
+    }
+
+    fn output_paths(&self) -> Option<Vec<String>> {
+        None
+    }
+
+    fn reset_processor(&mut self, _rib_meta: &RibMeta) {}
+
+    fn process_entry(&mut self, _elem: &BgpElem) -> Result<()> {
+        self.count += 1;
+        if self.count.is_multiple_of(self.fail_every) {
+            return Err(anyhow!("synthetic failure at element {}", self.count));
+        }
+        Ok(())
+    }
+
+    fn summarize_latest(
+        &self,
+        _rib_metas: &[RibMeta],
+        _ignore_error: bool,
+    ) -> Result<Option<Value>> {
+        Ok(None)
+    }
+}
+
+/// Run `processor` over `elems` of a RIB of `collector` and return the RIB metadata with the
+/// processor's output.
+///
+/// Panics if the processor fails on an element or produces no output.
+/// ```
+/// use ribeye::processors::Prefix2AsProcessor;
+/// use ribeye::testing::{collector_output, SyntheticRibBuilder};
+///
+/// let elems = SyntheticRibBuilder::new().generated(2, 3).build();
+/// let (rib_meta, output) = collector_output(Prefix2AsProcessor::new("-"), "rrc00", &elems);
+/// assert_eq!(rib_meta.collector, "rrc00");
+/// assert_eq!(output.pfx2as.len(), 3);
+/// ```
+pub fn collector_output<P: TypedProcessor>(
+    mut processor: P,
+    collector: &str,
+    elems: &[BgpElem],
+) -> (RibMeta, P::Output) {
+    let rib_meta = RibMeta {
+        collector: collector.to_string(),
+        ..Default::default()
+    };
+    processor.reset_processor(&rib_meta);
+    for elem in elems {
+        processor
+            .process_entry(elem)
+            .expect("processor failed on element");
+    }
+    let output = processor.to_output().expect("processor produced no output");
+    (rib_meta, output)
+}
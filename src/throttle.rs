@@ -0,0 +1,342 @@
+//! Rate limiting and concurrency caps for remote I/O.
+//!
+//! Parallel cooks open many RIB dumps on collector archives and upload many outputs to S3 at once,
+//! which gets them throttled by RIS and RouteViews mirrors. An [IoLimiter] shared by all pipelines
+//! caps the number of concurrent transfers and the rate at which new ones start (token bucket).
+//! Downloads hold their permit while the RIB is streamed, see
+//! [RibEye::with_download_limiter](crate::RibEye::with_download_limiter); uploads are throttled by
+//! a [ThrottledSink], see [RibEye::with_upload_limiter](crate::RibEye::with_upload_limiter).
+//!
+//! In metered environments, a [ByteCap] shared by all pipelines caps the total bytes downloaded,
+//! see [RibEye::with_download_cap](crate::RibEye::with_download_cap).
+
+use crate::processors::OutputSink;
+use crate::storage;
+use anyhow::{anyhow, Result};
+use serde_json::Value;
+use std::io::Read;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Condvar, Mutex};
+use std::time::{Duration, Instant};
+use tracing::debug;
+
+/// Check if a path refers to a remote file, i.e. an HTTP(S), FTP or object store URL.
+pub fn is_remote(path: &str) -> bool {
+    ["http://", "https://", "ftp://"]
+        .iter()
+        .any(|scheme| path.starts_with(scheme))
+        || storage::is_object_store(path)
+}
+
+struct TokenBucket {
+    /// tokens added per second
+    rate: f64,
+    /// maximum number of tokens, i.e. transfers started at once after an idle period
+    burst: f64,
+    tokens: f64,
+    last_refill: Instant,
+}
+
+impl TokenBucket {
+    /// Take a token, or return how long to wait for the next one.
+    fn take(&mut self) -> Option<Duration> {
+        let now = Instant::now();
+        let elapsed = now.duration_since(self.last_refill).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.rate).min(self.burst);
+        self.last_refill = now;
+        match self.tokens >= 1.0 {
+            true => {
+                self.tokens -= 1.0;
+                None
+            }
+            false => Some(Duration::from_secs_f64((1.0 - self.tokens) / self.rate)),
+        }
+    }
+}
+
+/// Limits on concurrent transfers and on the rate of new transfers, unlimited by default.
+pub struct IoLimiter {
+    max_concurrent: Option<usize>,
+    active: Mutex<usize>,
+    released: Condvar,
+    bucket: Option<Mutex<TokenBucket>>,
+}
+
+impl Default for IoLimiter {
+    fn default() -> Self {
+        IoLimiter {
+            max_concurrent: None,
+            active: Mutex::new(0),
+            released: Condvar::new(),
+            bucket: None,
+        }
+    }
+}
+
+impl IoLimiter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Allow at most `max` transfers at a time; 0 means unlimited.
+    pub fn with_max_concurrent(mut self, max: usize) -> Self {
+        self.max_concurrent = match max {
+            0 => None,
+            m => Some(m),
+        };
+        self
+    }
+
+    /// Start at most `per_second` transfers per second on average, with bursts of up to
+    /// `per_second` transfers (at least one); 0 means unlimited.
+    pub fn with_rate(mut self, per_second: f64) -> Self {
+        self.bucket = match per_second > 0.0 {
+            true => {
+                let burst = per_second.max(1.0);
+                Some(Mutex::new(TokenBucket {
+                    rate: per_second,
+                    burst,
+                    tokens: burst,
+                    last_refill: Instant::now(),
+                }))
+            }
+            false => None,
+        };
+        self
+    }
+
+    /// Number of transfers currently holding a permit.
+    pub fn active(&self) -> usize {
+        *self.active.lock().unwrap()
+    }
+
+    /// Block until a transfer may start; the returned permit ends it when dropped.
+    pub fn acquire(self: &Arc<Self>) -> IoPermit {
+        let start = Instant::now();
+        if let Some(bucket) = &self.bucket {
+            loop {
+                // bound first so that the lock is released while sleeping and waiting transfers
+                // take turns; a guard in the `while let` condition would live through the body
+                let wait = bucket.lock().unwrap().take();
+                match wait {
+                    Some(wait) => std::thread::sleep(wait),
+                    None => break,
+                }
+            }
+        }
+        let mut active = self.active.lock().unwrap();
+        if let Some(max) = self.max_concurrent {
+            while *active >= max {
+                active = self.released.wait(active).unwrap();
+            }
+        }
+        *active += 1;
+        let waited = start.elapsed();
+        if waited >= Duration::from_millis(100) {
+            debug!(
+                duration_ms = waited.as_millis() as u64,
+                "waited for remote I/O permit"
+            );
+        }
+        IoPermit {
+            limiter: self.clone(),
+        }
+    }
+}
+
+/// Permission to run one transfer, released on drop.
+pub struct IoPermit {
+    limiter: Arc<IoLimiter>,
+}
+
+impl Drop for IoPermit {
+    fn drop(&mut self) {
+        *self.limiter.active.lock().unwrap() -= 1;
+        self.limiter.released.notify_one();
+    }
+}
+
+/// Reader holding a permit until it is dropped, e.g. while a remote RIB dump is streamed.
+pub struct PermitReader<R> {
+    inner: R,
+    _permit: IoPermit,
+}
+
+impl<R> PermitReader<R> {
+    pub fn new(inner: R, permit: IoPermit) -> Self {
+        PermitReader {
+            inner,
+            _permit: permit,
+        }
+    }
+}
+
+impl<R: Read> Read for PermitReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        self.inner.read(buf)
+    }
+}
+
+/// Bytes read from a streamed remote file, shared between its [CappedReader] and the pipeline.
+#[derive(Debug, Default)]
+pub struct StreamedBytes {
+    bytes: AtomicU64,
+    over_cap: AtomicBool,
+}
+
+impl StreamedBytes {
+    /// Raw bytes read so far, as stored remotely, e.g. compressed.
+    pub fn bytes(&self) -> u64 {
+        self.bytes.load(Ordering::Relaxed)
+    }
+
+    /// Whether reading failed as the file exceeded the download cap.
+    pub fn over_cap(&self) -> bool {
+        self.over_cap.load(Ordering::Relaxed)
+    }
+}
+
+/// Reader counting the raw bytes of a streamed remote file into [StreamedBytes].
+///
+/// Bytes beyond the `reserved` ones, e.g. of a file of unknown size, are charged to the download
+/// cap as they are read, and reading fails once the cap would be exceeded. The unused part of the
+/// reservation is released when the reader is dropped.
+pub struct CappedReader<R> {
+    inner: R,
+    cap: Option<Arc<ByteCap>>,
+    reserved: u64,
+    streamed: Arc<StreamedBytes>,
+}
+
+impl<R> CappedReader<R> {
+    pub fn new(
+        inner: R,
+        cap: Option<Arc<ByteCap>>,
+        reserved: u64,
+        streamed: Arc<StreamedBytes>,
+    ) -> Self {
+        CappedReader {
+            inner,
+            cap,
+            reserved,
+            streamed,
+        }
+    }
+}
+
+impl<R: Read> Read for CappedReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        let before = self.streamed.bytes.fetch_add(n as u64, Ordering::Relaxed);
+        let after = before + n as u64;
+        let charge = after.saturating_sub(self.reserved) - before.saturating_sub(self.reserved);
+        if let (Some(cap), true) = (&self.cap, charge > 0) {
+            if let Err(e) = cap.reserve(charge) {
+                self.streamed.over_cap.store(true, Ordering::Relaxed);
+                return Err(std::io::Error::other(e));
+            }
+        }
+        Ok(n)
+    }
+}
+
+impl<R> Drop for CappedReader<R> {
+    fn drop(&mut self) {
+        if let Some(cap) = &self.cap {
+            cap.settle(self.reserved, self.streamed.bytes().min(self.reserved));
+        }
+    }
+}
+
+/// Sink taking a permit of a limiter for every write to a remote (S3) path. Local writes are not
+/// limited.
+pub struct ThrottledSink {
+    inner: Arc<dyn OutputSink>,
+    limiter: Arc<IoLimiter>,
+}
+
+impl ThrottledSink {
+    pub fn new(inner: Arc<dyn OutputSink>, limiter: Arc<IoLimiter>) -> Self {
+        ThrottledSink { inner, limiter }
+    }
+}
+
+impl OutputSink for ThrottledSink {
+    fn write_bytes(&self, path: &str, data: &[u8]) -> Result<()> {
+        let _permit = is_remote(path).then(|| self.limiter.acquire());
+        self.inner.write_bytes(path, data)
+    }
+
+    fn write_json(&self, path: &str, value: &Value) -> Result<()> {
+        let _permit = is_remote(path).then(|| self.limiter.acquire());
+        self.inner.write_json(path, value)
+    }
+
+    fn read_bytes(&self, path: &str) -> Result<Vec<u8>> {
+        self.inner.read_bytes(path)
+    }
+
+    fn is_stream(&self) -> bool {
+        self.inner.is_stream()
+    }
+}
+
+/// Cap on the total bytes downloaded by all pipelines sharing it.
+///
+/// Downloads reserve their expected size, if known, before they start and are refused once the
+/// cap would be exceeded, so parallel pipelines cannot overshoot it together. Files of unknown
+/// size start as long as the cap is not reached yet. Streamed files count the bytes actually read
+/// through a [CappedReader].
+#[derive(Debug)]
+pub struct ByteCap {
+    max_bytes: u64,
+    used: AtomicU64,
+}
+
+impl ByteCap {
+    pub fn new(max_bytes: u64) -> Self {
+        ByteCap {
+            max_bytes,
+            used: AtomicU64::new(0),
+        }
+    }
+
+    pub fn max_bytes(&self) -> u64 {
+        self.max_bytes
+    }
+
+    /// Bytes downloaded or reserved so far.
+    pub fn used(&self) -> u64 {
+        self.used.load(Ordering::Relaxed)
+    }
+
+    /// Reserve `bytes` for a download, failing if the cap is reached or would be exceeded.
+    pub fn reserve(&self, bytes: u64) -> Result<()> {
+        self.used
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |used| {
+                (used < self.max_bytes && used.saturating_add(bytes) <= self.max_bytes)
+                    .then_some(used + bytes)
+            })
+            .map(|_| ())
+            .map_err(|used| {
+                anyhow!(
+                    "download cap of {} bytes reached: {} bytes downloaded, {} more needed",
+                    self.max_bytes,
+                    used,
+                    bytes
+                )
+            })
+    }
+
+    /// Replace a reservation with the bytes actually downloaded.
+    pub fn settle(&self, reserved: u64, downloaded: u64) {
+        match downloaded >= reserved {
+            true => self
+                .used
+                .fetch_add(downloaded - reserved, Ordering::Relaxed),
+            false => self
+                .used
+                .fetch_sub(reserved - downloaded, Ordering::Relaxed),
+        };
+    }
+}
@@ -0,0 +1,114 @@
+//! Time-series output mode.
+//!
+//! Per-collector outputs overwrite `latest` and keep one dated file per RIB, which must be
+//! reprocessed or reparsed whole for longitudinal analyses. With
+//! [RibEye::with_timeseries](crate::RibEye::with_timeseries), each processed RIB additionally
+//! appends compact JSON Lines records to a dataset partitioned by date:
+//!
+//! `<dir>/timeseries/<processor>/<table>/date=<YYYY-MM-DD>/<collector>_<ts>.jsonl.gz`
+//!
+//! Each array of entries of an output (e.g. `pfx2as`, `peers`) is a table with one record per
+//! entry, and the `snapshot` table has one aggregate record per RIB with the output's other fields
+//! and the number of entries of each table. All records carry the RIB dump Unix timestamp `ts`
+//! and the `collector`. The layout follows Hive-style partitioning, so the dataset can be queried
+//! directly with tools like DuckDB or Spark.
+
+use crate::processors::{OutputSink, RibMeta};
+use anyhow::Result;
+use serde_json::{Map, Value};
+
+/// Directory under the output root directory holding the time-series datasets.
+pub const TIMESERIES_DIR: &str = "timeseries";
+
+/// Table of per-RIB aggregate records.
+pub const SNAPSHOT_TABLE: &str = "snapshot";
+
+/// Path of the records of a table for a RIB.
+pub fn get_timeseries_path(
+    output_dir: &str,
+    processor: &str,
+    table: &str,
+    rib_meta: &RibMeta,
+) -> String {
+    format!(
+        "{}/{}/{}/{}/date={}/{}_{}.jsonl.gz",
+        output_dir,
+        TIMESERIES_DIR,
+        processor,
+        table,
+        rib_meta.timestamp.format("%Y-%m-%d"),
+        rib_meta.collector,
+        rib_meta.timestamp.and_utc().timestamp()
+    )
+}
+
+/// Split a per-collector output into records per table, see the [module documentation](self).
+pub fn timeseries_records(rib_meta: &RibMeta, output: &Value) -> Vec<(String, Vec<Value>)> {
+    let ts = rib_meta.timestamp.and_utc().timestamp();
+    let fields = match output.as_object() {
+        None => return vec![],
+        Some(o) => o,
+    };
+
+    let mut snapshot = Map::new();
+    snapshot.insert("ts".to_string(), ts.into());
+    snapshot.insert("collector".to_string(), rib_meta.collector.clone().into());
+    let mut tables = vec![];
+    for (name, value) in fields {
+        let entries = match value.as_array() {
+            Some(entries) if entries.iter().all(|e| e.is_object()) => entries,
+            _ => {
+                snapshot
+                    .entry(name.clone())
+                    .or_insert_with(|| value.clone());
+                continue;
+            }
+        };
+        snapshot.insert(format!("{}_count", name), entries.len().into());
+        let records = entries
+            .iter()
+            .filter_map(|entry| {
+                let mut record = Map::new();
+                record.insert("ts".to_string(), ts.into());
+                record.insert("collector".to_string(), rib_meta.collector.clone().into());
+                for (key, value) in entry.as_object()? {
+                    record.insert(key.clone(), value.clone());
+                }
+                Some(Value::Object(record))
+            })
+            .collect();
+        tables.push((name.clone(), records));
+    }
+    tables.push((SNAPSHOT_TABLE.to_string(), vec![Value::Object(snapshot)]));
+    tables
+}
+
+/// Compact JSON Lines content of records.
+pub fn to_jsonl(records: &[Value]) -> String {
+    records
+        .iter()
+        .map(|r| format!("{}\n", r))
+        .collect::<Vec<_>>()
+        .concat()
+}
+
+/// Append the records of a processor's output for a RIB to the time-series dataset under an
+/// output root directory. Returns the number of records written.
+pub fn write_timeseries(
+    sink: &dyn OutputSink,
+    output_dir: &str,
+    processor: &str,
+    rib_meta: &RibMeta,
+    output: &Value,
+) -> Result<usize> {
+    let mut count = 0;
+    for (table, records) in timeseries_records(rib_meta, output) {
+        if records.is_empty() {
+            continue;
+        }
+        let path = get_timeseries_path(output_dir, processor, table.as_str(), rib_meta);
+        sink.write_bytes(path.as_str(), to_jsonl(&records).as_bytes())?;
+        count += records.len();
+    }
+    Ok(count)
+}
@@ -0,0 +1,253 @@
+//! Top-N views of summaries.
+//!
+//! Dashboards mostly show the largest entries of a dataset. [attach_top_views] adds them to
+//! summaries while they are written as a `top` object, so they can be read without scanning the
+//! full records; see [crate::RibEye::with_top_views]:
+//!
+//! - pfx2as: `largest_origins_v4` and `largest_origins_v6` by announced address space, counting
+//!   overlapping prefixes of an origin once, and `most_deaggregated_origins` by the number of
+//!   prefixes covered by a less-specific prefix of the same origin
+//! - as2rel: `busiest_links` by the number of paths crossing an AS link in either direction
+//! - communities: `most_prepended_asns` by the number of routes on which an AS prepends its ASN,
+//!   among the community owners the summary has baselines of
+//!
+//! Entries with equal counts are ordered by ASN.
+
+use crate::processors::{As2relSummaryJson, CommunitiesSummaryJson, Prefix2AsSummaryJson};
+use crate::util::PrefixSpace;
+use anyhow::Result;
+use ipnet::IpNet;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{BTreeMap, BTreeSet};
+
+/// Field of the top-N views in summaries.
+pub const TOP_FIELD: &str = "top";
+/// Processors whose summaries get top-N views.
+pub const TOP_VIEW_PROCESSORS: [&str; 3] = ["as2rel", "communities", "pfx2as"];
+/// Default number of entries of each view.
+pub const DEFAULT_TOP_N: usize = 10;
+
+/// Address space announced by an origin.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TopOriginSpace {
+    pub asn: u32,
+    /// announced space in units of /24 (IPv4) or /48 (IPv6) prefixes
+    pub space: f64,
+    /// number of announced prefixes of the address family
+    pub prefixes: usize,
+}
+
+/// Deaggregation of the prefixes announced by an origin.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TopDeaggregation {
+    pub asn: u32,
+    pub prefixes: usize,
+    /// prefixes covered by a less-specific prefix of the same origin
+    pub more_specifics: usize,
+}
+
+/// Prepending of an AS.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TopPrepending {
+    pub asn: u32,
+    /// routes with the AS on the path
+    pub routes: usize,
+    /// routes on which the AS prepends its ASN
+    pub prepended: usize,
+    /// share of the routes on which the AS prepends its ASN
+    pub ratio: f64,
+}
+
+/// AS link, the lower ASN first.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TopLink {
+    pub asn1: u32,
+    pub asn2: u32,
+    /// paths crossing the link in either direction
+    pub paths_count: usize,
+    /// largest number of peers observing the link in one direction
+    pub peers_count: usize,
+}
+
+/// Top-N views of a summary, only the views of its processor being set.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct TopViews {
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub largest_origins_v4: Vec<TopOriginSpace>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub largest_origins_v6: Vec<TopOriginSpace>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub most_deaggregated_origins: Vec<TopDeaggregation>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub busiest_links: Vec<TopLink>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub most_prepended_asns: Vec<TopPrepending>,
+}
+
+/// Compute the top-`n` views of a processor's summary, `None` for processors without views.
+pub fn top_views(processor: &str, summary: &Value, n: usize) -> Result<Option<TopViews>> {
+    let views = match processor {
+        "pfx2as" => {
+            let pfx2as = Prefix2AsSummaryJson::deserialize(summary)?;
+            let (largest_origins_v4, largest_origins_v6) = largest_origins(&pfx2as, n);
+            TopViews {
+                largest_origins_v4,
+                largest_origins_v6,
+                most_deaggregated_origins: most_deaggregated_origins(&pfx2as, n),
+                ..Default::default()
+            }
+        }
+        "as2rel" => TopViews {
+            busiest_links: busiest_links(&As2relSummaryJson::deserialize(summary)?, n),
+            ..Default::default()
+        },
+        "communities" => TopViews {
+            most_prepended_asns: most_prepended_asns(
+                &CommunitiesSummaryJson::deserialize(summary)?,
+                n,
+            ),
+            ..Default::default()
+        },
+        _ => return Ok(None),
+    };
+    Ok(Some(views))
+}
+
+/// Add the top-`n` views of a processor's summary as a `top` object. Returns whether views were
+/// added.
+pub fn attach_top_views(processor: &str, summary: &mut Value, n: usize) -> Result<bool> {
+    let Some(views) = top_views(processor, summary, n)? else {
+        return Ok(false);
+    };
+    let Value::Object(fields) = summary else {
+        return Ok(false);
+    };
+    fields.insert(TOP_FIELD.to_string(), serde_json::to_value(views)?);
+    Ok(true)
+}
+
+/// Unique prefixes announced by each origin.
+fn origin_prefixes(pfx2as: &Prefix2AsSummaryJson) -> BTreeMap<u32, BTreeSet<IpNet>> {
+    let mut origins: BTreeMap<u32, BTreeSet<IpNet>> = BTreeMap::new();
+    for entry in &pfx2as.pfx2as {
+        if let Ok(prefix) = entry.prefix.parse::<IpNet>() {
+            origins.entry(entry.asn).or_default().insert(prefix.trunc());
+        }
+    }
+    origins
+}
+
+fn largest_origins(
+    pfx2as: &Prefix2AsSummaryJson,
+    n: usize,
+) -> (Vec<TopOriginSpace>, Vec<TopOriginSpace>) {
+    let mut v4 = vec![];
+    let mut v6 = vec![];
+    for (asn, prefixes) in origin_prefixes(pfx2as) {
+        let (v4_prefixes, v6_prefixes): (Vec<IpNet>, Vec<IpNet>) = prefixes
+            .into_iter()
+            .partition(|p| matches!(p, IpNet::V4(_)));
+        for (prefixes, views, v4) in [(v4_prefixes, &mut v4, true), (v6_prefixes, &mut v6, false)] {
+            if prefixes.is_empty() {
+                continue;
+            }
+            let count = prefixes.len();
+            let mut space = PrefixSpace::new();
+            space.extend(prefixes);
+            views.push(TopOriginSpace {
+                asn,
+                space: match v4 {
+                    true => space.slash24_equivalents(),
+                    false => space.slash48_equivalents(),
+                },
+                prefixes: count,
+            });
+        }
+    }
+    for views in [&mut v4, &mut v6] {
+        views.sort_by(|a, b| b.space.total_cmp(&a.space).then(a.asn.cmp(&b.asn)));
+        views.truncate(n);
+    }
+    (v4, v6)
+}
+
+fn most_deaggregated_origins(pfx2as: &Prefix2AsSummaryJson, n: usize) -> Vec<TopDeaggregation> {
+    let mut views: Vec<TopDeaggregation> = origin_prefixes(pfx2as)
+        .into_iter()
+        .map(|(asn, prefixes)| TopDeaggregation {
+            asn,
+            prefixes: prefixes.len(),
+            more_specifics: count_more_specifics(&prefixes),
+        })
+        .filter(|v| v.more_specifics > 0)
+        .collect();
+    views.sort_by(|a, b| {
+        b.more_specifics
+            .cmp(&a.more_specifics)
+            .then(b.prefixes.cmp(&a.prefixes))
+            .then(a.asn.cmp(&b.asn))
+    });
+    views.truncate(n);
+    views
+}
+
+/// Number of prefixes covered by another prefix of the set.
+///
+/// Ordered by network address and then prefix length, a prefix's less-specifics precede it, so
+/// a stack of the enclosing prefixes is enough.
+fn count_more_specifics(prefixes: &BTreeSet<IpNet>) -> usize {
+    let mut enclosing: Vec<&IpNet> = vec![];
+    let mut count = 0;
+    for prefix in prefixes {
+        while enclosing.last().is_some_and(|p| !p.contains(prefix)) {
+            enclosing.pop();
+        }
+        if !enclosing.is_empty() {
+            count += 1;
+        }
+        enclosing.push(prefix);
+    }
+    count
+}
+
+fn busiest_links(as2rel: &As2relSummaryJson, n: usize) -> Vec<TopLink> {
+    let mut links: BTreeMap<(u32, u32), TopLink> = BTreeMap::new();
+    // adjacencies in path order, inferred relationships count the same paths again
+    for entry in as2rel.as2rel.iter().filter(|e| e.rel == 0) {
+        let (asn1, asn2) = (entry.asn1.min(entry.asn2), entry.asn1.max(entry.asn2));
+        let link = links.entry((asn1, asn2)).or_insert(TopLink {
+            asn1,
+            asn2,
+            paths_count: 0,
+            peers_count: 0,
+        });
+        link.paths_count += entry.paths_count;
+        link.peers_count = link.peers_count.max(entry.peers_count);
+    }
+    let mut views: Vec<TopLink> = links.into_values().collect();
+    views.sort_by(|a, b| {
+        b.paths_count
+            .cmp(&a.paths_count)
+            .then((a.asn1, a.asn2).cmp(&(b.asn1, b.asn2)))
+    });
+    views.truncate(n);
+    views
+}
+
+fn most_prepended_asns(communities: &CommunitiesSummaryJson, n: usize) -> Vec<TopPrepending> {
+    let mut views: Vec<TopPrepending> = communities
+        .owners
+        .iter()
+        .filter(|o| o.prepended > 0)
+        .map(|o| TopPrepending {
+            asn: o.asn,
+            routes: o.routes,
+            prepended: o.prepended,
+            ratio: o.prepended as f64 / o.routes.max(1) as f64,
+        })
+        .collect();
+    views.sort_by(|a, b| b.prepended.cmp(&a.prepended).then(a.asn.cmp(&b.asn)));
+    views.truncate(n);
+    views
+}
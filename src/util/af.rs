@@ -0,0 +1,44 @@
+use anyhow::{anyhow, Result};
+use bgpkit_parser::BgpElem;
+use serde::{Deserialize, Serialize};
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+/// Address family of prefixes, used to limit a pipeline to IPv4 or IPv6 elements.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AddressFamily {
+    V4,
+    V6,
+}
+
+impl AddressFamily {
+    /// Whether the element's prefix belongs to the address family.
+    pub fn matches(&self, elem: &BgpElem) -> bool {
+        match self {
+            AddressFamily::V4 => elem.prefix.prefix.addr().is_ipv4(),
+            AddressFamily::V6 => elem.prefix.prefix.addr().is_ipv6(),
+        }
+    }
+}
+
+impl FromStr for AddressFamily {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "v4" | "ipv4" | "4" => Ok(AddressFamily::V4),
+            "v6" | "ipv6" | "6" => Ok(AddressFamily::V6),
+            _ => Err(anyhow!("unknown address family: {}", s)),
+        }
+    }
+}
+
+impl Display for AddressFamily {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AddressFamily::V4 => write!(f, "v4"),
+            AddressFamily::V6 => write!(f, "v6"),
+        }
+    }
+}
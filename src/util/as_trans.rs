@@ -0,0 +1,68 @@
+use bgpkit_parser::models::{AsPathSegment, Asn};
+use bgpkit_parser::BgpElem;
+use std::collections::HashMap;
+use std::net::IpAddr;
+
+/// AS_TRANS (RFC 6793), reported in place of 4-byte ASNs by 2-byte BGP speakers.
+pub const AS_TRANS: u32 = 23456;
+
+/// Resolver of the real 4-byte ASN of peers reported as [AS_TRANS].
+///
+/// RIB dumps take peer ASNs from the MRT peer index table, which still lists peers with 2-byte
+/// sessions as AS23456. The peer's real ASN is the first hop of the AS paths it announces (merged
+/// with AS4_PATH by the parser), so the resolver takes it from there and remembers it per peer IP
+/// for the peer's withdrawals and AS_SET-led paths. Elements of peers not resolved yet are left
+/// as they are.
+#[derive(Debug, Clone, Default)]
+pub struct AsTransResolver {
+    peers: HashMap<IpAddr, u32>,
+}
+
+impl AsTransResolver {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Corrected copy of an element of an AS_TRANS peer, with the peer ASN and leading AS_TRANS
+    /// hops of the AS path replaced by the peer's real ASN. None if the element needs no
+    /// correction or its peer cannot be resolved.
+    pub fn correct(&mut self, elem: &BgpElem) -> Option<BgpElem> {
+        if elem.peer_asn.to_u32() != AS_TRANS {
+            return None;
+        }
+        let first_hop = elem
+            .as_path
+            .as_ref()
+            .and_then(|path| match path.iter_segments().next() {
+                Some(AsPathSegment::AsSequence(asns)) => asns.first().map(|asn| asn.to_u32()),
+                _ => None,
+            });
+        let asn = match first_hop.filter(|asn| *asn != AS_TRANS) {
+            Some(asn) => {
+                self.peers.insert(elem.peer_ip, asn);
+                asn
+            }
+            None => *self.peers.get(&elem.peer_ip)?,
+        };
+
+        let mut corrected = elem.clone();
+        corrected.peer_asn = Asn::from(asn);
+        if let Some(path) = corrected.as_path.as_mut() {
+            if let Some(AsPathSegment::AsSequence(asns)) = path.iter_segments_mut().next() {
+                for hop in asns.iter_mut().take_while(|hop| hop.to_u32() == AS_TRANS) {
+                    *hop = Asn::from(asn);
+                }
+            }
+        }
+        Some(corrected)
+    }
+
+    /// Number of AS_TRANS peers resolved so far.
+    pub fn len(&self) -> usize {
+        self.peers.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.peers.is_empty()
+    }
+}
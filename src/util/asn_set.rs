@@ -0,0 +1,81 @@
+use roaring::RoaringBitmap;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+/// Set of ASNs backed by a roaring bitmap.
+///
+/// Uses a fraction of the memory of a `HashSet<u32>` for the dense ASN ranges seen in BGP data,
+/// and serializes as a sorted list of ASNs.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct AsnSet {
+    bitmap: RoaringBitmap,
+}
+
+impl AsnSet {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add an ASN, returning whether it was newly inserted.
+    pub fn insert(&mut self, asn: u32) -> bool {
+        self.bitmap.insert(asn)
+    }
+
+    /// Remove an ASN, returning whether it was present.
+    pub fn remove(&mut self, asn: u32) -> bool {
+        self.bitmap.remove(asn)
+    }
+
+    pub fn contains(&self, asn: u32) -> bool {
+        self.bitmap.contains(asn)
+    }
+
+    pub fn len(&self) -> u64 {
+        self.bitmap.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.bitmap.is_empty()
+    }
+
+    /// Iterate over ASNs in ascending order.
+    pub fn iter(&self) -> impl Iterator<Item = u32> + '_ {
+        self.bitmap.iter()
+    }
+
+    /// Add all ASNs of another set.
+    pub fn union_with(&mut self, other: &AsnSet) {
+        self.bitmap |= &other.bitmap;
+    }
+
+    /// Number of ASNs in both sets.
+    pub fn intersection_len(&self, other: &AsnSet) -> u64 {
+        self.bitmap.intersection_len(&other.bitmap)
+    }
+}
+
+impl FromIterator<u32> for AsnSet {
+    fn from_iter<I: IntoIterator<Item = u32>>(iter: I) -> Self {
+        AsnSet {
+            bitmap: iter.into_iter().collect(),
+        }
+    }
+}
+
+impl Extend<u32> for AsnSet {
+    fn extend<I: IntoIterator<Item = u32>>(&mut self, iter: I) {
+        self.bitmap.extend(iter)
+    }
+}
+
+impl Serialize for AsnSet {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.bitmap.iter())
+    }
+}
+
+impl<'de> Deserialize<'de> for AsnSet {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let asns = Vec::<u32>::deserialize(deserializer)?;
+        Ok(asns.into_iter().collect())
+    }
+}
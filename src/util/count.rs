@@ -0,0 +1,12 @@
+/// Compact human-readable count for log lines, e.g. `950`, `1.2k`, `78k` or `3.4M`.
+pub fn format_count(count: usize) -> String {
+    let scaled = |value: f64, unit: &str| match value < 10.0 {
+        true => format!("{:.1}{}", value, unit).replace(".0", ""),
+        false => format!("{:.0}{}", value, unit),
+    };
+    match count {
+        0..=999 => count.to_string(),
+        1_000..=999_499 => scaled(count as f64 / 1e3, "k"),
+        _ => scaled(count as f64 / 1e6, "M"),
+    }
+}
@@ -0,0 +1,59 @@
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+
+/// Number of index bits; 2^14 registers give a standard error of about 0.8%.
+const PRECISION: u32 = 14;
+const REGISTERS: usize = 1 << PRECISION;
+
+/// HyperLogLog sketch estimating the number of distinct items in fixed memory.
+///
+/// A sketch takes 16 KiB once the first item is inserted, regardless of the number of items, and
+/// estimates counts within about 1%. Small counts use linear counting and are nearly exact.
+#[derive(Debug, Default, Clone)]
+pub struct HyperLogLog {
+    /// one register per index, empty until the first insert
+    registers: Vec<u8>,
+}
+
+impl HyperLogLog {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn insert<T: Hash + ?Sized>(&mut self, item: &T) {
+        if self.registers.is_empty() {
+            self.registers = vec![0; REGISTERS];
+        }
+        let mut hasher = DefaultHasher::new();
+        item.hash(&mut hasher);
+        let hash = hasher.finish();
+        let index = (hash >> (64 - PRECISION)) as usize;
+        // position of the first set bit in the remaining bits, the index bits are shifted out
+        let rank = ((hash << PRECISION) | (1 << (PRECISION - 1))).leading_zeros() as u8 + 1;
+        if rank > self.registers[index] {
+            self.registers[index] = rank;
+        }
+    }
+
+    /// Estimated number of distinct inserted items.
+    pub fn estimate(&self) -> usize {
+        if self.registers.is_empty() {
+            return 0;
+        }
+        let m = REGISTERS as f64;
+        let alpha = 0.7213 / (1.0 + 1.079 / m);
+        let sum: f64 = self.registers.iter().map(|r| 2f64.powi(-(*r as i32))).sum();
+        let raw = alpha * m * m / sum;
+        let zeros = self.registers.iter().filter(|r| **r == 0).count();
+        let estimate = match raw <= 2.5 * m && zeros > 0 {
+            // linear counting for small cardinalities
+            true => m * (m / zeros as f64).ln(),
+            false => raw,
+        };
+        estimate.round() as usize
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.registers.is_empty()
+    }
+}
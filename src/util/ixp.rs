@@ -0,0 +1,104 @@
+use crate::util::AsnSet;
+use anyhow::{anyhow, Result};
+use serde_json::Value;
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+/// Handling of IXP route-server ASNs on AS paths.
+///
+/// Route servers at IXPs that do not strip their own ASN appear on AS paths between the two
+/// actual neighbors, creating links to the route server instead of between the neighbors.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum IxpPolicy {
+    /// keep paths as they are
+    #[default]
+    Keep,
+    /// remove IXP ASNs from paths, linking their neighbors directly
+    Strip,
+    /// keep paths, but mark results involving IXP ASNs
+    Tag,
+}
+
+impl FromStr for IxpPolicy {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "keep" => Ok(IxpPolicy::Keep),
+            "strip" => Ok(IxpPolicy::Strip),
+            "tag" => Ok(IxpPolicy::Tag),
+            _ => Err(anyhow!("unknown IXP policy: {}", s)),
+        }
+    }
+}
+
+impl Display for IxpPolicy {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            IxpPolicy::Keep => write!(f, "keep"),
+            IxpPolicy::Strip => write!(f, "strip"),
+            IxpPolicy::Tag => write!(f, "tag"),
+        }
+    }
+}
+
+/// Load IXP route-server ASNs from a local file or URL.
+///
+/// Accepted formats:
+/// - a PeeringDB `net` API dump (`{"data": [...]}`), taking networks of type "Route Server";
+/// - a JSON array of ASNs;
+/// - plain text with one ASN per line, optionally prefixed with `AS`, and `#` comments.
+pub fn load_ixp_asns(path: &str) -> Result<AsnSet> {
+    let content = oneio::read_to_string(path)
+        .map_err(|e| anyhow!("failed to read IXP ASNs from {}: {}", path, e))?;
+    parse_ixp_asns(content.as_str())
+}
+
+/// Parse IXP route-server ASNs in any of the formats accepted by [load_ixp_asns].
+pub fn parse_ixp_asns(content: &str) -> Result<AsnSet> {
+    if let Ok(value) = serde_json::from_str::<Value>(content) {
+        return match value {
+            Value::Array(asns) => asns
+                .iter()
+                .map(|v| v.as_u64().and_then(|asn| u32::try_from(asn).ok()))
+                .collect::<Option<AsnSet>>()
+                .ok_or_else(|| anyhow!("invalid ASN in IXP ASN list")),
+            Value::Object(obj) => {
+                let nets = obj
+                    .get("data")
+                    .and_then(|d| d.as_array())
+                    .ok_or_else(|| anyhow!("expected PeeringDB net data"))?;
+                Ok(nets
+                    .iter()
+                    .filter(|net| is_route_server(net))
+                    .filter_map(|net| net.get("asn")?.as_u64())
+                    .filter_map(|asn| u32::try_from(asn).ok())
+                    .collect())
+            }
+            _ => Err(anyhow!("unsupported IXP ASN list format")),
+        };
+    }
+
+    let mut asns = AsnSet::new();
+    for line in content.lines() {
+        let line = line.split('#').next().unwrap_or_default().trim();
+        if line.is_empty() {
+            continue;
+        }
+        let digits = line.trim_start_matches("AS").trim_start_matches("as");
+        let asn =
+            u32::from_str(digits).map_err(|_| anyhow!("invalid ASN in IXP ASN list: {}", line))?;
+        asns.insert(asn);
+    }
+    Ok(asns)
+}
+
+/// Check if a PeeringDB network is a route server, by its `info_type` or `info_types`.
+fn is_route_server(net: &Value) -> bool {
+    let is_rs = |v: &Value| v.as_str() == Some("Route Server");
+    net.get("info_type").is_some_and(is_rs)
+        || net
+            .get("info_types")
+            .and_then(|t| t.as_array())
+            .is_some_and(|types| types.iter().any(is_rs))
+}
@@ -0,0 +1,40 @@
+//! Utilities shared by processors.
+//!
+//! - [AddressFamily]: IPv4 or IPv6, to limit a pipeline to one address family
+//! - [AsTransResolver]: real ASNs of peers reported as AS_TRANS (AS23456)
+//! - [AsnSet]: compact ASN set backed by a roaring bitmap
+//! - [HyperLogLog]: approximate distinct counting in fixed memory
+//! - [PrefixSpace]: address-space accounting that counts overlapping prefixes once
+//! - [PathView]: AS path view with prepending removed
+//! - [ElemView]: element with its path view computed once for all processors
+//! - [IxpPolicy], [load_ixp_asns]: handling of IXP route-server ASNs on AS paths
+//! - [PrefixCountries]: registration countries of address space from RIR delegated files
+//! - [RoutePolicy]: elements skipped before dispatch, e.g. default routes or bogons
+//! - [RouteTable]: current routes per peer and prefix of a stream of BGP updates
+//! - [format_count]: compact counts for brief summaries in logs
+
+mod af;
+mod as_trans;
+mod asn_set;
+mod count;
+mod hll;
+mod ixp;
+mod path;
+mod prefix_country;
+mod prefix_space;
+mod route_policy;
+mod route_table;
+
+pub use af::AddressFamily;
+pub use as_trans::{AsTransResolver, AS_TRANS};
+pub use asn_set::AsnSet;
+pub use count::format_count;
+pub use hll::HyperLogLog;
+pub use ixp::{load_ixp_asns, parse_ixp_asns, IxpPolicy};
+pub use path::{ElemView, PathView};
+pub use prefix_country::PrefixCountries;
+pub use prefix_space::PrefixSpace;
+pub use route_policy::{
+    is_bogon_prefix, is_reserved_asn, RoutePolicy, RouteSkipCounts, SkipReason,
+};
+pub use route_table::RouteTable;
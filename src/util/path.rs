@@ -0,0 +1,155 @@
+use crate::processors::peers::PeerId;
+use crate::util::AsnSet;
+use bgpkit_parser::models::{AsPath, ElemType};
+use bgpkit_parser::BgpElem;
+use itertools::Itertools;
+use std::cell::OnceCell;
+
+/// Element with attributes derived once and shared by all processors of a pipeline.
+///
+/// [RibEye](crate::RibEye) passes one view per element to each processor's `process_view`, so the
+/// AS path conversion is done at most once per element regardless of the number of processors.
+/// It also tags each view with the ID of the element's peer in the pipeline's peer tracker, so that
+/// per-peer processors can index their state without hashing peer IPs.
+#[derive(Debug, Clone)]
+pub struct ElemView<'a> {
+    elem: &'a BgpElem,
+    path: OnceCell<Option<PathView>>,
+    peer_id: Option<PeerId>,
+}
+
+impl<'a> ElemView<'a> {
+    pub fn new(elem: &'a BgpElem) -> Self {
+        ElemView {
+            elem,
+            path: OnceCell::new(),
+            peer_id: None,
+        }
+    }
+
+    /// Tag the view with the ID of its peer, assigned by the dispatching pipeline.
+    pub fn with_peer_id(mut self, peer_id: PeerId) -> Self {
+        self.peer_id = Some(peer_id);
+        self
+    }
+
+    pub fn elem(&self) -> &'a BgpElem {
+        self.elem
+    }
+
+    /// ID of the element's peer in the pipeline, none for views not created by a pipeline.
+    ///
+    /// IDs are assigned in order of first sight and stay stable until the pipeline's RIB meta
+    /// information is set again, which also resets all processors.
+    pub fn peer_id(&self) -> Option<PeerId> {
+        self.peer_id
+    }
+
+    /// AS path with prepending removed, computed on first use, see [PathView::from_elem].
+    pub fn path(&self) -> Option<&PathView> {
+        self.path
+            .get_or_init(|| PathView::from_elem(self.elem))
+            .as_ref()
+    }
+
+    /// Origin AS of a plain AS path.
+    pub fn origin(&self) -> Option<u32> {
+        self.path().map(|p| p.origin())
+    }
+
+    pub fn is_announce(&self) -> bool {
+        self.elem.elem_type == ElemType::ANNOUNCE
+    }
+
+    pub fn is_ipv4(&self) -> bool {
+        self.elem.prefix.prefix.addr().is_ipv4()
+    }
+
+    /// Whether the prefix is a default route (`0.0.0.0/0` or `::/0`).
+    pub fn is_default_route(&self) -> bool {
+        self.elem.prefix.prefix.prefix_len() == 0
+    }
+}
+
+/// AS path as a plain ASN sequence with prepending removed.
+///
+/// Paths containing AS sets or confederation segments have no plain sequence and yield no view.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PathView {
+    asns: Vec<u32>,
+}
+
+impl PathView {
+    /// Create a view of an AS path.
+    pub fn new(path: &AsPath) -> Option<PathView> {
+        let asns = path.to_u32_vec_opt(true)?;
+        match asns.is_empty() {
+            true => None,
+            false => Some(PathView { asns }),
+        }
+    }
+
+    /// Create a view of the AS path of an element, if any.
+    pub fn from_elem(elem: &BgpElem) -> Option<PathView> {
+        Self::new(elem.as_path.as_ref()?)
+    }
+
+    /// ASNs from the collector peer to the origin.
+    pub fn asns(&self) -> &[u32] {
+        self.asns.as_slice()
+    }
+
+    /// Iterate over ASNs from the collector peer to the origin.
+    pub fn iter(&self) -> impl Iterator<Item = u32> + '_ {
+        self.asns.iter().copied()
+    }
+
+    /// Iterate over adjacent ASN pairs, the first ASN being closer to the collector.
+    pub fn links(&self) -> impl Iterator<Item = (u32, u32)> + '_ {
+        self.iter().tuple_windows()
+    }
+
+    /// Origin AS, i.e. the last ASN.
+    pub fn origin(&self) -> u32 {
+        *self.asns.last().unwrap()
+    }
+
+    /// First AS, i.e. the collector peer's AS.
+    pub fn first_hop(&self) -> u32 {
+        self.asns[0]
+    }
+
+    pub fn contains(&self, asn: u32) -> bool {
+        self.asns.contains(&asn)
+    }
+
+    /// Check if any ASN of the path is in the given set.
+    pub fn contains_any(&self, asns: &AsnSet) -> bool {
+        self.iter().any(|asn| asns.contains(asn))
+    }
+
+    /// View of the path with the given ASNs removed, or none if no ASN remains.
+    pub fn without(&self, asns: &AsnSet) -> Option<PathView> {
+        let mut remaining: Vec<u32> = self.iter().filter(|asn| !asns.contains(*asn)).collect();
+        remaining.dedup();
+        match remaining.is_empty() {
+            true => None,
+            false => Some(PathView { asns: remaining }),
+        }
+    }
+
+    /// Number of ASes on the path, without prepending.
+    pub fn len(&self) -> usize {
+        self.asns.len()
+    }
+
+    /// Always false, views are never created for empty paths.
+    pub fn is_empty(&self) -> bool {
+        self.asns.is_empty()
+    }
+
+    /// Take the ASN sequence.
+    pub fn into_vec(self) -> Vec<u32> {
+        self.asns
+    }
+}
@@ -0,0 +1,113 @@
+use anyhow::{anyhow, Result};
+use ipnet::IpNet;
+use std::collections::{BTreeMap, BTreeSet};
+use std::net::IpAddr;
+use std::str::FromStr;
+
+/// Registration countries of address space, from RIR delegated statistics files.
+///
+/// Delegated blocks are kept as disjoint address ranges per address family, so the countries of a
+/// prefix are those of all blocks overlapping it.
+#[derive(Debug, Default, Clone)]
+pub struct PrefixCountries {
+    /// IPv4 blocks, start to inclusive end and country code
+    v4: BTreeMap<u128, (u128, String)>,
+    /// IPv6 blocks, start to inclusive end and country code
+    v6: BTreeMap<u128, (u128, String)>,
+}
+
+impl PrefixCountries {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Load RIR delegated statistics files (e.g. `delegated-ripencc-extended-latest`), local or
+    /// remote, one file per RIR.
+    pub fn load(paths: &[String]) -> Result<Self> {
+        let mut countries = PrefixCountries::new();
+        for path in paths {
+            let content = oneio::read_to_string(path.as_str())
+                .map_err(|e| anyhow!("failed to read delegated file {}: {}", path, e))?;
+            countries.add_delegated(content.as_str())?;
+        }
+        Ok(countries)
+    }
+
+    /// Add the allocated and assigned IPv4 and IPv6 blocks of a delegated statistics file
+    /// (`registry|cc|type|start|value|date|status[|...]` lines).
+    pub fn add_delegated(&mut self, content: &str) -> Result<()> {
+        for line in content.lines() {
+            let fields: Vec<&str> = line.split('|').collect();
+            // skip comments, the version line and summary lines
+            if line.starts_with('#') || fields.len() < 7 || fields[1] == "*" {
+                continue;
+            }
+            let (cc, block_type, start, value, status) =
+                (fields[1], fields[2], fields[3], fields[4], fields[6]);
+            if cc.is_empty()
+                || !matches!(block_type, "ipv4" | "ipv6")
+                || !matches!(status, "allocated" | "assigned")
+            {
+                continue;
+            }
+            let invalid = || anyhow!("invalid delegated line: {}", line);
+            let start = IpAddr::from_str(start).map_err(|_| invalid())?;
+            let value = u128::from_str(value).map_err(|_| invalid())?;
+            match (block_type, start) {
+                // IPv4 blocks are given as a number of addresses
+                ("ipv4", IpAddr::V4(start)) if value > 0 => {
+                    let start = u32::from(start) as u128;
+                    self.insert(false, start, start + value - 1, cc);
+                }
+                // IPv6 blocks are given as a prefix length
+                ("ipv6", IpAddr::V6(start)) if value <= 128 => {
+                    let start = u128::from(start);
+                    let end = match value {
+                        0 => u128::MAX,
+                        len => start | (u128::MAX >> len),
+                    };
+                    self.insert(true, start, end, cc);
+                }
+                _ => return Err(invalid()),
+            }
+        }
+        Ok(())
+    }
+
+    fn insert(&mut self, v6: bool, start: u128, end: u128, cc: &str) {
+        let blocks = match v6 {
+            true => &mut self.v6,
+            false => &mut self.v4,
+        };
+        blocks.insert(start, (end, cc.to_uppercase()));
+    }
+
+    /// Countries the address space of a prefix is registered in, empty if unregistered.
+    pub fn countries(&self, prefix: &IpNet) -> BTreeSet<&str> {
+        let (blocks, start, end) = match prefix {
+            IpNet::V4(p) => (
+                &self.v4,
+                u32::from(p.network()) as u128,
+                u32::from(p.broadcast()) as u128,
+            ),
+            IpNet::V6(p) => (&self.v6, u128::from(p.network()), u128::from(p.broadcast())),
+        };
+        // blocks are disjoint, so the ones overlapping the prefix are the last ones starting
+        // before its end, up to the first one ending before its start
+        blocks
+            .range(..=end)
+            .rev()
+            .take_while(|(_, (block_end, _))| *block_end >= start)
+            .map(|(_, (_, cc))| cc.as_str())
+            .collect()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.v4.is_empty() && self.v6.is_empty()
+    }
+
+    /// Number of delegated blocks.
+    pub fn len(&self) -> usize {
+        self.v4.len() + self.v6.len()
+    }
+}
@@ -0,0 +1,100 @@
+use ipnet::IpNet;
+use std::collections::BTreeMap;
+
+/// Address space covered by a set of prefixes, counting overlapping prefixes once.
+///
+/// Covered space is kept as disjoint address ranges per address family, so adding a prefix and
+/// any of its more-specifics covers exactly the less-specific's space.
+#[derive(Debug, Default, Clone)]
+pub struct PrefixSpace {
+    /// disjoint, non-adjacent IPv4 ranges, start to inclusive end
+    v4: BTreeMap<u128, u128>,
+    /// disjoint, non-adjacent IPv6 ranges, start to inclusive end
+    v6: BTreeMap<u128, u128>,
+}
+
+impl PrefixSpace {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add the address space of a prefix.
+    pub fn insert(&mut self, prefix: &IpNet) {
+        match prefix {
+            IpNet::V4(p) => insert_range(
+                &mut self.v4,
+                u32::from(p.network()) as u128,
+                u32::from(p.broadcast()) as u128,
+            ),
+            IpNet::V6(p) => insert_range(
+                &mut self.v6,
+                u128::from(p.network()),
+                u128::from(p.broadcast()),
+            ),
+        }
+    }
+
+    /// Number of covered IPv4 addresses.
+    pub fn v4_addresses(&self) -> u64 {
+        self.v4.iter().map(|(s, e)| (e - s + 1) as u64).sum()
+    }
+
+    /// Number of covered IPv6 addresses, saturating at `u128::MAX` for the whole address space.
+    pub fn v6_addresses(&self) -> u128 {
+        self.v6.iter().fold(0u128, |acc, (s, e)| {
+            acc.saturating_add((e - s).saturating_add(1))
+        })
+    }
+
+    /// Covered IPv4 space in units of /24 prefixes.
+    pub fn slash24_equivalents(&self) -> f64 {
+        self.v4_addresses() as f64 / 256.0
+    }
+
+    /// Covered IPv6 space in units of /48 prefixes.
+    pub fn slash48_equivalents(&self) -> f64 {
+        self.v6_addresses() as f64 / 2f64.powi(80)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.v4.is_empty() && self.v6.is_empty()
+    }
+}
+
+impl Extend<IpNet> for PrefixSpace {
+    fn extend<I: IntoIterator<Item = IpNet>>(&mut self, iter: I) {
+        for prefix in iter {
+            self.insert(&prefix);
+        }
+    }
+}
+
+impl FromIterator<IpNet> for PrefixSpace {
+    fn from_iter<I: IntoIterator<Item = IpNet>>(iter: I) -> Self {
+        let mut space = PrefixSpace::new();
+        space.extend(iter);
+        space
+    }
+}
+
+/// Insert an inclusive range, merging it with overlapping and adjacent ranges.
+fn insert_range(ranges: &mut BTreeMap<u128, u128>, mut start: u128, mut end: u128) {
+    if let Some((&s, &e)) = ranges.range(..=start).next_back() {
+        if e >= end {
+            // already covered
+            return;
+        }
+        if e.saturating_add(1) >= start {
+            ranges.remove(&s);
+            start = s;
+        }
+    }
+    while let Some((&s, &e)) = ranges.range(start..).next() {
+        if s > end.saturating_add(1) {
+            break;
+        }
+        ranges.remove(&s);
+        end = end.max(e);
+    }
+    ranges.insert(start, end);
+}
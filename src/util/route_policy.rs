@@ -0,0 +1,175 @@
+use crate::util::ElemView;
+use ipnet::{IpNet, Ipv4Net, Ipv6Net};
+use serde::{Deserialize, Serialize};
+use std::net::{Ipv4Addr, Ipv6Addr};
+
+/// IPv4 martians (RFC 6890) that are never routed on the Internet.
+const V4_BOGONS: [(u32, u8); 14] = [
+    (0x0000_0000, 8),  // 0.0.0.0/8
+    (0x0a00_0000, 8),  // 10.0.0.0/8
+    (0x6440_0000, 10), // 100.64.0.0/10
+    (0x7f00_0000, 8),  // 127.0.0.0/8
+    (0xa9fe_0000, 16), // 169.254.0.0/16
+    (0xac10_0000, 12), // 172.16.0.0/12
+    (0xc000_0000, 24), // 192.0.0.0/24
+    (0xc000_0200, 24), // 192.0.2.0/24
+    (0xc0a8_0000, 16), // 192.168.0.0/16
+    (0xc612_0000, 15), // 198.18.0.0/15
+    (0xc633_6400, 24), // 198.51.100.0/24
+    (0xcb00_7100, 24), // 203.0.113.0/24
+    (0xe000_0000, 4),  // 224.0.0.0/4
+    (0xf000_0000, 4),  // 240.0.0.0/4
+];
+
+/// IPv6 martians inside the global unicast space 2000::/3; everything outside it is a bogon too.
+const V6_BOGONS: [(u128, u8); 3] = [
+    (0x2001_0002_0000_0000_0000_0000_0000_0000, 48), // 2001:2::/48
+    (0x2001_0010_0000_0000_0000_0000_0000_0000, 28), // 2001:10::/28
+    (0x2001_0db8_0000_0000_0000_0000_0000_0000, 32), // 2001:db8::/32
+];
+
+/// Whether a prefix is a bogon, i.e. covered by a martian prefix. Default routes are not bogons.
+pub fn is_bogon_prefix(prefix: &IpNet) -> bool {
+    match prefix {
+        _ if prefix.prefix_len() == 0 => false,
+        IpNet::V4(p) => V4_BOGONS.iter().any(|(addr, len)| {
+            Ipv4Net::new(Ipv4Addr::from(*addr), *len).is_ok_and(|bogon| bogon.contains(p))
+        }),
+        IpNet::V6(p) => {
+            let global = Ipv6Net::new(Ipv6Addr::from(0x2000u128 << 112), 3).unwrap();
+            !global.contains(p)
+                || V6_BOGONS.iter().any(|(addr, len)| {
+                    Ipv6Net::new(Ipv6Addr::from(*addr), *len).is_ok_and(|bogon| bogon.contains(p))
+                })
+        }
+    }
+}
+
+/// Whether an ASN is reserved (RFC 7607, RFC 6793, RFC 5398, RFC 6996, RFC 7300) and should not
+/// appear on Internet AS paths.
+pub fn is_reserved_asn(asn: u32) -> bool {
+    // documentation, private use, last 16-bit ASN, documentation and IANA reserved 32-bit ASNs
+    // form one block
+    matches!(asn, 0 | 23456 | 64496..=131071 | 4_200_000_000..=u32::MAX)
+}
+
+/// Reason an element was skipped by a [RoutePolicy].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SkipReason {
+    DefaultRoute,
+    Bogon,
+    ReservedAsn,
+    PrefixLength,
+}
+
+/// Elements a processor does not see, applied by [RibEye](crate::RibEye) before dispatch.
+///
+/// Each processor declares its own policy (e.g. prefix-to-origin processors skip default
+/// routes), which pipelines can override per processor. The default policy skips nothing.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RoutePolicy {
+    /// skip default routes (`0.0.0.0/0` and `::/0`)
+    pub skip_default: bool,
+    /// skip prefixes covered by martian prefixes, see [is_bogon_prefix]
+    pub skip_bogons: bool,
+    /// skip AS paths containing reserved ASNs, see [is_reserved_asn]
+    pub skip_reserved_asns: bool,
+    /// skip IPv4 prefixes more specific than this length
+    pub max_v4_prefix_len: Option<u8>,
+    /// skip IPv6 prefixes more specific than this length
+    pub max_v6_prefix_len: Option<u8>,
+}
+
+impl RoutePolicy {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_skip_default(mut self, skip: bool) -> Self {
+        self.skip_default = skip;
+        self
+    }
+
+    pub fn with_skip_bogons(mut self, skip: bool) -> Self {
+        self.skip_bogons = skip;
+        self
+    }
+
+    pub fn with_skip_reserved_asns(mut self, skip: bool) -> Self {
+        self.skip_reserved_asns = skip;
+        self
+    }
+
+    pub fn with_max_prefix_len(mut self, v4: Option<u8>, v6: Option<u8>) -> Self {
+        self.max_v4_prefix_len = v4;
+        self.max_v6_prefix_len = v6;
+        self
+    }
+
+    /// Whether the policy lets all elements through.
+    pub fn is_noop(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Reason the policy skips an element, none if the element passes.
+    pub fn skip_reason(&self, view: &ElemView) -> Option<SkipReason> {
+        let prefix = &view.elem().prefix.prefix;
+        if self.skip_default && view.is_default_route() {
+            return Some(SkipReason::DefaultRoute);
+        }
+        let max_len = match prefix {
+            IpNet::V4(_) => self.max_v4_prefix_len,
+            IpNet::V6(_) => self.max_v6_prefix_len,
+        };
+        if max_len.is_some_and(|len| prefix.prefix_len() > len) {
+            return Some(SkipReason::PrefixLength);
+        }
+        if self.skip_bogons && is_bogon_prefix(prefix) {
+            return Some(SkipReason::Bogon);
+        }
+        if self.skip_reserved_asns {
+            let reserved = view.elem().as_path.as_ref().is_some_and(|path| {
+                path.iter_segments()
+                    .flat_map(|segment| segment.iter())
+                    .any(|asn| is_reserved_asn(asn.to_u32()))
+            });
+            if reserved {
+                return Some(SkipReason::ReservedAsn);
+            }
+        }
+        None
+    }
+}
+
+/// Numbers of elements skipped by a [RoutePolicy], per reason.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RouteSkipCounts {
+    pub default_route: u64,
+    pub bogon: u64,
+    pub reserved_asn: u64,
+    pub prefix_length: u64,
+}
+
+impl RouteSkipCounts {
+    pub fn add(&mut self, reason: SkipReason) {
+        match reason {
+            SkipReason::DefaultRoute => self.default_route += 1,
+            SkipReason::Bogon => self.bogon += 1,
+            SkipReason::ReservedAsn => self.reserved_asn += 1,
+            SkipReason::PrefixLength => self.prefix_length += 1,
+        }
+    }
+
+    pub fn merge(&mut self, other: &RouteSkipCounts) {
+        self.default_route += other.default_route;
+        self.bogon += other.bogon;
+        self.reserved_asn += other.reserved_asn;
+        self.prefix_length += other.prefix_length;
+    }
+
+    /// Total number of skipped elements.
+    pub fn total(&self) -> u64 {
+        self.default_route + self.bogon + self.reserved_asn + self.prefix_length
+    }
+}
@@ -0,0 +1,57 @@
+use bgpkit_parser::models::{ElemType, NetworkPrefix};
+use bgpkit_parser::BgpElem;
+use std::collections::HashMap;
+use std::net::IpAddr;
+
+/// Current routes of a stream of BGP updates, one per peer and prefix.
+///
+/// Processors count every element they see, so streaming sources cannot feed updates to them
+/// directly: re-announcements would be counted again and withdrawn routes never removed. Instead
+/// announcements replace the peer's route for the prefix in the table, withdrawals remove it, and
+/// the processors are rebuilt from [RouteTable::elems] for each snapshot.
+#[derive(Debug, Clone, Default)]
+pub struct RouteTable {
+    routes: HashMap<(IpAddr, NetworkPrefix), BgpElem>,
+}
+
+impl RouteTable {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Apply an announcement or withdrawal to the table.
+    pub fn apply(&mut self, elem: BgpElem) {
+        let key = (elem.peer_ip, elem.prefix);
+        match elem.elem_type {
+            ElemType::ANNOUNCE => {
+                self.routes.insert(key, elem);
+            }
+            ElemType::WITHDRAW => {
+                self.routes.remove(&key);
+            }
+        }
+    }
+
+    /// Remove all routes of a peer, e.g. once its session went down. Returns the number of routes
+    /// removed.
+    pub fn remove_peer(&mut self, peer_ip: IpAddr) -> usize {
+        let before = self.routes.len();
+        self.routes.retain(|(ip, _), _| *ip != peer_ip);
+        before - self.routes.len()
+    }
+
+    /// Current routes, ordered by peer and prefix.
+    pub fn elems(&self) -> Vec<BgpElem> {
+        let mut keys: Vec<&(IpAddr, NetworkPrefix)> = self.routes.keys().collect();
+        keys.sort_by_key(|(ip, prefix)| (*ip, prefix.prefix, prefix.path_id));
+        keys.into_iter().map(|k| self.routes[k].clone()).collect()
+    }
+
+    pub fn len(&self) -> usize {
+        self.routes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.routes.is_empty()
+    }
+}
@@ -0,0 +1,206 @@
+use ribeye::download::Downloader;
+use ribeye::testing::SyntheticRibBuilder;
+use ribeye::throttle::ByteCap;
+use ribeye::util::RouteTable;
+use ribeye::{RibEye, RibMeta};
+use std::io::{BufRead, BufReader, Write};
+use std::net::TcpListener;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+/// HTTP server of one file at any path, returning the number of requests received.
+fn mock_server(content: Vec<u8>) -> (String, Arc<AtomicUsize>) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let endpoint = format!("http://{}", listener.local_addr().unwrap());
+    let requests = Arc::new(AtomicUsize::new(0));
+    let received = requests.clone();
+    std::thread::spawn(move || {
+        for stream in listener.incoming() {
+            let mut stream = stream.unwrap();
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+            loop {
+                let mut line = String::new();
+                reader.read_line(&mut line).unwrap();
+                if line.trim_end().is_empty() {
+                    break;
+                }
+            }
+            received.fetch_add(1, Ordering::SeqCst);
+            let header = format!(
+                "HTTP/1.1 200 OK\r\ncontent-length: {}\r\n\r\n",
+                content.len()
+            );
+            let _ = stream.write_all(header.as_bytes());
+            let _ = stream.write_all(&content);
+        }
+    });
+    (endpoint, requests)
+}
+
+/// Bytes of a synthetic RIB dump file.
+fn rib_file() -> Vec<u8> {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("rib.bz2");
+    SyntheticRibBuilder::new()
+        .generated(2, 50)
+        .write_mrt_file(path.to_str().unwrap())
+        .unwrap();
+    std::fs::read(path).unwrap()
+}
+
+fn rib_meta(collector: &str, url: &str, file_size: Option<u64>) -> RibMeta {
+    RibMeta {
+        collector: collector.to_string(),
+        rib_dump_url: url.to_string(),
+        file_size,
+        ..Default::default()
+    }
+}
+
+fn dir_bytes(path: &std::path::Path) -> u64 {
+    std::fs::read_dir(path)
+        .unwrap()
+        .map(|entry| {
+            let entry = entry.unwrap();
+            match entry.file_type().unwrap().is_dir() {
+                true => dir_bytes(entry.path().as_path()),
+                false => entry.metadata().unwrap().len(),
+            }
+        })
+        .sum()
+}
+
+#[test]
+fn test_byte_cap() {
+    let cap = ByteCap::new(100);
+    cap.reserve(60).unwrap();
+    assert!(cap.reserve(50).is_err());
+    // the download turned out smaller than expected
+    cap.settle(60, 40);
+    assert_eq!(cap.used(), 40);
+    cap.reserve(60).unwrap();
+    // files of unknown size start until the cap is reached
+    assert!(cap.reserve(0).is_err());
+    cap.settle(60, 50);
+    cap.reserve(0).unwrap();
+    assert_eq!(cap.used(), 90);
+}
+
+#[test]
+fn test_downloaded_and_written_bytes() {
+    let content = rib_file();
+    let (endpoint, _) = mock_server(content.clone());
+    let url = format!("{}/rrc00/rib.bz2", endpoint);
+    let cache_dir = tempfile::tempdir().unwrap();
+    let output_dir = tempfile::tempdir().unwrap();
+    let processors = vec!["pfx2as".to_string(), "peer-stats".to_string()];
+    let mut ribeye = RibEye::new()
+        .with_processor_names(&processors, output_dir.path().to_str().unwrap())
+        .unwrap()
+        .with_downloader(Downloader::new(cache_dir.path().to_str().unwrap()))
+        .with_rib_meta(&rib_meta("rrc00", url.as_str(), None));
+    let stats = ribeye.process_mrt_file(url.as_str()).unwrap();
+    assert_eq!(stats.downloaded_bytes, content.len() as u64);
+
+    // written bytes are the sizes of the output files as stored
+    let report = ribeye.report();
+    assert_eq!(report.written_bytes.len(), 2);
+    assert!(report.written_bytes.iter().all(|p| p.bytes > 0));
+    assert_eq!(
+        report.written_bytes.iter().map(|p| p.bytes).sum::<u64>(),
+        stats.written_bytes
+    );
+    assert_eq!(stats.written_bytes, dir_bytes(output_dir.path()));
+}
+
+#[test]
+fn test_download_cap() {
+    let content = rib_file();
+    let size = content.len() as u64;
+    let (endpoint, requests) = mock_server(content);
+    let cache_dir = tempfile::tempdir().unwrap();
+    let output_dir = tempfile::tempdir().unwrap();
+    let cap = Arc::new(ByteCap::new(size + size / 2));
+    let processors = vec!["pfx2as".to_string()];
+    let pipeline = RibEye::new()
+        .with_processor_names(&processors, output_dir.path().to_str().unwrap())
+        .unwrap()
+        .with_downloader(Downloader::new(cache_dir.path().to_str().unwrap()))
+        .with_download_cap(cap.clone());
+
+    let url = format!("{}/rrc00/rib.bz2", endpoint);
+    let mut ribeye =
+        pipeline
+            .clone_pipeline()
+            .with_rib_meta(&rib_meta("rrc00", url.as_str(), Some(size)));
+    ribeye.process_mrt_file(url.as_str()).unwrap();
+    assert_eq!(cap.used(), size);
+
+    // the second dump of known size does not fit under the cap and is not requested
+    let url = format!("{}/rrc01/rib.bz2", endpoint);
+    let mut ribeye =
+        pipeline
+            .clone_pipeline()
+            .with_rib_meta(&rib_meta("rrc01", url.as_str(), Some(size)));
+    let e = ribeye.process_mrt_file(url.as_str()).unwrap_err();
+    assert!(e.to_string().contains("download cap"));
+    assert_eq!(requests.load(Ordering::SeqCst), 1);
+    assert_eq!(cap.used(), size);
+}
+
+#[test]
+fn test_streamed_download_cap() {
+    let content = rib_file();
+    let size = content.len() as u64;
+    let (endpoint, requests) = mock_server(content);
+    let output_dir = tempfile::tempdir().unwrap();
+    let cap = Arc::new(ByteCap::new(size + size / 2));
+    let processors = vec!["pfx2as".to_string()];
+    let pipeline = RibEye::new()
+        .with_processor_names(&processors, output_dir.path().to_str().unwrap())
+        .unwrap()
+        .with_download_cap(cap.clone());
+
+    // streamed dumps count the bytes read, not the size estimated beforehand
+    let url = format!("{}/rrc00/rib.bz2", endpoint);
+    let mut ribeye =
+        pipeline
+            .clone_pipeline()
+            .with_rib_meta(&rib_meta("rrc00", url.as_str(), Some(size / 2)));
+    let stats = ribeye.process_mrt_file(url.as_str()).unwrap();
+    assert_eq!(stats.downloaded_bytes, size);
+    assert!(stats.announcements > 0);
+    assert_eq!(cap.used(), size);
+
+    // a dump of unknown size starts under the cap but fails once it exceeds the cap
+    let url = format!("{}/rrc01/rib.bz2", endpoint);
+    let mut ribeye =
+        pipeline
+            .clone_pipeline()
+            .with_rib_meta(&rib_meta("rrc01", url.as_str(), None));
+    let e = ribeye.process_mrt_file(url.as_str()).unwrap_err();
+    assert!(e.to_string().contains("download cap"));
+    assert_eq!(requests.load(Ordering::SeqCst), 2);
+    assert!(cap.used() <= cap.max_bytes());
+}
+
+#[test]
+fn test_streamed_route_table() {
+    let content = rib_file();
+    let size = content.len() as u64;
+    let (endpoint, _) = mock_server(content);
+    let output_dir = tempfile::tempdir().unwrap();
+    let cap = Arc::new(ByteCap::new(size * 2));
+    let processors = vec!["pfx2as".to_string()];
+    let url = format!("{}/rrc00/rib.bz2", endpoint);
+    let mut ribeye = RibEye::new()
+        .with_processor_names(&processors, output_dir.path().to_str().unwrap())
+        .unwrap()
+        .with_download_cap(cap.clone())
+        .with_rib_meta(&rib_meta("rrc00", url.as_str(), None));
+    let mut table = RouteTable::new();
+    ribeye.load_route_table(url.as_str(), &mut table).unwrap();
+    assert!(!table.is_empty());
+    assert_eq!(ribeye.stats().downloaded_bytes, size);
+    assert_eq!(cap.used(), size);
+}
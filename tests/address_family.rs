@@ -0,0 +1,80 @@
+use ribeye::processors::ADDRESS_FAMILY_FIELD;
+use ribeye::testing::SyntheticRibBuilder;
+use ribeye::util::AddressFamily;
+use ribeye::{RibEye, RibMeta};
+use std::str::FromStr;
+
+fn builder() -> SyntheticRibBuilder {
+    SyntheticRibBuilder::new()
+        .peer("10.0.0.1", 65001)
+        .route("1.1.1.0/24", &[65001, 13335])
+        .route("2606:4700::/32", &[65001, 13335])
+        .route("8.8.8.0/24", &[65001, 15169])
+        .peer("2001:db8::1", 65002)
+        .route("2001:4860::/32", &[65002, 15169])
+}
+
+#[test]
+fn test_parse_address_family() {
+    assert_eq!(AddressFamily::from_str("v4").unwrap(), AddressFamily::V4);
+    assert_eq!(AddressFamily::from_str("IPv6").unwrap(), AddressFamily::V6);
+    assert!(AddressFamily::from_str("v5").is_err());
+    assert_eq!(AddressFamily::V6.to_string(), "v6");
+}
+
+#[test]
+fn test_address_family_filter() {
+    let output_dir = tempfile::tempdir().unwrap();
+    let dir = output_dir.path().to_str().unwrap();
+    let rib_meta = RibMeta {
+        collector: "rrc00".to_string(),
+        ..Default::default()
+    };
+    let mut ribeye = RibEye::new()
+        .with_processor_names(&vec!["pfx2as".to_string()], dir)
+        .unwrap()
+        .with_rib_meta(&rib_meta)
+        .with_address_family(Some(AddressFamily::V6));
+    ribeye.process_elems(builder().elems()).unwrap();
+    assert_eq!(ribeye.stats().elems, 2);
+    assert_eq!(ribeye.stats().ipv4_elems, 0);
+    ribeye.output().unwrap();
+
+    let path = format!("{dir}/pfx2as/rrc00/latest.json.bz2");
+    let output: serde_json::Value = oneio::read_json_struct(path.as_str()).unwrap();
+    let prefixes: Vec<&str> = output["pfx2as"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|e| e["prefix"].as_str().unwrap())
+        .collect();
+    assert_eq!(prefixes.len(), 2);
+    assert!(prefixes.iter().all(|p| p.contains(':')));
+    assert_eq!(output[ADDRESS_FAMILY_FIELD], "v6");
+
+    ribeye.summarize_latest_files(&[rib_meta]).unwrap();
+    let path = format!("{dir}/pfx2as/latest.json.bz2");
+    let summary: serde_json::Value = oneio::read_json_struct(path.as_str()).unwrap();
+    assert_eq!(summary[ADDRESS_FAMILY_FIELD], "v6");
+}
+
+#[test]
+fn test_no_address_family() {
+    let output_dir = tempfile::tempdir().unwrap();
+    let dir = output_dir.path().to_str().unwrap();
+    let rib_meta = RibMeta {
+        collector: "rrc00".to_string(),
+        ..Default::default()
+    };
+    let mut ribeye = RibEye::new()
+        .with_processor_names(&vec!["pfx2as".to_string()], dir)
+        .unwrap()
+        .with_rib_meta(&rib_meta);
+    ribeye.process_elems(builder().elems()).unwrap();
+    assert_eq!(ribeye.stats().elems, 4);
+    ribeye.output().unwrap();
+
+    let path = format!("{dir}/pfx2as/rrc00/latest.json.bz2");
+    let output: serde_json::Value = oneio::read_json_struct(path.as_str()).unwrap();
+    assert!(output.get(ADDRESS_FAMILY_FIELD).is_none());
+}
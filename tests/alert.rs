@@ -0,0 +1,240 @@
+use ribeye::alert::{Alert, AlertConfig, AlertRule, HookConfig, NotificationHook};
+use ribeye::testing::SyntheticRibBuilder;
+use ribeye::{RibEye, RibMeta};
+use serde_json::json;
+use std::sync::{Arc, Mutex};
+
+#[test]
+fn test_alert_config() {
+    let config = AlertConfig::from_json_str(
+        r#"{
+            "rules": [{"rule": "origin_change", "min_slash8": 0.5}, {"rule": "peer_drop"}],
+            "hooks": [{"type": "file", "path": "/tmp/alerts.jsonl"}, {"type": "command", "command": "cat"}]
+        }"#,
+    )
+    .unwrap();
+    assert_eq!(
+        config.rules,
+        vec![
+            AlertRule::OriginChange { min_slash8: 0.5 },
+            AlertRule::PeerDrop { ratio: 0.2 },
+        ]
+    );
+    assert_eq!(
+        config.hooks[0],
+        HookConfig::File {
+            path: "/tmp/alerts.jsonl".to_string()
+        }
+    );
+    assert_eq!(config.hooks().len(), 2);
+
+    // alerts are logged without configured hooks
+    assert_eq!(AlertConfig::default().hooks().len(), 1);
+    assert!(AlertConfig::from_json_str(r#"{"rules": [{"rule": "unknown"}]}"#).is_err());
+}
+
+#[test]
+fn test_origin_change_rule() {
+    let summary = |entries: Vec<(&str, u32)>| {
+        json!({
+            "rib_dump_urls": [],
+            "pfx2as": entries
+                .into_iter()
+                .map(|(prefix, asn)| json!({"prefix": prefix, "asn": asn, "count": 1}))
+                .collect::<Vec<_>>(),
+        })
+    };
+    let previous = summary(vec![
+        ("10.0.0.0/8", 64496),
+        ("11.0.0.0/9", 64497),
+        ("11.0.0.0/10", 64497),
+        ("12.0.0.0/8", 64498),
+        ("2001:db8::/32", 64499),
+    ]);
+    let current = summary(vec![
+        ("10.0.0.0/8", 64496),
+        // moved to another origin, overlapping space counted once
+        ("11.0.0.0/9", 64500),
+        ("11.0.0.0/10", 64500),
+        // newly multi-origin
+        ("12.0.0.0/8", 64498),
+        ("12.0.0.0/8", 64501),
+        ("2001:db8::/32", 64502),
+        // new prefixes are not origin changes
+        ("13.0.0.0/8", 64503),
+    ]);
+
+    let rule = AlertRule::OriginChange { min_slash8: 1.5 };
+    let alerts = rule.evaluate(&current, &previous).unwrap();
+    assert_eq!(alerts.len(), 1);
+    assert_eq!(alerts[0].rule, "origin_change");
+    assert_eq!(alerts[0].processor, "pfx2as");
+    assert_eq!(alerts[0].value, 1.5);
+
+    let rule = AlertRule::OriginChange { min_slash8: 2.0 };
+    assert!(rule.evaluate(&current, &previous).unwrap().is_empty());
+}
+
+/// Notification hook keeping the alerts it receives.
+#[derive(Default)]
+struct CollectHook {
+    alerts: Mutex<Vec<Alert>>,
+}
+
+impl NotificationHook for CollectHook {
+    fn notify(&self, alerts: &[Alert]) -> anyhow::Result<()> {
+        self.alerts.lock().unwrap().extend_from_slice(alerts);
+        Ok(())
+    }
+}
+
+#[test]
+fn test_summaries_with_alerts() {
+    let output_dir = tempfile::tempdir().unwrap();
+    let dir = output_dir.path().to_str().unwrap();
+    let alerts_path = output_dir.path().join("alerts.jsonl");
+    let alerts_path = alerts_path.to_str().unwrap();
+    let rib_meta = RibMeta {
+        collector: "rrc99".to_string(),
+        ..Default::default()
+    };
+    let processors = vec!["peer-stats".to_string(), "pfx2as".to_string()];
+    let config = AlertConfig::from_json_str(
+        json!({
+            "rules": [{"rule": "peer_drop", "ratio": 0.2}, {"rule": "origin_change", "min_slash8": 0.5}],
+            "hooks": [{"type": "file", "path": alerts_path}],
+        })
+        .to_string()
+        .as_str(),
+    )
+    .unwrap();
+    let hook = Arc::new(CollectHook::default());
+
+    let run = |builder: SyntheticRibBuilder| -> Vec<Alert> {
+        let mrt_path = output_dir.path().join("rib.test.bz2");
+        let mrt_path = mrt_path.to_str().unwrap();
+        builder.write_mrt_file(mrt_path).unwrap();
+        let mut ribeye = RibEye::new()
+            .with_processor_names(&processors, dir)
+            .unwrap()
+            .with_rib_meta(&rib_meta)
+            .with_alert_config(&config)
+            .with_notification_hook(hook.clone());
+        ribeye.process_mrt_file(mrt_path).unwrap();
+        ribeye
+            .summarize_latest_files(std::slice::from_ref(&rib_meta))
+            .unwrap();
+        ribeye.alerts().to_vec()
+    };
+
+    // nothing to compare with on the first run
+    let alerts = run(SyntheticRibBuilder::new()
+        .peer("192.0.2.1", 65001)
+        .route("10.0.0.0/8", &[65001, 64496])
+        .peer("192.0.2.2", 65002)
+        .route("10.0.0.0/8", &[65002, 64496]));
+    assert!(alerts.is_empty());
+
+    let alerts = run(SyntheticRibBuilder::new()
+        .peer("192.0.2.1", 65001)
+        .route("10.0.0.0/8", &[65001, 64497]));
+    let rules: Vec<_> = alerts.iter().map(|a| a.rule.as_str()).collect();
+    assert_eq!(rules, vec!["peer_drop", "origin_change"]);
+    assert_eq!(alerts[0].collector.as_deref(), Some("rrc99"));
+    assert_eq!(alerts[0].value, 0.5);
+
+    // alerts are sent to both the configured and the added hooks
+    assert_eq!(*hook.alerts.lock().unwrap(), alerts);
+    let written: Vec<Alert> = std::fs::read_to_string(alerts_path)
+        .unwrap()
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    assert_eq!(written, alerts);
+}
+
+#[test]
+fn test_moas_conflict_rule() {
+    let summary = |entries: Vec<(&str, u32)>| {
+        json!({
+            "rib_dump_urls": [],
+            "pfx2as": entries
+                .into_iter()
+                .map(|(prefix, asn)| json!({"prefix": prefix, "asn": asn, "count": 1}))
+                .collect::<Vec<_>>(),
+        })
+    };
+    let previous = summary(vec![
+        ("10.0.0.0/8", 64496),
+        ("11.0.0.0/8", 64497),
+        ("11.0.0.0/8", 64498),
+        ("12.0.0.0/8", 64499),
+    ]);
+    let current = summary(vec![
+        // newly multi-origin
+        ("10.0.0.0/8", 64496),
+        ("10.0.0.0/8", 64500),
+        // already multi-origin
+        ("11.0.0.0/8", 64497),
+        ("11.0.0.0/8", 64499),
+        // newly announced by two origins
+        ("13.0.0.0/8", 64501),
+        ("13.0.0.0/8", 64502),
+    ]);
+
+    let alerts = AlertRule::MoasConflict { min_prefixes: 1 }
+        .evaluate(&current, &previous)
+        .unwrap();
+    assert_eq!(alerts.len(), 1);
+    assert_eq!(alerts[0].value, 2.0);
+    assert_eq!(
+        alerts[0].message,
+        "2 new MOAS conflicts: 10.0.0.0/8 (AS64496, AS64500), 13.0.0.0/8 (AS64501, AS64502)"
+    );
+    assert!(AlertRule::MoasConflict { min_prefixes: 3 }
+        .evaluate(&current, &previous)
+        .unwrap()
+        .is_empty());
+}
+
+#[test]
+fn test_stale_collector_rule() {
+    let now = chrono::Utc::now().timestamp();
+    let summary = |dumps: Vec<(&str, i64, bool)>| {
+        json!({
+            "rib_dump_urls": [],
+            "peers": [],
+            "collector_dumps": dumps
+                .into_iter()
+                .map(|(collector, age_hours, stale)| json!({
+                    "project": "riperis",
+                    "collector": collector,
+                    "rib_timestamp": now - age_hours * 3600,
+                    "stale": stale,
+                }))
+                .collect::<Vec<_>>(),
+        })
+    };
+    let previous = summary(vec![
+        ("rrc00", 8, false),
+        ("rrc01", 50, true),
+        ("rrc02", 8, false),
+    ]);
+    let current = summary(vec![
+        // going stale
+        ("rrc00", 50, true),
+        // already stale
+        ("rrc01", 74, true),
+        ("rrc02", 8, false),
+        // new and stale
+        ("rrc03", 50, true),
+    ]);
+
+    let alerts = AlertRule::StaleCollector { hours: 48.0 }
+        .evaluate(&current, &previous)
+        .unwrap();
+    let collectors: Vec<_> = alerts.iter().map(|a| a.collector.as_deref()).collect();
+    assert_eq!(collectors, vec![Some("rrc00"), Some("rrc03")]);
+    assert_eq!(alerts[0].threshold, 48.0);
+    assert!(alerts[0].message.starts_with("collector rrc00 is stale"));
+}
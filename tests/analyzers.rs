@@ -0,0 +1,192 @@
+use anyhow::Result;
+use ribeye::analyzers::{get_analyzer, order_analyzers, Analyzer, AnalyzerInputs};
+use ribeye::hijack::{HijackReportJson, Vrps};
+use ribeye::processors::{get_default_output_path, ProcessorMeta};
+use ribeye::testing::SyntheticRibBuilder;
+use ribeye::{RibEye, RibMeta};
+use serde_json::{json, Value};
+use std::sync::Arc;
+
+/// Analyzer counting the entries of another result, written under `<dir>/<name>`.
+struct CountAnalyzer {
+    name: String,
+    input: String,
+    field: String,
+    output_dir: String,
+}
+
+impl CountAnalyzer {
+    fn shared(name: &str, input: &str, field: &str, output_dir: &str) -> Arc<dyn Analyzer> {
+        Arc::new(CountAnalyzer {
+            name: name.to_string(),
+            input: input.to_string(),
+            field: field.to_string(),
+            output_dir: output_dir.to_string(),
+        })
+    }
+}
+
+impl Analyzer for CountAnalyzer {
+    fn name(&self) -> String {
+        self.name.clone()
+    }
+
+    fn dependencies(&self) -> Vec<String> {
+        vec![self.input.clone()]
+    }
+
+    fn analyze(&self, _rib_meta: &RibMeta, inputs: &AnalyzerInputs) -> Result<Option<Value>> {
+        let input = inputs.get(self.input.as_str()).unwrap();
+        let count = match &input[self.field.as_str()] {
+            Value::Array(entries) => entries.len() as u64,
+            value => value.as_u64().unwrap(),
+        };
+        Ok(Some(json!({ "count": count })))
+    }
+
+    fn output_path(&self, rib_meta: &RibMeta) -> Option<String> {
+        let processor_meta = ProcessorMeta {
+            name: self.name.clone(),
+            output_dir: self.output_dir.clone(),
+        };
+        Some(get_default_output_path(rib_meta, &processor_meta))
+    }
+}
+
+fn rib_meta() -> RibMeta {
+    RibMeta {
+        collector: "rrc00".to_string(),
+        ..Default::default()
+    }
+}
+
+#[test]
+fn test_order_analyzers() {
+    let processors = vec!["pfx2as".to_string()];
+    let analyzers = vec![
+        CountAnalyzer::shared("twice", "once", "count", "/tmp"),
+        CountAnalyzer::shared("once", "pfx2as", "pfx2as", "/tmp"),
+    ];
+    let ordered: Vec<String> = order_analyzers(&analyzers, &processors)
+        .unwrap()
+        .iter()
+        .map(|a| a.name())
+        .collect();
+    assert_eq!(ordered, vec!["once", "twice"]);
+
+    let missing = vec![CountAnalyzer::shared("rels", "as2rel", "as2rel", "/tmp")];
+    let err = order_analyzers(&missing, &processors)
+        .map(|_| ())
+        .unwrap_err();
+    assert!(err.to_string().contains("depends on as2rel"), "{}", err);
+
+    let cycle = vec![
+        CountAnalyzer::shared("a", "b", "count", "/tmp"),
+        CountAnalyzer::shared("b", "a", "count", "/tmp"),
+    ];
+    let err = order_analyzers(&cycle, &processors)
+        .map(|_| ())
+        .unwrap_err();
+    assert!(
+        err.to_string().contains("cycle between analyzers a, b"),
+        "{}",
+        err
+    );
+
+    assert!(get_analyzer("unknown", "/tmp", None).is_err());
+}
+
+#[test]
+fn test_pipeline_analyzers() {
+    let output_dir = tempfile::tempdir().unwrap();
+    let dir = output_dir.path().to_str().unwrap();
+    let elems = SyntheticRibBuilder::new()
+        .peer("10.0.0.1", 65001)
+        .route("1.1.1.0/24", &[65001, 13335])
+        .route("8.8.8.0/24", &[65001, 15169])
+        .build();
+
+    let analyzers = vec![
+        CountAnalyzer::shared("twice", "once", "count", dir),
+        CountAnalyzer::shared("once", "pfx2as", "pfx2as", dir),
+    ];
+    let mut ribeye = RibEye::new()
+        .with_processor_names(&vec!["pfx2as".to_string()], dir)
+        .unwrap()
+        .with_analyzers(analyzers)
+        .unwrap()
+        .with_rib_meta(&rib_meta());
+    ribeye.process_elems(&elems).unwrap();
+    ribeye.output().unwrap();
+
+    // the analyzer depending on another one runs after it, on its in-memory result
+    let read = |name: &str| -> Value {
+        let processor_meta = ProcessorMeta {
+            name: name.to_string(),
+            output_dir: dir.to_string(),
+        };
+        let path = get_default_output_path(&rib_meta(), &processor_meta);
+        oneio::read_json_struct(path.as_str()).unwrap()
+    };
+    assert_eq!(read("once")["count"], 2);
+    assert_eq!(read("twice")["count"], 2);
+
+    // cloned pipelines keep the analyzers
+    let mut clone = ribeye.clone_pipeline().with_rib_meta(&rib_meta());
+    clone.process_elems(&elems[..1]).unwrap();
+    clone.output().unwrap();
+    assert_eq!(read("once")["count"], 1);
+
+    let missing = vec![CountAnalyzer::shared("rels", "as2rel", "as2rel", dir)];
+    assert!(RibEye::new()
+        .with_processor_names(&vec!["pfx2as".to_string()], dir)
+        .unwrap()
+        .with_analyzers(missing)
+        .is_err());
+}
+
+#[test]
+fn test_hijack_analyzer() {
+    let output_dir = tempfile::tempdir().unwrap();
+    let dir = output_dir.path().to_str().unwrap();
+    // 1.1.1.0/24 is originated by AS13335 and, seen by a single peer, by AS64496
+    let elems = SyntheticRibBuilder::new()
+        .peer("10.0.0.1", 65001)
+        .route("1.1.1.0/24", &[65001, 13335])
+        .peer("10.0.0.2", 65002)
+        .route("1.1.1.0/24", &[65002, 13335])
+        .peer("10.0.0.3", 65003)
+        .route("1.1.1.0/24", &[65003, 64496])
+        .build();
+    let vrps = Vrps::parse(
+        r#"{"roas": [{"asn": "AS13335", "prefix": "1.1.1.0/24", "maxLength": 24, "ta": "apnic"}]}"#,
+    )
+    .unwrap();
+    let analyzer = get_analyzer("hijacks", dir, Some(Arc::new(vrps))).unwrap();
+
+    let mut ribeye = RibEye::new()
+        .with_processor_names(&vec!["pfx2as".to_string(), "as2rel".to_string()], dir)
+        .unwrap()
+        .with_analyzers(vec![analyzer.clone()])
+        .unwrap()
+        .with_rib_meta(&rib_meta());
+    ribeye.process_elems(&elems).unwrap();
+    ribeye.output().unwrap();
+
+    let path = analyzer.output_path(&rib_meta()).unwrap();
+    assert!(path.starts_with(format!("{dir}/hijacks/rrc00/").as_str()));
+    let report: HijackReportJson = oneio::read_json_struct(path.as_str()).unwrap();
+    assert_eq!(report.hijacks[0].prefix, "1.1.1.0/24");
+    assert_eq!(report.hijacks[0].asn, 64496);
+    assert!(report.hijacks[0]
+        .reasons
+        .contains(&"rpki_invalid".to_string()));
+    assert!(report.hijacks[0].reasons.contains(&"moas".to_string()));
+
+    // without pfx2as, the hijacks analyzer cannot be added
+    assert!(RibEye::new()
+        .with_processor_names(&vec!["as2rel".to_string()], dir)
+        .unwrap()
+        .with_analyzers(vec![analyzer])
+        .is_err());
+}
@@ -0,0 +1,194 @@
+use chrono::{NaiveDate, NaiveDateTime};
+use ribeye::archive::{get_archive_dir, DeltaArchive, Pfx2AsDelta, SnapshotKind};
+use ribeye::processors::{FileSink, OutputSink, Prefix2AsCount, Prefix2AsSummaryJson};
+use ribeye::testing::SyntheticRibBuilder;
+use ribeye::{RibEye, RibMeta};
+
+fn entry(prefix: &str, asn: u32, count: usize) -> Prefix2AsCount {
+    Prefix2AsCount {
+        prefix: prefix.to_string(),
+        asn,
+        count,
+        peers: vec![],
+        collectors_count: None,
+        weighted_count: None,
+    }
+}
+
+fn summary(day: u32, entries: Vec<Prefix2AsCount>) -> Prefix2AsSummaryJson {
+    Prefix2AsSummaryJson {
+        rib_dump_urls: vec![format!("rib.202401{:02}.0000.bz2", day)],
+        pfx2as: entries,
+        aggregated: false,
+    }
+}
+
+fn date(day: u32) -> NaiveDate {
+    NaiveDate::from_ymd_opt(2024, 1, day).unwrap()
+}
+
+/// Daily summaries where prefixes appear, disappear and change counts or origins.
+fn days() -> Vec<Prefix2AsSummaryJson> {
+    vec![
+        summary(
+            1,
+            vec![
+                entry("1.1.1.0/24", 13335, 10),
+                entry("8.8.8.0/24", 15169, 5),
+                entry("9.9.9.0/24", 19281, 3),
+            ],
+        ),
+        summary(
+            2,
+            vec![
+                entry("8.8.8.0/24", 15169, 5),
+                entry("1.1.1.0/24", 13335, 12),
+                entry("9.9.9.0/24", 19281, 3),
+                entry("2001:db8::/32", 64496, 1),
+            ],
+        ),
+        summary(
+            3,
+            vec![
+                entry("1.1.1.0/24", 13335, 12),
+                entry("1.1.1.0/24", 4826, 2),
+                entry("2001:db8::/32", 64496, 1),
+            ],
+        ),
+        summary(4, vec![entry("1.1.1.0/24", 13335, 12)]),
+    ]
+}
+
+fn sorted(summary: &Prefix2AsSummaryJson) -> serde_json::Value {
+    let mut summary = summary.clone();
+    summary
+        .pfx2as
+        .sort_by(|a, b| (&a.prefix, a.asn).cmp(&(&b.prefix, b.asn)));
+    serde_json::to_value(summary).unwrap()
+}
+
+#[test]
+fn test_delta_roundtrip() {
+    let days = days();
+    let delta = Pfx2AsDelta::between(&days[0], &days[1]).unwrap();
+    assert_eq!(delta.added.len(), 1);
+    assert_eq!(delta.changed.len(), 1);
+    assert_eq!(delta.changed[0].count, 12);
+    assert!(delta.removed.is_empty());
+    assert_eq!(sorted(&delta.apply(&days[0])), sorted(&days[1]));
+
+    let delta = Pfx2AsDelta::between(&days[1], &days[2]).unwrap();
+    assert_eq!((delta.added.len(), delta.removed.len()), (1, 2));
+    assert!(Pfx2AsDelta::between(&days[3], &days[3]).unwrap().is_empty());
+}
+
+#[test]
+fn test_archive_reconstructs_every_date() {
+    let archive_dir = tempfile::tempdir().unwrap();
+    let dir = archive_dir.path().to_str().unwrap();
+    let archive = DeltaArchive::new(dir);
+    let days = days();
+    for (i, day) in days.iter().enumerate() {
+        archive.append(&FileSink, date(i as u32 + 1), day).unwrap();
+    }
+    let index = archive.index(&FileSink).unwrap();
+    let kinds: Vec<SnapshotKind> = index.snapshots.iter().map(|s| s.kind).collect();
+    assert_eq!(
+        kinds,
+        vec![
+            SnapshotKind::Full,
+            SnapshotKind::Delta,
+            SnapshotKind::Delta,
+            SnapshotKind::Delta
+        ]
+    );
+    for (i, day) in days.iter().enumerate() {
+        let (archived, summary) = archive.reconstruct(&FileSink, date(i as u32 + 1)).unwrap();
+        assert_eq!(archived, date(i as u32 + 1));
+        assert_eq!(sorted(&summary), sorted(day), "day {}", i + 1);
+    }
+    // dates without a snapshot fall back to the last archived one
+    let (archived, _) = archive.reconstruct(&FileSink, date(20)).unwrap();
+    assert_eq!(archived, date(4));
+    let before = NaiveDate::from_ymd_opt(2023, 12, 31).unwrap();
+    assert!(archive.reconstruct(&FileSink, before).is_err());
+
+    // deltas only store what changed
+    let delta: Pfx2AsDelta = serde_json::from_value(
+        FileSink
+            .read_json(format!("{}/2024-01-04.delta.json.bz2", dir).as_str())
+            .unwrap(),
+    )
+    .unwrap();
+    assert_eq!((delta.added.len(), delta.changed.len()), (0, 0));
+    assert_eq!(delta.removed.len(), 2);
+}
+
+#[test]
+fn test_archive_full_every_and_replaced_dates() {
+    let archive_dir = tempfile::tempdir().unwrap();
+    let dir = archive_dir.path().to_str().unwrap();
+    let archive = DeltaArchive::new(dir).with_full_every(2);
+    let days = days();
+    for (i, day) in days.iter().enumerate() {
+        archive.append(&FileSink, date(i as u32 + 1), day).unwrap();
+    }
+    // archiving the last date again replaces its snapshot
+    archive.append(&FileSink, date(4), &days[0]).unwrap();
+    assert!(archive.append(&FileSink, date(2), &days[0]).is_err());
+
+    let index = archive.index(&FileSink).unwrap();
+    let files: Vec<&str> = index.snapshots.iter().map(|s| s.file.as_str()).collect();
+    assert_eq!(
+        files,
+        vec![
+            "2024-01-01.full.json.bz2",
+            "2024-01-02.delta.json.bz2",
+            "2024-01-03.full.json.bz2",
+            "2024-01-04.delta.json.bz2"
+        ]
+    );
+    let (_, summary) = archive.reconstruct(&FileSink, date(4)).unwrap();
+    assert_eq!(sorted(&summary), sorted(&days[0]));
+    let (_, summary) = archive.reconstruct(&FileSink, date(3)).unwrap();
+    assert_eq!(sorted(&summary), sorted(&days[2]));
+}
+
+#[test]
+fn test_archive_after_summarize() {
+    let output_dir = tempfile::tempdir().unwrap();
+    let dir = output_dir.path().to_str().unwrap();
+    let archive = DeltaArchive::new(get_archive_dir(dir).as_str());
+    for (day, prefixes) in [(1, 20), (2, 25)] {
+        let timestamp = format!("2024-01-0{} 00:00", day);
+        let rib_metas = vec![RibMeta {
+            collector: "rrc00".to_string(),
+            timestamp: NaiveDateTime::parse_from_str(timestamp.as_str(), "%Y-%m-%d %H:%M").unwrap(),
+            ..Default::default()
+        }];
+        let mut ribeye = RibEye::new()
+            .with_processor_names(&vec!["pfx2as".to_string()], dir)
+            .unwrap()
+            .with_delta_archive(archive.clone())
+            .with_rib_meta(&rib_metas[0]);
+        ribeye
+            .process_elems(SyntheticRibBuilder::new().generated(2, prefixes).elems())
+            .unwrap();
+        ribeye.output().unwrap();
+        ribeye.summarize_latest_files(&rib_metas).unwrap();
+
+        let latest: Prefix2AsSummaryJson = serde_json::from_value(
+            FileSink
+                .read_json(format!("{}/pfx2as/latest.json.bz2", dir).as_str())
+                .unwrap(),
+        )
+        .unwrap();
+        let (archived, summary) = archive.reconstruct(&FileSink, date(day)).unwrap();
+        assert_eq!(archived, date(day));
+        assert_eq!(sorted(&summary), sorted(&latest));
+    }
+    let index = archive.index(&FileSink).unwrap();
+    assert_eq!(index.snapshots.len(), 2);
+    assert_eq!(index.snapshots[1].kind, SnapshotKind::Delta);
+    assert_eq!(index.snapshots[1].entries, 25);
+}
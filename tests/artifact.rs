@@ -0,0 +1,125 @@
+use ribeye::artifact::{build_artifact, get_artifact_path, Artifact};
+use ribeye::lookup::LookupData;
+use ribeye::testing::SyntheticRibBuilder;
+use ribeye::{RibEye, RibMeta};
+use serde_json::json;
+use std::collections::BTreeMap;
+use std::net::IpAddr;
+
+/// Process routes with pfx2as, as2rel and peer-stats and bundle their summaries in an artifact.
+fn process(dir: &str) {
+    let names = vec![
+        "pfx2as".to_string(),
+        "as2rel".to_string(),
+        "peer-stats".to_string(),
+    ];
+    let rib_metas = vec![RibMeta {
+        collector: "rrc00".to_string(),
+        ..Default::default()
+    }];
+    let mut ribeye = RibEye::new()
+        .with_processor_names(&names, dir)
+        .unwrap()
+        .with_artifact(get_artifact_path(dir).as_str())
+        .with_rib_meta(&rib_metas[0]);
+    let routes = SyntheticRibBuilder::new()
+        .peer("10.0.0.1", 174)
+        .route("1.0.0.0/8", &[174, 3356])
+        .route("1.1.0.0/16", &[174, 13335])
+        .route("1.1.1.0/24", &[174, 13335])
+        .route("2001:db8::/32", &[174, 6939])
+        .route("2001:db8:1::/48", &[174, 6939, 13335])
+        .peer("10.0.0.2", 6939)
+        .route("1.1.1.0/24", &[6939, 4826])
+        .route("2001:db8:1::/48", &[6939, 13335]);
+    ribeye.process_elems(routes.elems()).unwrap();
+    ribeye
+        .process_elems(SyntheticRibBuilder::new().generated(3, 20).elems())
+        .unwrap();
+    ribeye.output().unwrap();
+    ribeye.summarize_latest_files(&rib_metas).unwrap();
+}
+
+#[test]
+fn test_artifact_lookups_match_summaries() {
+    let output_dir = tempfile::tempdir().unwrap();
+    let dir = output_dir.path().to_str().unwrap();
+    process(dir);
+    let artifact = Artifact::load(get_artifact_path(dir).as_str()).unwrap();
+    let data = LookupData::load(dir).unwrap();
+    assert_eq!(
+        artifact.processors(),
+        vec!["as2rel", "peer-stats", "pfx2as"]
+    );
+    assert!(artifact.summary("pfx2as").unwrap().is_some());
+    assert!(artifact.summary("moas").unwrap().is_none());
+
+    for ip in [
+        "1.1.1.1",
+        "1.1.2.3",
+        "1.200.0.1",
+        "9.9.9.9",
+        "2001:db8:1::1",
+        "::1",
+    ] {
+        let ip: IpAddr = ip.parse().unwrap();
+        let (expected, found) = (data.lookup_ip(ip), artifact.lookup_ip(ip));
+        let mut expected_origins = expected.origins.clone();
+        let mut found_origins = found.origins.clone();
+        expected_origins.sort();
+        found_origins.sort();
+        assert_eq!(found_origins, expected_origins, "{}", ip);
+        let prefixes = |r: &ribeye::lookup::IpLookupResult| {
+            let mut p: Vec<(String, u32, usize)> = r
+                .covering_prefixes
+                .iter()
+                .map(|c| (c.prefix.clone(), c.origin, c.visibility))
+                .collect();
+            p.sort();
+            p
+        };
+        assert_eq!(prefixes(&found), prefixes(&expected), "{}", ip);
+    }
+    let found = artifact.lookup_ip("1.1.1.1".parse().unwrap());
+    assert_eq!(found.covering_prefixes[0].prefix, "1.1.1.0/24");
+    assert_eq!(found.covering_prefixes.last().unwrap().prefix, "1.0.0.0/8");
+
+    for asn in [174, 6939, 13335, 4826, 3356, 64999] {
+        let (expected, found) = (data.lookup_asn(asn), artifact.lookup_asn(asn).unwrap());
+        assert_eq!(
+            serde_json::to_value(&found.prefixes).unwrap(),
+            serde_json::to_value(&expected.prefixes).unwrap(),
+            "AS{}",
+            asn
+        );
+        assert_eq!(found.neighbors, expected.neighbors, "AS{}", asn);
+        assert_eq!(found.providers, expected.providers, "AS{}", asn);
+        assert_eq!(found.customers, expected.customers, "AS{}", asn);
+        let mut expected_peers = expected.collector_peers.clone();
+        expected_peers.sort_by_key(|p| (p.collector.clone(), p.ip));
+        assert_eq!(found.collector_peers, expected_peers, "AS{}", asn);
+    }
+    assert_eq!(artifact.lookup_asn(13335).unwrap().prefixes.len(), 3);
+}
+
+#[test]
+fn test_artifact_without_indexed_summaries() {
+    let mut summaries = BTreeMap::new();
+    summaries.insert("moas".to_string(), json!({"moas": []}));
+    let artifact = Artifact::from_bytes(build_artifact(&summaries).unwrap()).unwrap();
+    assert_eq!(artifact.processors(), vec!["moas"]);
+    assert_eq!(artifact.summary("moas").unwrap(), Some(json!({"moas": []})));
+    assert!(artifact
+        .lookup_ip("1.1.1.1".parse().unwrap())
+        .origins
+        .is_empty());
+    assert!(artifact.lookup_asn(13335).unwrap().prefixes.is_empty());
+}
+
+#[test]
+fn test_invalid_artifact() {
+    assert!(Artifact::from_bytes(b"{\"pfx2as\": []}".to_vec()).is_err());
+    let mut data = build_artifact(&BTreeMap::new()).unwrap();
+    data.truncate(40);
+    assert!(Artifact::from_bytes(data).is_err());
+}
@@ -0,0 +1,85 @@
+use ribeye::processors::{As2relEntry, As2relProcessor, TypedProcessor};
+use ribeye::testing::{collector_output, SyntheticRibBuilder};
+use ribeye::RibMeta;
+
+fn find(entries: &[As2relEntry], asn1: u32, asn2: u32, rel: u8) -> &As2relEntry {
+    entries
+        .iter()
+        .find(|e| (e.asn1, e.asn2, e.rel) == (asn1, asn2, rel))
+        .unwrap()
+}
+
+#[test]
+fn test_as2rel_confidence() {
+    let (_, output) = collector_output(
+        As2relProcessor::new("-"),
+        "rrc00",
+        &SyntheticRibBuilder::new()
+            .peer("192.0.2.1", 65001)
+            .route("1.1.1.0/24", &[65001, 174, 13335])
+            .route("10.0.0.0/24", &[65001, 3356, 64510, 64511])
+            .peer("192.0.2.2", 65002)
+            .route("1.1.1.0/24", &[65002, 13335, 174, 64500])
+            .route("10.0.1.0/24", &[65002, 3356, 64511, 64510])
+            .build(),
+    );
+    let entries = &output.as2rel;
+
+    // adjacency seen in both path directions
+    let entry = find(entries, 174, 13335, 0);
+    assert_eq!(entry.reverse_paths_count, 1);
+    assert_eq!(entry.confidence, 0.55);
+
+    // adjacency seen in one direction only
+    let entry = find(entries, 65001, 174, 0);
+    assert_eq!(entry.reverse_paths_count, 0);
+    assert_eq!(entry.confidence, 0.3);
+
+    // consistent provider-to-customer inference
+    let entry = find(entries, 174, 13335, 1);
+    assert_eq!(entry.reverse_paths_count, 0);
+    assert_eq!(entry.confidence, 0.55);
+
+    // conflicting inferences in both directions
+    let entry = find(entries, 64510, 64511, 1);
+    assert_eq!(entry.reverse_paths_count, 1);
+    assert_eq!(entry.confidence, 0.3);
+}
+
+#[test]
+fn test_as2rel_summary_rescored() {
+    let outputs = vec![
+        collector_output(
+            As2relProcessor::new("-"),
+            "rrc00",
+            &SyntheticRibBuilder::new()
+                .peer("192.0.2.1", 65001)
+                .route("1.1.1.0/24", &[65001, 174, 13335])
+                .build(),
+        ),
+        collector_output(
+            As2relProcessor::new("-"),
+            "rrc01",
+            &SyntheticRibBuilder::new()
+                .peer("192.0.2.2", 65002)
+                .route("1.1.1.0/24", &[65002, 174, 13335])
+                .route("8.8.8.0/24", &[65002, 13335, 174])
+                .build(),
+        ),
+    ];
+    assert_eq!(
+        find(&outputs[0].1.as2rel, 174, 13335, 0).reverse_paths_count,
+        0
+    );
+
+    let rib_metas: Vec<RibMeta> = outputs.iter().map(|(m, _)| m.clone()).collect();
+    let summary = As2relProcessor::new("-")
+        .summarize(&rib_metas, outputs)
+        .unwrap();
+    let entry = find(&summary.as2rel, 174, 13335, 0);
+    assert_eq!(entry.peers_count, 2);
+    assert_eq!(entry.reverse_paths_count, 1);
+    assert_eq!(entry.confidence, 0.6);
+    let entry = find(&summary.as2rel, 174, 13335, 1);
+    assert_eq!(entry.confidence, 0.6);
+}
@@ -0,0 +1,111 @@
+use ribeye::processors::{FileSink, OutputSink};
+use ribeye::testing::SyntheticRibBuilder;
+use ribeye::util::{AsTransResolver, AS_TRANS};
+use ribeye::{RibEye, RibMeta};
+use serde_json::Value;
+
+fn as_trans_rib() -> SyntheticRibBuilder {
+    SyntheticRibBuilder::new()
+        .peer("192.0.2.1", AS_TRANS)
+        .route("1.1.1.0/24", &[4200000001, 174, 13335])
+        .route("8.8.8.0/24", &[AS_TRANS, 4200000001, 3356, 15169])
+        .withdrawal("9.9.9.0/24")
+        .peer("192.0.2.2", 65002)
+        .route("1.1.1.0/24", &[65002, 3356, 13335])
+}
+
+/// Process the AS_TRANS RIB with peer-stats and as2rel, returning the outputs.
+fn process(dir: &str, correction: bool) -> (Value, Value, u64) {
+    let rib_meta = RibMeta {
+        collector: "rrc00".to_string(),
+        ..Default::default()
+    };
+    let names = vec!["peer-stats".to_string(), "as2rel".to_string()];
+    let mut ribeye = RibEye::new()
+        .with_processor_names(&names, dir)
+        .unwrap()
+        .with_as_trans_correction(correction)
+        .with_rib_meta(&rib_meta);
+    ribeye.process_elems(as_trans_rib().elems()).unwrap();
+    let corrected = ribeye.stats().as_trans_corrected;
+    ribeye.output().unwrap();
+    let read = |name: &str| {
+        FileSink
+            .read_json(format!("{}/{}/rrc00/latest.json.bz2", dir, name).as_str())
+            .unwrap()
+    };
+    (read("peer-stats"), read("as2rel"), corrected)
+}
+
+fn peer_asns(peer_stats: &Value) -> Vec<u64> {
+    let mut asns: Vec<u64> = peer_stats["peers"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|p| p["asn"].as_u64().unwrap())
+        .collect();
+    asns.sort();
+    asns
+}
+
+fn as2rel_asns(as2rel: &Value) -> Vec<u64> {
+    as2rel["as2rel"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .flat_map(|e| [e["asn1"].as_u64().unwrap(), e["asn2"].as_u64().unwrap()])
+        .collect()
+}
+
+#[test]
+fn test_resolver() {
+    let mut resolver = AsTransResolver::new();
+    let elems = as_trans_rib().build();
+
+    let corrected = resolver.correct(&elems[0]).unwrap();
+    assert_eq!(corrected.peer_asn.to_u32(), 4200000001);
+    assert_eq!(resolver.len(), 1);
+
+    // leading AS_TRANS hops are replaced by the resolved ASN
+    let corrected = resolver.correct(&elems[1]).unwrap();
+    assert_eq!(
+        corrected.as_path.unwrap().to_u32_vec_opt(false).unwrap(),
+        vec![4200000001, 4200000001, 3356, 15169]
+    );
+    // withdrawals have no AS path and use the remembered ASN
+    assert_eq!(
+        resolver.correct(&elems[2]).unwrap().peer_asn.to_u32(),
+        4200000001
+    );
+    // elements of other peers need no correction
+    assert!(resolver.correct(&elems[3]).is_none());
+}
+
+#[test]
+fn test_unresolved_peer_unchanged() {
+    let mut resolver = AsTransResolver::new();
+    let elems = SyntheticRibBuilder::new()
+        .peer("192.0.2.1", AS_TRANS)
+        .withdrawal("9.9.9.0/24")
+        .route("8.8.8.0/24", &[AS_TRANS, 3356, 15169])
+        .build();
+    assert!(resolver.correct(&elems[0]).is_none());
+    assert!(resolver.correct(&elems[1]).is_none());
+    assert!(resolver.is_empty());
+}
+
+#[test]
+fn test_pipeline_correction() {
+    let output_dir = tempfile::tempdir().unwrap();
+    let dir = output_dir.path().to_str().unwrap();
+
+    let (peer_stats, as2rel, corrected) = process(dir, true);
+    assert_eq!(corrected, 3);
+    assert_eq!(peer_asns(&peer_stats), vec![65002, 4200000001]);
+    assert!(!as2rel_asns(&as2rel).contains(&(AS_TRANS as u64)));
+
+    let (peer_stats, as2rel, corrected) = process(dir, false);
+    assert_eq!(corrected, 0);
+    assert_eq!(peer_asns(&peer_stats), vec![AS_TRANS as u64, 65002]);
+    assert!(as2rel_asns(&as2rel).contains(&(AS_TRANS as u64)));
+}
@@ -0,0 +1,121 @@
+use chrono::NaiveDateTime;
+use ribeye::audit::{AuditIssue, OutputAudit};
+use ribeye::index::write_output_index;
+use ribeye::listing::list_outputs;
+use ribeye::processors::{FileSink, OutputSink};
+use ribeye::signing::SigningKey;
+use ribeye::testing::SyntheticRibBuilder;
+use ribeye::{RibEye, RibMeta};
+use serde_json::json;
+use std::sync::Arc;
+
+/// Cook pfx2as outputs of two RIB dumps of rrc00 under `dir`, summarized and indexed.
+fn cook(dir: &str, key: Option<Arc<SigningKey>>) {
+    let elems = SyntheticRibBuilder::new()
+        .peer("192.0.2.1", 65001)
+        .route("1.1.1.0/24", &[65001, 174, 13335])
+        .build();
+    let mut rib_metas = vec![];
+    for time in ["2024-01-01 00:00", "2024-01-01 08:00"] {
+        let rib_meta = RibMeta {
+            collector: "rrc00".to_string(),
+            timestamp: NaiveDateTime::parse_from_str(time, "%Y-%m-%d %H:%M").unwrap(),
+            ..Default::default()
+        };
+        let mut ribeye = RibEye::new()
+            .with_processor_names(&vec!["pfx2as".to_string()], dir)
+            .unwrap()
+            .with_rib_meta(&rib_meta);
+        if let Some(key) = &key {
+            ribeye = ribeye.with_signing_key(key.clone());
+        }
+        ribeye.process_elems(&elems).unwrap();
+        ribeye.output().unwrap();
+        rib_metas = vec![rib_meta];
+    }
+    let mut ribeye = RibEye::new()
+        .with_processor_names(&vec!["pfx2as".to_string()], dir)
+        .unwrap();
+    if let Some(key) = &key {
+        ribeye = ribeye.with_signing_key(key.clone());
+    }
+    ribeye.summarize_latest_files(&rib_metas).unwrap();
+    write_output_index(dir).unwrap();
+}
+
+fn checks(issues: &[AuditIssue]) -> Vec<(&str, &str)> {
+    issues
+        .iter()
+        .map(|i| (i.check.as_str(), i.path.as_str()))
+        .collect()
+}
+
+#[test]
+fn test_audit_consistent_tree() {
+    let output_dir = tempfile::tempdir().unwrap();
+    let dir = output_dir.path().to_str().unwrap();
+    cook(dir, None);
+
+    let report = OutputAudit::new(dir).run().unwrap();
+    assert!(report.passed(), "{:?}", report.issues);
+    // summary, latest and two dated outputs
+    assert_eq!(report.files, 4);
+}
+
+#[test]
+fn test_audit_issues() {
+    let output_dir = tempfile::tempdir().unwrap();
+    let dir = output_dir.path().to_str().unwrap();
+    cook(dir, None);
+    let dated: Vec<String> = list_outputs(dir)
+        .unwrap()
+        .into_iter()
+        .filter(|o| o.ts.is_some())
+        .map(|o| o.path)
+        .collect();
+
+    // an unreadable dated output, a newer dated output than the latest one and a summary not
+    // matching its schema
+    std::fs::write(format!("{dir}/{}", dated[0]), "garbage").unwrap();
+    let newer = dated[1].replace("1704096000", "1704182400");
+    std::fs::copy(format!("{dir}/{}", dated[1]), format!("{dir}/{newer}")).unwrap();
+    FileSink
+        .write_json(
+            format!("{dir}/pfx2as/latest.json.bz2").as_str(),
+            &json!({"pfx2as": 1}),
+        )
+        .unwrap();
+
+    let report = OutputAudit::new(dir).run().unwrap();
+    assert_eq!(
+        checks(&report.issues),
+        vec![
+            ("schema", "pfx2as/latest.json.bz2"),
+            ("parse", dated[0].as_str()),
+            ("latest", "pfx2as/rrc00/latest.json.bz2"),
+            ("index", newer.as_str()),
+        ]
+    );
+
+    // files listed in the index must exist
+    std::fs::remove_file(format!("{dir}/{newer}")).unwrap();
+    std::fs::remove_file(format!("{dir}/{}", dated[1])).unwrap();
+    let report = OutputAudit::new(dir).run().unwrap();
+    assert!(checks(&report.issues).contains(&("index", dated[1].as_str())));
+}
+
+#[test]
+fn test_audit_signatures() {
+    let output_dir = tempfile::tempdir().unwrap();
+    let dir = output_dir.path().to_str().unwrap();
+    let key = Arc::new(SigningKey::generate().unwrap());
+    cook(dir, Some(key.clone()));
+
+    let audit = OutputAudit::new(dir).with_public_key(key.public_key());
+    assert!(audit.run().unwrap().passed());
+
+    let other = SigningKey::generate().unwrap().public_key();
+    let report = OutputAudit::new(dir).with_public_key(other).run().unwrap();
+    assert_eq!(report.issues.len(), 4);
+    assert!(report.issues.iter().all(|i| i.check == "signature"));
+}
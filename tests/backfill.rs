@@ -0,0 +1,160 @@
+use chrono::{NaiveDate, NaiveDateTime};
+use ribeye::backfill::{backfill_dates, select_backfill_ribs, BackfillInterval, BackfillState};
+use ribeye::collectors::CollectorProfiles;
+use ribeye::testing::SyntheticRibBuilder;
+use ribeye::{RibEye, RibMeta};
+
+fn date(s: &str) -> NaiveDate {
+    NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap()
+}
+
+fn rib(collector: &str, time: &str) -> RibMeta {
+    RibMeta {
+        collector: collector.to_string(),
+        rib_dump_url: format!("https://archive.example/{}/{}.bz2", collector, time),
+        timestamp: NaiveDateTime::parse_from_str(time, "%Y-%m-%d %H:%M").unwrap(),
+        ..Default::default()
+    }
+}
+
+#[test]
+fn test_backfill_dates() {
+    assert_eq!(
+        backfill_dates(
+            date("2015-01-30"),
+            date("2015-02-02"),
+            BackfillInterval::Daily
+        ),
+        vec![
+            date("2015-01-30"),
+            date("2015-01-31"),
+            date("2015-02-01"),
+            date("2015-02-02")
+        ]
+    );
+    assert_eq!(
+        backfill_dates(
+            date("2015-01-15"),
+            date("2015-04-01"),
+            BackfillInterval::Monthly
+        ),
+        vec![date("2015-02-01"), date("2015-03-01"), date("2015-04-01")]
+    );
+    assert!(backfill_dates(
+        date("2015-02-01"),
+        date("2015-01-01"),
+        BackfillInterval::Daily
+    )
+    .is_empty());
+    assert_eq!(
+        "monthly".parse::<BackfillInterval>().unwrap(),
+        BackfillInterval::Monthly
+    );
+}
+
+#[test]
+fn test_select_backfill_ribs() {
+    let ribs = vec![
+        rib("route-views2", "2015-02-01 02:00"),
+        rib("rrc00", "2015-02-01 00:00"),
+        rib("route-views2", "2015-02-01 00:00"),
+        rib("route-views2", "2015-02-02 00:00"),
+        rib("route-views2", "2015-01-01 00:00"),
+        // listed twice by overlapping queries
+        rib("route-views2", "2015-01-01 00:00"),
+    ];
+    let dates = backfill_dates(
+        date("2015-01-01"),
+        date("2015-02-28"),
+        BackfillInterval::Monthly,
+    );
+    let selected = select_backfill_ribs(
+        ribs.clone(),
+        &dates,
+        &["route-views2".to_string()],
+        &CollectorProfiles::new(),
+    );
+    let urls: Vec<_> = selected.iter().map(|r| r.rib_dump_url.as_str()).collect();
+    assert_eq!(
+        urls,
+        vec![
+            "https://archive.example/route-views2/2015-01-01 00:00.bz2",
+            "https://archive.example/route-views2/2015-02-01 00:00.bz2",
+        ]
+    );
+    // all collectors
+    assert_eq!(
+        select_backfill_ribs(ribs, &dates, &[], &CollectorProfiles::new()).len(),
+        3
+    );
+}
+
+#[test]
+fn test_backfill_state() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let path = temp_dir.path().join("state.json");
+    let path = path.to_str().unwrap();
+    let collectors = vec!["route-views2".to_string()];
+    let new_state = || {
+        BackfillState::new(
+            date("2015-01-01"),
+            date("2015-12-31"),
+            BackfillInterval::Daily,
+            &collectors,
+            &[],
+        )
+    };
+
+    let mut state = BackfillState::load_or(path, new_state()).unwrap();
+    let ribs = vec![
+        rib("route-views2", "2015-01-01 00:00"),
+        rib("route-views2", "2015-01-02 00:00"),
+    ];
+    state.mark_failed(ribs[0].rib_dump_url.as_str(), "connection reset");
+    state.mark_done(ribs[1].rib_dump_url.as_str());
+    state.save(path).unwrap();
+
+    // resumed: only the failed RIB dump remains
+    let state = BackfillState::load_or(path, new_state()).unwrap();
+    assert_eq!(state.failed.len(), 1);
+    let remaining = state.remaining(ribs.clone());
+    assert_eq!(remaining.len(), 1);
+    assert_eq!(remaining[0].rib_dump_url, ribs[0].rib_dump_url);
+
+    // another backfill does not pick up the state
+    let other = BackfillState::new(
+        date("2016-01-01"),
+        date("2016-12-31"),
+        BackfillInterval::Daily,
+        &collectors,
+        &[],
+    );
+    assert!(BackfillState::load_or(path, other).is_err());
+}
+
+#[test]
+fn test_backfill_keeps_latest_outputs() {
+    let output_dir = tempfile::tempdir().unwrap();
+    let dir = output_dir.path().to_str().unwrap();
+    let mrt_path = output_dir.path().join("rib.test.bz2");
+    let mrt_path = mrt_path.to_str().unwrap();
+    SyntheticRibBuilder::new()
+        .peer("192.0.2.1", 65001)
+        .route("1.1.1.0/24", &[65001, 174, 13335])
+        .write_mrt_file(mrt_path)
+        .unwrap();
+
+    let rib_meta = rib("rrc00", "2015-01-01 00:00");
+    let mut ribeye = RibEye::new()
+        .with_processor_names(&vec!["pfx2as".to_string()], dir)
+        .unwrap()
+        .with_latest_outputs(false)
+        .with_rib_meta(&rib_meta);
+    ribeye.process_mrt_file(mrt_path).unwrap();
+
+    let collector_dir = output_dir.path().join("pfx2as/rrc00");
+    assert!(collector_dir
+        .join("2015/01/pfx2as_rrc00_2015-01-01_0000_1420070400.json.bz2")
+        .exists());
+    assert!(!collector_dir.join("latest.json.bz2").exists());
+}
@@ -0,0 +1,111 @@
+use bgpkit_parser::models::ElemType;
+use ribeye::bmp::{bmp_msg_to_elems, read_bmp_message};
+use std::io::Cursor;
+
+const PEER_IP: [u8; 4] = [192, 0, 2, 1];
+const PEER_ASN: u32 = 65001;
+
+/// BMP message of the given type: common header, per-peer header of an IPv4 peer with 4-byte
+/// ASNs, and the body.
+fn bmp_message(msg_type: u8, body: &[u8]) -> Vec<u8> {
+    let mut per_peer = vec![0u8, 0u8];
+    per_peer.extend_from_slice(&[0u8; 8]);
+    per_peer.extend_from_slice(&[0u8; 12]);
+    per_peer.extend_from_slice(&PEER_IP);
+    per_peer.extend_from_slice(&PEER_ASN.to_be_bytes());
+    per_peer.extend_from_slice(&PEER_IP);
+    per_peer.extend_from_slice(&1_700_000_000u32.to_be_bytes());
+    per_peer.extend_from_slice(&0u32.to_be_bytes());
+
+    let msg_len = (6 + per_peer.len() + body.len()) as u32;
+    let mut msg = vec![3u8];
+    msg.extend_from_slice(&msg_len.to_be_bytes());
+    msg.push(msg_type);
+    msg.extend(per_peer);
+    msg.extend_from_slice(body);
+    msg
+}
+
+/// BGP UPDATE announcing 192.0.2.0/24 with the given AS path and withdrawing 198.51.100.0/24.
+fn bgp_update(as_path: &[u32]) -> Vec<u8> {
+    let withdrawn = [24u8, 198, 51, 100];
+
+    let mut path_segment = vec![2u8, as_path.len() as u8];
+    for asn in as_path {
+        path_segment.extend_from_slice(&asn.to_be_bytes());
+    }
+    let mut attributes = vec![0x40, 1, 1, 0];
+    attributes.extend_from_slice(&[0x40, 2, path_segment.len() as u8]);
+    attributes.extend(path_segment);
+    attributes.extend_from_slice(&[0x40, 3, 4]);
+    attributes.extend_from_slice(&PEER_IP);
+
+    let nlri = [24u8, 192, 0, 2];
+
+    let mut body = (withdrawn.len() as u16).to_be_bytes().to_vec();
+    body.extend_from_slice(&withdrawn);
+    body.extend_from_slice(&(attributes.len() as u16).to_be_bytes());
+    body.extend(attributes);
+    body.extend_from_slice(&nlri);
+
+    let mut msg = vec![0xffu8; 16];
+    msg.extend_from_slice(&((19 + body.len()) as u16).to_be_bytes());
+    msg.push(2);
+    msg.extend(body);
+    msg
+}
+
+#[test]
+fn test_read_bmp_message() {
+    let mut data = bmp_message(0, &bgp_update(&[65001, 64500]));
+    // peer down, remote system closed without data
+    data.extend(bmp_message(2, &[4]));
+    let mut reader = Cursor::new(data);
+
+    let route_monitoring = read_bmp_message(&mut reader).unwrap().unwrap();
+    let peer_header = route_monitoring.per_peer_header.unwrap();
+    assert_eq!(peer_header.peer_ip.to_string(), "192.0.2.1");
+    assert_eq!(peer_header.peer_asn.to_u32(), PEER_ASN);
+
+    let peer_down = read_bmp_message(&mut reader).unwrap().unwrap();
+    assert!(bmp_msg_to_elems(peer_down).is_empty());
+
+    // end of stream
+    assert!(read_bmp_message(&mut reader).unwrap().is_none());
+}
+
+#[test]
+fn test_read_invalid_bmp_message() {
+    // message length shorter than the common header
+    let mut reader = Cursor::new(vec![3u8, 0, 0, 0, 2, 0]);
+    assert!(read_bmp_message(&mut reader).is_err());
+
+    // stream ends within the message
+    let mut data = bmp_message(0, &bgp_update(&[65001, 64500]));
+    data.truncate(data.len() - 4);
+    let mut reader = Cursor::new(data);
+    assert!(read_bmp_message(&mut reader).is_err());
+}
+
+#[test]
+fn test_bmp_msg_to_elems() {
+    let mut reader = Cursor::new(bmp_message(0, &bgp_update(&[65001, 64500])));
+    let msg = read_bmp_message(&mut reader).unwrap().unwrap();
+    let mut elems = bmp_msg_to_elems(msg);
+    elems.sort_by_key(|e| e.prefix.to_string());
+    assert_eq!(elems.len(), 2);
+
+    let announce = &elems[0];
+    assert_eq!(announce.elem_type, ElemType::ANNOUNCE);
+    assert_eq!(announce.prefix.to_string(), "192.0.2.0/24");
+    assert_eq!(announce.peer_ip.to_string(), "192.0.2.1");
+    assert_eq!(announce.peer_asn.to_u32(), PEER_ASN);
+    assert_eq!(announce.timestamp, 1_700_000_000.0);
+    let origins = announce.origin_asns.clone().unwrap();
+    assert_eq!(origins.len(), 1);
+    assert_eq!(origins[0].to_u32(), 64500);
+
+    let withdraw = &elems[1];
+    assert_eq!(withdraw.elem_type, ElemType::WITHDRAW);
+    assert_eq!(withdraw.prefix.to_string(), "198.51.100.0/24");
+}
@@ -0,0 +1,65 @@
+use ribeye::processors::Prefix2AsProcessor;
+use ribeye::testing::{RecordingProcessor, SyntheticRibBuilder};
+use ribeye::util::format_count;
+use ribeye::{MessageProcessor, RibEye, RibMeta};
+
+#[test]
+fn test_format_count() {
+    assert_eq!(format_count(0), "0");
+    assert_eq!(format_count(950), "950");
+    assert_eq!(format_count(1_000), "1k");
+    assert_eq!(format_count(1_234), "1.2k");
+    assert_eq!(format_count(78_412), "78k");
+    assert_eq!(format_count(950_123), "950k");
+    assert_eq!(format_count(999_999), "1M");
+    assert_eq!(format_count(3_420_000), "3.4M");
+    assert_eq!(format_count(12_600_000), "13M");
+}
+
+#[test]
+fn test_brief_summaries() {
+    let output_dir = tempfile::tempdir().unwrap();
+    let dir = output_dir.path().to_str().unwrap();
+    let elems = SyntheticRibBuilder::new()
+        .peer("10.0.0.1", 65001)
+        .route("1.0.0.0/24", &[65001, 13335])
+        .route("1.1.1.0/24", &[65001, 13335])
+        .route("8.8.8.0/24", &[65001, 15169])
+        .peer("10.0.0.2", 65002)
+        .route("1.1.1.0/24", &[65002, 13335])
+        .build();
+    let processors = ["pfx2as", "pfxlen", "peer-stats", "defaults"]
+        .iter()
+        .map(|p| p.to_string())
+        .collect();
+    let mut ribeye = RibEye::new()
+        .with_processor_names(&processors, dir)
+        .unwrap()
+        .with_brief_summaries(true)
+        .with_rib_meta(&RibMeta {
+            collector: "rrc00".to_string(),
+            ..Default::default()
+        });
+    ribeye.process_elems(&elems).unwrap();
+    ribeye.output().unwrap();
+    assert_eq!(
+        ribeye.brief_summaries(),
+        vec![
+            "pfx2as: 3 prefixes, 2 origins, top origin AS13335 (2 prefixes)",
+            "pfxlen: 3 prefix-origin pairs, most common /24 (3)",
+            "peer-stats: 2 peers, 0 full-feed",
+            "defaults: 0 default routes",
+        ]
+    );
+
+    // outside a pipeline, without shared full-feed peers
+    let mut processor = Prefix2AsProcessor::new(dir);
+    MessageProcessor::reset_processor(&mut processor, &RibMeta::default());
+    assert_eq!(
+        MessageProcessor::brief_summary(&processor),
+        "pfx2as: 0 prefixes, 0 origins"
+    );
+
+    // processors without a brief summary give their name
+    assert_eq!(RecordingProcessor::new("probe").brief_summary(), "probe");
+}
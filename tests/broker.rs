@@ -0,0 +1,33 @@
+use chrono::NaiveDateTime;
+use ribeye::broker::BrokerConfig;
+use std::time::Duration;
+
+/// Broker URL that refuses connections.
+const UNREACHABLE_BROKER: &str = "http://127.0.0.1:9";
+
+#[test]
+fn test_broker_cache_and_retries() {
+    let cache_dir = tempfile::tempdir().unwrap();
+    let ts_start =
+        NaiveDateTime::parse_from_str("2024-01-01T00:00:00", "%Y-%m-%dT%H:%M:%S").unwrap();
+    let ts_end = NaiveDateTime::parse_from_str("2024-01-02T00:00:00", "%Y-%m-%dT%H:%M:%S").unwrap();
+
+    // without cache, the query fails after all retries
+    let broker = BrokerConfig::new(UNREACHABLE_BROKER).with_retries(2, Duration::ZERO);
+    assert!(broker.query_ribs(ts_start, ts_end).is_err());
+
+    // a cached item list is used without contacting the broker
+    let broker = broker.with_cache_dir(cache_dir.path().to_str().unwrap());
+    let cache_path = broker.rib_cache_path(ts_start, ts_end).unwrap();
+    std::fs::write(
+        &cache_path,
+        r#"[{"ts_start":"2024-01-01T00:00:00","ts_end":"2024-01-01T00:00:00","collector_id":"rrc00","data_type":"rib","url":"https://data.ris.ripe.net/rrc00/2024.01/bview.20240101.0000.gz","rough_size":1,"exact_size":0}]"#,
+    )
+    .unwrap();
+    let items = broker.query_ribs(ts_start, ts_end).unwrap();
+    assert_eq!(items.len(), 1);
+    assert_eq!(items[0].collector_id, "rrc00");
+
+    // latest items are never cached
+    assert!(broker.query_latest_ribs().is_err());
+}
@@ -0,0 +1,75 @@
+use ribeye::budget::{resident_memory, BudgetAction, BudgetLimit, ProcessingBudget};
+use ribeye::testing::{RecordingProcessor, SyntheticRibBuilder};
+use ribeye::{MessageProcessor, RibEye, RibMeta};
+use std::time::Duration;
+
+/// Process 10 elements with a critical and an optional recording processor, returning the
+/// number of elements each received and the pipeline.
+fn run(budget: ProcessingBudget) -> (usize, usize, RibEye) {
+    let critical = RecordingProcessor::new("critical");
+    let optional = RecordingProcessor::new("optional");
+    let (critical_recording, optional_recording) = (critical.recording(), optional.recording());
+    let mut ribeye = RibEye::new()
+        .with_processors(vec![critical.to_boxed(), optional.to_boxed()])
+        .with_rib_meta(&RibMeta::default())
+        .with_optional_processors(&["optional".to_string()])
+        .with_budget(budget);
+    ribeye
+        .process_elems(SyntheticRibBuilder::new().generated(2, 5).elems())
+        .unwrap();
+    let critical = critical_recording.lock().unwrap().elems.len();
+    let optional = optional_recording.lock().unwrap().elems.len();
+    (critical, optional, ribeye)
+}
+
+#[test]
+fn test_within_budget() {
+    let budget = ProcessingBudget::new().with_max_duration(Duration::from_secs(3600));
+    let (critical, optional, ribeye) = run(budget);
+    assert_eq!((critical, optional), (10, 10));
+    assert!(ribeye.report().budget_decisions.is_empty());
+}
+
+#[test]
+fn test_skip_over_budget() {
+    if resident_memory().is_none() {
+        return;
+    }
+    let (critical, optional, ribeye) = run(ProcessingBudget::new().with_max_memory(1));
+    assert_eq!((critical, optional), (10, 0));
+
+    let decisions = ribeye.report().budget_decisions;
+    assert_eq!(decisions.len(), 1);
+    assert_eq!(decisions[0].processor, "optional");
+    assert_eq!(decisions[0].action, BudgetAction::Skipped);
+    assert_eq!(decisions[0].limit, BudgetLimit::Memory);
+    assert_eq!(decisions[0].after_elems, 0);
+    assert_eq!(decisions[0].sample_every, None);
+}
+
+#[test]
+fn test_sample_over_budget() {
+    if resident_memory().is_none() {
+        return;
+    }
+    let budget = ProcessingBudget::new()
+        .with_max_memory(1)
+        .with_sample_every(3);
+    let (critical, optional, ribeye) = run(budget);
+    // elements 0, 3, 6 and 9
+    assert_eq!((critical, optional), (10, 4));
+    let decisions = ribeye.report().budget_decisions;
+    assert_eq!(decisions[0].action, BudgetAction::Sampled);
+    assert_eq!(decisions[0].sample_every, Some(3));
+}
+
+#[test]
+fn test_decisions_reset_per_rib() {
+    if resident_memory().is_none() {
+        return;
+    }
+    let (_, _, mut ribeye) = run(ProcessingBudget::new().with_max_memory(1));
+    assert_eq!(ribeye.report().budget_decisions.len(), 1);
+    ribeye.initialize_processors(&RibMeta::default()).unwrap();
+    assert!(ribeye.report().budget_decisions.is_empty());
+}
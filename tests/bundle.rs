@@ -0,0 +1,89 @@
+use chrono::NaiveDateTime;
+use ribeye::bundle::{read_bundle_part, BundleManifest, OutputBundle, BUNDLE_MANIFEST_FILE_NAME};
+use ribeye::listing::list_outputs;
+use ribeye::processors::FileSink;
+use ribeye::testing::SyntheticRibBuilder;
+use ribeye::{RibEye, RibMeta};
+use serde_json::Value;
+use std::sync::Arc;
+
+fn rib_meta(collector: &str, time: &str) -> RibMeta {
+    RibMeta {
+        collector: collector.to_string(),
+        timestamp: NaiveDateTime::parse_from_str(time, "%Y-%m-%d %H:%M").unwrap(),
+        ..Default::default()
+    }
+}
+
+#[test]
+fn test_bundle_dated_outputs() {
+    let output_dir = tempfile::tempdir().unwrap();
+    let dir = output_dir.path().to_str().unwrap();
+    let elems = SyntheticRibBuilder::new()
+        .peer("192.0.2.1", 65001)
+        .route("1.1.1.0/24", &[65001, 174, 13335])
+        .build();
+
+    // a part per output
+    let bundle = Arc::new(OutputBundle::new(dir, "run-1").with_part_size(1));
+    for rib_meta in [
+        rib_meta("rrc00", "2024-01-01 00:00"),
+        rib_meta("route-views2", "2024-01-01 00:00"),
+    ] {
+        let mut ribeye = RibEye::new()
+            .with_processor_names(&vec!["pfx2as".to_string()], dir)
+            .unwrap()
+            .with_bundle(bundle.clone())
+            .with_rib_meta(&rib_meta);
+        ribeye.process_elems(&elems).unwrap();
+        ribeye.output().unwrap();
+    }
+    assert_eq!(bundle.finish().unwrap(), 2);
+
+    // only latest outputs are written as files
+    let outputs = list_outputs(dir).unwrap();
+    assert_eq!(outputs.len(), 2);
+    assert!(outputs.iter().all(|o| o.ts.is_none()));
+
+    let manifest_path = format!("{dir}/bundles/run-1/{BUNDLE_MANIFEST_FILE_NAME}");
+    let manifest: BundleManifest = oneio::read_json_struct(manifest_path.as_str()).unwrap();
+    let paths: Vec<&str> = manifest.parts.iter().map(|p| p.path.as_str()).collect();
+    assert_eq!(paths, vec!["part-00000.tar.gz", "part-00001.tar.gz"]);
+
+    let part = format!("{dir}/bundles/run-1/part-00000.tar.gz");
+    let entries = read_bundle_part(&FileSink, part.as_str()).unwrap();
+    assert_eq!(entries.len(), 1);
+    assert_eq!(entries[0].path, manifest.parts[0].entries[0]);
+    assert!(entries[0]
+        .path
+        .starts_with("pfx2as/rrc00/2024/01/pfx2as_rrc00_"));
+    assert!(entries[0].path.ends_with("_1704067200.json"));
+
+    // bundled outputs have the content of the latest output of the same RIB dump
+    let bundled: Value = serde_json::from_slice(&entries[0].data).unwrap();
+    let latest: Value =
+        oneio::read_json_struct(format!("{dir}/pfx2as/rrc00/latest.json.bz2").as_str()).unwrap();
+    assert_eq!(bundled["pfx2as"], latest["pfx2as"]);
+}
+
+#[test]
+fn test_bundle_entry_paths() {
+    let bundle = OutputBundle::new("s3://bucket/ribeye/", "run-1");
+    assert_eq!(bundle.bundle_dir(), "s3://bucket/ribeye/bundles/run-1");
+    assert_eq!(
+        bundle
+            .entry_path("s3://bucket/ribeye/pfx2as/rrc00/2024/01/pfx2as_rrc00_2024-01-01_0000_1704067200.cbor.bz2")
+            .as_deref(),
+        Some("pfx2as/rrc00/2024/01/pfx2as_rrc00_2024-01-01_0000_1704067200.cbor")
+    );
+    for path in [
+        "s3://bucket/ribeye/pfx2as/rrc00/latest.json.bz2",
+        "s3://bucket/ribeye/pfx2as/latest.json.bz2",
+        "s3://bucket/other/pfx2as/rrc00/2024/01/pfx2as_rrc00_2024-01-01_0000_1704067200.json.bz2",
+    ] {
+        assert!(bundle.entry_path(path).is_none(), "{}", path);
+    }
+
+    // nothing bundled, nothing written
+    assert_eq!(bundle.finish().unwrap(), 0);
+}
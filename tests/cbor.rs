@@ -0,0 +1,100 @@
+use chrono::NaiveDateTime;
+use ribeye::inspect::OutputFile;
+use ribeye::listing::list_outputs;
+use ribeye::processors::{
+    get_encoded_path, is_cbor_path, FileSink, OutputEncoding, OutputSink, Prefix2AsProcessor,
+    TypedProcessor,
+};
+use ribeye::testing::SyntheticRibBuilder;
+use ribeye::{RibEye, RibMeta};
+
+const DATED: &str = "pfx2as/rrc00/2024/01/pfx2as_rrc00_2024-01-01_0800_1704096000";
+
+/// Process a synthetic RIB with pfx2as and summarize it.
+fn process(dir: &str, encoding: OutputEncoding) {
+    let rib_metas = vec![RibMeta {
+        collector: "rrc00".to_string(),
+        timestamp: NaiveDateTime::parse_from_str("2024-01-01 08:00", "%Y-%m-%d %H:%M").unwrap(),
+        ..Default::default()
+    }];
+    let mut ribeye = RibEye::new()
+        .with_processor_names(&vec!["pfx2as".to_string()], dir)
+        .unwrap()
+        .with_output_encoding(encoding)
+        .with_rib_meta(&rib_metas[0]);
+    ribeye
+        .process_elems(SyntheticRibBuilder::new().generated(4, 50).elems())
+        .unwrap();
+    ribeye.output().unwrap();
+    ribeye.summarize_latest_files(&rib_metas).unwrap();
+}
+
+#[test]
+fn test_encoded_paths() {
+    assert_eq!(
+        get_encoded_path("out/pfx2as_rrc00_1704096000.json.bz2", OutputEncoding::Cbor),
+        "out/pfx2as_rrc00_1704096000.cbor.bz2"
+    );
+    assert_eq!(
+        get_encoded_path("out.json/latest", OutputEncoding::Cbor),
+        "out.json/latest"
+    );
+    assert_eq!(
+        get_encoded_path("out/latest.json.bz2", OutputEncoding::Json),
+        "out/latest.json.bz2"
+    );
+    assert!(is_cbor_path("s3://bucket/pfx2as/latest.cbor.bz2"));
+    assert!(!is_cbor_path("results.cbor/pfx2as/latest.json.bz2"));
+    assert_eq!(
+        "CBOR".parse::<OutputEncoding>().unwrap(),
+        OutputEncoding::Cbor
+    );
+    assert!("msgpack".parse::<OutputEncoding>().is_err());
+}
+
+#[test]
+fn test_cbor_outputs() {
+    let json_dir = tempfile::tempdir().unwrap();
+    let json_dir = json_dir.path().to_str().unwrap();
+    process(json_dir, OutputEncoding::Json);
+    let cbor_dir = tempfile::tempdir().unwrap();
+    let cbor_dir = cbor_dir.path().to_str().unwrap();
+    process(cbor_dir, OutputEncoding::Cbor);
+
+    let json_path = format!("{}/{}.json.bz2", json_dir, DATED);
+    let cbor_path = format!("{}/{}.cbor.bz2", cbor_dir, DATED);
+    assert!(!std::path::Path::new(format!("{}/{}.json.bz2", cbor_dir, DATED).as_str()).exists());
+    let json_size = FileSink.read_bytes(json_path.as_str()).unwrap().len();
+    let cbor_size = FileSink.read_bytes(cbor_path.as_str()).unwrap().len();
+    assert!(cbor_size < json_size, "{} >= {}", cbor_size, json_size);
+
+    // same data model: the CBOR output decodes to the same value as the latest JSON output
+    let cbor = FileSink.read_json(cbor_path.as_str()).unwrap();
+    let latest = format!("{}/pfx2as/rrc00/latest.json.bz2", cbor_dir);
+    assert_eq!(cbor, FileSink.read_json(latest.as_str()).unwrap());
+    let output = Prefix2AsProcessor::read_output(cbor_path.as_str()).unwrap();
+    assert_eq!(output.pfx2as.len(), 50);
+    assert_eq!(
+        OutputFile::load(cbor_path.as_str()).unwrap().records.len(),
+        50
+    );
+
+    // latest outputs and summaries stay JSON
+    let summary = FileSink
+        .read_json(format!("{}/pfx2as/latest.json.bz2", cbor_dir).as_str())
+        .unwrap();
+    assert_eq!(summary["pfx2as"].as_array().unwrap().len(), 50);
+    let mut paths: Vec<String> = list_outputs(cbor_dir)
+        .unwrap()
+        .into_iter()
+        .map(|o| o.path)
+        .collect();
+    paths.sort();
+    assert_eq!(
+        paths,
+        vec![
+            format!("{}.cbor.bz2", DATED),
+            "pfx2as/rrc00/latest.json.bz2".to_string()
+        ]
+    );
+}
@@ -0,0 +1,121 @@
+use bgpkit_broker::BrokerItem;
+use chrono::NaiveDateTime;
+use ribeye::collectors::{CollectorProfile, CollectorProfiles};
+use ribeye::processors::Prefix2AsProcessor;
+use ribeye::{MessageProcessor, RibMeta};
+
+fn time(s: &str) -> NaiveDateTime {
+    NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M").unwrap()
+}
+
+fn broker_item(collector: &str, url: &str) -> BrokerItem {
+    BrokerItem {
+        ts_start: time("2024-01-01 00:00"),
+        ts_end: time("2024-01-01 00:00"),
+        collector_id: collector.to_string(),
+        data_type: "rib".to_string(),
+        url: url.to_string(),
+        rough_size: 0,
+        exact_size: 0,
+    }
+}
+
+#[test]
+fn test_builtin_projects() {
+    let profiles = CollectorProfiles::new();
+    let project = |collector: &str, url: &str| profiles.project(collector, url);
+    assert_eq!(
+        project("rrc00", "https://data.ris.ripe.net/rrc00/"),
+        "riperis"
+    );
+    assert_eq!(project("route-views2", ""), "route-views");
+    // kernel and extended collectors of RouteViews
+    assert_eq!(project("route-views.kixp", ""), "route-views");
+    assert_eq!(project("route-views.amsix", ""), "route-views");
+    assert_eq!(project("route-collector.sfo.pch.net", ""), "pch");
+    // collectors matching no pattern are identified by the archive host
+    assert_eq!(
+        project(
+            "decix.jhb",
+            "https://archive.routeviews.org/decix.jhb/bgpdata/2024.01/RIBS/rib.20240101.0000.bz2"
+        ),
+        "route-views"
+    );
+    assert_eq!(project("rrc100", "https://example.org/rib.bz2"), "unknown");
+
+    let item = broker_item(
+        "route-views.kixp",
+        "https://archive.routeviews.org/route-views.kixp/bgpdata/2024.01/RIBS/rib.20240101.0000.bz2",
+    );
+    assert_eq!(RibMeta::from(&item).project, "route-views");
+}
+
+#[test]
+fn test_custom_profiles() {
+    let profiles = CollectorProfiles::new()
+        .with_json_str(
+            r#"[{"project": "isolario", "collectors": ["Alderaan", "Dagobah"],
+                 "archive_hosts": ["isolario.it"], "daily_dump_hour": 2}]"#,
+        )
+        .unwrap()
+        .with_profile(CollectorProfile::new("lab", &["rrc99"]));
+    assert_eq!(profiles.project("Dagobah", ""), "isolario");
+    // custom profiles take precedence over the built-in ones
+    assert_eq!(profiles.project("rrc99", ""), "lab");
+    assert_eq!(profiles.project("rrc00", ""), "riperis");
+    assert!(CollectorProfiles::new().with_json_str("{}").is_err());
+
+    let item = broker_item("Dagobah", "https://isolario.it/Dagobah/rib.bz2");
+    assert_eq!(
+        RibMeta::from_broker_item(&item, &profiles).project,
+        "isolario"
+    );
+
+    // daily dumps follow the collector's cadence
+    let daily = |collector: &str, t: &str| profiles.is_daily_dump(collector, "", &time(t));
+    assert!(daily("Dagobah", "2024-01-01 02:00"));
+    assert!(!daily("Dagobah", "2024-01-01 00:00"));
+    assert!(daily("rrc00", "2024-01-01 00:00"));
+    assert!(daily("unknown", "2024-01-01 00:00"));
+}
+
+#[test]
+fn test_non_standard_archive_layout() {
+    // PCH-style layout with the collector as a host-like directory name
+    let path = "/data/pch/route-collector.sfo.pch.net/2024/01/rib.20240101.0000.gz";
+    let rib_meta = RibMeta::from_file_path(path).unwrap();
+    assert_eq!(rib_meta.project, "pch");
+    assert_eq!(rib_meta.collector, "route-collector.sfo.pch.net");
+
+    let mut processor = Prefix2AsProcessor::new("out");
+    MessageProcessor::reset_processor(&mut processor, &rib_meta);
+    assert_eq!(
+        processor.output_paths().unwrap()[0],
+        "out/pfx2as/route-collector.sfo.pch.net/2024/01/\
+         pfx2as_route-collector.sfo.pch.net_2024-01-01_0000_1704067200.json.bz2"
+    );
+
+    // extended RouteViews collectors and files directly under `bgpdata`
+    let rib_meta = RibMeta::from_file_path(
+        "mirror/route-views.kixp/bgpdata/2024.01/RIBS/rib.20240101.0000.bz2",
+    )
+    .unwrap();
+    assert_eq!(
+        (rib_meta.project.as_str(), rib_meta.collector.as_str()),
+        ("route-views", "route-views.kixp")
+    );
+    let rib_meta =
+        RibMeta::from_file_path("mirror/bgpdata/2024.01/RIBS/rib.20240101.0000.bz2").unwrap();
+    assert_eq!(rib_meta.collector, "route-views2");
+
+    // collectors of custom profiles
+    let path = "archive/Tatooine/2024/rib.20240101.0000.bz2";
+    assert_eq!(RibMeta::from_file_path(path).unwrap().collector, "unknown");
+    let profiles =
+        CollectorProfiles::new().with_profile(CollectorProfile::new("lab", &["Tatooine"]));
+    let rib_meta = RibMeta::from_file_path_with(path, &profiles).unwrap();
+    assert_eq!(
+        (rib_meta.project.as_str(), rib_meta.collector.as_str()),
+        ("lab", "Tatooine")
+    );
+}
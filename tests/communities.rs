@@ -0,0 +1,123 @@
+use ribeye::processors::{CommunitiesProcessor, CommunityAction, TypedProcessor};
+use ribeye::testing::{collector_output, SyntheticRibBuilder};
+use ribeye::RibMeta;
+
+/// Add `count` routes of AS64500's customer AS64999 through the given transit, with AS64500
+/// prepending its ASN if `prepend` is set.
+fn add_routes(
+    mut builder: SyntheticRibBuilder,
+    offset: usize,
+    count: usize,
+    communities: &[&str],
+    transit: u32,
+    prepend: bool,
+) -> SyntheticRibBuilder {
+    builder = builder.communities(communities);
+    for i in offset..offset + count {
+        let prefix = format!("10.0.{}.0/24", i);
+        let path: Vec<u32> = match prepend {
+            true => vec![65001, transit, 64500, 64500, 64999],
+            false => vec![65001, transit, 64500, 64999],
+        };
+        builder = builder.route(prefix.as_str(), &path);
+    }
+    builder.communities(&[])
+}
+
+fn rrc00() -> SyntheticRibBuilder {
+    let mut builder = SyntheticRibBuilder::new().peer("192.0.2.1", 65001);
+    // baseline: AS64500 exports evenly to AS174, AS3356 and AS2914 without prepending
+    builder = add_routes(builder, 0, 10, &[], 174, false);
+    builder = add_routes(builder, 10, 10, &[], 3356, false);
+    builder = add_routes(builder, 70, 10, &[], 2914, false);
+    // never exported to AS174
+    builder = add_routes(builder, 20, 6, &["64500:1000"], 3356, false);
+    builder = add_routes(builder, 30, 6, &["64500:1000"], 2914, false);
+    // always prepended
+    builder = add_routes(builder, 40, 4, &["64500:2"], 174, true);
+    builder = add_routes(builder, 44, 4, &["64500:2"], 3356, true);
+    builder = add_routes(builder, 48, 4, &["64500:2"], 2914, true);
+    // only exported to AS174, and only propagated to this collector
+    add_routes(builder, 60, 12, &["64500:0:1"], 174, false)
+}
+
+#[test]
+fn test_community_observations() {
+    let (_, output) = collector_output(CommunitiesProcessor::new("-"), "rrc00", rrc00().elems());
+    let communities: Vec<&str> = output
+        .communities
+        .iter()
+        .map(|c| c.community.as_str())
+        .collect();
+    assert_eq!(communities, vec!["64500:2", "64500:1000", "64500:0:1"]);
+
+    let no_export = &output.communities[1];
+    assert_eq!(no_export.owner, 64500);
+    assert_eq!(no_export.routes, 12);
+    assert_eq!(no_export.owner_routes, 12);
+    assert_eq!(no_export.owner_prepended, 0);
+    assert_eq!(no_export.neighbors.get(&3356), Some(&6));
+    assert_eq!(output.communities[0].owner_prepended, 12);
+
+    assert_eq!(output.owners.len(), 1);
+    let owner = &output.owners[0];
+    assert_eq!(owner.asn, 64500);
+    assert_eq!(owner.routes, 66);
+    assert_eq!(owner.prepended, 12);
+    assert_eq!(owner.neighbors.get(&174), Some(&26));
+}
+
+#[test]
+fn test_community_candidates() {
+    let other = add_routes(
+        SyntheticRibBuilder::new().peer("192.0.2.1", 65001),
+        0,
+        10,
+        &["64500:1000"],
+        3356,
+        false,
+    );
+    let outputs = vec![
+        collector_output(CommunitiesProcessor::new("-"), "rrc00", rrc00().elems()),
+        collector_output(CommunitiesProcessor::new("-"), "rrc01", other.elems()),
+        collector_output(
+            CommunitiesProcessor::new("-"),
+            "route-views2",
+            other.elems(),
+        ),
+    ];
+    let rib_metas: Vec<RibMeta> = outputs.iter().map(|(m, _)| m.clone()).collect();
+    let summary = CommunitiesProcessor::new("-")
+        .summarize(&rib_metas, outputs)
+        .unwrap();
+
+    assert_eq!(summary.owners[0].collectors.len(), 3);
+    let no_export = summary
+        .communities
+        .iter()
+        .find(|c| c.community == "64500:1000")
+        .unwrap();
+    assert_eq!(no_export.routes, 32);
+    assert_eq!(no_export.collectors.len(), 3);
+
+    let candidates: Vec<(&str, CommunityAction, Option<u32>)> = summary
+        .candidates
+        .iter()
+        .map(|c| (c.community.as_str(), c.action, c.target))
+        .collect();
+    assert_eq!(
+        candidates,
+        vec![
+            ("64500:0:1", CommunityAction::ExportOnlyTo, Some(174)),
+            ("64500:0:1", CommunityAction::LimitedScope, None),
+            ("64500:1000", CommunityAction::NoExportTo, Some(174)),
+            ("64500:2", CommunityAction::Prepend, None),
+            ("64500:2", CommunityAction::LimitedScope, None),
+        ]
+    );
+    let no_export = &summary.candidates[2];
+    assert_eq!(
+        no_export.description,
+        "64500:1000 looks like a no-export-to-AS174 action"
+    );
+}
@@ -0,0 +1,51 @@
+use ribeye::compare::{compare_as2rel, ExternalAs2rel, ExternalRel};
+use ribeye::processors::{As2relEntry, As2relSummaryJson};
+
+fn entry(asn1: u32, asn2: u32, rel: u8) -> As2relEntry {
+    As2relEntry {
+        asn1,
+        asn2,
+        paths_count: 1,
+        peers_count: 1,
+        rel,
+        ixp: false,
+        reverse_paths_count: 0,
+        confidence: 0.0,
+    }
+}
+
+#[test]
+fn test_compare_as2rel() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("as-rel.txt");
+    std::fs::write(
+        &path,
+        "# source:topology|BGP\n174|13335|-1\n3356|15169|0\n2914|64500|-1\n",
+    )
+    .unwrap();
+    let external = ExternalAs2rel::load_caida(path.to_str().unwrap()).unwrap();
+    assert_eq!(
+        external.links.get(&(174, 13335)),
+        Some(&ExternalRel::ProviderToCustomer)
+    );
+
+    let ribeye = As2relSummaryJson {
+        rib_dump_urls: vec![],
+        as2rel: vec![
+            entry(174, 13335, 0),
+            entry(174, 13335, 1),
+            entry(3356, 15169, 0),
+            entry(3356, 15169, 1),
+            entry(65001, 174, 0),
+        ],
+    };
+    let result = compare_as2rel(&ribeye, &external);
+    assert_eq!(result.ribeye_links, 3);
+    assert_eq!(result.external_links, 3);
+    assert_eq!(result.common_links, 2);
+    assert_eq!(result.p2c_compared, 2);
+    assert_eq!(result.p2c_agree, 1);
+    assert_eq!(result.p2c_as_peering, 1);
+    assert!(result.ribeye_only.contains(&(174, 65001)));
+    assert!(result.external_only.contains(&(2914, 64500)));
+}
@@ -0,0 +1,136 @@
+use ribeye::inspect::OutputFile;
+use ribeye::processors::csv::{get_summary_csv_path, summary_tables, CsvTable};
+use ribeye::processors::{FileSink, OutputSink, SummaryWriter};
+use ribeye::testing::SyntheticRibBuilder;
+use ribeye::{RibEye, RibMeta};
+use serde_json::json;
+
+const PROCESSORS: [&str; 14] = [
+    "pfx2as",
+    "peer-stats",
+    "as2rel",
+    "footprint",
+    "pfx2dist",
+    "pfxlen",
+    "pfxscope",
+    "defaults",
+    "communities",
+    "duplicates",
+    "pathdiv",
+    "vantage",
+    "firsthop",
+    "reachability",
+];
+
+fn summarize(dir: &str, csv: bool) {
+    let names: Vec<String> = PROCESSORS.iter().map(|p| p.to_string()).collect();
+    let rib_metas = vec![RibMeta {
+        collector: "rrc00".to_string(),
+        ..Default::default()
+    }];
+    let mut ribeye = RibEye::new()
+        .with_processor_names(&names, dir)
+        .unwrap()
+        .with_summary_writer(SummaryWriter::new().with_csv(csv))
+        .with_rib_meta(&rib_metas[0]);
+    ribeye
+        .process_elems(SyntheticRibBuilder::new().generated(2, 3).elems())
+        .unwrap();
+    ribeye.output().unwrap();
+    ribeye.summarize_latest_files(&rib_metas).unwrap();
+}
+
+fn read_csv(dir: &str, processor: &str, table: &str) -> Vec<String> {
+    let path = format!("{}/{}/latest.{}.csv", dir, processor, table);
+    std::fs::read_to_string(path)
+        .unwrap()
+        .lines()
+        .map(|l| l.to_string())
+        .collect()
+}
+
+#[test]
+fn test_csv_quoting() {
+    let table = CsvTable::from_records(
+        "peers",
+        &[
+            json!({"asn": 65001, "ip": "192.0.2.1", "collectors": ["rrc00", "rrc01"]}),
+            json!({"asn": 65002, "note": "a \"quoted\", text"}),
+        ],
+        None,
+    );
+    assert_eq!(table.columns, vec!["asn", "collectors", "ip", "note"]);
+    assert_eq!(
+        table.to_csv_string(),
+        "asn,collectors,ip,note\r\n\
+         65001,\"[\"\"rrc00\"\",\"\"rrc01\"\"]\",192.0.2.1,\r\n\
+         65002,,,\"a \"\"quoted\"\", text\"\r\n"
+    );
+    assert_eq!(
+        get_summary_csv_path("results/pfx2as/latest.json.bz2", "pfx2as"),
+        "results/pfx2as/latest.pfx2as.csv"
+    );
+}
+
+#[test]
+fn test_summary_csv_tables() {
+    let output_dir = tempfile::tempdir().unwrap();
+    let dir = output_dir.path().to_str().unwrap();
+    summarize(dir, true);
+
+    // columns follow the summary schema: required fields first, in declaration order
+    let pfx2as = read_csv(dir, "pfx2as", "pfx2as");
+    assert_eq!(
+        pfx2as[0],
+        "prefix,asn,count,collectors_count,peers,weighted_count"
+    );
+    assert_eq!(pfx2as.len(), 4);
+    assert!(pfx2as[1..].iter().all(|row| row.starts_with("10.0.")));
+
+    // summaries with several tables get one file per table
+    let peers = read_csv(dir, "peer-stats", "peers");
+    assert!(peers[0].starts_with("ip,asn,"));
+    assert_eq!(peers.len(), 3);
+    assert_eq!(
+        read_csv(dir, "peer-stats", "asns")[0],
+        "asn,num_sessions,collectors,num_v4_pfxs,num_v6_pfxs"
+    );
+
+    // columnar tables are written row by row
+    let edges = read_csv(dir, "reachability", "edges");
+    assert_eq!(edges[0], "peer,origin,prefixes");
+    assert!(edges.len() > 1);
+
+    for processor in PROCESSORS {
+        let summary = FileSink
+            .read_json(format!("{}/{}/latest.json.bz2", dir, processor).as_str())
+            .unwrap();
+        let tables = summary_tables(processor, &summary).unwrap();
+        assert!(!tables.is_empty(), "{}", processor);
+        for table in tables {
+            let lines = read_csv(dir, processor, table.name.as_str());
+            assert_eq!(lines[0], table.columns.join(","), "{}", processor);
+        }
+    }
+}
+
+#[test]
+fn test_summary_csv_disabled() {
+    let output_dir = tempfile::tempdir().unwrap();
+    let dir = output_dir.path().to_str().unwrap();
+    summarize(dir, false);
+    assert!(std::path::Path::new(format!("{}/pfx2as/latest.json.bz2", dir).as_str()).exists());
+    assert!(!std::path::Path::new(format!("{}/pfx2as/latest.pfx2as.csv", dir).as_str()).exists());
+}
+
+#[test]
+fn test_inspect_csv() {
+    let file = OutputFile::from_json_str(
+        r#"{"collector": "rrc00", "pfx2as": [{"asn": 13335, "count": 2, "prefix": "1.1.1.0/24"}]}"#,
+    )
+    .unwrap();
+    assert_eq!(
+        file.to_csv_string(),
+        "prefix,asn,count\r\n1.1.1.0/24,13335,2\r\n"
+    );
+}
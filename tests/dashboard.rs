@@ -0,0 +1,87 @@
+use chrono::NaiveDate;
+use ribeye::archive::{get_archive_dir, DeltaArchive};
+use ribeye::dashboard::Dashboard;
+use ribeye::processors::{FileSink, Prefix2AsSummaryJson};
+use ribeye::testing::SyntheticRibBuilder;
+use ribeye::{RibEye, RibMeta};
+use serde_json::json;
+
+#[test]
+fn test_dashboard() {
+    let output_dir = tempfile::tempdir().unwrap();
+    let dir = output_dir.path().to_str().unwrap();
+    let rib_meta = RibMeta {
+        collector: "rrc00".to_string(),
+        ..Default::default()
+    };
+    let elems = SyntheticRibBuilder::new()
+        .peer("10.0.0.1", 65001)
+        .route("10.0.0.0/16", &[65001, 3356, 64500])
+        .route("192.0.2.0/24", &[65001, 64501])
+        .peer("10.0.0.2", 65002)
+        .route("10.0.0.0/16", &[65002, 3356, 64500])
+        .build();
+    let processors = ["pfx2as", "as2rel", "peer-stats"]
+        .iter()
+        .map(|p| p.to_string())
+        .collect();
+    let mut ribeye = RibEye::new()
+        .with_processor_names(&processors, dir)
+        .unwrap()
+        .with_rib_meta(&rib_meta);
+    ribeye.process_elems(&elems).unwrap();
+    ribeye.output().unwrap();
+    ribeye.summarize_latest_files(&[rib_meta]).unwrap();
+
+    let html = Dashboard::new(dir)
+        .with_title("Daily <report>")
+        .render()
+        .unwrap();
+    assert!(html.starts_with("<!DOCTYPE html>"));
+    assert!(html.contains("<h1>Daily &lt;report&gt;</h1>"));
+    for section in [
+        "Collectors",
+        "Peers",
+        "Top origins",
+        "Relationships",
+        "Churn",
+    ] {
+        assert!(html.contains(format!("<h2>{}</h2>", section).as_str()));
+    }
+    assert!(html.contains("<td>rrc00</td><td>2</td>"));
+    assert!(html.contains("<td>AS64500</td><td>256.0</td><td>1</td>"));
+    assert!(html.contains("<td>AS3356 - AS64500</td><td>2</td>"));
+    assert!(html.contains("No churn data"));
+    assert!(html.contains("<svg"));
+
+    // churn from the pfx2as archive
+    let archive = DeltaArchive::new(get_archive_dir(dir).as_str());
+    for (day, entries) in [
+        (
+            1,
+            json!([{"prefix": "10.0.0.0/16", "asn": 64500, "count": 2}]),
+        ),
+        (
+            2,
+            json!([
+                {"prefix": "10.0.0.0/16", "asn": 64500, "count": 1},
+                {"prefix": "192.0.2.0/24", "asn": 64501, "count": 1},
+            ]),
+        ),
+    ] {
+        let summary: Prefix2AsSummaryJson =
+            serde_json::from_value(json!({"rib_dump_urls": [], "pfx2as": entries})).unwrap();
+        let date = NaiveDate::from_ymd_opt(2024, 1, day).unwrap();
+        archive.append(&FileSink, date, &summary).unwrap();
+    }
+    let html = Dashboard::new(dir).render().unwrap();
+    assert!(html.contains("<td>2024-01-02</td><td>2</td><td>1</td><td>0</td><td>1</td>"));
+    assert!(!html.contains("No churn data"));
+}
+
+#[test]
+fn test_dashboard_without_summaries() {
+    let output_dir = tempfile::tempdir().unwrap();
+    let dir = output_dir.path().to_str().unwrap();
+    assert!(Dashboard::new(dir).render().is_err());
+}
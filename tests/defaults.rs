@@ -0,0 +1,100 @@
+use ribeye::processors::{DefaultRouteProcessor, TypedProcessor};
+use ribeye::testing::{collector_output, SyntheticRibBuilder};
+use ribeye::RibMeta;
+
+fn builder() -> SyntheticRibBuilder {
+    SyntheticRibBuilder::new()
+        .peer("10.0.0.1", 65001)
+        .route("0.0.0.0/0", &[65001, 65001, 3356])
+        .route("1.1.1.0/24", &[65001, 13335])
+        .peer("10.0.0.2", 65002)
+        .route("0.0.0.0/0", &[65002, 3356])
+        .route("::/0", &[65002, 6939])
+        .peer("10.0.0.3", 65003)
+        .route("0.0.0.0/0", &[65003])
+        .peer("10.0.0.4", 65004)
+        .route("1.1.1.0/24", &[65004, 13335])
+}
+
+#[test]
+fn test_default_routes() {
+    let (_, output) = collector_output(DefaultRouteProcessor::new("-"), "rrc00", builder().elems());
+    let routes: Vec<(String, String, Option<u32>, &str)> = output
+        .default_routes
+        .iter()
+        .map(|r| {
+            (
+                r.prefix.to_string(),
+                r.peer_ip.to_string(),
+                r.origin,
+                r.as_path.as_str(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        routes,
+        vec![
+            (
+                "0.0.0.0/0".to_string(),
+                "10.0.0.1".to_string(),
+                Some(3356),
+                "65001 65001 3356"
+            ),
+            (
+                "0.0.0.0/0".to_string(),
+                "10.0.0.2".to_string(),
+                Some(3356),
+                "65002 3356"
+            ),
+            (
+                "0.0.0.0/0".to_string(),
+                "10.0.0.3".to_string(),
+                Some(65003),
+                "65003"
+            ),
+            (
+                "::/0".to_string(),
+                "10.0.0.2".to_string(),
+                Some(6939),
+                "65002 6939"
+            ),
+        ]
+    );
+    assert!(output.default_routes.iter().all(|r| r.collector == "rrc00"));
+}
+
+#[test]
+fn test_default_routes_summary() {
+    let outputs = vec![
+        collector_output(DefaultRouteProcessor::new("-"), "rrc00", builder().elems()),
+        collector_output(DefaultRouteProcessor::new("-"), "rrc01", builder().elems()),
+    ];
+    let rib_metas: Vec<RibMeta> = outputs.iter().map(|(m, _)| m.clone()).collect();
+    let summary = DefaultRouteProcessor::new("-")
+        .summarize(&rib_metas, outputs)
+        .unwrap();
+    assert_eq!(summary.default_routes.len(), 8);
+    assert_eq!(summary.default_routes[0].collector, "rrc00");
+    assert_eq!(summary.default_routes[1].collector, "rrc00");
+
+    let origins: Vec<(String, u32, usize, usize)> = summary
+        .origins
+        .iter()
+        .map(|o| {
+            (
+                o.prefix.to_string(),
+                o.origin,
+                o.peers_count,
+                o.collectors.len(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        origins,
+        vec![
+            ("0.0.0.0/0".to_string(), 3356, 4, 2),
+            ("0.0.0.0/0".to_string(), 65003, 2, 2),
+            ("::/0".to_string(), 6939, 2, 2),
+        ]
+    );
+}
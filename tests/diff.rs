@@ -0,0 +1,142 @@
+use ribeye::diff::{as2rel_changes, origin_changes, peer_changes, ChangeKind};
+use ribeye::processors::{As2relSummaryJson, PeerInfoSummaryJson, Prefix2AsSummaryJson};
+use serde_json::json;
+use std::collections::BTreeSet;
+
+fn pfx2as(entries: &[(&str, u32)]) -> Prefix2AsSummaryJson {
+    serde_json::from_value(json!({
+        "rib_dump_urls": [],
+        "pfx2as": entries
+            .iter()
+            .map(|(prefix, asn)| json!({"prefix": prefix, "asn": asn, "count": 1}))
+            .collect::<Vec<_>>(),
+    }))
+    .unwrap()
+}
+
+fn as2rel(entries: &[(u32, u32, u8)]) -> As2relSummaryJson {
+    serde_json::from_value(json!({
+        "rib_dump_urls": [],
+        "as2rel": entries
+            .iter()
+            .map(|(asn1, asn2, rel)| json!({
+                "asn1": asn1, "asn2": asn2, "rel": rel, "paths_count": 1, "peers_count": 1
+            }))
+            .collect::<Vec<_>>(),
+    }))
+    .unwrap()
+}
+
+fn peers(entries: &[(&str, u32, usize)]) -> PeerInfoSummaryJson {
+    serde_json::from_value(json!({
+        "rib_dump_urls": [],
+        "peers": entries
+            .iter()
+            .map(|(ip, asn, num_v4_pfxs)| json!({
+                "ip": ip, "collector": "rrc00", "asn": asn, "num_v4_pfxs": num_v4_pfxs,
+                "num_v6_pfxs": 0, "num_connected_asns": 1, "has_v4_default": false,
+                "has_v6_default": false
+            }))
+            .collect::<Vec<_>>(),
+    }))
+    .unwrap()
+}
+
+#[test]
+fn test_origin_changes() {
+    let old = pfx2as(&[
+        ("1.1.1.0/24", 13335),
+        ("8.8.8.0/24", 15169),
+        ("9.9.9.0/24", 19281),
+    ]);
+    let new = pfx2as(&[
+        ("1.1.1.0/24", 13335),
+        ("8.8.8.0/24", 15169),
+        ("8.8.8.0/24", 64512),
+        ("10.0.0.0/8", 64513),
+    ]);
+    let changes = origin_changes(&old, &new);
+    let summary: Vec<(&str, ChangeKind)> = changes
+        .iter()
+        .map(|c| (c.prefix.as_str(), c.kind))
+        .collect();
+    assert_eq!(
+        summary,
+        vec![
+            ("10.0.0.0/8", ChangeKind::Added),
+            ("8.8.8.0/24", ChangeKind::Changed),
+            ("9.9.9.0/24", ChangeKind::Removed),
+        ]
+    );
+    assert_eq!(changes[1].old_origins, BTreeSet::from([15169]));
+    assert_eq!(changes[1].new_origins, BTreeSet::from([15169, 64512]));
+    assert!(changes[2].new_origins.is_empty());
+
+    assert!(origin_changes(&new, &new).is_empty());
+}
+
+#[test]
+fn test_as2rel_changes() {
+    let old = as2rel(&[(174, 13335, 0), (174, 64512, 1)]);
+    let new = as2rel(&[(174, 13335, 0), (64512, 174, 1)]);
+    let changes: Vec<(u32, u32, u8, ChangeKind)> = as2rel_changes(&old, &new)
+        .into_iter()
+        .map(|c| (c.asn1, c.asn2, c.rel, c.kind))
+        .collect();
+    assert_eq!(
+        changes,
+        vec![
+            (174, 64512, 1, ChangeKind::Removed),
+            (64512, 174, 1, ChangeKind::Added),
+        ]
+    );
+}
+
+#[test]
+fn test_peer_changes() {
+    let old = peers(&[("192.0.2.1", 65001, 900000), ("192.0.2.2", 65002, 10)]);
+    let new = peers(&[
+        ("192.0.2.1", 65001, 900000),
+        ("192.0.2.2", 65002, 20),
+        ("192.0.2.3", 65003, 10),
+    ]);
+    let changes = peer_changes(&old, &new);
+    let summary: Vec<(String, ChangeKind)> =
+        changes.iter().map(|c| (c.ip.to_string(), c.kind)).collect();
+    assert_eq!(
+        summary,
+        vec![
+            ("192.0.2.2".to_string(), ChangeKind::Changed),
+            ("192.0.2.3".to_string(), ChangeKind::Added),
+        ]
+    );
+    assert_eq!(changes[0].old.as_ref().unwrap().num_v4_pfxs, 10);
+    assert_eq!(changes[0].new.as_ref().unwrap().num_v4_pfxs, 20);
+
+    let removed = peer_changes(&new, &old);
+    assert_eq!(removed.last().unwrap().kind, ChangeKind::Removed);
+    assert!(removed.last().unwrap().new.is_none());
+
+    // a peer IP moving to another collector ends one session and starts another
+    let mut moved = peers(&[("192.0.2.1", 65001, 900000)]);
+    moved.peers = moved
+        .peers
+        .into_iter()
+        .map(|mut p| {
+            p.collector = Some("rrc01".to_string());
+            p
+        })
+        .collect();
+    let changes = peer_changes(&peers(&[("192.0.2.1", 65001, 900000)]), &moved);
+    let summary: Vec<(Option<String>, ChangeKind)> = changes
+        .iter()
+        .map(|c| (c.collector.clone(), c.kind))
+        .collect();
+    assert_eq!(
+        summary,
+        vec![
+            (Some("rrc00".to_string()), ChangeKind::Removed),
+            (Some("rrc01".to_string()), ChangeKind::Added),
+        ]
+    );
+}
@@ -0,0 +1,316 @@
+use ribeye::download::{Downloader, PART_SUFFIX, VALIDATOR_SUFFIX};
+use ribeye::testing::SyntheticRibBuilder;
+use ribeye::{RibEye, RibMeta};
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::io::{BufRead, BufReader, Write};
+use std::net::TcpListener;
+use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// Range headers of the requests received, `None` without range.
+type Requests = Arc<Mutex<Vec<Option<String>>>>;
+
+/// Entity tag the mock server sends for a content.
+fn etag(content: &[u8]) -> String {
+    let mut hasher = DefaultHasher::new();
+    content.hash(&mut hasher);
+    format!("\"{:x}\"", hasher.finish())
+}
+
+/// HTTP server of one file at any path but `/missing`, honoring `bytes=<start>-` ranges unless an
+/// `If-Range` header does not match the content's ETag. The first `drops` responses announce the
+/// full length but close the connection halfway.
+fn mock_server(content: Vec<u8>, drops: usize) -> (String, Requests) {
+    mock_server_with_shift(content, drops, 0)
+}
+
+/// [mock_server] whose partial responses start `shift` bytes after the requested range.
+fn mock_server_with_shift(content: Vec<u8>, drops: usize, shift: usize) -> (String, Requests) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let endpoint = format!("http://{}", listener.local_addr().unwrap());
+    let requests: Requests = Arc::new(Mutex::new(vec![]));
+    let received = requests.clone();
+    let drops = AtomicUsize::new(drops);
+    std::thread::spawn(move || {
+        for stream in listener.incoming() {
+            let mut stream = stream.unwrap();
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+            let mut request_line = String::new();
+            reader.read_line(&mut request_line).unwrap();
+            let mut range = None;
+            let mut if_range = None;
+            loop {
+                let mut line = String::new();
+                reader.read_line(&mut line).unwrap();
+                let line = line.trim_end();
+                if line.is_empty() {
+                    break;
+                }
+                let (name, value) = line.split_once(": ").unwrap();
+                if name.eq_ignore_ascii_case("range") {
+                    range = Some(value.to_string());
+                }
+                if name.eq_ignore_ascii_case("if-range") {
+                    if_range = Some(value.to_string());
+                }
+            }
+            received.lock().unwrap().push(range.clone());
+            if if_range.is_some_and(|v| v != etag(&content)) {
+                range = None;
+            }
+            if request_line.contains("/missing") {
+                let _ = stream.write_all(b"HTTP/1.1 404 Not Found\r\ncontent-length: 0\r\n\r\n");
+                continue;
+            }
+            let start: usize = range
+                .as_deref()
+                .and_then(|r| r.strip_prefix("bytes="))
+                .and_then(|r| r.strip_suffix('-'))
+                .map(|r| r.parse::<usize>().unwrap() + shift)
+                .unwrap_or(0);
+            let header = match (range.is_some(), start >= content.len()) {
+                (true, true) => {
+                    let header = format!(
+                        "HTTP/1.1 416 Range Not Satisfiable\r\ncontent-range: bytes */{}\r\n\
+                         content-length: 0\r\n\r\n",
+                        content.len()
+                    );
+                    let _ = stream.write_all(header.as_bytes());
+                    continue;
+                }
+                (true, false) => format!(
+                    "HTTP/1.1 206 Partial Content\r\ncontent-range: bytes {}-{}/{}\r\n",
+                    start,
+                    content.len() - 1,
+                    content.len()
+                ),
+                (false, _) => "HTTP/1.1 200 OK\r\n".to_string(),
+            };
+            let body = &content[start..];
+            let header = format!(
+                "{}etag: {}\r\ncontent-length: {}\r\n\r\n",
+                header,
+                etag(&content),
+                body.len()
+            );
+            let _ = stream.write_all(header.as_bytes());
+            let dropped = drops
+                .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |d| d.checked_sub(1))
+                .is_ok();
+            let _ = match dropped {
+                true => stream.write_all(&body[..body.len() / 2]),
+                false => stream.write_all(body),
+            };
+        }
+    });
+    (endpoint, requests)
+}
+
+fn content() -> Vec<u8> {
+    (0..100_000u32).map(|i| (i % 251) as u8).collect()
+}
+
+fn downloader(dir: &str) -> Downloader {
+    Downloader::new(dir).with_retry_delay(Duration::from_millis(10))
+}
+
+#[test]
+fn test_cache_path() {
+    let downloader = Downloader::new("/tmp/cache/");
+    assert_eq!(
+        downloader.cache_path(
+            "https://archive.routeviews.org/route-views2/bgpdata/2024.01/RIBS/rib.20240101.0000.bz2"
+        ),
+        "/tmp/cache/archive.routeviews.org/route-views2/bgpdata/2024.01/RIBS/rib.20240101.0000.bz2"
+    );
+    assert_eq!(
+        downloader.cache_path("http://example.com/../a//b.gz?x=1"),
+        "/tmp/cache/example.com/a/b.gz"
+    );
+}
+
+#[test]
+fn test_resume_interrupted_download() {
+    let cache_dir = tempfile::tempdir().unwrap();
+    let (endpoint, requests) = mock_server(content(), 2);
+    let url = format!("{}/rrc00/bview.gz", endpoint);
+    let downloaded = downloader(cache_dir.path().to_str().unwrap())
+        .download(url.as_str())
+        .unwrap();
+    assert_eq!(std::fs::read(downloaded.path.as_str()).unwrap(), content());
+    assert!(!Path::new(format!("{}{}", downloaded.path, PART_SUFFIX).as_str()).exists());
+    assert_eq!(downloaded.bytes, 100_000);
+    // each attempt continues where the previous one stopped
+    assert_eq!(
+        *requests.lock().unwrap(),
+        vec![
+            None,
+            Some("bytes=50000-".to_string()),
+            Some("bytes=75000-".to_string())
+        ]
+    );
+
+    // complete files are reused
+    let downloaded = downloader(cache_dir.path().to_str().unwrap())
+        .download(url.as_str())
+        .unwrap();
+    assert_eq!(downloaded.bytes, 0);
+    assert_eq!(requests.lock().unwrap().len(), 3);
+}
+
+/// Leave a partial download of a URL as an earlier run would, with an optional validator.
+fn write_partial(downloader: &Downloader, url: &str, data: &[u8], validator: Option<&str>) {
+    let part = format!("{}{}", downloader.cache_path(url), PART_SUFFIX);
+    std::fs::create_dir_all(Path::new(part.as_str()).parent().unwrap()).unwrap();
+    std::fs::write(part.as_str(), data).unwrap();
+    if let Some(validator) = validator {
+        std::fs::write(format!("{}{}", part, VALIDATOR_SUFFIX), validator).unwrap();
+    }
+}
+
+#[test]
+fn test_resume_partial_file_across_runs() {
+    let cache_dir = tempfile::tempdir().unwrap();
+    let (endpoint, requests) = mock_server(content(), 0);
+    let downloader = downloader(cache_dir.path().to_str().unwrap());
+    let url = format!("{}/rrc00/bview.gz", endpoint);
+    let validator = etag(&content());
+    write_partial(
+        &downloader,
+        url.as_str(),
+        &content()[..1000],
+        Some(&validator),
+    );
+    let downloaded = downloader.download(url.as_str()).unwrap();
+    assert_eq!(std::fs::read(downloaded.path.as_str()).unwrap(), content());
+    assert_eq!(downloaded.bytes, 99_000);
+    assert_eq!(
+        *requests.lock().unwrap(),
+        vec![Some("bytes=1000-".to_string())]
+    );
+    let validator_file = format!("{}{}{}", downloaded.path, PART_SUFFIX, VALIDATOR_SUFFIX);
+    assert!(!Path::new(validator_file.as_str()).exists());
+
+    // a partial file holding the whole content only needs renaming
+    let url = format!("{}/rrc01/bview.gz", endpoint);
+    write_partial(&downloader, url.as_str(), &content(), Some(&validator));
+    let downloaded = downloader.download(url.as_str()).unwrap();
+    assert_eq!(std::fs::read(downloaded.path.as_str()).unwrap(), content());
+    assert_eq!(downloaded.bytes, 0);
+}
+
+#[test]
+fn test_changed_remote_file_is_downloaded_again() {
+    let cache_dir = tempfile::tempdir().unwrap();
+    let downloader = downloader(cache_dir.path().to_str().unwrap());
+    let new_content: Vec<u8> = content().iter().map(|b| b.wrapping_add(1)).collect();
+    let (endpoint, requests) = mock_server(new_content.clone(), 0);
+
+    // the partial file was started from an older version of the file
+    let url = format!("{}/rrc00/latest-bview.gz", endpoint);
+    write_partial(
+        &downloader,
+        url.as_str(),
+        &content()[..1000],
+        Some(&etag(&content())),
+    );
+    let downloaded = downloader.download(url.as_str()).unwrap();
+    assert_eq!(
+        std::fs::read(downloaded.path.as_str()).unwrap(),
+        new_content
+    );
+    assert_eq!(downloaded.bytes, 100_000);
+
+    // partial files without validator cannot be checked and start over
+    let url = format!("{}/rrc01/bview.gz", endpoint);
+    write_partial(&downloader, url.as_str(), &content()[..1000], None);
+    let downloaded = downloader.download(url.as_str()).unwrap();
+    assert_eq!(
+        std::fs::read(downloaded.path.as_str()).unwrap(),
+        new_content
+    );
+    assert_eq!(downloaded.bytes, 100_000);
+    assert_eq!(
+        *requests.lock().unwrap(),
+        vec![Some("bytes=1000-".to_string()), None]
+    );
+
+    // files whose content changes under the same URL are not reused once complete
+    let url = format!("{}/rrc00/latest-bview.gz", endpoint);
+    let downloaded = downloader.download(url.as_str()).unwrap();
+    assert_eq!(downloaded.bytes, 100_000);
+    assert_eq!(requests.lock().unwrap().len(), 3);
+}
+
+#[test]
+fn test_misplaced_partial_content_starts_over() {
+    let cache_dir = tempfile::tempdir().unwrap();
+    let downloader = downloader(cache_dir.path().to_str().unwrap());
+    let (endpoint, requests) = mock_server_with_shift(content(), 0, 10);
+    let url = format!("{}/rrc00/bview.gz", endpoint);
+    write_partial(
+        &downloader,
+        url.as_str(),
+        &content()[..1000],
+        Some(&etag(&content())),
+    );
+    let downloaded = downloader.download(url.as_str()).unwrap();
+    assert_eq!(std::fs::read(downloaded.path.as_str()).unwrap(), content());
+    assert_eq!(
+        *requests.lock().unwrap(),
+        vec![Some("bytes=1000-".to_string()), None]
+    );
+}
+
+#[test]
+fn test_missing_file_is_not_retried() {
+    let cache_dir = tempfile::tempdir().unwrap();
+    let (endpoint, requests) = mock_server(content(), 0);
+    let url = format!("{}/missing", endpoint);
+    assert!(downloader(cache_dir.path().to_str().unwrap())
+        .download(url.as_str())
+        .is_err());
+    assert_eq!(requests.lock().unwrap().len(), 1);
+
+    // interrupted downloads give up after the maximum attempts
+    let (endpoint, requests) = mock_server(content(), 10);
+    let url = format!("{}/rrc00/bview.gz", endpoint);
+    assert!(downloader(cache_dir.path().to_str().unwrap())
+        .with_max_attempts(2)
+        .download(url.as_str())
+        .is_err());
+    assert_eq!(requests.lock().unwrap().len(), 2);
+}
+
+#[test]
+fn test_process_downloaded_rib() {
+    let input_dir = tempfile::tempdir().unwrap();
+    let cache_dir = tempfile::tempdir().unwrap();
+    let output_dir = tempfile::tempdir().unwrap();
+    let rib_path = input_dir.path().join("rib.bz2");
+    SyntheticRibBuilder::new()
+        .generated(2, 50)
+        .write_mrt_file(rib_path.to_str().unwrap())
+        .unwrap();
+    let (endpoint, _) = mock_server(std::fs::read(rib_path).unwrap(), 1);
+    let url = format!("{}/rrc00/rib.bz2", endpoint);
+    let downloader = downloader(cache_dir.path().to_str().unwrap());
+    let dir = output_dir.path().to_str().unwrap();
+    let mut ribeye = RibEye::new()
+        .with_processor_names(&vec!["pfx2as".to_string()], dir)
+        .unwrap()
+        .with_downloader(downloader.clone())
+        .with_rib_meta(&RibMeta {
+            collector: "rrc00".to_string(),
+            rib_dump_url: url.clone(),
+            ..Default::default()
+        });
+    let stats = ribeye.process_mrt_file(url.as_str()).unwrap();
+    assert_eq!(stats.announcements, 100);
+    assert!(Path::new(format!("{}/pfx2as/rrc00/latest.json.bz2", dir).as_str()).exists());
+    // processed downloads are removed from the cache
+    assert!(!Path::new(downloader.cache_path(url.as_str()).as_str()).exists());
+}
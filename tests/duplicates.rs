@@ -0,0 +1,129 @@
+use bgpkit_parser::BgpElem;
+use ribeye::processors::{
+    DuplicatesCollectorJson, DuplicatesProcessor, TypedProcessor, MAX_CONFLICT_EXAMPLES,
+};
+use ribeye::testing::{collector_output, SyntheticRibBuilder};
+use ribeye::{RibEye, RibMeta};
+
+fn elems() -> Vec<BgpElem> {
+    SyntheticRibBuilder::new()
+        .peer("10.0.0.1", 65001)
+        .route("1.1.1.0/24", &[65001, 13335])
+        .route("1.1.1.0/24", &[65001, 13335])
+        .route("1.0.0.0/24", &[65001, 13335])
+        .route("1.0.0.0/24", &[65001, 3356, 13335])
+        .route("8.8.8.0/24", &[65001, 15169])
+        .peer("10.0.0.2", 65002)
+        .route("1.1.1.0/24", &[65002, 13335])
+        .route("8.8.8.0/24", &[65002, 15169])
+        .build()
+}
+
+#[test]
+fn test_duplicates_and_conflicts() {
+    let (_, output) = collector_output(DuplicatesProcessor::new("-"), "rrc00", &elems());
+    assert_eq!(output.peers.len(), 1);
+    let peer = &output.peers[0];
+    assert_eq!(peer.ip.to_string(), "10.0.0.1");
+    assert_eq!(peer.asn, 65001);
+    assert_eq!(peer.prefixes, 3);
+    assert_eq!(peer.duplicate_prefixes, 1);
+    assert_eq!(peer.conflicting_prefixes, 1);
+    assert_eq!(
+        peer.conflict_examples
+            .iter()
+            .map(|p| p.to_string())
+            .collect::<Vec<_>>(),
+        vec!["1.0.0.0/24"]
+    );
+}
+
+#[test]
+fn test_add_path_entries_are_not_duplicates() {
+    let mut elems = SyntheticRibBuilder::new()
+        .peer("10.0.0.1", 65001)
+        .route("1.1.1.0/24", &[65001, 13335])
+        .route("1.1.1.0/24", &[65001, 3356, 13335])
+        .build();
+    elems[1].prefix.path_id = 2;
+    let (_, output) = collector_output(DuplicatesProcessor::new("-"), "rrc00", &elems);
+    assert!(output.peers.is_empty());
+}
+
+#[test]
+fn test_conflict_examples_are_capped() {
+    let mut builder = SyntheticRibBuilder::new().peer("10.0.0.1", 65001);
+    for i in 0..20 {
+        let prefix = format!("10.{}.0.0/16", i);
+        builder = builder
+            .route(prefix.as_str(), &[65001, 13335])
+            .route(prefix.as_str(), &[65001, 15169]);
+    }
+    let (_, output) = collector_output(DuplicatesProcessor::new("-"), "rrc00", &builder.build());
+    assert_eq!(output.peers[0].conflicting_prefixes, 20);
+    assert_eq!(
+        output.peers[0].conflict_examples.len(),
+        MAX_CONFLICT_EXAMPLES
+    );
+}
+
+#[test]
+fn test_duplicates_summary() {
+    let outputs = vec![
+        collector_output(DuplicatesProcessor::new("-"), "rrc01", &elems()),
+        collector_output(DuplicatesProcessor::new("-"), "rrc00", &elems()),
+    ];
+    let rib_metas: Vec<RibMeta> = outputs.iter().map(|(m, _)| m.clone()).collect();
+    let summary = DuplicatesProcessor::new("-")
+        .summarize(&rib_metas, outputs)
+        .unwrap();
+    let collectors: Vec<&str> = summary.peers.iter().map(|p| p.collector.as_str()).collect();
+    assert_eq!(collectors, vec!["rrc00", "rrc01"]);
+}
+
+#[test]
+fn test_duplicates_across_collectors() {
+    // the same peer IP at two collectors processed together is not a duplicate
+    let temp_dir = tempfile::tempdir().unwrap();
+    let dir = temp_dir.path().to_str().unwrap();
+    let elems = SyntheticRibBuilder::new()
+        .peer("192.0.2.1", 65001)
+        .route("1.1.1.0/24", &[65001, 13335]);
+    let rib_metas: Vec<RibMeta> = ["rrc00", "route-views2"]
+        .into_iter()
+        .map(|collector| {
+            let path = format!("{}/{}.rib.bz2", dir, collector);
+            elems.write_mrt_file(path.as_str()).unwrap();
+            RibMeta {
+                collector: collector.to_string(),
+                rib_dump_url: path,
+                ..Default::default()
+            }
+        })
+        .collect();
+    let mut ribeye = RibEye::new()
+        .with_processor_names(&vec!["duplicates".to_string()], dir)
+        .unwrap();
+    ribeye.process_mrt_files(&rib_metas).unwrap();
+    let path = format!("{}/duplicates/all/latest.json.bz2", dir);
+    let output: DuplicatesCollectorJson = oneio::read_json_struct(path.as_str()).unwrap();
+    assert_eq!(output.collector, "all");
+    assert!(output.peers.is_empty());
+
+    // peers are labeled with their own collector
+    let mut processor = DuplicatesProcessor::new("-");
+    for (rib_meta, entries) in rib_metas.iter().zip([1, 2]) {
+        processor.reset_processor(rib_meta);
+        for _ in 0..entries {
+            processor.process_entry(&elems.elems()[0]).unwrap();
+        }
+    }
+    processor.reset_processor(&RibMeta::merged(&rib_metas));
+    let output = processor.to_output().unwrap();
+    assert_eq!(output.collector, "all");
+    assert_eq!(output.peers.len(), 1);
+    let peer = &output.peers[0];
+    assert_eq!(peer.collector, "route-views2");
+    assert_eq!(peer.ip.to_string(), "192.0.2.1");
+    assert_eq!((peer.prefixes, peer.duplicate_prefixes), (1, 1));
+}
@@ -0,0 +1,127 @@
+use ribeye::encryption::{decrypt, decrypt_stored, EncryptingSink, EncryptionKey, ENCRYPTED_MAGIC};
+use ribeye::processors::{FileSink, OutputSink};
+use ribeye::signing::SigningKey;
+use ribeye::testing::SyntheticRibBuilder;
+use ribeye::throttle::IoLimiter;
+use ribeye::{RibEye, RibMeta};
+use serde_json::Value;
+use std::sync::Arc;
+
+#[test]
+fn test_encrypt_and_decrypt() {
+    let key = EncryptionKey::generate().unwrap();
+    // keys survive a round trip through their files
+    let key = EncryptionKey::parse(key.to_key_string().as_str()).unwrap();
+
+    let path = "s3://bucket/pfx2as/rrc00/latest.json.bz2";
+    let encrypted = key.encrypt(path, b"ribeye output").unwrap();
+    assert!(encrypted.starts_with(ENCRYPTED_MAGIC));
+    assert_eq!(key.decrypt(path, &encrypted).unwrap(), b"ribeye output");
+    // random nonces
+    assert_ne!(key.encrypt(path, b"ribeye output").unwrap(), encrypted);
+
+    // tampered content
+    let mut tampered = encrypted.clone();
+    *tampered.last_mut().unwrap() ^= 1;
+    assert!(key.decrypt(path, &tampered).is_err());
+    // another key
+    let other_key = EncryptionKey::generate().unwrap();
+    assert!(other_key.decrypt(path, &encrypted).is_err());
+    // another path, e.g. the latest output of another collector
+    assert!(key
+        .decrypt("s3://bucket/pfx2as/rrc01/latest.json.bz2", &encrypted)
+        .is_err());
+    // not encrypted
+    assert!(key.decrypt(path, b"{}").is_err());
+}
+
+#[test]
+fn test_encrypted_outputs() {
+    let output_dir = tempfile::tempdir().unwrap();
+    let dir = output_dir.path().to_str().unwrap();
+    let mrt_path = output_dir.path().join("rib.test.bz2");
+    let mrt_path = mrt_path.to_str().unwrap();
+    SyntheticRibBuilder::new()
+        .peer("192.0.2.1", 65001)
+        .route("1.1.1.0/24", &[65001, 174, 13335])
+        .write_mrt_file(mrt_path)
+        .unwrap();
+
+    let key = Arc::new(EncryptionKey::generate().unwrap());
+    let rib_meta = RibMeta {
+        collector: "rrc99".to_string(),
+        ..Default::default()
+    };
+    let mut ribeye = RibEye::new()
+        .with_processor_names(&vec!["pfx2as".to_string()], dir)
+        .unwrap()
+        .with_rib_meta(&rib_meta)
+        .with_encryption_key(key.clone())
+        .unwrap();
+    ribeye.process_mrt_file(mrt_path).unwrap();
+    // the encrypted latest outputs are read back for summarization
+    ribeye.summarize_latest_files(&[rib_meta]).unwrap();
+
+    let summary = format!("{}/pfx2as/latest.json.bz2", dir);
+    assert!(std::fs::read(summary.as_str())
+        .unwrap()
+        .starts_with(ENCRYPTED_MAGIC));
+    assert!(oneio::read_json_struct::<Value>(summary.as_str()).is_err());
+    let summary: Value = serde_json::from_slice(&decrypt(summary.as_str(), &key).unwrap()).unwrap();
+    assert_eq!(summary["pfx2as"][0]["prefix"], "1.1.1.0/24");
+    assert_eq!(summary["pfx2as"][0]["asn"], 13335);
+}
+
+#[test]
+fn test_swapped_encrypted_outputs() {
+    let output_dir = tempfile::tempdir().unwrap();
+    let dir = output_dir.path().to_str().unwrap();
+    let key = EncryptionKey::generate().unwrap();
+    let sink = EncryptingSink::new(Arc::new(
+        EncryptionKey::parse(key.to_key_string().as_str()).unwrap(),
+    ));
+    let rrc00 = format!("{}/pfx2as/rrc00/latest.json", dir);
+    let rrc01 = format!("{}/pfx2as/rrc01/latest.json", dir);
+    sink.write_bytes(rrc00.as_str(), b"rrc00").unwrap();
+    sink.write_bytes(rrc01.as_str(), b"rrc01").unwrap();
+    assert_eq!(sink.read_bytes(rrc00.as_str()).unwrap(), b"rrc00");
+
+    // an output copied over another one does not decrypt at its new path
+    std::fs::copy(rrc01.as_str(), rrc00.as_str()).unwrap();
+    assert!(sink.read_bytes(rrc00.as_str()).is_err());
+    assert!(decrypt(rrc00.as_str(), &key).is_err());
+    // but does with the path it was written to
+    assert_eq!(
+        decrypt_stored(rrc00.as_str(), rrc01.as_str(), &key).unwrap(),
+        b"rrc01"
+    );
+}
+
+#[test]
+fn test_encryption_keeps_custom_sinks() {
+    let key = Arc::new(EncryptionKey::generate().unwrap());
+    let limiter = Arc::new(IoLimiter::new());
+    // sinks wrapping the encrypting sink
+    assert!(RibEye::new()
+        .with_encryption_key(key.clone())
+        .unwrap()
+        .with_upload_limiter(limiter.clone())
+        .with_signing_key(Arc::new(SigningKey::generate().unwrap()))
+        .with_encryption_key(key.clone())
+        .is_err());
+    // sinks encryption would drop
+    assert!(RibEye::new()
+        .with_upload_limiter(limiter)
+        .with_encryption_key(key.clone())
+        .is_err());
+    assert!(RibEye::new()
+        .with_sink(Box::new(FileSink))
+        .with_encryption_key(key.clone())
+        .is_err());
+    // streamed outputs are not encrypted
+    assert!(RibEye::new()
+        .with_processor_names(&vec!["pfx2as".to_string()], "-")
+        .unwrap()
+        .with_encryption_key(key)
+        .is_ok());
+}
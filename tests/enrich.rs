@@ -0,0 +1,276 @@
+use ribeye::country::{
+    country_matrix, get_country_matrix_path, CountryLinkEntry, CountryMatrixJson,
+};
+use ribeye::enrich::{
+    attach_asn_info, enrich_summaries, get_enriched_output_path, AsnInfoData, PeeringDb,
+};
+use ribeye::inspect::OutputFile;
+use ribeye::processors::{get_summary_output_path, As2relEntry, As2relSummaryJson};
+use ribeye::testing::SyntheticRibBuilder;
+use ribeye::{RibEye, RibMeta};
+use serde_json::json;
+use std::sync::Arc;
+
+const PEERINGDB_DUMP: &str = r#"{
+    "org": {"data": [{"id": 1, "name": "Cloudflare, Inc."}]},
+    "net": {"data": [
+        {"id": 10, "org_id": 1, "asn": 13335, "name": "Cloudflare", "info_type": "Content",
+         "info_traffic": "100+Tbps"},
+        {"id": 11, "org_id": 2, "asn": 64999, "name": "Unrelated", "info_type": "NSP"}
+    ]},
+    "netixlan": {"data": [
+        {"net_id": 10, "ix_id": 1}, {"net_id": 10, "ix_id": 2}, {"net_id": 10, "ix_id": 2}
+    ]}
+}"#;
+
+#[test]
+fn test_load_peeringdb() {
+    let data = PeeringDb::from_json_str(PEERINGDB_DUMP).unwrap();
+    let net = &data.nets[&13335];
+    assert_eq!(net.name, "Cloudflare");
+    assert_eq!(net.org_name.as_deref(), Some("Cloudflare, Inc."));
+    assert_eq!(net.info_type.as_deref(), Some("Content"));
+    assert_eq!(net.ix_count, 2);
+    // unknown organization
+    assert_eq!(data.nets[&64999].org_name, None);
+
+    // net API response with nested organizations
+    let api = r#"{"data": [{"asn": 174, "name": "Cogent", "org": {"name": "Cogent Communications"},
+        "info_type": "NSP", "info_traffic": "", "ix_count": 40}]}"#;
+    let data = PeeringDb::from_json_str(api).unwrap();
+    let net = &data.nets[&174];
+    assert_eq!(net.org_name.as_deref(), Some("Cogent Communications"));
+    assert_eq!(net.info_traffic, None);
+    assert_eq!(net.ix_count, 40);
+
+    assert!(PeeringDb::from_json_str("[]").is_err());
+}
+
+#[test]
+fn test_enrich_summaries() {
+    let output_dir = tempfile::tempdir().unwrap();
+    let dir = output_dir.path().to_str().unwrap();
+    let mrt_path = output_dir.path().join("rib.test.bz2");
+    let mrt_path = mrt_path.to_str().unwrap();
+    SyntheticRibBuilder::new()
+        .peer("192.0.2.1", 65001)
+        .route("1.1.1.0/24", &[65001, 174, 13335])
+        .write_mrt_file(mrt_path)
+        .unwrap();
+
+    let rib_meta = RibMeta {
+        collector: "rrc99".to_string(),
+        ..Default::default()
+    };
+    let processors = vec!["pfx2as".to_string(), "as2rel".to_string()];
+    let mut ribeye = RibEye::new()
+        .with_processor_names(&processors, dir)
+        .unwrap()
+        .with_rib_meta(&rib_meta);
+    ribeye.process_mrt_file(mrt_path).unwrap();
+    ribeye.summarize_latest_files(&[rib_meta]).unwrap();
+
+    let peeringdb = PeeringDb::from_json_str(PEERINGDB_DUMP).unwrap();
+    // the missing peer-stats summary is skipped
+    let written = enrich_summaries(dir, &[], &peeringdb).unwrap();
+    assert_eq!(
+        written,
+        vec![
+            get_enriched_output_path(dir, "as2rel"),
+            get_enriched_output_path(dir, "pfx2as"),
+        ]
+    );
+
+    let enriched = OutputFile::load(get_enriched_output_path(dir, "as2rel").as_str()).unwrap();
+    assert_eq!(enriched.dataset, "as2rel");
+    let peeringdb_obj = enriched.meta["peeringdb"].as_object().unwrap();
+    // only ASNs appearing in the records and known to PeeringDB
+    assert_eq!(peeringdb_obj.len(), 1);
+    assert_eq!(peeringdb_obj["13335"]["name"], "Cloudflare");
+    assert_eq!(peeringdb_obj["13335"]["ix_count"], 2);
+
+    let enriched = OutputFile::load(get_enriched_output_path(dir, "pfx2as").as_str()).unwrap();
+    assert_eq!(enriched.records.len(), 1);
+    assert!(enriched.meta["peeringdb"].get("13335").is_some());
+
+    assert!(enrich_summaries(dir, &["pfx2dist".to_string()], &peeringdb).is_err());
+}
+
+const ASINFO: &str = r#"{"asn": 13335, "name": "CLOUDFLARENET", "country": "US", "as2org": null}
+{"asn": 174, "name": "COGENT-174", "country": "US"}
+
+{"asn": 64999, "name": "UNRELATED", "country": "DE"}
+"#;
+
+#[test]
+fn test_load_asn_info() {
+    let data = AsnInfoData::from_json_str(ASINFO).unwrap();
+    assert_eq!(data.asns.len(), 3);
+    assert_eq!(data.asns[&13335].name, "CLOUDFLARENET");
+    assert_eq!(data.asns[&64999].country, "DE");
+
+    let data = AsnInfoData::from_json_str(r#"[{"asn": 174, "name": "COGENT-174"}]"#).unwrap();
+    assert_eq!(data.asns[&174].country, "");
+
+    assert!(AsnInfoData::from_json_str("{\"name\": \"no asn\"}").is_err());
+}
+
+#[test]
+fn test_attach_asn_info() {
+    let data = AsnInfoData::from_json_str(ASINFO).unwrap();
+    let mut summary = json!({
+        "rib_dump_urls": ["rib.bz2"],
+        "as2rel": [{"asn1": 174, "asn2": 13335}, {"asn1": 174, "asn2": 65001}],
+    });
+    assert_eq!(attach_asn_info(&mut summary, &data), 2);
+    assert_eq!(
+        summary["asn_info"],
+        json!({
+            "174": {"name": "COGENT-174", "country": "US"},
+            "13335": {"name": "CLOUDFLARENET", "country": "US"},
+        })
+    );
+}
+
+#[test]
+fn test_summaries_with_asn_info() {
+    let output_dir = tempfile::tempdir().unwrap();
+    let dir = output_dir.path().to_str().unwrap();
+    let mrt_path = output_dir.path().join("rib.test.bz2");
+    let mrt_path = mrt_path.to_str().unwrap();
+    SyntheticRibBuilder::new()
+        .peer("192.0.2.1", 65001)
+        .route("1.1.1.0/24", &[65001, 174, 13335])
+        .write_mrt_file(mrt_path)
+        .unwrap();
+
+    let rib_meta = RibMeta {
+        collector: "rrc99".to_string(),
+        ..Default::default()
+    };
+    let processors = vec!["as2rel".to_string(), "pfxlen".to_string()];
+    let mut ribeye = RibEye::new()
+        .with_processor_names(&processors, dir)
+        .unwrap()
+        .with_rib_meta(&rib_meta);
+    ribeye.process_mrt_file(mrt_path).unwrap();
+
+    let data = AsnInfoData::from_json_str(ASINFO).unwrap();
+    let mut ribeye = ribeye.with_asn_info(Arc::new(data));
+    ribeye.summarize_latest_files(&[rib_meta]).unwrap();
+
+    let as2rel: serde_json::Value =
+        oneio::read_json_struct(get_summary_output_path(dir, "as2rel").as_str()).unwrap();
+    let asn_info = as2rel["asn_info"].as_object().unwrap();
+    assert_eq!(asn_info.len(), 2);
+    assert_eq!(asn_info["174"]["name"], "COGENT-174");
+
+    // only as2rel, peer-stats and pfx2as summaries carry AS information
+    let pfxlen: serde_json::Value =
+        oneio::read_json_struct(get_summary_output_path(dir, "pfxlen").as_str()).unwrap();
+    assert!(pfxlen.get("asn_info").is_none());
+}
+
+#[test]
+fn test_country_matrix() {
+    let asn_info = AsnInfoData::from_json_str(
+        r#"{"asn": 174, "name": "COGENT-174", "country": "US"}
+{"asn": 3320, "name": "DTAG", "country": "DE"}
+{"asn": 64501, "name": "CUSTOMER-DE", "country": "DE"}
+{"asn": 64502, "name": "CUSTOMER-US", "country": "US"}"#,
+    )
+    .unwrap();
+    let entry = |asn1: u32, asn2: u32, rel: u8| As2relEntry {
+        asn1,
+        asn2,
+        paths_count: 2,
+        peers_count: 1,
+        rel,
+        ixp: false,
+        reverse_paths_count: 0,
+        confidence: 0.0,
+    };
+    let as2rel = As2relSummaryJson {
+        rib_dump_urls: vec!["rib.bz2".to_string()],
+        as2rel: vec![
+            // the same adjacency seen in both directions
+            entry(174, 3320, 0),
+            entry(3320, 174, 0),
+            entry(3320, 64501, 0),
+            entry(3320, 64501, 1),
+            entry(174, 64501, 0),
+            entry(174, 64501, 1),
+            entry(64502, 174, 0),
+            entry(174, 64502, 1),
+            // unknown country
+            entry(174, 65000, 0),
+        ],
+    };
+
+    let matrix = country_matrix(&as2rel, &asn_info);
+    assert_eq!(matrix.rib_dump_urls, as2rel.rib_dump_urls);
+    assert_eq!(
+        matrix.countries,
+        vec![
+            CountryLinkEntry {
+                country1: "DE".to_string(),
+                country2: "DE".to_string(),
+                as_links: 1,
+                p2c_links: 1,
+                c2p_links: 0,
+                paths_count: 2,
+            },
+            CountryLinkEntry {
+                country1: "DE".to_string(),
+                country2: "US".to_string(),
+                as_links: 2,
+                p2c_links: 0,
+                c2p_links: 1,
+                paths_count: 6,
+            },
+            CountryLinkEntry {
+                country1: "US".to_string(),
+                country2: "US".to_string(),
+                as_links: 1,
+                p2c_links: 1,
+                c2p_links: 0,
+                paths_count: 2,
+            },
+        ]
+    );
+}
+
+#[test]
+fn test_summaries_with_country_matrix() {
+    let output_dir = tempfile::tempdir().unwrap();
+    let dir = output_dir.path().to_str().unwrap();
+    let mrt_path = output_dir.path().join("rib.test.bz2");
+    let mrt_path = mrt_path.to_str().unwrap();
+    SyntheticRibBuilder::new()
+        .peer("192.0.2.1", 65001)
+        .route("1.1.1.0/24", &[65001, 174, 13335])
+        .write_mrt_file(mrt_path)
+        .unwrap();
+
+    let rib_meta = RibMeta {
+        collector: "rrc99".to_string(),
+        ..Default::default()
+    };
+    let processors = vec!["as2rel".to_string()];
+    let asn_info = Arc::new(AsnInfoData::from_json_str(ASINFO).unwrap());
+    let mut ribeye = RibEye::new()
+        .with_processor_names(&processors, dir)
+        .unwrap()
+        .with_rib_meta(&rib_meta)
+        .with_country_matrix(asn_info, get_country_matrix_path(dir).as_str());
+    ribeye.process_mrt_file(mrt_path).unwrap();
+    ribeye.summarize_latest_files(&[rib_meta]).unwrap();
+
+    let matrix: CountryMatrixJson =
+        oneio::read_json_struct(get_country_matrix_path(dir).as_str()).unwrap();
+    // 174 - 13335 is the only link with known countries, and 174 is a Tier-1 provider
+    assert_eq!(matrix.countries.len(), 1);
+    assert_eq!(matrix.countries[0].country1, "US");
+    assert_eq!(matrix.countries[0].as_links, 1);
+    assert_eq!(matrix.countries[0].p2c_links, 1);
+}
@@ -0,0 +1,129 @@
+use ribeye::export::{
+    bgpkit_as2rel, export_bgpkit, get_bgpkit_export_path, BgpkitAs2relEntry, BgpkitPeerStatsJson,
+};
+use ribeye::processors::As2relSummaryJson;
+use ribeye::testing::SyntheticRibBuilder;
+use ribeye::{RibEye, RibMeta};
+use serde_json::{json, Value};
+
+#[test]
+fn test_export_bgpkit() {
+    let output_dir = tempfile::tempdir().unwrap();
+    let dir = output_dir.path().to_str().unwrap();
+    let rib_meta = RibMeta {
+        collector: "rrc00".to_string(),
+        ..Default::default()
+    };
+    let elems = SyntheticRibBuilder::new()
+        .peer("10.0.0.2", 65002)
+        .route("10.0.0.0/16", &[65002, 3356, 64500])
+        .peer("10.0.0.1", 65001)
+        .route("10.0.0.0/16", &[65001, 3356, 64500])
+        .route("192.0.2.0/24", &[65001, 64501])
+        .build();
+    let processors = ["as2rel", "peer-stats"]
+        .iter()
+        .map(|p| p.to_string())
+        .collect();
+    let mut ribeye = RibEye::new()
+        .with_processor_names(&processors, dir)
+        .unwrap()
+        .with_rib_meta(&rib_meta);
+    ribeye.process_elems(&elems).unwrap();
+    ribeye.output().unwrap();
+    ribeye.summarize_latest_files(&[rib_meta]).unwrap();
+
+    let written = export_bgpkit(dir, &[]).unwrap();
+    assert_eq!(
+        written,
+        vec![
+            get_bgpkit_export_path(dir, "as2rel"),
+            get_bgpkit_export_path(dir, "peer-stats"),
+        ]
+    );
+
+    // a plain array of the original record fields
+    let as2rel: Value = oneio::read_json_struct(written[0].as_str()).unwrap();
+    for entry in as2rel.as_array().unwrap() {
+        let mut fields: Vec<&str> = entry
+            .as_object()
+            .unwrap()
+            .keys()
+            .map(|k| k.as_str())
+            .collect();
+        fields.sort();
+        assert_eq!(
+            fields,
+            vec!["asn1", "asn2", "paths_count", "peers_count", "rel"]
+        );
+    }
+    let as2rel: Vec<BgpkitAs2relEntry> = serde_json::from_value(as2rel).unwrap();
+    assert!(as2rel
+        .windows(2)
+        .all(|w| (w[0].asn1, w[0].asn2, w[0].rel) < (w[1].asn1, w[1].asn2, w[1].rel)));
+    assert!(as2rel.contains(&BgpkitAs2relEntry {
+        asn1: 3356,
+        asn2: 64500,
+        paths_count: 2,
+        peers_count: 2,
+        rel: 0,
+    }));
+    assert!(as2rel.contains(&BgpkitAs2relEntry {
+        asn1: 3356,
+        asn2: 64500,
+        paths_count: 2,
+        peers_count: 2,
+        rel: 1,
+    }));
+
+    let peer_stats: Value = oneio::read_json_struct(written[1].as_str()).unwrap();
+    let mut fields: Vec<&str> = peer_stats["peers"][0]
+        .as_object()
+        .unwrap()
+        .keys()
+        .map(|k| k.as_str())
+        .collect();
+    fields.sort();
+    assert_eq!(
+        fields,
+        vec![
+            "asn",
+            "collector",
+            "has_v4_default",
+            "has_v6_default",
+            "ip",
+            "num_connected_asns",
+            "num_v4_pfxs",
+            "num_v6_pfxs",
+        ]
+    );
+    let peer_stats: BgpkitPeerStatsJson = serde_json::from_value(peer_stats).unwrap();
+    let peers: Vec<String> = peer_stats.peers.iter().map(|p| p.ip.to_string()).collect();
+    assert_eq!(peers, vec!["10.0.0.1", "10.0.0.2"]);
+    assert_eq!(peer_stats.peers[0].num_v4_pfxs, 2);
+
+    assert!(export_bgpkit(dir, &["pfx2as".to_string()]).is_err());
+}
+
+#[test]
+fn test_bgpkit_as2rel_skips_ixp_links() {
+    let summary: As2relSummaryJson = serde_json::from_value(json!({
+        "rib_dump_urls": [],
+        "as2rel": [
+            {"asn1": 65001, "asn2": 6695, "paths_count": 1, "peers_count": 1, "rel": 0, "ixp": true},
+            {"asn1": 65001, "asn2": 64500, "paths_count": 3, "peers_count": 1, "rel": 0},
+            {"asn1": 174, "asn2": 64500, "paths_count": 2, "peers_count": 1, "rel": 1},
+        ]
+    }))
+    .unwrap();
+    let links: Vec<(u32, u32, u8)> = bgpkit_as2rel(&summary)
+        .into_iter()
+        .map(|e| (e.asn1, e.asn2, e.rel))
+        .collect();
+    assert_eq!(links, vec![(174, 64500, 1), (65001, 64500, 0)]);
+
+    // nothing to export without summaries
+    let output_dir = tempfile::tempdir().unwrap();
+    let dir = output_dir.path().to_str().unwrap();
+    assert!(export_bgpkit(dir, &[]).unwrap().is_empty());
+}
@@ -0,0 +1,123 @@
+use chrono::{Duration, Utc};
+use ribeye::alert::{Alert, AlertConfig, NotificationHook};
+use ribeye::feed::{FeedHook, JsonFeed};
+use ribeye::testing::SyntheticRibBuilder;
+use ribeye::{RibEye, RibMeta};
+use serde_json::json;
+
+fn alert(rule: &str, collector: Option<&str>, message: &str) -> Alert {
+    Alert {
+        rule: rule.to_string(),
+        processor: "pfx2as".to_string(),
+        collector: collector.map(|c| c.to_string()),
+        value: 1.0,
+        threshold: 1.0,
+        message: message.to_string(),
+    }
+}
+
+#[test]
+fn test_feed_hook() {
+    let output_dir = tempfile::tempdir().unwrap();
+    let path = output_dir.path().join("feed.json");
+    let path = path.to_str().unwrap();
+    let atom_path = output_dir.path().join("feed.xml");
+    let atom_path = atom_path.to_str().unwrap();
+    let hook = FeedHook::new(path)
+        .with_atom_path(atom_path)
+        .with_title("rrc alerts")
+        .with_max_items(3);
+
+    hook.notify(&[
+        alert("moas_conflict", None, "first"),
+        alert("stale_collector", Some("rrc00"), "second"),
+    ])
+    .unwrap();
+    hook.notify(&[
+        alert("moas_conflict", None, "third <&>"),
+        alert("moas_conflict", None, "fourth"),
+    ])
+    .unwrap();
+
+    let feed: JsonFeed = serde_json::from_str(&std::fs::read_to_string(path).unwrap()).unwrap();
+    assert_eq!(feed.version, "https://jsonfeed.org/version/1.1");
+    assert_eq!(feed.title, "rrc alerts");
+    // newest first, the oldest dropped
+    let contents: Vec<&str> = feed.items.iter().map(|i| i.content_text.as_str()).collect();
+    assert_eq!(contents, vec!["third <&>", "fourth", "first"]);
+    assert_eq!(feed.items[0].title, "moas_conflict in pfx2as");
+    assert_eq!(feed.items[0].alert.as_ref().unwrap().message, "third <&>");
+    assert_ne!(feed.items[0].id, feed.items[1].id);
+    assert_eq!(hook.read(), feed);
+
+    let atom = std::fs::read_to_string(atom_path).unwrap();
+    assert!(atom.starts_with("<?xml"));
+    assert!(atom.contains("<title>rrc alerts</title>"));
+    assert_eq!(atom.matches("<entry>").count(), 3);
+    assert!(atom.contains("<content type=\"text\">third &lt;&amp;&gt;</content>"));
+}
+
+#[test]
+fn test_feed_of_summary_alerts() {
+    let output_dir = tempfile::tempdir().unwrap();
+    let dir = output_dir.path().to_str().unwrap();
+    let feed_path = output_dir.path().join("feed.json");
+    let feed_path = feed_path.to_str().unwrap();
+    let processors = vec!["peer-stats".to_string(), "pfx2as".to_string()];
+    let config = AlertConfig::from_json_str(
+        json!({
+            "rules": [{"rule": "stale_collector", "hours": 24}, {"rule": "moas_conflict"}],
+            "hooks": [{"type": "feed", "path": feed_path}],
+        })
+        .to_string()
+        .as_str(),
+    )
+    .unwrap();
+
+    let run = |builder: SyntheticRibBuilder, age: Duration| {
+        let rib_meta = RibMeta {
+            collector: "rrc00".to_string(),
+            timestamp: (Utc::now() - age).naive_utc(),
+            ..Default::default()
+        };
+        let mut ribeye = RibEye::new()
+            .with_processor_names(&processors, dir)
+            .unwrap()
+            .with_rib_meta(&rib_meta)
+            .with_alert_config(&config);
+        ribeye.process_elems(builder.elems()).unwrap();
+        ribeye.output().unwrap();
+        ribeye
+            .summarize_latest_files(std::slice::from_ref(&rib_meta))
+            .unwrap();
+        ribeye.alerts().to_vec()
+    };
+
+    let alerts = run(
+        SyntheticRibBuilder::new()
+            .peer("192.0.2.1", 65001)
+            .route("10.0.0.0/8", &[65001, 64496]),
+        Duration::hours(30),
+    );
+    assert!(alerts.is_empty());
+
+    let alerts = run(
+        SyntheticRibBuilder::new()
+            .peer("192.0.2.1", 65001)
+            .route("10.0.0.0/8", &[65001, 64496])
+            .peer("192.0.2.2", 65002)
+            .route("10.0.0.0/8", &[65002, 64497]),
+        // older than the rule's threshold, not yet stale in the previous summary
+        Duration::hours(29),
+    );
+    let rules: Vec<_> = alerts.iter().map(|a| a.rule.as_str()).collect();
+    assert_eq!(rules, vec!["stale_collector", "moas_conflict"]);
+
+    let feed: JsonFeed =
+        serde_json::from_str(&std::fs::read_to_string(feed_path).unwrap()).unwrap();
+    let titles: Vec<&str> = feed.items.iter().map(|i| i.title.as_str()).collect();
+    assert_eq!(
+        titles,
+        vec!["stale_collector at rrc00", "moas_conflict in pfx2as"]
+    );
+}
@@ -0,0 +1,94 @@
+use bgpkit_parser::BgpElem;
+use ribeye::processors::{FirstHopProcessor, TypedProcessor};
+use ribeye::testing::{collector_output, SyntheticRibBuilder};
+
+/// AS65001 with two sessions and AS65002 sharing one prefix and origin AS200.
+fn elems() -> Vec<BgpElem> {
+    SyntheticRibBuilder::new()
+        .peer("10.0.0.1", 65001)
+        .route("1.0.0.0/24", &[65001, 100])
+        .route("2.0.0.0/24", &[65001, 200])
+        .peer("10.0.0.2", 65001)
+        .route("1.0.0.0/24", &[65001, 100])
+        .route("3.0.0.0/24", &[65001, 300])
+        .peer("10.0.0.3", 65002)
+        .route("1.0.0.0/24", &[65002, 174, 100])
+        .route("4.0.0.0/24", &[65002, 200])
+        .route("5.0.0.0/24", &[65002, 500])
+        .route("6.0.0.0/24", &[65002, 500])
+        .route("0.0.0.0/0", &[65002, 600])
+        .withdrawal("7.0.0.0/24")
+        .build()
+}
+
+#[test]
+fn test_exclusive_reach() {
+    let (_, output) = collector_output(FirstHopProcessor::new("-"), "rrc00", &elems());
+    assert_eq!((output.prefixes_count, output.origins_count), (6, 4));
+    let asns: Vec<u32> = output.peer_asns.iter().map(|e| e.peer_asn).collect();
+    assert_eq!(asns, vec![65002, 65001]);
+
+    let second = &output.peer_asns[0];
+    assert_eq!(second.peers_count, 1);
+    assert_eq!(
+        (
+            second.prefixes,
+            second.exclusive_prefixes,
+            second.redundant_prefixes
+        ),
+        (4, 3, 1)
+    );
+    assert_eq!(
+        (
+            second.origins,
+            second.exclusive_origins,
+            second.redundant_origins
+        ),
+        (3, 1, 2)
+    );
+    assert_eq!(second.exclusive_prefixes_share, 0.5);
+    assert_eq!(second.exclusive_origins_share, 0.25);
+
+    // sessions of the same peer ASN are not redundant with each other
+    let first = &output.peer_asns[1];
+    assert_eq!(first.peers_count, 2);
+    assert_eq!(
+        (
+            first.prefixes,
+            first.exclusive_prefixes,
+            first.redundant_prefixes
+        ),
+        (3, 2, 1)
+    );
+    assert_eq!((first.origins, first.exclusive_origins), (3, 1));
+}
+
+#[test]
+fn test_firsthop_summary() {
+    let (meta0, output0) = collector_output(FirstHopProcessor::new("-"), "rrc01", &elems());
+    let elems = SyntheticRibBuilder::new()
+        .peer("10.1.0.1", 65001)
+        .route("1.0.0.0/24", &[65001, 100])
+        .build();
+    let (meta1, output1) = collector_output(FirstHopProcessor::new("-"), "rrc00", &elems);
+    assert_eq!(output1.peer_asns[0].exclusive_prefixes_share, 1.0);
+    let summary = FirstHopProcessor::new("-")
+        .summarize(
+            &[meta0.clone(), meta1.clone()],
+            vec![(meta0, output0), (meta1, output1)],
+        )
+        .unwrap();
+    let entries: Vec<(&str, u32, u64)> = summary
+        .peer_asns
+        .iter()
+        .map(|e| (e.collector.as_str(), e.peer_asn, e.exclusive_prefixes))
+        .collect();
+    assert_eq!(
+        entries,
+        vec![
+            ("rrc00", 65001, 1),
+            ("rrc01", 65002, 3),
+            ("rrc01", 65001, 2)
+        ]
+    );
+}
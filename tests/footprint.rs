@@ -0,0 +1,110 @@
+use ribeye::processors::{FootprintProcessor, TypedProcessor};
+use ribeye::testing::{collector_output, SyntheticRibBuilder};
+use ribeye::util::PrefixCountries;
+use ribeye::RibMeta;
+use std::sync::Arc;
+
+const DELEGATED: &str = "2|ripencc|20240101|5|19830705|20240101|+0100
+ripencc|*|ipv4|*|3|summary
+# comment
+ripencc|DE|ipv4|10.0.0.0|256|20100101|allocated|abc
+ripencc|FR|ipv4|10.0.1.0|512|20100101|assigned|abc
+ripencc||ipv4|10.0.3.0|256||available
+ripencc|NL|ipv6|2001:db8::|32|20100101|allocated|abc
+ripencc|NL|asn|3333|1|19930901|allocated|abc
+";
+
+fn countries() -> PrefixCountries {
+    let mut countries = PrefixCountries::new();
+    countries.add_delegated(DELEGATED).unwrap();
+    countries
+}
+
+fn processor() -> FootprintProcessor {
+    FootprintProcessor::new("-").with_prefix_countries(Arc::new(countries()))
+}
+
+#[test]
+fn test_prefix_countries() {
+    let countries = countries();
+    assert_eq!(countries.len(), 3);
+    let lookup = |p: &str| -> Vec<String> {
+        countries
+            .countries(&p.parse().unwrap())
+            .into_iter()
+            .map(|c| c.to_string())
+            .collect()
+    };
+    assert_eq!(lookup("10.0.0.0/24"), vec!["DE"]);
+    assert_eq!(lookup("10.0.2.128/25"), vec!["FR"]);
+    // spans the German and French blocks
+    assert_eq!(lookup("10.0.0.0/22"), vec!["DE", "FR"]);
+    assert!(lookup("10.0.3.0/24").is_empty());
+    assert_eq!(lookup("2001:db8:1::/48"), vec!["NL"]);
+    assert_eq!(lookup("2001::/16"), vec!["NL"]);
+    assert!(lookup("2001:db9::/32").is_empty());
+
+    let mut invalid = PrefixCountries::new();
+    assert!(invalid
+        .add_delegated("ripencc|DE|ipv4|not-an-ip|256|20100101|allocated")
+        .is_err());
+}
+
+#[test]
+fn test_footprint() {
+    let (_, output) = collector_output(
+        processor(),
+        "rrc00",
+        &SyntheticRibBuilder::new()
+            .peer("192.0.2.1", 65001)
+            .route("10.0.0.0/24", &[65001, 64496])
+            .route("10.0.1.0/24", &[65001, 64496])
+            .route("2001:db8::/32", &[65001, 64496])
+            .route("10.0.2.0/24", &[65001, 64497])
+            .route("192.168.0.0/16", &[65001, 64497])
+            .build(),
+    );
+    // most countries first
+    assert_eq!(output.footprints[0].asn, 64496);
+    assert_eq!(
+        output.footprints[0].countries.iter().collect::<Vec<_>>(),
+        vec!["DE", "FR", "NL"]
+    );
+    assert_eq!(output.footprints[0].num_countries, 3);
+    assert_eq!(output.footprints[0].prefixes_count, 3);
+
+    let origin = &output.footprints[1];
+    assert_eq!(origin.asn, 64497);
+    assert_eq!(origin.num_countries, 1);
+    assert_eq!(origin.unregistered_count, 1);
+}
+
+#[test]
+fn test_footprint_summary() {
+    let outputs = vec![
+        collector_output(
+            processor(),
+            "rrc00",
+            &SyntheticRibBuilder::new()
+                .peer("192.0.2.1", 65001)
+                .route("10.0.0.0/24", &[65001, 64496])
+                .build(),
+        ),
+        collector_output(
+            processor(),
+            "rrc01",
+            &SyntheticRibBuilder::new()
+                .peer("192.0.2.2", 65002)
+                .route("10.0.1.0/24", &[65002, 64496])
+                .route("10.0.2.0/24", &[65002, 64496])
+                .build(),
+        ),
+    ];
+    let rib_metas: Vec<RibMeta> = outputs.iter().map(|(m, _)| m.clone()).collect();
+    let summary = FootprintProcessor::new("-")
+        .summarize(&rib_metas, outputs)
+        .unwrap();
+    assert_eq!(summary.footprints.len(), 1);
+    assert_eq!(summary.footprints[0].num_countries, 2);
+    assert_eq!(summary.footprints[0].prefixes_count, 2);
+}
@@ -0,0 +1,145 @@
+//! Golden-file regression tests for the built-in processors.
+//!
+//! Every built-in processor runs on the bundled MRT fixture `tests/fixtures/rib.fixture.bz2`, and
+//! both its per-collector output and its summary are compared against the checked-in files under
+//! `tests/golden`. Outputs are canonicalized (object keys and array items sorted) before
+//! comparison since processors emit entries in hash map order.
+//!
+//! Run with `RIBEYE_UPDATE_GOLDEN=1` to regenerate the fixture and all golden files after an
+//! intended output change.
+
+use ribeye::testing::SyntheticRibBuilder;
+use ribeye::{RibEye, RibMeta};
+use serde_json::Value;
+use std::path::{Path, PathBuf};
+
+const FIXTURE_COLLECTOR: &str = "rrc99";
+
+fn update_mode() -> bool {
+    std::env::var("RIBEYE_UPDATE_GOLDEN").is_ok_and(|v| v != "0")
+}
+
+fn test_data_dir(name: &str) -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join(name)
+}
+
+fn fixture_builder() -> SyntheticRibBuilder {
+    SyntheticRibBuilder::new()
+        .timestamp(1_704_067_200.0)
+        .peer("192.0.2.1", 65001)
+        .route("0.0.0.0/0", &[65001])
+        .route("1.1.1.0/24", &[65001, 174, 13335])
+        .route("8.8.8.0/24", &[65001, 3356, 15169])
+        .route("10.0.0.0/8", &[65001, 64500, 64501])
+        .route("203.0.113.0/24", &[65001, 174, 64496, 64496, 64497])
+        .peer("192.0.2.2", 65002)
+        .route("1.1.1.0/24", &[65002, 3356, 13335])
+        .route("8.8.8.0/24", &[65002, 15169])
+        .route("203.0.113.0/24", &[65002, 2914, 64499])
+        .peer("2001:db8::1", 65003)
+        .route("::/0", &[65003])
+        .route("2606:4700::/32", &[65003, 6939, 13335])
+        .route("2001:4860::/32", &[65003, 15169])
+}
+
+fn fixture_path() -> String {
+    let path = test_data_dir("fixtures").join("rib.fixture.bz2");
+    if update_mode() {
+        fixture_builder()
+            .write_mrt_file(path.to_str().unwrap())
+            .unwrap();
+    }
+    path.to_string_lossy().to_string()
+}
+
+fn fixture_meta() -> RibMeta {
+    RibMeta {
+        project: "riperis".to_string(),
+        collector: FIXTURE_COLLECTOR.to_string(),
+        rib_dump_url: "rib.fixture.bz2".to_string(),
+        timestamp: chrono::DateTime::from_timestamp(1_704_067_200, 0)
+            .unwrap()
+            .naive_utc(),
+        ..Default::default()
+    }
+}
+
+/// Sort object keys and array items recursively so that outputs compare independent of order.
+fn canonicalize(value: Value) -> Value {
+    match value {
+        Value::Array(items) => {
+            let mut items: Vec<Value> = items.into_iter().map(canonicalize).collect();
+            items.sort_by_key(|v| v.to_string());
+            Value::Array(items)
+        }
+        Value::Object(map) => {
+            let sorted: std::collections::BTreeMap<String, Value> =
+                map.into_iter().map(|(k, v)| (k, canonicalize(v))).collect();
+            Value::Object(sorted.into_iter().collect())
+        }
+        v => v,
+    }
+}
+
+fn check_golden(name: &str, output_path: &Path) {
+    let content = oneio::read_to_string(output_path.to_str().unwrap())
+        .unwrap_or_else(|e| panic!("failed to read {}: {}", output_path.display(), e));
+    let actual = canonicalize(serde_json::from_str(content.as_str()).unwrap());
+    let actual_str = serde_json::to_string_pretty(&actual).unwrap() + "\n";
+
+    let golden_path = test_data_dir("golden").join(format!("{}.json", name));
+    if update_mode() {
+        std::fs::write(&golden_path, actual_str).unwrap();
+        return;
+    }
+    let expected = std::fs::read_to_string(&golden_path).unwrap_or_else(|e| {
+        panic!(
+            "failed to read {} (run with RIBEYE_UPDATE_GOLDEN=1 to create it): {}",
+            golden_path.display(),
+            e
+        )
+    });
+    assert_eq!(
+        actual_str,
+        expected,
+        "output of {} differs from {}",
+        name,
+        golden_path.display()
+    );
+}
+
+#[test]
+fn test_processor_outputs_match_golden_files() {
+    let fixture = fixture_path();
+    let rib_meta = fixture_meta();
+
+    for name in RibEye::available_processors() {
+        let output_dir = tempfile::tempdir().unwrap();
+        let dir = output_dir.path().to_str().unwrap();
+        let processor_names = vec![name.to_string()];
+
+        let mut ribeye = RibEye::new()
+            .with_processor_names(&processor_names, dir)
+            .unwrap()
+            .with_rib_meta(&rib_meta);
+        ribeye.process_mrt_file(fixture.as_str()).unwrap();
+        check_golden(
+            format!("{}_collector", name).as_str(),
+            &output_dir
+                .path()
+                .join(name)
+                .join(FIXTURE_COLLECTOR)
+                .join("latest.json.bz2"),
+        );
+
+        ribeye
+            .summarize_latest_files(std::slice::from_ref(&rib_meta))
+            .unwrap();
+        check_golden(
+            format!("{}_summary", name).as_str(),
+            &output_dir.path().join(name).join("latest.json.bz2"),
+        );
+    }
+}
@@ -0,0 +1,131 @@
+use ribeye::health::{collector_health, get_health_report_path, CollectorHealthJson};
+use ribeye::processors::{PeerInfoEntry, PeerInfoSummaryJson};
+use ribeye::testing::SyntheticRibBuilder;
+use ribeye::{RibEye, RibMeta};
+
+fn peer(ip: &str, collector: &str, num_v4_pfxs: usize, num_v6_pfxs: usize) -> PeerInfoEntry {
+    PeerInfoEntry {
+        ip: ip.parse().unwrap(),
+        collector: Some(collector.to_string()),
+        asn: 65000,
+        num_v4_pfxs,
+        num_v6_pfxs,
+        num_connected_asns: 1,
+        num_origin_asns: 1,
+        num_first_last_hops: 1,
+        has_v4_default: false,
+        has_v6_default: false,
+        approximate: false,
+        full_feed: false,
+    }
+}
+
+fn summary(peers: Vec<PeerInfoEntry>) -> PeerInfoSummaryJson {
+    PeerInfoSummaryJson {
+        rib_dump_urls: vec!["rib.bz2".to_string()],
+        asns: vec![],
+        peers: peers.into_iter().collect(),
+    }
+}
+
+#[test]
+fn test_collector_health() {
+    let previous = summary(vec![
+        peer("192.0.2.1", "rrc00", 950_000, 0),
+        peer("192.0.2.2", "rrc00", 10, 0),
+        peer("192.0.2.3", "rrc00", 10, 0),
+        peer("192.0.2.4", "rrc00", 10, 0),
+        peer("192.0.2.5", "route-views2", 10, 0),
+        peer("192.0.2.6", "rrc01", 10, 0),
+    ]);
+    let current = summary(vec![
+        peer("192.0.2.1", "rrc00", 950_000, 200_000),
+        peer("192.0.2.2", "rrc00", 10, 0),
+        peer("192.0.2.5", "route-views2", 10, 0),
+        peer("192.0.2.7", "route-views2", 10, 0),
+    ]);
+
+    // without a previous summary, nothing is compared
+    let report = collector_health(&current, None, 0.2);
+    assert_eq!(report.collectors.len(), 2);
+    assert_eq!(report.collectors[0].previous_peers_count, None);
+    assert!(report.collectors.iter().all(|c| !c.dropped));
+
+    let report = collector_health(&current, Some(&previous), 0.2);
+    assert_eq!(report.rib_dump_urls, current.rib_dump_urls);
+    let collectors: Vec<_> = report
+        .collectors
+        .iter()
+        .map(|c| {
+            (
+                c.collector.as_str(),
+                c.peers_count,
+                c.full_feed_v4,
+                c.full_feed_v6,
+                c.peers_lost,
+                c.peers_gained,
+                c.dropped,
+            )
+        })
+        .collect();
+    assert_eq!(
+        collectors,
+        vec![
+            ("route-views2", 2, 0, 0, 0, 1, false),
+            ("rrc00", 2, 1, 1, 2, 0, true),
+            // collectors without any peer left are still reported
+            ("rrc01", 0, 0, 0, 1, 0, true),
+        ]
+    );
+    assert_eq!(report.collectors[1].previous_peers_count, Some(4));
+
+    // a larger drop ratio tolerates losing half of the peers
+    let report = collector_health(&current, Some(&previous), 0.5);
+    assert!(!report.collectors[1].dropped);
+}
+
+#[test]
+fn test_summaries_with_health_report() {
+    let output_dir = tempfile::tempdir().unwrap();
+    let dir = output_dir.path().to_str().unwrap();
+    let rib_meta = RibMeta {
+        collector: "rrc99".to_string(),
+        ..Default::default()
+    };
+    let processors = vec!["peer-stats".to_string()];
+
+    let run = |builder: SyntheticRibBuilder| -> CollectorHealthJson {
+        let mrt_path = output_dir.path().join("rib.test.bz2");
+        let mrt_path = mrt_path.to_str().unwrap();
+        builder.write_mrt_file(mrt_path).unwrap();
+        let mut ribeye = RibEye::new()
+            .with_processor_names(&processors, dir)
+            .unwrap()
+            .with_rib_meta(&rib_meta)
+            .with_health_report(get_health_report_path(dir).as_str(), 0.2);
+        ribeye.process_mrt_file(mrt_path).unwrap();
+        ribeye
+            .summarize_latest_files(std::slice::from_ref(&rib_meta))
+            .unwrap();
+        oneio::read_json_struct(get_health_report_path(dir).as_str()).unwrap()
+    };
+
+    let report = run(SyntheticRibBuilder::new()
+        .peer("192.0.2.1", 65001)
+        .route("1.1.1.0/24", &[65001, 13335])
+        .peer("192.0.2.2", 65002)
+        .route("1.1.1.0/24", &[65002, 13335]));
+    assert_eq!(report.collectors.len(), 1);
+    assert_eq!(report.collectors[0].collector, "rrc99");
+    assert_eq!(report.collectors[0].peers_count, 2);
+    assert_eq!(report.collectors[0].previous_peers_count, None);
+
+    // the second run is compared with the summary of the first one
+    let report = run(SyntheticRibBuilder::new()
+        .peer("192.0.2.1", 65001)
+        .route("1.1.1.0/24", &[65001, 13335]));
+    assert_eq!(report.collectors[0].peers_count, 1);
+    assert_eq!(report.collectors[0].previous_peers_count, Some(2));
+    assert_eq!(report.collectors[0].peers_lost, 1);
+    assert!(report.collectors[0].dropped);
+}
@@ -0,0 +1,158 @@
+use ribeye::hijack::{
+    analyze_hijacks, find_hijack_candidates, get_hijack_report_path, HijackReportJson, RpkiStatus,
+    Vrps,
+};
+use ribeye::processors::{As2relEntry, As2relSummaryJson, Prefix2AsCount, Prefix2AsSummaryJson};
+use ribeye::testing::SyntheticRibBuilder;
+use ribeye::{RibEye, RibMeta};
+
+const VRPS_JSON: &str = r#"{"metadata": {}, "roas": [
+    {"asn": "AS13335", "prefix": "1.1.1.0/24", "maxLength": 24, "ta": "apnic"},
+    {"asn": 64500, "prefix": "10.0.0.0/8", "maxLength": 16, "ta": "arin"},
+    {"asn": "AS64501", "prefix": "2001:db8::/32", "maxLength": 48, "ta": "ripe"}
+]}"#;
+
+#[test]
+fn test_vrps() {
+    let vrps = Vrps::parse(VRPS_JSON).unwrap();
+    assert_eq!(vrps.len(), 3);
+    let validate = |prefix: &str, asn: u32| vrps.validate(&prefix.parse().unwrap(), asn);
+    assert_eq!(validate("1.1.1.0/24", 13335), RpkiStatus::Valid);
+    assert_eq!(validate("1.1.1.0/24", 64496), RpkiStatus::Invalid);
+    // too specific
+    assert_eq!(validate("10.1.1.0/24", 64500), RpkiStatus::Invalid);
+    assert_eq!(validate("10.1.0.0/16", 64500), RpkiStatus::Valid);
+    assert_eq!(validate("2001:db8:1::/48", 64501), RpkiStatus::Valid);
+    assert_eq!(validate("8.8.8.0/24", 15169), RpkiStatus::NotFound);
+
+    let csv = "ASN,IP Prefix,Max Length,Trust Anchor\nAS13335,1.1.1.0/24,24,apnic\n";
+    let vrps = Vrps::parse(csv).unwrap();
+    assert_eq!(vrps.len(), 1);
+    assert_eq!(
+        vrps.validate(&"1.1.1.0/24".parse().unwrap(), 13335),
+        RpkiStatus::Valid
+    );
+    assert!(Vrps::parse(r#"{"data": []}"#).is_err());
+}
+
+fn pfx2as(prefix: &str, asn: u32, count: usize) -> Prefix2AsCount {
+    Prefix2AsCount {
+        prefix: prefix.to_string(),
+        asn,
+        count,
+        peers: vec![],
+        collectors_count: None,
+        weighted_count: None,
+    }
+}
+
+fn as2rel(asn1: u32, asn2: u32, rel: u8) -> As2relEntry {
+    As2relEntry {
+        asn1,
+        asn2,
+        paths_count: 1,
+        peers_count: 1,
+        rel,
+        ixp: false,
+        reverse_paths_count: 0,
+        confidence: 0.0,
+    }
+}
+
+#[test]
+fn test_find_hijack_candidates() {
+    let pfx2as = Prefix2AsSummaryJson {
+        rib_dump_urls: vec!["rib.bz2".to_string()],
+        pfx2as: vec![
+            pfx2as("1.1.1.0/24", 13335, 9),
+            // unrelated MOAS origin seen by a minority of peers, RPKI invalid
+            pfx2as("1.1.1.0/24", 64496, 1),
+            // MOAS between a provider and its customer
+            pfx2as("8.8.8.0/24", 15169, 5),
+            pfx2as("8.8.8.0/24", 174, 5),
+            // RPKI invalid only
+            pfx2as("10.1.1.0/24", 64500, 3),
+            pfx2as("192.0.2.0/24", 64497, 3),
+        ],
+        aggregated: false,
+    };
+    let relationships = As2relSummaryJson {
+        rib_dump_urls: vec![],
+        as2rel: vec![
+            as2rel(174, 15169, 1),
+            as2rel(174, 13335, 1),
+            as2rel(174, 64500, 1),
+            as2rel(64496, 64497, 0),
+        ],
+    };
+    let vrps = Vrps::parse(VRPS_JSON).unwrap();
+
+    let report = find_hijack_candidates(&pfx2as, Some(&relationships), Some(&vrps));
+    assert_eq!(report.rib_dump_urls, pfx2as.rib_dump_urls);
+    let candidates: Vec<_> = report
+        .hijacks
+        .iter()
+        .map(|c| (c.prefix.as_str(), c.asn, c.score, c.reasons.join(",")))
+        .collect();
+    assert_eq!(
+        candidates,
+        vec![
+            (
+                "1.1.1.0/24",
+                64496,
+                0.96,
+                "rpki_invalid,moas,low_visibility,no_known_upstream".to_string()
+            ),
+            ("10.1.1.0/24", 64500, 0.4, "rpki_invalid".to_string()),
+            // the legitimate origin of a MOAS conflict is still reported, with a lower score
+            ("1.1.1.0/24", 13335, 0.3, "moas".to_string()),
+        ]
+    );
+    assert_eq!(report.hijacks[0].moas_origins, vec![13335]);
+    assert_eq!(report.hijacks[0].rpki, Some(RpkiStatus::Invalid));
+    assert_eq!(report.hijacks[2].rpki, Some(RpkiStatus::Valid));
+
+    // without relationships and VRPs, only MOAS conflicts remain
+    let report = find_hijack_candidates(&pfx2as, None, None);
+    assert_eq!(report.hijacks.len(), 4);
+    assert!(report.hijacks.iter().all(|c| c.rpki.is_none()));
+    assert!(report.hijacks.iter().all(|c| c.reasons[0] == "moas"));
+}
+
+#[test]
+fn test_analyze_hijacks() {
+    let output_dir = tempfile::tempdir().unwrap();
+    let dir = output_dir.path().to_str().unwrap();
+    // no summaries yet
+    assert!(analyze_hijacks(dir, None).is_err());
+
+    let mrt_path = output_dir.path().join("rib.test.bz2");
+    let mrt_path = mrt_path.to_str().unwrap();
+    SyntheticRibBuilder::new()
+        .peer("192.0.2.1", 65001)
+        .route("1.1.1.0/24", &[65001, 174, 13335])
+        .peer("192.0.2.2", 65002)
+        .route("1.1.1.0/24", &[65002, 3356, 64496])
+        .write_mrt_file(mrt_path)
+        .unwrap();
+    let rib_meta = RibMeta {
+        collector: "rrc99".to_string(),
+        ..Default::default()
+    };
+    let processors = vec!["pfx2as".to_string(), "as2rel".to_string()];
+    let mut ribeye = RibEye::new()
+        .with_processor_names(&processors, dir)
+        .unwrap()
+        .with_rib_meta(&rib_meta);
+    ribeye.process_mrt_file(mrt_path).unwrap();
+    ribeye.summarize_latest_files(&[rib_meta]).unwrap();
+
+    let vrps = Vrps::parse(VRPS_JSON).unwrap();
+    let report = analyze_hijacks(dir, Some(&vrps)).unwrap();
+    assert_eq!(report.hijacks.len(), 2);
+    assert_eq!(report.hijacks[0].asn, 64496);
+
+    let written: HijackReportJson =
+        oneio::read_json_struct(get_hijack_report_path(dir).as_str()).unwrap();
+    assert_eq!(written.hijacks, report.hijacks);
+}
@@ -0,0 +1,83 @@
+use ribeye::processors::cache::{get_summary_cache_path, SummaryCache};
+use ribeye::processors::OutputSink;
+use ribeye::testing::SyntheticRibBuilder;
+use ribeye::{FileSink, RibEye, RibMeta};
+use serde_json::Value;
+
+fn process(dir: &str, collector: &str, prefix: &str) -> RibMeta {
+    let mrt_path = format!("{}/rib.{}.bz2", dir, collector);
+    SyntheticRibBuilder::new()
+        .peer("192.0.2.1", 65001)
+        .route(prefix, &[65001, 174, 13335])
+        .write_mrt_file(mrt_path.as_str())
+        .unwrap();
+    let rib_meta = RibMeta {
+        collector: collector.to_string(),
+        rib_dump_url: mrt_path.clone(),
+        ..Default::default()
+    };
+    let mut ribeye = RibEye::new()
+        .with_processor_names(&vec!["pfx2as".to_string()], dir)
+        .unwrap()
+        .with_rib_meta(&rib_meta);
+    ribeye.process_mrt_file(mrt_path.as_str()).unwrap();
+    rib_meta
+}
+
+fn summarize(dir: &str, rib_metas: &[RibMeta], incremental: bool) -> Vec<String> {
+    RibEye::new()
+        .with_processor_names(&vec!["pfx2as".to_string()], dir)
+        .unwrap()
+        .with_incremental_summaries(incremental)
+        .summarize_latest_files(rib_metas)
+        .unwrap();
+    let summary: Value =
+        oneio::read_json_struct(format!("{}/pfx2as/latest.json.bz2", dir).as_str()).unwrap();
+    let mut prefixes: Vec<String> = summary["pfx2as"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|e| e["prefix"].as_str().unwrap().to_string())
+        .collect();
+    prefixes.sort();
+    prefixes
+}
+
+#[test]
+fn test_incremental_summaries() {
+    let output_dir = tempfile::tempdir().unwrap();
+    let dir = output_dir.path().to_str().unwrap();
+    let rib_metas = vec![
+        process(dir, "rrc00", "1.1.1.0/24"),
+        process(dir, "rrc01", "1.0.0.0/24"),
+    ];
+
+    let full = summarize(dir, &rib_metas, false);
+    assert_eq!(summarize(dir, &rib_metas, true), full);
+    let cache_path = get_summary_cache_path(dir, "pfx2as");
+    let cache = SummaryCache::load(&FileSink, cache_path.as_str());
+    assert_eq!(cache.entries.len(), 2);
+
+    // mark the cached output of rrc00 to tell it apart from a reread
+    let mut cache = cache;
+    for entry in &mut cache.entries {
+        if entry.path.contains("/rrc00/") {
+            entry.output["pfx2as"][0]["prefix"] = "192.0.2.0/24".into();
+        }
+    }
+    FileSink
+        .write_bytes(cache_path.as_str(), &serde_json::to_vec(&cache).unwrap())
+        .unwrap();
+
+    // rrc01 has new data: it is reread, while rrc00 comes from the cache
+    process(dir, "rrc01", "8.8.8.0/24");
+    assert_eq!(
+        summarize(dir, &rib_metas, true),
+        vec!["192.0.2.0/24".to_string(), "8.8.8.0/24".to_string()]
+    );
+    // full summaries ignore the cache
+    assert_eq!(
+        summarize(dir, &rib_metas, false),
+        vec!["1.1.1.0/24".to_string(), "8.8.8.0/24".to_string()]
+    );
+}
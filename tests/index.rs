@@ -0,0 +1,94 @@
+use chrono::NaiveDateTime;
+use ribeye::index::{build_output_index, write_output_index, DatasetIndex};
+use ribeye::testing::SyntheticRibBuilder;
+use ribeye::{RibEye, RibMeta};
+use serde_json::Value;
+
+#[test]
+fn test_output_index() {
+    let output_dir = tempfile::tempdir().unwrap();
+    let dir = output_dir.path().to_str().unwrap();
+    let mrt_path = output_dir.path().join("rib.test.bz2");
+    let mrt_path = mrt_path.to_str().unwrap();
+    SyntheticRibBuilder::new()
+        .peer("192.0.2.1", 65001)
+        .route("1.1.1.0/24", &[65001, 174, 13335])
+        .write_mrt_file(mrt_path)
+        .unwrap();
+
+    let processors = vec!["pfx2as".to_string(), "peer-stats".to_string()];
+    let mut rib_metas = vec![];
+    for (collector, time) in [
+        ("rrc00", "2024-01-01 08:00"),
+        ("rrc00", "2024-01-01 00:00"),
+        ("route-views2", "2024-01-01 00:00"),
+    ] {
+        let rib_meta = RibMeta {
+            collector: collector.to_string(),
+            timestamp: NaiveDateTime::parse_from_str(time, "%Y-%m-%d %H:%M").unwrap(),
+            ..Default::default()
+        };
+        let mut ribeye = RibEye::new()
+            .with_processor_names(&processors, dir)
+            .unwrap()
+            .with_rib_meta(&rib_meta);
+        ribeye.process_mrt_file(mrt_path).unwrap();
+        rib_metas.push(rib_meta);
+    }
+    RibEye::new()
+        .with_processor_names(&processors, dir)
+        .unwrap()
+        .summarize_latest_files(&rib_metas)
+        .unwrap();
+
+    let datasets = build_output_index(dir).unwrap();
+    let names: Vec<_> = datasets.iter().map(|d| d.name.as_str()).collect();
+    assert_eq!(names, vec!["peer-stats", "pfx2as"]);
+    let pfx2as = &datasets[1];
+    assert_eq!(pfx2as.latest.as_deref(), Some("pfx2as/latest.json.bz2"));
+    assert_eq!(pfx2as.variants, vec!["pfx2as/latest.json"]);
+    let collectors: Vec<_> = pfx2as
+        .collectors
+        .iter()
+        .map(|c| c.collector.as_str())
+        .collect();
+    assert_eq!(collectors, vec!["route-views2", "rrc00"]);
+    let rrc00 = &pfx2as.collectors[1];
+    assert_eq!(
+        rrc00.latest.as_deref(),
+        Some("pfx2as/rrc00/latest.json.bz2")
+    );
+    assert_eq!(
+        rrc00
+            .files
+            .iter()
+            .map(|f| f.path.as_str())
+            .collect::<Vec<_>>(),
+        vec![
+            "pfx2as/rrc00/2024/01/pfx2as_rrc00_2024-01-01_0000_1704067200.json.bz2",
+            "pfx2as/rrc00/2024/01/pfx2as_rrc00_2024-01-01_0800_1704096000.json.bz2",
+        ]
+    );
+    // the uncompressed summary copies
+    assert_eq!(datasets[0].variants, vec!["peer-stats/latest.json"]);
+
+    assert_eq!(write_output_index(dir).unwrap(), 2);
+    let root: Value = serde_json::from_str(
+        &std::fs::read_to_string(output_dir.path().join("index.json")).unwrap(),
+    )
+    .unwrap();
+    assert_eq!(root["datasets"][1]["name"], "pfx2as");
+    assert_eq!(root["datasets"][1]["index"], "pfx2as/index.json");
+    assert_eq!(
+        root["datasets"][1]["collectors"],
+        serde_json::json!(["route-views2", "rrc00"])
+    );
+    let dataset: DatasetIndex = serde_json::from_str(
+        &std::fs::read_to_string(output_dir.path().join("pfx2as/index.json")).unwrap(),
+    )
+    .unwrap();
+    assert_eq!(dataset, *pfx2as);
+
+    // index files are not indexed themselves
+    assert_eq!(build_output_index(dir).unwrap(), datasets);
+}
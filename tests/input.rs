@@ -0,0 +1,65 @@
+use bzip2::write::BzEncoder;
+use flate2::write::GzEncoder;
+use ribeye::input::{get_decompress_reader, is_stdin, Compression};
+use ribeye::testing::{RecordingProcessor, SyntheticRibBuilder};
+use ribeye::{MessageProcessor, RibEye, RibMeta};
+use std::io::{Cursor, Read, Write};
+
+fn gzip(data: &[u8]) -> Vec<u8> {
+    let mut encoder = GzEncoder::new(vec![], flate2::Compression::default());
+    encoder.write_all(data).unwrap();
+    encoder.finish().unwrap()
+}
+
+fn bzip2(data: &[u8]) -> Vec<u8> {
+    let mut encoder = BzEncoder::new(vec![], bzip2::Compression::default());
+    encoder.write_all(data).unwrap();
+    encoder.finish().unwrap()
+}
+
+/// Elements the pipeline reads from an MRT stream, as it would from standard input.
+fn read_stream(data: Vec<u8>, compression: Compression) -> usize {
+    let processor = RecordingProcessor::new("recorder");
+    let recording = processor.recording();
+    let mut ribeye = RibEye::new()
+        .with_processors(vec![processor.to_boxed()])
+        .with_rib_meta(&RibMeta {
+            collector: "rrc00".to_string(),
+            ..Default::default()
+        })
+        .with_stdin_compression(compression);
+    let raw_reader: Box<dyn Read + Send> = Box::new(Cursor::new(data));
+    ribeye
+        .process_mrt_reader(get_decompress_reader(raw_reader, compression))
+        .unwrap();
+    let elems = recording.lock().unwrap().elems.len();
+    elems
+}
+
+#[test]
+fn test_compression() {
+    assert_eq!("gz".parse::<Compression>().unwrap(), Compression::Gzip);
+    assert_eq!("BZIP2".parse::<Compression>().unwrap(), Compression::Bzip2);
+    assert_eq!("".parse::<Compression>().unwrap(), Compression::None);
+    assert!("zstd".parse::<Compression>().is_err());
+    assert_eq!(Compression::Gzip.to_string(), "gzip");
+
+    assert!(is_stdin("-"));
+    assert!(!is_stdin("rib.20240101.0000.bz2"));
+}
+
+#[test]
+fn test_stdin_stream() {
+    let builder = SyntheticRibBuilder::new().generated(3, 4);
+    let mrt = builder.to_mrt_bytes();
+    assert_eq!(builder.elems().len(), 12);
+
+    assert_eq!(read_stream(mrt.clone(), Compression::None), 12);
+    assert_eq!(read_stream(gzip(&mrt), Compression::Gzip), 12);
+    assert_eq!(read_stream(bzip2(&mrt), Compression::Bzip2), 12);
+
+    // concatenated streams, e.g. several dumps piped one after another
+    let mut data = gzip(&mrt);
+    data.extend(gzip(&mrt));
+    assert_eq!(read_stream(data, Compression::Gzip), 24);
+}
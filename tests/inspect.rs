@@ -0,0 +1,145 @@
+use ribeye::inspect::{OutputFile, RecordFilter};
+use std::path::Path;
+
+fn golden(name: &str) -> OutputFile {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join("golden")
+        .join(format!("{}.json", name));
+    OutputFile::load(path.to_str().unwrap()).unwrap()
+}
+
+#[test]
+fn test_inspect_dataset() {
+    for (name, dataset, records) in [
+        ("pfx2as_collector", "pfx2as", 7),
+        ("pfx2as_summary", "pfx2as", 7),
+        ("as2rel_summary", "as2rel", 23),
+        ("peer-stats_summary", "peers", 3),
+        ("communities_summary", "communities", 0),
+        ("pfxscope_collector", "prefixes", 6),
+        ("pfxscope_summary", "prefixes", 6),
+        ("vantage_collector", "vantage_points", 3),
+        ("vantage_summary", "vantage_points", 3),
+        ("defaults_collector", "default_routes", 2),
+        ("defaults_summary", "default_routes", 2),
+        ("pfxlen_summary", "origins", 5),
+        ("reachability_summary", "collectors", 1),
+    ] {
+        let file = golden(name);
+        assert_eq!(file.dataset, dataset, "{}", name);
+        assert_eq!(file.records.len(), records, "{}", name);
+        assert!(file.meta.contains_key("rib_dump_urls") || file.meta.contains_key("rib_dump_url"));
+    }
+
+    // derived views stay meta information
+    let file = golden("peer-stats_summary");
+    assert!(file.meta.contains_key("asns"));
+    assert_eq!(file.stats().meta["asns"], 3);
+}
+
+#[test]
+fn test_inspect_filters() {
+    let prefix_filter = RecordFilter {
+        prefix: Some("8.0.0.0/7".parse().unwrap()),
+        ..Default::default()
+    };
+    let file = golden("pfx2as_summary").filter(&prefix_filter);
+    let prefixes: Vec<&str> = file
+        .records
+        .iter()
+        .map(|r| r["prefix"].as_str().unwrap())
+        .collect();
+    assert_eq!(prefixes, vec!["8.8.8.0/24"]);
+
+    let asn_filter = RecordFilter {
+        asn: Some(13335),
+        ..Default::default()
+    };
+    let file = golden("as2rel_summary").filter(&asn_filter);
+    assert!(!file.records.is_empty());
+    assert!(file.records.iter().all(|r| r["asn2"] == 13335));
+    assert_eq!(file.stats().distinct_values["asn2"], 1);
+
+    // per-record collector
+    let collector_filter = RecordFilter {
+        collector: Some("rrc99".to_string()),
+        ..Default::default()
+    };
+    assert_eq!(
+        golden("peer-stats_summary")
+            .filter(&collector_filter)
+            .records
+            .len(),
+        3
+    );
+    // file-level collector
+    assert_eq!(
+        golden("pfx2as_collector")
+            .filter(&collector_filter)
+            .records
+            .len(),
+        7
+    );
+    let other_collector = RecordFilter {
+        collector: Some("rrc00".to_string()),
+        ..Default::default()
+    };
+    assert!(golden("pfx2as_collector")
+        .filter(&other_collector)
+        .records
+        .is_empty());
+}
+
+#[test]
+fn test_inspect_communities_summary() {
+    // candidates sort before the communities
+    let file = OutputFile::from_json_str(
+        r#"{"rib_dump_urls": [], "candidates": [{"community": "64500:100"}],
+        "communities": [{"community": "64500:100"}, {"community": "64500:200"}], "owners": []}"#,
+    )
+    .unwrap();
+    assert_eq!(file.dataset, "communities");
+    assert_eq!(file.records.len(), 2);
+    assert!(file.meta.contains_key("candidates"));
+}
+
+#[test]
+fn test_inspect_pfxscope_filters() {
+    let file = golden("pfxscope_summary");
+    let prefix_filter = RecordFilter {
+        prefix: Some("1.1.1.0/24".parse().unwrap()),
+        ..Default::default()
+    };
+    assert_eq!(file.clone().filter(&prefix_filter).records.len(), 1);
+    let v4_filter = RecordFilter {
+        prefix: Some("0.0.0.0/0".parse().unwrap()),
+        ..Default::default()
+    };
+    assert_eq!(file.filter(&v4_filter).records.len(), 4);
+}
+
+#[test]
+fn test_inspect_vantage_filters() {
+    let asn_filter = RecordFilter {
+        asn: Some(65001),
+        ..Default::default()
+    };
+    let file = golden("vantage_summary").filter(&asn_filter);
+    assert_eq!(file.records.len(), 1);
+    assert_eq!(file.records[0]["ip"], "192.0.2.1");
+}
+
+#[test]
+fn test_inspect_defaults_filters() {
+    let file = golden("defaults_summary");
+    assert!(file.meta.contains_key("origins"));
+    let collector_filter = |collector: &str| RecordFilter {
+        collector: Some(collector.to_string()),
+        ..Default::default()
+    };
+    let records = file.clone().filter(&collector_filter("rrc99")).records;
+    assert_eq!(records.len(), 2);
+    assert!(records.iter().all(|r| r["peer_ip"].is_string()));
+    assert!(file.filter(&collector_filter("rrc00")).records.is_empty());
+}
@@ -0,0 +1,117 @@
+use ribeye::enrich::AsnInfoData;
+use ribeye::ip2asn::{get_ip2asn_path, ip2asn_ranges, to_tsv};
+use ribeye::processors::{Prefix2AsCount, Prefix2AsSummaryJson};
+use ribeye::testing::SyntheticRibBuilder;
+use ribeye::{RibEye, RibMeta};
+use std::io::Read;
+use std::sync::Arc;
+
+fn pfx2as(prefix: &str, asn: u32, count: usize) -> Prefix2AsCount {
+    Prefix2AsCount {
+        prefix: prefix.to_string(),
+        asn,
+        count,
+        peers: vec![],
+        collectors_count: None,
+        weighted_count: None,
+    }
+}
+
+const ASINFO: &str = r#"{"asn": 13335, "name": "CLOUDFLARENET", "country": "US"}
+{"asn": 64496, "name": "EXAMPLE\tNET", "country": ""}"#;
+
+#[test]
+fn test_ip2asn_ranges() {
+    let summary = Prefix2AsSummaryJson {
+        rib_dump_urls: vec![],
+        pfx2as: vec![
+            pfx2as("10.0.0.0/8", 64496, 5),
+            // more specifics at the start, in the middle and at the end of the /8
+            pfx2as("10.0.0.0/16", 64497, 5),
+            pfx2as("10.1.0.0/16", 64496, 5),
+            pfx2as("10.128.0.0/16", 64498, 5),
+            pfx2as("10.128.1.0/24", 64499, 5),
+            pfx2as("10.255.255.0/24", 64500, 5),
+            // MOAS, the origin seen by most peers wins
+            pfx2as("1.1.1.0/24", 64501, 1),
+            pfx2as("1.1.1.0/24", 13335, 9),
+            pfx2as("2001:db8::/32", 64496, 1),
+        ],
+        aggregated: false,
+    };
+    let ranges = ip2asn_ranges(&summary, None);
+    let rows: Vec<_> = ranges
+        .iter()
+        .map(|r| (r.start.to_string(), r.end.to_string(), r.asn))
+        .collect();
+    let row = |start: &str, end: &str, asn: u32| (start.to_string(), end.to_string(), asn);
+    assert_eq!(
+        rows,
+        vec![
+            row("1.1.1.0", "1.1.1.255", 13335),
+            row("10.0.0.0", "10.0.255.255", 64497),
+            // the /8 and its more specific with the same origin are merged
+            row("10.1.0.0", "10.127.255.255", 64496),
+            row("10.128.0.0", "10.128.0.255", 64498),
+            row("10.128.1.0", "10.128.1.255", 64499),
+            row("10.128.2.0", "10.128.255.255", 64498),
+            row("10.129.0.0", "10.255.254.255", 64496),
+            row("10.255.255.0", "10.255.255.255", 64500),
+            row(
+                "2001:db8::",
+                "2001:db8:ffff:ffff:ffff:ffff:ffff:ffff",
+                64496
+            ),
+        ]
+    );
+    assert_eq!(ranges[0].country, "None");
+    assert_eq!(ranges[0].name, "Unknown");
+
+    let asn_info = AsnInfoData::from_json_str(ASINFO).unwrap();
+    let ranges = ip2asn_ranges(&summary, Some(&asn_info));
+    let tsv = to_tsv(&ranges);
+    let lines: Vec<&str> = tsv.lines().collect();
+    assert_eq!(lines.len(), 9);
+    assert_eq!(lines[0], "1.1.1.0\t1.1.1.255\t13335\tUS\tCLOUDFLARENET");
+    assert_eq!(
+        lines[2],
+        "10.1.0.0\t10.127.255.255\t64496\tNone\tEXAMPLE NET"
+    );
+}
+
+#[test]
+fn test_summaries_with_ip2asn() {
+    let output_dir = tempfile::tempdir().unwrap();
+    let dir = output_dir.path().to_str().unwrap();
+    let mrt_path = output_dir.path().join("rib.test.bz2");
+    let mrt_path = mrt_path.to_str().unwrap();
+    SyntheticRibBuilder::new()
+        .peer("192.0.2.1", 65001)
+        .route("1.1.1.0/24", &[65001, 174, 13335])
+        .route("1.0.0.0/24", &[65001, 174, 13335])
+        .write_mrt_file(mrt_path)
+        .unwrap();
+
+    let rib_meta = RibMeta {
+        collector: "rrc99".to_string(),
+        ..Default::default()
+    };
+    let asn_info = Arc::new(AsnInfoData::from_json_str(ASINFO).unwrap());
+    let mut ribeye = RibEye::new()
+        .with_processor_names(&vec!["pfx2as".to_string()], dir)
+        .unwrap()
+        .with_rib_meta(&rib_meta)
+        .with_ip2asn(get_ip2asn_path(dir).as_str(), Some(asn_info));
+    ribeye.process_mrt_file(mrt_path).unwrap();
+    ribeye.summarize_latest_files(&[rib_meta]).unwrap();
+
+    let mut tsv = String::new();
+    oneio::get_reader(get_ip2asn_path(dir).as_str())
+        .unwrap()
+        .read_to_string(&mut tsv)
+        .unwrap();
+    assert_eq!(
+        tsv,
+        "1.0.0.0\t1.0.0.255\t13335\tUS\tCLOUDFLARENET\n1.1.1.0\t1.1.1.255\t13335\tUS\tCLOUDFLARENET\n"
+    );
+}
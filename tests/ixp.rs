@@ -0,0 +1,74 @@
+use ribeye::processors::{As2relProcessor, TypedProcessor};
+use ribeye::testing::SyntheticRibBuilder;
+use ribeye::util::{parse_ixp_asns, AsnSet, IxpPolicy, PathView};
+use ribeye::RibMeta;
+use std::sync::Arc;
+
+#[test]
+fn test_parse_ixp_asns() {
+    let asns = parse_ixp_asns("# route servers\nAS6695\n6777 # AMS-IX\n\n").unwrap();
+    assert_eq!(asns.iter().collect::<Vec<_>>(), vec![6695, 6777]);
+
+    let asns = parse_ixp_asns("[6695, 6777]").unwrap();
+    assert_eq!(asns.len(), 2);
+
+    let peeringdb = r#"{"data": [
+        {"asn": 6695, "info_type": "Route Server"},
+        {"asn": 8714, "info_types": ["Route Server", "Non-Profit"]},
+        {"asn": 13335, "info_type": "Content"}
+    ]}"#;
+    let asns = parse_ixp_asns(peeringdb).unwrap();
+    assert_eq!(asns.iter().collect::<Vec<_>>(), vec![6695, 8714]);
+
+    assert!(parse_ixp_asns("not-an-asn").is_err());
+}
+
+#[test]
+fn test_path_without() {
+    let elem = SyntheticRibBuilder::new()
+        .peer("192.0.2.1", 65001)
+        .route("1.1.1.0/24", &[65001, 6695, 174, 13335])
+        .build()
+        .remove(0);
+    let path = PathView::from_elem(&elem).unwrap();
+    let ixps: AsnSet = [6695].into_iter().collect();
+    assert!(path.contains_any(&ixps));
+    assert_eq!(path.without(&ixps).unwrap().asns(), &[65001, 174, 13335]);
+    let all: AsnSet = path.iter().collect();
+    assert!(path.without(&all).is_none());
+}
+
+fn as2rel_links(policy: IxpPolicy) -> Vec<(u32, u32, bool)> {
+    let ixps: AsnSet = [6695].into_iter().collect();
+    let mut processor = As2relProcessor::new("-").with_ixp_asns(Arc::new(ixps), policy);
+    processor.reset_processor(&RibMeta::default());
+    let elems = SyntheticRibBuilder::new()
+        .peer("192.0.2.1", 65001)
+        .route("1.1.1.0/24", &[65001, 6695, 64500])
+        .build();
+    for elem in &elems {
+        processor.process_entry(elem).unwrap();
+    }
+    let mut links: Vec<_> = processor
+        .to_output()
+        .unwrap()
+        .as2rel
+        .into_iter()
+        .map(|e| (e.asn1, e.asn2, e.ixp))
+        .collect();
+    links.sort();
+    links
+}
+
+#[test]
+fn test_as2rel_ixp_policy() {
+    assert_eq!(
+        as2rel_links(IxpPolicy::Keep),
+        vec![(6695, 64500, false), (65001, 6695, false)]
+    );
+    assert_eq!(as2rel_links(IxpPolicy::Strip), vec![(65001, 64500, false)]);
+    assert_eq!(
+        as2rel_links(IxpPolicy::Tag),
+        vec![(6695, 64500, true), (65001, 6695, true)]
+    );
+}
@@ -0,0 +1,79 @@
+use ribeye::processors::{get_labeled_output_dir, validate_label, ProcessorConfig, LABEL_FIELD};
+use ribeye::testing::SyntheticRibBuilder;
+use ribeye::{RibEye, RibMeta};
+
+fn rib_meta() -> RibMeta {
+    RibMeta {
+        collector: "rrc00".to_string(),
+        ..Default::default()
+    }
+}
+
+#[test]
+fn test_labeled_output_dirs() {
+    assert_eq!(
+        get_labeled_output_dir("s3://bucket/ribeye/", "exp-1"),
+        "s3://bucket/ribeye/labels/exp-1"
+    );
+    assert_eq!(get_labeled_output_dir("-", "exp-1"), "-");
+
+    assert!(validate_label("exp-1.v2_test").is_ok());
+    for label in ["", ".hidden", "a/b", "..", "exp 1"] {
+        assert!(validate_label(label).is_err(), "{}", label);
+    }
+
+    // per-processor directories set before and after the label are labeled
+    let config = ProcessorConfig::new()
+        .with_output_dir("pfx2as", "/data/pfx2as")
+        .with_label("exp-1")
+        .unwrap()
+        .with_output_dir("as2rel", "/data/as2rel/");
+    assert_eq!(config.output_dirs["pfx2as"], "/data/pfx2as/labels/exp-1");
+    assert_eq!(config.output_dirs["as2rel"], "/data/as2rel/labels/exp-1");
+    assert_eq!(config.labeled_output_dir("/data"), "/data/labels/exp-1");
+    assert_eq!(ProcessorConfig::new().labeled_output_dir("/data"), "/data");
+    assert!(ProcessorConfig::new().with_label("a/b").is_err());
+}
+
+#[test]
+fn test_labeled_run() {
+    let output_dir = tempfile::tempdir().unwrap();
+    let root = output_dir.path().to_str().unwrap();
+    let config = ProcessorConfig::new().with_label("exp-1").unwrap();
+    let dir = config.labeled_output_dir(root);
+    let elems = SyntheticRibBuilder::new()
+        .peer("10.0.0.1", 65001)
+        .route("1.1.1.0/24", &[65001, 13335])
+        .build();
+
+    let mut ribeye = RibEye::new()
+        .with_processor_names_and_config(&vec!["pfx2as".to_string()], dir.as_str(), &config)
+        .unwrap()
+        .with_rib_meta(&rib_meta());
+    ribeye.process_elems(&elems).unwrap();
+    ribeye.output().unwrap();
+    assert_eq!(ribeye.report().label.as_deref(), Some("exp-1"));
+
+    // outputs are written under the label and tagged with it, production outputs are untouched
+    let path = format!("{root}/labels/exp-1/pfx2as/rrc00/latest.json.bz2");
+    let output: serde_json::Value = oneio::read_json_struct(path.as_str()).unwrap();
+    assert_eq!(output[LABEL_FIELD], "exp-1");
+    assert!(!std::path::Path::new(format!("{root}/pfx2as").as_str()).exists());
+
+    ribeye.summarize_latest_files(&[rib_meta()]).unwrap();
+    let path = format!("{root}/labels/exp-1/pfx2as/latest.json.bz2");
+    let summary: serde_json::Value = oneio::read_json_struct(path.as_str()).unwrap();
+    assert_eq!(summary[LABEL_FIELD], "exp-1");
+
+    // unlabeled runs are not tagged
+    let mut ribeye = RibEye::new()
+        .with_processor_names(&vec!["pfx2as".to_string()], root)
+        .unwrap()
+        .with_rib_meta(&rib_meta());
+    ribeye.process_elems(&elems).unwrap();
+    ribeye.output().unwrap();
+    assert!(ribeye.report().label.is_none());
+    let path = format!("{root}/pfx2as/rrc00/latest.json.bz2");
+    let output: serde_json::Value = oneio::read_json_struct(path.as_str()).unwrap();
+    assert!(output.get(LABEL_FIELD).is_none());
+}
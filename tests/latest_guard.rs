@@ -0,0 +1,58 @@
+use chrono::NaiveDateTime;
+use ribeye::testing::SyntheticRibBuilder;
+use ribeye::{RibEye, RibMeta};
+use serde_json::Value;
+use std::path::Path;
+
+/// Process a synthetic RIB of the given time with pfx2as into `dir`.
+fn process(dir: &str, timestamp: &str, force_latest: bool) {
+    let rib_meta = RibMeta {
+        collector: "rrc00".to_string(),
+        timestamp: NaiveDateTime::parse_from_str(timestamp, "%Y-%m-%d %H:%M").unwrap(),
+        ..Default::default()
+    };
+    let mut ribeye = RibEye::new()
+        .with_processor_names(&vec!["pfx2as".to_string()], dir)
+        .unwrap()
+        .with_force_latest(force_latest)
+        .with_rib_meta(&rib_meta);
+    for elem in SyntheticRibBuilder::new().generated(1, 2).elems() {
+        ribeye.process_elem(elem).unwrap();
+    }
+    ribeye.output().unwrap();
+}
+
+/// RIB timestamp recorded in the latest output.
+fn latest_rib_timestamp(dir: &str) -> i64 {
+    let path = Path::new(dir).join("pfx2as/rrc00/latest.json.bz2");
+    let content = oneio::read_to_string(path.to_str().unwrap()).unwrap();
+    let value: Value = serde_json::from_str(content.as_str()).unwrap();
+    value["rib_timestamp"].as_i64().unwrap()
+}
+
+#[test]
+fn test_older_rib_keeps_newer_latest() {
+    let output_dir = tempfile::tempdir().unwrap();
+    let dir = output_dir.path().to_str().unwrap();
+    process(dir, "2024-01-02 08:00", false);
+    process(dir, "2024-01-01 08:00", false);
+
+    assert_eq!(latest_rib_timestamp(dir), 1704182400);
+    // the dated output of the older RIB is still written
+    assert!(Path::new(dir)
+        .join("pfx2as/rrc00/2024/01/pfx2as_rrc00_2024-01-01_0800_1704096000.json.bz2")
+        .exists());
+
+    process(dir, "2024-01-03 08:00", false);
+    assert_eq!(latest_rib_timestamp(dir), 1704268800);
+}
+
+#[test]
+fn test_force_latest_replaces_newer_latest() {
+    let output_dir = tempfile::tempdir().unwrap();
+    let dir = output_dir.path().to_str().unwrap();
+    process(dir, "2024-01-02 08:00", false);
+    process(dir, "2024-01-01 08:00", true);
+
+    assert_eq!(latest_rib_timestamp(dir), 1704096000);
+}
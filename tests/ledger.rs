@@ -0,0 +1,75 @@
+use anyhow::anyhow;
+use chrono::NaiveDateTime;
+use ribeye::ledger::RunLedger;
+use ribeye::{ProcessingStats, RibMeta};
+
+fn rib(collector: &str) -> RibMeta {
+    RibMeta {
+        project: "riperis".to_string(),
+        collector: collector.to_string(),
+        rib_dump_url: format!(
+            "https://archive.example/{}/bview.20240101.0000.gz",
+            collector
+        ),
+        timestamp: NaiveDateTime::parse_from_str("2024-01-01 00:00", "%Y-%m-%d %H:%M").unwrap(),
+        file_size: Some(1000),
+        dump_type: Some("bview".to_string()),
+        broker_item_id: Some(format!("{}-rib-1704067200", collector)),
+        ..Default::default()
+    }
+}
+
+fn stats(elems: u64) -> ProcessingStats {
+    ProcessingStats {
+        elems,
+        ..Default::default()
+    }
+}
+
+#[test]
+fn test_record_failed_ribs() {
+    let processors = vec!["pfx2as".to_string()];
+    let mut ledger = RunLedger::new(&processors);
+    ledger.record(&rib("rrc00"), &Ok(stats(10)));
+    ledger.record(&rib("rrc01"), &Err(anyhow!("connection reset")));
+    ledger.record(&rib("rrc03"), &Err(anyhow!("invalid MRT data")));
+    let failed: Vec<String> = ledger.failed().into_iter().map(|r| r.collector).collect();
+    assert_eq!(failed, vec!["rrc01", "rrc03"]);
+    assert_eq!(ledger.ribs[1].error.as_deref(), Some("connection reset"));
+
+    // a successful retry replaces the failure, keeping the run's RIB dumps
+    ledger.record(&rib("rrc01"), &Ok(stats(20)));
+    let failed: Vec<String> = ledger.failed().into_iter().map(|r| r.collector).collect();
+    assert_eq!(failed, vec!["rrc03"]);
+    assert_eq!(ledger.ribs.len(), 3);
+    assert_eq!(ledger.ribs[1].stats, Some(stats(20)));
+    let collectors: Vec<String> = ledger
+        .rib_metas()
+        .into_iter()
+        .map(|r| r.collector)
+        .collect();
+    assert_eq!(collectors, vec!["rrc00", "rrc01", "rrc03"]);
+}
+
+#[test]
+fn test_save_and_load() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("runs/ledger.json");
+    let path = path.to_str().unwrap();
+    assert!(RunLedger::load(path).is_err());
+
+    let mut ledger = RunLedger::new(&[]);
+    ledger.record(&rib("rrc00"), &Err(anyhow!("HTTP 503")));
+    ledger.save(path).unwrap();
+    let loaded = RunLedger::load(path).unwrap();
+    assert_eq!(loaded, ledger);
+
+    // the failed RIB dump is retried with the same meta information
+    let failed = loaded.failed();
+    let expected = rib("rrc00");
+    assert_eq!(failed.len(), 1);
+    assert_eq!(failed[0].rib_dump_url, expected.rib_dump_url);
+    assert_eq!(failed[0].timestamp, expected.timestamp);
+    assert_eq!(failed[0].file_size, expected.file_size);
+    assert_eq!(failed[0].broker_item_id, expected.broker_item_id);
+}
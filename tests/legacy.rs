@@ -0,0 +1,70 @@
+use ribeye::legacy::MrtElems;
+use ribeye::testing::{RecordingProcessor, SyntheticRibBuilder};
+use ribeye::{MessageProcessor, RibEye, RibMeta};
+
+fn legacy_builder() -> SyntheticRibBuilder {
+    SyntheticRibBuilder::new()
+        .peer("192.0.2.1", 65001)
+        .route("1.1.1.0/24", &[65001, 174, 13335])
+        .route("8.8.8.0/24", &[65001, 3356, 15169])
+        .peer("192.0.2.2", 4_200_000_002)
+        .route("1.1.1.0/24", &[4_200_000_002, 3356, 13335])
+        .route("203.0.113.0/24", &[4_200_000_002, 2914, 396_982])
+        .peer("2001:db8::1", 65003)
+        .route("2606:4700::/32", &[65003, 6939, 13335])
+}
+
+/// Peer, prefix, AS path and origins of each element, in order.
+fn summarize(elems: &[bgpkit_parser::BgpElem]) -> Vec<String> {
+    elems
+        .iter()
+        .map(|e| {
+            format!(
+                "{} {} {} {:?} {:?}",
+                e.peer_ip,
+                e.peer_asn,
+                e.prefix,
+                e.as_path.as_ref().and_then(|p| p.to_u32_vec_opt(false)),
+                e.origin_asns
+            )
+        })
+        .collect()
+}
+
+#[test]
+fn test_table_dump_v1_elems() {
+    let builder = legacy_builder();
+    let v1_bytes = builder.to_table_dump_v1_bytes();
+    let v2_bytes = builder.to_mrt_bytes();
+
+    let mut v1_elems = MrtElems::new(v1_bytes.as_slice());
+    let elems: Vec<_> = v1_elems.by_ref().collect();
+    // 4-byte peer ASNs and paths are restored from AS_TRANS and AS4_PATH
+    assert_eq!(summarize(&elems), summarize(builder.elems()));
+    assert_eq!(v1_elems.table_dump_v1_records(), 5);
+
+    let mut v2_elems = MrtElems::new(v2_bytes.as_slice());
+    assert_eq!(v2_elems.by_ref().count(), 5);
+    assert_eq!(v2_elems.table_dump_v1_records(), 0);
+}
+
+#[test]
+fn test_table_dump_v1_file() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("rib.20010101.0000.bz2");
+    let path = path.to_str().unwrap();
+    legacy_builder().write_table_dump_v1_file(path).unwrap();
+
+    let processor = RecordingProcessor::new("recorder");
+    let recording = processor.recording();
+    let mut ribeye = RibEye::new()
+        .with_processors(vec![processor.to_boxed()])
+        .with_rib_meta(&RibMeta::default());
+    ribeye.process_mrt_file(path).unwrap();
+
+    let recording = recording.lock().unwrap();
+    assert_eq!(
+        summarize(&recording.elems),
+        summarize(legacy_builder().elems())
+    );
+}
@@ -0,0 +1,114 @@
+use chrono::NaiveDateTime;
+use ribeye::listing::{
+    collector_listings, discover_rib_metas, latest_collectors, list_outputs, prune_outputs,
+};
+use ribeye::testing::SyntheticRibBuilder;
+use ribeye::{RibEye, RibMeta};
+use std::path::Path;
+
+/// Process a synthetic RIB of each collector and time with pfx2as into `dir`.
+fn cook(dir: &str, ribs: &[(&str, &str)]) {
+    for (collector, time) in ribs {
+        let rib_meta = RibMeta {
+            project: "riperis".to_string(),
+            collector: collector.to_string(),
+            rib_dump_url: format!("https://data.ris.ripe.net/{}/bview.gz", collector),
+            timestamp: NaiveDateTime::parse_from_str(time, "%Y-%m-%d %H:%M").unwrap(),
+            ..Default::default()
+        };
+        let mut ribeye = RibEye::new()
+            .with_processor_names(&vec!["pfx2as".to_string()], dir)
+            .unwrap()
+            .with_rib_meta(&rib_meta);
+        for elem in SyntheticRibBuilder::new().generated(1, 2).elems() {
+            ribeye.process_elem(elem).unwrap();
+        }
+        ribeye.output().unwrap();
+    }
+}
+
+#[test]
+fn test_list_outputs() {
+    let output_dir = tempfile::tempdir().unwrap();
+    let dir = output_dir.path().to_str().unwrap();
+    cook(
+        dir,
+        &[
+            ("rrc00", "2024-01-01 00:00"),
+            ("rrc00", "2024-01-02 00:00"),
+            ("route-views2", "2024-01-02 00:00"),
+        ],
+    );
+    // files outside the output layout are left out
+    std::fs::write(output_dir.path().join("pfx2as/notes.txt"), "").unwrap();
+
+    let outputs = list_outputs(dir).unwrap();
+    let paths: Vec<_> = outputs.iter().map(|o| o.path.as_str()).collect();
+    assert_eq!(
+        paths,
+        vec![
+            "pfx2as/route-views2/2024/01/pfx2as_route-views2_2024-01-02_0000_1704153600.json.bz2",
+            "pfx2as/route-views2/latest.json.bz2",
+            "pfx2as/rrc00/2024/01/pfx2as_rrc00_2024-01-01_0000_1704067200.json.bz2",
+            "pfx2as/rrc00/2024/01/pfx2as_rrc00_2024-01-02_0000_1704153600.json.bz2",
+            "pfx2as/rrc00/latest.json.bz2",
+        ]
+    );
+
+    let listings = collector_listings(&outputs);
+    assert_eq!(listings.len(), 2);
+    let rrc00 = &listings[1];
+    assert_eq!(rrc00.collector, "rrc00");
+    assert!(rrc00.latest);
+    assert_eq!(rrc00.files, 2);
+    assert_eq!(rrc00.first_ts, Some(1704067200));
+    assert_eq!(rrc00.last_ts, Some(1704153600));
+
+    assert_eq!(
+        latest_collectors(dir, "pfx2as").unwrap(),
+        vec!["route-views2", "rrc00"]
+    );
+}
+
+#[test]
+fn test_discover_rib_metas() {
+    let output_dir = tempfile::tempdir().unwrap();
+    let dir = output_dir.path().to_str().unwrap();
+    cook(dir, &[("rrc00", "2024-01-02 08:00")]);
+
+    let rib_metas = discover_rib_metas(dir, "pfx2as").unwrap();
+    assert_eq!(rib_metas.len(), 1);
+    assert_eq!(rib_metas[0].project, "riperis");
+    assert_eq!(rib_metas[0].collector, "rrc00");
+    assert_eq!(
+        rib_metas[0].rib_dump_url,
+        "https://data.ris.ripe.net/rrc00/bview.gz"
+    );
+    assert_eq!(rib_metas[0].timestamp.and_utc().timestamp(), 1704182400);
+}
+
+#[test]
+fn test_prune_outputs() {
+    let output_dir = tempfile::tempdir().unwrap();
+    let dir = output_dir.path().to_str().unwrap();
+    cook(
+        dir,
+        &[("rrc00", "2024-01-01 00:00"), ("rrc00", "2024-01-02 00:00")],
+    );
+    let old = Path::new(dir)
+        .join("pfx2as/rrc00/2024/01/pfx2as_rrc00_2024-01-01_0000_1704067200.json.bz2");
+    let signature = format!("{}.minisig", old.to_str().unwrap());
+    std::fs::write(signature.as_str(), "").unwrap();
+
+    let expired = prune_outputs(dir, 1704153600, true).unwrap();
+    assert_eq!(expired.len(), 1);
+    assert!(old.exists());
+
+    let expired = prune_outputs(dir, 1704153600, false).unwrap();
+    assert_eq!(expired[0].ts, Some(1704067200));
+    assert!(!old.exists());
+    assert!(!Path::new(signature.as_str()).exists());
+    let remaining = list_outputs(dir).unwrap();
+    assert_eq!(remaining.len(), 2);
+    assert!(remaining.iter().any(|o| o.ts.is_none()));
+}
@@ -0,0 +1,14 @@
+use ribeye::live::ris_peer_down;
+
+#[test]
+fn test_ris_peer_down() {
+    let down = r#"{"type":"ris_message","data":{"timestamp":1568365292.84,"peer":"192.0.2.1","peer_asn":"64496","id":"00-192-0-2-0-180513","host":"rrc00","type":"RIS_PEER_STATE","state":"down"}}"#;
+    assert_eq!(ris_peer_down(down), Some("192.0.2.1".parse().unwrap()));
+
+    let connected = down.replace("\"down\"", "\"connected\"");
+    assert_eq!(ris_peer_down(connected.as_str()), None);
+
+    let update = r#"{"type":"ris_message","data":{"timestamp":1568365292.84,"peer":"192.0.2.1","peer_asn":"64496","id":"00-192-0-2-0-180513","host":"rrc00","type":"UPDATE","path":[64496,64500],"announcements":[{"next_hop":"192.0.2.1","prefixes":["10.0.0.0/8"]}]}}"#;
+    assert_eq!(ris_peer_down(update), None);
+    assert_eq!(ris_peer_down("not json"), None);
+}
@@ -0,0 +1,78 @@
+use ribeye::lock::{get_lock_path, read_lease, LockOptions, SummaryLock};
+use ribeye::testing::SyntheticRibBuilder;
+use ribeye::{RibEye, RibMeta};
+use std::time::Duration;
+
+fn options(ttl: u64, wait: u64) -> LockOptions {
+    LockOptions {
+        ttl: Duration::from_secs(ttl),
+        wait: Duration::from_secs(wait),
+    }
+}
+
+#[test]
+fn test_summary_lock() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let path = temp_dir.path().join("pfx2as/latest.json.bz2");
+    let path = path.to_str().unwrap();
+    let lock_path = get_lock_path(path);
+
+    let lock = SummaryLock::acquire(path, &options(60, 0)).unwrap();
+    assert_eq!(read_lease(lock_path.as_str()).as_ref(), Some(lock.lease()));
+    // a concurrent writer gives up after waiting
+    let err = SummaryLock::acquire(path, &options(60, 0)).unwrap_err();
+    assert!(err.to_string().contains("is locked by"));
+    drop(lock);
+    assert!(!std::path::Path::new(&lock_path).exists());
+
+    // an expired lease of a crashed run is taken over
+    let crashed = SummaryLock::acquire(path, &options(0, 0)).unwrap();
+    std::mem::forget(crashed);
+    let lock = SummaryLock::acquire(path, &options(60, 0)).unwrap();
+    assert_eq!(
+        read_lease(lock_path.as_str()).unwrap().owner,
+        lock.lease().owner
+    );
+}
+
+#[test]
+fn test_summarize_skips_locked_summary() {
+    let output_dir = tempfile::tempdir().unwrap();
+    let dir = output_dir.path().to_str().unwrap();
+    let mrt_path = output_dir.path().join("rib.test.bz2");
+    let mrt_path = mrt_path.to_str().unwrap();
+    SyntheticRibBuilder::new()
+        .peer("192.0.2.1", 65001)
+        .route("1.1.1.0/24", &[65001, 174, 13335])
+        .write_mrt_file(mrt_path)
+        .unwrap();
+    let rib_meta = RibMeta {
+        collector: "rrc00".to_string(),
+        ..Default::default()
+    };
+    let mut ribeye = RibEye::new()
+        .with_processor_names(&vec!["pfx2as".to_string()], dir)
+        .unwrap()
+        .with_rib_meta(&rib_meta);
+    ribeye.process_mrt_file(mrt_path).unwrap();
+
+    let summary_path = format!("{}/pfx2as/latest.json.bz2", dir);
+    let summarize = || {
+        RibEye::new()
+            .with_processor_names(&vec!["pfx2as".to_string()], dir)
+            .unwrap()
+            .with_summary_lock(Some(options(60, 0)))
+            .summarize_latest_files(std::slice::from_ref(&rib_meta))
+            .unwrap();
+    };
+
+    // another run holds the summary: it is skipped rather than written concurrently
+    let lock = SummaryLock::acquire(summary_path.as_str(), &options(60, 0)).unwrap();
+    summarize();
+    assert!(!std::path::Path::new(&summary_path).exists());
+    drop(lock);
+
+    summarize();
+    assert!(std::path::Path::new(&summary_path).exists());
+    assert!(!std::path::Path::new(&get_lock_path(summary_path.as_str())).exists());
+}
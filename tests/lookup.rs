@@ -0,0 +1,50 @@
+use ribeye::lookup::LookupData;
+use ribeye::testing::SyntheticRibBuilder;
+use ribeye::{RibEye, RibMeta};
+
+#[test]
+fn test_lookup_ip_and_asn() {
+    let output_dir = tempfile::tempdir().unwrap();
+    let dir = output_dir.path().to_str().unwrap();
+    let mrt_path = output_dir.path().join("rib.test.bz2");
+    let mrt_path = mrt_path.to_str().unwrap();
+    SyntheticRibBuilder::new()
+        .peer("192.0.2.1", 65001)
+        .route("1.1.0.0/16", &[65001, 174, 64500])
+        .route("1.1.1.0/24", &[65001, 174, 13335])
+        .peer("192.0.2.2", 13335)
+        .route("1.1.1.0/24", &[13335])
+        .write_mrt_file(mrt_path)
+        .unwrap();
+
+    let rib_meta = RibMeta {
+        collector: "rrc99".to_string(),
+        ..Default::default()
+    };
+    let processors = vec![
+        "pfx2as".to_string(),
+        "as2rel".to_string(),
+        "peer-stats".to_string(),
+    ];
+    let mut ribeye = RibEye::new()
+        .with_processor_names(&processors, dir)
+        .unwrap()
+        .with_rib_meta(&rib_meta);
+    ribeye.process_mrt_file(mrt_path).unwrap();
+    ribeye.summarize_latest_files(&[rib_meta]).unwrap();
+
+    let data = LookupData::load(dir).unwrap();
+
+    let result = data.lookup_ip("1.1.1.1".parse().unwrap());
+    assert_eq!(result.origins, vec![13335]);
+    assert_eq!(result.covering_prefixes.len(), 2);
+    assert_eq!(result.covering_prefixes[0].prefix, "1.1.1.0/24");
+    assert_eq!(result.covering_prefixes[0].visibility, 2);
+    assert_eq!(result.covering_prefixes[1].origin, 64500);
+
+    let result = data.lookup_asn(13335);
+    assert_eq!(result.prefixes.len(), 1);
+    assert!(result.providers.contains(&174));
+    assert!(result.neighbors.contains(&174));
+    assert_eq!(result.collector_peers.len(), 1);
+}
@@ -0,0 +1,74 @@
+use chrono::NaiveDateTime;
+use ribeye::manifest::SourceManifest;
+
+fn time(s: &str) -> NaiveDateTime {
+    NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M").unwrap()
+}
+
+#[test]
+fn test_manifest_files() {
+    let manifest = SourceManifest::from_json_str(
+        r#"{"files": [
+            {"url": "/data/isolario/Alderaan/2024_01/rib.20240101.0000.bz2"},
+            {"url": "https://example.org/snapshot.mrt.gz", "collector": "route-collector.sfo.pch.net",
+             "timestamp": "2024-01-01T02:00:00Z", "file_size": 1000}
+        ]}"#,
+    )
+    .unwrap();
+    let rib_metas = manifest.rib_metas().unwrap();
+    assert_eq!(rib_metas.len(), 2);
+
+    // meta information inferred from the path
+    assert_eq!(rib_metas[0].project, "isolario");
+    assert_eq!(rib_metas[0].collector, "Alderaan");
+    assert_eq!(rib_metas[0].timestamp, time("2024-01-01 00:00"));
+    assert_eq!(rib_metas[0].dump_type.as_deref(), Some("rib"));
+
+    // explicit meta information, the project from the collector profiles
+    assert_eq!(rib_metas[1].project, "pch");
+    assert_eq!(rib_metas[1].collector, "route-collector.sfo.pch.net");
+    assert_eq!(rib_metas[1].timestamp, time("2024-01-01 02:00"));
+    assert_eq!(rib_metas[1].file_size, Some(1000));
+
+    // files without a dump time in their path need a timestamp
+    let manifest =
+        SourceManifest::from_json_str(r#"{"files": [{"url": "https://example.org/snapshot.gz"}]}"#)
+            .unwrap();
+    let err = manifest.rib_metas().unwrap_err().to_string();
+    assert!(err.contains("set its timestamp"), "{}", err);
+}
+
+#[test]
+fn test_manifest_patterns_and_profiles() {
+    let manifest = SourceManifest::from_json_str(
+        r#"{"profiles": [{"project": "lab", "collectors": ["lab-*"]}],
+            "patterns": [{"url": "https://example.org/{collector}/{year}/{month}/rib.{year}{month}{day}.{hour}00.gz",
+                          "collectors": ["lab-a", "lab-b"], "from": "2024-01-31", "to": "2024-02-01", "hour": 8}]}"#,
+    )
+    .unwrap();
+    let rib_metas = manifest.rib_metas().unwrap();
+    let urls: Vec<&str> = rib_metas.iter().map(|r| r.rib_dump_url.as_str()).collect();
+    assert_eq!(
+        urls,
+        vec![
+            "https://example.org/lab-a/2024/01/rib.20240131.0800.gz",
+            "https://example.org/lab-b/2024/01/rib.20240131.0800.gz",
+            "https://example.org/lab-a/2024/02/rib.20240201.0800.gz",
+            "https://example.org/lab-b/2024/02/rib.20240201.0800.gz",
+        ]
+    );
+    assert!(rib_metas.iter().all(|r| r.project == "lab"));
+    assert_eq!(rib_metas[3].collector, "lab-b");
+    assert_eq!(rib_metas[3].timestamp, time("2024-02-01 08:00"));
+
+    // the manifest's profiles extend the built-in ones
+    let profiles = manifest.collector_profiles();
+    assert_eq!(profiles.project("lab-c", ""), "lab");
+    assert_eq!(profiles.project("rrc00", ""), "riperis");
+
+    let invalid = SourceManifest::from_json_str(
+        r#"{"patterns": [{"url": "{collector}", "collectors": ["lab-a"], "from": "2024-01", "to": "2024-02"}]}"#,
+    )
+    .unwrap();
+    assert!(invalid.rib_metas().is_err());
+}
@@ -0,0 +1,205 @@
+use bgpkit_broker::BrokerItem;
+use chrono::NaiveDateTime;
+use ribeye::processors::{expand_input_paths, OutputGranularity, Prefix2AsProcessor};
+use ribeye::testing::SyntheticRibBuilder;
+use ribeye::RibMeta;
+use ribeye::{MessageProcessor, RibEye};
+use std::str::FromStr;
+
+#[test]
+fn test_rib_meta_from_broker_item() {
+    let ts = NaiveDateTime::parse_from_str("2024-01-01T00:00:00", "%Y-%m-%dT%H:%M:%S").unwrap();
+    let mut item = BrokerItem {
+        ts_start: ts,
+        ts_end: ts,
+        collector_id: "route-views2".to_string(),
+        data_type: "rib".to_string(),
+        url: "https://archive.routeviews.org/bgpdata/2024.01/RIBS/rib.20240101.0000.bz2"
+            .to_string(),
+        rough_size: 100,
+        exact_size: 0,
+    };
+
+    let rib_meta = RibMeta::from(&item);
+    assert_eq!(rib_meta.project, "route-views");
+    assert_eq!(rib_meta.file_size, Some(100));
+    assert_eq!(rib_meta.dump_type.as_deref(), Some("rib"));
+    assert_eq!(
+        rib_meta.broker_item_id.as_deref(),
+        Some("route-views2-rib-1704067200")
+    );
+
+    item.exact_size = 123;
+    assert_eq!(RibMeta::from(&item).file_size, Some(123));
+}
+
+#[test]
+fn test_rib_meta_from_file_path() {
+    let rib_meta = RibMeta::from_file_path("archive/rrc00/2024.01/bview.20240101.0800.gz").unwrap();
+    assert_eq!(rib_meta.project, "riperis");
+    assert_eq!(rib_meta.collector, "rrc00");
+    assert_eq!(rib_meta.dump_type.as_deref(), Some("bview"));
+    assert_eq!(
+        rib_meta.timestamp,
+        NaiveDateTime::parse_from_str("2024-01-01 08:00", "%Y-%m-%d %H:%M").unwrap()
+    );
+    assert_eq!(
+        rib_meta.rib_dump_url,
+        "archive/rrc00/2024.01/bview.20240101.0800.gz"
+    );
+    // the file does not exist
+    assert_eq!(rib_meta.file_size, None);
+
+    let rib_meta = RibMeta::from_file_path(
+        "https://archive.routeviews.org/route-views.sg/bgpdata/2024.01/RIBS/rib.20240101.0200.bz2",
+    )
+    .unwrap();
+    assert_eq!(rib_meta.project, "route-views");
+    assert_eq!(rib_meta.collector, "route-views.sg");
+    assert_eq!(rib_meta.dump_type.as_deref(), Some("rib"));
+    assert_eq!(rib_meta.timestamp.and_utc().timestamp(), 1704074400);
+
+    // files directly under bgpdata belong to route-views2
+    let rib_meta =
+        RibMeta::from_file_path("/data/bgpdata/2024.01/RIBS/rib.20240101.0000.bz2").unwrap();
+    assert_eq!(rib_meta.collector, "route-views2");
+
+    // the file exists
+    let temp_dir = tempfile::tempdir().unwrap();
+    let path = temp_dir.path().join("rrc01");
+    std::fs::create_dir_all(&path).unwrap();
+    let path = path.join("bview.20240101.0000.gz");
+    std::fs::write(&path, b"1234").unwrap();
+    let rib_meta = RibMeta::from_file_path(path.to_str().unwrap()).unwrap();
+    assert_eq!(rib_meta.collector, "rrc01");
+    assert_eq!(rib_meta.file_size, Some(4));
+}
+
+#[test]
+fn test_rib_meta_from_unparseable_file_path() {
+    // no timestamp in the file name
+    assert!(RibMeta::from_file_path("archive/rrc00/2024.01/latest-bview.gz").is_none());
+    assert!(RibMeta::from_file_path("archive/rrc00/rib.bz2").is_none());
+    // invalid date or time
+    assert!(RibMeta::from_file_path("archive/rrc00/2024.13/bview.20241301.0000.gz").is_none());
+    assert!(RibMeta::from_file_path("archive/rrc00/2024.01/bview.20240101.2500.gz").is_none());
+    assert!(RibMeta::from_file_path("").is_none());
+    assert!(RibMeta::from_file_path("/").is_none());
+
+    // timestamp but no known collector
+    let rib_meta = RibMeta::from_file_path("ribs/rib.20240101.0000.bz2").unwrap();
+    assert_eq!(rib_meta.collector, "unknown");
+}
+
+#[test]
+fn test_expand_input_paths() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let dir = temp_dir.path().to_str().unwrap();
+    for path in [
+        "rrc00/bview.20240101.0000.gz",
+        "rrc00/bview.20240101.0800.gz",
+        "rrc01/nested/bview.20240101.0000.gz",
+        "rib.20240101.0000.bz2",
+    ] {
+        let path = temp_dir.path().join(path);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, b"").unwrap();
+    }
+
+    // directories are listed recursively, patterns and overlapping inputs are deduplicated
+    let files = expand_input_paths(&[
+        format!("{}/rrc01", dir),
+        format!("{}/rrc00/*.gz", dir),
+        format!("{}/rrc00/bview.20240101.0000.gz", dir),
+    ])
+    .unwrap();
+    assert_eq!(
+        files,
+        vec![
+            format!("{}/rrc00/bview.20240101.0000.gz", dir),
+            format!("{}/rrc00/bview.20240101.0800.gz", dir),
+            format!("{}/rrc01/nested/bview.20240101.0000.gz", dir),
+        ]
+    );
+
+    // patterns only match files
+    let files = expand_input_paths(&[format!("{}/*", dir)]).unwrap();
+    assert_eq!(files, vec![format!("{}/rib.20240101.0000.bz2", dir)]);
+
+    // patterns matching no files
+    assert!(expand_input_paths(&[format!("{}/*.mrt", dir)])
+        .unwrap()
+        .is_empty());
+    assert!(expand_input_paths(&[format!("{}/missing/rib.*", dir)])
+        .unwrap()
+        .is_empty());
+    // invalid patterns
+    assert!(expand_input_paths(&[format!("{}/[", dir)]).is_err());
+}
+
+fn rib_meta(time: &str, output_granularity: OutputGranularity) -> RibMeta {
+    RibMeta {
+        collector: "rrc00".to_string(),
+        timestamp: NaiveDateTime::parse_from_str(time, "%Y-%m-%d %H:%M").unwrap(),
+        output_granularity,
+        ..Default::default()
+    }
+}
+
+fn output_path(rib_meta: &RibMeta) -> String {
+    let mut processor = Prefix2AsProcessor::new("out");
+    MessageProcessor::reset_processor(&mut processor, rib_meta);
+    processor.output_paths().unwrap()[0].clone()
+}
+
+#[test]
+fn test_output_granularity() {
+    assert_eq!(
+        OutputGranularity::from_str("daily").unwrap(),
+        OutputGranularity::Daily
+    );
+    assert!(OutputGranularity::from_str("hourly").is_err());
+
+    // dumps of the same day get distinct names including the dump hour
+    let exact = |time| output_path(&rib_meta(time, OutputGranularity::Exact));
+    assert_eq!(
+        exact("2024-01-01 08:00"),
+        "out/pfx2as/rrc00/2024/01/pfx2as_rrc00_2024-01-01_0800_1704096000.json.bz2"
+    );
+    assert_eq!(
+        exact("2024-01-01 16:00"),
+        "out/pfx2as/rrc00/2024/01/pfx2as_rrc00_2024-01-01_1600_1704124800.json.bz2"
+    );
+
+    // daily outputs share the name, with the timestamp of midnight
+    let daily = |time| output_path(&rib_meta(time, OutputGranularity::Daily));
+    assert_eq!(
+        daily("2024-01-01 08:00"),
+        "out/pfx2as/rrc00/2024/01/pfx2as_rrc00_2024-01-01_1704067200.json.bz2"
+    );
+    assert_eq!(daily("2024-01-01 08:00"), daily("2024-01-01 16:00"));
+}
+
+#[test]
+fn test_pipeline_output_granularity() {
+    let output_dir = tempfile::tempdir().unwrap();
+    let dir = output_dir.path().to_str().unwrap();
+    let mut ribeye = RibEye::new()
+        .with_processor_names(&vec!["pfx2as".to_string()], dir)
+        .unwrap()
+        .with_output_granularity(OutputGranularity::Daily);
+    for time in ["2024-01-01 00:00", "2024-01-01 08:00"] {
+        ribeye
+            .initialize_processors(&rib_meta(time, OutputGranularity::Exact))
+            .unwrap();
+        for elem in SyntheticRibBuilder::new().generated(1, 2).elems() {
+            ribeye.process_elem(elem).unwrap();
+        }
+        ribeye.output().unwrap();
+    }
+    let files: Vec<_> = std::fs::read_dir(output_dir.path().join("pfx2as/rrc00/2024/01"))
+        .unwrap()
+        .map(|e| e.unwrap().file_name().into_string().unwrap())
+        .collect();
+    assert_eq!(files, vec!["pfx2as_rrc00_2024-01-01_1704067200.json.bz2"]);
+}
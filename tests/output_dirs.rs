@@ -0,0 +1,74 @@
+use ribeye::processors::{ProcessorConfig, ProcessorConfigFile};
+use ribeye::testing::SyntheticRibBuilder;
+use ribeye::{RibEye, RibMeta};
+
+#[test]
+fn test_processor_config_file() {
+    let config_file = ProcessorConfigFile::from_json_str(
+        r#"{"output_dirs": {"Pfx2as": "s3://public-bucket/ribeye/", "pfx2dist": "/scratch"}}"#,
+    )
+    .unwrap();
+    let config = ProcessorConfig::new().with_config_file(&config_file);
+    assert_eq!(
+        config.output_dir("pfx2as"),
+        Some("s3://public-bucket/ribeye")
+    );
+    assert_eq!(config.output_dir("pfx2dist"), Some("/scratch"));
+    assert_eq!(config.output_dir("as2rel"), None);
+    // all settings are optional
+    assert_eq!(
+        ProcessorConfigFile::from_json_str("{}").unwrap(),
+        ProcessorConfigFile::default()
+    );
+}
+
+#[test]
+fn test_per_processor_output_dirs() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let dir = temp_dir.path().join("results");
+    let dir = dir.to_str().unwrap();
+    let scratch = temp_dir.path().join("scratch");
+    let scratch = scratch.to_str().unwrap();
+    let mrt_path = temp_dir.path().join("rib.test.bz2");
+    let mrt_path = mrt_path.to_str().unwrap();
+    SyntheticRibBuilder::new()
+        .peer("192.0.2.1", 65001)
+        .route("1.1.1.0/24", &[65001, 174, 13335])
+        .write_mrt_file(mrt_path)
+        .unwrap();
+
+    // overrides match the requested name and the output name of a processor
+    let config = ProcessorConfig::new()
+        .with_output_dir("pfx2as", scratch)
+        .with_output_dir("peer-stats", scratch);
+    let rib_meta = RibMeta {
+        collector: "rrc00".to_string(),
+        ..Default::default()
+    };
+    let mut ribeye = RibEye::new()
+        .with_processor_names_and_config(
+            &vec![
+                "pfx2as".to_string(),
+                "peer_stats".to_string(),
+                "pfxlen".to_string(),
+            ],
+            dir,
+            &config,
+        )
+        .unwrap()
+        .with_rib_meta(&rib_meta);
+    ribeye.process_mrt_file(mrt_path).unwrap();
+    ribeye.summarize_latest_files(&[rib_meta]).unwrap();
+
+    for (root, processor) in [
+        (scratch, "pfx2as"),
+        (scratch, "peer-stats"),
+        (dir, "pfxlen"),
+    ] {
+        let latest = format!("{}/{}/rrc00/latest.json.bz2", root, processor);
+        assert!(std::path::Path::new(&latest).exists(), "{}", latest);
+        let summary = format!("{}/{}/latest.json.bz2", root, processor);
+        assert!(std::path::Path::new(&summary).exists(), "{}", summary);
+    }
+    assert!(!temp_dir.path().join("results/pfx2as").exists());
+}
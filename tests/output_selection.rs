@@ -0,0 +1,39 @@
+use chrono::NaiveDateTime;
+use ribeye::processors::OutputSelection;
+use ribeye::testing::SyntheticRibBuilder;
+use ribeye::{RibEye, RibMeta};
+use std::path::Path;
+
+/// Process a synthetic RIB with pfx2as and return whether the dated and latest outputs exist.
+fn written(output_selection: OutputSelection) -> (bool, bool) {
+    let output_dir = tempfile::tempdir().unwrap();
+    let dir = output_dir.path().to_str().unwrap();
+    let rib_meta = RibMeta {
+        collector: "rrc00".to_string(),
+        timestamp: NaiveDateTime::parse_from_str("2024-01-01 08:00", "%Y-%m-%d %H:%M").unwrap(),
+        ..Default::default()
+    };
+    let mut ribeye = RibEye::new()
+        .with_processor_names(&vec!["pfx2as".to_string()], dir)
+        .unwrap()
+        .with_output_selection(output_selection)
+        .with_rib_meta(&rib_meta);
+    for elem in SyntheticRibBuilder::new().generated(1, 2).elems() {
+        ribeye.process_elem(elem).unwrap();
+    }
+    ribeye.output().unwrap();
+
+    let collector_dir = Path::new(dir).join("pfx2as/rrc00");
+    let dated = collector_dir
+        .join("2024/01/pfx2as_rrc00_2024-01-01_0800_1704096000.json.bz2")
+        .exists();
+    let latest = collector_dir.join("latest.json.bz2").exists();
+    (dated, latest)
+}
+
+#[test]
+fn test_output_selection() {
+    assert_eq!(written(OutputSelection::All), (true, true));
+    assert_eq!(written(OutputSelection::DatedOnly), (true, false));
+    assert_eq!(written(OutputSelection::LatestOnly), (false, true));
+}
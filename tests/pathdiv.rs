@@ -0,0 +1,113 @@
+use bgpkit_parser::BgpElem;
+use ribeye::processors::{as_path_edit_distance, PathDiversityProcessor, TypedProcessor};
+use ribeye::testing::{collector_output, SyntheticRibBuilder};
+
+/// Ten prefixes routed the same way by three peers but for the transit of the third peer, and one
+/// prefix routed through entirely different paths.
+fn elems() -> Vec<BgpElem> {
+    let mut builder = SyntheticRibBuilder::new();
+    for (peer, asn, transit) in [("10.0.0.1", 65001, 174), ("10.0.0.2", 65002, 174)]
+        .into_iter()
+        .chain([("10.0.0.3", 65003, 3356)])
+    {
+        builder = builder.peer(peer, asn);
+        for i in 0..10 {
+            let prefix = format!("10.{}.0.0/16", i);
+            builder = builder.route(prefix.as_str(), &[asn, transit, 64500 + i]);
+        }
+    }
+    builder
+        .peer("10.0.0.1", 65001)
+        .route("192.0.2.0/24", &[65001, 174, 64600])
+        .peer("10.0.0.2", 65002)
+        .route("192.0.2.0/24", &[65002, 6939, 1299, 2914, 64600])
+        .peer("10.0.0.3", 65003)
+        .route("192.0.2.0/24", &[65003, 65003, 3257, 6762, 3491, 64601])
+        // single peer prefixes are not compared
+        .route("198.51.100.0/24", &[65003, 64602])
+        .build()
+}
+
+#[test]
+fn test_edit_distance() {
+    assert_eq!(as_path_edit_distance(&[], &[]), 0);
+    assert_eq!(as_path_edit_distance(&[1, 2, 3], &[1, 2, 3]), 0);
+    assert_eq!(as_path_edit_distance(&[1, 2, 3], &[1, 4, 3]), 1);
+    assert_eq!(as_path_edit_distance(&[1, 2, 3], &[1, 3]), 1);
+    assert_eq!(as_path_edit_distance(&[1, 2], &[3, 4, 5]), 3);
+    assert_eq!(as_path_edit_distance(&[], &[1, 2]), 2);
+}
+
+#[test]
+fn test_path_diversity() {
+    let (_, output) = collector_output(PathDiversityProcessor::new("-"), "rrc00", &elems());
+    assert_eq!(output.prefixes.len(), 11);
+    let common = &output.prefixes[0];
+    assert_eq!(common.prefix.to_string(), "10.0.0.0/16");
+    assert_eq!(common.peers_count, 3);
+    assert_eq!(common.distinct_paths, 3);
+    assert_eq!(common.pairs, 3);
+    // peer ASN differs for all pairs, the transit for two of them
+    assert_eq!(common.avg_edit_distance, 5.0 / 3.0);
+    assert_eq!(common.max_edit_distance, 2);
+    assert!(!common.heterogeneous);
+
+    let diverse = output
+        .prefixes
+        .iter()
+        .find(|p| p.prefix.to_string() == "192.0.2.0/24")
+        .unwrap();
+    // prepending is removed: [65003, 3257, 6762, 3491, 64601]
+    assert_eq!(diverse.max_edit_distance, 5);
+    assert!(diverse.heterogeneous);
+    assert_eq!(
+        output.prefixes.iter().filter(|p| p.heterogeneous).count(),
+        1
+    );
+    assert!(output.mean_edit_distance > common.avg_edit_distance);
+    assert!(output.stddev_edit_distance > 0.0);
+}
+
+#[test]
+fn test_repeated_entries_replace_the_peer_path() {
+    let elems = SyntheticRibBuilder::new()
+        .peer("10.0.0.1", 65001)
+        .route("1.1.1.0/24", &[65001, 174, 13335])
+        .route("1.1.1.0/24", &[65001, 13335])
+        .peer("10.0.0.2", 65002)
+        .route("1.1.1.0/24", &[65002, 13335])
+        .build();
+    let (_, output) = collector_output(PathDiversityProcessor::new("-"), "rrc00", &elems);
+    assert_eq!(output.prefixes[0].peers_count, 2);
+    assert_eq!(output.prefixes[0].avg_edit_distance, 1.0);
+}
+
+#[test]
+fn test_path_diversity_summary() {
+    let (meta0, output0) = collector_output(PathDiversityProcessor::new("-"), "rrc00", &elems());
+    let elems = SyntheticRibBuilder::new()
+        .peer("10.1.0.1", 65010)
+        .route("10.0.0.0/16", &[65010, 64500])
+        .peer("10.1.0.2", 65011)
+        .route("10.0.0.0/16", &[65011, 64500])
+        .build();
+    let (meta1, output1) = collector_output(PathDiversityProcessor::new("-"), "rrc01", &elems);
+    let summary = PathDiversityProcessor::new("-")
+        .summarize(
+            &[meta0.clone(), meta1.clone()],
+            vec![(meta0, output0), (meta1, output1)],
+        )
+        .unwrap();
+    assert_eq!(summary.prefixes.len(), 11);
+    let merged = &summary.prefixes[0];
+    assert_eq!(merged.collectors.len(), 2);
+    assert_eq!(merged.peers_count, 5);
+    assert_eq!(merged.pairs, 4);
+    // pairs are only compared within collectors: (5 + 1) / 4
+    assert_eq!(merged.avg_edit_distance, 1.5);
+    assert_eq!(merged.distinct_paths, 3);
+    assert_eq!(
+        summary.prefixes.iter().filter(|p| p.heterogeneous).count(),
+        1
+    );
+}
@@ -0,0 +1,146 @@
+use ribeye::processors::{PeerAsnEntry, PeerInfoSummaryJson, PeerStatsProcessor, TypedProcessor};
+use ribeye::testing::SyntheticRibBuilder;
+use ribeye::{RibEye, RibMeta};
+
+#[test]
+fn test_peer_stats_approximate() {
+    let mut builder = SyntheticRibBuilder::new().peer("192.0.2.1", 65001);
+    for i in 0..2000u32 {
+        let prefix = format!("10.{}.{}.0/24", i / 256, i % 256);
+        builder = builder.route(prefix.as_str(), &[65001, 64496]);
+    }
+    let builder = builder
+        .peer("2001:db8::1", 65002)
+        .route("2001:db8:1::/48", &[65002, 64496])
+        .route("2001:db8:2::/48", &[65002, 64496]);
+
+    let rib_meta = RibMeta {
+        collector: "rrc00".to_string(),
+        ..Default::default()
+    };
+    let counts = |approximate: bool| {
+        let mut processor = PeerStatsProcessor::new("-").with_approximate(approximate);
+        processor.reset_processor(&rib_meta);
+        for elem in builder.elems() {
+            processor.process_entry(elem).unwrap();
+        }
+        let mut peers: Vec<_> = processor.to_output().unwrap().peers.into_iter().collect();
+        peers.sort_by_key(|p| p.asn);
+        peers
+            .into_iter()
+            .map(|p| (p.num_v4_pfxs, p.num_v6_pfxs, p.approximate))
+            .collect::<Vec<_>>()
+    };
+
+    assert_eq!(counts(false), vec![(2000, 0, false), (0, 2, false)]);
+    let approximate = counts(true);
+    assert!(approximate[0].0.abs_diff(2000) <= 20);
+    assert_eq!(approximate[1], (0, 2, true));
+}
+
+#[test]
+fn test_peer_stats_path_diversity() {
+    let rib_meta = RibMeta {
+        collector: "rrc00".to_string(),
+        ..Default::default()
+    };
+    let mut processor = PeerStatsProcessor::new("-");
+    processor.reset_processor(&rib_meta);
+    for elem in SyntheticRibBuilder::new()
+        .peer("192.0.2.1", 65001)
+        .route("1.1.1.0/24", &[65001, 174, 13335])
+        .route("1.0.0.0/24", &[65001, 174, 13335])
+        .route("8.8.8.0/24", &[65001, 3356, 15169])
+        .route("8.8.4.0/24", &[65001, 174, 15169])
+        .elems()
+    {
+        processor.process_entry(elem).unwrap();
+    }
+    let peer = processor
+        .to_output()
+        .unwrap()
+        .peers
+        .into_iter()
+        .next()
+        .unwrap();
+    assert_eq!(peer.num_v4_pfxs, 4);
+    assert_eq!(peer.num_origin_asns, 2);
+    // the peer's own ASN is the first hop of all paths
+    assert_eq!(peer.num_first_last_hops, 2);
+}
+
+#[test]
+fn test_peer_stats_asn_groups() {
+    let output_dir = tempfile::tempdir().unwrap();
+    let dir = output_dir.path().to_str().unwrap();
+    let collectors = [
+        (
+            "rrc00",
+            SyntheticRibBuilder::new()
+                .peer("192.0.2.1", 65001)
+                .route("1.1.1.0/24", &[65001, 13335])
+                .route("8.8.8.0/24", &[65001, 15169])
+                .peer("2001:db8::1", 65001)
+                .route("2606:4700::/32", &[65001, 13335]),
+        ),
+        (
+            "route-views2",
+            // the same router peers with both collectors: one session each
+            SyntheticRibBuilder::new()
+                .peer("192.0.2.1", 65001)
+                .route("1.1.1.0/24", &[65001, 13335])
+                .peer("198.51.100.2", 65002)
+                .route("8.8.8.0/24", &[65002, 15169]),
+        ),
+    ];
+    let mut rib_metas = vec![];
+    let mut ribeye = RibEye::new()
+        .with_processor_names(&vec!["peer-stats".to_string()], dir)
+        .unwrap();
+    for (collector, builder) in collectors {
+        let rib_meta = RibMeta {
+            collector: collector.to_string(),
+            ..Default::default()
+        };
+        ribeye = ribeye.with_rib_meta(&rib_meta);
+        ribeye.process_elems(builder.elems()).unwrap();
+        ribeye.output().unwrap();
+        rib_metas.push(rib_meta);
+    }
+    ribeye.summarize_latest_files(&rib_metas).unwrap();
+
+    let path = format!("{}/peer-stats/latest.json.bz2", dir);
+    let summary = PeerStatsProcessor::read_summary(path.as_str()).unwrap();
+    assert_eq!(summary.peers.len(), 4);
+    let sessions = summary
+        .peers
+        .iter()
+        .filter(|p| p.ip.to_string() == "192.0.2.1");
+    let mut collectors: Vec<_> = sessions.filter_map(|p| p.collector.clone()).collect();
+    collectors.sort();
+    assert_eq!(collectors, vec!["route-views2", "rrc00"]);
+    assert_eq!(
+        summary.asns,
+        vec![
+            PeerAsnEntry {
+                asn: 65001,
+                num_sessions: 3,
+                collectors: vec!["route-views2".to_string(), "rrc00".to_string()],
+                num_v4_pfxs: 3,
+                num_v6_pfxs: 1,
+            },
+            PeerAsnEntry {
+                asn: 65002,
+                num_sessions: 1,
+                collectors: vec!["route-views2".to_string()],
+                num_v4_pfxs: 1,
+                num_v6_pfxs: 0,
+            },
+        ]
+    );
+
+    // summaries written before the grouping read without it
+    let legacy: PeerInfoSummaryJson =
+        serde_json::from_str(r#"{"rib_dump_urls": [], "peers": []}"#).unwrap();
+    assert!(legacy.asns.is_empty());
+}
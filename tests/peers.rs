@@ -0,0 +1,103 @@
+use ribeye::processors::peers::{PeerId, PeerIndex, PeerTracker, PrefixPeerState};
+use ribeye::testing::SyntheticRibBuilder;
+use ribeye::util::ElemView;
+
+#[test]
+fn test_peer_tracker() {
+    let elems = SyntheticRibBuilder::new()
+        .peer("192.0.2.1", 65001)
+        .route("0.0.0.0/0", &[65001])
+        .route("1.1.1.0/24", &[65001, 13335])
+        .route("2606:4700::/32", &[65001, 13335])
+        .peer("192.0.2.2", 65002)
+        .route("1.1.1.0/24", &[65002, 13335])
+        .withdrawal("8.8.8.0/24")
+        .build();
+
+    let mut tracker = PeerTracker::new().with_full_feed_thresholds(2, 10);
+    tracker.set_collector(Some("rrc00".to_string()));
+    let ids: Vec<_> = elems.iter().map(|e| tracker.observe(e)).collect();
+    assert_eq!(tracker.len(), 2);
+    assert_eq!(ids[0], ids[2]);
+    assert_ne!(ids[0], ids[3]);
+
+    let first = tracker.get(ids[0]).unwrap();
+    assert_eq!(first.asn, 65001);
+    assert_eq!(first.v4_announcements, 2);
+    assert_eq!(first.v6_announcements, 1);
+    assert!(first.v4_default);
+    assert!(!first.v6_default);
+
+    let second = tracker.get(ids[3]).unwrap();
+    assert_eq!(second.withdrawals, 1);
+    assert_eq!(second.collector.as_deref(), Some("rrc00"));
+    assert_eq!(tracker.full_feed_peers(), vec![ids[0]]);
+
+    // the same peer IP at another collector is another peer
+    tracker.set_collector(Some("rrc01".to_string()));
+    assert!(tracker.get_by_ip(&"192.0.2.2".parse().unwrap()).is_none());
+    let other = tracker.observe(&elems[3]);
+    assert_ne!(other, ids[3]);
+    assert_eq!(
+        tracker.get(other).unwrap().collector.as_deref(),
+        Some("rrc01")
+    );
+    assert_eq!(
+        tracker.get(ids[3]).unwrap().collector.as_deref(),
+        Some("rrc00")
+    );
+    assert_eq!(tracker.len(), 3);
+    // and peers are found again once back at their collector
+    tracker.set_collector(Some("rrc00".to_string()));
+    assert_eq!(
+        tracker.get_by_ip(&"192.0.2.2".parse().unwrap()).unwrap().id,
+        ids[3]
+    );
+}
+
+#[test]
+fn test_prefix_peer_state() {
+    let prefix = "1.1.1.0/24".parse().unwrap();
+    let mut state = PrefixPeerState::new();
+    assert_eq!(state.insert(prefix, 0, "a"), None);
+    assert_eq!(state.insert(prefix, 1, "b"), None);
+    // a repeated entry of a peer replaces its value
+    assert_eq!(state.insert(prefix, 0, "c"), Some("a"));
+    assert_eq!(state.get(&prefix), &[(0, "c"), (1, "b")]);
+    assert!(state.get(&"8.8.8.0/24".parse().unwrap()).is_empty());
+    assert_eq!(state.len(), 1);
+    state.clear();
+    assert!(state.is_empty());
+}
+
+#[test]
+fn test_peer_index() {
+    let elems = SyntheticRibBuilder::new()
+        .peer("192.0.2.1", 65001)
+        .route("1.1.1.0/24", &[65001, 13335])
+        .peer("192.0.2.2", 65002)
+        .route("1.1.1.0/24", &[65002, 13335])
+        .build();
+
+    let mut index = PeerIndex::new();
+    index.reset("rrc00");
+    assert_eq!(index.index(&ElemView::new(&elems[0])), 0);
+    assert_eq!(index.index(&ElemView::new(&elems[1])), 1);
+    assert_eq!(index.index(&ElemView::new(&elems[0])), 0);
+
+    // the same peer IP at another collector is another peer
+    index.reset("rrc01");
+    let view = ElemView::new(&elems[0]).with_peer_id(PeerId(0));
+    assert_eq!(index.index(&view), 2);
+    // resolved by pipeline peer ID
+    assert_eq!(index.index(&view), 2);
+    assert_eq!(index.len(), 3);
+    assert_eq!(index.get(2), Some(("192.0.2.1".parse().unwrap(), 65001)));
+    assert_eq!(index.collector(0), Some("rrc00"));
+    assert_eq!(index.collector(2), Some("rrc01"));
+
+    // peer indexes are kept for later RIBs of a collector
+    index.reset("rrc00");
+    assert_eq!(index.index(&ElemView::new(&elems[1])), 1);
+    assert_eq!(index.collector(3), None);
+}
@@ -0,0 +1,176 @@
+use ribeye::processors::{aggregate_pfx2as, Prefix2AsCount, Prefix2AsProcessor, TypedProcessor};
+use ribeye::testing::SyntheticRibBuilder;
+use ribeye::RibMeta;
+
+fn entry(prefix: &str, asn: u32, count: usize) -> Prefix2AsCount {
+    Prefix2AsCount {
+        prefix: prefix.to_string(),
+        asn,
+        count,
+        peers: vec![],
+        collectors_count: None,
+        weighted_count: None,
+    }
+}
+
+fn aggregated(entries: Vec<Prefix2AsCount>) -> Vec<(String, u32, usize)> {
+    let mut res: Vec<_> = aggregate_pfx2as(entries)
+        .into_iter()
+        .map(|e| (e.prefix, e.asn, e.count))
+        .collect();
+    res.sort();
+    res
+}
+
+#[test]
+fn test_aggregate_covered_and_siblings() {
+    let res = aggregated(vec![
+        // covered by 10.0.0.0/8 with the same origin
+        entry("10.0.0.0/8", 64500, 3),
+        entry("10.1.0.0/16", 64500, 5),
+        // siblings, merged up to 192.0.2.0/24
+        entry("192.0.2.0/26", 64501, 1),
+        entry("192.0.2.64/26", 64501, 2),
+        entry("192.0.2.128/25", 64501, 1),
+        // same origins as a set
+        entry("2001:db8::/33", 64502, 1),
+        entry("2001:db8::/33", 64503, 1),
+        entry("2001:db8:8000::/33", 64503, 1),
+        entry("2001:db8:8000::/33", 64502, 4),
+    ]);
+    assert_eq!(
+        res,
+        vec![
+            ("10.0.0.0/8".to_string(), 64500, 5),
+            ("192.0.2.0/24".to_string(), 64501, 2),
+            ("2001:db8::/32".to_string(), 64502, 4),
+            ("2001:db8::/32".to_string(), 64503, 1),
+        ]
+    );
+}
+
+#[test]
+fn test_aggregate_keeps_lpm_results() {
+    let entries = vec![
+        // more-specific with a different origin is kept
+        entry("10.0.0.0/8", 64500, 1),
+        entry("10.1.0.0/16", 64501, 1),
+        // sibling parent is announced with another origin
+        entry("198.51.100.0/24", 64502, 1),
+        entry("198.51.100.0/25", 64503, 1),
+        entry("198.51.100.128/25", 64503, 1),
+        // siblings with different origin sets
+        entry("203.0.113.0/25", 64504, 1),
+        entry("203.0.113.128/25", 64504, 1),
+        entry("203.0.113.128/25", 64505, 1),
+        entry("invalid", 64506, 1),
+    ];
+    let mut expected: Vec<_> = entries
+        .iter()
+        .map(|e| (e.prefix.clone(), e.asn, e.count))
+        .collect();
+    expected.sort();
+    assert_eq!(aggregated(entries), expected);
+}
+
+#[test]
+fn test_pfx2as_peers() {
+    let mut processor = Prefix2AsProcessor::new("-").with_peers(true);
+    processor.reset_processor(&RibMeta {
+        collector: "rrc00".to_string(),
+        ..Default::default()
+    });
+    let elems = SyntheticRibBuilder::new()
+        .peer("192.0.2.1", 65001)
+        .route("1.1.1.0/24", &[65001, 174, 13335])
+        .peer("192.0.2.2", 65002)
+        .route("1.1.1.0/24", &[65002, 13335])
+        .peer("192.0.2.3", 65003)
+        .route("1.1.1.0/24", &[65003, 64496])
+        .build();
+    for elem in &elems {
+        processor.process_entry(elem).unwrap();
+    }
+
+    let mut pfx2as = processor.to_output().unwrap().pfx2as;
+    pfx2as.sort_by_key(|e| e.asn);
+    let peers = |e: &Prefix2AsCount| -> Vec<(String, u32)> {
+        e.peers.iter().map(|p| (p.ip.to_string(), p.asn)).collect()
+    };
+    assert_eq!(pfx2as[0].asn, 13335);
+    assert_eq!(pfx2as[0].count, 2);
+    assert_eq!(
+        peers(&pfx2as[0]),
+        vec![
+            ("192.0.2.1".to_string(), 65001),
+            ("192.0.2.2".to_string(), 65002)
+        ]
+    );
+    // the peer disagreeing about the origin
+    assert_eq!(pfx2as[1].asn, 64496);
+    assert_eq!(peers(&pfx2as[1]), vec![("192.0.2.3".to_string(), 65003)]);
+    assert_eq!(pfx2as[1].peers[0].collector.as_deref(), Some("rrc00"));
+
+    // peers are not recorded by default
+    let mut processor = Prefix2AsProcessor::new("-");
+    processor.reset_processor(&RibMeta::default());
+    processor.process_entry(&elems[0]).unwrap();
+    assert!(processor.to_output().unwrap().pfx2as[0].peers.is_empty());
+}
+
+#[test]
+fn test_pfx2as_weighted_summary() {
+    let collector_output = |collector: &str, builder: SyntheticRibBuilder| {
+        let rib_meta = RibMeta {
+            collector: collector.to_string(),
+            ..Default::default()
+        };
+        let mut processor = Prefix2AsProcessor::new("-");
+        processor.reset_processor(&rib_meta);
+        for elem in builder.elems() {
+            processor.process_entry(elem).unwrap();
+        }
+        (rib_meta, processor.to_output().unwrap())
+    };
+    let outputs = vec![
+        // many peers, all seeing 1.1.1.0/24
+        collector_output(
+            "rrc00",
+            SyntheticRibBuilder::new()
+                .peer("192.0.2.1", 65001)
+                .route("1.1.1.0/24", &[65001, 13335])
+                .peer("192.0.2.2", 65002)
+                .route("1.1.1.0/24", &[65002, 13335])
+                .peer("192.0.2.3", 65003)
+                .route("1.1.1.0/24", &[65003, 13335])
+                .peer("192.0.2.4", 65004)
+                .route("1.1.1.0/24", &[65004, 13335]),
+        ),
+        // a single peer seeing both prefixes
+        collector_output(
+            "rrc01",
+            SyntheticRibBuilder::new()
+                .peer("192.0.2.5", 65005)
+                .route("1.1.1.0/24", &[65005, 13335])
+                .route("8.8.8.0/24", &[65005, 15169]),
+        ),
+    ];
+    assert_eq!(outputs[0].1.peers_count, 4);
+    // per-collector outputs carry no weights
+    assert_eq!(outputs[0].1.pfx2as[0].weighted_count, None);
+
+    let rib_metas: Vec<RibMeta> = outputs.iter().map(|(m, _)| m.clone()).collect();
+    let mut summary = Prefix2AsProcessor::new("-")
+        .summarize(&rib_metas, outputs)
+        .unwrap()
+        .pfx2as;
+    summary.sort_by_key(|e| e.asn);
+    assert_eq!(summary[0].asn, 13335);
+    assert_eq!(summary[0].count, 5);
+    assert_eq!(summary[0].collectors_count, Some(2));
+    assert_eq!(summary[0].weighted_count, Some(2.0));
+    // seen by every peer of a collector with fewer peers: raw count of 1, weight of a full collector
+    assert_eq!(summary[1].count, 1);
+    assert_eq!(summary[1].collectors_count, Some(1));
+    assert_eq!(summary[1].weighted_count, Some(1.0));
+}
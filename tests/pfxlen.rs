@@ -0,0 +1,80 @@
+use ribeye::processors::{PrefixLenProcessor, TypedProcessor};
+use ribeye::testing::{collector_output, SyntheticRibBuilder};
+use ribeye::RibMeta;
+
+#[test]
+fn test_pfxlen_distribution() {
+    let (_, output) = collector_output(
+        PrefixLenProcessor::new("-"),
+        "rrc00",
+        &SyntheticRibBuilder::new()
+            .peer("192.0.2.1", 65001)
+            .route("1.1.1.0/24", &[65001, 13335])
+            .route("192.0.2.128/25", &[65001, 64496])
+            .route("2001:db8:1::/48", &[65001, 64496])
+            .route("2001:db8:2::/56", &[65001, 64496])
+            .peer("192.0.2.2", 65002)
+            // same prefix, different origin: counted once overall
+            .route("1.1.1.0/24", &[65002, 64497])
+            .build(),
+    );
+    assert_eq!(output.lengths.v4.get(&24), Some(&1));
+    assert_eq!(output.lengths.v4.get(&25), Some(&1));
+    assert_eq!(output.lengths.v4_too_long(), 1);
+    assert_eq!(output.lengths.v6_too_long(), 1);
+
+    let origin = output.origins.iter().find(|o| o.asn == 64496).unwrap();
+    assert_eq!(origin.v4_too_long, 1);
+    assert_eq!(origin.v6_too_long, 1);
+    assert_eq!(origin.lengths.v6.get(&48), Some(&1));
+
+    let mut too_long: Vec<String> = output
+        .too_long
+        .iter()
+        .map(|p| format!("{} {}", p.prefix, p.asn))
+        .collect();
+    too_long.sort();
+    assert_eq!(
+        too_long,
+        vec!["192.0.2.128/25 64496", "2001:db8:2::/56 64496"]
+    );
+}
+
+#[test]
+fn test_pfxlen_summary() {
+    let outputs = vec![
+        collector_output(
+            PrefixLenProcessor::new("-"),
+            "rrc00",
+            &SyntheticRibBuilder::new()
+                .peer("192.0.2.1", 65001)
+                .route("1.1.1.0/24", &[65001, 13335])
+                .route("192.0.2.128/25", &[65001, 64496])
+                .build(),
+        ),
+        collector_output(
+            PrefixLenProcessor::new("-"),
+            "rrc01",
+            &SyntheticRibBuilder::new()
+                .peer("192.0.2.2", 65002)
+                .route("1.1.1.0/24", &[65002, 13335])
+                .route("1.0.0.0/24", &[65002, 13335])
+                .route("192.0.2.192/26", &[65002, 64496])
+                .build(),
+        ),
+    ];
+    let rib_metas: Vec<RibMeta> = outputs.iter().map(|(m, _)| m.clone()).collect();
+    let summary = PrefixLenProcessor::new("-")
+        .summarize(&rib_metas, outputs)
+        .unwrap();
+
+    // largest count among collectors
+    assert_eq!(summary.lengths.v4.get(&24), Some(&2));
+    let origin = summary.origins.iter().find(|o| o.asn == 13335).unwrap();
+    assert_eq!(origin.lengths.v4.get(&24), Some(&2));
+    // a /25 at one collector and a /26 at the other
+    let origin = summary.origins.iter().find(|o| o.asn == 64496).unwrap();
+    assert_eq!(origin.v4_too_long, 2);
+    // flagged prefixes of all collectors
+    assert_eq!(summary.too_long.len(), 2);
+}
@@ -0,0 +1,79 @@
+use ribeye::processors::{PrefixScopeProcessor, TypedProcessor, SCOPED_VISIBILITY};
+use ribeye::testing::{collector_output, SyntheticRibBuilder};
+use ribeye::util::PrefixCountries;
+use ribeye::RibMeta;
+use std::collections::BTreeSet;
+use std::sync::Arc;
+
+const DELEGATED: &str = "2|ripencc|20240101|5|19830705|20240101|+0100
+ripencc|DE|ipv4|192.0.2.0|128|20100101|allocated|abc
+ripencc|FR|ipv4|192.0.2.128|128|20100101|assigned|abc
+";
+
+/// Ten peers in Germany and one in France all see 1.1.1.0/24; only the French peer sees
+/// 1.1.0.0/24.
+fn builder() -> SyntheticRibBuilder {
+    let mut builder = SyntheticRibBuilder::new();
+    for i in 1..=10 {
+        builder = builder
+            .peer(format!("192.0.2.{}", i).as_str(), 65000 + i)
+            .route("1.1.1.0/24", &[65000 + i, 13335]);
+    }
+    builder
+        .peer("192.0.2.129", 65100)
+        .route("0.0.0.0/0", &[65100])
+        .route("1.1.1.0/24", &[65100, 13335])
+        .route("1.1.0.0/24", &[65100, 13335])
+}
+
+fn processor() -> PrefixScopeProcessor {
+    let mut countries = PrefixCountries::new();
+    countries.add_delegated(DELEGATED).unwrap();
+    PrefixScopeProcessor::new("-").with_prefix_countries(Arc::new(countries))
+}
+
+#[test]
+fn test_prefix_scope() {
+    let (_, output) = collector_output(processor(), "rrc00", builder().elems());
+    assert_eq!(output.peers_count, 11);
+    assert_eq!(
+        output.countries,
+        BTreeSet::from(["DE".to_string(), "FR".to_string()])
+    );
+    // default routes are skipped
+    assert_eq!(output.prefixes.len(), 2);
+
+    let scoped = &output.prefixes[0];
+    assert_eq!(scoped.prefix.to_string(), "1.1.0.0/24");
+    assert_eq!(scoped.peers_count, 1);
+    assert_eq!(scoped.countries, BTreeSet::from(["FR".to_string()]));
+    assert!(scoped.visibility <= SCOPED_VISIBILITY);
+    assert!(scoped.scoped);
+
+    let global = &output.prefixes[1];
+    assert_eq!(global.peers_count, 11);
+    assert_eq!(global.visibility, 1.0);
+    assert!(!global.scoped);
+}
+
+#[test]
+fn test_prefix_scope_summary() {
+    let outputs = vec![
+        collector_output(processor(), "rrc00", builder().elems()),
+        collector_output(processor(), "rrc01", builder().elems()),
+    ];
+    let rib_metas: Vec<RibMeta> = outputs.iter().map(|(m, _)| m.clone()).collect();
+    let summary = PrefixScopeProcessor::new("-")
+        .summarize(&rib_metas, outputs)
+        .unwrap();
+    assert_eq!(summary.peers_count, 22);
+    assert_eq!(summary.prefixes.len(), 2);
+    let scoped = &summary.prefixes[0];
+    assert_eq!(
+        scoped.collectors,
+        BTreeSet::from(["rrc00".to_string(), "rrc01".to_string()])
+    );
+    assert_eq!(scoped.peers_count, 2);
+    assert!(scoped.scoped);
+    assert_eq!(summary.prefixes[1].visibility, 1.0);
+}
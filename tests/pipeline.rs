@@ -0,0 +1,125 @@
+use ribeye::pipeline::{JobId, JobPool, JobStatus};
+use ribeye::testing::SyntheticRibBuilder;
+use ribeye::{RibEye, RibMeta};
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+/// Write a synthetic RIB dump per collector and return their meta information.
+fn rib_metas(dir: &str, collectors: &[&str]) -> Vec<RibMeta> {
+    collectors
+        .iter()
+        .map(|collector| {
+            let path = format!("{}/{}.rib.bz2", dir, collector);
+            SyntheticRibBuilder::new()
+                .generated(2, 50)
+                .write_mrt_file(path.as_str())
+                .unwrap();
+            RibMeta {
+                collector: collector.to_string(),
+                rib_dump_url: path,
+                ..Default::default()
+            }
+        })
+        .collect()
+}
+
+fn pfx2as(output_dir: &str) -> impl Fn(&RibMeta) -> anyhow::Result<RibEye> + Sync + '_ {
+    move |_| RibEye::new().with_processor_names(&vec!["pfx2as".to_string()], output_dir)
+}
+
+fn latest_output(output_dir: &str, collector: &str) -> bool {
+    Path::new(format!("{}/pfx2as/{}/latest.json.bz2", output_dir, collector).as_str()).exists()
+}
+
+type Events = Arc<Mutex<Vec<(JobId, JobStatus)>>>;
+
+fn statuses(events: &Events, id: JobId) -> Vec<JobStatus> {
+    events
+        .lock()
+        .unwrap()
+        .iter()
+        .filter(|(job, _)| *job == id)
+        .map(|(_, status)| status.clone())
+        .collect()
+}
+
+#[test]
+fn test_job_statuses() {
+    let input_dir = tempfile::tempdir().unwrap();
+    let output_dir = tempfile::tempdir().unwrap();
+    let output = output_dir.path().to_str().unwrap();
+    let events: Events = Arc::new(Mutex::new(vec![]));
+    let recorded = events.clone();
+    let mut pool = JobPool::new(Some(2))
+        .unwrap()
+        .with_status_callback(move |job, status| {
+            recorded.lock().unwrap().push((job.id(), status.clone()));
+        });
+    for rib_meta in rib_metas(input_dir.path().to_str().unwrap(), &["rrc00", "rrc01"]) {
+        pool.submit(rib_meta);
+    }
+    let missing = pool.submit(RibMeta {
+        collector: "rrc02".to_string(),
+        rib_dump_url: format!("{}/missing.rib.bz2", input_dir.path().display()),
+        ..Default::default()
+    });
+
+    let results = pool.run(pfx2as(output));
+    assert_eq!(results.len(), 3);
+    for id in 0..2 {
+        assert_eq!(
+            statuses(&events, id),
+            vec![
+                JobStatus::Queued,
+                JobStatus::Downloading,
+                JobStatus::Processing,
+                JobStatus::Output,
+                JobStatus::Done
+            ]
+        );
+        let result = results.iter().find(|r| r.id == id).unwrap();
+        assert!(result.result.as_ref().unwrap().announcements > 0);
+    }
+    assert!(latest_output(output, "rrc00") && latest_output(output, "rrc01"));
+    assert!(matches!(missing.status(), JobStatus::Failed(_)));
+    assert!(missing.status().is_finished());
+
+    // finished jobs are not run again
+    assert!(pool.run(pfx2as(output)).is_empty());
+}
+
+#[test]
+fn test_cancel_jobs() {
+    let input_dir = tempfile::tempdir().unwrap();
+    let output_dir = tempfile::tempdir().unwrap();
+    let output = output_dir.path().to_str().unwrap();
+    // the second job cancels itself once it starts processing
+    let mut pool = JobPool::new(Some(1))
+        .unwrap()
+        .with_status_callback(|job, status| {
+            if job.id() == 1 && *status == JobStatus::Processing {
+                job.cancel();
+            }
+        });
+    let collectors = ["rrc00", "rrc01", "rrc02"];
+    for rib_meta in rib_metas(input_dir.path().to_str().unwrap(), &collectors) {
+        pool.submit(rib_meta);
+    }
+    assert!(pool.cancel(0));
+    assert!(!pool.cancel(3));
+
+    let results = pool.run(pfx2as(output));
+    let statuses: Vec<JobStatus> = pool.jobs().iter().map(|job| job.status()).collect();
+    assert_eq!(
+        statuses,
+        vec![JobStatus::Cancelled, JobStatus::Cancelled, JobStatus::Done]
+    );
+    assert!(results
+        .iter()
+        .filter(|r| r.id < 2)
+        .all(|r| r.result.is_err()));
+    // cancelled jobs write no outputs
+    assert!(!latest_output(output, "rrc00"));
+    assert!(!latest_output(output, "rrc01"));
+    assert!(latest_output(output, "rrc02"));
+}
@@ -0,0 +1,117 @@
+use ribeye::processors::proto::{encode_summary, ProtoMessage, PROTO_SCHEMA};
+use ribeye::processors::{
+    As2relEntry, As2relSummaryJson, FileSink, OutputSink, Prefix2AsCount, Prefix2AsPeer,
+    Prefix2AsSummaryJson, SummaryWriter,
+};
+use ribeye::testing::SyntheticRibBuilder;
+use ribeye::{RibEye, RibMeta};
+use serde_json::json;
+
+#[test]
+fn test_as2rel_entry_encoding() {
+    let entry = As2relEntry {
+        asn1: 174,
+        asn2: 13335,
+        paths_count: 3,
+        peers_count: 2,
+        rel: 1,
+        ixp: false,
+        reverse_paths_count: 0,
+        confidence: 0.5,
+    };
+    let data = entry.encode_to_vec();
+    // default values (`ixp`, `reverse_paths_count`) are omitted as in any proto3 encoder
+    assert_eq!(
+        data,
+        vec![
+            0x08, 0xae, 0x01, // asn1 = 174
+            0x10, 0x97, 0x68, // asn2 = 13335
+            0x18, 0x03, // paths_count = 3
+            0x20, 0x02, // peers_count = 2
+            0x28, 0x01, // rel = 1
+            0x41, 0, 0, 0, 0, 0, 0, 0xe0, 0x3f, // confidence = 0.5
+        ]
+    );
+    let decoded = As2relEntry::decode(&data).unwrap();
+    assert_eq!((decoded.asn1, decoded.asn2, decoded.rel), (174, 13335, 1));
+    assert_eq!(decoded.confidence, 0.5);
+    assert!(As2relEntry::decode(&data[..data.len() - 1]).is_err());
+}
+
+#[test]
+fn test_pfx2as_round_trip() {
+    let summary = Prefix2AsSummaryJson {
+        rib_dump_urls: vec!["https://example.com/rib.bz2".to_string()],
+        pfx2as: vec![Prefix2AsCount {
+            prefix: "2606:4700::/32".to_string(),
+            asn: 4200000001,
+            count: 0,
+            peers: vec![Prefix2AsPeer {
+                collector: Some("rrc00".to_string()),
+                ip: "192.0.2.1".parse().unwrap(),
+                asn: 65001,
+            }],
+            collectors_count: Some(0),
+            weighted_count: None,
+        }],
+        aggregated: true,
+    };
+    let decoded = Prefix2AsSummaryJson::decode(&summary.encode_to_vec()).unwrap();
+    assert_eq!(
+        serde_json::to_value(&decoded).unwrap(),
+        serde_json::to_value(&summary).unwrap()
+    );
+    assert!(PROTO_SCHEMA.contains("message Pfx2AsSummary"));
+    // other summaries have no binary encoding
+    assert!(encode_summary("peer-stats", &json!({})).unwrap().is_none());
+}
+
+#[test]
+fn test_summary_protobuf() {
+    let output_dir = tempfile::tempdir().unwrap();
+    let dir = output_dir.path().to_str().unwrap();
+    let names = vec![
+        "pfx2as".to_string(),
+        "as2rel".to_string(),
+        "peer-stats".to_string(),
+    ];
+    let rib_metas = vec![RibMeta {
+        collector: "rrc00".to_string(),
+        ..Default::default()
+    }];
+    let mut ribeye = RibEye::new()
+        .with_processor_names(&names, dir)
+        .unwrap()
+        .with_summary_writer(SummaryWriter::new().with_protobuf(true))
+        .with_rib_meta(&rib_metas[0]);
+    ribeye
+        .process_elems(SyntheticRibBuilder::new().generated(2, 3).elems())
+        .unwrap();
+    ribeye.output().unwrap();
+    ribeye.summarize_latest_files(&rib_metas).unwrap();
+
+    let read = |processor: &str| {
+        let json = FileSink
+            .read_json(format!("{}/{}/latest.json.bz2", dir, processor).as_str())
+            .unwrap();
+        let data = std::fs::read(format!("{}/{}/latest.pb", dir, processor)).unwrap();
+        (json, data)
+    };
+    let (json, data) = read("pfx2as");
+    let decoded = Prefix2AsSummaryJson::decode(&data).unwrap();
+    assert_eq!(decoded.pfx2as.len(), 3);
+    assert_eq!(
+        serde_json::to_value(&decoded.pfx2as).unwrap(),
+        json["pfx2as"]
+    );
+    assert!(data.len() < json.to_string().len());
+
+    let (json, data) = read("as2rel");
+    let decoded = As2relSummaryJson::decode(&data).unwrap();
+    assert_eq!(
+        serde_json::to_value(&decoded.as2rel).unwrap(),
+        json["as2rel"]
+    );
+
+    assert!(!std::path::Path::new(format!("{}/peer-stats/latest.pb", dir).as_str()).exists());
+}
@@ -0,0 +1,148 @@
+use ribeye::processors::merge::merge_outputs;
+use ribeye::processors::readers::read_reachability_output;
+use ribeye::processors::{ReachabilityProcessor, ReachabilityRow, TypedProcessor};
+use ribeye::testing::{collector_output, SyntheticRibBuilder};
+use ribeye::util::ElemView;
+use ribeye::{RibEye, RibMeta};
+
+fn builder() -> SyntheticRibBuilder {
+    SyntheticRibBuilder::new()
+        .peer("192.0.2.2", 65002)
+        .route("1.1.1.0/24", &[65002, 3356, 13335])
+        .route("1.0.0.0/24", &[65002, 3356, 13335])
+        .peer("192.0.2.1", 65001)
+        .route("1.1.1.0/24", &[65001, 174, 13335])
+        .route("8.8.8.0/24", &[65001, 15169])
+        .withdrawal("9.9.9.0/24")
+}
+
+#[test]
+fn test_reachability_columns() {
+    let (_, output) = collector_output(ReachabilityProcessor::new("-"), "rrc00", builder().elems());
+    assert_eq!(output.collectors, vec!["rrc00".to_string()]);
+    // peers are sorted by IP
+    assert_eq!(output.peers.asn, vec![65001, 65002]);
+    assert_eq!(output.peers.collector, vec![0, 0]);
+    assert_eq!(output.edges.peer, vec![0, 0, 1]);
+    assert_eq!(output.edges.origin, vec![13335, 15169, 13335]);
+    assert_eq!(output.edges.prefixes, vec![1, 1, 2]);
+}
+
+#[test]
+fn test_reachability_summary() {
+    let outputs = vec![
+        collector_output(ReachabilityProcessor::new("-"), "rrc00", builder().elems()),
+        collector_output(
+            ReachabilityProcessor::new("-"),
+            "route-views2",
+            builder().elems(),
+        ),
+    ];
+    let rib_metas: Vec<RibMeta> = outputs.iter().map(|(m, _)| m.clone()).collect();
+    let summary = merge_outputs(&rib_metas, outputs.into_iter().map(|(_, o)| o));
+    assert_eq!(
+        summary.collectors,
+        vec!["route-views2".to_string(), "rrc00".to_string()]
+    );
+    assert_eq!(summary.peers.collector, vec![0, 0, 1, 1]);
+    let rows = summary.rows();
+    assert_eq!(rows.len(), 6);
+    assert_eq!(
+        rows[2],
+        ReachabilityRow {
+            collector: "route-views2".to_string(),
+            peer_ip: "192.0.2.2".parse().unwrap(),
+            peer_asn: 65002,
+            origin: 13335,
+            prefixes: 2,
+        }
+    );
+}
+
+#[test]
+fn test_pipeline_peer_ids() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let dir = temp_dir.path().to_str().unwrap();
+    let elems = builder().build();
+    let mut ribeye = RibEye::new()
+        .with_processor_names(&vec!["reachability".to_string()], dir)
+        .unwrap()
+        .with_rib_meta(&RibMeta {
+            collector: "rrc00".to_string(),
+            ..Default::default()
+        });
+    ribeye.process_elems(&elems).unwrap();
+    ribeye.output().unwrap();
+    let output =
+        read_reachability_output(&format!("{}/reachability/rrc00/latest.json.bz2", dir)).unwrap();
+    let (_, expected) =
+        collector_output(ReachabilityProcessor::new("-"), "rrc00", builder().elems());
+    assert_eq!(output.peers, expected.peers);
+    assert_eq!(output.edges, expected.edges);
+
+    // views created outside of a pipeline carry no peer ID
+    assert_eq!(ElemView::new(&elems[0]).peer_id(), None);
+}
+
+#[test]
+fn test_peer_ids_across_ribs() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let dir = temp_dir.path().to_str().unwrap();
+    let rib_meta = RibMeta {
+        collector: "rrc00".to_string(),
+        ..Default::default()
+    };
+    let mut ribeye = RibEye::new()
+        .with_processor_names(&vec!["reachability".to_string()], dir)
+        .unwrap()
+        .with_rib_meta(&rib_meta);
+    let first = SyntheticRibBuilder::new()
+        .peer("192.0.2.1", 65001)
+        .route("1.1.1.0/24", &[65001, 13335])
+        .build();
+    ribeye.process_elems(&first).unwrap();
+
+    // the next RIB's first peer gets the same pipeline peer ID
+    ribeye.initialize_processors(&rib_meta).unwrap();
+    let second = SyntheticRibBuilder::new()
+        .peer("192.0.2.2", 65002)
+        .route("8.8.8.0/24", &[65002, 15169])
+        .build();
+    ribeye.process_elems(&second).unwrap();
+    ribeye.output().unwrap();
+
+    let output =
+        read_reachability_output(&format!("{}/reachability/rrc00/latest.json.bz2", dir)).unwrap();
+    let rows: Vec<(u32, u32)> = output
+        .edges
+        .peer
+        .iter()
+        .zip(&output.edges.origin)
+        .map(|(peer, origin)| (output.peers.asn[*peer as usize], *origin))
+        .collect();
+    assert_eq!(rows, vec![(65001, 13335), (65002, 15169)]);
+}
+
+#[test]
+fn test_peers_across_collectors() {
+    // RIBs of two collectors through the same processor, e.g. with `ribeye files --merge`
+    let elems = SyntheticRibBuilder::new()
+        .peer("192.0.2.1", 65001)
+        .route("1.1.1.0/24", &[65001, 13335])
+        .build();
+    let mut processor = ReachabilityProcessor::new("-");
+    for collector in ["rrc00", "rrc01"] {
+        let rib_meta = RibMeta {
+            collector: collector.to_string(),
+            ..Default::default()
+        };
+        TypedProcessor::reset_processor(&mut processor, &rib_meta);
+        for elem in &elems {
+            TypedProcessor::process_entry(&mut processor, elem).unwrap();
+        }
+    }
+    let output = processor.to_output().unwrap();
+    assert_eq!(output.collectors, vec!["rrc00", "rrc01"]);
+    assert_eq!(output.peers.collector, vec![0, 1]);
+    assert_eq!(output.edges.prefixes, vec![1, 1]);
+}
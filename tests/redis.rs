@@ -0,0 +1,238 @@
+use ribeye::processors::{As2relEntry, As2relSummaryJson, Prefix2AsCount, Prefix2AsSummaryJson};
+use ribeye::redis::{as2rel_commands, pfx2as_commands, RedisExporter};
+use ribeye::testing::SyntheticRibBuilder;
+use ribeye::{RibEye, RibMeta};
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::{Arc, Mutex};
+
+type Commands = Arc<Mutex<Vec<Vec<String>>>>;
+
+/// Minimal Redis server recording the commands it receives; sets are kept for `SMEMBERS`.
+fn fake_redis() -> (String, Commands) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("redis://{}", listener.local_addr().unwrap());
+    let commands: Commands = Arc::new(Mutex::new(vec![]));
+    let sets: Arc<Mutex<HashMap<String, Vec<String>>>> = Arc::new(Mutex::new(HashMap::new()));
+    let recorded = commands.clone();
+    std::thread::spawn(move || {
+        for stream in listener.incoming() {
+            let (commands, sets) = (recorded.clone(), sets.clone());
+            std::thread::spawn(move || serve(stream.unwrap(), commands, sets));
+        }
+    });
+    (url, commands)
+}
+
+fn serve(stream: TcpStream, commands: Commands, sets: Arc<Mutex<HashMap<String, Vec<String>>>>) {
+    let mut reader = BufReader::new(stream.try_clone().unwrap());
+    let mut writer = stream;
+    let mut queued = 0;
+    loop {
+        let mut line = String::new();
+        if reader.read_line(&mut line).unwrap_or(0) == 0 {
+            return;
+        }
+        let argc: usize = line.trim()[1..].parse().unwrap();
+        let mut command = vec![];
+        for _ in 0..argc {
+            let mut len = String::new();
+            reader.read_line(&mut len).unwrap();
+            let len: usize = len.trim()[1..].parse().unwrap();
+            let mut arg = vec![0; len + 2];
+            reader.read_exact(&mut arg).unwrap();
+            command.push(String::from_utf8(arg[..len].to_vec()).unwrap());
+        }
+        let reply = match command[0].as_str() {
+            "MULTI" => {
+                queued = 1;
+                "+OK\r\n".to_string()
+            }
+            "EXEC" => {
+                let reply = format!("*{}\r\n{}", queued - 1, ":1\r\n".repeat(queued - 1));
+                queued = 0;
+                reply
+            }
+            "SMEMBERS" => {
+                let members = sets
+                    .lock()
+                    .unwrap()
+                    .get(&command[1])
+                    .cloned()
+                    .unwrap_or_default();
+                let mut reply = format!("*{}\r\n", members.len());
+                for m in members {
+                    reply.push_str(format!("${}\r\n{}\r\n", m.len(), m).as_str());
+                }
+                reply
+            }
+            _ if queued > 0 => {
+                queued += 1;
+                if command[0] == "SADD" {
+                    let mut sets = sets.lock().unwrap();
+                    let set = sets.entry(command[1].clone()).or_default();
+                    set.extend(command[2..].iter().cloned());
+                }
+                if command[0] == "DEL" {
+                    let mut sets = sets.lock().unwrap();
+                    for key in &command[1..] {
+                        sets.remove(key);
+                    }
+                }
+                "+QUEUED\r\n".to_string()
+            }
+            _ => "+OK\r\n".to_string(),
+        };
+        commands.lock().unwrap().push(command);
+        writer.write_all(reply.as_bytes()).unwrap();
+    }
+}
+
+fn pfx2as_entry(prefix: &str, asn: u32, count: usize) -> Prefix2AsCount {
+    Prefix2AsCount {
+        prefix: prefix.to_string(),
+        asn,
+        count,
+        peers: vec![],
+        collectors_count: None,
+        weighted_count: None,
+    }
+}
+
+fn as2rel_entry(asn1: u32, asn2: u32, rel: u8, paths_count: usize) -> As2relEntry {
+    As2relEntry {
+        asn1,
+        asn2,
+        paths_count,
+        peers_count: 1,
+        rel,
+        ixp: false,
+        reverse_paths_count: 0,
+        confidence: 0.0,
+    }
+}
+
+fn strings(args: &[&str]) -> Vec<String> {
+    args.iter().map(|a| a.to_string()).collect()
+}
+
+#[test]
+fn test_pfx2as_commands() {
+    let summary = Prefix2AsSummaryJson {
+        rib_dump_urls: vec![],
+        pfx2as: vec![
+            pfx2as_entry("1.1.1.0/24", 13335, 10),
+            pfx2as_entry("8.8.8.0/24", 15169, 5),
+            pfx2as_entry("1.1.1.0/24", 4826, 30),
+        ],
+        aggregated: false,
+    };
+    assert_eq!(
+        pfx2as_commands("ribeye", &summary),
+        vec![
+            strings(&["DEL", "ribeye:pfx2as"]),
+            strings(&[
+                "HSET",
+                "ribeye:pfx2as",
+                "1.1.1.0/24",
+                "4826,13335",
+                "8.8.8.0/24",
+                "15169"
+            ]),
+        ]
+    );
+}
+
+#[test]
+fn test_as2rel_commands() {
+    let summary = As2relSummaryJson {
+        rib_dump_urls: vec![],
+        as2rel: vec![
+            as2rel_entry(174, 13335, 1, 4),
+            as2rel_entry(13335, 6939, 0, 2),
+            as2rel_entry(6939, 13335, 0, 3),
+        ],
+    };
+    let stale = strings(&["rel:as2rel:1:neighbors"]);
+    assert_eq!(
+        as2rel_commands("rel", &summary, &stale),
+        vec![
+            strings(&["DEL", "rel:as2rel:1:neighbors"]),
+            strings(&["DEL", "rel:as2rel:keys"]),
+            strings(&["ZADD", "rel:as2rel:13335:neighbors", "5", "6939"]),
+            strings(&["ZADD", "rel:as2rel:13335:providers", "4", "174"]),
+            strings(&["ZADD", "rel:as2rel:174:customers", "4", "13335"]),
+            strings(&["ZADD", "rel:as2rel:6939:neighbors", "5", "13335"]),
+            strings(&[
+                "SADD",
+                "rel:as2rel:keys",
+                "rel:as2rel:13335:neighbors",
+                "rel:as2rel:13335:providers",
+                "rel:as2rel:174:customers",
+                "rel:as2rel:6939:neighbors"
+            ]),
+        ]
+    );
+}
+
+#[test]
+fn test_export_after_summarize() {
+    let (url, commands) = fake_redis();
+    let output_dir = tempfile::tempdir().unwrap();
+    let dir = output_dir.path().to_str().unwrap();
+    let names = vec![
+        "pfx2as".to_string(),
+        "as2rel".to_string(),
+        "peer-stats".to_string(),
+    ];
+    let rib_metas = vec![RibMeta {
+        collector: "rrc00".to_string(),
+        ..Default::default()
+    }];
+    let mut ribeye = RibEye::new()
+        .with_processor_names(&names, dir)
+        .unwrap()
+        .with_redis(RedisExporter::new(format!("{}/2", url).as_str()))
+        .with_rib_meta(&rib_metas[0]);
+    ribeye
+        .process_elems(SyntheticRibBuilder::new().generated(2, 3).elems())
+        .unwrap();
+    ribeye.output().unwrap();
+    ribeye.summarize_latest_files(&rib_metas).unwrap();
+    ribeye.summarize_latest_files(&rib_metas).unwrap();
+
+    let commands = commands.lock().unwrap().clone();
+    let names: Vec<&str> = commands.iter().map(|c| c[0].as_str()).collect();
+    assert!(names.contains(&"SELECT"));
+    assert!(names.iter().filter(|n| **n == "EXEC").count() == 4);
+    let hset = commands.iter().find(|c| c[0] == "HSET").unwrap();
+    assert_eq!(hset[1], "ribeye:pfx2as");
+    assert_eq!(hset.len(), 2 + 3 * 2);
+    // the second export removes the keys of the first one before writing them again
+    let second = commands.iter().rposition(|c| c[0] == "SMEMBERS").unwrap();
+    assert!(commands[second..]
+        .iter()
+        .any(|c| c[0] == "DEL" && c.len() > 2 && c[1].starts_with("ribeye:as2rel:")));
+}
+
+#[test]
+fn test_export_failure_does_not_fail_summarize() {
+    let output_dir = tempfile::tempdir().unwrap();
+    let dir = output_dir.path().to_str().unwrap();
+    let rib_metas = vec![RibMeta {
+        collector: "rrc00".to_string(),
+        ..Default::default()
+    }];
+    // nothing listens on port 1
+    let mut ribeye = RibEye::new()
+        .with_processor_names(&vec!["pfx2as".to_string()], dir)
+        .unwrap()
+        .with_redis(RedisExporter::new("redis://127.0.0.1:1"))
+        .with_rib_meta(&rib_metas[0]);
+    ribeye
+        .process_elems(SyntheticRibBuilder::new().generated(1, 2).elems())
+        .unwrap();
+    ribeye.output().unwrap();
+    ribeye.summarize_latest_files(&rib_metas).unwrap();
+}
@@ -0,0 +1,160 @@
+use ribeye::processors::{FileSink, OutputSink, ProcessorConfig, ProcessorConfigFile};
+use ribeye::testing::SyntheticRibBuilder;
+use ribeye::util::{
+    is_bogon_prefix, is_reserved_asn, ElemView, RoutePolicy, RouteSkipCounts, SkipReason,
+};
+use ribeye::{RibEye, RibMeta};
+use serde_json::Value;
+
+fn rib() -> SyntheticRibBuilder {
+    SyntheticRibBuilder::new()
+        .peer("192.0.2.1", 3333)
+        .route("0.0.0.0/0", &[3333])
+        .route("1.1.1.0/24", &[3333, 174, 13335])
+        .route("10.0.0.0/16", &[3333, 64496])
+        .route("8.8.8.128/25", &[3333, 3356, 15169])
+        .route("9.9.9.0/24", &[3333, 4200000001, 19281])
+}
+
+/// Process the RIB with the given pipeline, returning the pfx2as and defaults outputs.
+fn process(dir: &str, ribeye: RibEye) -> (Value, Value) {
+    let rib_meta = RibMeta {
+        collector: "rrc00".to_string(),
+        ..Default::default()
+    };
+    let mut ribeye = ribeye.with_rib_meta(&rib_meta);
+    ribeye.process_elems(rib().elems()).unwrap();
+    ribeye.output().unwrap();
+    let read = |name: &str| {
+        FileSink
+            .read_json(format!("{}/{}/rrc00/latest.json.bz2", dir, name).as_str())
+            .unwrap()
+    };
+    (read("pfx2as"), read("defaults"))
+}
+
+fn prefixes(pfx2as: &Value) -> Vec<String> {
+    let mut prefixes: Vec<String> = pfx2as["pfx2as"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|e| e["prefix"].as_str().unwrap().to_string())
+        .collect();
+    prefixes.sort();
+    prefixes
+}
+
+fn processor_names() -> Vec<String> {
+    vec!["pfx2as".to_string(), "defaults".to_string()]
+}
+
+#[test]
+fn test_bogons_and_reserved_asns() {
+    for bogon in [
+        "10.1.0.0/16",
+        "192.168.1.0/24",
+        "240.0.0.0/8",
+        "fe80::/64",
+        "2001:db8::/48",
+    ] {
+        assert!(is_bogon_prefix(&bogon.parse().unwrap()), "{}", bogon);
+    }
+    for routed in [
+        "0.0.0.0/0",
+        "::/0",
+        "1.1.1.0/24",
+        "11.0.0.0/8",
+        "2606:4700::/32",
+    ] {
+        assert!(!is_bogon_prefix(&routed.parse().unwrap()), "{}", routed);
+    }
+    for reserved in [
+        0,
+        23456,
+        64496,
+        64512,
+        65535,
+        65551,
+        100000,
+        4200000000,
+        u32::MAX,
+    ] {
+        assert!(is_reserved_asn(reserved), "{}", reserved);
+    }
+    for public in [174, 13335, 64495, 131072, 4199999999] {
+        assert!(!is_reserved_asn(public), "{}", public);
+    }
+}
+
+#[test]
+fn test_skip_reasons() {
+    let elems = rib().build();
+    let policy = RoutePolicy::new()
+        .with_skip_default(true)
+        .with_skip_bogons(true)
+        .with_skip_reserved_asns(true)
+        .with_max_prefix_len(Some(24), None);
+    let reasons: Vec<Option<SkipReason>> = elems
+        .iter()
+        .map(|elem| policy.skip_reason(&ElemView::new(elem)))
+        .collect();
+    assert_eq!(
+        reasons,
+        vec![
+            Some(SkipReason::DefaultRoute),
+            None,
+            Some(SkipReason::Bogon),
+            Some(SkipReason::PrefixLength),
+            Some(SkipReason::ReservedAsn),
+        ]
+    );
+    assert!(RoutePolicy::default().is_noop());
+    assert!(elems.iter().all(|elem| RoutePolicy::default()
+        .skip_reason(&ElemView::new(elem))
+        .is_none()));
+}
+
+#[test]
+fn test_processor_policies() {
+    let output_dir = tempfile::tempdir().unwrap();
+    let dir = output_dir.path().to_str().unwrap();
+    let processors = RibEye::get_processors(&processor_names(), dir).unwrap();
+    assert!(processors[0].route_policy().skip_default);
+    assert!(processors[1].route_policy().is_noop());
+    let ribeye = RibEye::new().with_processors(processors);
+
+    let (pfx2as, defaults) = process(dir, ribeye);
+    assert_eq!(
+        prefixes(&pfx2as),
+        vec!["1.1.1.0/24", "10.0.0.0/16", "8.8.8.128/25", "9.9.9.0/24"]
+    );
+    let skipped: RouteSkipCounts =
+        serde_json::from_value(pfx2as["route_policy_skipped"].clone()).unwrap();
+    assert_eq!(skipped.default_route, 1);
+    assert_eq!(skipped.total(), 1);
+    // processors without a policy see every element and carry no counts
+    assert!(defaults.get("route_policy_skipped").is_none());
+}
+
+#[test]
+fn test_policy_override() {
+    let output_dir = tempfile::tempdir().unwrap();
+    let dir = output_dir.path().to_str().unwrap();
+    let config_file = ProcessorConfigFile::from_json_str(
+        r#"{"route_policies": {"pfx2as": {"skip_default": true, "skip_bogons": true, "skip_reserved_asns": true, "max_v4_prefix_len": 24}}}"#,
+    )
+    .unwrap();
+    let config = ProcessorConfig::new().with_config_file(&config_file);
+    let ribeye = RibEye::new()
+        .with_processor_names_and_config(&processor_names(), dir, &config)
+        .unwrap()
+        .with_route_policy("defaults", RoutePolicy::new().with_skip_default(true));
+
+    let (pfx2as, defaults) = process(dir, ribeye);
+    assert_eq!(prefixes(&pfx2as), vec!["1.1.1.0/24"]);
+    assert_eq!(
+        pfx2as["route_policy_skipped"],
+        serde_json::json!({"default_route": 1, "bogon": 1, "reserved_asn": 1, "prefix_length": 1})
+    );
+    assert_eq!(defaults["route_policy_skipped"]["default_route"], 1);
+}
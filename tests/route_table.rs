@@ -0,0 +1,105 @@
+use bgpkit_parser::models::ElemType;
+use bgpkit_parser::BgpElem;
+use ribeye::testing::SyntheticRibBuilder;
+use ribeye::util::RouteTable;
+use ribeye::{RibEye, RibMeta};
+use serde_json::Value;
+
+fn routes() -> Vec<BgpElem> {
+    SyntheticRibBuilder::new()
+        .peer("192.0.2.1", 65001)
+        .route("10.0.0.0/8", &[65001, 64500])
+        .route("192.0.2.0/24", &[65001, 64501])
+        .peer("192.0.2.2", 65002)
+        .route("10.0.0.0/8", &[65002, 64500])
+        .build()
+}
+
+fn withdrawal(elem: &BgpElem) -> BgpElem {
+    let mut elem = elem.clone();
+    elem.elem_type = ElemType::WITHDRAW;
+    elem.as_path = None;
+    elem
+}
+
+#[test]
+fn test_route_table() {
+    let routes = routes();
+    let mut table = RouteTable::new();
+    // re-announcements replace the peer's route
+    for elem in routes.iter().chain(routes.iter()) {
+        table.apply(elem.clone());
+    }
+    assert_eq!(table.len(), 3);
+
+    table.apply(withdrawal(&routes[0]));
+    assert_eq!(table.len(), 2);
+    // withdrawals of unknown routes are ignored
+    table.apply(withdrawal(&routes[0]));
+    assert_eq!(table.len(), 2);
+    let prefixes: Vec<String> = table.elems().iter().map(|e| e.prefix.to_string()).collect();
+    assert_eq!(prefixes, vec!["192.0.2.0/24", "10.0.0.0/8"]);
+
+    assert_eq!(table.remove_peer("192.0.2.2".parse().unwrap()), 1);
+    assert_eq!(table.len(), 1);
+}
+
+#[test]
+fn test_table_snapshots() {
+    let output_dir = tempfile::tempdir().unwrap();
+    let dir = output_dir.path().to_str().unwrap();
+    let rib_meta = RibMeta {
+        collector: "rrc00".to_string(),
+        ..Default::default()
+    };
+    let mut ribeye = RibEye::new()
+        .with_processor_names(&vec!["pfx2as".to_string()], dir)
+        .unwrap()
+        .with_rib_meta(&rib_meta);
+
+    let routes = routes();
+    let mut table = RouteTable::new();
+    let pfx2as = |ribeye: &mut RibEye, table: &RouteTable| {
+        ribeye.emit_table_snapshot(&rib_meta, table).unwrap();
+        let path = format!("{dir}/pfx2as/rrc00/latest.json.bz2");
+        let output: Value = oneio::read_json_struct(path.as_str()).unwrap();
+        let mut entries: Vec<(String, u64)> = output["pfx2as"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|e| {
+                (
+                    e["prefix"].as_str().unwrap().to_string(),
+                    e["count"].as_u64().unwrap(),
+                )
+            })
+            .collect();
+        entries.sort();
+        entries
+    };
+
+    for elem in &routes {
+        table.apply(elem.clone());
+    }
+    let first = pfx2as(&mut ribeye, &table);
+    assert_eq!(
+        first,
+        vec![
+            ("10.0.0.0/8".to_string(), 2),
+            ("192.0.2.0/24".to_string(), 1)
+        ]
+    );
+
+    // snapshots are rebuilt, re-announcements do not inflate counts
+    for elem in &routes {
+        table.apply(elem.clone());
+    }
+    assert_eq!(pfx2as(&mut ribeye, &table), first);
+
+    table.apply(withdrawal(&routes[1]));
+    table.apply(withdrawal(&routes[2]));
+    assert_eq!(
+        pfx2as(&mut ribeye, &table),
+        vec![("10.0.0.0/8".to_string(), 1)]
+    );
+}
@@ -0,0 +1,118 @@
+use ribeye::processors::readers::{
+    read_as2rel_summary, read_footprint_summary, read_peer_stats_output, read_peer_stats_summary,
+    read_pfx2as_output, read_pfx2as_summary, read_pfx2dist_summary, read_pfxlen_summary,
+};
+use ribeye::processors::schema::processor_schemas;
+use ribeye::testing::SyntheticRibBuilder;
+use ribeye::{RibEye, RibMeta};
+
+#[test]
+fn test_processor_schemas() {
+    for name in [
+        "peer-stats",
+        "pfx2as",
+        "as2rel",
+        "pfx2dist",
+        "pfxlen",
+        "footprint",
+    ] {
+        let schemas = processor_schemas(name).unwrap();
+        assert_eq!(schemas.processor, name);
+        let summary = serde_json::to_value(&schemas.summary).unwrap();
+        assert!(
+            summary["properties"]["rib_dump_urls"].is_object(),
+            "{}",
+            name
+        );
+        let output = serde_json::to_value(&schemas.output).unwrap();
+        assert!(output["properties"]["collector"].is_object(), "{}", name);
+    }
+
+    // aliases and variants share the schemas of their base processor
+    assert_eq!(
+        processor_schemas("peer_stats").unwrap().processor,
+        "peer-stats"
+    );
+    let aggregated = processor_schemas("pfx2as-aggregated").unwrap();
+    assert_eq!(aggregated.processor, "pfx2as");
+    let summary = serde_json::to_value(&aggregated.summary).unwrap();
+    assert!(summary["properties"]["aggregated"].is_object());
+
+    assert!(processor_schemas("unknown").is_none());
+}
+
+#[test]
+fn test_typed_readers() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let dir = temp_dir.path().join("results");
+    let dir = dir.to_str().unwrap();
+    let mrt_path = temp_dir.path().join("rib.test.bz2");
+    let mrt_path = mrt_path.to_str().unwrap();
+    SyntheticRibBuilder::new()
+        .peer("192.0.2.1", 65001)
+        .route("1.1.1.0/24", &[65001, 174, 13335])
+        .write_mrt_file(mrt_path)
+        .unwrap();
+
+    let rib_meta = RibMeta {
+        collector: "rrc00".to_string(),
+        ..Default::default()
+    };
+    let mut ribeye = RibEye::new()
+        .with_processor_names(
+            &vec![
+                "peer-stats".to_string(),
+                "pfx2as".to_string(),
+                "as2rel".to_string(),
+                "pfx2dist".to_string(),
+                "pfxlen".to_string(),
+                "footprint".to_string(),
+            ],
+            dir,
+        )
+        .unwrap()
+        .with_rib_meta(&rib_meta);
+    ribeye.process_mrt_file(mrt_path).unwrap();
+    ribeye.summarize_latest_files(&[rib_meta]).unwrap();
+
+    let output =
+        read_peer_stats_output(&format!("{}/peer-stats/rrc00/latest.json.bz2", dir)).unwrap();
+    assert_eq!(output.collector, "rrc00");
+    assert_eq!(output.peers.len(), 1);
+    let summary = read_peer_stats_summary(&format!("{}/peer-stats/latest.json.bz2", dir)).unwrap();
+    assert_eq!(summary.peers.iter().next().unwrap().asn, 65001);
+
+    let output = read_pfx2as_output(&format!("{}/pfx2as/rrc00/latest.json.bz2", dir)).unwrap();
+    assert_eq!(output.pfx2as.len(), 1);
+    // uncompressed summary copies are readable as well
+    let summary = read_pfx2as_summary(&format!("{}/pfx2as/latest.json", dir)).unwrap();
+    assert_eq!(summary.pfx2as[0].asn, 13335);
+
+    assert!(
+        !read_as2rel_summary(&format!("{}/as2rel/latest.json.bz2", dir))
+            .unwrap()
+            .as2rel
+            .is_empty()
+    );
+    assert!(
+        !read_pfx2dist_summary(&format!("{}/pfx2dist/latest.json.bz2", dir))
+            .unwrap()
+            .pfx2dist
+            .is_empty()
+    );
+    assert!(
+        !read_pfxlen_summary(&format!("{}/pfxlen/latest.json.bz2", dir))
+            .unwrap()
+            .origins
+            .is_empty()
+    );
+    assert!(
+        !read_footprint_summary(&format!("{}/footprint/latest.json.bz2", dir))
+            .unwrap()
+            .footprints
+            .is_empty()
+    );
+
+    let err = read_pfx2as_summary(&format!("{}/missing/latest.json.bz2", dir)).unwrap_err();
+    assert!(err.to_string().contains("missing/latest.json.bz2"));
+}
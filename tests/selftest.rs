@@ -0,0 +1,56 @@
+use ribeye::processors::schema::{processor_schemas, validate};
+use ribeye::selftest::Selftest;
+use serde_json::json;
+
+#[test]
+fn test_selftest() {
+    let storage_dir = tempfile::tempdir().unwrap();
+    let report = Selftest::new()
+        .with_storage_dir(storage_dir.path().to_str().unwrap())
+        .run()
+        .unwrap();
+    assert!(report.passed(), "{:?}", report.checks);
+    let names: Vec<_> = report.checks.iter().map(|c| c.name.as_str()).collect();
+    assert_eq!(names.first(), Some(&"fixture"));
+    assert!(names.contains(&"schema:pfx2as"));
+    assert_eq!(names.last(), Some(&"storage"));
+    // the round-trip object is removed again
+    assert_eq!(std::fs::read_dir(storage_dir.path()).unwrap().count(), 0);
+}
+
+#[test]
+fn test_selftest_missing_mrt_file() {
+    let report = Selftest::new()
+        .with_mrt_file("/nonexistent/rib.bz2")
+        .run()
+        .unwrap();
+    assert!(!report.passed());
+    assert_eq!(report.checks.len(), 1);
+    assert_eq!(report.checks[0].name, "process");
+}
+
+#[test]
+fn test_validate_against_schema() {
+    let schemas = processor_schemas("pfxlen").unwrap();
+    let summary = json!({
+        "rib_dump_urls": [],
+        "lengths": {"v4": {"24": 1}, "v6": {"48": 2}},
+        "origins": [],
+        "too_long": []
+    });
+    let errors = validate(&summary, &schemas.summary);
+    assert!(
+        errors.iter().all(|e| !e.starts_with("/lengths")),
+        "{:?}",
+        errors
+    );
+
+    let invalid = json!({
+        "rib_dump_urls": "not an array",
+        "lengths": {"v4": {"long": 1}, "v6": {}},
+    });
+    let errors = validate(&invalid, &schemas.summary);
+    assert!(errors.contains(&"/rib_dump_urls: expected array".to_string()));
+    assert!(errors.contains(&"/lengths/v4/long: not allowed".to_string()));
+    assert!(errors.iter().any(|e| e.ends_with(": missing")));
+}
@@ -0,0 +1,217 @@
+use anyhow::Result;
+use bgpkit_parser::BgpElem;
+use ribeye::processors::shared::{SharedData, SharedInput};
+use ribeye::processors::{PeerInfoCollectorJson, PeerStatsProcessor};
+use ribeye::testing::SyntheticRibBuilder;
+use ribeye::{MessageProcessor, RibEye, RibMeta};
+use serde_json::Value;
+use std::sync::{Arc, Mutex};
+
+fn rib_meta() -> RibMeta {
+    RibMeta {
+        collector: "rrc00".to_string(),
+        ..Default::default()
+    }
+}
+
+fn elems() -> Vec<BgpElem> {
+    // 10.0.0.1 announces three IPv4 prefixes, 10.0.0.2 a single one
+    SyntheticRibBuilder::new()
+        .peer("10.0.0.1", 65001)
+        .route("1.1.1.0/24", &[65001, 65001, 13335])
+        .route("8.8.8.0/24", &[65001, 15169])
+        .route("9.9.9.0/24", &[65001, 19281])
+        .peer("10.0.0.2", 65002)
+        .route("1.1.1.0/24", &[65002, 13335])
+        .build()
+}
+
+/// Processor recording the number of full-feed peers it was handed, if any.
+struct FullFeedProbe {
+    declare: bool,
+    full_feed: Arc<Mutex<Option<usize>>>,
+}
+
+impl MessageProcessor for FullFeedProbe {
+    fn name(&self) -> String {
+        "probe".to_string()
+    }
+
+    fn new_instance(&self) -> Box<dyn MessageProcessor> {
+        Box::new(FullFeedProbe {
+            declare: self.declare,
+            full_feed: self.full_feed.clone(),
+        })
+    }
+
+    fn output_paths(&self) -> Option<Vec<String>> {
+        None
+    }
+
+    fn reset_processor(&mut self, _rib_meta: &RibMeta) {}
+
+    fn process_entry(&mut self, _elem: &BgpElem) -> Result<()> {
+        Ok(())
+    }
+
+    fn shared_inputs(&self) -> Vec<SharedInput> {
+        match self.declare {
+            true => vec![SharedInput::FullFeedPeers],
+            false => vec![],
+        }
+    }
+
+    fn set_shared_data(&mut self, shared: &SharedData) {
+        *self.full_feed.lock().unwrap() = shared.full_feed_peers.as_ref().map(|f| f.len());
+    }
+
+    fn summarize_latest(
+        &self,
+        _rib_metas: &[RibMeta],
+        _ignore_error: bool,
+    ) -> Result<Option<Value>> {
+        Ok(None)
+    }
+}
+
+#[test]
+fn test_shared_full_feed_peers() {
+    let output_dir = tempfile::tempdir().unwrap();
+    let dir = output_dir.path().to_str().unwrap();
+    let mut ribeye = RibEye::new()
+        .with_processor_names(&vec!["peer-stats".to_string()], dir)
+        .unwrap()
+        .with_full_feed_thresholds(3, 1000)
+        .with_rib_meta(&rib_meta());
+    ribeye.process_elems(&elems()).unwrap();
+    ribeye.output().unwrap();
+
+    let path = format!("{dir}/peer-stats/rrc00/latest.json.bz2");
+    let output: PeerInfoCollectorJson = oneio::read_json_struct(path.as_str()).unwrap();
+    let full_feed: Vec<String> = output
+        .peers
+        .iter()
+        .filter(|p| p.full_feed)
+        .map(|p| p.ip.to_string())
+        .collect();
+    assert_eq!(full_feed, vec!["10.0.0.1"]);
+
+    // outside a pipeline, no full-feed peers are shared
+    let mut processor = PeerStatsProcessor::new(dir);
+    MessageProcessor::reset_processor(&mut processor, &rib_meta());
+    for elem in elems() {
+        MessageProcessor::process_entry(&mut processor, &elem).unwrap();
+    }
+    let result = processor.to_result().unwrap();
+    let output: PeerInfoCollectorJson = serde_json::from_value(result).unwrap();
+    assert!(output.peers.iter().all(|p| !p.full_feed));
+}
+
+#[test]
+fn test_shared_data_for_declared_inputs() {
+    let declared = Arc::new(Mutex::new(None));
+    let undeclared = Arc::new(Mutex::new(None));
+    let mut ribeye = RibEye::new()
+        .with_processors(vec![
+            Box::new(FullFeedProbe {
+                declare: true,
+                full_feed: declared.clone(),
+            }),
+            Box::new(FullFeedProbe {
+                declare: false,
+                full_feed: undeclared.clone(),
+            }),
+        ])
+        .with_full_feed_thresholds(1, 1)
+        .with_rib_meta(&rib_meta());
+    ribeye.process_elems(&elems()).unwrap();
+    ribeye.output().unwrap();
+    assert_eq!(*declared.lock().unwrap(), Some(2));
+    assert_eq!(*undeclared.lock().unwrap(), None);
+
+    // cloned pipelines keep the thresholds
+    let mut clone = ribeye.clone_pipeline().with_rib_meta(&rib_meta());
+    clone.process_elems(&elems()[..1]).unwrap();
+    clone.output().unwrap();
+    assert_eq!(*declared.lock().unwrap(), Some(1));
+}
+
+#[test]
+fn test_peers_of_merged_collectors() {
+    // the same peer IP at two collectors, announcing three prefixes in total
+    let temp_dir = tempfile::tempdir().unwrap();
+    let dir = temp_dir.path().to_str().unwrap();
+    let rib_metas: Vec<RibMeta> = [("rrc00", 2), ("route-views2", 1)]
+        .into_iter()
+        .map(|(collector, prefixes)| {
+            let mut builder = SyntheticRibBuilder::new().peer("10.0.0.1", 65001);
+            for i in 0..prefixes {
+                builder = builder.route(format!("10.{}.0.0/16", i).as_str(), &[65001, 13335]);
+            }
+            let path = format!("{}/{}.rib.bz2", dir, collector);
+            builder.write_mrt_file(path.as_str()).unwrap();
+            RibMeta {
+                collector: collector.to_string(),
+                rib_dump_url: path,
+                ..Default::default()
+            }
+        })
+        .collect();
+
+    let full_feed = Arc::new(Mutex::new(None));
+    let mut ribeye = RibEye::new()
+        .with_processors(vec![Box::new(FullFeedProbe {
+            declare: true,
+            full_feed: full_feed.clone(),
+        })])
+        .with_full_feed_thresholds(3, 1000);
+    let stats = ribeye.process_mrt_files(&rib_metas).unwrap();
+    // one peer per collector, neither announcing a full feed on its own
+    assert_eq!(stats.peers, 2);
+    assert_eq!(*full_feed.lock().unwrap(), Some(0));
+}
+
+#[test]
+fn test_full_feed_peers_by_collector() {
+    // the same peer IP with a full feed at one collector and a partial one at another
+    let temp_dir = tempfile::tempdir().unwrap();
+    let dir = temp_dir.path().to_str().unwrap();
+    let rib_metas: Vec<RibMeta> = [("rrc00", 3), ("route-views2", 1)]
+        .into_iter()
+        .map(|(collector, prefixes)| {
+            let mut builder = SyntheticRibBuilder::new().peer("10.0.0.1", 65001);
+            for i in 0..prefixes {
+                builder = builder.route(format!("10.{}.0.0/16", i).as_str(), &[65001, 13335]);
+            }
+            let path = format!("{}/{}.rib.bz2", dir, collector);
+            builder.write_mrt_file(path.as_str()).unwrap();
+            RibMeta {
+                collector: collector.to_string(),
+                rib_dump_url: path,
+                ..Default::default()
+            }
+        })
+        .collect();
+
+    let mut ribeye = RibEye::new()
+        .with_processor_names(&vec!["peer-stats".to_string()], dir)
+        .unwrap()
+        .with_full_feed_thresholds(3, 1000);
+    ribeye.process_mrt_files(&rib_metas).unwrap();
+
+    let path = format!("{dir}/peer-stats/all/latest.json.bz2");
+    let output: PeerInfoCollectorJson = oneio::read_json_struct(path.as_str()).unwrap();
+    let mut full_feed: Vec<(String, bool)> = output
+        .peers
+        .iter()
+        .map(|p| (p.collector.clone().unwrap_or_default(), p.full_feed))
+        .collect();
+    full_feed.sort();
+    assert_eq!(
+        full_feed,
+        vec![
+            ("route-views2".to_string(), false),
+            ("rrc00".to_string(), true)
+        ]
+    );
+}
@@ -0,0 +1,76 @@
+use ribeye::signing::{signature_path, verify_file, PublicKey, SigningKey};
+use ribeye::testing::SyntheticRibBuilder;
+use ribeye::{RibEye, RibMeta};
+use std::sync::Arc;
+
+#[test]
+fn test_sign_and_verify() {
+    let key = SigningKey::generate().unwrap();
+    // keys survive a round trip through their files
+    let key = SigningKey::parse(key.to_secret_key_string().as_str()).unwrap();
+    let public_key_file = key.public_key().to_string();
+    assert!(public_key_file.starts_with("untrusted comment: minisign public key "));
+    let public_key = PublicKey::parse(public_key_file.as_str()).unwrap();
+    assert_eq!(public_key, key.public_key());
+
+    let signature = key.sign(b"ribeye output", "latest.json.bz2");
+    assert_eq!(signature.lines().count(), 4);
+    let comment = public_key
+        .verify(b"ribeye output", signature.as_str())
+        .unwrap();
+    assert!(comment.starts_with("timestamp:"));
+    assert!(comment.ends_with("\tfile:latest.json.bz2"));
+
+    // tampered content
+    assert!(public_key
+        .verify(b"ribeye outpuT", signature.as_str())
+        .is_err());
+    // tampered trusted comment
+    let tampered = signature.replace("file:latest", "file:other");
+    assert!(public_key
+        .verify(b"ribeye output", tampered.as_str())
+        .is_err());
+    // another key
+    let other_key = SigningKey::generate().unwrap().public_key();
+    assert!(other_key
+        .verify(b"ribeye output", signature.as_str())
+        .is_err());
+}
+
+#[test]
+fn test_signed_outputs() {
+    let output_dir = tempfile::tempdir().unwrap();
+    let dir = output_dir.path().to_str().unwrap();
+    let mrt_path = output_dir.path().join("rib.test.bz2");
+    let mrt_path = mrt_path.to_str().unwrap();
+    SyntheticRibBuilder::new()
+        .peer("192.0.2.1", 65001)
+        .route("1.1.1.0/24", &[65001, 174, 13335])
+        .write_mrt_file(mrt_path)
+        .unwrap();
+
+    let key = Arc::new(SigningKey::generate().unwrap());
+    let rib_meta = RibMeta {
+        collector: "rrc99".to_string(),
+        ..Default::default()
+    };
+    let mut ribeye = RibEye::new()
+        .with_processor_names(&vec!["pfx2as".to_string()], dir)
+        .unwrap()
+        .with_rib_meta(&rib_meta)
+        .with_signing_key(key.clone());
+    ribeye.process_mrt_file(mrt_path).unwrap();
+    ribeye.summarize_latest_files(&[rib_meta]).unwrap();
+
+    let public_key = key.public_key();
+    let latest = format!("{}/pfx2as/rrc99/latest.json.bz2", dir);
+    let summary = format!("{}/pfx2as/latest.json.bz2", dir);
+    for path in [latest.as_str(), summary.as_str()] {
+        assert!(std::path::Path::new(signature_path(path).as_str()).exists());
+        verify_file(path, None, &public_key).unwrap();
+    }
+
+    // a modified output no longer verifies
+    std::fs::copy(latest.as_str(), summary.as_str()).unwrap();
+    assert!(verify_file(summary.as_str(), None, &public_key).is_err());
+}
@@ -0,0 +1,53 @@
+use anyhow::Result;
+use ribeye::testing::SyntheticRibBuilder;
+use ribeye::{OutputSink, RibEye, RibMeta};
+use std::sync::{Arc, Mutex};
+
+/// Sink capturing the paths written to, optionally behaving as a stream.
+#[derive(Default)]
+struct CapturingSink {
+    stream: bool,
+    paths: Arc<Mutex<Vec<String>>>,
+}
+
+impl OutputSink for CapturingSink {
+    fn write_bytes(&self, path: &str, _data: &[u8]) -> Result<()> {
+        self.paths.lock().unwrap().push(path.to_string());
+        Ok(())
+    }
+
+    fn is_stream(&self) -> bool {
+        self.stream
+    }
+}
+
+fn written_paths(stream: bool) -> Vec<String> {
+    let paths = Arc::new(Mutex::new(vec![]));
+    let sink = CapturingSink {
+        stream,
+        paths: paths.clone(),
+    };
+    let mut ribeye = RibEye::new()
+        .with_processor_names(&vec!["pfx2as".to_string()], "-")
+        .unwrap()
+        .with_sink(Box::new(sink))
+        .with_rib_meta(&RibMeta::default());
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("rib.test.bz2");
+    let path = path.to_str().unwrap();
+    SyntheticRibBuilder::new()
+        .generated(2, 3)
+        .write_mrt_file(path)
+        .unwrap();
+    ribeye.process_mrt_file(path).unwrap();
+    let paths = paths.lock().unwrap().clone();
+    paths
+}
+
+#[test]
+fn test_stream_sink_writes_once() {
+    // dated and latest outputs
+    assert_eq!(written_paths(false).len(), 2);
+    // a stream only gets the result once
+    assert_eq!(written_paths(true).len(), 1);
+}
@@ -0,0 +1,91 @@
+use chrono::{Duration as ChronoDuration, Utc};
+use ribeye::inspect::OutputFile;
+use ribeye::processors::{collector_dumps, CollectorDump, FileSink, OutputSink};
+use ribeye::testing::SyntheticRibBuilder;
+use ribeye::{RibEye, RibMeta};
+use std::time::Duration;
+
+fn rib_meta(collector: &str, age_hours: i64) -> RibMeta {
+    RibMeta {
+        project: "riperis".to_string(),
+        collector: collector.to_string(),
+        timestamp: (Utc::now() - ChronoDuration::hours(age_hours)).naive_utc(),
+        ..Default::default()
+    }
+}
+
+#[test]
+fn test_collector_dumps() {
+    let rib_metas = [
+        RibMeta {
+            collector: "rrc01".to_string(),
+            timestamp: chrono::DateTime::from_timestamp(1_704_067_200, 0)
+                .unwrap()
+                .naive_utc(),
+            ..Default::default()
+        },
+        RibMeta {
+            collector: "rrc00".to_string(),
+            timestamp: chrono::DateTime::from_timestamp(1_704_240_000, 0)
+                .unwrap()
+                .naive_utc(),
+            ..Default::default()
+        },
+    ];
+    let now = 1_704_240_000 + 3600;
+    let dumps = collector_dumps(&rib_metas, Duration::from_secs(48 * 3600), now);
+    assert_eq!(
+        dumps,
+        vec![
+            CollectorDump {
+                project: String::new(),
+                collector: "rrc00".to_string(),
+                rib_timestamp: 1_704_240_000,
+                stale: false,
+            },
+            CollectorDump {
+                project: String::new(),
+                collector: "rrc01".to_string(),
+                rib_timestamp: 1_704_067_200,
+                stale: true,
+            },
+        ]
+    );
+}
+
+#[test]
+fn test_summary_flags_stale_collectors() {
+    let output_dir = tempfile::tempdir().unwrap();
+    let dir = output_dir.path().to_str().unwrap();
+    let rib_metas = vec![rib_meta("rrc00", 8), rib_meta("route-views2", 72)];
+    let mut ribeye = RibEye::new()
+        .with_processor_names(&vec!["pfx2as".to_string()], dir)
+        .unwrap()
+        .with_stale_after(Duration::from_secs(24 * 3600));
+    for rib_meta in &rib_metas {
+        ribeye = ribeye.with_rib_meta(rib_meta);
+        ribeye
+            .process_elems(SyntheticRibBuilder::new().generated(1, 2).elems())
+            .unwrap();
+        ribeye.output().unwrap();
+    }
+    ribeye.summarize_latest_files(&rib_metas).unwrap();
+
+    let summary = FileSink
+        .read_json(format!("{}/pfx2as/latest.json.bz2", dir).as_str())
+        .unwrap();
+    let dumps: Vec<CollectorDump> =
+        serde_json::from_value(summary["collector_dumps"].clone()).unwrap();
+    let flags: Vec<(&str, bool)> = dumps
+        .iter()
+        .map(|d| (d.collector.as_str(), d.stale))
+        .collect();
+    assert_eq!(flags, vec![("route-views2", true), ("rrc00", false)]);
+    assert_eq!(
+        dumps[1].rib_timestamp,
+        rib_metas[0].timestamp.and_utc().timestamp()
+    );
+    // the collector dumps are meta information, not the dataset
+    let output = OutputFile::from_json_str(summary.to_string().as_str()).unwrap();
+    assert_eq!(output.dataset, "pfx2as");
+}
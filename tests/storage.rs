@@ -0,0 +1,283 @@
+use chrono::NaiveDateTime;
+use ribeye::listing::{list_outputs, prune_outputs};
+use ribeye::processors::cache::{file_fingerprint, get_summary_cache_path, SummaryCache};
+use ribeye::processors::{FileSink, OutputSink};
+use ribeye::storage::{env_check, parse_object_url, ObjectStore};
+use ribeye::testing::SyntheticRibBuilder;
+use ribeye::{RibEye, RibMeta};
+use serde_json::{json, Value};
+use std::collections::hash_map::DefaultHasher;
+use std::collections::BTreeMap;
+use std::hash::{Hash, Hasher};
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::TcpListener;
+use std::sync::{Arc, Mutex};
+
+type Objects = Arc<Mutex<BTreeMap<String, Vec<u8>>>>;
+
+fn decode(value: &str) -> String {
+    let bytes = value.as_bytes();
+    let mut decoded = vec![];
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'%' => {
+                decoded.push(u8::from_str_radix(&value[i + 1..i + 3], 16).unwrap());
+                i += 3;
+            }
+            byte => {
+                decoded.push(byte);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8(decoded).unwrap()
+}
+
+fn query_param(query: &str, name: &str) -> Option<String> {
+    query
+        .split('&')
+        .filter_map(|p| p.split_once('='))
+        .find(|(k, _)| *k == name)
+        .map(|(_, v)| decode(v))
+}
+
+/// Entity tag of an object's content.
+fn etag(data: &[u8]) -> String {
+    let mut hasher = DefaultHasher::new();
+    data.hash(&mut hasher);
+    format!("\"{:x}\"", hasher.finish())
+}
+
+/// Minimal Google Cloud Storage JSON API and Azure Blob Storage REST API, one request per
+/// connection. Returns the endpoint and the stored objects by `<bucket>/<key>`.
+fn mock_object_store() -> (String, Objects) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let endpoint = format!("http://{}", listener.local_addr().unwrap());
+    let objects: Objects = Arc::new(Mutex::new(BTreeMap::new()));
+    let store = objects.clone();
+    std::thread::spawn(move || {
+        for stream in listener.incoming() {
+            let mut stream = stream.unwrap();
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+            let mut request_line = String::new();
+            reader.read_line(&mut request_line).unwrap();
+            let mut content_length = 0;
+            let mut authorized = false;
+            loop {
+                let mut line = String::new();
+                reader.read_line(&mut line).unwrap();
+                let line = line.trim_end();
+                if line.is_empty() {
+                    break;
+                }
+                let (name, value) = line.split_once(": ").unwrap();
+                match name.to_lowercase().as_str() {
+                    "content-length" => content_length = value.parse().unwrap(),
+                    "authorization" => authorized = value == "Bearer gcs-token",
+                    _ => {}
+                }
+            }
+            let mut body = vec![0; content_length];
+            reader.read_exact(&mut body).unwrap();
+
+            let mut parts = request_line.split_whitespace();
+            let method = parts.next().unwrap().to_string();
+            let target = parts.next().unwrap().to_string();
+            let (path, query) = target.split_once('?').unwrap_or((target.as_str(), ""));
+            let mut objects = store.lock().unwrap();
+            let mut etag_header = None;
+            let (status, response): (&str, Vec<u8>) = if let Some(rest) =
+                path.strip_prefix("/upload/storage/v1/b/")
+            {
+                let bucket = rest.trim_end_matches("/o");
+                let name = query_param(query, "name").unwrap();
+                match authorized {
+                    true => {
+                        objects.insert(format!("{}/{}", bucket, name), body);
+                        ("200 OK", b"{}".to_vec())
+                    }
+                    false => ("401 Unauthorized", vec![]),
+                }
+            } else if let Some(rest) = path.strip_prefix("/storage/v1/b/") {
+                let (bucket, object) = rest.split_once("/o").unwrap();
+                let key = format!("{}/{}", bucket, decode(object.trim_start_matches('/')));
+                match (authorized, method.as_str(), object.is_empty()) {
+                    (false, _, _) => ("401 Unauthorized", vec![]),
+                    (_, "GET", true) => {
+                        let prefix =
+                            format!("{}/{}", bucket, query_param(query, "prefix").unwrap());
+                        let items: Vec<Value> = objects
+                            .keys()
+                            .filter(|k| k.starts_with(prefix.as_str()))
+                            .map(|k| json!({"name": k.split_once('/').unwrap().1}))
+                            .collect();
+                        ("200 OK", json!({ "items": items }).to_string().into_bytes())
+                    }
+                    (_, "GET", false) => match objects.get(&key) {
+                        Some(data) if query_param(query, "alt").as_deref() == Some("media") => {
+                            ("200 OK", data.clone())
+                        }
+                        Some(data) => {
+                            let metadata = json!({"name": object, "etag": etag(data)});
+                            ("200 OK", metadata.to_string().into_bytes())
+                        }
+                        None => ("404 Not Found", vec![]),
+                    },
+                    (_, "DELETE", false) => {
+                        objects.remove(&key);
+                        ("204 No Content", vec![])
+                    }
+                    _ => ("400 Bad Request", vec![]),
+                }
+            } else if query_param(query, "sig").as_deref() != Some("azure-signature") {
+                ("403 Forbidden", vec![])
+            } else {
+                let key = decode(path.trim_start_matches('/'));
+                match method.as_str() {
+                    "PUT" => {
+                        objects.insert(key, body);
+                        ("201 Created", vec![])
+                    }
+                    "GET" if query_param(query, "comp").as_deref() == Some("list") => {
+                        let prefix = format!("{}/{}", key, query_param(query, "prefix").unwrap());
+                        let names: String = objects
+                            .keys()
+                            .filter(|k| k.starts_with(prefix.as_str()))
+                            .map(|k| {
+                                format!(
+                                    "<Blob><Name>{}</Name></Blob>",
+                                    k.split_once('/').unwrap().1
+                                )
+                            })
+                            .collect();
+                        let xml = format!(
+                            "<?xml version=\"1.0\"?><EnumerationResults><Blobs>{}</Blobs><NextMarker /></EnumerationResults>",
+                            names
+                        );
+                        ("200 OK", xml.into_bytes())
+                    }
+                    "GET" => match objects.get(&key) {
+                        Some(data) => ("200 OK", data.clone()),
+                        None => ("404 Not Found", vec![]),
+                    },
+                    "HEAD" => match objects.get(&key) {
+                        Some(data) => {
+                            etag_header = Some(etag(data));
+                            ("200 OK", vec![])
+                        }
+                        None => ("404 Not Found", vec![]),
+                    },
+                    "DELETE" => {
+                        objects.remove(&key);
+                        ("202 Accepted", vec![])
+                    }
+                    _ => ("400 Bad Request", vec![]),
+                }
+            };
+            let etag_header = etag_header
+                .map(|e| format!("ETag: {}\r\n", e))
+                .unwrap_or_default();
+            let header = format!(
+                "HTTP/1.1 {}\r\nContent-Length: {}\r\n{}Connection: close\r\n\r\n",
+                status,
+                response.len(),
+                etag_header
+            );
+            stream.write_all(header.as_bytes()).unwrap();
+            stream.write_all(&response).unwrap();
+        }
+    });
+    (endpoint, objects)
+}
+
+/// Write, read, list and prune outputs under an object store output directory.
+fn check_output_dir(dir: &str, objects: &Objects, bucket: &str) {
+    let rib_meta = RibMeta {
+        collector: "rrc00".to_string(),
+        timestamp: NaiveDateTime::parse_from_str("2024-01-01 00:00", "%Y-%m-%d %H:%M").unwrap(),
+        ..Default::default()
+    };
+    let mut ribeye = RibEye::new()
+        .with_processor_names(&vec!["pfx2as".to_string()], dir)
+        .unwrap()
+        .with_rib_meta(&rib_meta);
+    for elem in SyntheticRibBuilder::new().generated(1, 2).elems() {
+        ribeye.process_elem(elem).unwrap();
+    }
+    ribeye.output().unwrap();
+    ribeye
+        .summarize_latest_files(std::slice::from_ref(&rib_meta))
+        .unwrap();
+
+    assert!(objects
+        .lock()
+        .unwrap()
+        .contains_key(format!("{}/ribeye/pfx2as/rrc00/latest.json.bz2", bucket).as_str()));
+    let summary = FileSink
+        .read_json(format!("{}/pfx2as/latest.json.bz2", dir).as_str())
+        .unwrap();
+    assert_eq!(summary["rib_dump_urls"].as_array().unwrap().len(), 1);
+
+    let outputs = list_outputs(dir).unwrap();
+    let paths: Vec<_> = outputs.iter().map(|o| o.path.as_str()).collect();
+    assert_eq!(
+        paths,
+        vec![
+            "pfx2as/rrc00/2024/01/pfx2as_rrc00_2024-01-01_0000_1704067200.json.bz2",
+            "pfx2as/rrc00/latest.json.bz2",
+        ]
+    );
+    let pruned = prune_outputs(dir, 1704153600, false).unwrap();
+    assert_eq!(pruned.len(), 1);
+    assert_eq!(list_outputs(dir).unwrap().len(), 1);
+
+    // incremental summaries fingerprint latest outputs by their ETag
+    let latest_path = format!("{}/pfx2as/rrc00/latest.json.bz2", dir);
+    let fingerprint = file_fingerprint(latest_path.as_str()).unwrap();
+    RibEye::new()
+        .with_processor_names(&vec!["pfx2as".to_string()], dir)
+        .unwrap()
+        .with_incremental_summaries(true)
+        .summarize_latest_files(&[rib_meta])
+        .unwrap();
+    let cache = SummaryCache::load(&FileSink, get_summary_cache_path(dir, "pfx2as").as_str());
+    assert_eq!(cache.entries.len(), 1);
+    assert_eq!(cache.entries[0].fingerprint, fingerprint);
+}
+
+#[test]
+fn test_parse_object_url() {
+    let (store, bucket, key) =
+        parse_object_url("gs://bucket/ribeye/pfx2as/latest.json.bz2").unwrap();
+    assert_eq!(store, ObjectStore::Gcs);
+    assert_eq!(bucket, "bucket");
+    assert_eq!(key, "ribeye/pfx2as/latest.json.bz2");
+    let (store, bucket, key) = parse_object_url("az://container").unwrap();
+    assert_eq!(store, ObjectStore::Azure);
+    assert_eq!(bucket, "container");
+    assert_eq!(key, "");
+    assert_eq!(ObjectStore::of("s3://bucket/key"), Some(ObjectStore::S3));
+    assert_eq!(ObjectStore::of("/local/path"), None);
+    assert!(parse_object_url("gs:///key").is_err());
+    assert!(env_check("/local/path").is_ok());
+}
+
+#[test]
+fn test_gcs_output_dir() {
+    let (endpoint, objects) = mock_object_store();
+    std::env::set_var("GCS_ENDPOINT", endpoint);
+    std::env::set_var("GCS_ACCESS_TOKEN", "gcs-token");
+    env_check("gs://outputs/ribeye").unwrap();
+    check_output_dir("gs://outputs/ribeye", &objects, "outputs");
+}
+
+#[test]
+fn test_azure_output_dir() {
+    let (endpoint, objects) = mock_object_store();
+    std::env::set_var("AZURE_STORAGE_ENDPOINT", endpoint);
+    std::env::set_var("AZURE_STORAGE_ACCOUNT", "account");
+    std::env::set_var("AZURE_STORAGE_SAS_TOKEN", "?sv=2024&sig=azure-signature");
+    env_check("az://outputs/ribeye").unwrap();
+    check_output_dir("az://outputs/ribeye", &objects, "outputs");
+}
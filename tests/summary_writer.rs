@@ -0,0 +1,77 @@
+use ribeye::input::Compression;
+use ribeye::processors::summary::get_summary_copy_path;
+use ribeye::processors::SummaryWriter;
+use ribeye::testing::SyntheticRibBuilder;
+use ribeye::{RibEye, RibMeta};
+use serde_json::Value;
+
+#[test]
+fn test_summary_copy_paths() {
+    let path = "results/pfx2as/latest.json.bz2";
+    assert_eq!(
+        get_summary_copy_path(path, Compression::None),
+        "results/pfx2as/latest.json"
+    );
+    assert_eq!(
+        get_summary_copy_path(path, Compression::Gzip),
+        "results/pfx2as/latest.json.gz"
+    );
+    // the canonical path is written once
+    assert_eq!(
+        SummaryWriter::new()
+            .with_copies(&[Compression::Bzip2, Compression::Gzip])
+            .paths(&[path.to_string()]),
+        vec![
+            path.to_string(),
+            "results/pfx2as/latest.json.gz".to_string()
+        ]
+    );
+    assert_eq!(
+        SummaryWriter::new()
+            .with_copies(&[])
+            .paths(&[path.to_string()]),
+        vec![path.to_string()]
+    );
+}
+
+#[test]
+fn test_summary_copies_of_all_processors() {
+    let output_dir = tempfile::tempdir().unwrap();
+    let dir = output_dir.path().to_str().unwrap();
+    let mrt_path = output_dir.path().join("rib.test.bz2");
+    let mrt_path = mrt_path.to_str().unwrap();
+    SyntheticRibBuilder::new()
+        .peer("192.0.2.1", 65001)
+        .route("1.1.1.0/24", &[65001, 174, 13335])
+        .write_mrt_file(mrt_path)
+        .unwrap();
+    let rib_meta = RibMeta {
+        collector: "rrc00".to_string(),
+        ..Default::default()
+    };
+    let processors = vec!["peer-stats".to_string(), "pfx2as".to_string()];
+    let mut ribeye = RibEye::new()
+        .with_processor_names(&processors, dir)
+        .unwrap()
+        .with_rib_meta(&rib_meta);
+    ribeye.process_mrt_file(mrt_path).unwrap();
+    RibEye::new()
+        .with_processor_names(&processors, dir)
+        .unwrap()
+        .with_summary_writer(
+            SummaryWriter::new().with_copies(&[Compression::None, Compression::Gzip]),
+        )
+        .summarize_latest_files(std::slice::from_ref(&rib_meta))
+        .unwrap();
+
+    for processor in &processors {
+        let canonical: Value =
+            oneio::read_json_struct(&format!("{}/{}/latest.json.bz2", dir, processor)).unwrap();
+        // plain JSON, e.g. for curl
+        let plain = std::fs::read_to_string(format!("{}/{}/latest.json", dir, processor)).unwrap();
+        assert_eq!(serde_json::from_str::<Value>(&plain).unwrap(), canonical);
+        let gzip: Value =
+            oneio::read_json_struct(&format!("{}/{}/latest.json.gz", dir, processor)).unwrap();
+        assert_eq!(gzip, canonical);
+    }
+}
@@ -0,0 +1,46 @@
+use ribeye::testing::{RecordingProcessor, SyntheticRibBuilder};
+use ribeye::{MessageProcessor, RibEye, RibMeta};
+
+#[test]
+fn test_synthetic_rib_round_trip() {
+    let builder = SyntheticRibBuilder::new()
+        .peer("10.0.0.1", 65001)
+        .route("1.1.1.0/24", &[65001, 174, 13335])
+        .route("8.8.8.0/24", &[65001, 3356, 15169])
+        .peer("2001:db8::1", 65002)
+        .route("2606:4700::/32", &[65002, 13335])
+        .withdrawal("9.9.9.0/24");
+    assert_eq!(builder.elems().len(), 4);
+
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("rib.test.bz2");
+    let path = path.to_str().unwrap();
+    builder.write_mrt_file(path).unwrap();
+
+    let processor = RecordingProcessor::new("recorder");
+    let recording = processor.recording();
+    let mut ribeye = RibEye::new()
+        .with_processors(vec![processor.to_boxed()])
+        .with_rib_meta(&RibMeta::default());
+    ribeye.process_mrt_file(path).unwrap();
+
+    let recording = recording.lock().unwrap();
+    // the withdrawal is not part of the RIB dump
+    assert_eq!(recording.elems.len(), 3);
+    assert_eq!(recording.outputs, 1);
+    let mut origins: Vec<u32> = recording
+        .elems
+        .iter()
+        .map(|e| {
+            *e.as_path
+                .as_ref()
+                .unwrap()
+                .to_u32_vec_opt(false)
+                .unwrap()
+                .last()
+                .unwrap()
+        })
+        .collect();
+    origins.sort();
+    assert_eq!(origins, vec![13335, 13335, 15169]);
+}
@@ -0,0 +1,92 @@
+use anyhow::Result;
+use ribeye::throttle::{is_remote, IoLimiter, ThrottledSink};
+use ribeye::OutputSink;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+#[test]
+fn test_max_concurrent() {
+    let limiter = Arc::new(IoLimiter::new().with_max_concurrent(2));
+    let max_active = Arc::new(AtomicUsize::new(0));
+    let handles: Vec<_> = (0..6)
+        .map(|_| {
+            let limiter = limiter.clone();
+            let max_active = max_active.clone();
+            std::thread::spawn(move || {
+                let _permit = limiter.acquire();
+                max_active.fetch_max(limiter.active(), Ordering::SeqCst);
+                std::thread::sleep(Duration::from_millis(20));
+            })
+        })
+        .collect();
+    for handle in handles {
+        handle.join().unwrap();
+    }
+    assert_eq!(max_active.load(Ordering::SeqCst), 2);
+    assert_eq!(limiter.active(), 0);
+}
+
+#[test]
+fn test_rate() {
+    let limiter = Arc::new(IoLimiter::new().with_rate(20.0));
+    let start = Instant::now();
+    // the burst of 20 transfers starts right away
+    for _ in 0..20 {
+        drop(limiter.acquire());
+    }
+    assert!(start.elapsed() < Duration::from_millis(50));
+    // further transfers start every 50ms
+    for _ in 0..3 {
+        drop(limiter.acquire());
+    }
+    assert!(start.elapsed() >= Duration::from_millis(140));
+
+    // no limits
+    let limiter = Arc::new(IoLimiter::new().with_max_concurrent(0).with_rate(0.0));
+    let permits: Vec<_> = (0..100).map(|_| limiter.acquire()).collect();
+    assert_eq!(limiter.active(), 100);
+    drop(permits);
+    assert_eq!(limiter.active(), 0);
+}
+
+/// Sink recording the number of active permits of a limiter at each write.
+struct RecordingSink {
+    limiter: Arc<IoLimiter>,
+    writes: Mutex<Vec<(String, usize)>>,
+}
+
+impl OutputSink for RecordingSink {
+    fn write_bytes(&self, path: &str, _data: &[u8]) -> Result<()> {
+        self.writes
+            .lock()
+            .unwrap()
+            .push((path.to_string(), self.limiter.active()));
+        Ok(())
+    }
+}
+
+#[test]
+fn test_throttled_sink() {
+    assert!(is_remote("s3://bucket/pfx2as/latest.json.bz2"));
+    assert!(is_remote("https://data.ris.ripe.net/rrc00/latest-bview.gz"));
+    assert!(!is_remote("./results/pfx2as/latest.json.bz2"));
+
+    let limiter = Arc::new(IoLimiter::new().with_max_concurrent(1));
+    let inner = Arc::new(RecordingSink {
+        limiter: limiter.clone(),
+        writes: Mutex::new(vec![]),
+    });
+    let sink = ThrottledSink::new(inner.clone(), limiter.clone());
+    sink.write_bytes("s3://bucket/a.json", b"{}").unwrap();
+    sink.write_json("results/b.json", &serde_json::json!({}))
+        .unwrap();
+    assert_eq!(
+        *inner.writes.lock().unwrap(),
+        vec![
+            ("s3://bucket/a.json".to_string(), 1),
+            ("results/b.json".to_string(), 0),
+        ]
+    );
+    assert_eq!(limiter.active(), 0);
+}
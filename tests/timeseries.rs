@@ -0,0 +1,111 @@
+use chrono::NaiveDateTime;
+use ribeye::index::build_output_index;
+use ribeye::testing::SyntheticRibBuilder;
+use ribeye::timeseries::{get_timeseries_path, timeseries_records, SNAPSHOT_TABLE};
+use ribeye::{RibEye, RibMeta};
+use serde_json::{json, Value};
+use std::io::Read;
+
+fn read_records(path: &str) -> Vec<Value> {
+    let mut content = String::new();
+    oneio::get_reader(path)
+        .unwrap()
+        .read_to_string(&mut content)
+        .unwrap();
+    content
+        .lines()
+        .map(|l| serde_json::from_str(l).unwrap())
+        .collect()
+}
+
+#[test]
+fn test_timeseries_records() {
+    let rib_meta = RibMeta {
+        collector: "rrc00".to_string(),
+        timestamp: NaiveDateTime::parse_from_str("2024-01-01 08:00", "%Y-%m-%d %H:%M").unwrap(),
+        ..Default::default()
+    };
+    let output = json!({
+        "collector": "rrc00",
+        "peers_count": 3,
+        "pfx2as": [{"prefix": "1.1.1.0/24", "asn": 13335, "count": 3}],
+        "too_long": [],
+    });
+    let tables = timeseries_records(&rib_meta, &output);
+    let names: Vec<_> = tables.iter().map(|(name, _)| name.as_str()).collect();
+    assert_eq!(names, vec!["pfx2as", "too_long", SNAPSHOT_TABLE]);
+    assert_eq!(
+        tables[0].1,
+        vec![json!({
+            "ts": 1704096000,
+            "collector": "rrc00",
+            "prefix": "1.1.1.0/24",
+            "asn": 13335,
+            "count": 3
+        })]
+    );
+    assert_eq!(
+        tables[2].1,
+        vec![json!({
+            "ts": 1704096000,
+            "collector": "rrc00",
+            "peers_count": 3,
+            "pfx2as_count": 1,
+            "too_long_count": 0
+        })]
+    );
+    assert_eq!(
+        get_timeseries_path("results", "pfx2as", "pfx2as", &rib_meta),
+        "results/timeseries/pfx2as/pfx2as/date=2024-01-01/rrc00_1704096000.jsonl.gz"
+    );
+}
+
+#[test]
+fn test_timeseries_outputs() {
+    let output_dir = tempfile::tempdir().unwrap();
+    let dir = output_dir.path().to_str().unwrap();
+    let mrt_path = output_dir.path().join("rib.test.bz2");
+    let mrt_path = mrt_path.to_str().unwrap();
+    SyntheticRibBuilder::new()
+        .peer("192.0.2.1", 65001)
+        .route("1.1.1.0/24", &[65001, 174, 13335])
+        .write_mrt_file(mrt_path)
+        .unwrap();
+
+    let mut rib_metas = vec![];
+    for time in ["2024-01-01 00:00", "2024-01-02 00:00"] {
+        let rib_meta = RibMeta {
+            collector: "rrc00".to_string(),
+            timestamp: NaiveDateTime::parse_from_str(time, "%Y-%m-%d %H:%M").unwrap(),
+            ..Default::default()
+        };
+        let mut ribeye = RibEye::new()
+            .with_processor_names(&vec!["pfx2as".to_string()], dir)
+            .unwrap()
+            .with_rib_meta(&rib_meta)
+            .with_timeseries(dir);
+        ribeye.process_mrt_file(mrt_path).unwrap();
+        rib_metas.push(rib_meta);
+    }
+
+    // one partition per day, earlier snapshots are kept
+    for rib_meta in &rib_metas {
+        let ts = rib_meta.timestamp.and_utc().timestamp();
+        let records = read_records(&get_timeseries_path(dir, "pfx2as", "pfx2as", rib_meta));
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0]["ts"], ts);
+        assert_eq!(records[0]["prefix"], "1.1.1.0/24");
+        let snapshot = read_records(&get_timeseries_path(
+            dir,
+            "pfx2as",
+            SNAPSHOT_TABLE,
+            rib_meta,
+        ));
+        assert_eq!(snapshot[0]["pfx2as_count"], 1);
+    }
+
+    // time series are not part of the output index
+    let datasets = build_output_index(dir).unwrap();
+    let names: Vec<_> = datasets.iter().map(|d| d.name.as_str()).collect();
+    assert_eq!(names, vec!["pfx2as"]);
+}
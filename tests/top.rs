@@ -0,0 +1,148 @@
+use ribeye::testing::SyntheticRibBuilder;
+use ribeye::top::{top_views, TopDeaggregation, TopLink, TopViews, TOP_FIELD};
+use ribeye::{RibEye, RibMeta};
+use serde_json::json;
+
+#[test]
+fn test_top_views_in_summaries() {
+    let output_dir = tempfile::tempdir().unwrap();
+    let dir = output_dir.path().to_str().unwrap();
+    let rib_meta = RibMeta {
+        collector: "rrc00".to_string(),
+        ..Default::default()
+    };
+    let elems = SyntheticRibBuilder::new()
+        .peer("10.0.0.1", 65001)
+        .route("10.0.0.0/16", &[65001, 3356, 64500])
+        .route("10.0.1.0/24", &[65001, 3356, 64500])
+        .route("10.0.2.0/24", &[65001, 3356, 64500])
+        .route("192.0.2.0/24", &[65001, 64501])
+        .communities(&["3356:100"])
+        .route("198.51.100.0/24", &[65001, 3356, 3356, 64502])
+        .communities(&[])
+        .peer("10.0.0.2", 65002)
+        .route("10.0.0.0/16", &[65002, 3356, 64500])
+        .build();
+    let processors = ["pfx2as", "as2rel", "communities", "peer-stats"]
+        .iter()
+        .map(|p| p.to_string())
+        .collect();
+    let mut ribeye = RibEye::new()
+        .with_processor_names(&processors, dir)
+        .unwrap()
+        .with_top_views(2)
+        .with_rib_meta(&rib_meta);
+    ribeye.process_elems(&elems).unwrap();
+    ribeye.output().unwrap();
+    ribeye.summarize_latest_files(&[rib_meta]).unwrap();
+
+    let read_top = |processor: &str| {
+        let path = format!("{dir}/{processor}/latest.json.bz2");
+        let summary: serde_json::Value = oneio::read_json_struct(path.as_str()).unwrap();
+        summary[TOP_FIELD].clone()
+    };
+
+    let top = read_top("pfx2as");
+    let largest = top["largest_origins_v4"].as_array().unwrap();
+    assert_eq!(largest.len(), 2);
+    assert_eq!(largest[0]["asn"], 64500);
+    // the /24s are covered by the /16
+    assert_eq!(largest[0]["space"], 256.0);
+    assert_eq!(largest[0]["prefixes"], 3);
+    assert_eq!(
+        top["most_deaggregated_origins"],
+        json!([{"asn": 64500, "prefixes": 3, "more_specifics": 2}])
+    );
+    assert!(top.get("largest_origins_v6").is_none());
+
+    let top = read_top("as2rel");
+    let links = top["busiest_links"].as_array().unwrap();
+    assert_eq!(links.len(), 2);
+    assert_eq!(
+        (&links[0]["asn1"], &links[0]["asn2"]),
+        (&json!(3356), &json!(64500))
+    );
+    assert_eq!(links[0]["paths_count"], 4);
+    // tied with the link to the peer, ordered by ASN
+    assert_eq!(links[1]["asn2"], 65001);
+    assert_eq!(links[1]["paths_count"], 4);
+
+    let top = read_top("communities");
+    let prepended = top["most_prepended_asns"].as_array().unwrap();
+    assert_eq!(prepended.len(), 1);
+    assert_eq!(prepended[0]["asn"], 3356);
+    assert_eq!(prepended[0]["prepended"], 1);
+
+    // processors without views are left unchanged
+    assert!(read_top("peer-stats").is_null());
+}
+
+#[test]
+fn test_top_views_ordering() {
+    let summary = json!({
+        "rib_dump_urls": [],
+        "pfx2as": [
+            {"prefix": "10.0.0.0/8", "asn": 2, "count": 1},
+            {"prefix": "10.1.0.0/16", "asn": 2, "count": 1},
+            {"prefix": "10.2.0.0/16", "asn": 2, "count": 1},
+            {"prefix": "10.2.1.0/24", "asn": 2, "count": 1},
+            {"prefix": "11.0.0.0/16", "asn": 2, "count": 1},
+            {"prefix": "20.0.0.0/16", "asn": 1, "count": 1},
+            {"prefix": "20.0.0.0/24", "asn": 1, "count": 1},
+            {"prefix": "20.1.0.0/24", "asn": 1, "count": 1},
+            {"prefix": "2001:db8::/32", "asn": 1, "count": 1},
+            {"prefix": "2001:db8:1::/48", "asn": 1, "count": 1},
+        ]
+    });
+    let views = top_views("pfx2as", &summary, 10).unwrap().unwrap();
+    assert_eq!(
+        views.most_deaggregated_origins,
+        vec![
+            TopDeaggregation {
+                asn: 2,
+                prefixes: 5,
+                more_specifics: 3
+            },
+            TopDeaggregation {
+                asn: 1,
+                prefixes: 5,
+                more_specifics: 2
+            },
+        ]
+    );
+    assert_eq!(views.largest_origins_v6.len(), 1);
+    assert_eq!(views.largest_origins_v6[0].space, 65536.0);
+
+    let summary = json!({
+        "rib_dump_urls": [],
+        "as2rel": [
+            {"asn1": 1, "asn2": 2, "paths_count": 3, "peers_count": 2, "rel": 0},
+            {"asn1": 2, "asn2": 1, "paths_count": 2, "peers_count": 3, "rel": 0},
+            {"asn1": 1, "asn2": 2, "paths_count": 5, "peers_count": 3, "rel": 1},
+            {"asn1": 3, "asn2": 4, "paths_count": 5, "peers_count": 1, "rel": 0},
+        ]
+    });
+    let views = top_views("as2rel", &summary, 10).unwrap().unwrap();
+    assert_eq!(
+        views,
+        TopViews {
+            busiest_links: vec![
+                TopLink {
+                    asn1: 1,
+                    asn2: 2,
+                    paths_count: 5,
+                    peers_count: 3
+                },
+                TopLink {
+                    asn1: 3,
+                    asn2: 4,
+                    paths_count: 5,
+                    peers_count: 1
+                },
+            ],
+            ..Default::default()
+        }
+    );
+
+    assert!(top_views("pfxlen", &summary, 10).unwrap().is_none());
+}
@@ -0,0 +1,178 @@
+use ribeye::processors::{Prefix2AsProcessor, TypedProcessor};
+use ribeye::testing::SyntheticRibBuilder;
+use ribeye::{MessageProcessor, RibMeta};
+
+#[test]
+fn test_typed_output_and_schema() {
+    let rib_meta = RibMeta {
+        collector: "rrc99".to_string(),
+        ..Default::default()
+    };
+    let mut processor = Prefix2AsProcessor::new("/tmp/ribeye-unused");
+    MessageProcessor::reset_processor(&mut processor, &rib_meta);
+    for elem in SyntheticRibBuilder::new()
+        .peer("192.0.2.1", 65001)
+        .route("1.1.1.0/24", &[65001, 13335])
+        .build()
+    {
+        MessageProcessor::process_entry(&mut processor, &elem).unwrap();
+    }
+
+    let output = processor.to_output().unwrap();
+    assert_eq!(output.collector, "rrc99");
+    assert_eq!(output.pfx2as.len(), 1);
+    assert_eq!(output.pfx2as[0].asn, 13335);
+
+    // the untyped result is the serialized typed output
+    let result = processor.to_result().unwrap();
+    assert_eq!(result["pfx2as"][0]["prefix"], "1.1.1.0/24");
+
+    let schema = serde_json::to_value(Prefix2AsProcessor::output_schema()).unwrap();
+    assert!(schema["properties"]["pfx2as"].is_object());
+    let schema = serde_json::to_value(Prefix2AsProcessor::summary_schema()).unwrap();
+    assert!(schema["properties"]["rib_dump_urls"].is_object());
+}
+
+#[test]
+fn test_merge_outputs() {
+    use ribeye::processors::merge::merge_outputs;
+    use ribeye::processors::{Prefix2AsCollectorJson, Prefix2AsCount};
+
+    let output = |collector: &str, count: usize| Prefix2AsCollectorJson {
+        project: "riperis".to_string(),
+        collector: collector.to_string(),
+        rib_dump_url: format!("https://example.com/{collector}"),
+        peers_count: 0,
+        pfx2as: vec![Prefix2AsCount {
+            prefix: "1.1.1.0/24".to_string(),
+            asn: 13335,
+            count,
+            peers: vec![],
+            collectors_count: None,
+            weighted_count: None,
+        }],
+    };
+    let rib_metas: Vec<RibMeta> = ["rrc00", "rrc01"]
+        .iter()
+        .map(|c| RibMeta {
+            collector: c.to_string(),
+            rib_dump_url: format!("https://example.com/{c}"),
+            ..Default::default()
+        })
+        .collect();
+
+    let summary = merge_outputs(&rib_metas, vec![output("rrc00", 2), output("rrc01", 3)]);
+    assert_eq!(summary.rib_dump_urls.len(), 2);
+    assert_eq!(summary.pfx2as.len(), 1);
+    assert_eq!(summary.pfx2as[0].count, 5);
+}
+
+/// Processor that keeps the prefixes of its previous output and adds newly seen ones.
+struct SeenPrefixes {
+    processor_meta: ribeye::processors::ProcessorMeta,
+    rib_meta: Option<RibMeta>,
+    prefixes: std::collections::BTreeSet<String>,
+}
+
+impl TypedProcessor for SeenPrefixes {
+    type Output = ribeye::processors::Prefix2AsCollectorJson;
+    type Summary = ribeye::processors::Prefix2AsSummaryJson;
+    const DATASET_FIELD: &'static str = "pfx2as";
+
+    fn processor_meta(&self) -> &ribeye::processors::ProcessorMeta {
+        &self.processor_meta
+    }
+
+    fn rib_meta(&self) -> Option<&RibMeta> {
+        self.rib_meta.as_ref()
+    }
+
+    fn new_instance(&self) -> Self {
+        SeenPrefixes {
+            processor_meta: self.processor_meta.clone(),
+            rib_meta: None,
+            prefixes: Default::default(),
+        }
+    }
+
+    fn reset_processor(&mut self, rib_meta: &RibMeta) {
+        self.rib_meta = Some(rib_meta.clone());
+    }
+
+    fn process_entry(&mut self, elem: &bgpkit_parser::BgpElem) -> anyhow::Result<()> {
+        self.prefixes.insert(elem.prefix.to_string());
+        Ok(())
+    }
+
+    fn load_previous(&mut self, path: &str) -> anyhow::Result<()> {
+        let previous = Self::read_output(path)?;
+        self.prefixes
+            .extend(previous.pfx2as.into_iter().map(|e| e.prefix));
+        Ok(())
+    }
+
+    fn to_output(&self) -> Option<Self::Output> {
+        let rib_meta = self.rib_meta.as_ref()?;
+        Some(ribeye::processors::Prefix2AsCollectorJson {
+            project: rib_meta.project.clone(),
+            collector: rib_meta.collector.clone(),
+            rib_dump_url: rib_meta.rib_dump_url.clone(),
+            peers_count: 0,
+            pfx2as: self
+                .prefixes
+                .iter()
+                .map(|p| ribeye::processors::Prefix2AsCount {
+                    prefix: p.clone(),
+                    asn: 0,
+                    count: 1,
+                    peers: vec![],
+                    collectors_count: None,
+                    weighted_count: None,
+                })
+                .collect(),
+        })
+    }
+}
+
+#[test]
+fn test_warm_start() {
+    let output_dir = tempfile::tempdir().unwrap();
+    let processor = SeenPrefixes {
+        processor_meta: ribeye::processors::ProcessorMeta {
+            name: "seen".to_string(),
+            output_dir: output_dir.path().to_str().unwrap().to_string(),
+        },
+        rib_meta: None,
+        prefixes: Default::default(),
+    };
+    let rib_meta = RibMeta {
+        collector: "rrc00".to_string(),
+        ..Default::default()
+    };
+
+    let run = |prefix: &str, warm_start: bool| {
+        let mut ribeye = ribeye::RibEye::new()
+            .with_processors(vec![TypedProcessor::new_instance(&processor).to_boxed()])
+            .with_rib_meta(&rib_meta);
+        if warm_start {
+            ribeye.warm_start().unwrap();
+        }
+        for elem in SyntheticRibBuilder::new()
+            .peer("192.0.2.1", 65001)
+            .route(prefix, &[65001, 13335])
+            .build()
+        {
+            ribeye.process_elem(&elem).unwrap();
+        }
+        ribeye.output().unwrap();
+        format!(
+            "{}/seen/rrc00/latest.json.bz2",
+            processor.processor_meta.output_dir
+        )
+    };
+    run("1.1.1.0/24", true);
+    let path = run("1.0.0.0/24", true);
+    assert_eq!(SeenPrefixes::read_output(&path).unwrap().pfx2as.len(), 2);
+    let path = run("1.0.0.0/24", false);
+    assert_eq!(SeenPrefixes::read_output(&path).unwrap().pfx2as.len(), 1);
+}
@@ -0,0 +1,108 @@
+use bgpkit_parser::models::AsPath;
+use ipnet::IpNet;
+use ribeye::testing::SyntheticRibBuilder;
+use ribeye::util::{AsnSet, ElemView, HyperLogLog, PathView, PrefixSpace};
+
+#[test]
+fn test_asn_set() {
+    let mut set: AsnSet = [13335, 174, 13335].into_iter().collect();
+    assert_eq!(set.len(), 2);
+    assert!(set.insert(4_200_000_000));
+    assert!(!set.insert(174));
+    assert!(set.contains(13335));
+
+    let other: AsnSet = [174, 3356].into_iter().collect();
+    assert_eq!(set.intersection_len(&other), 1);
+    set.union_with(&other);
+    assert_eq!(
+        set.iter().collect::<Vec<_>>(),
+        vec![174, 3356, 13335, 4_200_000_000]
+    );
+
+    let json = serde_json::to_string(&set).unwrap();
+    assert_eq!(json, "[174,3356,13335,4200000000]");
+    assert_eq!(serde_json::from_str::<AsnSet>(json.as_str()).unwrap(), set);
+}
+
+#[test]
+fn test_prefix_space() {
+    let prefixes: Vec<IpNet> = [
+        "10.0.0.0/16",
+        "10.0.1.0/24",
+        "10.1.0.0/25",
+        "10.1.0.128/25",
+        "2001:db8::/47",
+        "2001:db8:1::/48",
+    ]
+    .iter()
+    .map(|p| p.parse().unwrap())
+    .collect();
+    let space: PrefixSpace = prefixes.into_iter().collect();
+    // the /24 is inside the /16, the two /25s make up one /24
+    assert_eq!(space.v4_addresses(), 65536 + 256);
+    assert_eq!(space.slash24_equivalents(), 257.0);
+    assert_eq!(space.slash48_equivalents(), 2.0);
+
+    let all: PrefixSpace = ["::/0".parse().unwrap()].into_iter().collect();
+    assert_eq!(all.v6_addresses(), u128::MAX);
+}
+
+#[test]
+fn test_path_view() {
+    let path = AsPath::from_sequence([65001, 174, 174, 174, 13335]);
+    let view = PathView::new(&path).unwrap();
+    assert_eq!(view.asns(), &[65001, 174, 13335]);
+    assert_eq!(view.first_hop(), 65001);
+    assert_eq!(view.origin(), 13335);
+    assert_eq!(
+        view.links().collect::<Vec<_>>(),
+        vec![(65001, 174), (174, 13335)]
+    );
+}
+
+#[test]
+fn test_elem_view() {
+    let elems = SyntheticRibBuilder::new()
+        .peer("192.0.2.1", 65001)
+        .route("1.1.1.0/24", &[65001, 65001, 174, 13335])
+        .route("::/0", &[65001, 174])
+        .withdrawal("2001:db8::/32")
+        .build();
+
+    let view = ElemView::new(&elems[0]);
+    assert!(view.is_announce() && view.is_ipv4() && !view.is_default_route());
+    assert_eq!(view.path().unwrap().asns(), &[65001, 174, 13335]);
+    assert_eq!(view.origin(), Some(13335));
+    // the path is converted once and shared
+    assert!(std::ptr::eq(view.path().unwrap(), view.path().unwrap()));
+
+    let view = ElemView::new(&elems[1]);
+    assert!(view.is_default_route() && !view.is_ipv4());
+
+    let view = ElemView::new(&elems[2]);
+    assert!(!view.is_announce());
+    assert_eq!(view.origin(), None);
+}
+
+#[test]
+fn test_hyperloglog() {
+    let mut hll = HyperLogLog::new();
+    assert!(hll.is_empty());
+    assert_eq!(hll.estimate(), 0);
+
+    // small counts are nearly exact, duplicates are ignored
+    for i in 0..100u32 {
+        hll.insert(&i);
+        hll.insert(&i);
+    }
+    assert!(hll.estimate().abs_diff(100) <= 1);
+
+    for n in [10_000u32, 200_000] {
+        let mut hll = HyperLogLog::new();
+        for i in 0..n {
+            hll.insert(&format!("10.{}.{}.0/24", i / 256, i % 256));
+        }
+        let error = hll.estimate().abs_diff(n as usize) as f64 / n as f64;
+        assert!(error < 0.025, "error {} for {} items", error, n);
+    }
+}
@@ -0,0 +1,120 @@
+use bgpkit_parser::BgpElem;
+use ribeye::processors::{ProcessorConfig, ProcessorConfigFile, TypedProcessor, VantageProcessor};
+use ribeye::testing::{collector_output, SyntheticRibBuilder};
+use ribeye::RibEye;
+
+/// Two peers with the same ten prefix-origin pairs through AS174, and a third peer seeing half of
+/// them and two more prefixes through AS3356.
+///
+/// Prefix-origin pairs: 12, AS links: 11 of the first peer, 1 more of the second (65002-174) and 8
+/// of the third.
+fn elems() -> Vec<BgpElem> {
+    let mut builder = SyntheticRibBuilder::new();
+    for (peer, asn) in [("10.0.0.1", 65001), ("10.0.0.2", 65002)] {
+        builder = builder.peer(peer, asn);
+        for i in 0..10 {
+            let prefix = format!("10.{}.0.0/16", i);
+            builder = builder.route(prefix.as_str(), &[asn, 174, 64500 + i]);
+        }
+    }
+    builder = builder.peer("10.0.0.3", 65003);
+    for i in 0..5 {
+        let prefix = format!("10.{}.0.0/16", i);
+        builder = builder.route(prefix.as_str(), &[65003, 3356, 64500 + i]);
+    }
+    builder
+        .route("192.0.2.0/24", &[65003, 65003, 3356, 64600])
+        .route("198.51.100.0/24", &[65003, 3356, 64601])
+        .withdrawal("203.0.113.0/24")
+        .build()
+}
+
+#[test]
+fn test_greedy_vantage_points() {
+    let (_, output) = collector_output(VantageProcessor::new("-"), "rrc00", &elems());
+    let summary = &output.coverage_summary;
+    assert_eq!(summary.peers_count, 3);
+    assert_eq!((summary.pairs_count, summary.links_count), (12, 20));
+
+    // the second peer only adds one link, below the remaining 5%
+    assert_eq!(output.vantage_points.len(), 2);
+    let first = &output.vantage_points[0];
+    assert_eq!((first.rank, first.asn), (1, 65001));
+    assert_eq!((first.new_pairs, first.new_links), (10, 11));
+    let second = &output.vantage_points[1];
+    assert_eq!((second.rank, second.asn), (2, 65003));
+    assert_eq!((second.pairs, second.links), (7, 8));
+    assert_eq!((second.new_pairs, second.new_links), (2, 8));
+    assert_eq!(second.pairs_coverage, 1.0);
+    assert_eq!(second.links_coverage, 19.0 / 20.0);
+    assert_eq!(summary.vantage_points_count, 2);
+    assert_eq!(summary.links_coverage, second.links_coverage);
+}
+
+#[test]
+fn test_coverage_target() {
+    let (_, output) = collector_output(
+        VantageProcessor::new("-").with_coverage(1.0),
+        "rrc00",
+        &elems(),
+    );
+    assert_eq!(output.vantage_points.len(), 3);
+    assert_eq!(output.vantage_points[2].asn, 65002);
+    assert_eq!(output.coverage_summary.links_coverage, 1.0);
+
+    let (_, output) = collector_output(
+        VantageProcessor::new("-").with_coverage(0.5),
+        "rrc00",
+        &elems(),
+    );
+    assert_eq!(output.coverage, 0.5);
+    assert_eq!(output.vantage_points.len(), 1);
+
+    let (_, output) = collector_output(VantageProcessor::new("-"), "rrc00", &[]);
+    assert!(output.vantage_points.is_empty());
+    assert_eq!(output.coverage_summary.pairs_coverage, 1.0);
+}
+
+#[test]
+fn test_vantage_summary() {
+    let (meta0, output0) = collector_output(VantageProcessor::new("-"), "rrc01", &elems());
+    let elems = SyntheticRibBuilder::new()
+        .peer("10.1.0.1", 65010)
+        .route("10.0.0.0/16", &[65010, 64500])
+        .build();
+    let (meta1, output1) = collector_output(VantageProcessor::new("-"), "rrc00", &elems);
+    let summary = VantageProcessor::new("-")
+        .summarize(
+            &[meta0.clone(), meta1.clone()],
+            vec![(meta0, output0), (meta1, output1)],
+        )
+        .unwrap();
+    let collectors: Vec<&str> = summary
+        .collectors
+        .iter()
+        .map(|c| c.collector.as_str())
+        .collect();
+    assert_eq!(collectors, vec!["rrc00", "rrc01"]);
+    let points: Vec<(&str, usize, u32)> = summary
+        .vantage_points
+        .iter()
+        .map(|p| (p.collector.as_str(), p.rank, p.asn))
+        .collect();
+    assert_eq!(
+        points,
+        vec![
+            ("rrc00", 1, 65010),
+            ("rrc01", 1, 65001),
+            ("rrc01", 2, 65003)
+        ]
+    );
+}
+
+#[test]
+fn test_vantage_coverage_config() {
+    let config_file = ProcessorConfigFile::from_json_str(r#"{"vantage_coverage": 1.0}"#).unwrap();
+    assert_eq!(config_file.vantage_coverage, Some(1.0));
+    let config = ProcessorConfig::new().with_config_file(&config_file);
+    assert_eq!(config.vantage_coverage, Some(1.0));
+    assert!(RibEye::get_processor_with_config("vantage", "-", &config).is_some());
+}